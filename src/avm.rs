@@ -0,0 +1,290 @@
+//! A typed entry point to the operations of the `avm` CLI, for embedding avm in other programs.
+//!
+//! ```no_run
+//! # async fn example(client: any_version_manager::HttpClient) -> any_version_manager::Result<()> {
+//! use any_version_manager::avm::{Avm, InstallRequest};
+//! use any_version_manager::tool::general_tool::node;
+//! use std::sync::Arc;
+//!
+//! let avm = Avm::new(client.clone(), "/path/to/data");
+//! let node = node::Tool::new(Arc::new(client.for_tool("node")), None);
+//! let handle = avm.install("node", &node, InstallRequest::default()).await?;
+//! println!("Installing {} from {}", handle.tag, handle.url);
+//! handle.wait().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use smol_str::SmolStr;
+
+use crate::io::DownloadExtractState;
+use crate::pinned_hashes::PinnedHashes;
+use crate::tool::general_tool::{self, InstallInfo, InstalledTag, RepairedAlias};
+use crate::tool::{GeneralTool, VersionFilter};
+use crate::{CancellationToken, HttpClient, Status};
+
+/// The directory under the data directory holding a directory per tool.
+pub const TOOLS_DIR: &str = "tools";
+
+/// The directory under the data directory holding the shims written by `avm reshim`.
+pub const SHIMS_DIR: &str = "shims";
+
+/// Moves the data directory `from` to `to`, which must not exist or be empty, and re-points
+/// the alias tags, which link to their targets by absolute path. Returns the aliases of each
+/// tool.
+pub async fn move_data_dir(
+    from: &Path,
+    to: &Path,
+) -> crate::Result<Vec<(SmolStr, Vec<RepairedAlias>)>> {
+    if !from.is_dir() {
+        return Err(anyhow::anyhow!("'{}' is not a directory", from.display()).into());
+    }
+    if to.starts_with(from) {
+        return Err(anyhow::anyhow!(
+            "'{}' is inside the data directory '{}'",
+            to.display(),
+            from.display()
+        )
+        .into());
+    }
+    let (from, to) = (from.to_path_buf(), to.to_path_buf());
+    let tools_base = to.join(TOOLS_DIR);
+    let tool_names = crate::spawn_blocking(move || {
+        crate::io::blocking::move_dir(&from, &to, crate::cancellation_token())?;
+        let mut tool_names = Vec::new();
+        match std::fs::read_dir(to.join(TOOLS_DIR)) {
+            Ok(entries) => {
+                for entry in entries {
+                    let entry = entry?;
+                    if entry.file_type()?.is_dir() {
+                        tool_names.push(SmolStr::from(entry.file_name().to_string_lossy()));
+                    }
+                }
+            }
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {}
+            Err(err) => return Err(err.into()),
+        }
+        tool_names.sort();
+        Ok(tool_names)
+    })
+    .await?;
+
+    let mut repaired = Vec::new();
+    for tool_name in tool_names {
+        let aliases = general_tool::repair_aliases(&tool_name, &tools_base, Vec::new()).await?;
+        repaired.push((tool_name, aliases));
+    }
+    Ok(repaired)
+}
+
+/// The tools installed under a data directory. Tool-specific operations take the tool's name,
+/// which names its directory, and the tool itself.
+pub struct Avm {
+    client: HttpClient,
+    tools_base: PathBuf,
+    cancel: CancellationToken,
+    pinned_hashes: Option<Arc<PinnedHashes>>,
+}
+
+/// What [`Avm::install`] installs. The default installs the newest release for the tool's
+/// default platform and flavor.
+#[derive(Default)]
+pub struct InstallRequest {
+    /// `None` for the tool's default platform.
+    pub platform: Option<SmolStr>,
+    /// `None` for the tool's default flavor.
+    pub flavor: Option<SmolStr>,
+    pub version: VersionFilter,
+    /// Replace the tag if it is already installed.
+    pub update: bool,
+    /// Replace the tag if it is installed and not an alias, keeping it if the install fails,
+    /// and remove a stale operation lock of an interrupted install.
+    pub force: bool,
+    /// Point the `default` alias to the installed tag.
+    pub default: bool,
+    /// Extract tar archives while downloading instead of saving them first.
+    pub stream_extract: bool,
+}
+
+/// An install in progress. Nothing is installed until it is driven to the end with
+/// [`InstallHandle::wait`], [`InstallHandle::run`] or [`InstallHandle::advance`].
+pub struct InstallHandle {
+    /// The tag being installed.
+    pub tag: SmolStr,
+    /// Where the archive is downloaded from.
+    pub url: SmolStr,
+    state: DownloadExtractState,
+}
+
+impl InstallHandle {
+    pub fn status(&self) -> Status {
+        self.state.status()
+    }
+
+    /// Runs the next step of the install, such as downloading a chunk.
+    pub async fn advance(self) -> crate::Result<Self> {
+        Ok(Self {
+            state: self.state.advance().await?,
+            ..self
+        })
+    }
+
+    /// Runs the install to the end.
+    pub async fn wait(self) -> crate::Result<()> {
+        self.run(|_| Ok(())).await
+    }
+
+    /// Runs the install to the end, passing the status to `on_progress` before each step.
+    /// An error returned by `on_progress` aborts the install.
+    pub async fn run(
+        self,
+        on_progress: impl FnMut(Status) -> anyhow::Result<()>,
+    ) -> crate::Result<()> {
+        self.state.run(on_progress).await
+    }
+}
+
+impl Avm {
+    /// Uses the same layout of `data_dir` as the CLI. Operations are cancelled with the
+    /// process-wide [`crate::cancellation_token`].
+    pub fn new(client: HttpClient, data_dir: impl AsRef<Path>) -> Self {
+        Self {
+            client,
+            tools_base: data_dir.as_ref().join(TOOLS_DIR),
+            cancel: crate::cancellation_token().clone(),
+            pinned_hashes: None,
+        }
+    }
+
+    /// Cancels the operations with `cancel` instead of the process-wide token.
+    pub fn with_cancellation(self, cancel: CancellationToken) -> Self {
+        Self { cancel, ..self }
+    }
+
+    /// Cross-checks the vendor hashes of the installed archives with `pinned_hashes`.
+    pub fn with_pinned_hashes(self, pinned_hashes: Arc<PinnedHashes>) -> Self {
+        Self {
+            pinned_hashes: Some(pinned_hashes),
+            ..self
+        }
+    }
+
+    pub fn tools_dir(&self) -> &Path {
+        &self.tools_base
+    }
+
+    /// Starts installing a release, failing if its tag exists unless `update` is set.
+    pub async fn install(
+        &self,
+        tool_name: &str,
+        tool: &impl GeneralTool,
+        request: InstallRequest,
+    ) -> crate::Result<InstallHandle> {
+        let info = tool.info();
+        let platform_fallback = request.platform.is_none();
+        let (tag, url, state) = general_tool::InstallArgs {
+            tool_name,
+            tool,
+            client: &self.client,
+            tools_base: &self.tools_base,
+            platform: request.platform.or_else(|| info.default_platform.clone()),
+            flavor: request.flavor.or_else(|| info.default_flavor.clone()),
+            install_version: request.version,
+            update: request.update,
+            force: request.force,
+            default: request.default,
+            keep_partial: false,
+            stream_extract: request.stream_extract,
+            platform_fallback,
+            pinned_hashes: self.pinned_hashes.as_deref(),
+            cancel: &self.cancel,
+        }
+        .install()
+        .await?;
+        Ok(InstallHandle { tag, url, state })
+    }
+
+    /// The tags of the tool in name order, with the targets of the aliases.
+    pub async fn list(&self, tool_name: &str) -> crate::Result<Vec<(SmolStr, Option<SmolStr>)>> {
+        general_tool::list_tags(tool_name, &self.tools_base).await
+    }
+
+    /// The tags that are not aliases, with what they were installed from.
+    pub async fn installed_tags(
+        &self,
+        tool_name: &str,
+        tool: &impl GeneralTool,
+    ) -> crate::Result<Vec<InstalledTag>> {
+        general_tool::installed_tags(tool_name, tool, &self.tools_base).await
+    }
+
+    pub async fn install_info(
+        &self,
+        tool_name: &str,
+        tag: SmolStr,
+    ) -> crate::Result<Option<InstallInfo>> {
+        general_tool::install_info(tool_name, &self.tools_base, tag).await
+    }
+
+    /// Removes the tags. Alias targets are only removed with `allow_dangling`.
+    pub async fn remove(
+        &self,
+        tool_name: &str,
+        tags: Vec<SmolStr>,
+        allow_dangling: bool,
+    ) -> crate::Result<()> {
+        general_tool::remove_tag(tool_name, &self.tools_base, tags, allow_dangling).await
+    }
+
+    /// Points `alias` to `target`, creating or replacing it.
+    pub async fn alias(
+        &self,
+        tool_name: &str,
+        target: SmolStr,
+        alias: SmolStr,
+    ) -> crate::Result<()> {
+        general_tool::create_alias_tag(tool_name, &self.tools_base, target, alias).await
+    }
+
+    /// The alias tags with their targets.
+    pub async fn aliases(&self, tool_name: &str) -> crate::Result<Vec<general_tool::AliasEntry>> {
+        general_tool::list_aliases(tool_name, &self.tools_base).await
+    }
+
+    /// Points the existing `alias` to `target`. Returns the previous target.
+    pub async fn move_alias(
+        &self,
+        tool_name: &str,
+        alias: SmolStr,
+        target: SmolStr,
+    ) -> crate::Result<SmolStr> {
+        general_tool::move_alias(tool_name, &self.tools_base, alias, target).await
+    }
+
+    /// Deletes alias tags, keeping their targets.
+    pub async fn delete_aliases(
+        &self,
+        tool_name: &str,
+        aliases: Vec<SmolStr>,
+    ) -> crate::Result<()> {
+        general_tool::delete_aliases(tool_name, &self.tools_base, aliases).await
+    }
+
+    /// The directory of an installed tag.
+    pub fn path(&self, tool_name: &str, tag: &str) -> crate::Result<PathBuf> {
+        general_tool::get_tag_path(tool_name, &self.tools_base, tag)
+    }
+
+    /// The main executable or entry file of an installed tag.
+    pub fn entry_path(
+        &self,
+        tool_name: &str,
+        tool: &impl GeneralTool,
+        tag: &str,
+    ) -> crate::Result<PathBuf> {
+        general_tool::get_entry_path(tool_name, tool, &self.tools_base, tag)
+    }
+}
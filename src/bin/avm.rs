@@ -1,45 +1,126 @@
 mod avm_cli;
 
-use any_version_manager::HttpClient;
+use any_version_manager::{AvmError, HttpClient};
 use anyhow::Context;
 use avm_cli::{load_config, run, LoadedConfig};
-use log::LevelFilter;
 use std::sync::Arc;
 
 fn main() {
     log::debug!("avm started");
-    stderrlog::new()
-        .verbosity(LevelFilter::Trace)
-        .init()
-        .expect("Failed to initialize logger");
+    avm_cli::logging::init().expect("Failed to initialize logger");
 
     let r = (|| -> anyhow::Result<()> {
+        let (config_path, data_dir) = avm_cli::path_options(std::env::args_os());
         let LoadedConfig {
             mirrors: mirror,
+            proxy,
+            tls,
+            max_download_rate,
+            download_cache,
+            download_buffer_size,
+            github_token,
+            auth,
             paths,
             default_platform,
-        } = load_config()?;
+            tool_defaults,
+            security,
+            manifests,
+            plugins,
+        } = load_config(config_path, data_dir)?;
         ctrlc::set_handler(move || {
-            any_version_manager::set_cancelled();
+            // The tool gets the Ctrl-C too and decides whether to stop.
+            if !avm_cli::is_tool_running() {
+                any_version_manager::set_cancelled();
+            }
         })
         .context("Error setting Ctrl-C handler")?;
 
-        let runtime = tokio::runtime::Builder::new_current_thread()
+        let runtime = tokio::runtime::Builder::new_multi_thread()
             .enable_all()
             .build()
             .unwrap();
 
-        let http_client = Arc::new(HttpClient::new(mirror));
+        let http_client = Arc::new(
+            HttpClient::new(mirror, proxy, tls)?
+                .with_max_download_rate(max_download_rate)
+                .with_download_cache(download_cache)
+                .with_download_buffer_size(download_buffer_size)
+                .with_github_token(github_token)
+                .with_auth(auth)?,
+        );
         runtime
             .block_on(any_version_manager::CancellableFuture::new(run(
                 paths,
                 http_client,
                 default_platform,
+                tool_defaults,
+                security,
+                manifests,
+                plugins,
             )))
-            .unwrap_or(Ok(()))
+            .unwrap_or_else(|| Err(AvmError::Cancelled.into()))
     })();
 
     if let Err(e) = r {
+        if let Some(avm_cli::ToolExit(code)) = e.downcast_ref() {
+            std::process::exit(*code);
+        }
         log::error!("{e:?}");
+        std::process::exit(exit_code::of(&e));
+    }
+}
+
+/// The exit codes of the failures, which scripts may rely on. Clap exits with 2 on invalid
+/// arguments.
+mod exit_code {
+    use any_version_manager::AvmError;
+
+    use crate::avm_cli::ConfirmationRequired;
+
+    pub const FAILURE: i32 = 1;
+    pub const NETWORK: i32 = 3;
+    pub const TAG_EXISTS: i32 = 4;
+    pub const TAG_NOT_FOUND: i32 = 5;
+    pub const HASH_MISMATCH: i32 = 6;
+    pub const RELEASE_NOT_FOUND: i32 = 7;
+    pub const CONFIRMATION_REQUIRED: i32 = 8;
+    pub const CANCELLED: i32 = 130;
+
+    /// The code of the first cause in the chain of `err` that has one.
+    pub fn of(err: &anyhow::Error) -> i32 {
+        err.chain()
+            .find_map(|cause| {
+                if let Some(err) = cause.downcast_ref::<AvmError>() {
+                    return of_avm_error(err);
+                }
+                if cause.is::<reqwest::Error>() {
+                    return Some(NETWORK);
+                }
+                cause
+                    .is::<ConfirmationRequired>()
+                    .then_some(CONFIRMATION_REQUIRED)
+            })
+            .unwrap_or(FAILURE)
+    }
+
+    fn of_avm_error(err: &AvmError) -> Option<i32> {
+        Some(match err {
+            AvmError::Network(_) | AvmError::HttpStatus { .. } => NETWORK,
+            AvmError::TagExists { .. } => TAG_EXISTS,
+            AvmError::TagNotFound { .. } => TAG_NOT_FOUND,
+            AvmError::HashMismatch { .. }
+            | AvmError::DownloadHashMismatch(_)
+            | AvmError::SignatureMismatch { .. }
+            | AvmError::PinnedHashMismatch { .. } => HASH_MISMATCH,
+            AvmError::VersionNotFound
+            | AvmError::PlatformRequired
+            | AvmError::UnsupportedPlatform { .. }
+            | AvmError::UnsupportedFlavor { .. }
+            | AvmError::UnsupportedCombination { .. } => RELEASE_NOT_FOUND,
+            AvmError::Cancelled => CANCELLED,
+            // The cause is further down the chain.
+            AvmError::Other(_) => return None,
+            _ => FAILURE,
+        })
     }
 }
@@ -0,0 +1,196 @@
+//! `avm config` commands. `set` edits the parsed document, keeping the comments and the layout
+//! of the rest of the file.
+
+use std::path::Path;
+
+use any_version_manager::Config;
+use clap::{Args, Subcommand};
+
+use super::Paths;
+
+#[derive(Debug, Args)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    pub command: ConfigCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum ConfigCommand {
+    #[command(about = "Print the value of a key, such as `data_path` or `tools.node.lts_only`")]
+    Get {
+        #[arg(help = "Dotted key")]
+        key: String,
+    },
+
+    #[command(
+        about = "Set the value of a key",
+        long_about = "Sets the value of a key, such as `max_download_rate` or `tools.node.lts_only`, creating the config file if needed. The value is read as TOML, like `true`, `5` or `[\"go\", \"node\"]`, and as a string otherwise. The other lines of the file, including comments, are kept."
+    )]
+    Set {
+        #[arg(help = "Dotted key")]
+        key: String,
+        #[arg(help = "New value")]
+        value: String,
+    },
+
+    #[command(
+        about = "Open the config file in an editor",
+        long_about = "Opens the config file in `$VISUAL` or `$EDITOR`, or a default editor of the system, and checks it once the editor exits."
+    )]
+    Edit,
+}
+
+pub fn run(args: ConfigArgs, paths: &Paths) -> anyhow::Result<()> {
+    let path = &paths.config_file;
+    match args.command {
+        ConfigCommand::Get { key } => {
+            let table = parse_table(&read_config(path)?)?;
+            let value = get(&table, &key)
+                .ok_or_else(|| anyhow::anyhow!("`{key}` is not set in '{}'", path.display()))?;
+            match value {
+                toml::Value::String(value) => println!("{value}"),
+                value => println!("{value}"),
+            }
+            Ok(())
+        }
+        ConfigCommand::Set { key, value } => set_in_file(path, &key, parse_value(&value)),
+        ConfigCommand::Edit => edit(path),
+    }
+}
+
+/// Sets `key` to `value` in the config file at `path`, creating it if needed.
+pub fn set_in_file(path: &Path, key: &str, value: toml::Value) -> anyhow::Result<()> {
+    let content = set(&read_config(path)?, key, value)?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, content)?;
+    Ok(())
+}
+
+/// The content of the config file, empty if there is none yet.
+fn read_config(path: &Path) -> anyhow::Result<String> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => Ok(content),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(String::new()),
+        Err(err) => Err(anyhow::Error::from(err).context(path.display().to_string())),
+    }
+}
+
+fn parse_table(content: &str) -> anyhow::Result<toml::Table> {
+    toml::from_str(content).map_err(|err| anyhow::anyhow!("Invalid config: {err}"))
+}
+
+fn parse_value(value: &str) -> toml::Value {
+    match toml::from_str::<toml::Table>(&format!("value = {value}")) {
+        Ok(mut table) => table.remove("value").expect("The value is parsed"),
+        Err(_) => toml::Value::String(value.to_owned()),
+    }
+}
+
+fn get<'a>(table: &'a toml::Table, key: &str) -> Option<&'a toml::Value> {
+    let mut segments = key.split('.');
+    let mut value = table.get(segments.next()?)?;
+    for segment in segments {
+        value = value.as_table()?.get(segment)?;
+    }
+    Some(value)
+}
+
+/// Checks the keys and value types, warning about the top-level keys avm does not know.
+fn check(content: &str) -> anyhow::Result<toml::Table> {
+    let table = parse_table(content)?;
+    toml::from_str::<Config>(content).map_err(|err| anyhow::anyhow!("Invalid config: {err}"))?;
+    for key in table.keys() {
+        if !Config::KEYS.contains(&key.as_str()) {
+            log::warn!("Unknown config key `{key}`");
+        }
+    }
+    Ok(table)
+}
+
+/// Returns `content` with `key` set to `value`. An existing value is replaced in place, a new
+/// key is added at the end of its table, and a missing table after the other tables of its
+/// parent, or at the end of the file.
+fn set(content: &str, key: &str, value: toml::Value) -> anyhow::Result<String> {
+    let segments = key.split('.').map(str::trim).collect::<Vec<_>>();
+    if segments.iter().any(|segment| segment.is_empty()) {
+        anyhow::bail!("Invalid key `{key}`");
+    }
+    if !Config::KEYS.contains(&segments[0]) {
+        anyhow::bail!(
+            "Unknown config key `{}`, the keys are: {}",
+            segments[0],
+            Config::KEYS.join(", ")
+        );
+    }
+    let (name, table_path) = segments.split_last().expect("The key is not empty");
+
+    let mut document = content
+        .parse::<toml_edit::DocumentMut>()
+        .map_err(|err| anyhow::anyhow!("Invalid config: {err}"))?;
+    let mut table: &mut dyn toml_edit::TableLike = document.as_table_mut();
+    for segment in table_path {
+        table = table
+            .entry(segment)
+            .or_insert_with(|| {
+                // Only the innermost table gets a header.
+                let mut table = toml_edit::Table::new();
+                table.set_implicit(true);
+                toml_edit::Item::Table(table)
+            })
+            .as_table_like_mut()
+            .ok_or_else(|| anyhow::anyhow!("Failed to set `{key}`, `{segment}` is not a table"))?;
+    }
+    let mut new_value = value
+        .to_string()
+        .parse::<toml_edit::Value>()
+        .map_err(|err| anyhow::anyhow!("Invalid value `{value}`: {err}"))?;
+    match table.get_mut(name).and_then(toml_edit::Item::as_value_mut) {
+        // Keeps a comment after the value.
+        Some(old) => {
+            *new_value.decor_mut() = old.decor().clone();
+            *old = new_value;
+        }
+        None => {
+            table.insert(name, toml_edit::Item::Value(new_value));
+        }
+    }
+    let content = document.to_string();
+
+    let table = check(&content).map_err(|err| err.context(format!("Failed to set `{key}`")))?;
+    if get(&table, key) != Some(&value) {
+        anyhow::bail!("Failed to set `{key}` in place, run `avm config edit` to edit the file");
+    }
+    Ok(content)
+}
+
+fn edit(path: &Path) -> anyhow::Result<()> {
+    let editor = ["VISUAL", "EDITOR"]
+        .into_iter()
+        .find_map(|var| {
+            std::env::var(var)
+                .ok()
+                .filter(|editor| !editor.trim().is_empty())
+        })
+        .unwrap_or_else(|| if cfg!(windows) { "notepad" } else { "vi" }.to_owned());
+    if !path.exists() {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, "")?;
+    }
+    let mut words = editor.split_whitespace();
+    let program = words.next().expect("The editor is not empty");
+    let status = std::process::Command::new(program)
+        .args(words)
+        .arg(path)
+        .status()
+        .map_err(|err| anyhow::anyhow!("Failed to run the editor `{editor}`: {err}"))?;
+    if !status.success() {
+        anyhow::bail!("The editor `{editor}` failed with {status}");
+    }
+    check(&read_config(path)?)
+        .map_err(|err| err.context("The config was saved, run `avm config edit` to fix it"))?;
+    Ok(())
+}
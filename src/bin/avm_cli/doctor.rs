@@ -0,0 +1,118 @@
+use any_version_manager::journal;
+use any_version_manager::tool::general_tool::{self, EndpointCheck};
+use any_version_manager::tool::GeneralTool;
+use any_version_manager::HttpClient;
+use clap::ValueEnum;
+use smol_str::SmolStr;
+
+use super::general_tool::{async_invoke_tool, AsyncFnTool, SupportStatusFn, ToolName, ToolSet};
+use super::Paths;
+
+struct PingFn<'a> {
+    client: &'a HttpClient,
+}
+
+impl AsyncFnTool for PingFn<'_> {
+    type Output = Vec<EndpointCheck>;
+
+    async fn invoke(&self, tool: &impl GeneralTool) -> Self::Output {
+        general_tool::ping(tool, self.client).await
+    }
+}
+
+/// Warns about the operations left unfinished in the journal, and, for each installed tool,
+/// about its unreachable endpoints, its dangling aliases and a default tag whose version is
+/// past its end of life.
+pub async fn run(tools: &ToolSet, client: &HttpClient, paths: &Paths) -> anyhow::Result<()> {
+    let mut problems = 0;
+    for operation in journal::pending().await? {
+        log::warn!(
+            "An operation on '{}' is unfinished, it is recovered once no avm process uses the tool",
+            operation.tag_dir().display()
+        );
+        problems += 1;
+    }
+    for tool in <ToolName as ValueEnum>::value_variants() {
+        let tool_name = tool.command_name();
+        let tags = general_tool::list_tags(&tool_name, &paths.tool_dir).await?;
+        if tags.is_empty() {
+            continue;
+        }
+
+        let client = client.for_tool(&tool_name);
+        let checks = async_invoke_tool(tools, tool, &PingFn { client: &client }).await;
+        // The upstream URL behind a mirror is informational, downloads don't use it.
+        for check in checks
+            .iter()
+            .filter(|check| !check.direct && !check.is_ok())
+        {
+            match &check.result {
+                Ok((status, _)) => log::warn!(
+                    "The {tool_name} endpoint {} responded with {}",
+                    check.url,
+                    status.as_u16()
+                ),
+                Err(err) => log::warn!(
+                    "The {tool_name} endpoint {} is unreachable: {err}",
+                    check.url
+                ),
+            }
+            problems += 1;
+        }
+
+        let aliases = general_tool::list_aliases(&tool_name, &paths.tool_dir).await?;
+        for entry in aliases.iter().filter(|entry| !entry.target_exists) {
+            log::warn!(
+                "The {tool_name} alias `{}` points to the removed tag `{}`, `avm clean {tool_name} --dangling-aliases` deletes or re-points it",
+                entry.alias,
+                entry.target
+            );
+            problems += 1;
+        }
+
+        if !tags.iter().any(|(tag, _)| tag == "default") {
+            continue;
+        }
+        let Some(info) =
+            general_tool::install_info(&tool_name, &paths.tool_dir, SmolStr::new("default"))
+                .await?
+        else {
+            continue;
+        };
+        let versions = [info.version];
+        let status = match async_invoke_tool(
+            tools,
+            tool,
+            &SupportStatusFn {
+                versions: &versions,
+            },
+        )
+        .await
+        {
+            Ok(mut statuses) => statuses.pop().flatten(),
+            Err(err) => {
+                log::warn!(
+                    "Failed to check the support of {tool_name} {}: {err:#}",
+                    versions[0]
+                );
+                continue;
+            }
+        };
+        if let Some(status) = status.filter(|status| status.is_eol) {
+            let since = status
+                .eol_date
+                .map(|date| format!(" since {date}"))
+                .unwrap_or_default();
+            log::warn!(
+                "The default {tool_name} tag, {}, is in the {} release cycle, which is past its end of life{since}",
+                versions[0],
+                status.cycle
+            );
+            problems += 1;
+        }
+    }
+    if problems == 0 {
+        log::info!("No problems found");
+    }
+    Ok(())
+}
@@ -0,0 +1,401 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use any_version_manager::pinned_hashes::PinnedHashes;
+use any_version_manager::tool::general_tool;
+use any_version_manager::tool::{GeneralTool, TagOptions, Version, VersionFilter};
+use any_version_manager::FileHash;
+use clap::{Args, ValueEnum};
+use serde::{Deserialize, Serialize};
+use smol_str::SmolStr;
+
+use super::general_tool::{
+    async_invoke_tool, drive_download_state, finish_install, AsyncFnTool, ToolName, ToolSet,
+};
+use super::Paths;
+use crate::HttpClient;
+
+pub const DEFAULT_LOCK_FILE: &str = "avm.lock";
+
+#[derive(Debug, Clone, Args)]
+pub struct ExportArgs {
+    #[arg(
+        value_name = "tool",
+        help = "Tools to export. Defaults to every tool with installed tags."
+    )]
+    pub tools: Vec<ToolName>,
+    #[arg(
+        long,
+        help = "Also write the download URL and hash of each tag, for `avm sync`."
+    )]
+    pub lock: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct ImportArgs {
+    #[arg(value_name = "file", help = "File written by `avm export`.")]
+    pub file: PathBuf,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct SyncArgs {
+    #[arg(
+        value_name = "file",
+        default_value = DEFAULT_LOCK_FILE,
+        help = "Lock file written by `avm export --lock`."
+    )]
+    pub file: PathBuf,
+}
+
+/// The installed tags and aliases of the tools, keyed by tool name.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(transparent)]
+struct Exported {
+    tools: BTreeMap<SmolStr, ExportedTool>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ExportedTool {
+    #[serde(default)]
+    tags: Vec<ExportedTag>,
+    /// Alias tag to target tag.
+    #[serde(default)]
+    aliases: BTreeMap<SmolStr, SmolStr>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ExportedTag {
+    tag: SmolStr,
+    version: SmolStr,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    lts: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    platform: Option<SmolStr>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    flavor: Option<SmolStr>,
+    /// Where the archive is downloaded from, in lock files.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    url: Option<SmolStr>,
+    /// The hash of the archive, in lock files.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hash: Option<FileHash>,
+}
+
+pub async fn run_export(args: ExportArgs, tools: &ToolSet, paths: &Paths) -> anyhow::Result<()> {
+    let tool_names = if args.tools.is_empty() {
+        ToolName::value_variants().to_vec()
+    } else {
+        args.tools
+    };
+
+    let mut exported = Exported::default();
+    for tool_name in &tool_names {
+        let name = tool_name.command_name();
+        let fn_tool = InstalledTagsFn {
+            tool_name: &name,
+            tools_base: &paths.tool_dir,
+        };
+        let mut tags = async_invoke_tool(tools, tool_name, &fn_tool).await?;
+        for tag in &mut tags {
+            if !args.lock {
+                tag.url = None;
+                tag.hash = None;
+            } else if tag.url.is_none() || tag.hash.is_none() {
+                // Installed by an older version or from a local archive, look the release up.
+                let (url, hash) = async_invoke_tool(tools, tool_name, &LockTagFn { tag }).await?;
+                tag.url = Some(url);
+                tag.hash = Some(hash);
+            }
+        }
+        let aliases = general_tool::list_tags(&name, &paths.tool_dir)
+            .await?
+            .into_iter()
+            .filter_map(|(alias, target)| Some((alias, target?)))
+            .collect::<BTreeMap<_, _>>();
+        if tags.is_empty() && aliases.is_empty() {
+            continue;
+        }
+        exported
+            .tools
+            .insert(name.into(), ExportedTool { tags, aliases });
+    }
+    print!("{}", toml::to_string(&exported)?);
+    Ok(())
+}
+
+pub async fn run_import(
+    args: ImportArgs,
+    tools: &ToolSet,
+    client: &HttpClient,
+    paths: &Paths,
+) -> anyhow::Result<()> {
+    provision(&args.file, false, tools, client, paths).await
+}
+
+pub async fn run_sync(
+    args: SyncArgs,
+    tools: &ToolSet,
+    client: &HttpClient,
+    paths: &Paths,
+) -> anyhow::Result<()> {
+    provision(&args.file, true, tools, client, paths).await
+}
+
+/// Installs the missing tags of the file and points the aliases to them. With `locked`, the
+/// tags are downloaded from the recorded URLs and verified against the recorded hashes, and
+/// existing tags must have the recorded versions.
+async fn provision(
+    file: &Path,
+    locked: bool,
+    tools: &ToolSet,
+    client: &HttpClient,
+    paths: &Paths,
+) -> anyhow::Result<()> {
+    let content = std::fs::read_to_string(file)
+        .map_err(|e| anyhow::anyhow!("Failed to read '{}': {e}", file.display()))?;
+    let exported: Exported = toml::from_str(&content)
+        .map_err(|e| anyhow::anyhow!("Failed to parse '{}': {e}", file.display()))?;
+
+    // Resolve every tool first, so that nothing is installed from a file this avm can't handle.
+    let mut imports = Vec::new();
+    for (name, tool) in exported.tools {
+        let tool_name = ToolName::from_str(&name, false).map_err(|_| {
+            anyhow::anyhow!("Unknown tool \"{name}\". Run `avm tool` to list tools.")
+        })?;
+        if locked {
+            if let Some(tag) = tool
+                .tags
+                .iter()
+                .find(|tag| tag.url.is_none() || tag.hash.as_ref().is_none_or(FileHash::is_empty))
+            {
+                anyhow::bail!(
+                    "{name} {} has no URL or hash, write the lock file with `avm export --lock`",
+                    tag.tag
+                );
+            }
+        }
+        imports.push((name, tool_name, tool));
+    }
+
+    let mut failed = 0;
+    for (name, tool_name, tool) in imports {
+        let installed = if locked {
+            let fn_tool = InstalledTagsFn {
+                tool_name: &name,
+                tools_base: &paths.tool_dir,
+            };
+            async_invoke_tool(tools, &tool_name, &fn_tool).await?
+        } else {
+            Vec::new()
+        };
+
+        for tag in &tool.tags {
+            if paths.tool_dir.join(&*name).join(&*tag.tag).exists() {
+                let version = installed
+                    .iter()
+                    .find(|installed| installed.tag == tag.tag)
+                    .map(|installed| &installed.version);
+                if locked && version != Some(&tag.version) {
+                    log::error!(
+                        "{name} {} exists with version {}, but {} is locked",
+                        tag.tag,
+                        version.map_or("unknown", |v| v.as_str()),
+                        tag.version
+                    );
+                    failed += 1;
+                } else {
+                    println!("exists: {name} {}", tag.tag);
+                }
+                continue;
+            }
+            let result = if locked {
+                install_locked(&name, tag, client, paths).await
+            } else {
+                let fn_tool = ImportTagFn {
+                    tool_name: &name,
+                    client,
+                    tools_base: &paths.tool_dir,
+                    pinned_hashes: tools.pinned_hashes.as_ref(),
+                    tag,
+                };
+                async_invoke_tool(tools, &tool_name, &fn_tool).await
+            };
+            let result = match result {
+                Ok(()) => {
+                    finish_install(tools, &tool_name, &tag.tag, &TagOptions::default(), paths).await
+                }
+                Err(err) => Err(err),
+            };
+            match result {
+                Ok(()) => println!("installed: {name} {}", tag.tag),
+                Err(err) => {
+                    log::error!("Failed to install {name} {}: {err:#}", tag.tag);
+                    failed += 1;
+                }
+            }
+        }
+
+        let current = general_tool::list_tags(&name, &paths.tool_dir).await?;
+        for (alias, target) in tool.aliases {
+            if current
+                .iter()
+                .any(|(tag, current)| *tag == alias && current.as_ref() == Some(&target))
+            {
+                continue;
+            }
+            match general_tool::create_alias_tag(
+                &name,
+                &paths.tool_dir,
+                target.clone(),
+                alias.clone(),
+            )
+            .await
+            {
+                Ok(()) => println!("aliased: {name} {alias} -> {target}"),
+                Err(err) => {
+                    log::error!("Failed to alias {name} {alias} -> {target}: {err:#}");
+                    failed += 1;
+                }
+            }
+        }
+    }
+
+    if failed > 0 {
+        anyhow::bail!("Failed to import {failed} tag(s) or alias(es)");
+    }
+    Ok(())
+}
+
+async fn install_locked(
+    tool_name: &str,
+    tag: &ExportedTag,
+    client: &HttpClient,
+    paths: &Paths,
+) -> anyhow::Result<()> {
+    let url = tag.url.as_deref().unwrap_or_default();
+    let download_state = general_tool::UrlInstaller {
+        tool_name,
+        client,
+        tools_base: &paths.tool_dir,
+        url,
+        target_tag: &tag.tag,
+        version: Version {
+            version: tag.version.clone(),
+            is_lts: tag.lts,
+        },
+        hash: tag.hash.clone().unwrap_or_default(),
+        update: false,
+        default: false,
+        keep_partial: false,
+        stream_extract: false,
+        cancel: any_version_manager::cancellation_token(),
+    }
+    .install()
+    .await?;
+    drive_download_state(tag.tag.clone(), url.into(), download_state).await
+}
+
+struct InstalledTagsFn<'a> {
+    tool_name: &'a str,
+    tools_base: &'a Path,
+}
+
+impl AsyncFnTool for InstalledTagsFn<'_> {
+    type Output = anyhow::Result<Vec<ExportedTag>>;
+
+    async fn invoke(&self, tool: &impl GeneralTool) -> Self::Output {
+        let tags = general_tool::installed_tags(self.tool_name, tool, self.tools_base).await?;
+        Ok(tags
+            .into_iter()
+            .map(|installed| {
+                let info = installed.info;
+                ExportedTag {
+                    tag: installed.tag,
+                    version: info.version,
+                    lts: info.is_lts,
+                    platform: info.platform,
+                    flavor: info.flavor,
+                    url: info.url,
+                    hash: (!info.hash.is_empty()).then_some(info.hash),
+                }
+            })
+            .collect())
+    }
+}
+
+/// Resolves the download URL and hash of the tag's release.
+struct LockTagFn<'a> {
+    tag: &'a ExportedTag,
+}
+
+impl AsyncFnTool for LockTagFn<'_> {
+    type Output = anyhow::Result<(SmolStr, FileHash)>;
+
+    async fn invoke(&self, tool: &impl GeneralTool) -> Self::Output {
+        let down_info = general_tool::get_downinfo(
+            tool,
+            self.tag.platform.clone(),
+            self.tag.flavor.clone(),
+            exact_version(&self.tag.version),
+        )
+        .await?;
+        Ok((down_info.url, down_info.hash))
+    }
+}
+
+struct ImportTagFn<'a> {
+    tool_name: &'a str,
+    client: &'a HttpClient,
+    tools_base: &'a Path,
+    pinned_hashes: Option<&'a PinnedHashes>,
+    tag: &'a ExportedTag,
+}
+
+impl AsyncFnTool for ImportTagFn<'_> {
+    type Output = anyhow::Result<()>;
+
+    async fn invoke(&self, tool: &impl GeneralTool) -> Self::Output {
+        let (installed_tag, download_url, download_state) = general_tool::InstallArgs {
+            tool_name: self.tool_name,
+            tool,
+            client: self.client,
+            tools_base: self.tools_base,
+            platform: self.tag.platform.clone(),
+            flavor: self.tag.flavor.clone(),
+            install_version: exact_version(&self.tag.version),
+            update: false,
+            force: false,
+            default: false,
+            keep_partial: false,
+            stream_extract: false,
+            platform_fallback: false,
+            pinned_hashes: self.pinned_hashes,
+            cancel: any_version_manager::cancellation_token(),
+        }
+        .install()
+        .await?;
+        drive_download_state(installed_tag.clone(), download_url, download_state).await?;
+
+        // Tags not named after their release, such as renamed ones, get their name back.
+        if installed_tag != self.tag.tag {
+            general_tool::rename_tag(
+                self.tool_name,
+                self.tools_base,
+                installed_tag,
+                self.tag.tag.clone(),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+fn exact_version(version: &SmolStr) -> VersionFilter {
+    VersionFilter {
+        include_prerelease: true,
+        exact_version: Some(version.clone()),
+        ..Default::default()
+    }
+}
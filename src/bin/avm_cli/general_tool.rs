@@ -1,43 +1,209 @@
 use std::ffi::OsString;
+use std::io::IsTerminal;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
 
-use crate::avm_cli::Paths;
+use crate::avm_cli::{hooks, is_non_interactive, is_progress_json, ConfirmationRequired, Paths};
 use crate::HttpClient;
+use any_version_manager::bundle;
+use any_version_manager::pinned_hashes::PinnedHashes;
+use any_version_manager::project;
+use any_version_manager::tool::end_of_life::SupportStatus;
 use any_version_manager::tool::general_tool::{
-    self, dotnet as dotnet_tool, go as go_tool, liberica as liberica_tool, node as node_tool,
-    pnpm as pnpm_tool,
+    self, cmake as cmake_tool, dotnet as dotnet_tool, elixir as elixir_tool, erlang as erlang_tool,
+    flutter as flutter_tool, go as go_tool, graalvm as graalvm_tool, gradle as gradle_tool,
+    julia as julia_tool, kotlin as kotlin_tool, liberica as liberica_tool, llvm as llvm_tool,
+    manifest as manifest_tool, maven as maven_tool, ninja as ninja_tool, node as node_tool,
+    opentofu as opentofu_tool, php as php_tool, plugin as plugin_tool, pnpm as pnpm_tool,
+    protoc as protoc_tool, ruby as ruby_tool, swift as swift_tool, temurin as temurin_tool,
+    terraform as terraform_tool,
 };
-use any_version_manager::tool::{GeneralTool, ToolInfo, Version, VersionFilter, VersionPrefix};
-use any_version_manager::DefaultPlatform;
-use clap::{Args, ValueEnum};
-use indicatif::{ProgressBar, ProgressStyle};
+use any_version_manager::tool::{
+    FlavorOptions, GeneralTool, NewTag, TagOptions, ToolInfo, ToolOption, Version, VersionFilter,
+    VersionPrefix, VersionSpec,
+};
+use any_version_manager::usage::{self, UsageStats};
+use any_version_manager::{AvmError, DefaultPlatform, FileHash, ToolDefaults};
+use clap::builder::PossibleValue;
+use clap::{Args, Subcommand, ValueEnum};
+use indicatif::{HumanBytes, HumanDuration, ProgressBar, ProgressStyle};
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::Serialize;
 use smol_str::SmolStr;
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq, ValueEnum)]
+#[derive(Debug, Clone, Eq, PartialEq)]
 pub enum ToolName {
     Dotnet,
     Liberica,
+    Temurin,
+    Graalvm,
     Go,
     Node,
     Pnpm,
+    Erlang,
+    Elixir,
+    Ruby,
+    Kotlin,
+    Gradle,
+    Maven,
+    Terraform,
+    Opentofu,
+    Flutter,
+    Swift,
+    Julia,
+    Php,
+    Protoc,
+    Cmake,
+    Ninja,
+    Llvm,
+    /// A tool declared by a manifest in the config directory.
+    Manifest(SmolStr),
+    /// A tool provided by a plugin in the data directory.
+    Plugin(SmolStr),
 }
 
+const BUILTIN_TOOLS: [ToolName; 23] = [
+    ToolName::Dotnet,
+    ToolName::Liberica,
+    ToolName::Temurin,
+    ToolName::Graalvm,
+    ToolName::Go,
+    ToolName::Node,
+    ToolName::Pnpm,
+    ToolName::Erlang,
+    ToolName::Elixir,
+    ToolName::Ruby,
+    ToolName::Kotlin,
+    ToolName::Gradle,
+    ToolName::Maven,
+    ToolName::Terraform,
+    ToolName::Opentofu,
+    ToolName::Flutter,
+    ToolName::Swift,
+    ToolName::Julia,
+    ToolName::Php,
+    ToolName::Protoc,
+    ToolName::Cmake,
+    ToolName::Ninja,
+    ToolName::Llvm,
+];
+
+static TOOL_NAMES: OnceLock<Vec<ToolName>> = OnceLock::new();
+
 impl ToolName {
-    pub fn command_name(self) -> String {
+    pub fn command_name(&self) -> String {
         self.to_possible_value()
             .expect("ToolName variants always map to clap values")
             .get_name()
             .to_owned()
     }
+
+    /// Makes manifest and plugin tools parse as tool names.
+    /// Must be called before parsing the command line.
+    pub fn register_external(
+        manifests: impl IntoIterator<Item = SmolStr>,
+        plugins: impl IntoIterator<Item = SmolStr>,
+    ) -> anyhow::Result<()> {
+        let mut all = BUILTIN_TOOLS.to_vec();
+        let external = manifests
+            .into_iter()
+            .map(ToolName::Manifest)
+            .chain(plugins.into_iter().map(ToolName::Plugin));
+        for tool in external {
+            let name = tool.command_name();
+            if let Some(existing) = all.iter().find(|t| t.command_name() == name) {
+                anyhow::bail!(
+                    "{} \"{name}\" conflicts with {}",
+                    tool.kind(),
+                    existing.kind().to_lowercase()
+                );
+            }
+            all.push(tool);
+        }
+        TOOL_NAMES
+            .set(all)
+            .map_err(|_| anyhow::anyhow!("Tool names are already registered"))
+    }
+}
+
+impl ToolName {
+    fn kind(&self) -> &'static str {
+        match self {
+            ToolName::Manifest(_) => "Tool manifest",
+            ToolName::Plugin(_) => "Plugin",
+            _ => "A built-in tool",
+        }
+    }
+}
+
+impl ValueEnum for ToolName {
+    fn value_variants<'a>() -> &'a [Self] {
+        TOOL_NAMES.get_or_init(|| BUILTIN_TOOLS.to_vec())
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self {
+            ToolName::Dotnet => PossibleValue::new("dotnet"),
+            ToolName::Liberica => PossibleValue::new("liberica"),
+            ToolName::Temurin => PossibleValue::new("temurin"),
+            ToolName::Graalvm => PossibleValue::new("graalvm"),
+            ToolName::Go => PossibleValue::new("go"),
+            ToolName::Node => PossibleValue::new("node"),
+            ToolName::Pnpm => PossibleValue::new("pnpm"),
+            ToolName::Erlang => PossibleValue::new("erlang"),
+            ToolName::Elixir => PossibleValue::new("elixir"),
+            ToolName::Ruby => PossibleValue::new("ruby"),
+            ToolName::Kotlin => PossibleValue::new("kotlin"),
+            ToolName::Gradle => PossibleValue::new("gradle"),
+            ToolName::Maven => PossibleValue::new("maven"),
+            ToolName::Terraform => PossibleValue::new("terraform"),
+            ToolName::Opentofu => PossibleValue::new("opentofu"),
+            ToolName::Flutter => PossibleValue::new("flutter"),
+            ToolName::Swift => PossibleValue::new("swift"),
+            ToolName::Julia => PossibleValue::new("julia"),
+            ToolName::Php => PossibleValue::new("php"),
+            ToolName::Protoc => PossibleValue::new("protoc"),
+            ToolName::Cmake => PossibleValue::new("cmake"),
+            ToolName::Ninja => PossibleValue::new("ninja"),
+            ToolName::Llvm => PossibleValue::new("llvm"),
+            ToolName::Manifest(name) | ToolName::Plugin(name) => {
+                PossibleValue::new(name.to_string())
+            }
+        })
+    }
 }
 
 pub struct ToolSet {
     pub dotnet: dotnet_tool::Tool,
     pub liberica: liberica_tool::Tool,
+    pub temurin: temurin_tool::Tool,
+    pub graalvm: graalvm_tool::Tool,
     pub go: go_tool::Tool,
     pub node: node_tool::Tool,
     pub pnpm: pnpm_tool::Tool,
+    pub erlang: erlang_tool::Tool,
+    pub elixir: elixir_tool::Tool,
+    pub ruby: ruby_tool::Tool,
+    pub kotlin: kotlin_tool::Tool,
+    pub gradle: gradle_tool::Tool,
+    pub maven: maven_tool::Tool,
+    pub terraform: terraform_tool::Tool,
+    pub opentofu: opentofu_tool::Tool,
+    pub flutter: flutter_tool::Tool,
+    pub swift: swift_tool::Tool,
+    pub julia: julia_tool::Tool,
+    pub php: php_tool::Tool,
+    pub protoc: protoc_tool::Tool,
+    pub cmake: cmake_tool::Tool,
+    pub ninja: ninja_tool::Tool,
+    pub llvm: llvm_tool::Tool,
+    pub manifests: Vec<(SmolStr, manifest_tool::Tool)>,
+    pub plugins: Vec<(SmolStr, plugin_tool::Tool)>,
+    /// The `tools` table of the config.
+    pub defaults: FxHashMap<String, ToolDefaults>,
+    /// Loaded if `security.pin_hashes` is set.
+    pub pinned_hashes: Option<PinnedHashes>,
 }
 
 pub trait FnTool {
@@ -46,89 +212,342 @@ pub trait FnTool {
     fn invoke(&self, tool: &impl GeneralTool) -> Self::Output;
 }
 
-trait AsyncFnTool {
+pub(super) trait AsyncFnTool {
     type Output;
 
     async fn invoke(&self, tool: &impl GeneralTool) -> Self::Output;
 }
 
-fn invoke_tool<FT: FnTool>(tool_set: &ToolSet, tool_name: ToolName, fn_tool: &FT) -> FT::Output {
+fn invoke_tool<FT: FnTool>(tool_set: &ToolSet, tool_name: &ToolName, fn_tool: &FT) -> FT::Output {
     match tool_name {
         ToolName::Dotnet => fn_tool.invoke(&tool_set.dotnet),
         ToolName::Liberica => fn_tool.invoke(&tool_set.liberica),
+        ToolName::Temurin => fn_tool.invoke(&tool_set.temurin),
+        ToolName::Graalvm => fn_tool.invoke(&tool_set.graalvm),
         ToolName::Go => fn_tool.invoke(&tool_set.go),
         ToolName::Node => fn_tool.invoke(&tool_set.node),
         ToolName::Pnpm => fn_tool.invoke(&tool_set.pnpm),
+        ToolName::Erlang => fn_tool.invoke(&tool_set.erlang),
+        ToolName::Elixir => fn_tool.invoke(&tool_set.elixir),
+        ToolName::Ruby => fn_tool.invoke(&tool_set.ruby),
+        ToolName::Kotlin => fn_tool.invoke(&tool_set.kotlin),
+        ToolName::Gradle => fn_tool.invoke(&tool_set.gradle),
+        ToolName::Maven => fn_tool.invoke(&tool_set.maven),
+        ToolName::Terraform => fn_tool.invoke(&tool_set.terraform),
+        ToolName::Opentofu => fn_tool.invoke(&tool_set.opentofu),
+        ToolName::Flutter => fn_tool.invoke(&tool_set.flutter),
+        ToolName::Swift => fn_tool.invoke(&tool_set.swift),
+        ToolName::Julia => fn_tool.invoke(&tool_set.julia),
+        ToolName::Php => fn_tool.invoke(&tool_set.php),
+        ToolName::Protoc => fn_tool.invoke(&tool_set.protoc),
+        ToolName::Cmake => fn_tool.invoke(&tool_set.cmake),
+        ToolName::Ninja => fn_tool.invoke(&tool_set.ninja),
+        ToolName::Llvm => fn_tool.invoke(&tool_set.llvm),
+        ToolName::Manifest(name) => fn_tool.invoke(tool_set.manifest(name)),
+        ToolName::Plugin(name) => fn_tool.invoke(tool_set.plugin(name)),
     }
 }
 
-async fn async_invoke_tool<FT: AsyncFnTool>(
+pub(super) async fn async_invoke_tool<FT: AsyncFnTool>(
     tool_set: &ToolSet,
-    tool_name: ToolName,
+    tool_name: &ToolName,
     fn_tool: &FT,
 ) -> FT::Output {
     match tool_name {
         ToolName::Dotnet => fn_tool.invoke(&tool_set.dotnet).await,
         ToolName::Liberica => fn_tool.invoke(&tool_set.liberica).await,
+        ToolName::Temurin => fn_tool.invoke(&tool_set.temurin).await,
+        ToolName::Graalvm => fn_tool.invoke(&tool_set.graalvm).await,
         ToolName::Go => fn_tool.invoke(&tool_set.go).await,
         ToolName::Node => fn_tool.invoke(&tool_set.node).await,
         ToolName::Pnpm => fn_tool.invoke(&tool_set.pnpm).await,
+        ToolName::Erlang => fn_tool.invoke(&tool_set.erlang).await,
+        ToolName::Elixir => fn_tool.invoke(&tool_set.elixir).await,
+        ToolName::Ruby => fn_tool.invoke(&tool_set.ruby).await,
+        ToolName::Kotlin => fn_tool.invoke(&tool_set.kotlin).await,
+        ToolName::Gradle => fn_tool.invoke(&tool_set.gradle).await,
+        ToolName::Maven => fn_tool.invoke(&tool_set.maven).await,
+        ToolName::Terraform => fn_tool.invoke(&tool_set.terraform).await,
+        ToolName::Opentofu => fn_tool.invoke(&tool_set.opentofu).await,
+        ToolName::Flutter => fn_tool.invoke(&tool_set.flutter).await,
+        ToolName::Swift => fn_tool.invoke(&tool_set.swift).await,
+        ToolName::Julia => fn_tool.invoke(&tool_set.julia).await,
+        ToolName::Php => fn_tool.invoke(&tool_set.php).await,
+        ToolName::Protoc => fn_tool.invoke(&tool_set.protoc).await,
+        ToolName::Cmake => fn_tool.invoke(&tool_set.cmake).await,
+        ToolName::Ninja => fn_tool.invoke(&tool_set.ninja).await,
+        ToolName::Llvm => fn_tool.invoke(&tool_set.llvm).await,
+        ToolName::Manifest(name) => fn_tool.invoke(tool_set.manifest(name)).await,
+        ToolName::Plugin(name) => fn_tool.invoke(tool_set.plugin(name)).await,
     }
 }
 
 impl ToolSet {
-    pub fn new(client: Arc<HttpClient>, default_platform: &DefaultPlatform) -> Self {
+    pub fn new(
+        client: Arc<HttpClient>,
+        data_dir: &Path,
+        default_platform: &DefaultPlatform,
+        defaults: FxHashMap<String, ToolDefaults>,
+        manifests: Vec<(SmolStr, manifest_tool::Manifest)>,
+        plugins: Vec<(SmolStr, plugin_tool::Plugin)>,
+        pinned_hashes: Option<PinnedHashes>,
+    ) -> Self {
         let resolve = |tool_name: &str| -> Option<SmolStr> {
-            default_platform
-                .tools
+            defaults
                 .get(tool_name)
-                .or(default_platform.global.as_ref())
-                .map(SmolStr::new)
+                .and_then(|defaults| defaults.platform.clone())
+                .or_else(|| {
+                    default_platform
+                        .tools
+                        .get(tool_name)
+                        .or(default_platform.global.as_ref())
+                        .map(SmolStr::new)
+                })
         };
         Self {
-            dotnet: dotnet_tool::Tool::new(client.clone(), resolve("dotnet")),
-            liberica: liberica_tool::Tool::new(client.clone(), resolve("liberica")),
-            go: go_tool::Tool::new(client.clone(), resolve("go")),
-            node: node_tool::Tool::new(client.clone(), resolve("node")),
-            pnpm: pnpm_tool::Tool::new(client),
+            dotnet: dotnet_tool::Tool::new(Arc::new(client.for_tool("dotnet")), resolve("dotnet")),
+            liberica: liberica_tool::Tool::new(
+                Arc::new(client.for_tool("liberica")),
+                resolve("liberica"),
+            ),
+            temurin: temurin_tool::Tool::new(
+                Arc::new(client.for_tool("temurin")),
+                resolve("temurin"),
+            ),
+            graalvm: graalvm_tool::Tool::new(
+                Arc::new(client.for_tool("graalvm")),
+                resolve("graalvm"),
+            ),
+            go: {
+                let go = go_tool::Tool::new(Arc::new(client.for_tool("go")), resolve("go"));
+                if defaults.get("go").is_some_and(|go| go.isolated_gopath) {
+                    go.with_isolated_gopath(data_dir)
+                } else {
+                    go
+                }
+            },
+            node: node_tool::Tool::new(Arc::new(client.for_tool("node")), resolve("node")),
+            pnpm: pnpm_tool::Tool::new(Arc::new(client.for_tool("pnpm"))),
+            erlang: erlang_tool::Tool::new(Arc::new(client.for_tool("erlang")), resolve("erlang")),
+            elixir: elixir_tool::Tool::new(Arc::new(client.for_tool("elixir"))),
+            ruby: ruby_tool::Tool::new(Arc::new(client.for_tool("ruby")), resolve("ruby")),
+            kotlin: kotlin_tool::Tool::new(Arc::new(client.for_tool("kotlin"))),
+            gradle: gradle_tool::Tool::new(Arc::new(client.for_tool("gradle"))),
+            maven: maven_tool::Tool::new(Arc::new(client.for_tool("maven"))),
+            terraform: terraform_tool::Tool::new(
+                Arc::new(client.for_tool("terraform")),
+                resolve("terraform"),
+            ),
+            opentofu: opentofu_tool::Tool::new(
+                Arc::new(client.for_tool("opentofu")),
+                resolve("opentofu"),
+            ),
+            flutter: flutter_tool::Tool::new(
+                Arc::new(client.for_tool("flutter")),
+                resolve("flutter"),
+            ),
+            swift: swift_tool::Tool::new(Arc::new(client.for_tool("swift")), resolve("swift")),
+            julia: julia_tool::Tool::new(Arc::new(client.for_tool("julia")), resolve("julia")),
+            php: php_tool::Tool::new(Arc::new(client.for_tool("php")), resolve("php")),
+            protoc: protoc_tool::Tool::new(Arc::new(client.for_tool("protoc")), resolve("protoc")),
+            cmake: cmake_tool::Tool::new(Arc::new(client.for_tool("cmake")), resolve("cmake")),
+            ninja: ninja_tool::Tool::new(Arc::new(client.for_tool("ninja")), resolve("ninja")),
+            llvm: llvm_tool::Tool::new(Arc::new(client.for_tool("llvm")), resolve("llvm")),
+            manifests: manifests
+                .into_iter()
+                .map(|(name, manifest)| {
+                    let tool = manifest_tool::Tool::new(
+                        Arc::new(client.for_tool(&name)),
+                        manifest,
+                        resolve(&name),
+                    );
+                    (name, tool)
+                })
+                .collect(),
+            plugins: plugins
+                .into_iter()
+                .map(|(name, plugin)| {
+                    let default_platform = resolve(&name);
+                    let tool = plugin_tool::Tool::new(name.clone(), plugin, default_platform);
+                    (name, tool)
+                })
+                .collect(),
+            defaults,
+            pinned_hashes,
         }
     }
 
-    pub fn tool_info(&self, tool: ToolName) -> &ToolInfo {
+    fn manifest(&self, name: &str) -> &manifest_tool::Tool {
+        self.manifests
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, tool)| tool)
+            .expect("manifest tool names are registered from the same manifests")
+    }
+
+    fn plugin(&self, name: &str) -> &plugin_tool::Tool {
+        self.plugins
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, tool)| tool)
+            .expect("plugin tool names are registered from the same plugins")
+    }
+
+    /// The flavor used when none is given, from the config or the tool.
+    pub fn default_flavor(&self, tool: &ToolName) -> Option<&SmolStr> {
+        self.defaults
+            .get(&tool.command_name())
+            .and_then(|defaults| defaults.flavor.as_ref())
+            .or(self.tool_info(tool).default_flavor.as_ref())
+    }
+
+    pub fn tool_info(&self, tool: &ToolName) -> &ToolInfo {
         match tool {
             ToolName::Dotnet => self.dotnet.info(),
             ToolName::Liberica => self.liberica.info(),
+            ToolName::Temurin => self.temurin.info(),
+            ToolName::Graalvm => self.graalvm.info(),
             ToolName::Go => self.go.info(),
             ToolName::Node => self.node.info(),
             ToolName::Pnpm => self.pnpm.info(),
+            ToolName::Erlang => self.erlang.info(),
+            ToolName::Elixir => self.elixir.info(),
+            ToolName::Ruby => self.ruby.info(),
+            ToolName::Kotlin => self.kotlin.info(),
+            ToolName::Gradle => self.gradle.info(),
+            ToolName::Maven => self.maven.info(),
+            ToolName::Terraform => self.terraform.info(),
+            ToolName::Opentofu => self.opentofu.info(),
+            ToolName::Flutter => self.flutter.info(),
+            ToolName::Swift => self.swift.info(),
+            ToolName::Julia => self.julia.info(),
+            ToolName::Php => self.php.info(),
+            ToolName::Protoc => self.protoc.info(),
+            ToolName::Cmake => self.cmake.info(),
+            ToolName::Ninja => self.ninja.info(),
+            ToolName::Llvm => self.llvm.info(),
+            ToolName::Manifest(name) => self.manifest(name).info(),
+            ToolName::Plugin(name) => self.plugin(name).info(),
         }
     }
 
-    pub fn all_infos(&self) -> [(String, &ToolInfo); 5] {
-        [
-            (ToolName::Go.command_name(), self.tool_info(ToolName::Go)),
-            (
-                ToolName::Liberica.command_name(),
-                self.tool_info(ToolName::Liberica),
-            ),
-            (
-                ToolName::Node.command_name(),
-                self.tool_info(ToolName::Node),
-            ),
-            (
-                ToolName::Pnpm.command_name(),
-                self.tool_info(ToolName::Pnpm),
-            ),
-            (
-                ToolName::Dotnet.command_name(),
-                self.tool_info(ToolName::Dotnet),
-            ),
-        ]
+    pub fn all_infos(&self) -> Vec<(String, &ToolInfo)> {
+        let builtins = [
+            ToolName::Go,
+            ToolName::Liberica,
+            ToolName::Temurin,
+            ToolName::Graalvm,
+            ToolName::Node,
+            ToolName::Pnpm,
+            ToolName::Dotnet,
+            ToolName::Erlang,
+            ToolName::Elixir,
+            ToolName::Ruby,
+            ToolName::Kotlin,
+            ToolName::Gradle,
+            ToolName::Maven,
+            ToolName::Terraform,
+            ToolName::Opentofu,
+            ToolName::Flutter,
+            ToolName::Swift,
+            ToolName::Julia,
+            ToolName::Php,
+            ToolName::Protoc,
+            ToolName::Cmake,
+            ToolName::Ninja,
+            ToolName::Llvm,
+        ];
+        builtins
+            .iter()
+            .map(|tool| (tool.command_name(), self.tool_info(tool)))
+            .chain(
+                self.manifests
+                    .iter()
+                    .map(|(name, tool)| (name.to_string(), tool.info())),
+            )
+            .chain(
+                self.plugins
+                    .iter()
+                    .map(|(name, tool)| (name.to_string(), tool.info())),
+            )
+            .collect()
     }
 
-    pub fn describe_flavor(&self, tool: ToolName, flavor: &str) -> &'static str {
+    pub fn describe_flavor(&self, tool: &ToolName, flavor: &str) -> &'static str {
         invoke_tool(self, tool, &DescribeFlavorFn { flavor })
     }
+
+    /// Names of the executables the tool ships, the entry first.
+    pub fn exe_names(&self, tool: &ToolName) -> anyhow::Result<Vec<SmolStr>> {
+        // The names of the executables that every tag has don't depend on where it is.
+        invoke_tool(
+            self,
+            tool,
+            &ExeNamesFn {
+                tag_dir: Path::new(""),
+            },
+        )
+    }
+
+    /// Names of the executables of an installed tag, including those only some tags have, such
+    /// as the scripts of `corepack enable` for node.
+    pub fn tag_exe_names(&self, tool: &ToolName, tag_dir: &Path) -> anyhow::Result<Vec<SmolStr>> {
+        invoke_tool(self, tool, &ExeNamesFn { tag_dir })
+    }
+
+    pub fn support_matrix(&self, tool: &ToolName) -> Option<Vec<(SmolStr, Vec<SmolStr>)>> {
+        invoke_tool(self, tool, &SupportMatrixFn)
+    }
+
+    /// The directories of the executables of a tag, and the variables `avm run` sets for it.
+    pub fn tag_env(&self, tool: &ToolName, tag_dir: &Path) -> anyhow::Result<TagEnv> {
+        invoke_tool(self, tool, &TagEnvFn { tag_dir })
+    }
+}
+
+type TagEnv = (Vec<PathBuf>, Vec<(OsString, OsString)>);
+
+struct TagEnvFn<'a> {
+    tag_dir: &'a Path,
+}
+
+impl FnTool for TagEnvFn<'_> {
+    type Output = anyhow::Result<TagEnv>;
+
+    fn invoke(&self, tool: &impl GeneralTool) -> Self::Output {
+        let mut bin_dirs = Vec::<PathBuf>::new();
+        for (_, path) in tool.exe_paths(self.tag_dir.to_path_buf())? {
+            if let Some(dir) = path.parent() {
+                if !bin_dirs.iter().any(|bin_dir| bin_dir == dir) {
+                    bin_dirs.push(dir.to_path_buf());
+                }
+            }
+        }
+        Ok((bin_dirs, tool.env_vars(self.tag_dir)))
+    }
+}
+
+struct ExeNamesFn<'a> {
+    tag_dir: &'a Path,
+}
+
+impl FnTool for ExeNamesFn<'_> {
+    type Output = anyhow::Result<Vec<SmolStr>>;
+
+    fn invoke(&self, tool: &impl GeneralTool) -> Self::Output {
+        let exe_paths = tool.exe_paths(self.tag_dir.to_path_buf())?;
+        Ok(exe_paths.into_iter().map(|(name, _)| name).collect())
+    }
+}
+
+struct SupportMatrixFn;
+
+impl FnTool for SupportMatrixFn {
+    type Output = Option<Vec<(SmolStr, Vec<SmolStr>)>>;
+
+    fn invoke(&self, tool: &impl GeneralTool) -> Self::Output {
+        general_tool::support_matrix(tool)
+    }
 }
 
 struct DescribeFlavorFn<'a> {
@@ -143,7 +562,7 @@ impl FnTool for DescribeFlavorFn<'_> {
     }
 }
 
-#[derive(Debug, Clone, Args)]
+#[derive(Debug, Clone, Default, Args)]
 pub struct SelectorArgs {
     #[arg(
         short = 'v',
@@ -157,42 +576,148 @@ pub struct SelectorArgs {
         help = "Version prefix in strict x, x.y, or x.y.z format."
     )]
     pub version_prefix: Option<String>,
+    #[arg(
+        short = 's',
+        long,
+        value_name = "spec",
+        conflicts_with_all = ["version", "version_prefix"],
+        help = "Version spec: `latest`, `lts`, a prefix like `18` or `18.x`, a range like `>=1.21 <1.23`, or an exact version."
+    )]
+    pub spec: Option<String>,
     #[arg(
         short = 'p',
         long,
-        help = "Target platform identifier. Defaults to the avm binary's compile-target platform unless overridden by config."
+        help = "Target platform identifier. Defaults to the current platform unless overridden by config."
     )]
     pub platform: Option<String>,
     #[arg(short = 'f', long, help = "Tool-specific flavor identifier.")]
     pub flavor: Option<String>,
-    #[arg(long = "lts-only", help = "Only allow LTS releases.")]
+    #[arg(
+        long,
+        conflicts_with_all = ["version", "version_prefix", "spec"],
+        help = "Select the newest release, or the newest LTS release with `--lts`."
+    )]
+    pub latest: bool,
+    #[arg(
+        long = "lts-only",
+        visible_alias = "lts",
+        help = "Only allow LTS releases. Tools without LTS releases ignore it with a warning."
+    )]
     pub lts_only: bool,
-    #[arg(long = "allow-prere", help = "Allow prerelease versions (beta/rc).")]
-    pub allow_prerelease: bool,
+    #[arg(
+        long,
+        visible_alias = "allow-prere",
+        help = "Include prerelease versions (beta/rc), which are skipped unless an exact version is given."
+    )]
+    pub include_prerelease: bool,
+    #[arg(
+        long,
+        help = "Liberica only: select a build with LibericaFX, adding `+fx` to the flavor."
+    )]
+    pub fx: bool,
+    #[arg(
+        long,
+        value_name = "type",
+        help = "Liberica only: archive package type, `tar.gz` or `zip`, added to the flavor like `+zip`."
+    )]
+    pub package_type: Option<String>,
+    #[arg(
+        long,
+        value_name = "channel",
+        conflicts_with = "flavor",
+        help = "Node only: release channel, `release`, `rc` or `nightly`. The `rc` and `nightly` channels are the flavors of the same name."
+    )]
+    pub channel: Option<String>,
 }
 
 impl SelectorArgs {
+    /// Fills the options not given on the command line from the `tools` table of the config,
+    /// then adds the tool-specific options to the flavor. The platform default is already the
+    /// default platform of the tool.
+    pub fn apply_defaults(&mut self, tools: &ToolSet, tool: &ToolName) -> anyhow::Result<()> {
+        if let Some(defaults) = tools.defaults.get(&tool.command_name()) {
+            if self.flavor.is_none() {
+                self.flavor = defaults.flavor.as_ref().map(ToString::to_string);
+            }
+            // An exact version is asked for even if it is not an LTS release.
+            self.lts_only |= defaults.lts_only && !self.asks_exact_version();
+            self.include_prerelease |= defaults.include_prerelease;
+        }
+        let mut requested = Vec::new();
+        if self.fx {
+            requested.push(ToolOption::Fx);
+        }
+        if self.package_type.is_some() {
+            requested.push(ToolOption::PackageType);
+        }
+        if self.channel.is_some() {
+            requested.push(ToolOption::Channel);
+        }
+        if !requested.is_empty() {
+            check_tool_options(tools, tool, &requested)?;
+            let options = FlavorOptions {
+                fx: self.fx,
+                package_type: self.package_type.as_deref().map(SmolStr::from),
+                channel: self.channel.as_deref().map(SmolStr::from),
+            };
+            self.flavor = invoke_tool(
+                tools,
+                tool,
+                &FlavorWithOptionsFn {
+                    flavor: self.flavor.as_deref(),
+                    options: &options,
+                },
+            )?
+            .map(|flavor| flavor.to_string());
+        }
+        if let Some(flavor) = &self.flavor {
+            self.include_prerelease |= invoke_tool(tools, tool, &IsPrereleaseFlavorFn { flavor });
+        }
+        Ok(())
+    }
+
+    fn asks_exact_version(&self) -> bool {
+        self.version.is_some()
+            || self
+                .spec
+                .as_deref()
+                .is_some_and(|spec| matches!(VersionSpec::parse(spec), Ok(VersionSpec::Exact(_))))
+    }
+
     fn is_empty(&self) -> bool {
         self.version.is_none()
             && self.version_prefix.is_none()
+            && self.spec.is_none()
             && self.platform.is_none()
             && self.flavor.is_none()
+            && !self.latest
             && !self.lts_only
-            && !self.allow_prerelease
+            && !self.include_prerelease
+            && !self.fx
+            && self.package_type.is_none()
+            && self.channel.is_none()
     }
 }
 
-fn resolve_selector_filters(
+pub(super) fn resolve_selector_filters(
     tool: &impl GeneralTool,
     selector: &SelectorArgs,
 ) -> anyhow::Result<(Option<SmolStr>, Option<SmolStr>, VersionFilter)> {
     let (platform, flavor) = resolve_platform_flavor(tool, &selector.platform, &selector.flavor);
-    let version_filter = to_version_filter(
-        selector.version.as_deref(),
-        selector.version_prefix.as_deref(),
-        selector.lts_only,
-        selector.allow_prerelease,
-    )?;
+    let version_filter = match &selector.spec {
+        Some(spec) => {
+            let mut version_filter =
+                VersionSpec::parse(spec)?.into_filter(selector.include_prerelease);
+            version_filter.lts_only |= selector.lts_only;
+            version_filter
+        }
+        None => to_version_filter(
+            selector.version.as_deref(),
+            selector.version_prefix.as_deref(),
+            selector.lts_only,
+            selector.include_prerelease,
+        )?,
+    };
     Ok((platform, flavor, version_filter))
 }
 
@@ -209,6 +734,53 @@ pub struct InstallArgs {
     pub default: bool,
     #[arg(short = 'u', long, help = "Replace existing tag if already installed.")]
     pub update: bool,
+    #[arg(
+        long,
+        conflicts_with = "from_bundle",
+        help = "Replace the existing tag, such as one left broken by an interrupted install, and remove the stale lock of an interrupted install. The tag is kept if the install fails. Aliases are not replaced."
+    )]
+    pub force: bool,
+    #[arg(
+        long,
+        help = "Print the resolved version, URL, hash and tag directory without downloading."
+    )]
+    pub dry_run: bool,
+    #[arg(
+        long,
+        value_name = "bundle",
+        conflicts_with_all = ["dry_run", "stream", "version", "version_prefix", "spec"],
+        help = "Install from a bundle written by `avm bundle` instead of downloading, picking the release of the platform and flavor."
+    )]
+    pub from_bundle: Option<PathBuf>,
+    #[arg(
+        long,
+        help = "Keep the temporary directory if the install fails, for debugging."
+    )]
+    pub keep_partial: bool,
+    #[arg(
+        long,
+        value_name = "n",
+        default_value_t = 0,
+        conflicts_with_all = ["from_bundle", "keep_partial"],
+        help = "Download and install again up to n times if the download breaks or doesn't match its hash."
+    )]
+    pub retries: u32,
+    #[arg(
+        long,
+        help = "Extract tar archives while downloading instead of saving them first, using less disk space."
+    )]
+    pub stream: bool,
+    #[arg(
+        long,
+        help = "Node only: run `corepack enable` once installed, adding `yarn` and `pnpm` next to `node`."
+    )]
+    pub with_corepack: bool,
+    #[arg(
+        long,
+        value_name = "version",
+        help = "Node only: replace the bundled npm with this version once installed, through `npm install --global`."
+    )]
+    pub npm: Option<String>,
 }
 
 #[derive(Debug, Clone, Args)]
@@ -217,6 +789,22 @@ pub struct GetVersArgs {
     pub tool: ToolName,
     #[clap(flatten)]
     pub selector: SelectorArgs,
+    #[arg(long, value_name = "N", help = "Only print the newest N versions.")]
+    pub limit: Option<usize>,
+    #[arg(long, help = "Print the newest version first.")]
+    pub reverse: bool,
+    #[arg(
+        long,
+        help = "Only print the newest version of each major version, the leading number of the version."
+    )]
+    pub majors_only: bool,
+    #[arg(long, help = "Print a table with a column marking the LTS releases.")]
+    pub table: bool,
+    #[arg(
+        long,
+        help = "Mark the versions past their end of life, for the tools with published support windows."
+    )]
+    pub eol: bool,
 }
 
 #[derive(Debug, Clone, Args)]
@@ -227,6 +815,25 @@ pub struct GetDowninfoArgs {
     pub selector: SelectorArgs,
 }
 
+#[derive(Debug, Clone, Args)]
+pub struct ChangelogArgs {
+    #[arg(value_enum, help = "Tool name.")]
+    pub tool: ToolName,
+    #[arg(
+        value_name = "version",
+        conflicts_with_all = ["version", "version_prefix", "spec", "latest"],
+        help = "Version spec of the release, like `--spec`. Defaults to the latest release."
+    )]
+    pub release: Option<String>,
+    #[clap(flatten)]
+    pub selector: SelectorArgs,
+    #[arg(
+        long,
+        help = "Open the release notes in the browser instead of printing the URL."
+    )]
+    pub open: bool,
+}
+
 #[derive(Debug, Clone, Args)]
 pub struct InstallLocalArgs {
     #[arg(value_enum, help = "Tool name.")]
@@ -235,9 +842,13 @@ pub struct InstallLocalArgs {
     pub archive: PathBuf,
     #[arg(value_name = "target_tag", help = "Tag to install as.")]
     pub target_tag: String,
-    #[arg(long, value_name = "version", help = "Tool's version.")]
-    pub version: String,
-    #[arg(long, help = "If tool's version is LTS.")]
+    #[arg(
+        long,
+        value_name = "version",
+        help = "Tool's version. Required unless the archive was written by `avm export-tag`, whose recorded version it overrides."
+    )]
+    pub version: Option<String>,
+    #[arg(long, requires = "version", help = "If tool's version is LTS.")]
     pub lts: bool,
     #[arg(
         long,
@@ -247,14 +858,54 @@ pub struct InstallLocalArgs {
     pub hash: Option<String>,
     #[arg(long, help = "Replace existing tag if already installed.")]
     pub update: bool,
+    #[arg(
+        long,
+        help = "Replace the existing tag, such as one left broken by an interrupted install, and remove the stale lock of an interrupted install. The tag is kept if the install fails. Aliases are not replaced."
+    )]
+    pub force: bool,
     #[arg(long, help = "Set installed version as the `default` alias.")]
     pub default: bool,
+    #[arg(
+        long,
+        help = "Keep the temporary directory if the install fails, for debugging."
+    )]
+    pub keep_partial: bool,
 }
 
 #[derive(Debug, Clone, Args)]
 pub struct ListArgs {
+    #[arg(value_enum, help = "Tool name.", required_unless_present = "all")]
+    pub tool: Option<ToolName>,
+    #[arg(
+        long,
+        help = "Also show the version, platform, flavor and install time of each tag."
+    )]
+    pub verbose: bool,
+    #[arg(
+        long,
+        conflicts_with_all = ["tool", "verbose"],
+        help = "List the tags of every tool in one table, with their versions and disk usage."
+    )]
+    pub all: bool,
+    #[arg(long, requires = "all", help = "Print the table of `--all` as JSON.")]
+    pub json: bool,
+    #[arg(
+        long,
+        conflicts_with = "all",
+        help = "Mark the tags whose versions are past their end of life, for the tools with published support windows."
+    )]
+    pub eol: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct InfoArgs {
     #[arg(value_enum, help = "Tool name.")]
     pub tool: ToolName,
+    #[arg(
+        help = "Tag to show. Defaults to `default`.",
+        default_value = "default"
+    )]
+    pub tag: String,
 }
 
 #[derive(Debug, Clone, Args)]
@@ -277,6 +928,12 @@ pub struct EntryPathArgs {
         default_value = "default"
     )]
     pub tag: String,
+    #[arg(
+        long,
+        value_name = "name",
+        help = "Executable to resolve instead of the tool's entry, for example `javac` for liberica."
+    )]
+    pub exe: Option<String>,
 }
 
 #[derive(Debug, Clone, Args)]
@@ -291,6 +948,12 @@ pub struct RunArgs {
     pub tag: Option<String>,
     #[clap(flatten)]
     pub selector: SelectorArgs,
+    #[arg(
+        long,
+        value_name = "name",
+        help = "Executable to run instead of the tool's entry, for example `npm` for node."
+    )]
+    pub exe: Option<String>,
     #[arg(
         help = "Arguments passed to the tool executable. Use `--` before these arguments.",
         last = true,
@@ -300,13 +963,113 @@ pub struct RunArgs {
 }
 
 #[derive(Debug, Clone, Args)]
+pub struct InstallUrlArgs {
+    #[arg(value_enum, help = "Tool name.")]
+    pub tool: ToolName,
+    #[arg(
+        value_name = "url",
+        help = "URL of the archive, also `s3://bucket/key` or `gs://bucket/key`."
+    )]
+    pub url: String,
+    #[arg(long, value_name = "tag", help = "Tag to install as.")]
+    pub tag: String,
+    #[arg(
+        long,
+        value_name = "version",
+        help = "Tool's version. Defaults to the tag."
+    )]
+    pub version: Option<String>,
+    #[arg(long, help = "If tool's version is LTS.")]
+    pub lts: bool,
+    #[arg(
+        long,
+        value_name = "hex",
+        help = "SHA-256 of the archive. The archive is not verified without it."
+    )]
+    pub sha256: Option<String>,
+    #[arg(long, help = "Replace existing tag if already installed.")]
+    pub update: bool,
+    #[arg(long, help = "Set installed version as the `default` alias.")]
+    pub default: bool,
+    #[arg(
+        long,
+        help = "Keep the temporary directory if the install fails, for debugging."
+    )]
+    pub keep_partial: bool,
+    #[arg(
+        long,
+        value_name = "n",
+        default_value_t = 0,
+        conflicts_with_all = ["keep_partial"],
+        help = "Download and install again up to n times if the download breaks or doesn't match its hash."
+    )]
+    pub retries: u32,
+    #[arg(
+        long,
+        help = "Extract tar archives while downloading instead of saving them first, using less disk space."
+    )]
+    pub stream: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 pub struct AliasArgs {
+    #[command(subcommand)]
+    pub command: Option<AliasCommand>,
+    #[arg(value_enum, required = true, help = "Tool name.")]
+    pub tool: Option<ToolName>,
+    #[arg(value_name = "src_tag", required = true, help = "Source tag.")]
+    pub src_tag: Option<String>,
+    #[arg(
+        value_name = "alias_tag",
+        required = true,
+        help = "Alias tag to create."
+    )]
+    pub alias_tag: Option<String>,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum AliasCommand {
+    #[command(
+        about = "List the aliases with their targets",
+        long_about = "Lists the alias tags with their targets. Targets that no longer exist are marked as missing."
+    )]
+    List {
+        #[arg(value_enum, help = "Tool name.")]
+        tool: ToolName,
+    },
+
+    #[command(about = "Delete aliases, keeping their targets")]
+    Delete {
+        #[arg(value_enum, help = "Tool name.")]
+        tool: ToolName,
+        #[arg(value_name = "alias_tag", required = true, num_args = 1.., help = "Alias tag(s) to delete.")]
+        aliases: Vec<String>,
+    },
+
+    #[command(
+        about = "Point an existing alias to another tag",
+        long_about = "Points an existing alias tag to another tag. Unlike creating the alias again, fails if the alias doesn't exist."
+    )]
+    Move {
+        #[arg(value_enum, help = "Tool name.")]
+        tool: ToolName,
+        #[arg(value_name = "alias_tag", help = "Alias tag to move.")]
+        alias_tag: String,
+        #[arg(value_name = "new_target", help = "Tag to point the alias to.")]
+        new_target: String,
+    },
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct RepairAliasArgs {
     #[arg(value_enum, help = "Tool name.")]
     pub tool: ToolName,
-    #[arg(value_name = "src_tag", help = "Source tag.")]
-    pub src_tag: String,
-    #[arg(value_name = "alias_tag", help = "Alias tag to create.")]
-    pub alias_tag: String,
+    #[arg(
+        value_name = "alias_tag",
+        help = "Alias tag(s) to repair. Defaults to all aliases."
+    )]
+    pub tags: Vec<String>,
 }
 
 #[derive(Debug, Clone, Args)]
@@ -315,63 +1078,376 @@ pub struct CopyArgs {
     pub tool: ToolName,
     #[arg(value_name = "src_tag", help = "Source tag.")]
     pub src_tag: String,
-    #[arg(value_name = "target_tag", help = "Target tag.")]
-    pub target_tag: String,
+    #[arg(
+        value_name = "target_tag",
+        required_unless_present_any = ["to_tag", "from_data_dir"],
+        conflicts_with = "to_tag",
+        help = "Target tag. Defaults to the source tag when copying from another data directory."
+    )]
+    pub target_tag: Option<String>,
+    #[arg(
+        long,
+        value_name = "tag",
+        help = "Target tag, instead of the positional argument."
+    )]
+    pub to_tag: Option<String>,
+    #[arg(
+        long,
+        value_name = "path",
+        help = "Copy the tag from another data directory, such as a mounted backup."
+    )]
+    pub from_data_dir: Option<PathBuf>,
 }
 
 #[derive(Debug, Clone, Args)]
-pub struct RemoveArgs {
+pub struct ExportTagArgs {
     #[arg(value_enum, help = "Tool name.")]
     pub tool: ToolName,
-    #[arg(value_name = "tag", required = true, num_args = 1.., help = "Tag(s) to remove.")]
-    pub tags: Vec<String>,
     #[arg(
-        long,
-        help = "Allow deleting an alias target and leaving dangling aliases."
+        value_name = "tag",
+        help = "Tag to export. An alias exports its target."
     )]
-    pub allow_dangling: bool,
+    pub tag: String,
+    #[arg(
+        value_name = "output",
+        help = "Path of the `.tar.zst` archive to write."
+    )]
+    pub output: PathBuf,
 }
 
 #[derive(Debug, Clone, Args)]
-pub struct CleanArgs {
+pub struct BundleArgs {
     #[arg(value_enum, help = "Tool name.")]
     pub tool: ToolName,
-}
-
-struct RunInstallFn<'a> {
-    tool_name: &'a str,
-    client: &'a HttpClient,
-    tools_base: &'a Path,
-    args: &'a InstallArgs,
-}
-
-impl AsyncFnTool for RunInstallFn<'_> {
-    type Output = anyhow::Result<()>;
-
-    async fn invoke(&self, tool: &impl GeneralTool) -> Self::Output {
+    #[arg(
+        short = 'v',
+        long = "version",
+        help = "Exact version string, for example 22.13.1."
+    )]
+    pub version: Option<String>,
+    #[arg(
+        short = 'x',
+        long = "verpfx",
+        help = "Version prefix in strict x, x.y, or x.y.z format."
+    )]
+    pub version_prefix: Option<String>,
+    #[arg(
+        short = 's',
+        long,
+        value_name = "spec",
+        conflicts_with_all = ["version", "version_prefix"],
+        help = "Version spec: `latest`, `lts`, a prefix like `18` or `18.x`, a range like `>=1.21 <1.23`, or an exact version."
+    )]
+    pub spec: Option<String>,
+    #[arg(
+        short = 'p',
+        long = "platform",
+        value_name = "platform",
+        help = "Target platform identifier, repeated to bundle several platforms. Defaults to the current platform unless overridden by config."
+    )]
+    pub platforms: Vec<String>,
+    #[arg(short = 'f', long, help = "Tool-specific flavor identifier.")]
+    pub flavor: Option<String>,
+    #[arg(
+        long = "lts-only",
+        visible_alias = "lts",
+        help = "Only allow LTS releases. Tools without LTS releases ignore it with a warning."
+    )]
+    pub lts_only: bool,
+    #[arg(
+        long,
+        visible_alias = "allow-prere",
+        help = "Include prerelease versions (beta/rc), which are skipped unless an exact version is given."
+    )]
+    pub include_prerelease: bool,
+    #[arg(
+        short = 'o',
+        long,
+        value_name = "output",
+        help = "Path of the bundle to write, a tar file."
+    )]
+    pub output: PathBuf,
+}
+
+impl BundleArgs {
+    /// The selectors of the releases to bundle, one for each platform.
+    fn selectors(&self) -> Vec<SelectorArgs> {
+        let platforms = if self.platforms.is_empty() {
+            vec![None]
+        } else {
+            self.platforms.iter().cloned().map(Some).collect()
+        };
+        platforms
+            .into_iter()
+            .map(|platform| SelectorArgs {
+                version: self.version.clone(),
+                version_prefix: self.version_prefix.clone(),
+                spec: self.spec.clone(),
+                platform,
+                flavor: self.flavor.clone(),
+                lts_only: self.lts_only,
+                include_prerelease: self.include_prerelease,
+                ..SelectorArgs::default()
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct RenameArgs {
+    #[arg(value_enum, help = "Tool name.")]
+    pub tool: ToolName,
+    #[arg(value_name = "tag", help = "Tag to rename.")]
+    pub tag: String,
+    #[arg(value_name = "new_tag", help = "New tag name.")]
+    pub new_tag: String,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct RemoveArgs {
+    #[arg(value_enum, help = "Tool name.")]
+    pub tool: ToolName,
+    #[arg(value_name = "tag", required = true, num_args = 1.., help = "Tag(s) to remove.")]
+    pub tags: Vec<String>,
+    #[arg(
+        long,
+        help = "Allow deleting an alias target and leaving dangling aliases."
+    )]
+    pub allow_dangling: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+#[command(group(
+    clap::ArgGroup::new("selector")
+        .required(true)
+        .multiple(true)
+        .args(["version_prefix", "major", "platform", "flavor", "all_but_latest", "unused_for"])
+))]
+pub struct PruneArgs {
+    #[arg(value_enum, help = "Tool name.")]
+    pub tool: ToolName,
+    #[arg(
+        short = 'x',
+        long = "verpfx",
+        help = "Version prefix in strict x, x.y, or x.y.z format."
+    )]
+    pub version_prefix: Option<String>,
+    #[arg(
+        long,
+        conflicts_with = "version_prefix",
+        help = "Major version, the same as `--verpfx <major>`."
+    )]
+    pub major: Option<u32>,
+    #[arg(short = 'p', long, help = "Only prune tags of this platform.")]
+    pub platform: Option<String>,
+    #[arg(short = 'f', long, help = "Only prune tags of this flavor.")]
+    pub flavor: Option<String>,
+    #[arg(
+        long,
+        value_name = "n",
+        default_value_t = 0,
+        help = "Keep the newest n matching tags."
+    )]
+    pub all_but_latest: usize,
+    #[arg(
+        long,
+        value_name = "period",
+        value_parser = parse_period,
+        help = "Only prune tags neither run nor installed within this period, such as `90d`, `12w` or `36h`. A number alone is days."
+    )]
+    pub unused_for: Option<Duration>,
+    #[arg(short = 'y', long, help = "Remove without asking for confirmation.")]
+    pub yes: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct CleanArgs {
+    #[arg(value_enum, required_unless_present = "cache", help = "Tool name.")]
+    pub tool: Option<ToolName>,
+    #[arg(long, help = "Also empty the download cache shared by the tools.")]
+    pub cache: bool,
+    #[arg(long, help = "Only report what would be removed.")]
+    pub dry_run: bool,
+    #[arg(
+        long,
+        value_name = "days",
+        help = "Only remove temporary directories not modified for at least this many days."
+    )]
+    pub older_than: Option<u64>,
+    #[arg(
+        long,
+        requires = "tool",
+        conflicts_with_all = ["cache", "older_than"],
+        help = "Only look for aliases whose targets no longer exist, and ask whether to delete or re-point each of them."
+    )]
+    pub dangling_aliases: bool,
+    #[arg(
+        long,
+        value_name = "tag",
+        requires = "dangling_aliases",
+        help = "Re-point the dangling aliases to this tag instead of asking."
+    )]
+    pub retarget: Option<String>,
+    #[arg(
+        short = 'y',
+        long,
+        requires = "dangling_aliases",
+        conflicts_with = "retarget",
+        help = "Delete the dangling aliases without asking."
+    )]
+    pub yes: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct DedupArgs {
+    #[arg(value_enum, help = "Tool name.")]
+    pub tool: ToolName,
+    #[arg(long, help = "Only report how much space would be saved.")]
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct PingArgs {
+    #[arg(value_enum, help = "Tool name.")]
+    pub tool: ToolName,
+}
+
+struct RunInstallFn<'a> {
+    tool_name: &'a str,
+    client: &'a HttpClient,
+    tools_base: &'a Path,
+    pinned_hashes: Option<&'a PinnedHashes>,
+    args: &'a InstallArgs,
+}
+
+impl AsyncFnTool for RunInstallFn<'_> {
+    /// The installed tag, `None` on a dry run.
+    type Output = anyhow::Result<Option<SmolStr>>;
+
+    async fn invoke(&self, tool: &impl GeneralTool) -> Self::Output {
         let tool_name = self.tool_name;
         let client = self.client;
         let tools_base = self.tools_base;
         let args = self.args;
 
+        if let Some(bundle) = &args.from_bundle {
+            let (platform, _) = resolve_platform_flavor(tool, &args.selector.platform, &None);
+            let (target_tag, url, download_state) = general_tool::BundleInstaller {
+                tool_name,
+                tool,
+                tools_base,
+                bundle,
+                platform,
+                flavor: option_to_smol_str(&args.selector.flavor),
+                update: args.update,
+                default: args.default,
+                keep_partial: args.keep_partial,
+                cancel: any_version_manager::cancellation_token(),
+            }
+            .install()
+            .await?;
+            drive_download_state(target_tag.clone(), url, download_state).await?;
+            return Ok(Some(target_tag));
+        }
+
         let (platform, flavor, install_version) = resolve_selector_filters(tool, &args.selector)?;
 
-        let (target_tag, download_url, download_state) = general_tool::InstallArgs {
+        let install_args = || general_tool::InstallArgs {
             tool_name,
             tool,
             client,
             tools_base,
-            platform,
-            flavor,
-            install_version,
+            platform: platform.clone(),
+            flavor: flavor.clone(),
+            install_version: install_version.clone(),
             update: args.update,
+            force: args.force,
             default: args.default,
+            keep_partial: args.keep_partial,
+            stream_extract: args.stream,
+            platform_fallback: args.selector.platform.is_none(),
+            pinned_hashes: self.pinned_hashes,
+            cancel: any_version_manager::cancellation_token(),
+        };
+        if args.dry_run {
+            let plan = install_args().plan().await?;
+            let overwrites = match (plan.exists, args.update, args.force) {
+                (false, _, _) => "no",
+                (true, _, true) => "yes, `--force` replaces the tag",
+                (true, true, false) => "yes",
+                (true, false, false) => {
+                    "no, the tag is installed already and `--update` replaces it"
+                }
+            };
+            print_install_plan(&plan, overwrites);
+            return Ok(None);
         }
-        .install()
+        let target_tag = with_retries(args.retries, async || {
+            let (target_tag, download_url, download_state) = install_args().install().await?;
+            drive_download_state(target_tag.clone(), download_url, download_state).await?;
+            Ok(target_tag)
+        })
         .await?;
 
-        drive_download_state(target_tag, download_url, download_state).await?;
+        Ok(Some(target_tag))
+    }
+}
+
+struct BundleFn<'a> {
+    tool_name: &'a str,
+    client: &'a HttpClient,
+    tools_base: &'a Path,
+    pinned_hashes: Option<&'a PinnedHashes>,
+    selectors: &'a [SelectorArgs],
+    output: &'a Path,
+}
+
+impl AsyncFnTool for BundleFn<'_> {
+    type Output = anyhow::Result<()>;
 
+    async fn invoke(&self, tool: &impl GeneralTool) -> Self::Output {
+        let cancel = any_version_manager::cancellation_token();
+        let mut releases = Vec::with_capacity(self.selectors.len());
+        for selector in self.selectors {
+            let (platform, flavor, install_version) = resolve_selector_filters(tool, selector)?;
+            let release = general_tool::InstallArgs {
+                tool_name: self.tool_name,
+                tool,
+                client: self.client,
+                tools_base: self.tools_base,
+                platform,
+                flavor,
+                install_version,
+                update: false,
+                force: false,
+                default: false,
+                keep_partial: false,
+                stream_extract: false,
+                platform_fallback: false,
+                pinned_hashes: self.pinned_hashes,
+                cancel,
+            }
+            .bundle_release()
+            .await?;
+            if releases
+                .iter()
+                .any(|bundled: &bundle::BundledRelease| bundled.tag == release.tag)
+            {
+                anyhow::bail!(
+                    "\"{}\" is bundled twice, remove the repeated platform",
+                    release.tag
+                );
+            }
+            releases.push(release);
+        }
+        let tags = releases
+            .iter()
+            .map(|release| release.tag.clone())
+            .collect::<Vec<_>>();
+        bundle::write(self.client, self.tool_name, releases, self.output, cancel).await?;
+        for tag in tags {
+            println!("bundled: {tag}");
+        }
         Ok(())
     }
 }
@@ -387,15 +1463,103 @@ impl AsyncFnTool for RunGetVersFn<'_> {
         let args = self.args;
         let (platform, flavor, version_filter) = resolve_selector_filters(tool, &args.selector)?;
 
-        let vers = general_tool::get_vers(tool, platform, flavor, version_filter).await?;
-        for v in vers {
-            println!("{}{}", v.version, if v.is_lts { " [LTS]" } else { "" });
+        let mut vers = general_tool::get_vers(tool, platform, flavor, version_filter).await?;
+        if args.majors_only {
+            vers = newest_of_majors(vers);
+        }
+        if let Some(limit) = args.limit {
+            vers.drain(..vers.len().saturating_sub(limit));
+        }
+        if args.reverse {
+            vers.reverse();
+        }
+        let mut eol = Vec::with_capacity(vers.len());
+        for v in &vers {
+            eol.push(args.eol && is_eol(tool, &v.version).await?);
+        }
+
+        if args.table {
+            let width = vers
+                .iter()
+                .map(|v| v.version.chars().count())
+                .chain(std::iter::once("VERSION".len()))
+                .max()
+                .unwrap_or_default();
+            let eol_header = if args.eol { "  EOL" } else { "" };
+            println!("{:<width$}  LTS{eol_header}", "VERSION");
+            for (v, eol) in vers.into_iter().zip(eol) {
+                let lts = if v.is_lts { "yes" } else { "-" };
+                if args.eol {
+                    println!(
+                        "{:<width$}  {lts:<3}  {}",
+                        v.version,
+                        if eol { "yes" } else { "-" }
+                    );
+                } else {
+                    println!("{:<width$}  {lts}", v.version);
+                }
+            }
+        } else {
+            for (v, eol) in vers.into_iter().zip(eol) {
+                println!(
+                    "{}{}{}",
+                    v.version,
+                    if v.is_lts { " [LTS]" } else { "" },
+                    if eol { " [EOL]" } else { "" }
+                );
+            }
         }
 
         Ok(())
     }
 }
 
+/// Whether the release cycle of `version` is past its end of life, `false` if unknown.
+async fn is_eol(tool: &impl GeneralTool, version: &str) -> anyhow::Result<bool> {
+    Ok(tool
+        .support_status(version)
+        .await?
+        .is_some_and(|status| status.is_eol))
+}
+
+/// The support status of each version, for the tools with published support windows.
+pub(super) struct SupportStatusFn<'a> {
+    pub versions: &'a [SmolStr],
+}
+
+impl AsyncFnTool for SupportStatusFn<'_> {
+    type Output = anyhow::Result<Vec<Option<SupportStatus>>>;
+
+    async fn invoke(&self, tool: &impl GeneralTool) -> Self::Output {
+        let mut statuses = Vec::with_capacity(self.versions.len());
+        for version in self.versions {
+            statuses.push(tool.support_status(version).await?);
+        }
+        Ok(statuses)
+    }
+}
+
+/// The last of each run of versions with the same major version, which is the newest one as
+/// the versions are sorted from the oldest.
+fn newest_of_majors(vers: Vec<Version>) -> Vec<Version> {
+    fn major(version: &Version) -> &str {
+        let end = version
+            .version
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(version.version.len());
+        &version.version[..end]
+    }
+
+    let mut newest: Vec<Version> = Vec::new();
+    for version in vers {
+        match newest.last_mut() {
+            Some(last) if major(last) == major(&version) => *last = version,
+            _ => newest.push(version),
+        }
+    }
+    newest
+}
+
 struct RunGetDowninfoFn<'a> {
     args: &'a GetDowninfoArgs,
 }
@@ -413,6 +1577,61 @@ impl AsyncFnTool for RunGetDowninfoFn<'_> {
     }
 }
 
+struct RunChangelogFn<'a> {
+    args: &'a ChangelogArgs,
+}
+
+impl AsyncFnTool for RunChangelogFn<'_> {
+    type Output = anyhow::Result<SmolStr>;
+
+    async fn invoke(&self, tool: &impl GeneralTool) -> Self::Output {
+        let tool_name = self.args.tool.command_name();
+        let (platform, flavor, version_filter) =
+            resolve_selector_filters(tool, &self.args.selector)?;
+        let version = general_tool::get_vers(tool, platform, flavor, version_filter)
+            .await?
+            .pop()
+            .ok_or(AvmError::VersionNotFound)?;
+        tool.release_notes_url(&version.version).ok_or_else(|| {
+            anyhow::anyhow!("The release notes of `{tool_name}` are not known to avm")
+        })
+    }
+}
+
+struct RunPingFn<'a> {
+    client: &'a HttpClient,
+}
+
+impl AsyncFnTool for RunPingFn<'_> {
+    type Output = anyhow::Result<()>;
+
+    async fn invoke(&self, tool: &impl GeneralTool) -> Self::Output {
+        let checks = general_tool::ping(tool, self.client).await;
+        for check in &checks {
+            let via = if check.direct { " (upstream)" } else { "" };
+            match &check.result {
+                Ok((status, latency)) => println!(
+                    "{:<4} {} {:>6} ms  {}{}",
+                    if check.is_ok() { "OK" } else { "FAIL" },
+                    status.as_u16(),
+                    latency.as_millis(),
+                    check.url,
+                    via
+                ),
+                Err(err) => println!("FAIL {}{}: {}", check.url, via, err),
+            }
+        }
+
+        // The upstream URL behind a mirror is informational, downloads don't use it.
+        let used = checks.iter().filter(|c| !c.direct);
+        let failed = used.clone().filter(|c| !c.is_ok()).count();
+        if failed > 0 {
+            anyhow::bail!("{failed} of {} endpoint(s) failed", used.count());
+        }
+        Ok(())
+    }
+}
+
 struct RunEntryPathFn<'a> {
     tool_name: &'a str,
     tools_base: &'a Path,
@@ -423,8 +1642,13 @@ impl FnTool for RunEntryPathFn<'_> {
     type Output = anyhow::Result<()>;
 
     fn invoke(&self, tool: &impl GeneralTool) -> Self::Output {
-        let path =
-            general_tool::get_entry_path(self.tool_name, tool, self.tools_base, &self.args.tag)?;
+        let args = self.args;
+        let path = match &args.exe {
+            Some(exe) => {
+                general_tool::get_exe_path(self.tool_name, tool, self.tools_base, &args.tag, exe)?
+            }
+            None => general_tool::get_entry_path(self.tool_name, tool, self.tools_base, &args.tag)?,
+        };
         println!("{}", path.display());
         Ok(())
     }
@@ -433,7 +1657,8 @@ impl FnTool for RunEntryPathFn<'_> {
 struct RunRunFn<'a> {
     tool_name: &'a str,
     client: &'a HttpClient,
-    tools_base: &'a Path,
+    tools: &'a ToolSet,
+    paths: &'a Paths,
     args: &'a RunArgs,
 }
 
@@ -443,7 +1668,7 @@ impl AsyncFnTool for RunRunFn<'_> {
     async fn invoke(&self, tool: &impl GeneralTool) -> Self::Output {
         let tool_name = self.tool_name;
         let client = self.client;
-        let tools_base = self.tools_base;
+        let tools_base = &self.paths.tool_dir;
         let args = self.args;
 
         let tag = if let Some(tag) = args.tag.as_ref() {
@@ -476,211 +1701,1404 @@ impl AsyncFnTool for RunRunFn<'_> {
                     flavor,
                     install_version: version_filter,
                     update: false,
+                    force: false,
                     default: false,
+                    keep_partial: false,
+                    stream_extract: false,
+                    platform_fallback: args.selector.platform.is_none(),
+                    pinned_hashes: self.tools.pinned_hashes.as_ref(),
+                    cancel: any_version_manager::cancellation_token(),
                 }
                 .install()
                 .await?;
                 drive_download_state(target_tag.clone(), download_url, download_state).await?;
+                finish_install(
+                    self.tools,
+                    &args.tool,
+                    &target_tag,
+                    &TagOptions::default(),
+                    self.paths,
+                )
+                .await?;
                 target_tag
             }
         } else {
             SmolStr::new("default")
         };
 
-        let entry_path = general_tool::get_entry_path(tool_name, tool, tools_base, &tag)?;
-        tool.run(entry_path, args.args.clone()).await
+        let entry_path = match &args.exe {
+            Some(exe) => general_tool::get_exe_path(tool_name, tool, tools_base, &tag, exe)?,
+            None => general_tool::get_entry_path(tool_name, tool, tools_base, &tag)?,
+        };
+        let tag_dir = general_tool::get_tag_path(tool_name, tools_base, &tag)?;
+        let env_vars = tool.env_vars(&tag_dir);
+        let command = tool.command(entry_path, args.args.clone(), env_vars)?;
+        if let Err(err) =
+            usage::record_use(&self.paths.state_dir, tools_base, tool_name, &tag).await
+        {
+            log::warn!("Failed to record the use of {tool_name} {tag}: {err}");
+        }
+        exec_tool(command).await
+    }
+}
+
+/// Runs the tool in place of avm, so that stdin, signals and job control behave as if it was
+/// started directly. Where the process can't be replaced, waits for the tool, leaving Ctrl-C to
+/// it, and fails with [`ToolExit`] if it exits unsuccessfully.
+async fn exec_tool(mut command: std::process::Command) -> anyhow::Result<()> {
+    let program = command.get_program().to_string_lossy().into_owned();
+    log::debug!("Running '{program}'");
+    log::logger().flush();
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        // Only returns on failure.
+        let err = command.exec();
+        Err(anyhow::Error::from(err).context(format!("Failed to run '{program}'")))
+    }
+    #[cfg(not(unix))]
+    {
+        use crate::avm_cli::{ToolExit, TOOL_RUNNING};
+        use anyhow::Context;
+        TOOL_RUNNING.store(true, std::sync::atomic::Ordering::Relaxed);
+        let status = any_version_manager::spawn_blocking(move || {
+            command
+                .status()
+                .with_context(|| format!("Failed to run '{program}'"))
+        })
+        .await?;
+        match status.code() {
+            Some(0) => Ok(()),
+            code => Err(ToolExit(code.unwrap_or(1)).into()),
+        }
     }
 }
 
 pub async fn run_install(
-    args: InstallArgs,
+    mut args: InstallArgs,
     tools: &ToolSet,
     client: &HttpClient,
     paths: &Paths,
 ) -> anyhow::Result<()> {
+    args.selector.apply_defaults(tools, &args.tool)?;
+    let mut requested = Vec::new();
+    if args.npm.is_some() {
+        requested.push(ToolOption::Npm);
+    }
+    if args.with_corepack {
+        requested.push(ToolOption::Corepack);
+    }
+    check_tool_options(tools, &args.tool, &requested)?;
     let tool_name = args.tool.command_name();
     let fn_tool = RunInstallFn {
         tool_name: &tool_name,
         client,
         tools_base: &paths.tool_dir,
+        pinned_hashes: tools.pinned_hashes.as_ref(),
         args: &args,
     };
-    async_invoke_tool(tools, args.tool, &fn_tool).await
-}
+    let Some(tag) = async_invoke_tool(tools, &args.tool, &fn_tool).await? else {
+        return Ok(());
+    };
 
-pub async fn run_get_vers(args: GetVersArgs, tools: &ToolSet) -> anyhow::Result<()> {
-    let fn_tool = RunGetVersFn { args: &args };
-    async_invoke_tool(tools, args.tool, &fn_tool).await
+    let options = TagOptions {
+        npm: args.npm.as_deref().map(SmolStr::from),
+        corepack: args.with_corepack,
+    };
+    finish_install(tools, &args.tool, &tag, &options, paths).await
 }
 
-pub async fn run_get_downinfo(args: GetDowninfoArgs, tools: &ToolSet) -> anyhow::Result<()> {
-    let fn_tool = RunGetDowninfoFn { args: &args };
-    async_invoke_tool(tools, args.tool, &fn_tool).await
+/// Fails if the tool doesn't support one of the `requested` options, naming the tools that do.
+fn check_tool_options(
+    tools: &ToolSet,
+    tool: &ToolName,
+    requested: &[ToolOption],
+) -> anyhow::Result<()> {
+    let supported = invoke_tool(tools, tool, &ToolOptionsFn);
+    let Some(option) = requested.iter().find(|option| !supported.contains(option)) else {
+        return Ok(());
+    };
+    let supporting: Vec<_> = <ToolName as ValueEnum>::value_variants()
+        .iter()
+        .filter(|other| invoke_tool(tools, other, &ToolOptionsFn).contains(option))
+        .map(ToolName::command_name)
+        .collect();
+    anyhow::bail!(
+        "`{}` is only supported by {}",
+        option.flag(),
+        supporting.join(", ")
+    )
 }
 
-pub async fn run_install_local(args: InstallLocalArgs, paths: &Paths) -> anyhow::Result<()> {
-    let tool_name = args.tool.command_name();
-    general_tool::LocalInstaller {
-        tool_name: &tool_name,
-        tools_base: &paths.tool_dir,
-        archive: args.archive,
-        target_tag: &args.target_tag,
-        version: Version {
-            version: args.version.into(),
-            is_lts: args.lts,
-        },
-        hash: args.hash.as_deref(),
-        update: args.update,
-        default: args.default,
+struct ToolOptionsFn;
+
+impl FnTool for ToolOptionsFn {
+    type Output = &'static [ToolOption];
+
+    fn invoke(&self, tool: &impl GeneralTool) -> Self::Output {
+        tool.options()
     }
-    .install()
-    .await
 }
 
-pub async fn run_list(args: ListArgs, paths: &Paths) -> anyhow::Result<()> {
-    let tool_name = args.tool.command_name();
-    for (tag, target) in general_tool::list_tags(&tool_name, &paths.tool_dir).await? {
-        print!("{}", tag);
-        if let Some(target) = target {
-            print!(" -> {}", target);
-        }
-        println!();
+struct FlavorWithOptionsFn<'a> {
+    flavor: Option<&'a str>,
+    options: &'a FlavorOptions,
+}
+
+impl FnTool for FlavorWithOptionsFn<'_> {
+    type Output = any_version_manager::Result<Option<SmolStr>>;
+
+    fn invoke(&self, tool: &impl GeneralTool) -> Self::Output {
+        tool.flavor_with_options(self.flavor, self.options)
     }
-    Ok(())
 }
 
-pub fn run_path(args: PathArgs, paths: &Paths) -> anyhow::Result<()> {
-    let tool_name = args.tool.command_name();
-    let path = general_tool::get_tag_path(&tool_name, &paths.tool_dir, &args.tag)?;
-    println!("{}", path.display());
-    Ok(())
+struct IsPrereleaseFlavorFn<'a> {
+    flavor: &'a str,
 }
 
-pub fn run_entry_path(args: EntryPathArgs, tools: &ToolSet, paths: &Paths) -> anyhow::Result<()> {
-    let tool_name = args.tool.command_name();
-    let fn_tool = RunEntryPathFn {
-        tool_name: &tool_name,
-        tools_base: &paths.tool_dir,
-        args: &args,
-    };
-    invoke_tool(tools, args.tool, &fn_tool)
+impl FnTool for IsPrereleaseFlavorFn<'_> {
+    type Output = bool;
+
+    fn invoke(&self, tool: &impl GeneralTool) -> Self::Output {
+        tool.is_prerelease_flavor(self.flavor)
+    }
+}
+
+struct SetUpTagFn<'a> {
+    tag: &'a NewTag<'a>,
+}
+
+impl AsyncFnTool for SetUpTagFn<'_> {
+    type Output = any_version_manager::Result<()>;
+
+    async fn invoke(&self, tool: &impl GeneralTool) -> Self::Output {
+        tool.set_up_tag(self.tag).await
+    }
+}
+
+/// The setup shared by every way of installing a tag, once it is in place: sets the tag up
+/// the way the tool does, with the `options` of the install, then runs the `post_install`
+/// hooks of the tool in the config.
+pub(super) async fn finish_install(
+    tools: &ToolSet,
+    tool: &ToolName,
+    tag: &SmolStr,
+    options: &TagOptions,
+    paths: &Paths,
+) -> anyhow::Result<()> {
+    let tool_name = tool.command_name();
+    let tag_dir = general_tool::get_tag_path(&tool_name, &paths.tool_dir, tag)?;
+    let info = general_tool::install_info(&tool_name, &paths.tool_dir, tag.clone()).await?;
+    let new_tag = NewTag {
+        tools_base: &paths.tool_dir,
+        tag,
+        tag_dir: &tag_dir,
+        flavor: info.as_ref().and_then(|info| info.flavor.as_deref()),
+        options,
+    };
+    async_invoke_tool(tools, tool, &SetUpTagFn { tag: &new_tag })
+        .await
+        .map_err(|err| {
+            anyhow::Error::from(err)
+                .context(format!("\"{tag}\" is installed, but setting it up failed"))
+        })?;
+
+    let hooks = tools
+        .defaults
+        .get(&tool_name)
+        .map(|defaults| defaults.post_install.clone())
+        .unwrap_or_default();
+    if hooks.is_empty() {
+        return Ok(());
+    }
+    let (bin_dirs, env_vars) = tools.tag_env(tool, &tag_dir)?;
+    let context = hooks::HookContext {
+        tool_name,
+        tag: tag.to_string(),
+        tag_dir,
+        bin_dirs,
+        env_vars,
+    };
+    hooks::run_post_install(hooks, context)
+        .await
+        .map_err(|err| {
+            err.context(format!(
+                "\"{tag}\" is installed, but a post-install hook failed"
+            ))
+        })
+}
+
+/// Prints what an install would do, for `--dry-run`.
+pub(super) fn print_install_plan(plan: &general_tool::InstallPlan, overwrites: &str) {
+    println!("Tag: {}", plan.tag);
+    println!("Path: {}", plan.tag_dir.display());
+    println!(
+        "Version: {}{}",
+        plan.version.version,
+        if plan.version.is_lts { " (LTS)" } else { "" }
+    );
+    if let Some(platform) = &plan.platform {
+        println!("Platform: {platform}");
+    }
+    if let Some(flavor) = &plan.flavor {
+        println!("Flavor: {flavor}");
+    }
+    println!("URL: {}", plan.url);
+    for (algorithm, hash) in plan.hash.iter() {
+        println!("{algorithm}: {hash}");
+    }
+    println!("Overwrites: {overwrites}");
+}
+
+pub async fn run_get_vers(mut args: GetVersArgs, tools: &ToolSet) -> anyhow::Result<()> {
+    args.selector.apply_defaults(tools, &args.tool)?;
+    let fn_tool = RunGetVersFn { args: &args };
+    async_invoke_tool(tools, &args.tool, &fn_tool).await
+}
+
+pub async fn run_get_downinfo(mut args: GetDowninfoArgs, tools: &ToolSet) -> anyhow::Result<()> {
+    args.selector.apply_defaults(tools, &args.tool)?;
+    let fn_tool = RunGetDowninfoFn { args: &args };
+    async_invoke_tool(tools, &args.tool, &fn_tool).await
+}
+
+pub async fn run_changelog(mut args: ChangelogArgs, tools: &ToolSet) -> anyhow::Result<()> {
+    if let Some(release) = args.release.take() {
+        args.selector.spec = Some(release);
+    }
+    args.selector.apply_defaults(tools, &args.tool)?;
+    let url = async_invoke_tool(tools, &args.tool, &RunChangelogFn { args: &args }).await?;
+    if !args.open {
+        println!("{url}");
+        return Ok(());
+    }
+    if !url.starts_with("https://") && !url.starts_with("http://") {
+        anyhow::bail!("Refusing to open {url}, it is not an HTTP URL");
+    }
+    // `rundll32` passes the URL to the default handler as is, unlike `cmd /C start`, which
+    // would interpret the metacharacters in it.
+    let (program, leading_args): (&str, &[&str]) = if cfg!(windows) {
+        ("rundll32", &["url.dll,FileProtocolHandler"])
+    } else if cfg!(target_os = "macos") {
+        ("open", &[])
+    } else {
+        ("xdg-open", &[])
+    };
+    any_version_manager::spawn_blocking(move || {
+        let status = std::process::Command::new(program)
+            .args(leading_args)
+            .arg(url.as_str())
+            .status()
+            .map_err(|err| anyhow::anyhow!("Failed to run `{program}` to open {url}: {err}"))?;
+        if !status.success() {
+            anyhow::bail!("`{program}` failed to open {url} with {status}");
+        }
+        Ok(())
+    })
+    .await
+}
+
+pub async fn run_ping(args: PingArgs, tools: &ToolSet, client: &HttpClient) -> anyhow::Result<()> {
+    let client = client.for_tool(&args.tool.command_name());
+    let fn_tool = RunPingFn { client: &client };
+    async_invoke_tool(tools, &args.tool, &fn_tool).await
+}
+
+pub async fn run_install_local(
+    args: InstallLocalArgs,
+    tools: &ToolSet,
+    paths: &Paths,
+) -> anyhow::Result<()> {
+    let tool_name = args.tool.command_name();
+    general_tool::LocalInstaller {
+        tool_name: &tool_name,
+        tools_base: &paths.tool_dir,
+        archive: args.archive,
+        target_tag: &args.target_tag,
+        version: args.version.map(|version| Version {
+            version: version.into(),
+            is_lts: args.lts,
+        }),
+        hash: args.hash.as_deref(),
+        update: args.update,
+        force: args.force,
+        default: args.default,
+        keep_partial: args.keep_partial,
+        cancel: any_version_manager::cancellation_token(),
+    }
+    .install()
+    .await?;
+    finish_install(
+        tools,
+        &args.tool,
+        &SmolStr::from(&args.target_tag),
+        &TagOptions::default(),
+        paths,
+    )
+    .await
+}
+
+pub async fn run_install_url(
+    args: InstallUrlArgs,
+    tools: &ToolSet,
+    client: &HttpClient,
+    paths: &Paths,
+) -> anyhow::Result<()> {
+    let tool_name = args.tool.command_name();
+    let version = Version {
+        version: args.version.unwrap_or_else(|| args.tag.clone()).into(),
+        is_lts: args.lts,
+    };
+    let hash = args
+        .sha256
+        .map(|sha256| FileHash::from_sha256(sha256.into()))
+        .unwrap_or_default();
+    with_retries(args.retries, async || {
+        let download_state = general_tool::UrlInstaller {
+            tool_name: &tool_name,
+            client,
+            tools_base: &paths.tool_dir,
+            url: &args.url,
+            target_tag: &args.tag,
+            version: version.clone(),
+            hash: hash.clone(),
+            update: args.update,
+            default: args.default,
+            keep_partial: args.keep_partial,
+            stream_extract: args.stream,
+            cancel: any_version_manager::cancellation_token(),
+        }
+        .install()
+        .await?;
+
+        drive_download_state(
+            args.tag.as_str().into(),
+            args.url.as_str().into(),
+            download_state,
+        )
+        .await
+    })
+    .await?;
+    finish_install(
+        tools,
+        &args.tool,
+        &SmolStr::from(&args.tag),
+        &TagOptions::default(),
+        paths,
+    )
+    .await
+}
+
+/// Runs an install again, up to `retries` more times, while its download breaks or doesn't
+/// match the expected hash, which a new download may fix.
+async fn with_retries<T>(
+    retries: u32,
+    mut install: impl AsyncFnMut() -> anyhow::Result<T>,
+) -> anyhow::Result<T> {
+    let mut attempt = 0;
+    loop {
+        match install().await {
+            Err(err) if attempt < retries && is_download_failure(&err) => {
+                attempt += 1;
+                log::warn!("{err:#}");
+                log::warn!("Retrying the download ({attempt}/{retries})");
+            }
+            result => return result,
+        }
+    }
+}
+
+fn is_download_failure(err: &anyhow::Error) -> bool {
+    err.chain()
+        .any(|cause| match cause.downcast_ref::<AvmError>() {
+            Some(AvmError::HttpStatus { status, .. }) => status.is_server_error(),
+            Some(err) => matches!(
+                err,
+                AvmError::Network(_)
+                    | AvmError::HashMismatch { .. }
+                    | AvmError::DownloadHashMismatch(_)
+            ),
+            None => cause.is::<reqwest::Error>(),
+        })
+}
+
+pub async fn run_list(args: ListArgs, tools: &ToolSet, paths: &Paths) -> anyhow::Result<()> {
+    let Some(tool) = args.tool else {
+        return run_list_all(args.json, paths).await;
+    };
+    let tool_name = tool.command_name();
+    let mut rows = Vec::new();
+    for (tag, target) in general_tool::list_tags(&tool_name, &paths.tool_dir).await? {
+        let info = match target {
+            None if args.verbose || args.eol => {
+                general_tool::install_info(&tool_name, &paths.tool_dir, tag.clone()).await?
+            }
+            _ => None,
+        };
+        rows.push((tag, target, info));
+    }
+    let eol = if args.eol {
+        let versions = rows
+            .iter()
+            .filter_map(|(_, _, info)| Some(info.as_ref()?.version.clone()))
+            .collect::<Vec<_>>();
+        let statuses = async_invoke_tool(
+            tools,
+            &tool,
+            &SupportStatusFn {
+                versions: &versions,
+            },
+        )
+        .await?;
+        versions
+            .into_iter()
+            .zip(statuses)
+            .filter(|(_, status)| status.as_ref().is_some_and(|status| status.is_eol))
+            .map(|(version, _)| version)
+            .collect()
+    } else {
+        FxHashSet::default()
+    };
+
+    for (tag, target, info) in rows {
+        print!("{}", tag);
+        if let Some(target) = target {
+            print!(" -> {}", target);
+        } else if let Some(info) = info {
+            if args.verbose {
+                print!(" ({})", describe_install(&info));
+            }
+            if eol.contains(&info.version) {
+                print!(" [EOL]");
+            }
+        }
+        println!();
+    }
+    Ok(())
+}
+
+/// A row of `list --all`.
+#[derive(Serialize)]
+struct ListAllRow {
+    tool: String,
+    tag: SmolStr,
+    alias_of: Option<SmolStr>,
+    version: Option<SmolStr>,
+    lts: bool,
+    /// In bytes.
+    size: Option<u64>,
+}
+
+async fn run_list_all(json: bool, paths: &Paths) -> anyhow::Result<()> {
+    let mut rows = Vec::new();
+    for tool in <ToolName as ValueEnum>::value_variants() {
+        let tool_name = tool.command_name();
+        for details in general_tool::list_tag_details(&tool_name, &paths.tool_dir).await? {
+            rows.push(ListAllRow {
+                tool: tool_name.clone(),
+                tag: details.tag,
+                alias_of: details.alias_of,
+                lts: details.info.as_ref().is_some_and(|info| info.is_lts),
+                version: details.info.map(|info| info.version),
+                size: details.size,
+            });
+        }
+    }
+
+    if json {
+        println!("{}", serde_json::to_string_pretty(&rows)?);
+        return Ok(());
+    }
+    let cells = rows
+        .iter()
+        .map(|row| {
+            [
+                row.tool.clone(),
+                match &row.alias_of {
+                    Some(target) => format!("{} -> {}", row.tag, target),
+                    None => row.tag.to_string(),
+                },
+                row.version.as_deref().unwrap_or("-").to_owned(),
+                if row.lts { "yes" } else { "-" }.to_owned(),
+                row.size
+                    .map(|size| HumanBytes(size).to_string())
+                    .unwrap_or_else(|| "-".to_owned()),
+            ]
+        })
+        .collect::<Vec<_>>();
+    let header = ["TOOL", "TAG", "VERSION", "LTS", "SIZE"].map(str::to_owned);
+    let widths: [usize; 4] = std::array::from_fn(|column| {
+        std::iter::once(&header)
+            .chain(&cells)
+            .map(|row| row[column].chars().count())
+            .max()
+            .unwrap_or_default()
+    });
+    for [tool, tag, version, lts, size] in std::iter::once(header).chain(cells) {
+        println!(
+            "{tool:<w0$}  {tag:<w1$}  {version:<w2$}  {lts:<w3$}  {size}",
+            w0 = widths[0],
+            w1 = widths[1],
+            w2 = widths[2],
+            w3 = widths[3]
+        );
+    }
+    let total: u64 = rows.iter().filter_map(|row| row.size).sum();
+    println!("Total: {}", HumanBytes(total));
+    Ok(())
+}
+
+/// One-line summary of the install info for `list --verbose`.
+fn describe_install(info: &general_tool::InstallInfo) -> String {
+    let mut parts = vec![format!(
+        "{}{}",
+        info.version,
+        if info.is_lts { " LTS" } else { "" }
+    )];
+    parts.extend(info.platform.iter().map(|platform| platform.to_string()));
+    parts.extend(info.flavor.iter().map(|flavor| flavor.to_string()));
+    if let Some(age) = info.age() {
+        parts.push(format!("installed {} ago", HumanDuration(age)));
+    }
+    parts.join(", ")
+}
+
+pub async fn run_info(args: InfoArgs, paths: &Paths) -> anyhow::Result<()> {
+    let tool_name = args.tool.command_name();
+    let tag = SmolStr::from(args.tag);
+    let path = general_tool::get_tag_path(&tool_name, &paths.tool_dir, &tag)?;
+    let target = general_tool::list_tags(&tool_name, &paths.tool_dir)
+        .await?
+        .into_iter()
+        .find(|(name, _)| *name == tag)
+        .and_then(|(_, target)| target);
+    let info = general_tool::install_info(&tool_name, &paths.tool_dir, tag.clone()).await?;
+
+    println!("Tag: {tag}");
+    if let Some(target) = target {
+        println!("Alias of: {target}");
+    }
+    println!("Path: {}", path.display());
+    let Some(info) = info else {
+        println!("No install info, the tag was not installed by avm.");
+        return Ok(());
+    };
+    println!(
+        "Version: {}{}",
+        info.version,
+        if info.is_lts { " (LTS)" } else { "" }
+    );
+    if let Some(platform) = &info.platform {
+        println!("Platform: {platform}");
+    }
+    if let Some(flavor) = &info.flavor {
+        println!("Flavor: {flavor}");
+    }
+    if let Some(url) = &info.url {
+        println!("URL: {url}");
+    }
+    for (algorithm, hash) in info.hash.iter() {
+        println!("{algorithm}: {hash}");
+    }
+    if let Some(age) = info.age() {
+        println!("Installed: {} ago", HumanDuration(age));
+    }
+    Ok(())
+}
+
+pub fn run_path(args: PathArgs, paths: &Paths) -> anyhow::Result<()> {
+    let tool_name = args.tool.command_name();
+    let path = general_tool::get_tag_path(&tool_name, &paths.tool_dir, &args.tag)?;
+    println!("{}", path.display());
+    Ok(())
+}
+
+pub fn run_entry_path(args: EntryPathArgs, tools: &ToolSet, paths: &Paths) -> anyhow::Result<()> {
+    let tool_name = args.tool.command_name();
+    let fn_tool = RunEntryPathFn {
+        tool_name: &tool_name,
+        tools_base: &paths.tool_dir,
+        args: &args,
+    };
+    invoke_tool(tools, &args.tool, &fn_tool)
 }
 
 pub async fn run_run(
-    args: RunArgs,
+    mut args: RunArgs,
     tools: &ToolSet,
     client: &HttpClient,
     paths: &Paths,
 ) -> anyhow::Result<()> {
     let tool_name = args.tool.command_name();
+    // Without a tag or selector flags, the version pinned by `AVM_<TOOL>_VERSION` or the project
+    // is run, or else the default tag.
+    if args.tag.is_none() && args.selector.is_empty() {
+        apply_pinned_version(&mut args, &tool_name, paths)?;
+    }
+    if !args.selector.is_empty() {
+        args.selector.apply_defaults(tools, &args.tool)?;
+    }
     let fn_tool = RunRunFn {
         tool_name: &tool_name,
         client,
-        tools_base: &paths.tool_dir,
+        tools,
+        paths,
         args: &args,
     };
-    async_invoke_tool(tools, args.tool, &fn_tool).await
+    async_invoke_tool(tools, &args.tool, &fn_tool).await
+}
+
+/// A version pinned for a tool.
+enum PinnedVersion {
+    /// An installed tag.
+    Tag(SmolStr),
+    /// A version spec.
+    Spec(SmolStr),
+}
+
+/// The environment variable overriding the version of a tool, such as `AVM_GO_VERSION`.
+fn version_env(tool_name: &str) -> String {
+    format!(
+        "AVM_{}_VERSION",
+        tool_name.to_ascii_uppercase().replace('-', "_")
+    )
+}
+
+/// The version pinned for the tool by its `AVM_<TOOL>_VERSION` variable or else by the nearest
+/// `.tool-versions`, as a tag if it is installed and as a version spec otherwise.
+fn pinned_version(tool_name: &str, paths: &Paths) -> anyhow::Result<Option<PinnedVersion>> {
+    let env = version_env(tool_name);
+    let version = match std::env::var(&env) {
+        Ok(version) if !version.trim().is_empty() => {
+            log::debug!("Using {tool_name} {version} from {env}");
+            SmolStr::from(version.trim())
+        }
+        _ => {
+            let Some(pinned) = project::find_pinned_version(&std::env::current_dir()?, tool_name)?
+            else {
+                return Ok(None);
+            };
+            log::debug!(
+                "Using {tool_name} {} from '{}'",
+                pinned.version,
+                pinned.file.display()
+            );
+            pinned.version
+        }
+    };
+    let is_tag = any_version_manager::tag::normalize(&version)
+        .is_ok_and(|tag| paths.tool_dir.join(tool_name).join(&*tag).exists());
+    Ok(Some(if is_tag {
+        PinnedVersion::Tag(version)
+    } else {
+        PinnedVersion::Spec(version)
+    }))
+}
+
+fn apply_pinned_version(args: &mut RunArgs, tool_name: &str, paths: &Paths) -> anyhow::Result<()> {
+    match pinned_version(tool_name, paths)? {
+        Some(PinnedVersion::Tag(tag)) => args.tag = Some(tag.into()),
+        Some(PinnedVersion::Spec(spec)) => args.selector.spec = Some(spec.into()),
+        None => {}
+    }
+    Ok(())
+}
+
+/// The installed tag of the version pinned for the tool, without installing it. `None` if no
+/// version is pinned or no installed tag matches it.
+pub async fn find_pinned_tag(
+    tools: &ToolSet,
+    tool: &ToolName,
+    paths: &Paths,
+) -> anyhow::Result<Option<SmolStr>> {
+    let tool_name = tool.command_name();
+    let spec = match pinned_version(&tool_name, paths)? {
+        Some(PinnedVersion::Tag(tag)) => return Ok(Some(tag)),
+        Some(PinnedVersion::Spec(spec)) => spec,
+        None => return Ok(None),
+    };
+    let mut selector = SelectorArgs {
+        spec: Some(spec.into()),
+        ..Default::default()
+    };
+    selector.apply_defaults(tools, tool)?;
+    let fn_tool = FindLocalTagFn {
+        tool_name: &tool_name,
+        tools_base: &paths.tool_dir,
+        selector: &selector,
+    };
+    async_invoke_tool(tools, tool, &fn_tool).await
+}
+
+struct FindLocalTagFn<'a> {
+    tool_name: &'a str,
+    tools_base: &'a Path,
+    selector: &'a SelectorArgs,
+}
+
+impl AsyncFnTool for FindLocalTagFn<'_> {
+    type Output = anyhow::Result<Option<SmolStr>>;
+
+    async fn invoke(&self, tool: &impl GeneralTool) -> Self::Output {
+        let (platform, flavor, version_filter) = resolve_selector_filters(tool, self.selector)?;
+        Ok(general_tool::find_matching_local_tag(
+            self.tool_name,
+            tool,
+            self.tools_base,
+            platform,
+            flavor,
+            version_filter,
+        )
+        .await?)
+    }
 }
 
 pub async fn run_alias(args: AliasArgs, paths: &Paths) -> anyhow::Result<()> {
-    let tool_name = args.tool.command_name();
-    general_tool::create_alias_tag(
+    let (tool, src_tag, alias_tag) = match args.command {
+        Some(command) => return run_alias_command(command, paths).await,
+        // Required by Clap without a subcommand.
+        None => (
+            args.tool.unwrap(),
+            args.src_tag.unwrap(),
+            args.alias_tag.unwrap(),
+        ),
+    };
+    let tool_name = tool.command_name();
+    Ok(general_tool::create_alias_tag(
         &tool_name,
         &paths.tool_dir,
-        args.src_tag.into(),
-        args.alias_tag.into(),
+        src_tag.into(),
+        alias_tag.into(),
     )
-    .await
+    .await?)
+}
+
+async fn run_alias_command(command: AliasCommand, paths: &Paths) -> anyhow::Result<()> {
+    match command {
+        AliasCommand::List { tool } => {
+            let tool_name = tool.command_name();
+            for entry in general_tool::list_aliases(&tool_name, &paths.tool_dir).await? {
+                print!("{} -> {}", entry.alias, entry.target);
+                if !entry.target_exists {
+                    print!(" (missing)");
+                }
+                println!();
+            }
+            Ok(())
+        }
+        AliasCommand::Delete { tool, aliases } => {
+            let tool_name = tool.command_name();
+            let aliases = aliases.into_iter().map(SmolStr::from).collect();
+            Ok(general_tool::delete_aliases(&tool_name, &paths.tool_dir, aliases).await?)
+        }
+        AliasCommand::Move {
+            tool,
+            alias_tag,
+            new_target,
+        } => {
+            let tool_name = tool.command_name();
+            let new_target = SmolStr::from(new_target);
+            let old_target = general_tool::move_alias(
+                &tool_name,
+                &paths.tool_dir,
+                alias_tag.clone().into(),
+                new_target.clone(),
+            )
+            .await?;
+            println!("{alias_tag}: {old_target} -> {new_target}");
+            Ok(())
+        }
+    }
+}
+
+pub async fn run_repair_alias(args: RepairAliasArgs, paths: &Paths) -> anyhow::Result<()> {
+    let tool_name = args.tool.command_name();
+    let tags = args.tags.into_iter().map(SmolStr::from).collect();
+    for repaired in general_tool::repair_aliases(&tool_name, &paths.tool_dir, tags).await? {
+        println!(
+            "{}: {} -> {}",
+            repair_action(&repaired.repair),
+            repaired.alias,
+            repaired.target
+        );
+    }
+    Ok(())
+}
+
+pub(super) fn repair_action(repair: &general_tool::AliasRepair) -> &'static str {
+    match repair {
+        general_tool::AliasRepair::Unchanged => "unchanged",
+        general_tool::AliasRepair::Relinked => "relinked",
+        general_tool::AliasRepair::Copied => "copied",
+        general_tool::AliasRepair::Dangling => "dangling",
+    }
 }
 
 pub async fn run_copy(args: CopyArgs, paths: &Paths) -> anyhow::Result<()> {
     let tool_name = args.tool.command_name();
-    general_tool::copy_tag(
+    let target_tag = args
+        .target_tag
+        .or(args.to_tag)
+        .unwrap_or_else(|| args.src_tag.clone());
+    let src_tools_base = match &args.from_data_dir {
+        Some(data_dir) => data_dir.join(any_version_manager::avm::TOOLS_DIR),
+        None => paths.tool_dir.clone(),
+    };
+    Ok(general_tool::copy_tag_from(
         &tool_name,
+        &src_tools_base,
         &paths.tool_dir,
         args.src_tag.into(),
-        args.target_tag.into(),
+        target_tag.into(),
     )
-    .await
+    .await?)
+}
+
+pub async fn run_dedup(args: DedupArgs, paths: &Paths) -> anyhow::Result<()> {
+    let tool_name = args.tool.command_name();
+    let stats = general_tool::dedup_tags(&tool_name, &paths.tool_dir, args.dry_run).await?;
+    let action = if args.dry_run { "would link" } else { "linked" };
+    println!(
+        "{action}: {} file(s), saving {}",
+        stats.linked_files,
+        HumanBytes(stats.saved_bytes)
+    );
+    Ok(())
+}
+
+pub async fn run_export_tag(args: ExportTagArgs, paths: &Paths) -> anyhow::Result<()> {
+    let tool_name = args.tool.command_name();
+    general_tool::export_tag(
+        &tool_name,
+        &paths.tool_dir,
+        args.tag.into(),
+        args.output.clone(),
+        any_version_manager::cancellation_token(),
+    )
+    .await?;
+    log::info!("Exported to {}", args.output.display());
+    Ok(())
+}
+
+pub async fn run_bundle(
+    args: BundleArgs,
+    tools: &ToolSet,
+    client: &HttpClient,
+    paths: &Paths,
+) -> anyhow::Result<()> {
+    let mut selectors = args.selectors();
+    for selector in &mut selectors {
+        selector.apply_defaults(tools, &args.tool)?;
+    }
+    let tool_name = args.tool.command_name();
+    let fn_tool = BundleFn {
+        tool_name: &tool_name,
+        client,
+        tools_base: &paths.tool_dir,
+        pinned_hashes: tools.pinned_hashes.as_ref(),
+        selectors: &selectors,
+        output: &args.output,
+    };
+    async_invoke_tool(tools, &args.tool, &fn_tool).await?;
+    log::info!("Bundled to {}", args.output.display());
+    Ok(())
+}
+
+pub async fn run_rename(args: RenameArgs, paths: &Paths) -> anyhow::Result<()> {
+    let tool_name = args.tool.command_name();
+    let new_tag = SmolStr::from(args.new_tag);
+    for alias in general_tool::rename_tag(
+        &tool_name,
+        &paths.tool_dir,
+        args.tag.into(),
+        new_tag.clone(),
+    )
+    .await?
+    {
+        println!("{alias} -> {new_tag}");
+    }
+    Ok(())
 }
 
 pub async fn run_remove(args: RemoveArgs, paths: &Paths) -> anyhow::Result<()> {
     let tool_name = args.tool.command_name();
     let tags_to_remove = args.tags.into_iter().map(SmolStr::from).collect::<Vec<_>>();
-    general_tool::remove_tag(
+    Ok(general_tool::remove_tag(
         &tool_name,
         &paths.tool_dir,
         tags_to_remove,
         args.allow_dangling,
     )
-    .await
+    .await?)
 }
 
-pub async fn run_clean(args: CleanArgs, paths: &Paths) -> anyhow::Result<()> {
+pub async fn run_prune(args: PruneArgs, tools: &ToolSet, paths: &Paths) -> anyhow::Result<()> {
     let tool_name = args.tool.command_name();
-    general_tool::clean(&tool_name, &paths.tool_dir).await
+    let version_prefix = match args.major {
+        Some(major) => Some(VersionPrefix {
+            major,
+            minor: None,
+            patch: None,
+        }),
+        None => args
+            .version_prefix
+            .as_deref()
+            .map(VersionPrefix::parse)
+            .transpose()?,
+    };
+    let filter = general_tool::PruneFilter {
+        platform: option_to_smol_str(&args.platform),
+        flavor: option_to_smol_str(&args.flavor),
+        version_filter: VersionFilter {
+            include_prerelease: true,
+            version_prefix,
+            ..Default::default()
+        },
+        keep_latest: args.all_but_latest,
+    };
+    let fn_tool = PruneCandidatesFn {
+        tool_name: &tool_name,
+        tools_base: &paths.tool_dir,
+        filter: &filter,
+    };
+    let mut candidates = async_invoke_tool(tools, &args.tool, &fn_tool).await?;
+    if let Some(period) = args.unused_for {
+        let stats = UsageStats::load(&paths.state_dir).await?;
+        candidates.retain(|installed| stats.is_unused_for(&tool_name, installed, period));
+    }
+    if candidates.is_empty() {
+        log::info!("No tags to prune");
+        return Ok(());
+    }
+
+    for installed in &candidates {
+        eprintln!("{} ({})", installed.tag, installed.info.version);
+    }
+    if !args.yes && !confirm(&format!("Remove {} tag(s)?", candidates.len()))? {
+        anyhow::bail!("Aborted");
+    }
+
+    let tags = candidates
+        .into_iter()
+        .map(|installed| installed.tag)
+        .collect::<Vec<_>>();
+    general_tool::remove_tag(&tool_name, &paths.tool_dir, tags.clone(), false).await?;
+    for tag in tags {
+        println!("removed: {tag}");
+    }
+    Ok(())
+}
+
+/// Parses a period like `90d`, `12w` or `36h`, in days without a unit.
+fn parse_period(value: &str) -> Result<Duration, String> {
+    let (count, unit_secs) = match value.char_indices().last() {
+        Some((i, 'h')) => (&value[..i], 60 * 60),
+        Some((i, 'd')) => (&value[..i], 24 * 60 * 60),
+        Some((i, 'w')) => (&value[..i], 7 * 24 * 60 * 60),
+        _ => (value, 24 * 60 * 60),
+    };
+    let count = count.parse::<u64>().map_err(|_| {
+        format!("Invalid period \"{value}\", expected a number with `h`, `d` or `w`")
+    })?;
+    Ok(Duration::from_secs(count * unit_secs))
+}
+
+/// Asks a yes/no question on the terminal, defaulting to no.
+fn confirm(question: &str) -> anyhow::Result<bool> {
+    let answer = ask(&format!("{question} [y/N]"))?;
+    Ok(matches!(answer.as_str(), "y" | "Y" | "yes"))
+}
+
+/// Asks a question on the terminal, returning the trimmed answer.
+fn ask(question: &str) -> anyhow::Result<String> {
+    if is_non_interactive() {
+        return Err(ConfirmationRequired("Running in non-interactive mode").into());
+    }
+    if !std::io::stdin().is_terminal() {
+        return Err(ConfirmationRequired("Not running in a terminal").into());
+    }
+    eprint!("{question} ");
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    Ok(answer.trim().to_owned())
+}
+
+struct PruneCandidatesFn<'a> {
+    tool_name: &'a str,
+    tools_base: &'a Path,
+    filter: &'a general_tool::PruneFilter,
+}
+
+impl AsyncFnTool for PruneCandidatesFn<'_> {
+    type Output = anyhow::Result<Vec<general_tool::InstalledTag>>;
+
+    async fn invoke(&self, tool: &impl GeneralTool) -> Self::Output {
+        Ok(
+            general_tool::prune_candidates(self.tool_name, tool, self.tools_base, self.filter)
+                .await?,
+        )
+    }
+}
+
+pub async fn run_clean(args: CleanArgs, paths: &Paths) -> anyhow::Result<()> {
+    if args.cache {
+        let size =
+            any_version_manager::download_cache::clear(&paths.cache_dir, !args.dry_run).await?;
+        let action = if args.dry_run {
+            "would remove"
+        } else {
+            "removed"
+        };
+        println!(
+            "{action}: {} ({})",
+            paths
+                .cache_dir
+                .join(any_version_manager::download_cache::DIR)
+                .display(),
+            HumanBytes(size)
+        );
+    }
+    let Some(tool) = &args.tool else {
+        return Ok(());
+    };
+    let tool_name = tool.command_name();
+    if args.dangling_aliases {
+        return run_clean_dangling_aliases(&tool_name, &args, paths).await;
+    }
+    let report = general_tool::clean(
+        &tool_name,
+        &paths.tool_dir,
+        general_tool::CleanOptions {
+            dry_run: args.dry_run,
+            older_than: args
+                .older_than
+                .map(|days| Duration::from_secs(days * 24 * 60 * 60)),
+        },
+    )
+    .await?;
+
+    for tmp_dir in &report.tmp_dirs {
+        let action = if tmp_dir.removed {
+            "removed"
+        } else if tmp_dir.expired && args.dry_run {
+            "would remove"
+        } else {
+            "kept"
+        };
+        let age = match tmp_dir.age {
+            Some(age) => format!("{} old", HumanDuration(age)),
+            None => "unknown age".to_owned(),
+        };
+        println!(
+            "{action}: {} ({age}, {})",
+            tmp_dir.path.display(),
+            HumanBytes(tmp_dir.size)
+        );
+    }
+    for alias in &report.dangling_aliases {
+        let action = if args.dry_run {
+            "would remove"
+        } else {
+            "removed"
+        };
+        println!("{action} dangling alias: {alias}");
+    }
+    Ok(())
+}
+
+/// Deletes or re-points the aliases whose targets no longer exist, asking for each of them
+/// unless `--retarget` or `--yes` is given.
+async fn run_clean_dangling_aliases(
+    tool_name: &str,
+    args: &CleanArgs,
+    paths: &Paths,
+) -> anyhow::Result<()> {
+    let dangling = general_tool::list_aliases(tool_name, &paths.tool_dir)
+        .await?
+        .into_iter()
+        .filter(|entry| !entry.target_exists);
+    for entry in dangling {
+        if args.dry_run {
+            println!("dangling alias: {} -> {}", entry.alias, entry.target);
+            continue;
+        }
+        let new_target = match &args.retarget {
+            Some(tag) => Some(SmolStr::from(tag)),
+            None if args.yes => None,
+            None => loop {
+                let answer = ask(&format!(
+                    "Alias \"{}\" points to missing tag \"{}\". [d]elete, [r]e-point or [k]eep?",
+                    entry.alias, entry.target
+                ))?;
+                match answer.as_str() {
+                    "d" | "delete" => break None,
+                    "r" | "re-point" => break Some(SmolStr::from(ask("New target tag:")?)),
+                    "k" | "keep" | "" => break Some(entry.target.clone()),
+                    _ => {}
+                }
+            },
+        };
+        match new_target {
+            Some(target) if target == entry.target => {
+                println!("kept dangling alias: {}", entry.alias);
+            }
+            Some(target) => {
+                general_tool::move_alias(
+                    tool_name,
+                    &paths.tool_dir,
+                    entry.alias.clone(),
+                    target.clone(),
+                )
+                .await?;
+                println!("re-pointed dangling alias: {} -> {target}", entry.alias);
+            }
+            None => {
+                general_tool::delete_aliases(tool_name, &paths.tool_dir, vec![entry.alias.clone()])
+                    .await?;
+                println!("removed dangling alias: {}", entry.alias);
+            }
+        }
+    }
+    Ok(())
 }
 
 pub fn to_version_filter(
     version: Option<&str>,
     version_prefix: Option<&str>,
     lts: bool,
-    allow_prerelease: bool,
+    include_prerelease: bool,
 ) -> anyhow::Result<VersionFilter> {
     Ok(VersionFilter {
         exact_version: version.map(SmolStr::from),
         version_prefix: version_prefix.map(VersionPrefix::parse).transpose()?,
         lts_only: lts,
-        allow_prerelease,
+        include_prerelease,
+        ..Default::default()
     })
 }
 
-async fn drive_download_state(
+pub(super) async fn drive_download_state(
     target_tag: SmolStr,
     download_url: SmolStr,
-    mut download_state: any_version_manager::io::DownloadExtractState,
+    download_state: any_version_manager::io::DownloadExtractState,
 ) -> anyhow::Result<()> {
     log::info!("Will download from {download_url}");
     log::info!("\"{target_tag}\" will be installed");
-    let mut prev_name: Option<SmolStr> = None;
-    let mut pb: Option<ProgressBar> = None;
+    if is_progress_json() {
+        return report_json_progress(target_tag, download_url, download_state, EventSink::Stdout)
+            .await;
+    }
+    let mut progress = StepProgress::new();
+    download_state
+        .run(|status| match status {
+            any_version_manager::Status::InProgress {
+                name,
+                progress_ratio,
+            } => progress.update(name, progress_ratio),
+            any_version_manager::Status::Stopped => {
+                progress.finish();
+                Ok(())
+            }
+        })
+        .await?;
+    Ok(())
+}
 
-    #[allow(clippy::while_let_loop)]
-    loop {
-        match download_state.status() {
+/// Runs the download, reporting its steps and progress as JSON events to `sink`.
+pub(super) async fn report_json_progress(
+    target_tag: SmolStr,
+    download_url: SmolStr,
+    download_state: any_version_manager::io::DownloadExtractState,
+    sink: EventSink,
+) -> anyhow::Result<()> {
+    let mut progress = JsonProgress::new(target_tag, sink);
+    progress.emit(
+        "download-started",
+        serde_json::json!({ "url": download_url.as_str() }),
+    );
+    let result = download_state
+        .run(|status| {
+            progress.update(status);
+            Ok(())
+        })
+        .await;
+    if let Err(err) = &result {
+        progress.emit("error", serde_json::json!({ "message": err.to_string() }));
+    }
+    Ok(result?)
+}
+
+/// Where the JSON events of the progress go, one per line.
+#[derive(Clone)]
+pub(super) enum EventSink {
+    Stdout,
+    /// The response of a request to `avm serve`.
+    Channel(tokio::sync::mpsc::UnboundedSender<String>),
+}
+
+impl EventSink {
+    pub(super) fn send(&self, event: serde_json::Value) {
+        match self {
+            EventSink::Stdout => println!("{event}"),
+            // The receiver is gone once the client disconnects, the install goes on.
+            EventSink::Channel(sender) => {
+                let _ = sender.send(event.to_string());
+            }
+        }
+    }
+}
+
+/// The steps and the download progress as JSON events, for `--progress json` and `avm serve`.
+struct JsonProgress {
+    tag: SmolStr,
+    sink: EventSink,
+    step: Option<SmolStr>,
+    /// The download progress not printed yet.
+    pending: Option<(u64, u64)>,
+    last_printed: Option<Instant>,
+}
+
+impl JsonProgress {
+    const INTERVAL: Duration = Duration::from_millis(500);
+
+    fn new(tag: SmolStr, sink: EventSink) -> Self {
+        Self {
+            tag,
+            sink,
+            step: None,
+            pending: None,
+            last_printed: None,
+        }
+    }
+
+    /// Sends an event with the tag and the fields of the `fields` object.
+    fn emit(&self, event: &str, fields: serde_json::Value) {
+        let mut line = serde_json::json!({ "event": event, "tag": self.tag.as_str() });
+        if let (Some(line), serde_json::Value::Object(fields)) = (line.as_object_mut(), fields) {
+            line.extend(fields);
+        }
+        self.sink.send(line);
+    }
+
+    fn flush_progress(&mut self) {
+        if let Some((bytes, total)) = self.pending.take() {
+            self.emit(
+                "progress",
+                serde_json::json!({ "bytes": bytes, "total": total }),
+            );
+            self.last_printed = Some(Instant::now());
+        }
+    }
+
+    fn update(&mut self, status: any_version_manager::Status) {
+        let name = match status {
             any_version_manager::Status::InProgress {
                 name,
                 progress_ratio,
             } => {
-                if prev_name.as_ref() != Some(&name) {
-                    if let Some(pb) = pb.take() {
-                        pb.finish_with_message("Completed.");
+                if progress_ratio.is_some() {
+                    self.pending = progress_ratio;
+                    if self
+                        .last_printed
+                        .is_none_or(|last| last.elapsed() >= Self::INTERVAL)
+                    {
+                        self.flush_progress();
                     }
-
-                    log::info!("{name} ...");
-                    prev_name = Some(name);
                 }
+                Some(name)
+            }
+            any_version_manager::Status::Stopped => None,
+        };
+        if self.step == name {
+            return;
+        }
+        // The last progress of a step is printed before the next one starts.
+        self.flush_progress();
+        match &name {
+            // Announced by `download-started`.
+            Some(name) if name == "Downloading" => {}
+            Some(name) => self.emit(&name.to_lowercase(), serde_json::json!({})),
+            None => self.emit("done", serde_json::json!({})),
+        }
+        self.step = name;
+    }
+}
 
-                if let Some(progress_ratio) = progress_ratio {
-                    if let Some(pb) = &mut pb {
-                        pb.set_position(progress_ratio.0);
-                    } else {
-                        let new_pb = ProgressBar::new(progress_ratio.1);
-                        new_pb.set_style(ProgressStyle::default_bar().template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} ({eta})")?.progress_chars("#>-"));
-                        new_pb.set_position(progress_ratio.0);
-                        pb = Some(new_pb);
-                    }
-                }
+/// Renders the progress of each step, as a bar on a terminal or as periodic log lines otherwise.
+struct StepProgress {
+    interactive: bool,
+    name: Option<SmolStr>,
+    bar: Option<ProgressBar>,
+    last_logged: Instant,
+}
+
+impl StepProgress {
+    const LOG_INTERVAL: Duration = Duration::from_secs(5);
+
+    fn new() -> Self {
+        Self {
+            interactive: std::io::stderr().is_terminal() && !is_non_interactive(),
+            name: None,
+            bar: None,
+            last_logged: Instant::now(),
+        }
+    }
+
+    fn update(&mut self, name: SmolStr, progress_ratio: Option<(u64, u64)>) -> anyhow::Result<()> {
+        if self.name.as_ref() != Some(&name) {
+            self.finish();
+            log::info!("{name} ...");
+            self.last_logged = Instant::now();
+            if self.interactive {
+                self.bar = Some(Self::create_bar(progress_ratio.map(|(_, total)| total))?);
             }
-            any_version_manager::Status::Stopped => {
-                break;
+            self.name = Some(name);
+        }
+
+        let position = progress_ratio.map_or(0, |(position, _)| position);
+        if let Some(bar) = &self.bar {
+            bar.set_position(position);
+        } else if let Some((position, total)) = progress_ratio {
+            if self.last_logged.elapsed() >= Self::LOG_INTERVAL {
+                self.last_logged = Instant::now();
+                log::info!(
+                    "{}: {}% ({} / {})",
+                    self.name.as_deref().unwrap_or_default(),
+                    (position * 100).checked_div(total).unwrap_or(100),
+                    HumanBytes(position),
+                    HumanBytes(total)
+                );
             }
         }
+        Ok(())
+    }
 
-        download_state = download_state.advance().await?;
+    fn create_bar(total: Option<u64>) -> anyhow::Result<ProgressBar> {
+        let bar = match total {
+            Some(total) => {
+                let bar = ProgressBar::new(total);
+                bar.set_style(ProgressStyle::default_bar().template("{spinner:.green} [{elapsed_precise}] [{bar:40.cyan/blue}] {bytes}/{total_bytes} {bytes_per_sec} ({eta})")?.progress_chars("#>-"));
+                bar
+            }
+            None => {
+                let bar = ProgressBar::new_spinner();
+                bar.set_style(
+                    ProgressStyle::default_spinner()
+                        .template("{spinner:.green} [{elapsed_precise}]")?,
+                );
+                bar
+            }
+        };
+        bar.enable_steady_tick(Duration::from_millis(100));
+        Ok(bar)
     }
 
-    Ok(())
+    fn finish(&mut self) {
+        if let Some(bar) = self.bar.take() {
+            bar.finish_with_message("Completed.");
+        }
+    }
 }
 
 pub fn option_to_smol_str(value: &Option<String>) -> Option<SmolStr> {
@@ -1,8 +1,12 @@
 use clap::Args;
 
 use any_version_manager::platform::{cpu, os};
+use any_version_manager::tool::general_tool::get_vers;
+use any_version_manager::tool::{GeneralTool, VersionSpec};
 
-use crate::avm_cli::general_tool::{ToolName, ToolSet};
+use crate::avm_cli::general_tool::{
+    async_invoke_tool, resolve_platform_flavor, AsyncFnTool, ToolName, ToolSet,
+};
 
 #[derive(Debug, Clone, Args)]
 pub struct ToolGuideArgs {
@@ -12,11 +16,87 @@ pub struct ToolGuideArgs {
 
 pub fn run_tool_guide(args: ToolGuideArgs, tools: &ToolSet) {
     match args.tool {
-        Some(tool) => print_tool_detail(tool, tools),
+        Some(tool) => print_tool_detail(&tool, tools),
         None => print_tool_list(tools),
     }
 }
 
+#[derive(Debug, Clone, Args)]
+pub struct SearchArgs {
+    #[arg(help = "Text to find in the tool names and descriptions, case-insensitively.")]
+    pub query: String,
+    #[arg(
+        help = "Also look up the newest release of each matching tool with this version spec, such as `1.22`, `>=1.21 <1.23` or an exact version."
+    )]
+    pub version: Option<String>,
+}
+
+pub async fn run_search(args: SearchArgs, tools: &ToolSet) -> anyhow::Result<()> {
+    let query = args.query.to_lowercase();
+    let matches = tools
+        .all_infos()
+        .into_iter()
+        .filter(|(name, info)| {
+            name.to_lowercase().contains(&query) || info.about.to_lowercase().contains(&query)
+        })
+        .collect::<Vec<_>>();
+    if matches.is_empty() {
+        anyhow::bail!(
+            "No tool matches \"{}\", run `avm tool` to list them",
+            args.query
+        );
+    }
+
+    for (name, info) in matches {
+        let Some(version) = &args.version else {
+            println!("- {}: {}", name, info.about);
+            continue;
+        };
+        let tool = <ToolName as clap::ValueEnum>::from_str(&name, false)
+            .map_err(|err| anyhow::anyhow!(err))?;
+        let newest_release = NewestReleaseFn {
+            version,
+            flavor: tools.default_flavor(&tool).map(ToString::to_string),
+        };
+        let release = match async_invoke_tool(tools, &tool, &newest_release).await {
+            Ok(Some(release)) => format!("newest {version} release is {release}"),
+            Ok(None) => format!("no release matching {version}"),
+            Err(err) => {
+                log::warn!("Failed to look up the releases of {name}: {err:#}");
+                "releases unavailable".to_owned()
+            }
+        };
+        println!("- {}: {} ({})", name, info.about, release);
+    }
+    Ok(())
+}
+
+/// Finds the newest release matching a version spec on the default platform and flavor.
+/// Prereleases only match their exact version.
+struct NewestReleaseFn<'a> {
+    version: &'a str,
+    flavor: Option<String>,
+}
+
+impl AsyncFnTool for NewestReleaseFn<'_> {
+    type Output = anyhow::Result<Option<String>>;
+
+    async fn invoke(&self, tool: &impl GeneralTool) -> Self::Output {
+        let (platform, flavor) = resolve_platform_flavor(tool, &None, &self.flavor);
+        let spec = VersionSpec::parse(self.version)?;
+        let include_prerelease = matches!(spec, VersionSpec::Exact(_));
+        let version_filter = spec.into_filter(include_prerelease);
+        let versions = get_vers(tool, platform, flavor, version_filter).await?;
+        Ok(versions.last().map(|version| {
+            format!(
+                "{}{}",
+                version.version,
+                if version.is_lts { " [LTS]" } else { "" }
+            )
+        }))
+    }
+}
+
 fn print_tool_list(tools: &ToolSet) {
     println!("Supported tools:\n");
     for (name, info) in tools.all_infos() {
@@ -30,11 +110,16 @@ fn print_tool_list(tools: &ToolSet) {
     println!("Example: `avm install liberica --platform x64-linux --flavor jdk`");
 }
 
-fn print_tool_detail(tool: ToolName, tools: &ToolSet) {
+fn print_tool_detail(tool: &ToolName, tools: &ToolSet) {
     let info = tools.tool_info(tool);
     let name = tool.command_name();
     println!("Tool: {}", name);
     println!("Description: {}", info.about);
+    if let Ok(exe_names) = tools.exe_names(tool) {
+        if exe_names.len() > 1 {
+            println!("Executables: {}", exe_names.join(", "));
+        }
+    }
     println!();
     println!("Install examples:");
     println!("- avm install {}", name);
@@ -49,7 +134,7 @@ fn print_tool_detail(tool: ToolName, tools: &ToolSet) {
     if let Some(default_platform) = &info.default_platform {
         println!("Default platform: {}", default_platform);
     }
-    if let Some(default_flavor) = &info.default_flavor {
+    if let Some(default_flavor) = tools.default_flavor(tool) {
         println!("Default flavor: {}", default_flavor);
     }
 
@@ -69,6 +154,20 @@ fn print_tool_detail(tool: ToolName, tools: &ToolSet) {
             println!("- {}: {}", flavor, detail);
         }
     }
+
+    if let Some(matrix) = tools.support_matrix(tool) {
+        let all_flavors = info.all_flavors.as_ref().map_or(0, Vec::len);
+        if matrix
+            .iter()
+            .any(|(_, flavors)| flavors.len() != all_flavors)
+        {
+            println!();
+            println!("Flavors by platform:");
+            for (platform, flavors) in matrix {
+                println!("- {}: {}", platform, flavors.join(", "));
+            }
+        }
+    }
 }
 
 fn describe_platform(platform: &str) -> String {
@@ -0,0 +1,66 @@
+//! Post-install hooks, the shell commands of the `post_install` list of a tool in the config,
+//! run once a tag is installed, whether by `avm install`, `upgrade`, `import`, `sync`, `run`,
+//! `install-url`, `install-local` or `avm serve`.
+
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// What the hooks of an installed tag run with.
+pub struct HookContext {
+    pub tool_name: String,
+    pub tag: String,
+    pub tag_dir: PathBuf,
+    /// The directories of the executables of the tag, put first on `PATH`.
+    pub bin_dirs: Vec<PathBuf>,
+    /// The variables `avm run` sets for the tag, such as `JAVA_HOME`.
+    pub env_vars: Vec<(OsString, OsString)>,
+}
+
+/// Runs the hooks in order, stopping at the first one that fails. Besides `PATH` and the
+/// variables of the tool, the hooks get `AVM_TOOL`, `AVM_TAG` and `AVM_TAG_DIR`.
+pub async fn run_post_install(hooks: Vec<String>, context: HookContext) -> anyhow::Result<()> {
+    any_version_manager::spawn_blocking(move || {
+        let paths = std::env::var_os("PATH").unwrap_or_default();
+        let path = std::env::join_paths(
+            context
+                .bin_dirs
+                .iter()
+                .cloned()
+                .chain(std::env::split_paths(&paths)),
+        )?;
+        for hook in &hooks {
+            log::info!(
+                "Running the post-install hook of \"{}\": {hook}",
+                context.tag
+            );
+            let status = shell(hook)
+                .envs(context.env_vars.iter().cloned())
+                .env("PATH", &path)
+                .env("AVM_TOOL", &context.tool_name)
+                .env("AVM_TAG", &context.tag)
+                .env("AVM_TAG_DIR", &context.tag_dir)
+                // Keeps stdout to avm's own output, such as the events of `--progress json`.
+                .stdout(std::io::stderr())
+                .status()
+                .map_err(|err| anyhow::anyhow!("Failed to run the hook `{hook}`: {err}"))?;
+            if !status.success() {
+                anyhow::bail!("The hook `{hook}` failed with {status}");
+            }
+        }
+        Ok(())
+    })
+    .await
+}
+
+fn shell(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C").arg(command);
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(command);
+        shell
+    }
+}
@@ -0,0 +1,221 @@
+//! `avm init <shell>`, the snippet a shell evaluates at startup to put the shims on PATH, complete
+//! the avm commands and, with `--auto-switch`, put the tags pinned by `.tool-versions` on PATH
+//! when changing directories.
+
+use any_version_manager::avm::SHIMS_DIR;
+use anyhow::Context;
+use clap::{Args, CommandFactory, ValueEnum};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+use super::general_tool::{self, ToolName, ToolSet};
+use super::{Cli, Paths};
+
+/// The directories put on PATH by the last switch, to take them off on the next one.
+const SWITCH_PATH_ENV: &str = "AVM_SWITCH_PATH";
+/// The comma-separated variables set by the last switch.
+const SWITCH_VARS_ENV: &str = "AVM_SWITCH_VARS";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+    Powershell,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct InitArgs {
+    #[arg(value_enum, help = "Shell to print the snippet for.")]
+    pub shell: Shell,
+
+    #[arg(
+        long,
+        help = "When changing directories, put the tags pinned by `.tool-versions` on PATH and set the variables `avm run` sets for them."
+    )]
+    pub auto_switch: bool,
+
+    // Run by the hook of `--auto-switch`.
+    #[arg(long, hide = true)]
+    pub hook_env: bool,
+}
+
+pub async fn run(args: InitArgs, tools: &ToolSet, paths: &Paths) -> anyhow::Result<()> {
+    let shell = args.shell;
+    if args.hook_env {
+        print!("{}", hook_env(shell, tools, paths).await?);
+        return Ok(());
+    }
+
+    let mut snippet = shell.prepend_path(&paths.data_dir.join(SHIMS_DIR));
+    let mut completion = Vec::new();
+    clap_complete::generate(
+        shell.completion_shell(),
+        &mut Cli::command(),
+        "avm",
+        &mut completion,
+    );
+    snippet.push_str(&String::from_utf8(completion)?);
+    if args.auto_switch {
+        let avm = std::env::current_exe().context("Failed to locate the avm executable")?;
+        snippet.push_str(&shell.hook(&shell.quote(&avm.display().to_string())));
+    }
+    print!("{snippet}");
+    Ok(())
+}
+
+/// The commands switching the environment to the tags pinned for the current directory, undoing
+/// the previous switch.
+async fn hook_env(shell: Shell, tools: &ToolSet, paths: &Paths) -> anyhow::Result<String> {
+    let mut bin_dirs = Vec::<PathBuf>::new();
+    let mut vars = Vec::<(OsString, OsString)>::new();
+    for tool in ToolName::value_variants() {
+        let tool_name = tool.command_name();
+        let tag = match general_tool::find_pinned_tag(tools, tool, paths).await {
+            Ok(Some(tag)) => tag,
+            Ok(None) => continue,
+            Err(err) => {
+                log::warn!("Not switching {tool_name}: {err:#}");
+                continue;
+            }
+        };
+        let (tag_bin_dirs, tag_vars) =
+            tools.tag_env(tool, &paths.tool_dir.join(&tool_name).join(&*tag))?;
+        bin_dirs.extend(tag_bin_dirs);
+        vars.extend(tag_vars);
+    }
+
+    let switched_dirs = std::env::var_os(SWITCH_PATH_ENV)
+        .map(|dirs| std::env::split_paths(&dirs).collect::<Vec<_>>())
+        .unwrap_or_default();
+    let path = std::env::var_os("PATH").unwrap_or_default();
+    let kept_dirs = std::env::split_paths(&path).filter(|dir| !switched_dirs.contains(dir));
+    let path =
+        std::env::join_paths(bin_dirs.iter().cloned().chain(kept_dirs)).context("Invalid PATH")?;
+
+    let mut commands = shell.set_var("PATH", &path.to_string_lossy());
+    if bin_dirs.is_empty() {
+        commands.push_str(&shell.unset_var(SWITCH_PATH_ENV));
+    } else {
+        let dirs = std::env::join_paths(&bin_dirs).context("Invalid tag directory")?;
+        commands.push_str(&shell.set_var(SWITCH_PATH_ENV, &dirs.to_string_lossy()));
+    }
+    let switched_vars = std::env::var(SWITCH_VARS_ENV).unwrap_or_default();
+    for name in switched_vars.split(',').filter(|name| !name.is_empty()) {
+        if !vars.iter().any(|(var, _)| var == name) {
+            commands.push_str(&shell.unset_var(name));
+        }
+    }
+    for (name, value) in &vars {
+        commands.push_str(&shell.set_var(&name.to_string_lossy(), &value.to_string_lossy()));
+    }
+    if vars.is_empty() {
+        commands.push_str(&shell.unset_var(SWITCH_VARS_ENV));
+    } else {
+        let names = vars
+            .iter()
+            .map(|(name, _)| name.to_string_lossy())
+            .collect::<Vec<_>>()
+            .join(",");
+        commands.push_str(&shell.set_var(SWITCH_VARS_ENV, &names));
+    }
+    Ok(commands)
+}
+
+impl Shell {
+    fn completion_shell(self) -> clap_complete::Shell {
+        match self {
+            Shell::Bash => clap_complete::Shell::Bash,
+            Shell::Zsh => clap_complete::Shell::Zsh,
+            Shell::Fish => clap_complete::Shell::Fish,
+            Shell::Powershell => clap_complete::Shell::PowerShell,
+        }
+    }
+
+    fn quote(self, value: &str) -> String {
+        match self {
+            Shell::Bash | Shell::Zsh => format!("'{}'", value.replace('\'', r"'\''")),
+            Shell::Fish => format!("'{}'", value.replace('\\', r"\\").replace('\'', r"\'")),
+            Shell::Powershell => format!("'{}'", value.replace('\'', "''")),
+        }
+    }
+
+    fn set_var(self, name: &str, value: &str) -> String {
+        let value = self.quote(value);
+        match self {
+            Shell::Bash | Shell::Zsh => format!("export {name}={value}\n"),
+            // Fish splits the variables ending with PATH on colons.
+            Shell::Fish => format!("set -gx {name} {value}\n"),
+            Shell::Powershell => format!("$env:{name} = {value}\n"),
+        }
+    }
+
+    fn unset_var(self, name: &str) -> String {
+        match self {
+            Shell::Bash | Shell::Zsh => format!("unset {name}\n"),
+            Shell::Fish => format!("set -e {name}\n"),
+            Shell::Powershell => format!("Remove-Item Env:{name} -ErrorAction SilentlyContinue\n"),
+        }
+    }
+
+    /// Puts a directory in front of PATH unless it is already on it.
+    fn prepend_path(self, dir: &Path) -> String {
+        let dir = self.quote(&dir.display().to_string());
+        match self {
+            Shell::Bash | Shell::Zsh => format!(
+                "case \":$PATH:\" in\n  *:{dir}:*) ;;\n  *) export PATH={dir}\"${{PATH:+:$PATH}}\" ;;\nesac\n"
+            ),
+            Shell::Fish => format!("fish_add_path --global --path {dir}\n"),
+            Shell::Powershell => format!(
+                "if (($env:PATH -split [IO.Path]::PathSeparator) -notcontains {dir}) {{\n    $env:PATH = {dir} + [IO.Path]::PathSeparator + $env:PATH\n}}\n"
+            ),
+        }
+    }
+
+    /// Evaluates `avm init --hook-env` when the current directory changes.
+    fn hook(self, avm: &str) -> String {
+        match self {
+            Shell::Bash => format!(
+                r#"_avm_hook() {{
+  local status=$?
+  if [[ "$PWD" != "${{_avm_last_pwd-}}" ]]; then
+    _avm_last_pwd=$PWD
+    eval "$({avm} init bash --hook-env)"
+  fi
+  return $status
+}}
+if [[ ";${{PROMPT_COMMAND-}};" != *";_avm_hook;"* ]]; then
+  PROMPT_COMMAND="_avm_hook${{PROMPT_COMMAND:+;$PROMPT_COMMAND}}"
+fi
+"#
+            ),
+            Shell::Zsh => format!(
+                r#"_avm_hook() {{
+  eval "$({avm} init zsh --hook-env)"
+}}
+autoload -Uz add-zsh-hook
+add-zsh-hook chpwd _avm_hook
+_avm_hook
+"#
+            ),
+            Shell::Fish => format!(
+                r#"function __avm_hook --on-variable PWD
+    {avm} init fish --hook-env | source
+end
+__avm_hook
+"#
+            ),
+            Shell::Powershell => format!(
+                r#"$global:__AvmPrompt = $function:prompt
+function global:prompt {{
+    if ($PWD.Path -ne $global:__AvmLastPwd) {{
+        $global:__AvmLastPwd = $PWD.Path
+        (& {avm} init powershell --hook-env) -join "`n" | Invoke-Expression
+    }}
+    & $global:__AvmPrompt
+}}
+"#
+            ),
+        }
+    }
+}
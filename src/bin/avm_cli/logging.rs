@@ -0,0 +1,180 @@
+//! The logger of the CLI. Records go to stderr as text or JSON lines, and optionally to a
+//! log file as well. It starts with the defaults so that config errors are logged, and is
+//! configured once the command line is parsed.
+
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock, RwLock};
+use std::time::SystemTime;
+
+use any_version_manager::format_utc;
+use clap::ValueEnum;
+use log::{LevelFilter, Log, Metadata, Record};
+
+pub const LOG_ENV: &str = "AVM_LOG";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum LogFormat {
+    /// `LEVEL - message`, with the time in the log file.
+    #[default]
+    Text,
+    /// One JSON object per line with `time`, `level`, `target` and `message`.
+    Json,
+}
+
+/// Log levels by module, like `info,any_version_manager::io=debug`. A bare level applies to
+/// the modules without a more specific directive.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogFilter {
+    default: LevelFilter,
+    modules: Vec<(String, LevelFilter)>,
+}
+
+impl LogFilter {
+    pub fn new(default: LevelFilter) -> Self {
+        Self {
+            default,
+            modules: Vec::new(),
+        }
+    }
+
+    /// Parses directives separated by `,` on top of `default`.
+    pub fn parse(spec: &str, default: LevelFilter) -> anyhow::Result<Self> {
+        let mut filter = Self::new(default);
+        for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            let parse_level = |level: &str| {
+                level
+                    .trim()
+                    .parse::<LevelFilter>()
+                    .map_err(|_| anyhow::anyhow!("Invalid log level \"{level}\" in \"{spec}\""))
+            };
+            match directive.split_once('=') {
+                Some((module, level)) => filter
+                    .modules
+                    .push((module.trim().to_owned(), parse_level(level)?)),
+                None => filter.default = parse_level(directive)?,
+            }
+        }
+        // The most specific directive is found first.
+        filter
+            .modules
+            .sort_by_key(|(module, _)| std::cmp::Reverse(module.len()));
+        Ok(filter)
+    }
+
+    fn level(&self, target: &str) -> LevelFilter {
+        self.modules
+            .iter()
+            .find(|(module, _)| {
+                target
+                    .strip_prefix(module.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with("::"))
+            })
+            .map_or(self.default, |(_, level)| *level)
+    }
+
+    fn max_level(&self) -> LevelFilter {
+        self.modules
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.default, |max, level| max.max(level))
+    }
+}
+
+struct Config {
+    format: LogFormat,
+    filter: LogFilter,
+    file: Option<Mutex<File>>,
+}
+
+struct Logger {
+    config: RwLock<Config>,
+}
+
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+/// Installs the logger with text records of `info` and above to stderr.
+pub fn init() -> Result<(), log::SetLoggerError> {
+    let filter = LogFilter::new(LevelFilter::Info);
+    log::set_max_level(filter.max_level());
+    let logger = LOGGER.get_or_init(|| Logger {
+        config: RwLock::new(Config {
+            format: LogFormat::Text,
+            filter,
+            file: None,
+        }),
+    });
+    log::set_logger(logger)
+}
+
+/// Applies the logging options of the command line. Records are appended to `file` if given.
+pub fn configure(format: LogFormat, filter: LogFilter, file: Option<&Path>) -> anyhow::Result<()> {
+    let file = file
+        .map(|path| -> anyhow::Result<_> {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let file = File::options().create(true).append(true).open(path)?;
+            Ok(Mutex::new(file))
+        })
+        .transpose()
+        .map_err(|err| anyhow::anyhow!("Failed to open the log file: {err}"))?;
+    log::set_max_level(filter.max_level());
+    if let Some(logger) = LOGGER.get() {
+        *logger.config.write().unwrap_or_else(|e| e.into_inner()) = Config {
+            format,
+            filter,
+            file,
+        };
+    }
+    Ok(())
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        let config = self.config.read().unwrap_or_else(|e| e.into_inner());
+        metadata.level() <= config.filter.level(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        let config = self.config.read().unwrap_or_else(|e| e.into_inner());
+        if record.level() > config.filter.level(record.target()) {
+            return;
+        }
+        let time = format_utc(SystemTime::now());
+        let (line, file_line) = match config.format {
+            LogFormat::Text => {
+                let line = format!("{} - {}", record.level(), record.args());
+                let file_line = format!("{time} {line}");
+                (line, file_line)
+            }
+            LogFormat::Json => {
+                let line = json_record(&time, record);
+                (line.clone(), line)
+            }
+        };
+        let _ = writeln!(std::io::stderr(), "{line}");
+        if let Some(file) = &config.file {
+            let mut file = file.lock().unwrap_or_else(|e| e.into_inner());
+            let _ = writeln!(file, "{file_line}");
+        }
+    }
+
+    fn flush(&self) {
+        let config = self.config.read().unwrap_or_else(|e| e.into_inner());
+        if let Some(file) = &config.file {
+            let _ = file.lock().unwrap_or_else(|e| e.into_inner()).flush();
+        }
+    }
+}
+
+fn json_record(time: &str, record: &Record) -> String {
+    serde_json::json!({
+        "time": time,
+        "level": record.level().as_str(),
+        "target": record.target(),
+        "message": record.args().to_string(),
+    })
+    .to_string()
+}
@@ -0,0 +1,53 @@
+use std::path::PathBuf;
+
+use clap::Args;
+
+use super::general_tool::repair_action;
+use super::{config, Paths};
+
+#[derive(Debug, Args)]
+pub struct MigrateDataArgs {
+    #[arg(
+        value_name = "new_dir",
+        help = "New data directory, which must not exist or be empty"
+    )]
+    pub new_dir: PathBuf,
+}
+
+/// Moves the data directory and points `data_path` of the config to it, unless the data
+/// directory was given with `--data-dir`.
+pub async fn run(args: MigrateDataArgs, paths: &Paths, update_config: bool) -> anyhow::Result<()> {
+    let new_dir = std::path::absolute(&args.new_dir)?;
+    let new_dir_str = new_dir
+        .to_str()
+        .ok_or_else(|| anyhow::anyhow!("'{}' is not valid UTF-8", new_dir.display()))?
+        .to_owned();
+    for (tool_name, aliases) in
+        any_version_manager::avm::move_data_dir(&paths.data_dir, &new_dir).await?
+    {
+        for repaired in aliases {
+            println!(
+                "{}: {tool_name} {} -> {}",
+                repair_action(&repaired.repair),
+                repaired.alias,
+                repaired.target
+            );
+        }
+    }
+    log::info!(
+        "Moved '{}' to '{}'",
+        paths.data_dir.display(),
+        new_dir.display()
+    );
+    if update_config {
+        config::set_in_file(
+            &paths.config_file,
+            "data_path",
+            toml::Value::String(new_dir_str),
+        )?;
+        log::info!("Set `data_path` in '{}'", paths.config_file.display());
+    } else {
+        log::warn!("The config is not updated, pass `--data-dir {new_dir_str}` from now on");
+    }
+    Ok(())
+}
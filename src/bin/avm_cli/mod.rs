@@ -1,15 +1,39 @@
+pub mod config;
 pub mod dirln;
+pub mod doctor;
+pub mod export;
 pub mod general_tool;
 pub mod global;
+pub mod hooks;
+pub mod init;
+pub mod logging;
+pub mod migrate;
+pub mod reshim;
+pub mod rust;
+pub mod serve;
+pub mod shim_pack;
+pub mod upgrade;
 
-use any_version_manager::{DefaultPlatform, HttpClient, UrlMirror};
+use any_version_manager::download_cache::DownloadCache;
+use any_version_manager::pinned_hashes::{PinnedHashes, SigningKeys, DEFAULT_PINNED_HASHES_URL};
+use any_version_manager::tool::general_tool::{manifest, plugin};
+use any_version_manager::{
+    ByteRate, ByteSize, DefaultPlatform, HostAuth, HttpClient, ProxyConfig, SecurityConfig,
+    TlsConfig, ToolDefaults, UrlMirror,
+};
 use clap::{Parser, Subcommand};
 use directories::ProjectDirs;
 use log::LevelFilter;
+use rustc_hash::FxHashMap;
+use smol_str::SmolStr;
+use std::ffi::OsString;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 pub const CONFIG_PATH_ENV: &str = "CONFIG_PATH";
+pub const NON_INTERACTIVE_ENV: &str = "AVM_NONINTERACTIVE";
+const GITHUB_TOKEN_ENV: &str = "GITHUB_TOKEN";
 
 #[derive(Debug, Parser)]
 #[command(
@@ -24,6 +48,88 @@ pub struct Cli {
     #[arg(long, global = true, action = clap::ArgAction::SetTrue, help = "Enable debug logs")]
     pub debug: bool,
 
+    #[arg(
+        long,
+        global = true,
+        value_name = "directives",
+        help = "Log levels by module, for example `warn,any_version_manager=debug`. Defaults to the `AVM_LOG` environment variable."
+    )]
+    pub log_level: Option<String>,
+
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        default_value_t = logging::LogFormat::Text,
+        help = "Format of the log records"
+    )]
+    pub log_format: logging::LogFormat,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "path",
+        help = "Also append the log records to this file, with their time"
+    )]
+    pub log_file: Option<PathBuf>,
+
+    #[arg(
+        long,
+        global = true,
+        help = "Never prompt, fail instead, and don't draw progress bars. Also enabled by a non-empty `AVM_NONINTERACTIVE` other than `0` or `false`."
+    )]
+    pub non_interactive: bool,
+
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        default_value_t = ProgressFormat::Auto,
+        help = "How to report the progress of downloads"
+    )]
+    pub progress: ProgressFormat,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "rate",
+        help = "Limit the download rate, for example 5MB/s. Overrides `max_download_rate` of the config."
+    )]
+    pub limit_rate: Option<ByteRate>,
+
+    #[arg(
+        long,
+        global = true,
+        overrides_with = "no_wait",
+        help = "Wait for another avm process changing the same tool, which is the default"
+    )]
+    pub wait: bool,
+
+    #[arg(
+        long,
+        global = true,
+        overrides_with = "wait",
+        help = "Fail instead of waiting for another avm process changing the same tool"
+    )]
+    pub no_wait: bool,
+
+    // Read by `path_options` before the command line is parsed.
+    #[arg(
+        long,
+        global = true,
+        value_name = "path",
+        help = "Use this config file. Overrides the `CONFIG_PATH` environment variable."
+    )]
+    pub config: Option<PathBuf>,
+
+    #[arg(
+        long,
+        global = true,
+        value_name = "path",
+        help = "Keep the installed tools and other data in this directory. Overrides `data_path` of the config."
+    )]
+    pub data_dir: Option<PathBuf>,
+
     #[command(subcommand)]
     pub command: Command,
 }
@@ -33,9 +139,18 @@ pub enum Command {
     #[command(about = "Get the path of the config file")]
     ConfigPath,
 
+    #[command(about = "Get, set or edit the values of the config file")]
+    Config(config::ConfigArgs),
+
     #[command(about = "List tools, or show tool-specific install guidance")]
     Tool(global::ToolGuideArgs),
 
+    #[command(
+        about = "Find tools by name or description",
+        long_about = "Finds the tools whose name or description contains the query. With a version, also looks up the newest matching release of each found tool on its default platform and flavor."
+    )]
+    Search(global::SearchArgs),
+
     #[command(about = "Install a specific tool")]
     Install(general_tool::InstallArgs),
 
@@ -45,98 +160,445 @@ pub enum Command {
     #[command(about = "Get download info")]
     GetDowninfo(general_tool::GetDowninfoArgs),
 
+    #[command(
+        about = "Print or open the release notes of a version",
+        long_about = "Prints the URL of the upstream release notes of the newest release matching the version and selector flags, or opens it in the browser with `--open`. Supported by node, go and liberica."
+    )]
+    Changelog(general_tool::ChangelogArgs),
+
+    #[command(
+        about = "Check reachability and latency of a tool's upstream endpoints",
+        long_about = "Checks reachability and latency of a tool's upstream metadata endpoints. When a mirror applies to an endpoint, both the mirror and the upstream URL are checked."
+    )]
+    Ping(general_tool::PingArgs),
+
     #[command(about = "Install a specific tool from a local archive")]
     InstallLocal(general_tool::InstallLocalArgs),
 
+    #[command(
+        about = "Install a specific tool from an archive URL",
+        long_about = "Downloads the archive at the URL and installs it as a tag, without looking up the tool's releases. Useful for releases that are not in the vendor index yet, or internal builds."
+    )]
+    InstallUrl(general_tool::InstallUrlArgs),
+
     #[command(about = "List existing tags")]
     List(general_tool::ListArgs),
 
+    #[command(about = "Show what a tag was installed from")]
+    Info(general_tool::InfoArgs),
+
+    #[command(
+        about = "List installed tags with newer releases",
+        long_about = "Compares each installed tag with the newest release of its platform and flavor in the same major version, or LTS release if the tag is LTS."
+    )]
+    Outdated(upgrade::OutdatedArgs),
+
+    #[command(
+        about = "Install newer releases of tags and re-point their aliases",
+        long_about = "Installs the newest release of each tag's platform and flavor in the same major version, or LTS release if the tag is LTS, then re-points the aliases of the old tag to the new one."
+    )]
+    Upgrade(upgrade::UpgradeArgs),
+
     #[command(about = "Get the tool path of a specific tag")]
     Path(general_tool::PathArgs),
 
     #[command(about = "Get the tool entry path (executable binary or runtime entry file)")]
     EntryPath(general_tool::EntryPathArgs),
 
-    #[command(about = "Run by tag, selector, or default tag")]
+    #[command(
+        about = "Run by tag, selector, or default tag",
+        long_about = "Runs a tag of the tool, or the tag matching the selector flags, installing it if needed. Without either, runs the version in the `AVM_<TOOL>_VERSION` variable (such as `AVM_GO_VERSION` or `AVM_NODE_VERSION`), else the version for the tool in the nearest `.tool-versions`, else the default tag. The version may be a tag or a version spec."
+    )]
     Run(general_tool::RunArgs),
 
-    #[command(about = "Create a tag alias")]
+    #[command(
+        about = "Create, list, delete or move tag aliases",
+        long_about = "Creates an alias tag pointing to a source tag with `avm alias <tool> <src_tag> <alias_tag>`, replacing an existing alias. `list`, `delete` and `move` manage the existing aliases."
+    )]
     Alias(general_tool::AliasArgs),
 
-    #[command(about = "Copy an existing tag to a new tag")]
+    #[command(
+        about = "Re-point alias tags to their targets",
+        long_about = "Re-points alias tags to their targets in the current tool directory, for example after moving the data directory. Aliases created as copies, where links are not available, are refreshed."
+    )]
+    RepairAlias(general_tool::RepairAliasArgs),
+
+    #[command(
+        about = "Copy an existing tag to a new tag",
+        long_about = "Copies an existing tag, with what it was installed from, to a new tag. With `--from-data-dir`, the tag is copied from another data directory, such as a mounted backup. An alias is copied as a regular tag with the content of its target. Files are reflinked on file systems that support it."
+    )]
     Copy(general_tool::CopyArgs),
 
+    #[command(
+        about = "Hard link the identical files of the installed tags",
+        long_about = "Replaces the files that are identical across the installed tags of a tool, such as the shared files of JDK releases, by hard links, and prints the space saved. Removing a tag later keeps the files of the other tags, but changing a linked file in place changes it in every tag."
+    )]
+    Dedup(general_tool::DedupArgs),
+
+    #[command(
+        about = "Export a tag as an archive to install on another machine",
+        long_about = "Packs a tag, with what it was installed from, into a `.tar.zst` archive. `avm install-local` installs it on another machine without downloading it again, for example on one without network access."
+    )]
+    ExportTag(general_tool::ExportTagArgs),
+
+    #[command(
+        about = "Download releases for several platforms into a bundle to install offline",
+        long_about = "Downloads and verifies the archives of a release for each given platform and writes them, with what they were resolved to, into a tar file. `avm install --from-bundle` installs from it on another machine without network access."
+    )]
+    Bundle(general_tool::BundleArgs),
+
+    #[command(about = "Rename a tag, re-pointing the aliases to it")]
+    Rename(general_tool::RenameArgs),
+
     #[command(about = "Remove existing tags")]
     Remove(general_tool::RemoveArgs),
 
-    #[command(about = "Clean temporary directories and dangling aliases")]
+    #[command(
+        about = "Remove the installed tags matching a version filter",
+        long_about = "Removes every installed tag matching the filter, except alias targets and the newest tags kept by `--all-but-latest`. With `--unused-for`, only the tags that were neither run by `avm run` (or a shim) nor installed within the period are removed, as recorded locally under the state directory. Lists the tags and asks for confirmation unless `--yes` is given."
+    )]
+    Prune(general_tool::PruneArgs),
+
+    #[command(about = "Clean temporary directories and dangling aliases, or the download cache")]
     Clean(general_tool::CleanArgs),
 
+    #[command(
+        about = "Print the installed tags and aliases in TOML",
+        long_about = "Prints the installed tags and aliases of the tools in TOML, for `avm import` to re-provision them on another machine."
+    )]
+    Export(export::ExportArgs),
+
+    #[command(
+        about = "Install the tags and aliases written by `avm export`",
+        long_about = "Installs the tags written by `avm export` that are missing, downloading the same versions, then points the aliases to them. Tags that already exist are kept."
+    )]
+    Import(export::ImportArgs),
+
+    #[command(
+        about = "Install exactly the artifacts of a lock file",
+        long_about = "Installs the missing tags of a lock file written by `avm export --lock` from the recorded URLs, failing if a downloaded archive doesn't match the recorded hash or an existing tag has another version. Then points the aliases to them."
+    )]
+    Sync(export::SyncArgs),
+
+    #[command(
+        about = "Move the data directory and point the config to it",
+        long_about = "Moves the data directory, with the installed tools and plugins, to a new directory, which must not exist or be empty. Re-points the alias tags and sets `data_path` in the config, unless the data directory was given with `--data-dir`. The cache and state directories move along when they are inside the data directory."
+    )]
+    MigrateData(migrate::MigrateDataArgs),
+
+    #[command(
+        about = "Check the installed tools for problems",
+        long_about = "Checks the installed tools for problems, warning about the operations left unfinished by an interrupted avm process, and, for each installed tool, about the endpoints that are unreachable, the aliases whose tags were removed and a default tag whose version is past its end of life according to endoflife.date."
+    )]
+    Doctor,
+
+    #[command(
+        about = "Download the newest database of pinned hashes",
+        long_about = "Downloads the database of known-good archive hashes from `security.pin_hashes_url`, or from the avm repository by default, into the data directory, once its detached signature is verified with the pinned keys. Requires `gpg`. Its entries take precedence over the database built into avm. The hashes are checked on install when `security.pin_hashes` is set."
+    )]
+    UpdatePinnedHashes,
+
+    #[command(
+        about = "Manage Rust toolchains with a rustup kept by avm",
+        long_about = "Manages Rust toolchains with a rustup installed under the avm data directory, separate from a rustup installed by the user. Add the directory printed by `avm rust path` to PATH to use the default toolchain."
+    )]
+    Rust(rust::RustArgs),
+
     #[command(
         about = "Create a directory symbolic link (equivalent ln -s for Unix, mklink /J for Windows)",
         long_about = "Creates a directory symbolic link. This is equivalent to 'ln -s' on Unix systems and 'mklink /J' on Windows. This command is a utility and not directly tied to core avm flows."
     )]
     Dirln(dirln::DirlnArgs),
+
+    #[command(
+        about = "Write a self-bootstrapping avm launcher and tool shims into a directory",
+        long_about = "Writes `avmw` (plus `avmw.cmd`/`avmw.ps1` for Windows) and `avmw.properties` into a directory, usually a repository root. `./avmw <args>` downloads and verifies the pinned avm binary if it is missing, then runs it with the arguments. Each `--tool` also gets a shim per executable, so `./node ...` runs `avm run node -- ...` and `./npm ...` runs `avm run node --exe npm -- ...`."
+    )]
+    InstallShimPack(shim_pack::ShimPackArgs),
+
+    #[command(
+        about = "Write shims for the executables of a tool into the shims directory",
+        long_about = "Writes a shim per executable of a tool into the `shims` directory under the data directory, for adding to PATH. A shim runs `avm run`, which picks the tag each time it is invoked: the version in `AVM_<TOOL>_VERSION` (such as `AVM_GO_VERSION`), else the version for the tool in the nearest `.tool-versions`, else the default tag. Run it again after installing tags that ship other executables."
+    )]
+    Reshim(reshim::ReshimArgs),
+
+    #[command(
+        about = "Print the shell snippet that puts the shims on PATH and completes avm commands",
+        long_about = "Prints the snippet for a shell config to evaluate at startup, such as `eval \"$(avm init bash)\"` in `~/.bashrc`, `avm init fish | source` in `config.fish` or `avm init powershell | Out-String | Invoke-Expression` in the PowerShell profile. It puts the shims directory written by `avm reshim` on PATH and sets up the completion of avm commands. With `--auto-switch`, changing directories also puts the tags pinned by `.tool-versions` on PATH, with the variables `avm run` sets for them, such as `JAVA_HOME`. Pinned versions that are not installed are skipped."
+    )]
+    Init(init::InitArgs),
+
+    #[command(
+        about = "Serve a local HTTP API to list, resolve, install and remove tags",
+        long_about = "Serves a local HTTP API for IDE plugins and other programs, so that they don't start avm for each request and share its metadata cache. `GET /tools/{tool}/tags` lists the installed tags, `GET /tools/{tool}/resolve?spec=...` finds the installed tag matching a version spec, `POST /tools/{tool}/install?spec=...` installs and streams the JSON events of `--progress json`, and `DELETE /tools/{tool}/tags/{tag}` removes a tag. Prints the URL, or the socket path, once listening."
+    )]
+    Serve(serve::ServeArgs),
 }
 
+static NON_INTERACTIVE: AtomicBool = AtomicBool::new(false);
+static PROGRESS_JSON: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum ProgressFormat {
+    /// Progress bars on a terminal, periodic log records otherwise.
+    #[default]
+    Auto,
+    /// One JSON event per line on stdout: `download-started`, `progress`, `verifying`,
+    /// `extracting`, `done` and `error`.
+    Json,
+}
+
+/// Whether prompts and progress bars are turned off by `--non-interactive`.
+pub fn is_non_interactive() -> bool {
+    NON_INTERACTIVE.load(Ordering::Relaxed)
+}
+
+/// Whether the progress of downloads is printed as JSON events by `--progress json`.
+pub fn is_progress_json() -> bool {
+    PROGRESS_JSON.load(Ordering::Relaxed)
+}
+
+/// A tool started by `avm run` that exited unsuccessfully, avm exits with the same code.
+#[derive(Debug, thiserror::Error)]
+#[error("The tool exited with code {0}")]
+pub struct ToolExit(pub i32);
+
+static TOOL_RUNNING: AtomicBool = AtomicBool::new(false);
+
+/// Whether `avm run` waits for a tool, which handles Ctrl-C itself.
+pub fn is_tool_running() -> bool {
+    TOOL_RUNNING.load(Ordering::Relaxed)
+}
+
+/// A prompt that can't be shown, reported with its own exit code.
+#[derive(Debug, thiserror::Error)]
+#[error("{0}, pass `--yes` to confirm")]
+pub struct ConfirmationRequired(pub &'static str);
+
 pub struct LoadedConfig {
     pub mirrors: UrlMirror,
+    pub proxy: ProxyConfig,
+    pub tls: TlsConfig,
+    pub max_download_rate: Option<ByteRate>,
+    /// `None` if turned off with a maximum size of 0.
+    pub download_cache: Option<DownloadCache>,
+    pub download_buffer_size: usize,
+    pub github_token: Option<SmolStr>,
+    pub auth: Vec<HostAuth>,
     pub paths: Paths,
     pub default_platform: DefaultPlatform,
+    pub tool_defaults: FxHashMap<String, ToolDefaults>,
+    pub security: SecurityConfig,
+    pub manifests: Vec<(SmolStr, manifest::Manifest)>,
+    pub plugins: Vec<(SmolStr, plugin::Plugin)>,
 }
 
 #[allow(dead_code)]
 pub struct Paths {
     pub config_file: PathBuf,
+    /// The installed tools, plugins and the other data that is costly to rebuild.
     pub data_dir: PathBuf,
     pub tool_dir: PathBuf,
+    /// Downloads that can be fetched again.
+    pub cache_dir: PathBuf,
+    /// Records of what avm did, such as logs of past operations.
+    pub state_dir: PathBuf,
 }
 
 pub async fn run(
     paths: Paths,
     client: Arc<HttpClient>,
     default_platform: DefaultPlatform,
+    tool_defaults: FxHashMap<String, ToolDefaults>,
+    security: SecurityConfig,
+    manifests: Vec<(SmolStr, manifest::Manifest)>,
+    plugins: Vec<(SmolStr, plugin::Plugin)>,
 ) -> anyhow::Result<()> {
     let cli = Cli::parse();
-    if !cli.debug {
-        log::set_max_level(LevelFilter::Info);
+    let log_filter = match cli
+        .log_level
+        .clone()
+        .or_else(|| std::env::var(logging::LOG_ENV).ok())
+    {
+        Some(spec) => logging::LogFilter::parse(&spec, default_level(cli.debug))?,
+        None => logging::LogFilter::new(default_level(cli.debug)),
+    };
+    logging::configure(cli.log_format, log_filter, cli.log_file.as_deref())?;
+    let non_interactive = cli.non_interactive
+        || std::env::var(NON_INTERACTIVE_ENV)
+            .is_ok_and(|value| !matches!(value.as_str(), "" | "0" | "false"));
+    NON_INTERACTIVE.store(non_interactive, Ordering::Relaxed);
+    PROGRESS_JSON.store(cli.progress == ProgressFormat::Json, Ordering::Relaxed);
+    any_version_manager::set_wait_for_locks(!cli.no_wait);
+    any_version_manager::journal::set_dir(paths.state_dir.join("journal"));
+    match any_version_manager::journal::recover().await {
+        Ok(0) => {}
+        Ok(count) => log::warn!("Recovered {count} interrupted operation(s)"),
+        Err(err) => log::warn!("Failed to recover interrupted operations: {err}"),
     }
+    let client = match cli.limit_rate {
+        Some(rate) => Arc::new(HttpClient::clone(&client).with_max_download_rate(Some(rate))),
+        None => client,
+    };
 
-    let tools = general_tool::ToolSet::new(client.clone(), &default_platform);
+    let pinned_hashes = if security.pin_hashes {
+        Some(PinnedHashes::load(&paths.data_dir).await?)
+    } else {
+        None
+    };
+    let tools = general_tool::ToolSet::new(
+        client.clone(),
+        &paths.data_dir,
+        &default_platform,
+        tool_defaults,
+        manifests,
+        plugins,
+        pinned_hashes,
+    );
 
     match cli.command {
         Command::ConfigPath => {
             println!("{}", paths.config_file.display());
             Ok(())
         }
+        Command::Config(args) => config::run(args, &paths),
         Command::Tool(args) => {
             global::run_tool_guide(args, &tools);
             Ok(())
         }
+        Command::Search(args) => global::run_search(args, &tools).await,
         Command::Install(args) => general_tool::run_install(args, &tools, &client, &paths).await,
         Command::GetVers(args) => general_tool::run_get_vers(args, &tools).await,
         Command::GetDowninfo(args) => general_tool::run_get_downinfo(args, &tools).await,
-        Command::InstallLocal(args) => general_tool::run_install_local(args, &paths).await,
-        Command::List(args) => general_tool::run_list(args, &paths).await,
+        Command::Changelog(args) => general_tool::run_changelog(args, &tools).await,
+        Command::Ping(args) => general_tool::run_ping(args, &tools, &client).await,
+        Command::InstallLocal(args) => general_tool::run_install_local(args, &tools, &paths).await,
+        Command::InstallUrl(args) => {
+            general_tool::run_install_url(args, &tools, &client, &paths).await
+        }
+        Command::List(args) => general_tool::run_list(args, &tools, &paths).await,
+        Command::Info(args) => general_tool::run_info(args, &paths).await,
+        Command::Outdated(args) => upgrade::run_outdated(args, &tools, &paths).await,
+        Command::Upgrade(args) => upgrade::run_upgrade(args, &tools, &client, &paths).await,
         Command::Path(args) => general_tool::run_path(args, &paths),
         Command::EntryPath(args) => general_tool::run_entry_path(args, &tools, &paths),
         Command::Run(args) => general_tool::run_run(args, &tools, &client, &paths).await,
         Command::Alias(args) => general_tool::run_alias(args, &paths).await,
+        Command::RepairAlias(args) => general_tool::run_repair_alias(args, &paths).await,
         Command::Copy(args) => general_tool::run_copy(args, &paths).await,
+        Command::Dedup(args) => general_tool::run_dedup(args, &paths).await,
+        Command::ExportTag(args) => general_tool::run_export_tag(args, &paths).await,
+        Command::Bundle(args) => general_tool::run_bundle(args, &tools, &client, &paths).await,
+        Command::Rename(args) => general_tool::run_rename(args, &paths).await,
         Command::Remove(args) => general_tool::run_remove(args, &paths).await,
+        Command::Prune(args) => general_tool::run_prune(args, &tools, &paths).await,
         Command::Clean(args) => general_tool::run_clean(args, &paths).await,
+        Command::Export(args) => export::run_export(args, &tools, &paths).await,
+        Command::Import(args) => export::run_import(args, &tools, &client, &paths).await,
+        Command::Sync(args) => export::run_sync(args, &tools, &client, &paths).await,
+        Command::MigrateData(args) => migrate::run(args, &paths, cli.data_dir.is_none()).await,
+        Command::Doctor => doctor::run(&tools, &client, &paths).await,
+        Command::UpdatePinnedHashes => {
+            let url = security
+                .pin_hashes_url
+                .as_deref()
+                .unwrap_or(DEFAULT_PINNED_HASHES_URL);
+            let keys = pinned_hashes_keys(&security).await?;
+            let count = PinnedHashes::update(&client, url, &keys, &paths.data_dir).await?;
+            log::info!("Downloaded {count} pinned hashes from {url}");
+            if !security.pin_hashes {
+                log::warn!("Pinned hashes are not checked unless `security.pin_hashes` is set");
+            }
+            Ok(())
+        }
+        Command::Rust(args) => rust::run(args, &client, &paths).await,
         Command::Dirln(args) => dirln::run(args).await,
+        Command::InstallShimPack(args) => shim_pack::run(args, &tools).await,
+        Command::Reshim(args) => reshim::run(args, &tools, &paths).await,
+        Command::Init(args) => init::run(args, &tools, &paths).await,
+        Command::Serve(args) => serve::run(args, tools, client, paths).await,
+    }
+}
+
+/// The keys the downloaded database of pinned hashes must be signed by: those of the config
+/// for a database of its own, or those of the avm repository.
+async fn pinned_hashes_keys(security: &SecurityConfig) -> anyhow::Result<SigningKeys> {
+    let Some(path) = security.pin_hashes_keys.clone() else {
+        if security.pin_hashes_url.is_some() {
+            anyhow::bail!("Set `security.pin_hashes_keys` and `security.pin_hashes_fingerprints` to the keys that sign the database at `security.pin_hashes_url`");
+        }
+        return SigningKeys::default_database().ok_or_else(|| {
+            anyhow::anyhow!("The avm repository publishes no signed database of pinned hashes yet, set `security.pin_hashes_url` with the keys that sign another one")
+        });
+    };
+    if security.pin_hashes_fingerprints.is_empty() {
+        anyhow::bail!("Set `security.pin_hashes_fingerprints` to the fingerprints of the keys in '{}' that sign the database", path.display());
+    }
+    let keys = any_version_manager::spawn_blocking(move || {
+        std::fs::read(&path)
+            .map_err(|err| anyhow::anyhow!("Failed to read '{}': {err}", path.display()))
+    })
+    .await?;
+    Ok(SigningKeys {
+        keys,
+        fingerprints: security
+            .pin_hashes_fingerprints
+            .iter()
+            .map(|fingerprint| fingerprint.replace(' ', "").to_ascii_uppercase().into())
+            .collect(),
+    })
+}
+
+fn default_level(debug: bool) -> LevelFilter {
+    if debug {
+        LevelFilter::Trace
+    } else {
+        LevelFilter::Info
     }
 }
 
-pub fn load_config() -> anyhow::Result<LoadedConfig> {
+/// Finds the values of `--config` and `--data-dir` in `args`, the command line with the
+/// program name. They are needed before parsing it, since the tool names it accepts depend on
+/// the tool manifests and plugins found through them.
+pub fn path_options(
+    args: impl IntoIterator<Item = OsString>,
+) -> (Option<PathBuf>, Option<PathBuf>) {
+    let (mut config, mut data_dir) = (None, None);
+    let mut args = args.into_iter().skip(1);
+    while let Some(arg) = args.next() {
+        // What follows `--` is passed to the tool.
+        if arg == "--" {
+            break;
+        }
+        let Some(arg) = arg.to_str() else {
+            continue;
+        };
+        let (name, value) = match arg.split_once('=') {
+            Some((name, value)) => (name, Some(PathBuf::from(value))),
+            None => (arg, None),
+        };
+        let option = match name {
+            "--config" => &mut config,
+            "--data-dir" => &mut data_dir,
+            _ => continue,
+        };
+        *option = value.or_else(|| args.next().map(PathBuf::from));
+    }
+    (config, data_dir)
+}
+
+/// Loads the config from `config_path`, or else from `CONFIG_PATH` or the default location.
+/// `data_dir` overrides `data_path` of the config.
+pub fn load_config(
+    config_path: Option<PathBuf>,
+    data_dir: Option<PathBuf>,
+) -> anyhow::Result<LoadedConfig> {
     let dirs =
         ProjectDirs::from("", "", "avm").ok_or_else(|| anyhow::anyhow!("No home directory"))?;
 
-    let config_path = match std::env::var_os(CONFIG_PATH_ENV) {
-        Some(path) => path.into(),
-        None => dirs.config_dir().join("config.toml"),
-    };
+    let config_path = config_path
+        .or_else(|| std::env::var_os(CONFIG_PATH_ENV).map(PathBuf::from))
+        .unwrap_or_else(|| dirs.config_dir().join("config.toml"));
 
     let config: any_version_manager::Config = match std::fs::read_to_string(&config_path) {
         Ok(config_str) => toml::from_str(&config_str)?,
@@ -146,18 +608,90 @@ pub fn load_config() -> anyhow::Result<LoadedConfig> {
         Err(e) => return Err(e.into()),
     };
 
-    let data_path = config
-        .data_path
+    let manifest_dir = config_path
+        .parent()
+        .unwrap_or(std::path::Path::new(""))
+        .join("tools");
+    let manifests = manifest::load_dir(&manifest_dir)?;
+
+    // A data directory chosen by the user holds the cache and state unless they are set too,
+    // so that it is self-contained.
+    let custom_data_path = data_dir.or(config.data_path);
+    let cache_path = config
+        .cache_path
+        .unwrap_or_else(|| match &custom_data_path {
+            Some(data_path) => data_path.join("cache"),
+            None => dirs.cache_dir().to_path_buf(),
+        });
+    let data_path = custom_data_path
+        .clone()
         .unwrap_or_else(|| dirs.data_local_dir().to_path_buf());
-    let tool_path = data_path.join("tools");
+    let state_path =
+        config
+            .state_path
+            .unwrap_or_else(|| match (&custom_data_path, dirs.state_dir()) {
+                (None, Some(state_dir)) => state_dir.to_path_buf(),
+                _ => data_path.join("state"),
+            });
+    let tool_path = data_path.join(any_version_manager::avm::TOOLS_DIR);
+    let plugins = plugin::discover(&data_path.join("plugins"))?;
+
+    general_tool::ToolName::register_external(
+        manifests.iter().map(|(name, _)| name.clone()),
+        plugins.iter().map(|(name, _)| name.clone()),
+    )?;
+
+    if let Some(mirrors) = &config.mirrors {
+        for tool in mirrors.scoped_tools() {
+            if <general_tool::ToolName as clap::ValueEnum>::from_str(tool, false).is_err() {
+                anyhow::bail!("Unknown tool \"{tool}\" in mirror `tools`");
+            }
+        }
+    }
+
+    let tool_defaults = config.tools.unwrap_or_default();
+    for tool in tool_defaults.keys() {
+        if <general_tool::ToolName as clap::ValueEnum>::from_str(tool, false).is_err() {
+            anyhow::bail!("Unknown tool \"{tool}\" in `tools`");
+        }
+    }
+
+    let download_cache = match config
+        .max_download_cache_size
+        .map_or(DownloadCache::DEFAULT_MAX_SIZE, ByteSize::bytes)
+    {
+        0 => None,
+        max_size => Some(DownloadCache::new(&cache_path, max_size)),
+    };
 
     Ok(LoadedConfig {
         mirrors: config.mirrors.unwrap_or_default(),
+        proxy: config.proxy.unwrap_or_default(),
+        tls: config.tls.unwrap_or_default(),
+        max_download_rate: config.max_download_rate,
+        download_cache,
+        download_buffer_size: config
+            .download_buffer_size
+            .map_or(HttpClient::DEFAULT_DOWNLOAD_BUFFER_SIZE, |size| {
+                size.bytes() as usize
+            }),
+        github_token: std::env::var(GITHUB_TOKEN_ENV)
+            .ok()
+            .filter(|token| !token.is_empty())
+            .map(SmolStr::from)
+            .or(config.github_token),
+        auth: config.auth.unwrap_or_default(),
         paths: Paths {
             config_file: config_path,
             data_dir: data_path,
             tool_dir: tool_path,
+            cache_dir: cache_path,
+            state_dir: state_path,
         },
         default_platform: config.default_platform.unwrap_or_default(),
+        tool_defaults,
+        security: config.security.unwrap_or_default(),
+        manifests,
+        plugins,
     })
 }
@@ -0,0 +1,111 @@
+use any_version_manager::avm::SHIMS_DIR;
+use any_version_manager::tool::general_tool;
+use anyhow::Context;
+use clap::Args;
+use smol_str::SmolStr;
+use std::path::Path;
+
+use super::general_tool::{ToolName, ToolSet};
+use super::shim_pack::write_file;
+use super::Paths;
+
+/// Marks a shim written by `avm reshim`, followed by the name of its tool.
+const SHIM_MARKER: &str = "avm shim for ";
+
+#[derive(Debug, Clone, Args)]
+pub struct ReshimArgs {
+    #[arg(value_enum, help = "Tool name.")]
+    pub tool: ToolName,
+}
+
+pub async fn run(args: ReshimArgs, tools: &ToolSet, paths: &Paths) -> anyhow::Result<()> {
+    let tool_name = args.tool.command_name();
+    let mut exe_names = tools.exe_names(&args.tool)?;
+    for (tag, target) in general_tool::list_tags(&tool_name, &paths.tool_dir).await? {
+        if target.is_some() {
+            continue;
+        }
+        let tag_dir = paths.tool_dir.join(&tool_name).join(&*tag);
+        for name in tools.tag_exe_names(&args.tool, &tag_dir)? {
+            if !exe_names.contains(&name) {
+                exe_names.push(name);
+            }
+        }
+    }
+
+    let avm = std::env::current_exe().context("Failed to locate the avm executable")?;
+    // The entry runs without `--exe`.
+    let shims = exe_names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let exe = (i > 0).then_some(name.as_str());
+            shim(&avm, &tool_name, exe, name)
+        })
+        .collect::<Vec<_>>();
+    let shims_dir = paths.data_dir.join(SHIMS_DIR);
+    any_version_manager::spawn_blocking(move || {
+        std::fs::create_dir_all(&shims_dir)?;
+        // The shims of executables the tool no longer ships.
+        for entry in std::fs::read_dir(&shims_dir)? {
+            let path = entry?.path();
+            let is_stale = shim_tool(&path).is_some_and(|tool| tool == tool_name)
+                && !shims
+                    .iter()
+                    .any(|(file, _)| path.file_name() == Some(std::ffi::OsStr::new(file)));
+            if is_stale {
+                std::fs::remove_file(&path)
+                    .with_context(|| format!("Failed to remove '{}'", path.display()))?;
+            }
+        }
+        for (file, content) in shims {
+            let path = shims_dir.join(&file);
+            match shim_tool(&path) {
+                Some(tool) if tool != tool_name => {
+                    log::warn!("Skipping '{}', the shim of {tool}", path.display());
+                    continue;
+                }
+                None if path.exists() => {
+                    log::warn!("Skipping '{}', which is not a shim", path.display());
+                    continue;
+                }
+                _ => {}
+            }
+            write_file(&path, &content, true)
+                .with_context(|| format!("Failed to write '{}'", path.display()))?;
+            println!("{}", path.display());
+        }
+        Ok(())
+    })
+    .await
+}
+
+/// The file name and content of the shim of an executable, which runs `avm run` so that
+/// the tag is resolved each time it is invoked.
+fn shim(avm: &Path, tool_name: &str, exe: Option<&str>, name: &str) -> (String, String) {
+    let exe_arg = exe.map(|exe| format!(" --exe {exe}")).unwrap_or_default();
+    #[cfg(windows)]
+    return (
+        format!("{name}.cmd"),
+        format!(
+            "@rem {SHIM_MARKER}{tool_name}\r\n@\"{}\" run {tool_name}{exe_arg} -- %*\r\n",
+            avm.display()
+        ),
+    );
+    #[cfg(not(windows))]
+    return (
+        name.to_owned(),
+        format!(
+            "#!/bin/sh\n# {SHIM_MARKER}{tool_name}\nexec '{}' run {tool_name}{exe_arg} -- \"$@\"\n",
+            avm.display().to_string().replace('\'', r"'\''")
+        ),
+    );
+}
+
+/// The tool of a shim written by `avm reshim`, `None` for other files.
+fn shim_tool(path: &Path) -> Option<SmolStr> {
+    let content = std::fs::read_to_string(path).ok()?;
+    let line = content.lines().find(|line| line.contains(SHIM_MARKER))?;
+    let (_, tool) = line.split_once(SHIM_MARKER)?;
+    Some(tool.trim().into())
+}
@@ -0,0 +1,120 @@
+use any_version_manager::rustup::Rustup;
+use any_version_manager::HttpClient;
+use clap::{Args, Subcommand};
+
+use super::Paths;
+
+#[derive(Debug, Args)]
+pub struct RustArgs {
+    #[command(subcommand)]
+    pub command: RustCommand,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum RustCommand {
+    #[command(
+        about = "Download and set up the managed rustup",
+        long_about = "Downloads rustup-init and installs rustup under the data directory, without a toolchain and without touching the shell profiles. `avm rust install` does this when needed."
+    )]
+    Setup,
+
+    #[command(about = "Install toolchains, such as `stable` or `1.82.0`")]
+    Install {
+        #[arg(required = true, help = "Toolchains to install")]
+        toolchains: Vec<String>,
+        #[arg(long, help = "Set the first toolchain as the default")]
+        default: bool,
+    },
+
+    #[command(about = "List installed toolchains")]
+    List,
+
+    #[command(about = "Set the default toolchain")]
+    Default {
+        #[arg(help = "Installed toolchain")]
+        toolchain: String,
+    },
+
+    #[command(about = "Remove toolchains")]
+    Remove {
+        #[arg(required = true, help = "Toolchains to remove")]
+        toolchains: Vec<String>,
+    },
+
+    #[command(about = "Get the directory of `cargo`, `rustc` and the other toolchain executables")]
+    Path,
+}
+
+pub async fn run(args: RustArgs, client: &HttpClient, paths: &Paths) -> anyhow::Result<()> {
+    let rustup = Rustup::new(&paths.data_dir);
+    match args.command {
+        RustCommand::Setup => {
+            if rustup.is_installed().await? {
+                log::info!("rustup is already set up, updating it");
+                return Ok(rustup.run(["self", "update"]).await?);
+            }
+            Ok(rustup.install(client).await?)
+        }
+        RustCommand::Install {
+            toolchains,
+            default,
+        } => {
+            if !rustup.is_installed().await? {
+                log::info!("Setting up rustup");
+                rustup.install(client).await?;
+            }
+            rustup
+                .run(
+                    ["toolchain", "install"]
+                        .into_iter()
+                        .chain(toolchains.iter().map(String::as_str)),
+                )
+                .await?;
+            if default {
+                rustup.run(["default", &toolchains[0]]).await?;
+            }
+            Ok(())
+        }
+        RustCommand::List => {
+            if !rustup.is_installed().await? {
+                return Ok(());
+            }
+            let toolchains = rustup.toolchains().await?;
+            // Printed like the tags and aliases of `avm list`.
+            if let Some(default) = toolchains.iter().find(|toolchain| toolchain.is_default) {
+                println!("default -> {}", default.name);
+            }
+            for toolchain in toolchains {
+                println!("{}", toolchain.name);
+            }
+            Ok(())
+        }
+        RustCommand::Default { toolchain } => {
+            ensure_installed(&rustup).await?;
+            Ok(rustup.run(["default", &toolchain]).await?)
+        }
+        RustCommand::Remove { toolchains } => {
+            ensure_installed(&rustup).await?;
+            Ok(rustup
+                .run(
+                    ["toolchain", "uninstall"]
+                        .into_iter()
+                        .chain(toolchains.iter().map(String::as_str)),
+                )
+                .await?)
+        }
+        RustCommand::Path => {
+            println!("{}", rustup.bin_dir().display());
+            Ok(())
+        }
+    }
+}
+
+async fn ensure_installed(rustup: &Rustup) -> anyhow::Result<()> {
+    if !rustup.is_installed().await? {
+        anyhow::bail!(
+            "rustup is not set up, run `avm rust setup` or `avm rust install <toolchain>`"
+        );
+    }
+    Ok(())
+}
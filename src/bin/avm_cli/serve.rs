@@ -0,0 +1,624 @@
+//! `avm serve`, a local HTTP API for IDE plugins and other programs to drive avm without
+//! starting a process per request. The requests share the HTTP client and the metadata the
+//! tools cache.
+//!
+//! - `GET /tools/{tool}/tags` lists the installed tags.
+//! - `GET /tools/{tool}/resolve?spec=..` finds the installed tag matching a version spec.
+//! - `POST /tools/{tool}/install?spec=..` installs a version, responding with the JSON lines
+//!   of `--progress json` as they happen.
+//! - `DELETE /tools/{tool}/tags/{tag}` removes a tag.
+//!
+//! The selector of `resolve` and `install` takes `spec`, `platform`, `flavor`, `lts_only` and
+//! `include_prerelease`, and `install` also takes `update` and `default`.
+//!
+//! Every request must send the token generated at start as `Authorization: Bearer <token>`.
+//! Requests with an `Origin` header or a `Host` other than the local machine are rejected, so
+//! that web pages can't drive the API, also through DNS rebinding.
+
+use std::path::Path;
+use std::rc::Rc;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use any_version_manager::io::DownloadExtractState;
+use any_version_manager::pinned_hashes::PinnedHashes;
+use any_version_manager::tool::general_tool;
+use any_version_manager::tool::{GeneralTool, TagOptions};
+use any_version_manager::HttpClient;
+use clap::{Args, ValueEnum};
+use rustc_hash::FxHashMap;
+use serde_json::json;
+use smol_str::SmolStr;
+use tokio::io::{
+    AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader,
+};
+use tokio::net::TcpListener;
+
+use super::general_tool::{
+    async_invoke_tool, finish_install, report_json_progress, resolve_selector_filters, AsyncFnTool,
+    EventSink, SelectorArgs, ToolName, ToolSet,
+};
+use super::Paths;
+
+#[derive(Debug, Args)]
+pub struct ServeArgs {
+    #[arg(
+        long,
+        value_name = "address",
+        default_value = "127.0.0.1:7280",
+        help = "Loopback address to listen on, port 0 picks a free port. The URL is printed once listening."
+    )]
+    pub listen: String,
+
+    #[cfg(unix)]
+    #[arg(
+        long,
+        value_name = "path",
+        help = "Listen on this unix socket instead of a TCP port"
+    )]
+    pub socket: Option<std::path::PathBuf>,
+
+    #[arg(
+        long,
+        value_name = "path",
+        help = "Write the token of the requests to this file, readable only by the user, instead of printing it after the URL"
+    )]
+    pub token_file: Option<std::path::PathBuf>,
+}
+
+/// The longest request line or header line read.
+const MAX_LINE_LEN: u64 = 8 * 1024;
+const MAX_HEADERS: usize = 64;
+
+struct Server {
+    tools: ToolSet,
+    client: Arc<HttpClient>,
+    paths: Paths,
+    /// The bearer token the requests must send.
+    token: String,
+}
+
+pub async fn run(
+    args: ServeArgs,
+    tools: ToolSet,
+    client: Arc<HttpClient>,
+    paths: Paths,
+) -> anyhow::Result<()> {
+    // Nobody is there to answer a prompt.
+    super::NON_INTERACTIVE.store(true, Ordering::Relaxed);
+    let mut token = [0_u8; 32];
+    getrandom::fill(&mut token)
+        .map_err(|err| anyhow::anyhow!("Failed to generate the token: {err}"))?;
+    let token = hex::encode(token);
+    if let Some(path) = &args.token_file {
+        write_token_file(path, &token).await?;
+    }
+    let print_token = args.token_file.is_none();
+    let server = Rc::new(Server {
+        tools,
+        client,
+        paths,
+        token,
+    });
+    // The futures of the tools are not `Send`, so the connections are served on this thread.
+    tokio::task::LocalSet::new()
+        .run_until(async move {
+            #[cfg(unix)]
+            if let Some(path) = &args.socket {
+                return serve_unix_socket(path, server, print_token).await;
+            }
+            let listener = TcpListener::bind(&args.listen)
+                .await
+                .map_err(|err| anyhow::anyhow!("Failed to listen on {}: {err}", args.listen))?;
+            let address = listener.local_addr()?;
+            if !address.ip().is_loopback() {
+                anyhow::bail!("{address} is not a loopback address, `avm serve` only serves the local machine");
+            }
+            println!("http://{address}");
+            if print_token {
+                println!("{}", server.token);
+            }
+            loop {
+                let (stream, _) = listener.accept().await?;
+                spawn_connection(stream, server.clone());
+            }
+        })
+        .await
+}
+
+/// Writes `token` to the file at `path`, which only the user may read.
+async fn write_token_file(path: &Path, token: &str) -> anyhow::Result<()> {
+    let (path, token) = (path.to_owned(), token.to_owned());
+    any_version_manager::spawn_blocking(move || {
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{OpenOptionsExt, PermissionsExt};
+
+            options.mode(0o600);
+            // `mode` only applies to a new file.
+            if path.exists() {
+                std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600))?;
+            }
+        }
+        let mut file = options
+            .open(&path)
+            .map_err(|err| anyhow::anyhow!("Failed to write '{}': {err}", path.display()))?;
+        std::io::Write::write_all(&mut file, token.as_bytes())?;
+        Ok(())
+    })
+    .await
+}
+
+#[cfg(unix)]
+async fn serve_unix_socket(
+    path: &Path,
+    server: Rc<Server>,
+    print_token: bool,
+) -> anyhow::Result<()> {
+    use std::os::unix::fs::FileTypeExt;
+
+    // The socket of a previous run is left behind when it is stopped.
+    let stale = path.to_owned();
+    any_version_manager::spawn_blocking(move || {
+        if std::fs::symlink_metadata(&stale).is_ok_and(|metadata| metadata.file_type().is_socket())
+        {
+            std::fs::remove_file(&stale)?;
+        }
+        Ok(())
+    })
+    .await?;
+    let listener = tokio::net::UnixListener::bind(path)
+        .map_err(|err| anyhow::anyhow!("Failed to listen on '{}': {err}", path.display()))?;
+    println!("{}", path.display());
+    if print_token {
+        println!("{}", server.token);
+    }
+    loop {
+        let (stream, _) = listener.accept().await?;
+        spawn_connection(stream, server.clone());
+    }
+}
+
+fn spawn_connection<S: AsyncRead + AsyncWrite + Unpin + 'static>(stream: S, server: Rc<Server>) {
+    tokio::task::spawn_local(async move {
+        let mut stream = BufReader::new(stream);
+        let result = match read_request(&mut stream).await {
+            Ok(request) => {
+                log::debug!("{} /{}", request.method, request.path.join("/"));
+                match server.authorize(&request) {
+                    Ok(()) => server.respond(&request, stream.get_mut()).await,
+                    Err((status, message)) => {
+                        write_json(stream.get_mut(), status, &json!({ "error": message }))
+                            .await
+                            .map_err(anyhow::Error::from)
+                    }
+                }
+            }
+            Err(err) => {
+                let (status, body) = bad_request(err);
+                let result = write_json(stream.get_mut(), status, &body).await;
+                // Closing with the rest of the request unread would reset the connection, which
+                // may discard the response before the client reads it.
+                if result.is_ok() {
+                    discard_input(&mut stream).await;
+                }
+                result.map_err(anyhow::Error::from)
+            }
+        };
+        if let Err(err) = result {
+            log::warn!("Failed to respond to a request: {err:#}");
+        }
+    });
+}
+
+/// Ends the response and reads what is left of the request, up to a limit and for a short time.
+async fn discard_input<S: AsyncRead + AsyncWrite + Unpin>(stream: &mut BufReader<S>) {
+    if stream.get_mut().shutdown().await.is_err() {
+        return;
+    }
+    let mut rest = (&mut *stream).take(1024 * 1024);
+    let _ = tokio::time::timeout(
+        std::time::Duration::from_secs(1),
+        tokio::io::copy(&mut rest, &mut tokio::io::sink()),
+    )
+    .await;
+}
+
+struct Request {
+    method: String,
+    path: Vec<String>,
+    query: FxHashMap<String, String>,
+    /// The headers the requests are authorized by, by lowercase name.
+    headers: FxHashMap<String, String>,
+}
+
+impl Request {
+    fn selector(&self) -> anyhow::Result<SelectorArgs> {
+        Ok(SelectorArgs {
+            spec: self.query.get("spec").cloned(),
+            platform: self.query.get("platform").cloned(),
+            flavor: self.query.get("flavor").cloned(),
+            lts_only: self.flag("lts_only")?,
+            include_prerelease: self.flag("include_prerelease")?,
+            ..SelectorArgs::default()
+        })
+    }
+
+    /// A boolean parameter, set by `name`, `name=true` or `name=1`.
+    fn flag(&self, name: &str) -> anyhow::Result<bool> {
+        match self.query.get(name).map(String::as_str) {
+            None | Some("false" | "0") => Ok(false),
+            Some("" | "true" | "1") => Ok(true),
+            Some(value) => {
+                anyhow::bail!("Invalid value \"{value}\" of `{name}`, expected `true` or `false`")
+            }
+        }
+    }
+}
+
+/// Reads a line of at most [`MAX_LINE_LEN`] bytes, empty at the end of the stream.
+async fn read_line<R: AsyncBufRead + Unpin>(reader: &mut R) -> anyhow::Result<String> {
+    let mut line = String::new();
+    let len = (&mut *reader)
+        .take(MAX_LINE_LEN)
+        .read_line(&mut line)
+        .await?;
+    if len as u64 == MAX_LINE_LEN && !line.ends_with('\n') {
+        anyhow::bail!("A line of the request is longer than {MAX_LINE_LEN} bytes");
+    }
+    Ok(line)
+}
+
+/// Reads the request line and the headers, since no request has a body.
+async fn read_request<R: AsyncBufRead + Unpin>(reader: &mut R) -> anyhow::Result<Request> {
+    let line = read_line(reader).await?;
+    let mut parts = line.split_whitespace();
+    let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+        anyhow::bail!("Invalid request line {:?}", line.trim_end());
+    };
+    let mut headers = FxHashMap::default();
+    for i in 0.. {
+        let header = read_line(reader).await?;
+        if header.trim().is_empty() {
+            break;
+        }
+        if i == MAX_HEADERS {
+            anyhow::bail!("The request has more than {MAX_HEADERS} headers");
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            let name = name.trim().to_ascii_lowercase();
+            if matches!(name.as_str(), "host" | "origin" | "authorization") {
+                headers.insert(name, value.trim().to_owned());
+            }
+        }
+    }
+    let url = reqwest::Url::parse("http://localhost/")?.join(target)?;
+    Ok(Request {
+        method: method.to_owned(),
+        path: url
+            .path_segments()
+            .map(|segments| {
+                segments
+                    .filter(|segment| !segment.is_empty())
+                    .map(str::to_owned)
+                    .collect()
+            })
+            .unwrap_or_default(),
+        query: url.query_pairs().into_owned().collect(),
+        headers,
+    })
+}
+
+/// Whether `host`, the `Host` header, names the local machine.
+fn is_local_host(host: &str) -> bool {
+    let Ok(url) = reqwest::Url::parse(&format!("http://{host}/")) else {
+        return false;
+    };
+    match url.host_str() {
+        Some("localhost") => true,
+        Some(host) => host
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse::<std::net::IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback()),
+        None => false,
+    }
+}
+
+/// Compares the tokens in a time independent of where they differ.
+fn token_matches(given: &str, token: &str) -> bool {
+    given.len() == token.len()
+        && given
+            .bytes()
+            .zip(token.bytes())
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+async fn write_json<W: AsyncWrite + Unpin>(
+    stream: &mut W,
+    status: &str,
+    body: &serde_json::Value,
+) -> std::io::Result<()> {
+    let body = body.to_string();
+    let response = format!(
+        "HTTP/1.1 {status}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.flush().await
+}
+
+impl Server {
+    /// Rejects the requests of web pages and those without the token, with the status and the
+    /// error to respond with.
+    fn authorize(&self, request: &Request) -> Result<(), (&'static str, &'static str)> {
+        if request.headers.contains_key("origin") {
+            return Err(("403 Forbidden", "Requests from web pages are not allowed"));
+        }
+        if !request
+            .headers
+            .get("host")
+            .is_some_and(|host| is_local_host(host))
+        {
+            return Err(("403 Forbidden", "The Host must be the local machine"));
+        }
+        let token = request
+            .headers
+            .get("authorization")
+            .and_then(|value| value.strip_prefix("Bearer "));
+        if !token.is_some_and(|token| token_matches(token.trim(), &self.token)) {
+            return Err((
+                "401 Unauthorized",
+                "Send the token printed by `avm serve` as `Authorization: Bearer <token>`",
+            ));
+        }
+        Ok(())
+    }
+
+    async fn respond<W: AsyncWrite + Unpin>(
+        &self,
+        request: &Request,
+        stream: &mut W,
+    ) -> anyhow::Result<()> {
+        let path = request.path.iter().map(String::as_str).collect::<Vec<_>>();
+        let (status, body) = match (request.method.as_str(), path.as_slice()) {
+            (method, ["tools", tool, ..]) => match <ToolName as ValueEnum>::from_str(tool, false) {
+                Ok(tool) => match (method, &path[2..]) {
+                    ("POST", ["install"]) => return self.install(&tool, request, stream).await,
+                    ("GET", ["tags"]) => reply(self.tags(&tool).await),
+                    ("GET", ["resolve"]) => match self.selector(&tool, request) {
+                        Ok(selector) => match self.resolve(&tool, &selector).await {
+                            Ok(Some(resolved)) => ("200 OK", resolved),
+                            Ok(None) => (
+                                "404 Not Found",
+                                json!({ "error": "No installed tag matches" }),
+                            ),
+                            Err(err) => reply(Err(err)),
+                        },
+                        Err(err) => bad_request(err),
+                    },
+                    ("DELETE", ["tags", tag]) => reply(self.remove(&tool, tag).await),
+                    _ => not_found(),
+                },
+                Err(_) => (
+                    "404 Not Found",
+                    json!({ "error": format!("Unknown tool \"{tool}\"") }),
+                ),
+            },
+            _ => not_found(),
+        };
+        Ok(write_json(stream, status, &body).await?)
+    }
+
+    async fn tags(&self, tool: &ToolName) -> anyhow::Result<serde_json::Value> {
+        let tags = general_tool::list_tags(&tool.command_name(), &self.paths.tool_dir).await?;
+        Ok(tags
+            .into_iter()
+            .map(|(tag, alias_of)| json!({ "tag": tag.as_str(), "alias_of": alias_of.as_deref() }))
+            .collect())
+    }
+
+    /// The selector of the query with the defaults of the tool in the config.
+    fn selector(&self, tool: &ToolName, request: &Request) -> anyhow::Result<SelectorArgs> {
+        let mut selector = request.selector()?;
+        selector.apply_defaults(&self.tools, tool)?;
+        Ok(selector)
+    }
+
+    async fn resolve(
+        &self,
+        tool: &ToolName,
+        selector: &SelectorArgs,
+    ) -> anyhow::Result<Option<serde_json::Value>> {
+        let tool_name = tool.command_name();
+        let fn_tool = ResolveFn {
+            tool_name: &tool_name,
+            tools_base: &self.paths.tool_dir,
+            selector,
+        };
+        async_invoke_tool(&self.tools, tool, &fn_tool).await
+    }
+
+    async fn remove(&self, tool: &ToolName, tag: &str) -> anyhow::Result<serde_json::Value> {
+        general_tool::remove_tag(
+            &tool.command_name(),
+            &self.paths.tool_dir,
+            vec![SmolStr::from(tag)],
+            false,
+        )
+        .await?;
+        Ok(json!({ "removed": tag }))
+    }
+
+    /// Responds with the JSON events of the install as they happen, ending with `done` or
+    /// `error`.
+    async fn install<W: AsyncWrite + Unpin>(
+        &self,
+        tool: &ToolName,
+        request: &Request,
+        stream: &mut W,
+    ) -> anyhow::Result<()> {
+        let options = (|| -> anyhow::Result<_> {
+            let selector = self.selector(tool, request)?;
+            Ok((selector, request.flag("update")?, request.flag("default")?))
+        })();
+        let (selector, update, default) = match options {
+            Ok(options) => options,
+            Err(err) => {
+                let (status, body) = bad_request(err);
+                return Ok(write_json(stream, status, &body).await?);
+            }
+        };
+        stream
+            .write_all(
+                b"HTTP/1.1 200 OK\r\nContent-Type: application/x-ndjson\r\nConnection: close\r\n\r\n",
+            )
+            .await?;
+        stream.flush().await?;
+
+        let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+        let install = async move {
+            let sink = EventSink::Channel(sender);
+            let tool_name = tool.command_name();
+            let fn_tool = ServeInstallFn {
+                tool_name: &tool_name,
+                client: &self.client,
+                tools_base: &self.paths.tool_dir,
+                pinned_hashes: self.tools.pinned_hashes.as_ref(),
+                selector: &selector,
+                update,
+                default,
+            };
+            let (tag, url, state) = match async_invoke_tool(&self.tools, tool, &fn_tool).await {
+                Ok(resolved) => resolved,
+                Err(err) => {
+                    sink.send(json!({ "event": "error", "message": format!("{err:#}") }));
+                    return;
+                }
+            };
+            // The failure of the download is reported by its events.
+            if report_json_progress(tag.clone(), url, state, sink.clone())
+                .await
+                .is_err()
+            {
+                return;
+            }
+            if let Err(err) =
+                finish_install(&self.tools, tool, &tag, &TagOptions::default(), &self.paths).await
+            {
+                sink.send(json!({
+                    "event": "error",
+                    "tag": tag.as_str(),
+                    "message": format!("{err:#}"),
+                }));
+            }
+        };
+        let forward = async {
+            // The install goes on if the client disconnects.
+            let mut connected = true;
+            while let Some(line) = receiver.recv().await {
+                if connected {
+                    connected = stream
+                        .write_all(format!("{line}\n").as_bytes())
+                        .await
+                        .is_ok()
+                        && stream.flush().await.is_ok();
+                }
+            }
+        };
+        tokio::join!(install, forward);
+        Ok(())
+    }
+}
+
+fn reply(result: anyhow::Result<serde_json::Value>) -> (&'static str, serde_json::Value) {
+    match result {
+        Ok(body) => ("200 OK", body),
+        Err(err) => (
+            "500 Internal Server Error",
+            json!({ "error": format!("{err:#}") }),
+        ),
+    }
+}
+
+fn bad_request(err: anyhow::Error) -> (&'static str, serde_json::Value) {
+    ("400 Bad Request", json!({ "error": format!("{err:#}") }))
+}
+
+fn not_found() -> (&'static str, serde_json::Value) {
+    ("404 Not Found", json!({ "error": "Not found" }))
+}
+
+struct ResolveFn<'a> {
+    tool_name: &'a str,
+    tools_base: &'a Path,
+    selector: &'a SelectorArgs,
+}
+
+impl AsyncFnTool for ResolveFn<'_> {
+    type Output = anyhow::Result<Option<serde_json::Value>>;
+
+    async fn invoke(&self, tool: &impl GeneralTool) -> Self::Output {
+        let (platform, flavor, version_filter) = resolve_selector_filters(tool, self.selector)?;
+        let Some(tag) = general_tool::find_matching_local_tag(
+            self.tool_name,
+            tool,
+            self.tools_base,
+            platform,
+            flavor,
+            version_filter,
+        )
+        .await?
+        else {
+            return Ok(None);
+        };
+        let path = general_tool::get_tag_path(self.tool_name, self.tools_base, &tag)?;
+        let entry_path = general_tool::get_entry_path(self.tool_name, tool, self.tools_base, &tag)?;
+        Ok(Some(json!({
+            "tag": tag.as_str(),
+            "path": path.display().to_string(),
+            "entry_path": entry_path.display().to_string(),
+        })))
+    }
+}
+
+struct ServeInstallFn<'a> {
+    tool_name: &'a str,
+    client: &'a HttpClient,
+    tools_base: &'a Path,
+    pinned_hashes: Option<&'a PinnedHashes>,
+    selector: &'a SelectorArgs,
+    update: bool,
+    default: bool,
+}
+
+impl AsyncFnTool for ServeInstallFn<'_> {
+    /// The tag to install, the URL to download and the download to run.
+    type Output = anyhow::Result<(SmolStr, SmolStr, DownloadExtractState)>;
+
+    async fn invoke(&self, tool: &impl GeneralTool) -> Self::Output {
+        let (platform, flavor, install_version) = resolve_selector_filters(tool, self.selector)?;
+        Ok(general_tool::InstallArgs {
+            tool_name: self.tool_name,
+            tool,
+            client: self.client,
+            tools_base: self.tools_base,
+            platform,
+            flavor,
+            install_version,
+            update: self.update,
+            force: false,
+            default: self.default,
+            keep_partial: false,
+            stream_extract: false,
+            platform_fallback: self.selector.platform.is_none(),
+            pinned_hashes: self.pinned_hashes,
+            cancel: any_version_manager::cancellation_token(),
+        }
+        .install()
+        .await?)
+    }
+}
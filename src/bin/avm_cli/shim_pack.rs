@@ -0,0 +1,289 @@
+use anyhow::Context;
+use clap::Args;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+use super::general_tool::{ToolName, ToolSet};
+
+/// The release archives are named after the Rust target triples, see
+/// `.github/workflows/draft.yml`.
+const DEFAULT_DIST_URL: &str =
+    "https://github.com/Jason5Lee/avm/releases/download/v{version}/avm-{target}.zip";
+const PROPERTIES_FILE: &str = "avmw.properties";
+
+#[derive(Debug, Clone, Args)]
+pub struct ShimPackArgs {
+    #[arg(help = "Directory to write the launcher and shims into, usually the repository root")]
+    pub dir: PathBuf,
+    #[arg(
+        long = "tool",
+        value_name = "tool[=verpfx]",
+        help = "Create shims for the tool's executables, optionally pinned to a version prefix (for example `node=22`). Can be repeated."
+    )]
+    pub tools: Vec<String>,
+    #[arg(
+        long,
+        value_name = "version",
+        help = "avm version the launcher bootstraps. Defaults to the running version."
+    )]
+    pub avm_version: Option<String>,
+    #[arg(
+        long,
+        value_name = "url",
+        help = "Download URL of the zip archive with the avm binary. `{version}`, `{platform}` and `{target}`, the Rust target triple, are substituted."
+    )]
+    pub dist_url: Option<String>,
+    #[arg(
+        long = "sha256",
+        value_name = "platform=hash",
+        help = "Expected SHA-256 of the avm archive for a platform, for example `x64-linux=<hash>`. Can be repeated. The launcher refuses to run on platforms without one."
+    )]
+    pub sha256: Vec<String>,
+    #[arg(
+        long,
+        conflicts_with = "sha256",
+        help = "Let the launcher run the downloaded avm without verifying it."
+    )]
+    pub no_verify: bool,
+    #[arg(long, help = "Overwrite existing files.")]
+    pub force: bool,
+}
+
+pub async fn run(args: ShimPackArgs, tools: &ToolSet) -> anyhow::Result<()> {
+    let mut files = vec![
+        (PROPERTIES_FILE.to_owned(), properties(&args)?, false),
+        ("avmw".to_owned(), AVMW_SH.to_owned(), true),
+        ("avmw.cmd".to_owned(), AVMW_CMD.to_owned(), false),
+        ("avmw.ps1".to_owned(), AVMW_PS1.to_owned(), false),
+    ];
+    for tool in &args.tools {
+        let (name, version_prefix) = match tool.split_once('=') {
+            Some((name, version_prefix)) => (name, Some(version_prefix)),
+            None => (tool.as_str(), None),
+        };
+        let tool_name = <ToolName as clap::ValueEnum>::from_str(name, false).map_err(|_| {
+            anyhow::anyhow!("Unknown tool \"{name}\". Run `avm tool` to list tools.")
+        })?;
+        let selector = match version_prefix {
+            Some(version_prefix) => format!(" -x {version_prefix}"),
+            None => String::new(),
+        };
+        // One shim per executable, the entry runs without `--exe`.
+        for (i, exe) in tools.exe_names(&tool_name)?.into_iter().enumerate() {
+            if files.iter().any(|(file, _, _)| *file == exe) {
+                anyhow::bail!("More than one tool provides a shim named \"{exe}\"");
+            }
+            let exe_arg = if i == 0 {
+                String::new()
+            } else {
+                format!(" --exe {exe}")
+            };
+            files.push((
+                exe.to_string(),
+                format!(
+                    "#!/bin/sh\nexec \"$(dirname \"$0\")/avmw\" run {name}{selector}{exe_arg} -- \"$@\"\n"
+                ),
+                true,
+            ));
+            files.push((
+                format!("{exe}.cmd"),
+                format!("@\"%~dp0avmw.cmd\" run {name}{selector}{exe_arg} -- %*\r\n"),
+                false,
+            ));
+        }
+    }
+
+    any_version_manager::spawn_blocking(move || {
+        std::fs::create_dir_all(&args.dir)?;
+        for (name, _, _) in &files {
+            let path = args.dir.join(name);
+            if !args.force && path.exists() {
+                anyhow::bail!(
+                    "'{}' already exists. Use `--force` to overwrite.",
+                    path.display()
+                );
+            }
+        }
+        for (name, content, executable) in files {
+            let path = args.dir.join(&name);
+            write_file(&path, &content, executable)
+                .with_context(|| format!("Failed to write '{}'", path.display()))?;
+            println!("{}", path.display());
+        }
+        Ok(())
+    })
+    .await
+}
+
+fn properties(args: &ShimPackArgs) -> anyhow::Result<String> {
+    if args.sha256.is_empty() && !args.no_verify {
+        anyhow::bail!(
+            "Pass `--sha256 <platform>=<hash>` for each platform the launcher runs on, or `--no-verify` to skip the verification"
+        );
+    }
+    let mut content = String::from("# Read by avmw to bootstrap avm.\n");
+    writeln!(
+        content,
+        "version={}",
+        args.avm_version
+            .as_deref()
+            .unwrap_or(env!("CARGO_PKG_VERSION"))
+    )?;
+    writeln!(
+        content,
+        "dist_url={}",
+        args.dist_url.as_deref().unwrap_or(DEFAULT_DIST_URL)
+    )?;
+    for entry in &args.sha256 {
+        let (platform, hash) = entry
+            .split_once('=')
+            .filter(|(_, hash)| hash.len() == 64 && hex::decode(hash).is_ok())
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Invalid `--sha256` \"{entry}\", expected `<platform>=<hex sha256>`"
+                )
+            })?;
+        writeln!(content, "sha256.{platform}={}", hash.to_ascii_lowercase())?;
+    }
+    if args.no_verify {
+        writeln!(content, "verify=false")?;
+    }
+    Ok(content)
+}
+
+pub(super) fn write_file(path: &Path, content: &str, executable: bool) -> std::io::Result<()> {
+    std::fs::write(path, content)?;
+    #[cfg(unix)]
+    if executable {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))?;
+    }
+    #[cfg(not(unix))]
+    let _ = executable;
+    Ok(())
+}
+
+const AVMW_SH: &str = r#"#!/bin/sh
+# avm launcher. Downloads the avm version pinned in avmw.properties if missing, then runs it.
+set -e
+
+dir=$(cd "$(dirname "$0")" && pwd)
+prop() {
+    sed -n "s/^$1=//p" "$dir/avmw.properties" | tr -d '\r' | head -n 1
+}
+
+case "$(uname -s)" in
+    Linux) os=linux ;;
+    Darwin) os=mac ;;
+    *) echo "avmw: unsupported OS $(uname -s)" >&2; exit 1 ;;
+esac
+case "$(uname -m)" in
+    x86_64 | amd64) cpu=x64 ;;
+    aarch64 | arm64) cpu=arm64 ;;
+    i386 | i686) cpu=x86 ;;
+    *) echo "avmw: unsupported CPU $(uname -m)" >&2; exit 1 ;;
+esac
+platform="$cpu-$os"
+# The targets avm is released for.
+case "$platform" in
+    x64-linux) target=x86_64-unknown-linux-musl ;;
+    arm64-linux) target=aarch64-unknown-linux-musl ;;
+    x86-linux) target=i686-unknown-linux-gnu ;;
+    x64-mac) target=x86_64-apple-darwin ;;
+    arm64-mac) target=aarch64-apple-darwin ;;
+    *) echo "avmw: avm is not released for $platform" >&2; exit 1 ;;
+esac
+
+version=$(prop version)
+avm="${AVMW_HOME:-$HOME/.avm/wrapper}/$version/avm"
+if [ ! -x "$avm" ]; then
+    url=$(prop dist_url | sed -e "s/{version}/$version/g" -e "s/{platform}/$platform/g" -e "s/{target}/$target/g")
+    expected=$(prop "sha256.$platform")
+    if [ -z "$expected" ] && [ "$(prop verify)" != false ]; then
+        echo "avmw: no sha256.$platform in avmw.properties to verify $url with" >&2
+        exit 1
+    fi
+    mkdir -p "$(dirname "$avm")"
+    tmp="$avm.tmp.$$"
+    trap 'rm -rf "$tmp" "$tmp.zip"' EXIT
+    echo "avmw: downloading $url" >&2
+    if command -v curl >/dev/null 2>&1; then
+        curl -fsSL -o "$tmp.zip" "$url"
+    else
+        wget -q -O "$tmp.zip" "$url"
+    fi
+    if [ -n "$expected" ]; then
+        if command -v sha256sum >/dev/null 2>&1; then
+            actual=$(sha256sum "$tmp.zip" | cut -d ' ' -f 1)
+        else
+            actual=$(shasum -a 256 "$tmp.zip" | cut -d ' ' -f 1)
+        fi
+        if [ "$actual" != "$expected" ]; then
+            echo "avmw: SHA-256 mismatch for $url, expected $expected, got $actual" >&2
+            exit 1
+        fi
+    fi
+    unzip -q "$tmp.zip" -d "$tmp"
+    bin=$(find "$tmp" -type f -name avm | head -n 1)
+    if [ -z "$bin" ]; then
+        echo "avmw: no avm binary in $url" >&2
+        exit 1
+    fi
+    chmod +x "$bin"
+    mv "$bin" "$avm"
+fi
+
+exec "$avm" "$@"
+"#;
+
+const AVMW_CMD: &str = "@echo off\r
+powershell -NoProfile -ExecutionPolicy Bypass -File \"%~dp0avmw.ps1\" %*\r
+exit /b %ERRORLEVEL%\r
+";
+
+const AVMW_PS1: &str = r#"# avm launcher. Downloads the avm version pinned in avmw.properties if missing, then runs it.
+$ErrorActionPreference = 'Stop'
+
+$props = @{}
+Get-Content -LiteralPath (Join-Path $PSScriptRoot 'avmw.properties') |
+    Where-Object { $_ -match '^[^#].*=' } |
+    ForEach-Object { $k, $v = $_ -split '=', 2; $props[$k.Trim()] = $v.Trim() }
+
+$cpu = @{ 'AMD64' = 'x64'; 'ARM64' = 'arm64'; 'x86' = 'x86' }[$env:PROCESSOR_ARCHITECTURE]
+if (-not $cpu) { throw "avmw: unsupported CPU $env:PROCESSOR_ARCHITECTURE" }
+$platform = "$cpu-win"
+# The targets avm is released for.
+$target = @{ 'x64-win' = 'x86_64-pc-windows-msvc'; 'arm64-win' = 'aarch64-pc-windows-msvc' }[$platform]
+if (-not $target) { throw "avmw: avm is not released for $platform" }
+
+$wrapperHome = if ($env:AVMW_HOME) { $env:AVMW_HOME } else { Join-Path $env:USERPROFILE '.avm\wrapper' }
+$avm = Join-Path (Join-Path $wrapperHome $props['version']) 'avm.exe'
+if (-not (Test-Path -LiteralPath $avm)) {
+    $url = $props['dist_url'].Replace('{version}', $props['version']).Replace('{platform}', $platform).Replace('{target}', $target)
+    $expected = $props["sha256.$platform"]
+    if (-not $expected -and $props['verify'] -ne 'false') {
+        throw "avmw: no sha256.$platform in avmw.properties to verify $url with"
+    }
+    New-Item -ItemType Directory -Force -Path (Split-Path $avm) | Out-Null
+    $tmp = "$avm.tmp"
+    [Console]::Error.WriteLine("avmw: downloading $url")
+    try {
+        Invoke-WebRequest -UseBasicParsing -Uri $url -OutFile "$tmp.zip"
+        if ($expected) {
+            $actual = (Get-FileHash -Algorithm SHA256 -LiteralPath "$tmp.zip").Hash.ToLower()
+            if ($actual -ne $expected) {
+                throw "avmw: SHA-256 mismatch for $url, expected $expected, got $actual"
+            }
+        }
+        Expand-Archive -Force -LiteralPath "$tmp.zip" -DestinationPath $tmp
+        $bin = Get-ChildItem -LiteralPath $tmp -Recurse -File -Filter 'avm.exe' | Select-Object -First 1
+        if (-not $bin) { throw "avmw: no avm.exe in $url" }
+        Move-Item -Force -LiteralPath $bin.FullName -Destination $avm
+    } finally {
+        Remove-Item -Recurse -Force -ErrorAction SilentlyContinue -LiteralPath $tmp, "$tmp.zip"
+    }
+}
+
+& $avm @args
+exit $LASTEXITCODE
+"#;
@@ -0,0 +1,234 @@
+use std::path::Path;
+
+use any_version_manager::pinned_hashes::PinnedHashes;
+use any_version_manager::tool::general_tool::{self, InstalledTag};
+use any_version_manager::tool::{GeneralTool, TagOptions, Version, VersionFilter};
+use any_version_manager::AvmError;
+use clap::Args;
+use smol_str::SmolStr;
+
+use super::general_tool::{
+    async_invoke_tool, drive_download_state, finish_install, print_install_plan, AsyncFnTool,
+    ToolName, ToolSet,
+};
+use super::Paths;
+use crate::HttpClient;
+
+#[derive(Debug, Clone, Args)]
+pub struct OutdatedArgs {
+    #[arg(value_enum, help = "Tool name.")]
+    pub tool: ToolName,
+    #[arg(
+        long,
+        help = "Compare with the newest release of any major version, not only the installed one."
+    )]
+    pub any_major: bool,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct UpgradeArgs {
+    #[arg(value_enum, help = "Tool name.")]
+    pub tool: ToolName,
+    #[arg(
+        value_name = "tag",
+        required_unless_present = "all",
+        conflicts_with = "all",
+        help = "Tag(s) to upgrade."
+    )]
+    pub tags: Vec<String>,
+    #[arg(long, help = "Upgrade every outdated tag.")]
+    pub all: bool,
+    #[arg(
+        long,
+        help = "Upgrade to the newest release of any major version, not only the installed one."
+    )]
+    pub any_major: bool,
+    #[arg(long, help = "Remove the old tags after upgrading.")]
+    pub remove_old: bool,
+    #[arg(
+        long,
+        help = "Print the release each tag would be upgraded to, with its URL and hash, without downloading."
+    )]
+    pub dry_run: bool,
+}
+
+pub async fn run_outdated(
+    args: OutdatedArgs,
+    tools: &ToolSet,
+    paths: &Paths,
+) -> anyhow::Result<()> {
+    let tool_name = args.tool.command_name();
+    let fn_tool = FindUpgradesFn {
+        tool_name: &tool_name,
+        tools_base: &paths.tool_dir,
+        tags: None,
+        any_major: args.any_major,
+    };
+    let upgrades = async_invoke_tool(tools, &args.tool, &fn_tool).await?;
+    if upgrades.is_empty() {
+        log::info!("All tags are up to date");
+    }
+    for (installed, latest) in upgrades {
+        println!(
+            "{}: {} -> {}",
+            installed.tag, installed.info.version, latest.version
+        );
+    }
+    Ok(())
+}
+
+pub async fn run_upgrade(
+    args: UpgradeArgs,
+    tools: &ToolSet,
+    client: &HttpClient,
+    paths: &Paths,
+) -> anyhow::Result<()> {
+    let tool_name = args.tool.command_name();
+    let tags = args.tags.into_iter().map(SmolStr::from).collect::<Vec<_>>();
+    let fn_tool = FindUpgradesFn {
+        tool_name: &tool_name,
+        tools_base: &paths.tool_dir,
+        tags: (!args.all).then_some(&tags),
+        any_major: args.any_major,
+    };
+    let upgrades = async_invoke_tool(tools, &args.tool, &fn_tool).await?;
+    if upgrades.is_empty() {
+        log::info!("All tags are up to date");
+    }
+
+    for (installed, latest) in upgrades {
+        let fn_tool = InstallUpgradeFn {
+            tool_name: &tool_name,
+            client,
+            tools_base: &paths.tool_dir,
+            pinned_hashes: tools.pinned_hashes.as_ref(),
+            installed: &installed,
+            latest,
+            dry_run: args.dry_run,
+        };
+        let (new_tag, newly_installed) = async_invoke_tool(tools, &args.tool, &fn_tool).await?;
+        if args.dry_run {
+            println!("would upgrade: {} -> {new_tag}", installed.tag);
+            continue;
+        }
+        if newly_installed {
+            finish_install(tools, &args.tool, &new_tag, &TagOptions::default(), paths).await?;
+        }
+        println!("upgraded: {} -> {new_tag}", installed.tag);
+
+        let aliases = general_tool::retarget_aliases(
+            &tool_name,
+            &paths.tool_dir,
+            &installed.tag,
+            new_tag.clone(),
+        )
+        .await?;
+        for alias in aliases {
+            println!("aliased: {alias} -> {new_tag}");
+        }
+        if args.remove_old {
+            general_tool::remove_tag(
+                &tool_name,
+                &paths.tool_dir,
+                vec![installed.tag.clone()],
+                false,
+            )
+            .await?;
+            println!("removed: {}", installed.tag);
+        }
+    }
+    Ok(())
+}
+
+/// Finds the installed tags with newer releases, among `tags` if given.
+struct FindUpgradesFn<'a> {
+    tool_name: &'a str,
+    tools_base: &'a Path,
+    tags: Option<&'a Vec<SmolStr>>,
+    any_major: bool,
+}
+
+impl AsyncFnTool for FindUpgradesFn<'_> {
+    type Output = anyhow::Result<Vec<(InstalledTag, Version)>>;
+
+    async fn invoke(&self, tool: &impl GeneralTool) -> Self::Output {
+        let mut installed_tags =
+            general_tool::installed_tags(self.tool_name, tool, self.tools_base).await?;
+        if let Some(tags) = self.tags {
+            for tag in tags {
+                if !installed_tags.iter().any(|installed| installed.tag == *tag) {
+                    return Err(AvmError::TagNotFound { tag: tag.clone() }.into());
+                }
+            }
+            installed_tags.retain(|installed| tags.contains(&installed.tag));
+        }
+
+        let mut upgrades = Vec::new();
+        for installed in installed_tags {
+            if let Some(latest) =
+                general_tool::find_upgrade(tool, &installed, self.any_major).await?
+            {
+                upgrades.push((installed, latest));
+            }
+        }
+        Ok(upgrades)
+    }
+}
+
+/// Installs the newer release, returning its tag and whether it was newly installed. A tag
+/// already installed for it is reused. On a dry run, only prints what would be installed.
+struct InstallUpgradeFn<'a> {
+    tool_name: &'a str,
+    client: &'a HttpClient,
+    tools_base: &'a Path,
+    pinned_hashes: Option<&'a PinnedHashes>,
+    installed: &'a InstalledTag,
+    latest: Version,
+    dry_run: bool,
+}
+
+impl AsyncFnTool for InstallUpgradeFn<'_> {
+    type Output = anyhow::Result<(SmolStr, bool)>;
+
+    async fn invoke(&self, tool: &impl GeneralTool) -> Self::Output {
+        let install_args = general_tool::InstallArgs {
+            tool_name: self.tool_name,
+            tool,
+            client: self.client,
+            tools_base: self.tools_base,
+            platform: self.installed.info.platform.clone(),
+            flavor: self.installed.info.flavor.clone(),
+            install_version: VersionFilter {
+                include_prerelease: true,
+                exact_version: Some(self.latest.version.clone()),
+                ..Default::default()
+            },
+            update: false,
+            force: false,
+            default: false,
+            keep_partial: false,
+            stream_extract: false,
+            platform_fallback: false,
+            pinned_hashes: self.pinned_hashes,
+            cancel: any_version_manager::cancellation_token(),
+        };
+        if self.dry_run {
+            let plan = install_args.plan().await?;
+            let overwrites = if plan.exists {
+                "no, the installed tag is reused"
+            } else {
+                "no"
+            };
+            print_install_plan(&plan, overwrites);
+            return Ok((plan.tag, false));
+        }
+        match install_args.install().await {
+            Ok((new_tag, download_url, download_state)) => {
+                drive_download_state(new_tag.clone(), download_url, download_state).await?;
+                Ok((new_tag, true))
+            }
+            Err(AvmError::TagExists { tag }) => Ok((tag, false)),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
@@ -0,0 +1,283 @@
+//! Bundles of release archives for several platforms with what they were resolved to, written
+//! by `avm bundle` on a connected machine and installed by `avm install --from-bundle`
+//! without network access, such as in an air-gapped network.
+//!
+//! A bundle is a tar file holding the archives under `archives/<tag>/`, with the companion
+//! archives under `archives/<tag>/companions/`, and the [`BundleManifest`] as
+//! [`MANIFEST_FILE`].
+
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+
+use anyhow::Context as _;
+use serde::{Deserialize, Serialize};
+use smol_str::SmolStr;
+
+use crate::signature::DetachedSignature;
+use crate::tool::CompanionArchive;
+use crate::{CancellationToken, FileHash, HttpClient};
+
+pub const MANIFEST_FILE: &str = "avm-bundle.toml";
+const ARCHIVES_DIR: &str = "archives";
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BundleManifest {
+    pub tool: SmolStr,
+    #[serde(rename = "release", default)]
+    pub releases: Vec<BundledRelease>,
+}
+
+/// A release in a bundle, resolved like an install.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BundledRelease {
+    pub tag: SmolStr,
+    pub version: SmolStr,
+    #[serde(rename = "lts", default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_lts: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub platform: Option<SmolStr>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flavor: Option<SmolStr>,
+    /// Where the archive was downloaded from, before the mirrors.
+    pub url: SmolStr,
+    /// The vendor hash of the archive, or its SHA-256 digest if the vendor has none.
+    pub hash: FileHash,
+    /// The path of the archive in the bundle.
+    #[serde(default)]
+    pub file: String,
+    /// The detached signature of the archive, for tools that sign their archives.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signature: Option<BundledSignature>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub companions: Vec<BundledCompanion>,
+}
+
+/// The detached signature of a bundled archive with the public keys to verify it. The keys are
+/// only trusted if they have the fingerprints the tool pins.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BundledSignature {
+    pub url: SmolStr,
+    /// Base64 of the signature.
+    pub signature: String,
+    /// Base64 of the armored public keys.
+    pub keys: String,
+}
+
+impl BundledSignature {
+    pub fn new(signature: &DetachedSignature) -> Self {
+        use base64::Engine as _;
+
+        let base64 = base64::engine::general_purpose::STANDARD;
+        Self {
+            url: signature.url.clone(),
+            signature: base64.encode(&signature.signature),
+            keys: base64.encode(&signature.keys),
+        }
+    }
+
+    /// The signature to verify the archive with, made by a key with one of `fingerprints`.
+    pub fn to_detached(&self, fingerprints: &[&'static str]) -> anyhow::Result<DetachedSignature> {
+        use base64::Engine as _;
+
+        let base64 = base64::engine::general_purpose::STANDARD;
+        Ok(DetachedSignature {
+            url: self.url.clone(),
+            signature: base64
+                .decode(&self.signature)
+                .with_context(|| format!("Invalid bundled signature '{}'", self.url))?,
+            keys: base64
+                .decode(&self.keys)
+                .with_context(|| format!("Invalid bundled keys of '{}'", self.url))?,
+            fingerprints: fingerprints
+                .iter()
+                .copied()
+                .map(SmolStr::new_static)
+                .collect(),
+        })
+    }
+}
+
+/// A companion archive installed with a bundled release, see
+/// [`crate::tool::GeneralTool::companion_archives`].
+#[derive(Clone, Serialize, Deserialize)]
+pub struct BundledCompanion {
+    pub url: SmolStr,
+    pub hash: FileHash,
+    pub exe: SmolStr,
+    /// The path of the archive in the bundle.
+    #[serde(default)]
+    pub file: String,
+}
+
+impl From<CompanionArchive> for BundledCompanion {
+    fn from(companion: CompanionArchive) -> Self {
+        Self {
+            url: companion.url,
+            hash: companion.hash,
+            exe: companion.exe,
+            file: String::new(),
+        }
+    }
+}
+
+impl BundledCompanion {
+    pub fn to_archive(&self) -> CompanionArchive {
+        CompanionArchive {
+            url: self.url.clone(),
+            hash: self.hash.clone(),
+            exe: self.exe.clone(),
+        }
+    }
+}
+
+/// The name of the archive at `url`, the last segment of its URL.
+fn file_name(url: &str) -> String {
+    reqwest::Url::parse(url)
+        .ok()
+        .and_then(|url| {
+            url.path_segments()?
+                .next_back()
+                .filter(|name| !name.is_empty())
+                .map(str::to_owned)
+        })
+        .unwrap_or_else(|| "archive".to_owned())
+}
+
+/// Downloads the archives of `releases` of `tool` and writes them with the manifest to the
+/// bundle at `output`, which is only created once every archive is verified.
+pub async fn write(
+    client: &HttpClient,
+    tool: &str,
+    releases: Vec<BundledRelease>,
+    output: &Path,
+    cancel: &CancellationToken,
+) -> crate::Result<()> {
+    let file_name = output
+        .file_name()
+        .ok_or_else(|| anyhow::anyhow!("Invalid bundle path '{}'", output.display()))?;
+    let work_dir = output.with_file_name(format!(".{}.partial", file_name.to_string_lossy()));
+    crate::spawn_blocking({
+        let work_dir = work_dir.clone();
+        move || Ok(std::fs::create_dir_all(work_dir)?)
+    })
+    .await?;
+    let result = write_in(client, tool, releases, output, &work_dir, cancel).await;
+    crate::spawn_blocking(move || Ok(std::fs::remove_dir_all(work_dir)?))
+        .await
+        .ok();
+    result
+}
+
+async fn write_in(
+    client: &HttpClient,
+    tool: &str,
+    releases: Vec<BundledRelease>,
+    output: &Path,
+    work_dir: &Path,
+    cancel: &CancellationToken,
+) -> crate::Result<()> {
+    let client = client.for_tool(tool);
+    let mut manifest = BundleManifest {
+        tool: tool.into(),
+        releases: Vec::with_capacity(releases.len()),
+    };
+    let mut downloads = Vec::with_capacity(releases.len());
+    for (i, mut release) in releases.into_iter().enumerate() {
+        log::info!("Downloading {} for \"{}\"", release.url, release.tag);
+        let download = work_dir.join(i.to_string());
+        let digest =
+            crate::io::download_verified(&client, &release.url, &release.hash, &download, cancel)
+                .await?;
+        if release.hash.is_empty() {
+            release.hash = digest;
+        }
+        release.file = format!("{ARCHIVES_DIR}/{}/{}", release.tag, file_name(&release.url));
+        downloads.push((download, release.file.clone()));
+        for (j, companion) in release.companions.iter_mut().enumerate() {
+            log::info!("Downloading {} for \"{}\"", companion.url, release.tag);
+            let download = work_dir.join(format!("{i}-{j}"));
+            let digest = crate::io::download_verified(
+                &client,
+                &companion.url,
+                &companion.hash,
+                &download,
+                cancel,
+            )
+            .await?;
+            if companion.hash.is_empty() {
+                companion.hash = digest;
+            }
+            companion.file = format!(
+                "{ARCHIVES_DIR}/{}/companions/{}",
+                release.tag,
+                file_name(&companion.url)
+            );
+            downloads.push((download, companion.file.clone()));
+        }
+        manifest.releases.push(release);
+    }
+
+    let manifest = toml::to_string(&manifest).map_err(anyhow::Error::from)?;
+    let (output, partial) = (output.to_path_buf(), work_dir.join("bundle.tar"));
+    crate::spawn_blocking(move || {
+        let mut builder = tar::Builder::new(BufWriter::new(std::fs::File::create(&partial)?));
+        let mut header = tar::Header::new_gnu();
+        header.set_size(manifest.len() as u64);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append_data(&mut header, MANIFEST_FILE, manifest.as_bytes())?;
+        for (download, file) in downloads {
+            builder.append_path_with_name(&download, &file)?;
+        }
+        builder
+            .into_inner()?
+            .into_inner()
+            .map_err(|err| err.into_error())?;
+        std::fs::rename(&partial, &output)
+            .with_context(|| format!("Failed to write '{}'", output.display()))?;
+        Ok(())
+    })
+    .await?;
+    Ok(())
+}
+
+/// Reads the manifest of the bundle at `path`.
+pub async fn read_manifest(path: &Path) -> crate::Result<BundleManifest> {
+    let path = path.to_path_buf();
+    Ok(crate::spawn_blocking(move || {
+        let mut archive = open(&path)?;
+        for entry in archive.entries()? {
+            let mut entry = entry?;
+            if *entry.path()? == *Path::new(MANIFEST_FILE) {
+                let mut content = String::new();
+                std::io::Read::read_to_string(&mut entry, &mut content)?;
+                return toml::from_str(&content)
+                    .with_context(|| format!("Invalid {MANIFEST_FILE} in '{}'", path.display()));
+            }
+        }
+        anyhow::bail!(
+            "'{}' is not a bundle written by `avm bundle`",
+            path.display()
+        )
+    })
+    .await?)
+}
+
+/// Copies the archive at `file` in the bundle at `path` to `dest`.
+pub(crate) fn extract_archive_blocking(path: &Path, file: &str, dest: &Path) -> anyhow::Result<()> {
+    let mut archive = open(path)?;
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if *entry.path()? == *Path::new(file) {
+            std::io::copy(&mut entry, &mut std::fs::File::create(dest)?)?;
+            return Ok(());
+        }
+    }
+    anyhow::bail!("No '{file}' in the bundle '{}'", path.display())
+}
+
+fn open(path: &Path) -> anyhow::Result<tar::Archive<BufReader<std::fs::File>>> {
+    let file = std::fs::File::open(path)
+        .with_context(|| format!("Failed to open the bundle '{}'", path.display()))?;
+    Ok(tar::Archive::new(BufReader::new(file)))
+}
@@ -0,0 +1,281 @@
+//! A cache of downloaded archives shared by every tool and addressed by their SHA-256
+//! digest. Installing an artifact that was downloaded before, for another tag or after
+//! removing the first one, copies the cached archive instead of downloading it again.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::SystemTime;
+
+use crate::io::blocking;
+use crate::{CancellationToken, FileHash};
+
+/// The directory of the entries under the cache directory, named after their digest.
+pub const DIR: &str = "sha256";
+
+/// The files being written to the cache start with this prefix.
+const PARTIAL_PREFIX: &str = ".partial-";
+
+/// Tells apart the files written at the same time by one process.
+static NEXT_PARTIAL: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone)]
+pub struct DownloadCache {
+    dir: PathBuf,
+    /// The least recently used entries are removed once the cache grows beyond this size.
+    max_size: u64,
+}
+
+impl DownloadCache {
+    pub const DEFAULT_MAX_SIZE: u64 = 2 << 30;
+
+    pub fn new(cache_dir: &Path, max_size: u64) -> Self {
+        Self {
+            dir: cache_dir.join(DIR),
+            max_size,
+        }
+    }
+
+    /// The entry of an archive with the SHA-256 digest, `None` if it is not a digest.
+    fn entry_path(&self, sha256: &str) -> Option<PathBuf> {
+        let sha256 = sha256.to_ascii_lowercase();
+        (sha256.len() == 64 && sha256.bytes().all(|b| b.is_ascii_hexdigit()))
+            .then(|| self.dir.join(sha256))
+    }
+
+    /// Copies the cached archive matching `expected` to `dest` and returns its digests.
+    /// `None` if `expected` has no SHA-256 digest or no archive matches it. An entry that
+    /// no longer matches its digest is removed.
+    pub(crate) fn fetch_blocking(
+        &self,
+        expected: &FileHash,
+        dest: &Path,
+        cancel: &CancellationToken,
+    ) -> anyhow::Result<Option<FileHash>> {
+        let Some(path) = expected
+            .sha256
+            .as_deref()
+            .and_then(|sha256| self.entry_path(sha256))
+        else {
+            return Ok(None);
+        };
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let digest = blocking::hash_file(expected, &path, cancel)?;
+        if crate::io::verify_digest(expected, &digest).is_err() {
+            log::warn!("Removing the corrupted cache entry '{}'", path.display());
+            std::fs::remove_file(&path)?;
+            return Ok(None);
+        }
+        // Copied rather than linked, since the installed files must not share the entry.
+        std::fs::copy(&path, dest)?;
+        // The entries are evicted by the time they were last used.
+        std::fs::File::options()
+            .append(true)
+            .open(&path)?
+            .set_modified(SystemTime::now())?;
+        Ok(Some(digest))
+    }
+
+    /// Restores the archive matching `expected` to `dest` from the zstd patch at `patch`, made
+    /// from the cached archive matching `base`, and returns its digests. The restored archive
+    /// is added to the cache. `None` if `base` is not cached.
+    pub(crate) fn patch_blocking(
+        &self,
+        base: &FileHash,
+        patch: &Path,
+        expected: &FileHash,
+        dest: &Path,
+        cancel: &CancellationToken,
+    ) -> anyhow::Result<Option<FileHash>> {
+        let Some(base_path) = base
+            .sha256
+            .as_deref()
+            .and_then(|sha256| self.entry_path(sha256))
+            .filter(|path| path.is_file())
+        else {
+            return Ok(None);
+        };
+        blocking::apply_zstd_patch(&base_path, patch, dest, cancel)?;
+        let digest = blocking::hash_file(expected, dest, cancel)?;
+        crate::io::verify_digest(expected, &digest)?;
+        if let Some(sha256) = &expected.sha256 {
+            self.store_blocking(sha256, dest)?;
+        }
+        Ok(Some(digest))
+    }
+
+    /// Adds the verified archive at `archive_path` with the SHA-256 digest, then trims the
+    /// cache to its maximum size.
+    pub(crate) fn store_blocking(&self, sha256: &str, archive_path: &Path) -> anyhow::Result<()> {
+        let Some(path) = self.entry_path(sha256) else {
+            anyhow::bail!("Invalid SHA-256 digest \"{sha256}\"");
+        };
+        if !path.exists() {
+            std::fs::create_dir_all(&self.dir)?;
+            let partial = self.dir.join(format!(
+                "{PARTIAL_PREFIX}{}-{}",
+                std::process::id(),
+                NEXT_PARTIAL.fetch_add(1, Ordering::Relaxed)
+            ));
+            std::fs::copy(archive_path, &partial)?;
+            std::fs::rename(&partial, &path)?;
+        }
+        self.evict_blocking()
+    }
+
+    /// Removes the least recently used entries until the cache fits in its maximum size.
+    fn evict_blocking(&self) -> anyhow::Result<()> {
+        let mut entries = Vec::new();
+        for entry in std::fs::read_dir(&self.dir)? {
+            let entry = entry?;
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let metadata = entry.metadata()?;
+            if metadata.is_file() {
+                entries.push((metadata.modified()?, metadata.len(), entry.path()));
+            }
+        }
+        let mut size = entries.iter().map(|(_, len, _)| len).sum::<u64>();
+        entries.sort();
+        for (_, len, path) in entries {
+            if size <= self.max_size {
+                break;
+            }
+            log::debug!("Evicting '{}' from the download cache", path.display());
+            std::fs::remove_file(&path)?;
+            size -= len;
+        }
+        Ok(())
+    }
+}
+
+/// The size of the cached archives under the cache directory. With `remove`, they are
+/// removed as well.
+pub async fn clear(cache_dir: &Path, remove: bool) -> crate::Result<u64> {
+    let dir = cache_dir.join(DIR);
+    Ok(crate::spawn_blocking(move || {
+        let size = match blocking::dir_size(&dir) {
+            Ok(size) => size,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+            Err(err) => return Err(err.into()),
+        };
+        if remove {
+            std::fs::remove_dir_all(&dir)?;
+        }
+        Ok(size)
+    })
+    .await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sha256_of(content: &[u8]) -> String {
+        use sha2::Digest;
+
+        hex::encode(sha2::Sha256::digest(content))
+    }
+
+    #[test]
+    fn fetches_stored_archives_and_drops_corrupted_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DownloadCache::new(dir.path(), DownloadCache::DEFAULT_MAX_SIZE);
+        let cancel = CancellationToken::new();
+        let archive = dir.path().join("archive");
+        std::fs::write(&archive, b"archive").unwrap();
+        let sha256 = sha256_of(b"archive");
+        let expected = FileHash::from_sha256(sha256.to_ascii_uppercase().into());
+        let dest = dir.path().join("dest");
+
+        assert!(cache
+            .fetch_blocking(&expected, &dest, &cancel)
+            .unwrap()
+            .is_none());
+        cache.store_blocking(&sha256, &archive).unwrap();
+        let digest = cache.fetch_blocking(&expected, &dest, &cancel).unwrap();
+        assert_eq!(digest.unwrap().sha256.as_deref(), Some(sha256.as_str()));
+        assert_eq!(std::fs::read(&dest).unwrap(), b"archive");
+
+        std::fs::write(dir.path().join(DIR).join(&sha256), b"tampered").unwrap();
+        assert!(cache
+            .fetch_blocking(&expected, &dest, &cancel)
+            .unwrap()
+            .is_none());
+        assert!(!dir.path().join(DIR).join(&sha256).exists());
+    }
+
+    #[test]
+    fn restores_archives_from_patches_of_cached_ones() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DownloadCache::new(dir.path(), DownloadCache::DEFAULT_MAX_SIZE);
+        let cancel = CancellationToken::new();
+        let old: Vec<u8> = (0..100_000_u32)
+            .flat_map(|i| (i % 251).to_le_bytes())
+            .collect();
+        let mut new = old.clone();
+        new[1000..1010].copy_from_slice(b"new bytes!");
+        let archive = dir.path().join("archive");
+        std::fs::write(&archive, &old).unwrap();
+        let base = FileHash::from_sha256(sha256_of(&old).into());
+        let expected = FileHash::from_sha256(sha256_of(&new).into());
+
+        let mut encoder =
+            zstd::stream::write::Encoder::with_ref_prefix(Vec::new(), 3, &old).unwrap();
+        std::io::Write::write_all(&mut encoder, &new).unwrap();
+        let patch_content = encoder.finish().unwrap();
+        assert!(patch_content.len() < new.len() / 10);
+        let patch = dir.path().join("patch");
+        std::fs::write(&patch, patch_content).unwrap();
+        let dest = dir.path().join("dest");
+
+        assert!(cache
+            .patch_blocking(&base, &patch, &expected, &dest, &cancel)
+            .unwrap()
+            .is_none());
+        cache
+            .store_blocking(base.sha256.as_deref().unwrap(), &archive)
+            .unwrap();
+        assert!(cache
+            .patch_blocking(&base, &patch, &expected, &dest, &cancel)
+            .unwrap()
+            .is_some());
+        assert_eq!(std::fs::read(&dest).unwrap(), new);
+        // The restored archive is a base for the next patch.
+        assert!(dir.path().join(DIR).join(sha256_of(&new)).is_file());
+
+        // A patch restoring something else is rejected.
+        assert!(cache
+            .patch_blocking(&base, &patch, &base, &dest, &cancel)
+            .is_err());
+    }
+
+    #[test]
+    fn evicts_least_recently_used_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DownloadCache::new(dir.path(), 10);
+        let mut digests = Vec::new();
+        for content in [b"first1", b"second", b"third3"] {
+            let archive = dir.path().join("archive");
+            std::fs::write(&archive, content).unwrap();
+            let sha256 = sha256_of(content);
+            cache.store_blocking(&sha256, &archive).unwrap();
+            // Older modification times for the earlier entries.
+            let entry = dir.path().join(DIR).join(&sha256);
+            let age = std::time::Duration::from_secs(100 - digests.len() as u64);
+            std::fs::File::options()
+                .append(true)
+                .open(&entry)
+                .unwrap()
+                .set_modified(SystemTime::now() - age)
+                .unwrap();
+            digests.push(sha256);
+        }
+        let cached = |sha256: &String| dir.path().join(DIR).join(sha256).exists();
+        assert!(!cached(&digests[0]));
+        assert!(!cached(&digests[1]));
+        assert!(cached(&digests[2]));
+    }
+}
@@ -0,0 +1,187 @@
+use smol_str::SmolStr;
+use std::path::PathBuf;
+
+pub type Result<T, E = AvmError> = std::result::Result<T, E>;
+
+/// Errors returned by the library API. Failures without a dedicated variant are kept as
+/// [`AvmError::Other`] with their context chain.
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum AvmError {
+    #[error(transparent)]
+    Network(#[from] reqwest::Error),
+    #[error("Failed to download '{url}': {status}\n{body}")]
+    HttpStatus {
+        url: String,
+        status: reqwest::StatusCode,
+        body: String,
+    },
+    #[error("\"{tag}\" already exists")]
+    TagExists { tag: SmolStr },
+    #[error("Tag \"{tag}\" not found")]
+    TagNotFound { tag: SmolStr },
+    #[error("Tag \"{tag}\" is reserved for temporary use")]
+    TagReserved { tag: SmolStr },
+    #[error("Invalid tag \"{tag}\": {reason}")]
+    TagInvalid {
+        tag: SmolStr,
+        reason: crate::TagIsNotValid,
+    },
+    #[error("\"{tag}\" is being operated. If no other avm process is running, it was left by an interrupted one, remove '{}' with `avm clean`", tmp_dir.display())]
+    TagBusy { tag: SmolStr, tmp_dir: PathBuf },
+    #[error("Another avm process is changing '{}'", tool_dir.display())]
+    ToolBusy { tool_dir: PathBuf },
+    #[error("Tag \"{tag}\" is an alias target of \"{alias}\", remove the alias first")]
+    AliasTarget { tag: SmolStr, alias: SmolStr },
+    #[error("{algorithm} verification failed, expected {expected}, got {actual}")]
+    HashMismatch {
+        algorithm: &'static str,
+        expected: SmolStr,
+        actual: SmolStr,
+    },
+    /// A downloaded archive doesn't match its expected hash.
+    #[error("{0}")]
+    DownloadHashMismatch(Box<DownloadMismatch>),
+    #[error("The signature '{url}' doesn't verify the downloaded archive:\n{reason}")]
+    SignatureMismatch { url: SmolStr, reason: String },
+    /// The vendor checksum of a release differs from the one in the pinned hashes.
+    #[error("The vendor {algorithm} of {tool} {version} is {vendor}, which differs from the pinned {pinned}")]
+    PinnedHashMismatch {
+        tool: SmolStr,
+        version: SmolStr,
+        algorithm: &'static str,
+        pinned: SmolStr,
+        vendor: SmolStr,
+    },
+    #[error("Platform is required")]
+    PlatformRequired,
+    #[error("Unsupported platform \"{platform}\". Supported platforms: {}", supported.join(", "))]
+    UnsupportedPlatform {
+        platform: SmolStr,
+        supported: Vec<SmolStr>,
+    },
+    #[error("Unsupported flavor \"{flavor}\". Supported flavors: {}", supported.join(", "))]
+    UnsupportedFlavor {
+        flavor: SmolStr,
+        supported: Vec<SmolStr>,
+    },
+    #[error("Flavor \"{flavor}\" is not available for platform \"{platform}\".\n\nSupported platform/flavor matrix:{}", format_matrix(matrix))]
+    UnsupportedCombination {
+        platform: SmolStr,
+        flavor: SmolStr,
+        matrix: Vec<(SmolStr, Vec<SmolStr>)>,
+    },
+    #[error("Unknown executable \"{name}\". Available executables: {}", available.join(", "))]
+    UnknownExecutable {
+        name: SmolStr,
+        available: Vec<SmolStr>,
+    },
+    #[error("No download URL found.")]
+    VersionNotFound,
+    #[error("Operation cancelled")]
+    Cancelled,
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error(transparent)]
+    Other(anyhow::Error),
+}
+
+fn format_matrix(matrix: &[(SmolStr, Vec<SmolStr>)]) -> String {
+    matrix
+        .iter()
+        .map(|(platform, flavors)| format!("\n  {}: {}", platform, flavors.join(", ")))
+        .collect()
+}
+
+/// What was downloaded when an archive doesn't match its expected hash.
+#[derive(Debug)]
+pub struct DownloadMismatch {
+    pub url: SmolStr,
+    /// The URL requested instead, if a mirror applies.
+    pub mirrored_url: Option<SmolStr>,
+    pub algorithm: &'static str,
+    pub expected: SmolStr,
+    pub actual: SmolStr,
+    pub downloaded_size: u64,
+    /// The size announced by the server.
+    pub content_length: Option<u64>,
+    /// The `from` of the mirror entry, if the direct URL serves the expected file.
+    pub blamed_mirror: Option<SmolStr>,
+}
+
+impl std::fmt::Display for DownloadMismatch {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} verification failed for '{}'\n  expected: {}\n  actual:   {}",
+            self.algorithm, self.url, self.expected, self.actual
+        )?;
+        if let Some(mirrored_url) = &self.mirrored_url {
+            write!(f, "\n  requested through a mirror as '{mirrored_url}'")?;
+        }
+        write!(f, "\n  downloaded {} bytes", self.downloaded_size)?;
+        match self.content_length {
+            Some(length) if length != self.downloaded_size => {
+                write!(f, ", but the server announced {length}")?
+            }
+            Some(_) => write!(f, ", as announced by the server")?,
+            None => {}
+        }
+        if let Some(mirror) = &self.blamed_mirror {
+            write!(
+                f,
+                "\nThe direct URL serves the expected file, the mirror of \"{mirror}\" serves a different one. Fix or remove it in the config."
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl From<anyhow::Error> for AvmError {
+    /// Recovers the typed error if one was raised in code that uses `anyhow` internally.
+    fn from(err: anyhow::Error) -> Self {
+        match err.downcast::<AvmError>() {
+            Ok(err) => err,
+            Err(err) => match err.downcast::<reqwest::Error>() {
+                Ok(err) => AvmError::Network(err),
+                Err(err) => AvmError::Other(err),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn typed_error_survives_anyhow() {
+        let err = anyhow::Error::from(AvmError::TagNotFound { tag: "a".into() });
+        assert!(matches!(
+            AvmError::from(err),
+            AvmError::TagNotFound { tag } if tag == "a"
+        ));
+
+        let err = AvmError::from(anyhow::anyhow!("plain"));
+        assert!(matches!(err, AvmError::Other(_)));
+        assert_eq!(err.to_string(), "plain");
+    }
+
+    #[test]
+    fn download_mismatch_reports_the_download() {
+        let err = AvmError::DownloadHashMismatch(Box::new(DownloadMismatch {
+            url: "https://example.com/a.tar.gz".into(),
+            mirrored_url: Some("https://mirror.example.com/a.tar.gz".into()),
+            algorithm: "Sha256",
+            expected: "aa".into(),
+            actual: "bb".into(),
+            downloaded_size: 10,
+            content_length: Some(20),
+            blamed_mirror: Some("https://example.com/".into()),
+        }));
+        assert_eq!(
+            err.to_string(),
+            "Sha256 verification failed for 'https://example.com/a.tar.gz'\n  expected: aa\n  actual:   bb\n  requested through a mirror as 'https://mirror.example.com/a.tar.gz'\n  downloaded 10 bytes, but the server announced 20\nThe direct URL serves the expected file, the mirror of \"https://example.com/\" serves a different one. Fix or remove it in the config."
+        );
+    }
+}
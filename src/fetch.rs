@@ -0,0 +1,95 @@
+//! Fetchers for URL schemes other than HTTP, such as the object stores behind `s3://` and
+//! `gs://` URLs. [`HttpClient`](crate::HttpClient) sends the requests for those URLs,
+//! including the ones rewritten by mirrors and redirects, through the fetcher registered for
+//! their scheme, so that archive downloads and metadata requests work with them alike.
+
+mod gcs;
+mod s3;
+
+use async_trait::async_trait;
+
+pub use gcs::GcsFetcher;
+pub use s3::S3Fetcher;
+
+#[async_trait]
+pub trait Fetcher: Send + Sync {
+    /// Sends `request`, whose URL has the scheme the fetcher is registered for, with `http`.
+    async fn send(
+        &self,
+        http: &reqwest::Client,
+        request: reqwest::Request,
+    ) -> anyhow::Result<reqwest::Response>;
+}
+
+/// The bucket and the object key of an object store URL, like `s3://bucket/path/to/key`.
+fn bucket_and_key(url: &reqwest::Url) -> anyhow::Result<(String, String)> {
+    let bucket = url
+        .host_str()
+        .filter(|bucket| !bucket.is_empty())
+        .ok_or_else(|| anyhow::anyhow!("No bucket in '{url}'"))?;
+    let key = percent_decode(url.path().trim_start_matches('/'));
+    if key.is_empty() {
+        anyhow::bail!("No object key in '{url}'");
+    }
+    Ok((bucket.to_owned(), key))
+}
+
+/// Percent-encodes everything but the unreserved characters of RFC 3986 and, with
+/// `keep_slash`, `/`.
+fn percent_encode(s: &str, keep_slash: bool) -> String {
+    let mut encoded = String::with_capacity(s.len());
+    for byte in s.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if keep_slash => encoded.push('/'),
+            byte => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
+
+fn percent_decode(s: &str) -> String {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// The value of an environment variable, `None` if it is unset or empty.
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_and_encodes_object_urls() {
+        let url = reqwest::Url::parse("s3://tools/go/go 1.22.tar.gz").unwrap();
+        let (bucket, key) = bucket_and_key(&url).unwrap();
+        assert_eq!(bucket, "tools");
+        assert_eq!(key, "go/go 1.22.tar.gz");
+        assert_eq!(percent_encode(&key, true), "go/go%201.22.tar.gz");
+        assert_eq!(percent_encode(&key, false), "go%2Fgo%201.22.tar.gz");
+        assert!(bucket_and_key(&reqwest::Url::parse("s3://tools/").unwrap()).is_err());
+    }
+}
@@ -0,0 +1,211 @@
+//! `gs://bucket/key` URLs, fetched from Google Cloud Storage with an OAuth 2.0 access token.
+//!
+//! The token is `GOOGLE_OAUTH_ACCESS_TOKEN`, such as the output of `gcloud auth
+//! print-access-token`, or is obtained with the user credentials of
+//! `GOOGLE_APPLICATION_CREDENTIALS` or of `gcloud auth application-default login`, or from the
+//! metadata server on Google Cloud. Without any of them the requests are anonymous, for public
+//! buckets. `STORAGE_EMULATOR_HOST` replaces the endpoint, such as with an emulator.
+
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use anyhow::Context as _;
+use async_trait::async_trait;
+use serde::Deserialize;
+
+use super::{bucket_and_key, env_var, percent_encode, Fetcher};
+
+const ENDPOINT: &str = "https://storage.googleapis.com";
+const TOKEN_URL: &str = "https://oauth2.googleapis.com/token";
+const METADATA_HOST: &str = "metadata.google.internal";
+const METADATA_TIMEOUT: Duration = Duration::from_secs(2);
+/// Tokens are obtained again this long before they expire.
+const EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+
+#[derive(Default)]
+pub struct GcsFetcher {
+    /// The token obtained last, shared by the requests until it expires.
+    token: tokio::sync::Mutex<Option<CachedToken>>,
+}
+
+enum CachedToken {
+    Anonymous,
+    Token { token: String, expires: Instant },
+}
+
+#[async_trait]
+impl Fetcher for GcsFetcher {
+    async fn send(
+        &self,
+        http: &reqwest::Client,
+        mut request: reqwest::Request,
+    ) -> anyhow::Result<reqwest::Response> {
+        let (bucket, key) = bucket_and_key(request.url())?;
+        let endpoint = match env_var("STORAGE_EMULATOR_HOST") {
+            Some(host) if host.contains("://") => host,
+            Some(host) => format!("http://{host}"),
+            None => ENDPOINT.to_owned(),
+        };
+        let url = format!(
+            "{}/download/storage/v1/b/{bucket}/o/{}?alt=media",
+            endpoint.trim_end_matches('/'),
+            percent_encode(&key, false)
+        );
+        log::debug!("Fetching {} from {url}", request.url());
+        *request.url_mut() =
+            reqwest::Url::parse(&url).with_context(|| format!("Invalid GCS URL '{url}'"))?;
+        if let Some(token) = self.token(http).await? {
+            let mut authorization =
+                reqwest::header::HeaderValue::from_str(&format!("Bearer {token}"))?;
+            authorization.set_sensitive(true);
+            request
+                .headers_mut()
+                .insert(reqwest::header::AUTHORIZATION, authorization);
+        }
+        Ok(http.execute(request).await?)
+    }
+}
+
+impl GcsFetcher {
+    /// The access token to send, `None` for anonymous requests.
+    async fn token(&self, http: &reqwest::Client) -> anyhow::Result<Option<String>> {
+        if let Some(token) = env_var("GOOGLE_OAUTH_ACCESS_TOKEN") {
+            return Ok(Some(token));
+        }
+        let mut cached = self.token.lock().await;
+        match &*cached {
+            Some(CachedToken::Anonymous) => return Ok(None),
+            Some(CachedToken::Token { token, expires }) if *expires > Instant::now() => {
+                return Ok(Some(token.clone()));
+            }
+            _ => {}
+        }
+        let response = match application_credentials_path() {
+            Some(path) if path.is_file() => Some(refresh_user_token(http, path).await?),
+            _ => metadata_token(http).await,
+        };
+        let token = response
+            .as_ref()
+            .map(|response| response.access_token.clone());
+        *cached = Some(match response {
+            Some(response) => CachedToken::Token {
+                token: response.access_token,
+                expires: Instant::now()
+                    + Duration::from_secs(response.expires_in).saturating_sub(EXPIRY_MARGIN),
+            },
+            None => {
+                log::debug!("No Google Cloud credentials found, sending anonymous requests");
+                CachedToken::Anonymous
+            }
+        });
+        Ok(token)
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ApplicationCredentials {
+    AuthorizedUser {
+        client_id: String,
+        client_secret: String,
+        refresh_token: String,
+    },
+    #[serde(other)]
+    Other,
+}
+
+/// `GOOGLE_APPLICATION_CREDENTIALS`, or the file written by `gcloud auth
+/// application-default login`.
+fn application_credentials_path() -> Option<PathBuf> {
+    const FILE: &str = "application_default_credentials.json";
+    if let Some(path) = env_var("GOOGLE_APPLICATION_CREDENTIALS") {
+        return Some(path.into());
+    }
+    if let Some(dir) = env_var("CLOUDSDK_CONFIG") {
+        return Some(PathBuf::from(dir).join(FILE));
+    }
+    let dirs = directories::BaseDirs::new()?;
+    let config_dir = if cfg!(windows) {
+        dirs.config_dir().to_path_buf()
+    } else {
+        dirs.home_dir().join(".config")
+    };
+    Some(config_dir.join("gcloud").join(FILE))
+}
+
+async fn refresh_user_token(
+    http: &reqwest::Client,
+    path: PathBuf,
+) -> anyhow::Result<TokenResponse> {
+    let content = crate::spawn_blocking({
+        let path = path.clone();
+        move || Ok(std::fs::read(path)?)
+    })
+    .await
+    .with_context(|| path.display().to_string())?;
+    let credentials = serde_json::from_slice(&content)
+        .with_context(|| format!("Invalid Google Cloud credentials '{}'", path.display()))?;
+    let ApplicationCredentials::AuthorizedUser {
+        client_id,
+        client_secret,
+        refresh_token,
+    } = credentials
+    else {
+        anyhow::bail!(
+            "Only user credentials are supported in '{}', set `GOOGLE_OAUTH_ACCESS_TOKEN` instead, for example to the output of `gcloud auth print-access-token`",
+            path.display()
+        );
+    };
+    let form = [
+        ("client_id", client_id.as_str()),
+        ("client_secret", client_secret.as_str()),
+        ("refresh_token", refresh_token.as_str()),
+        ("grant_type", "refresh_token"),
+    ]
+    .iter()
+    .map(|(name, value)| format!("{name}={}", percent_encode(value, false)))
+    .collect::<Vec<_>>()
+    .join("&");
+    let response = http
+        .post(TOKEN_URL)
+        .header(
+            reqwest::header::CONTENT_TYPE,
+            "application/x-www-form-urlencoded",
+        )
+        .body(form)
+        .send()
+        .await?
+        .error_for_status()
+        .context("Failed to obtain a Google Cloud access token")?;
+    Ok(response.json().await?)
+}
+
+/// The token of the service account of the Google Cloud machine, `None` elsewhere.
+async fn metadata_token(http: &reqwest::Client) -> Option<TokenResponse> {
+    let host = env_var("GCE_METADATA_HOST").unwrap_or_else(|| METADATA_HOST.to_owned());
+    let url = format!("http://{host}/computeMetadata/v1/instance/service-accounts/default/token");
+    let result = async {
+        http.get(&url)
+            .header("Metadata-Flavor", "Google")
+            .timeout(METADATA_TIMEOUT)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<TokenResponse>()
+            .await
+    }
+    .await;
+    match result {
+        Ok(token) => Some(token),
+        Err(err) => {
+            log::debug!("No token from the Google Cloud metadata server: {err}");
+            None
+        }
+    }
+}
@@ -1,38 +1,76 @@
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, Mutex, Weak};
 
 use anyhow::Context;
 use flate2::read::GzDecoder;
-use sha1::Digest;
+use serde::{Deserialize, Serialize};
 use smol_str::SmolStr;
 use zip::ZipArchive;
 
-use crate::FileHash;
+use crate::{CancellationToken, FileHash};
 
 pub struct Operating {
     pub tmp_dir_path: PathBuf,
     pub drop_should_not_block: bool,
+    /// Leave the temporary directory in place if the operation is dropped before
+    /// [`Operating::commit`], so that a failed install can be inspected.
+    pub keep_partial: bool,
+    committed: bool,
     lock_file_path: PathBuf,
+    /// Released after the temporary directory is removed.
+    _tool_dir_lock: ToolDirLock,
 }
 
 pub enum CreateOperatingError {
     AlreadyOperating,
+    /// Another process holds the lock of the tool directory.
+    ToolDirBusy,
     Io(std::io::Error),
 }
 
 impl Operating {
-    pub fn create_in_tmp_dir(tmp_dir_path: PathBuf) -> Result<Self, CreateOperatingError> {
+    /// Takes the lock of the tool directory containing `tmp_dir_path`, then the operation
+    /// lock of the tag. With `break_stale_lock`, an operation lock left by a process that died
+    /// is removed instead of failing with [`CreateOperatingError::AlreadyOperating`].
+    pub fn create_in_tmp_dir(
+        tmp_dir_path: PathBuf,
+        break_stale_lock: bool,
+    ) -> Result<Self, CreateOperatingError> {
+        let tool_dir = tmp_dir_path.parent().unwrap_or(Path::new("."));
+        let tool_dir_lock = ToolDirLock::acquire(tool_dir)
+            .map_err(CreateOperatingError::Io)?
+            .ok_or(CreateOperatingError::ToolDirBusy)?;
         std::fs::create_dir_all(&tmp_dir_path).map_err(CreateOperatingError::Io)?;
         let lock_file_path = tmp_dir_path.join(".lock");
+        // Operations of other processes hold the lock of the tool directory, which is taken now.
+        if break_stale_lock && lock_file_path.exists() {
+            log::warn!(
+                "Removing the stale operation lock '{}'",
+                lock_file_path.display()
+            );
+            std::fs::remove_file(&lock_file_path).map_err(CreateOperatingError::Io)?;
+        }
         match std::fs::OpenOptions::new()
             .write(true)
             .create_new(true)
             .open(&lock_file_path)
         {
-            Ok(_) => Ok(Self {
-                tmp_dir_path,
-                drop_should_not_block: false,
-                lock_file_path,
-            }),
+            Ok(_) => {
+                // A previous operation kept its partial files, start from a clean directory.
+                if let Err(err) = remove_dir_contents_except(&tmp_dir_path, &lock_file_path) {
+                    let _ = std::fs::remove_file(&lock_file_path);
+                    return Err(CreateOperatingError::Io(err));
+                }
+                Ok(Self {
+                    tmp_dir_path,
+                    drop_should_not_block: false,
+                    keep_partial: false,
+                    committed: false,
+                    lock_file_path,
+                    _tool_dir_lock: tool_dir_lock,
+                })
+            }
             Err(err) => {
                 if err.kind() == std::io::ErrorKind::AlreadyExists {
                     Err(CreateOperatingError::AlreadyOperating)
@@ -45,7 +83,12 @@ impl Operating {
         }
     }
 
-    fn remove(&self) {
+    /// Mark the operation as succeeded, the temporary directory is always removed on drop.
+    pub fn commit(&mut self) {
+        self.committed = true;
+    }
+
+    fn remove_lock(&self) {
         std::fs::remove_file(&self.lock_file_path).unwrap_or_else(|e| {
             if e.kind() != std::io::ErrorKind::NotFound {
                 log::error!(
@@ -55,6 +98,10 @@ impl Operating {
                 );
             }
         });
+    }
+
+    fn remove(&self) {
+        self.remove_lock();
         std::fs::remove_dir_all(&self.tmp_dir_path).unwrap_or_else(|e| {
             log::error!(
                 "Failed to remove directory '{}': {}",
@@ -71,8 +118,339 @@ impl Drop for Operating {
             log::warn!("Blocking remove: {}", self.tmp_dir_path.display());
         }
 
-        self.remove();
+        if self.keep_partial && !self.committed {
+            self.remove_lock();
+            log::warn!("Kept partial files in '{}'", self.tmp_dir_path.display());
+        } else {
+            self.remove();
+        }
+    }
+}
+
+fn remove_dir_contents_except(dir: &Path, keep: &Path) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path == keep {
+            continue;
+        }
+        if path.is_dir() && !path.is_symlink() {
+            std::fs::remove_dir_all(&path)?;
+        } else {
+            std::fs::remove_file(&path)?;
+        }
+    }
+    Ok(())
+}
+
+/// Total size of the files under `path`, symbolic links are not followed.
+pub fn dir_size(path: &Path) -> std::io::Result<u64> {
+    let mut size = 0;
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            size += dir_size(&entry.path())?;
+        } else {
+            size += entry.metadata()?.len();
+        }
+    }
+    Ok(size)
+}
+
+/// What [`dedup_files`] did, or would do on a dry run.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DedupStats {
+    /// Files replaced by a hard link to an identical file.
+    pub linked_files: u64,
+    /// Disk space freed by the replaced files.
+    pub saved_bytes: u64,
+}
+
+/// Prefix of the files written by avm into a tag, such as its install info. They are
+/// rewritten in place, so they are never linked.
+const AVM_FILE_PREFIX: &str = ".avm";
+
+/// Replaces the files under `dirs` that have the same content and permissions as another one
+/// by hard links to it. Links are not followed. Files are only hashed if another file has
+/// the same size, and files that are already linked together are left as they are.
+///
+/// Removing one of the directories later is safe, the other links keep the content. Writing to
+/// a linked file changes it in every directory, though.
+pub fn dedup_files(
+    dirs: &[PathBuf],
+    dry_run: bool,
+    cancel: &CancellationToken,
+) -> anyhow::Result<DedupStats> {
+    let mut by_size: HashMap<u64, Vec<(PathBuf, std::fs::Metadata)>> = HashMap::new();
+    for dir in dirs {
+        collect_dedup_candidates(dir, true, &mut by_size)
+            .with_context(|| format!("Failed to read '{}'", dir.display()))?;
+    }
+
+    let sha256 = FileHash::from_sha256(SmolStr::default());
+    let mut stats = DedupStats::default();
+    let mut replaced_ids = std::collections::HashSet::new();
+    for (size, files) in by_size {
+        if files.len() < 2 {
+            continue;
+        }
+        // By permissions and SHA-256 digest.
+        let mut originals: HashMap<(u32, Option<SmolStr>), (PathBuf, Option<FileId>)> =
+            HashMap::new();
+        for (path, metadata) in files {
+            let digest = hash_file(&sha256, &path, cancel)
+                .with_context(|| format!("Failed to hash '{}'", path.display()))?;
+            let key = (permission_bits(&metadata), digest.sha256);
+            let id = file_id(&metadata);
+            let Some((original, original_id)) = originals.get(&key) else {
+                originals.insert(key, (path, id));
+                continue;
+            };
+            if id.is_some() && id == *original_id {
+                continue;
+            }
+            if !dry_run {
+                if let Err(err) = replace_with_hard_link(original, &path) {
+                    log::warn!("Failed to link '{}' ({err})", path.display());
+                    continue;
+                }
+            }
+            stats.linked_files += 1;
+            // The space is freed once every link to the replaced file is replaced.
+            if id.is_none_or(|id| replaced_ids.insert(id)) {
+                stats.saved_bytes += size;
+            }
+        }
+    }
+    Ok(stats)
+}
+
+fn collect_dedup_candidates(
+    dir: &Path,
+    is_root: bool,
+    by_size: &mut HashMap<u64, Vec<(PathBuf, std::fs::Metadata)>>,
+) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(dir)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            collect_dedup_candidates(&entry.path(), false, by_size)?;
+        } else if file_type.is_file() {
+            if is_root
+                && entry
+                    .file_name()
+                    .to_string_lossy()
+                    .starts_with(AVM_FILE_PREFIX)
+            {
+                continue;
+            }
+            let metadata = entry.metadata()?;
+            if metadata.len() > 0 {
+                by_size
+                    .entry(metadata.len())
+                    .or_default()
+                    .push((entry.path(), metadata));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The permissions a hard link shares with the original.
+fn permission_bits(metadata: &std::fs::Metadata) -> u32 {
+    #[cfg(unix)]
+    return std::os::unix::fs::PermissionsExt::mode(&metadata.permissions());
+    #[cfg(not(unix))]
+    return metadata.permissions().readonly() as u32;
+}
+
+/// The device and inode of a file.
+type FileId = (u64, u64);
+
+/// `None` where the ID of a file is not available.
+fn file_id(metadata: &std::fs::Metadata) -> Option<FileId> {
+    #[cfg(unix)]
+    return Some((
+        std::os::unix::fs::MetadataExt::dev(metadata),
+        std::os::unix::fs::MetadataExt::ino(metadata),
+    ));
+    #[cfg(not(unix))]
+    {
+        let _ = metadata;
+        None
+    }
+}
+
+/// Replaces `path` by a hard link to `original` with a rename, so that `path` always exists.
+fn replace_with_hard_link(original: &Path, path: &Path) -> std::io::Result<()> {
+    let mut tmp_name = path.file_name().unwrap_or_default().to_owned();
+    tmp_name.push(TMP_SUFFIX);
+    let tmp_path = path.with_file_name(tmp_name);
+    std::fs::remove_file(&tmp_path).ok();
+    std::fs::hard_link(original, &tmp_path)?;
+    std::fs::rename(&tmp_path, path).inspect_err(|_| {
+        std::fs::remove_file(&tmp_path).ok();
+    })
+}
+
+/// Flush every file and directory under `path` to disk.
+fn sync_tree(path: &Path) -> std::io::Result<()> {
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            sync_tree(&entry.path())?;
+        } else if file_type.is_file() {
+            std::fs::File::open(entry.path())?.sync_all()?;
+        }
     }
+    sync_dir(path)
+}
+
+pub(crate) fn sync_dir(path: &Path) -> std::io::Result<()> {
+    // Directories cannot be opened as files on Windows, and renames there are already durable.
+    #[cfg(unix)]
+    std::fs::File::open(path)?.sync_all()?;
+    #[cfg(not(unix))]
+    let _ = path;
+    Ok(())
+}
+
+/// Move the fully prepared `source` directory to `target` with a rename, so that `target`
+/// is either the old or the new content. An existing `target` is first moved to `backup`
+/// and restored if the rename fails; the caller is responsible for removing `backup`.
+pub fn install_dir(source: &Path, target: &Path, backup: &Path) -> anyhow::Result<()> {
+    sync_tree(source).with_context(|| format!("Failed to sync '{}' to disk", source.display()))?;
+    let parent = target.parent();
+    if let Some(parent) = parent {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let replacing = target.symlink_metadata().is_ok();
+    if replacing {
+        std::fs::rename(target, backup).with_context(|| {
+            format!(
+                "Failed to move the existing '{}' out of the way",
+                target.display()
+            )
+        })?;
+    }
+    if let Err(err) = std::fs::rename(source, target) {
+        if replacing {
+            if let Err(rollback_err) = std::fs::rename(backup, target) {
+                log::error!(
+                    "Failed to restore '{}' from '{}': {}",
+                    target.display(),
+                    backup.display(),
+                    rollback_err
+                );
+            }
+        }
+        return Err(anyhow::Error::from(err).context(format!(
+            "Failed to move '{}' to '{}'",
+            source.display(),
+            target.display()
+        )));
+    }
+    if let Some(parent) = parent {
+        sync_dir(parent)?;
+    }
+    Ok(())
+}
+
+/// Moves the directory `from` to `to`, which must not exist or be empty. Across file systems,
+/// the directory is copied, with links copied as links, and then removed.
+pub fn move_dir(from: &Path, to: &Path, cancel: &CancellationToken) -> anyhow::Result<()> {
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    if to.symlink_metadata().is_ok() {
+        std::fs::remove_dir(to)
+            .with_context(|| format!("'{}' exists and is not empty", to.display()))?;
+    }
+    match std::fs::rename(from, to) {
+        Ok(()) => return Ok(()),
+        Err(err) if err.kind() == std::io::ErrorKind::CrossesDevices => {}
+        Err(err) => {
+            return Err(anyhow::Error::from(err).context(format!(
+                "Failed to move '{}' to '{}'",
+                from.display(),
+                to.display()
+            )))
+        }
+    }
+    log::info!(
+        "'{}' is on another file system, copying '{}'",
+        to.display(),
+        from.display()
+    );
+    if let Err(err) = copy_tree(from, to, cancel) {
+        std::fs::remove_dir_all(to).ok();
+        return Err(err.context(format!(
+            "Failed to copy '{}' to '{}'",
+            from.display(),
+            to.display()
+        )));
+    }
+    sync_tree(to).with_context(|| format!("Failed to sync '{}' to disk", to.display()))?;
+    std::fs::remove_dir_all(from).with_context(|| {
+        format!(
+            "Copied to '{}' but failed to remove '{}'",
+            to.display(),
+            from.display()
+        )
+    })
+}
+
+/// Copies the directory `from` to `to`, which must not exist, with links copied as links.
+/// Files are copied with [`std::fs::copy`], which uses `copy_file_range` on Linux and
+/// `clonefile` on macOS, so they are reflinked on file systems that support it.
+pub fn copy_tree(from: &Path, to: &Path, cancel: &CancellationToken) -> anyhow::Result<()> {
+    std::fs::create_dir(to)?;
+    for entry in std::fs::read_dir(from)? {
+        crate::check_cancelled(cancel)?;
+        let entry = entry?;
+        let (source, target) = (entry.path(), to.join(entry.file_name()));
+        let file_type = entry.file_type()?;
+        if file_type.is_symlink() {
+            let link_target = std::fs::read_link(&source)?;
+            #[cfg(unix)]
+            std::os::unix::fs::symlink(&link_target, &target)?;
+            #[cfg(windows)]
+            if source.is_dir() {
+                create_link(&link_target, &target)?;
+            } else {
+                std::os::windows::fs::symlink_file(&link_target, &target)?;
+            }
+        } else if file_type.is_dir() {
+            copy_tree(&source, &target, cancel)?;
+        } else {
+            std::fs::copy(&source, &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// Undoes [`install_dir`], moving `target` back to `source` and restoring the replaced
+/// directory from `backup` if there was one.
+pub fn uninstall_dir(source: &Path, target: &Path, backup: &Path) -> anyhow::Result<()> {
+    std::fs::rename(target, source).with_context(|| {
+        format!(
+            "Failed to move '{}' back to '{}'",
+            target.display(),
+            source.display()
+        )
+    })?;
+    if backup.symlink_metadata().is_ok() {
+        std::fs::rename(backup, target).with_context(|| {
+            format!(
+                "Failed to restore '{}' from '{}'",
+                target.display(),
+                backup.display()
+            )
+        })?;
+    }
+    Ok(())
 }
 
 pub enum GetLinkResult<R> {
@@ -142,21 +520,58 @@ pub fn remove_link(path: &Path) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Marker file in an alias tag that is a copy of its target, holding the target tag name.
+/// Copies are used where links can't be created.
+pub const COPY_ALIAS_MARKER: &str = ".avm-alias";
+
+/// Returns the target tag of an alias tag created as a copy.
+pub fn copy_alias_target(path: &Path) -> Option<SmolStr> {
+    let target = std::fs::read_to_string(path.join(COPY_ALIAS_MARKER)).ok()?;
+    Some(SmolStr::new(target.trim()))
+}
+
+/// How an alias tag was created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AliasKind {
+    Link,
+    Copy,
+}
+
+/// Points `alias_path` to `src_path`, replacing an existing alias.
+///
+/// Creates a link (a junction on Windows), falling back to a copy of the source tag with
+/// a [`COPY_ALIAS_MARKER`] if linking fails. The copy is staged in `staging_dir`. On Unix, an
+/// existing link is replaced atomically by renaming a new link staged there over it.
 pub fn set_alias_tag(
     src_tag: &str,
     src_path: &Path,
     alias_tag: &str,
     alias_path: &Path,
-) -> anyhow::Result<()> {
+    staging_dir: &Path,
+) -> anyhow::Result<AliasKind> {
     if !src_path.exists() {
-        anyhow::bail!("Src tag \"{src_tag}\" not found");
+        return Err(crate::AvmError::TagNotFound {
+            tag: src_tag.into(),
+        }
+        .into());
     }
 
     match check_is_link(alias_path) {
         GetLinkResult::Link(_) => {
+            // Swapped by a rename where it replaces a link, so the alias is never missing.
+            #[cfg(unix)]
+            if create_link(src_path, staging_dir)
+                .and_then(|()| std::fs::rename(staging_dir, alias_path))
+                .is_ok()
+            {
+                return Ok(AliasKind::Link);
+            }
             remove_link(alias_path)?;
         }
         GetLinkResult::NotFound => {}
+        GetLinkResult::NotLink if copy_alias_target(alias_path).is_some() => {
+            std::fs::remove_dir_all(alias_path)?;
+        }
         GetLinkResult::NotLink => {
             anyhow::bail!("Tag \"{alias_tag}\" exists and is not an alias");
         }
@@ -166,15 +581,185 @@ pub fn set_alias_tag(
         }
     }
 
-    create_link(src_path, alias_path)?;
+    match create_link(src_path, alias_path) {
+        Ok(()) => Ok(AliasKind::Link),
+        Err(err) => {
+            log::warn!("Failed to link \"{alias_tag}\" to \"{src_tag}\" ({err}), copying instead");
+            copy_alias(src_tag, src_path, alias_path, staging_dir)
+                .with_context(|| format!("Failed to copy \"{src_tag}\" as \"{alias_tag}\""))?;
+            Ok(AliasKind::Copy)
+        }
+    }
+}
 
+fn copy_alias(
+    src_tag: &str,
+    src_path: &Path,
+    alias_path: &Path,
+    staging_dir: &Path,
+) -> anyhow::Result<()> {
+    std::fs::remove_dir_all(staging_dir).ok();
+    std::fs::create_dir_all(staging_dir)?;
+    let copy_options = fs_extra::dir::CopyOptions::new().content_only(true);
+    fs_extra::dir::copy(src_path, staging_dir, &copy_options)?;
+    std::fs::write(staging_dir.join(COPY_ALIAS_MARKER), src_tag)?;
+    std::fs::rename(staging_dir, alias_path)?;
     Ok(())
 }
 
+/// File in a tool directory locked while its tags are changed, see [`ToolDirLock`].
+const TOOL_LOCK_FILE: &str = ".avm.lock";
+
+/// The tool directory locks held by this process, shared by nested operations such as
+/// setting the `default` alias at the end of an install.
+static TOOL_DIR_LOCKS: LazyLock<Mutex<HashMap<PathBuf, Weak<std::fs::File>>>> =
+    LazyLock::new(Default::default);
+
+/// An advisory lock on a tool directory, held while installing, removing or aliasing its tags
+/// so that avm processes running in parallel don't change it at the same time. It is released
+/// on drop, or by the OS when the process dies.
+pub struct ToolDirLock {
+    _file: Arc<std::fs::File>,
+}
+
+impl ToolDirLock {
+    /// Takes the lock of `tool_dir`, waiting for another process holding it if
+    /// [`crate::wait_for_locks`]. Returns `None` if it is held and not waited for.
+    pub fn acquire(tool_dir: &Path) -> std::io::Result<Option<Self>> {
+        Self::acquire_with(tool_dir, crate::wait_for_locks())
+    }
+
+    /// Takes the lock of `tool_dir` unless another process holds it.
+    pub fn try_acquire(tool_dir: &Path) -> std::io::Result<Option<Self>> {
+        Self::acquire_with(tool_dir, false)
+    }
+
+    fn acquire_with(tool_dir: &Path, wait: bool) -> std::io::Result<Option<Self>> {
+        let mut locks = TOOL_DIR_LOCKS.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(file) = locks.get(tool_dir).and_then(Weak::upgrade) {
+            return Ok(Some(Self { _file: file }));
+        }
+        std::fs::create_dir_all(tool_dir)?;
+        let lock_path = tool_dir.join(TOOL_LOCK_FILE);
+        let file = std::fs::File::create(&lock_path)?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(std::fs::TryLockError::WouldBlock) if wait => {
+                log::info!(
+                    "Waiting for another avm process to finish with '{}'",
+                    tool_dir.display()
+                );
+                file.lock()?;
+            }
+            Err(std::fs::TryLockError::WouldBlock) => return Ok(None),
+            Err(std::fs::TryLockError::Error(err)) => return Err(err),
+        }
+        let file = Arc::new(file);
+        locks.insert(tool_dir.to_path_buf(), Arc::downgrade(&file));
+        Ok(Some(Self { _file: file }))
+    }
+}
+
+/// File in a tool directory recording its alias tags and their targets.
+pub const ALIAS_STORE_FILE: &str = ".aliases.json";
+const ALIAS_STORE_LOCK_FILE: &str = ".aliases.lock";
+
+/// The alias tags of a tool directory, kept in [`ALIAS_STORE_FILE`].
+///
+/// The links and copies in the tool directory are materialized from the store, so it stays
+/// the source of truth when they are broken or can't be read back.
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AliasStore {
+    /// Alias tag to target tag.
+    #[serde(default)]
+    pub aliases: BTreeMap<SmolStr, SmolStr>,
+}
+
+impl AliasStore {
+    /// Reads the store of `tool_dir`. Without one, the aliases are derived from the links and
+    /// copied aliases in the directory, as created by earlier versions.
+    pub fn load(tool_dir: &Path) -> anyhow::Result<Self> {
+        let path = tool_dir.join(ALIAS_STORE_FILE);
+        match std::fs::read(&path) {
+            Ok(content) => serde_json::from_slice(&content)
+                .with_context(|| format!("Failed to parse '{}'", path.display())),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                let aliases = scan_tags(tool_dir, TMP_PREFIX)?
+                    .into_iter()
+                    .filter_map(|(tag, target)| Some((tag, target?)))
+                    .collect();
+                Ok(Self { aliases })
+            }
+            Err(err) => {
+                Err(anyhow::Error::from(err)
+                    .context(format!("Failed to read '{}'", path.display())))
+            }
+        }
+    }
+
+    /// Runs `f` on the store of `tool_dir` while holding its lock, and writes the store back
+    /// atomically if `f` succeeds. Alias tags should only be changed inside `f`.
+    pub fn update<R>(
+        tool_dir: &Path,
+        f: impl FnOnce(&mut Self) -> anyhow::Result<R>,
+    ) -> anyhow::Result<R> {
+        std::fs::create_dir_all(tool_dir)?;
+        let lock_path = tool_dir.join(ALIAS_STORE_LOCK_FILE);
+        let lock = std::fs::File::create(&lock_path)
+            .with_context(|| format!("Failed to open '{}'", lock_path.display()))?;
+        lock.lock()
+            .with_context(|| format!("Failed to lock '{}'", lock_path.display()))?;
+
+        let mut store = Self::load(tool_dir)?;
+        let before = serde_json::to_vec_pretty(&store)?;
+        let result = f(&mut store)?;
+        let after = serde_json::to_vec_pretty(&store)?;
+        if before != after || !tool_dir.join(ALIAS_STORE_FILE).exists() {
+            store.write(tool_dir, &after)?;
+        }
+        Ok(result)
+    }
+
+    fn write(&self, tool_dir: &Path, content: &[u8]) -> anyhow::Result<()> {
+        let path = tool_dir.join(ALIAS_STORE_FILE);
+        let tmp_path = tool_dir.join(format!("{ALIAS_STORE_FILE}{TMP_SUFFIX}"));
+        let mut file = std::fs::File::create(&tmp_path)?;
+        std::io::Write::write_all(&mut file, content)?;
+        file.sync_all()?;
+        drop(file);
+        std::fs::rename(&tmp_path, &path)
+            .with_context(|| format!("Failed to write '{}'", path.display()))?;
+        sync_dir(tool_dir)?;
+        Ok(())
+    }
+}
+
+/// Prefix of the temporary directories of install and alias operations in a tool directory.
+pub const TMP_PREFIX: &str = ".tmp.";
+const TMP_SUFFIX: &str = ".tmp";
+
+fn is_store_file(file_name: &str) -> bool {
+    file_name == ALIAS_STORE_LOCK_FILE
+        || file_name == TOOL_LOCK_FILE
+        || file_name.starts_with(ALIAS_STORE_FILE)
+}
+
+/// Lists the tags in a tool directory with the targets of the alias tags.
+///
+/// Aliases are taken from the [`AliasStore`], so an alias whose link is missing is still listed.
 pub fn list_tags(
     path: &Path,
     ignore_prefix: &str,
-) -> std::io::Result<Vec<(SmolStr, Option<SmolStr>)>> {
+) -> anyhow::Result<Vec<(SmolStr, Option<SmolStr>)>> {
+    let mut tags: BTreeMap<_, _> = scan_tags(path, ignore_prefix)?.into_iter().collect();
+    for (alias, target) in AliasStore::load(path)?.aliases {
+        tags.insert(alias, Some(target));
+    }
+    Ok(tags.into_iter().collect())
+}
+
+/// Lists the tags by reading the tool directory, with the targets of links and copied aliases.
+fn scan_tags(path: &Path, ignore_prefix: &str) -> std::io::Result<Vec<(SmolStr, Option<SmolStr>)>> {
     log::debug!("Listing tags in: {}", path.display());
     let mut tags = Vec::new();
     let entries = match std::fs::read_dir(path) {
@@ -190,7 +775,7 @@ pub fn list_tags(
     for entry in entries {
         let entry = entry?;
         let file_name: SmolStr = entry.file_name().to_string_lossy().into();
-        if file_name.starts_with(ignore_prefix) {
+        if file_name.starts_with(ignore_prefix) || is_store_file(&file_name) {
             continue;
         }
         match get_link_target(&entry.path()) {
@@ -212,76 +797,216 @@ pub fn list_tags(
                     .into();
                 tags.push((file_name, Some(target_name)));
             }
-            GetLinkResult::NotLink => tags.push((file_name, None)),
+            GetLinkResult::NotLink => {
+                let target = copy_alias_target(&entry.path());
+                tags.push((file_name, target));
+            }
         }
     }
     Ok(tags)
 }
 
-// It seems `pub(super)` cause problem. Use `pub(crate)` now before investigating the root cause.
-pub(crate) fn verify_hash(hash: &FileHash, path: &Path) -> Result<(), anyhow::Error> {
-    if let Some(sha1) = &hash.sha1 {
-        let mut file = std::fs::File::open(path)?;
-        let sha1_bytes = hex::decode(sha1)?;
-        let mut hasher = sha1::Sha1::new();
-        update_digest_from_reader(&mut file, &mut hasher)?;
-        if hasher.finalize().as_slice() != sha1_bytes.as_slice() {
-            anyhow::bail!("Sha1 verification failed");
+/// Blocking reader over the chunks sent by the downloading task.
+pub(crate) struct ChannelReader {
+    receiver: tokio::sync::mpsc::Receiver<bytes::Bytes>,
+    chunk: bytes::Bytes,
+}
+
+impl ChannelReader {
+    pub(crate) fn new(receiver: tokio::sync::mpsc::Receiver<bytes::Bytes>) -> Self {
+        Self {
+            receiver,
+            chunk: bytes::Bytes::new(),
         }
     }
+}
+
+impl std::io::Read for ChannelReader {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        while self.chunk.is_empty() {
+            match self.receiver.blocking_recv() {
+                Some(chunk) => self.chunk = chunk,
+                None => return Ok(0),
+            }
+        }
+        let len = buf.len().min(self.chunk.len());
+        buf[..len].copy_from_slice(&self.chunk.split_to(len));
+        Ok(len)
+    }
+}
+
+pub(crate) fn parse_json_array<T, U, F>(
+    reader: impl std::io::Read,
+    filter_map: F,
+) -> anyhow::Result<Vec<U>>
+where
+    T: serde::de::DeserializeOwned,
+    F: FnMut(T) -> super::JsonItemAction<U>,
+{
+    struct ArrayVisitor<'a, T, U, F> {
+        filter_map: F,
+        items: &'a mut Vec<U>,
+        stopped: &'a mut bool,
+        _item: std::marker::PhantomData<T>,
+    }
+
+    impl<'de, T, U, F> serde::de::Visitor<'de> for ArrayVisitor<'_, T, U, F>
+    where
+        T: serde::de::DeserializeOwned,
+        F: FnMut(T) -> super::JsonItemAction<U>,
+    {
+        type Value = ();
+
+        fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+            formatter.write_str("a JSON array")
+        }
 
-    if let Some(sha256) = &hash.sha256 {
-        let mut file = std::fs::File::open(path)?;
-        let sha256_bytes = hex::decode(sha256)?;
-        let mut hasher = sha2::Sha256::new();
-        update_digest_from_reader(&mut file, &mut hasher)?;
-        if hasher.finalize().as_slice() != sha256_bytes.as_slice() {
-            anyhow::bail!("Sha256 verification failed");
+        fn visit_seq<A: serde::de::SeqAccess<'de>>(mut self, mut seq: A) -> Result<(), A::Error> {
+            while let Some(item) = seq.next_element::<T>()? {
+                match (self.filter_map)(item) {
+                    super::JsonItemAction::Skip => {}
+                    super::JsonItemAction::Keep(item) => self.items.push(item),
+                    super::JsonItemAction::KeepAndStop(item) => {
+                        self.items.push(item);
+                        *self.stopped = true;
+                        return Ok(());
+                    }
+                }
+            }
+            Ok(())
         }
     }
 
-    if let Some(sha512) = &hash.sha512 {
-        let mut file = std::fs::File::open(path)?;
-        let sha512_bytes = hex::decode(sha512)?;
-        let mut hasher = sha2::Sha512::new();
-        update_digest_from_reader(&mut file, &mut hasher)?;
-        if hasher.finalize().as_slice() != sha512_bytes.as_slice() {
-            anyhow::bail!("Sha512 verification failed");
+    let mut items = Vec::new();
+    let mut stopped = false;
+    let result = serde::Deserializer::deserialize_seq(
+        &mut serde_json::Deserializer::from_reader(std::io::BufReader::new(reader)),
+        ArrayVisitor {
+            filter_map,
+            items: &mut items,
+            stopped: &mut stopped,
+            _item: std::marker::PhantomData,
+        },
+    );
+    match result {
+        // Leaving the array unfinished is reported as trailing characters.
+        Err(_) if stopped => {}
+        result => result?,
+    }
+    Ok(items)
+}
+
+/// Fails reads once cancelled, so that long blocking reads and everything driven by them
+/// (hashing, decompression, extraction) stop promptly.
+struct CancellableReader<'a, R> {
+    inner: R,
+    cancel: &'a CancellationToken,
+}
+
+impl<R: std::io::Read> std::io::Read for CancellableReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if self.cancel.is_cancelled() {
+            return Err(std::io::Error::other("Operation cancelled"));
         }
+        self.inner.read(buf)
+    }
+}
+
+impl<R: std::io::Seek> std::io::Seek for CancellableReader<'_, R> {
+    fn seek(&mut self, pos: std::io::SeekFrom) -> std::io::Result<u64> {
+        self.inner.seek(pos)
     }
+}
 
-    log::debug!("Hash verification passed");
+// It seems `pub(super)` cause problem. Use `pub(crate)` now before investigating the root cause.
+pub(crate) fn verify_hash(
+    hash: &FileHash,
+    path: &Path,
+    cancel: &CancellationToken,
+) -> Result<(), anyhow::Error> {
+    super::verify_digest(hash, &hash_file(hash, path, cancel)?)?;
     Ok(())
 }
 
-fn update_digest_from_reader(
-    reader: &mut impl std::io::Read,
-    digest: &mut impl Digest,
-) -> Result<(), std::io::Error> {
+/// The digests of the file, for the algorithms present in `hash`.
+pub(crate) fn hash_file(
+    hash: &FileHash,
+    path: &Path,
+    cancel: &CancellationToken,
+) -> anyhow::Result<FileHash> {
+    let mut file = CancellableReader {
+        inner: std::fs::File::open(path)?,
+        cancel,
+    };
+    let mut hasher = super::FileHasher::new(hash);
     let mut buffer = [0_u8; 8192];
     loop {
-        let read_len = reader.read(&mut buffer)?;
+        let read_len = std::io::Read::read(&mut file, &mut buffer)?;
         if read_len == 0 {
-            return Ok(());
+            break;
         }
-        digest.update(&buffer[..read_len]);
+        hasher.update(&buffer[..read_len]);
     }
+    Ok(hasher.finalize())
+}
+
+/// Detect the archive type of a file without a known extension from its magic bytes.
+pub(crate) fn detect_archive_type(path: &Path) -> anyhow::Result<super::ArchiveType> {
+    use std::io::Read;
+
+    let mut header = Vec::with_capacity(512);
+    std::fs::File::open(path)?
+        .take(512)
+        .read_to_end(&mut header)?;
+    super::ArchiveType::from_magic(&header)
+        .ok_or_else(|| anyhow::anyhow!("unknown archive type of '{}'", path.display()))
 }
 
 pub(crate) fn extract_archive(
     archive_type: super::ArchiveType,
     archive_path: &Path,
     extracted_dir: &Path,
+    cancel: &CancellationToken,
 ) -> Result<(), anyhow::Error> {
     std::fs::create_dir_all(extracted_dir)?;
-    let archive_file = std::fs::File::open(archive_path)?;
+    let extracted_dir = &long_path(extracted_dir)?;
+    let archive_file = CancellableReader {
+        inner: std::fs::File::open(archive_path)?,
+        cancel,
+    };
+    let result = extract_archive_inner(
+        archive_type,
+        archive_path,
+        archive_file,
+        extracted_dir,
+        cancel,
+    );
+    // Report the cancellation rather than the I/O error it caused.
+    crate::check_cancelled(cancel)?;
+    result
+}
+
+fn extract_archive_inner(
+    archive_type: super::ArchiveType,
+    archive_path: &Path,
+    archive_file: CancellableReader<'_, std::fs::File>,
+    extracted_dir: &Path,
+    cancel: &CancellationToken,
+) -> Result<(), anyhow::Error> {
     match archive_type {
         super::ArchiveType::Zip => {
             let mut archive = ZipArchive::new(archive_file)?;
+            let mut links = Vec::new();
 
             for i in 0..archive.len() {
                 let mut file = archive.by_index(i)?;
-                let out_path = extracted_dir.join(file.mangled_name());
+                let name = file.mangled_name();
+                let out_path = extracted_dir.join(&name);
+                check_no_linked_parent(extracted_dir, &name)?;
+                // An earlier entry with the same name could have been a link.
+                if out_path.is_symlink() {
+                    std::fs::remove_file(&out_path)?;
+                }
 
                 if file.is_dir() {
                     std::fs::create_dir_all(&out_path)?;
@@ -291,50 +1016,938 @@ pub(crate) fn extract_archive(
                             std::fs::create_dir_all(p)?;
                         }
                     }
+                    if file.is_symlink() {
+                        let mut target = String::new();
+                        std::io::Read::read_to_string(&mut file, &mut target)?;
+                        let target = PathBuf::from(target);
+                        if link_source(&name, &target).is_none() {
+                            log::warn!(
+                                "Skipping the link '{}' to '{}', which is outside the archive",
+                                name.display(),
+                                target.display()
+                            );
+                            continue;
+                        }
+                        #[cfg(unix)]
+                        if std::os::unix::fs::symlink(&target, &out_path).is_ok() {
+                            continue;
+                        }
+                        links.push((name, target));
+                        continue;
+                    }
                     let mut out_file = std::fs::File::create(&out_path)?;
                     std::io::copy(&mut file, &mut out_file)?;
                 }
 
                 #[cfg(unix)]
-                {
-                    use std::os::unix::fs::PermissionsExt;
-
-                    if let Some(mode) = file.unix_mode() {
-                        let permissions = std::fs::Permissions::from_mode(mode);
-                        std::fs::set_permissions(&out_path, permissions)?;
-                    }
+                if let Some(mode) = file.unix_mode() {
+                    set_mode(&out_path, mode, file.is_dir())?;
                 }
             }
+            copy_link_targets(extracted_dir, links, cancel)?;
+        }
+        super::ArchiveType::SevenZ => {
+            let extract_entry = |entry: &sevenz_rust::SevenZArchiveEntry,
+                                 reader: &mut dyn std::io::Read,
+                                 dest: &PathBuf| {
+                // The entry would be written to `extracted_dir.join(name)`, unlike zip names
+                // they are not sanitized.
+                let enclosed = Path::new(entry.name()).components().all(|component| {
+                    matches!(
+                        component,
+                        std::path::Component::Normal(_) | std::path::Component::CurDir
+                    )
+                });
+                if !enclosed {
+                    return Err(sevenz_rust::Error::other(format!(
+                        "The archive entry '{}' is outside the archive",
+                        entry.name()
+                    )));
+                }
+                sevenz_rust::default_entry_extract_fn(entry, reader, dest)
+            };
+            sevenz_rust::decompress_with_extract_fn(archive_file, extracted_dir, extract_entry)
+                .with_context(|| {
+                    anyhow::anyhow!(
+                        "Failed to unpack 7z archive '{}' into '{}'.",
+                        archive_path.display(),
+                        extracted_dir.display()
+                    )
+                })?;
         }
-        super::ArchiveType::TarGz => {
-            let tar_gz_reader = GzDecoder::new(archive_file);
+        // Nothing to unpack, the installer moves the file in place.
+        super::ArchiveType::SingleBinary => {}
+        _ => unpack_tar_archive(
+            archive_type,
+            archive_file,
+            &archive_path.display(),
+            extracted_dir,
+            cancel,
+        )?,
+    }
 
-            // 2. Create a tar Archive from the decompressed stream
-            let mut archive = tar::Archive::new(tar_gz_reader);
+    Ok(())
+}
 
-            // 3. Unpack the contents into the specified directory
-            // The `unpack` method handles creating directories and files.
-            // It also attempts to restore permissions and timestamps.
-            archive.unpack(extracted_dir).with_context(|| {
-                anyhow::anyhow!(
-                    "Failed to unpack tar.gz archive '{}' into '{}'.",
-                    archive_path.display(),
-                    extracted_dir.display()
-                )
-            })?;
+/// `path` as an absolute extended-length path on Windows, which is not limited to 260
+/// characters like the deep paths of JDK archives. Unchanged elsewhere.
+fn long_path(path: &Path) -> std::io::Result<PathBuf> {
+    #[cfg(windows)]
+    {
+        use std::ffi::OsString;
+        use std::os::windows::ffi::{OsStrExt, OsStringExt};
+        use std::path::{Component, Prefix};
+
+        let path = std::path::absolute(path)?;
+        // Verbatim and device paths are kept.
+        let unc = match path.components().next() {
+            Some(Component::Prefix(prefix)) => match prefix.kind() {
+                Prefix::Disk(_) => Some(false),
+                Prefix::UNC(..) => Some(true),
+                _ => None,
+            },
+            _ => None,
+        };
+        let Some(unc) = unc else {
+            return Ok(path);
+        };
+        let mut long = OsString::new();
+        if unc {
+            // `\\server\share` becomes `\\?\UNC\server\share`.
+            long.push(r"\\?\UNC");
+            let wide = path.as_os_str().encode_wide().skip(1).collect::<Vec<_>>();
+            long.push(OsString::from_wide(&wide));
+        } else {
+            long.push(r"\\?\");
+            long.push(path.as_os_str());
+        }
+        Ok(PathBuf::from(long))
+    }
+    #[cfg(not(windows))]
+    Ok(path.to_path_buf())
+}
+
+/// The permissions of an extracted file or directory with the mode of its archive entry. The
+/// setuid, setgid and sticky bits are dropped, and the owner can always read and write it,
+/// and enter it if it is a directory, so that the tag can be moved and removed later.
+/// Executables stay executable by the owner.
+#[cfg(unix)]
+fn extracted_mode(mode: u32, is_dir: bool) -> u32 {
+    let mut mode = mode & 0o777 | if is_dir { 0o700 } else { 0o600 };
+    if mode & 0o111 != 0 {
+        mode |= 0o100;
+    }
+    mode
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32, is_dir: bool) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    std::fs::set_permissions(
+        path,
+        std::fs::Permissions::from_mode(extracted_mode(mode, is_dir)),
+    )
+}
+
+/// Writes the file that the zstd patch at `patch`, made with `zstd --patch-from` from the
+/// file at `base`, restores to `dest`. The patch is streamed, and the base is mapped rather
+/// than read into memory, since the patch may refer to any part of it.
+pub(crate) fn apply_zstd_patch(
+    base: &Path,
+    patch: &Path,
+    dest: &Path,
+    cancel: &CancellationToken,
+) -> anyhow::Result<()> {
+    let base = std::fs::File::open(base)?;
+    // SAFETY: the base is an entry of the download cache, which is only ever replaced by a
+    // rename, never written in place, so the mapped bytes don't change while patching.
+    let base = unsafe { memmap2::Mmap::map(&base)? };
+    let patch = std::io::BufReader::new(CancellableReader {
+        inner: std::fs::File::open(patch)?,
+        cancel,
+    });
+    let mut decoder = zstd::stream::read::Decoder::with_ref_prefix(patch, &base)?;
+    // The window of a patch covers the whole base, beyond the default limit of 128MiB.
+    decoder.window_log_max(31)?;
+    let mut dest = std::fs::File::create(dest)?;
+    std::io::copy(&mut decoder, &mut dest)?;
+    Ok(())
+}
+
+/// The path that the link at `link` to `target` points to, both relative to the extraction
+/// directory, or `None` if it points outside of it.
+fn link_source(link: &Path, target: &Path) -> Option<PathBuf> {
+    let mut source = link.parent().map(Path::to_path_buf).unwrap_or_default();
+    for component in target.components() {
+        match component {
+            std::path::Component::Normal(name) => source.push(name),
+            std::path::Component::ParentDir => {
+                if !source.pop() {
+                    return None;
+                }
+            }
+            std::path::Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    Some(source)
+}
+
+/// Fails if a parent of the entry at `name` is a symbolic link, which the entry would be
+/// written through, like `tar::Entry::unpack_in` does.
+fn check_no_linked_parent(extracted_dir: &Path, name: &Path) -> anyhow::Result<()> {
+    let mut path = extracted_dir.to_path_buf();
+    for component in name.parent().into_iter().flat_map(Path::components) {
+        path.push(component);
+        if path.is_symlink() {
+            anyhow::bail!(
+                "The archive entry '{}' is inside the link '{}'",
+                name.display(),
+                path.display()
+            );
         }
-        super::ArchiveType::TarXz => {
-            let tar_xz_reader = xz2::read::XzDecoder::new(archive_file);
-            let mut archive = tar::Archive::new(tar_xz_reader);
-            archive.unpack(extracted_dir).with_context(|| {
-                anyhow::anyhow!(
-                    "Failed to unpack tar.xz archive '{}' into '{}'.",
-                    archive_path.display(),
-                    extracted_dir.display()
-                )
-            })?;
+    }
+    Ok(())
+}
+
+/// Replaces the symbolic links that could not be created, such as on Windows without the
+/// privilege, with copies of their targets. `links` are the paths of the links relative to
+/// `extracted_dir` with their targets. Links to outside the archive are left out.
+fn copy_link_targets(
+    extracted_dir: &Path,
+    links: Vec<(PathBuf, PathBuf)>,
+    cancel: &CancellationToken,
+) -> anyhow::Result<()> {
+    for (link, target) in links {
+        crate::check_cancelled(cancel)?;
+        let source = link_source(&link, &target).map(|source| extracted_dir.join(source));
+        let Some(source) = source.filter(|source| source.exists()) else {
+            log::warn!(
+                "Skipping the link '{}' to '{}', which is outside the archive",
+                link.display(),
+                target.display()
+            );
+            continue;
+        };
+        log::debug!(
+            "Copying '{}' to '{}' instead of linking it",
+            target.display(),
+            link.display()
+        );
+        let link = extracted_dir.join(&link);
+        if source.is_dir() {
+            copy_tree(&source, &link, cancel)?;
+        } else {
+            std::fs::copy(&source, &link)?;
         }
     }
+    Ok(())
+}
+
+/// Moves a downloaded bare executable to `<dir>/bin/<file_name>` and makes it executable.
+pub(crate) fn place_single_binary(file: &Path, dir: &Path, file_name: &str) -> anyhow::Result<()> {
+    let bin_dir = dir.join("bin");
+    std::fs::create_dir_all(&bin_dir)?;
+    let target = bin_dir.join(file_name);
+    std::fs::rename(file, &target).with_context(|| {
+        format!(
+            "Failed to move '{}' to '{}'",
+            file.display(),
+            target.display()
+        )
+    })?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
 
+        std::fs::set_permissions(&target, std::fs::Permissions::from_mode(0o755))?;
+    }
     Ok(())
 }
+
+/// Extracts a tar-based archive as it is downloaded, reading the chunks sent to `reader`.
+pub(crate) fn extract_archive_stream(
+    archive_type: super::ArchiveType,
+    reader: ChannelReader,
+    url: &str,
+    extracted_dir: &Path,
+    cancel: &CancellationToken,
+) -> Result<(), anyhow::Error> {
+    std::fs::create_dir_all(extracted_dir)?;
+    let extracted_dir = &long_path(extracted_dir)?;
+    let reader = CancellableReader {
+        inner: reader,
+        cancel,
+    };
+    let result = unpack_tar_archive(archive_type, reader, &url, extracted_dir, cancel);
+    crate::check_cancelled(cancel)?;
+    result
+}
+
+/// Unpacks the archive types that can be read without seeking.
+fn unpack_tar_archive(
+    archive_type: super::ArchiveType,
+    reader: impl std::io::Read,
+    archive: &dyn std::fmt::Display,
+    extracted_dir: &Path,
+    cancel: &CancellationToken,
+) -> Result<(), anyhow::Error> {
+    match archive_type {
+        super::ArchiveType::Tar => unpack_tar(reader, "tar", archive, extracted_dir, cancel),
+        super::ArchiveType::TarGz => unpack_tar(
+            GzDecoder::new(reader),
+            "tar.gz",
+            archive,
+            extracted_dir,
+            cancel,
+        ),
+        super::ArchiveType::TarXz => unpack_tar(
+            xz2::read::XzDecoder::new(reader),
+            "tar.xz",
+            archive,
+            extracted_dir,
+            cancel,
+        ),
+        super::ArchiveType::TarBz2 => unpack_tar(
+            bzip2::read::BzDecoder::new(reader),
+            "tar.bz2",
+            archive,
+            extracted_dir,
+            cancel,
+        ),
+        super::ArchiveType::TarZst => unpack_tar(
+            zstd::stream::read::Decoder::new(reader)?,
+            "tar.zst",
+            archive,
+            extracted_dir,
+            cancel,
+        ),
+        super::ArchiveType::Zip | super::ArchiveType::SevenZ | super::ArchiveType::SingleBinary => {
+            anyhow::bail!("{archive_type:?} archives can't be unpacked as a stream")
+        }
+    }
+}
+
+fn unpack_tar(
+    reader: impl std::io::Read,
+    kind: &str,
+    archive: &dyn std::fmt::Display,
+    extracted_dir: &Path,
+    cancel: &CancellationToken,
+) -> Result<(), anyhow::Error> {
+    unpack_tar_entries(tar::Archive::new(reader), extracted_dir, cancel).with_context(|| {
+        anyhow::anyhow!(
+            "Failed to unpack {} archive '{}' into '{}'.",
+            kind,
+            archive,
+            extracted_dir.display()
+        )
+    })
+}
+
+/// Unpacks the entries like [`tar::Archive::unpack`], with the directories last so that their
+/// permissions don't prevent writing the files in them. Symbolic links are kept as links
+/// where they can be created.
+fn unpack_tar_entries<R: std::io::Read>(
+    mut archive: tar::Archive<R>,
+    extracted_dir: &Path,
+    cancel: &CancellationToken,
+) -> anyhow::Result<()> {
+    let mut directories = Vec::new();
+    let mut links = Vec::new();
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let entry_type = entry.header().entry_type();
+        if entry_type.is_dir() {
+            directories.push(entry);
+            continue;
+        }
+        if !entry.unpack_in(extracted_dir).or_else(|err| {
+            // Copied from the target once everything is unpacked instead.
+            match (entry_type.is_symlink(), entry.path(), entry.link_name()) {
+                (true, Ok(path), Ok(Some(target))) => {
+                    log::debug!("Failed to create the link '{}': {err}", path.display());
+                    links.push((path.into_owned(), target.into_owned()));
+                    Ok(false)
+                }
+                _ => Err(err),
+            }
+        })? {
+            continue;
+        }
+        #[cfg(unix)]
+        if !entry_type.is_symlink() && !entry_type.is_hard_link() {
+            set_mode(
+                &extracted_dir.join(entry_path(&entry)?),
+                entry.header().mode()?,
+                false,
+            )?;
+        }
+    }
+    // The deepest directories first, like `tar::Archive::unpack`.
+    directories.sort_by(|a, b| b.path_bytes().cmp(&a.path_bytes()));
+    for mut directory in directories {
+        if directory.unpack_in(extracted_dir)? {
+            #[cfg(unix)]
+            set_mode(
+                &extracted_dir.join(entry_path(&directory)?),
+                directory.header().mode()?,
+                true,
+            )?;
+        }
+    }
+    copy_link_targets(extracted_dir, links, cancel)
+}
+
+/// The path an entry is unpacked to under the extraction directory, with the root and `.`
+/// components that [`tar::Entry::unpack_in`] skips left out.
+#[cfg(unix)]
+fn entry_path<R: std::io::Read>(entry: &tar::Entry<'_, R>) -> std::io::Result<PathBuf> {
+    Ok(entry
+        .path()?
+        .components()
+        .filter(|component| matches!(component, std::path::Component::Normal(_)))
+        .collect())
+}
+
+/// Packs the content of `dir` into a zstd-compressed tar archive at `output`, leaving out the
+/// [`COPY_ALIAS_MARKER`]. Links are stored as links. The archive is written next to `output`
+/// and renamed into place, so that `output` is never partially written.
+pub(crate) fn pack_tar_zst(
+    dir: &Path,
+    output: &Path,
+    cancel: &CancellationToken,
+) -> anyhow::Result<()> {
+    let mut partial = output.as_os_str().to_owned();
+    partial.push(".partial");
+    let partial = PathBuf::from(partial);
+    let result = (|| -> anyhow::Result<()> {
+        let file = std::io::BufWriter::new(std::fs::File::create(&partial)?);
+        let mut builder = tar::Builder::new(zstd::stream::write::Encoder::new(file, 0)?);
+        builder.follow_symlinks(false);
+        let mut entries = std::fs::read_dir(dir)?.collect::<Result<Vec<_>, _>>()?;
+        entries.sort_by_key(|entry| entry.file_name());
+        for entry in entries {
+            crate::check_cancelled(cancel)?;
+            let name = entry.file_name();
+            if name == COPY_ALIAS_MARKER {
+                continue;
+            }
+            if entry.file_type()?.is_dir() {
+                builder.append_dir_all(&name, entry.path())?;
+            } else {
+                builder.append_path_with_name(entry.path(), &name)?;
+            }
+        }
+        let file = builder.into_inner()?.finish()?;
+        file.into_inner()
+            .map_err(std::io::IntoInnerError::into_error)?
+            .sync_all()?;
+        std::fs::rename(&partial, output)?;
+        Ok(())
+    })();
+    if result.is_err() {
+        std::fs::remove_file(&partial).ok();
+    }
+    result.with_context(|| format!("Failed to pack '{}'", output.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::{ArchiveType, JsonItemAction};
+
+    #[test]
+    fn parse_json_array_stops_without_reading_the_rest() {
+        // Everything after the stopping item is malformed and must never be parsed.
+        let input: &[u8] = br#"[1, 2, 3, 4, "not a number"#;
+        let items = parse_json_array(input, |n: u32| match n {
+            1 => JsonItemAction::Skip,
+            3 => JsonItemAction::KeepAndStop(n),
+            _ => JsonItemAction::Keep(n),
+        })
+        .unwrap();
+        assert_eq!(items, vec![2, 3]);
+
+        let items = parse_json_array(&b"[1, 2]"[..], |n: u32| JsonItemAction::Keep(n)).unwrap();
+        assert_eq!(items, vec![1, 2]);
+        assert!(parse_json_array(&b"[1, 2"[..], |n: u32| JsonItemAction::Keep(n)).is_err());
+    }
+
+    #[test]
+    fn cancellable_reader_fails_once_cancelled() {
+        use std::io::Read;
+
+        let cancel = CancellationToken::new();
+        let mut reader = CancellableReader {
+            inner: &b"data"[..],
+            cancel: &cancel,
+        };
+        let mut buf = [0_u8; 2];
+        assert_eq!(reader.read(&mut buf).unwrap(), 2);
+        cancel.cancel();
+        assert!(reader.read(&mut buf).is_err());
+    }
+
+    #[test]
+    fn install_dir_replaces_or_restores_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("tag");
+        let backup = dir.path().join("previous");
+        std::fs::create_dir(&target).unwrap();
+        std::fs::write(target.join("old"), "").unwrap();
+
+        // The source is missing, so the rename fails and the old content comes back.
+        assert!(install_dir(&dir.path().join("missing"), &target, &backup).is_err());
+        assert!(target.join("old").exists());
+
+        let source = dir.path().join("new");
+        std::fs::create_dir(&source).unwrap();
+        std::fs::write(source.join("new"), "").unwrap();
+        install_dir(&source, &target, &backup).unwrap();
+        assert!(target.join("new").exists());
+        assert!(backup.join("old").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn dedup_links_identical_files_once() {
+        use std::os::unix::fs::MetadataExt;
+        let dir = tempfile::tempdir().unwrap();
+        let tags = ["1.0.0", "1.0.1"].map(|tag| dir.path().join(tag));
+        for tag in &tags {
+            std::fs::create_dir_all(tag.join("lib")).unwrap();
+            std::fs::write(tag.join("lib").join("shared"), "same content").unwrap();
+            std::fs::write(tag.join(".avm.version-info.toml"), "same content").unwrap();
+        }
+        std::fs::write(tags[0].join("lib").join("own"), "content one").unwrap();
+        std::fs::write(tags[1].join("lib").join("own"), "content two").unwrap();
+        let cancel = CancellationToken::new();
+
+        let expected = DedupStats {
+            linked_files: 1,
+            saved_bytes: 12,
+        };
+        assert_eq!(dedup_files(&tags, true, &cancel).unwrap(), expected);
+        assert_eq!(dedup_files(&tags, false, &cancel).unwrap(), expected);
+        let ino = |tag: &Path, file: &str| std::fs::metadata(tag.join(file)).unwrap().ino();
+        assert_eq!(ino(&tags[0], "lib/shared"), ino(&tags[1], "lib/shared"));
+        assert_ne!(ino(&tags[0], "lib/own"), ino(&tags[1], "lib/own"));
+        // The files written by avm stay separate.
+        assert_ne!(
+            ino(&tags[0], ".avm.version-info.toml"),
+            ino(&tags[1], ".avm.version-info.toml")
+        );
+        assert_eq!(
+            dedup_files(&tags, false, &cancel).unwrap(),
+            DedupStats::default()
+        );
+    }
+
+    #[test]
+    fn copy_alias_is_listed_and_replaced() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("1.0.0");
+        std::fs::create_dir_all(src.join("bin")).unwrap();
+        std::fs::write(src.join("bin").join("tool"), "v1").unwrap();
+        let alias = dir.path().join("default");
+
+        copy_alias("1.0.0", &src, &alias, &dir.path().join(".tmp.default")).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(alias.join("bin").join("tool")).unwrap(),
+            "v1"
+        );
+        assert_eq!(copy_alias_target(&alias).as_deref(), Some("1.0.0"));
+        let mut tags = list_tags(dir.path(), ".tmp.").unwrap();
+        tags.sort();
+        assert_eq!(
+            tags,
+            [
+                ("1.0.0".into(), None),
+                ("default".into(), Some("1.0.0".into()))
+            ]
+        );
+
+        // A copied alias is replaced like a link, a regular tag is not.
+        let kind = set_alias_tag(
+            "1.0.0",
+            &src,
+            "default",
+            &alias,
+            &dir.path().join(".tmp.default"),
+        )
+        .unwrap();
+        assert_eq!(kind, AliasKind::Link);
+        assert!(
+            set_alias_tag("default", &alias, "1.0.0", &src, &dir.path().join(".tmp.x")).is_err()
+        );
+    }
+
+    #[test]
+    fn alias_store_migrates_links_and_outlives_them() {
+        let dir = tempfile::tempdir().unwrap();
+        let src = dir.path().join("1.0.0");
+        std::fs::create_dir(&src).unwrap();
+        create_link(&src, &dir.path().join("default")).unwrap();
+
+        // Without a store, the aliases come from the links.
+        let store = AliasStore::load(dir.path()).unwrap();
+        assert_eq!(store.aliases["default"], "1.0.0");
+
+        AliasStore::update(dir.path(), |store| {
+            store.aliases.insert("lts".into(), "1.0.0".into());
+            Ok(())
+        })
+        .unwrap();
+        remove_link(&dir.path().join("default")).unwrap();
+        assert_eq!(
+            list_tags(dir.path(), TMP_PREFIX).unwrap(),
+            [
+                ("1.0.0".into(), None),
+                ("default".into(), Some("1.0.0".into())),
+                ("lts".into(), Some("1.0.0".into()))
+            ]
+        );
+
+        // A failed update leaves the store as it was.
+        assert!(
+            AliasStore::update(dir.path(), |store| -> anyhow::Result<()> {
+                store.aliases.clear();
+                anyhow::bail!("failed")
+            })
+            .is_err()
+        );
+        assert_eq!(AliasStore::load(dir.path()).unwrap().aliases.len(), 2);
+    }
+
+    #[test]
+    fn operating_keeps_partial_files_until_reused() {
+        let dir = tempfile::tempdir().unwrap();
+        let tmp_dir = dir.path().join(".tmp.tag");
+
+        let mut operating = Operating::create_in_tmp_dir(tmp_dir.clone(), false)
+            .ok()
+            .unwrap();
+        operating.keep_partial = true;
+        std::fs::write(tmp_dir.join("download"), "").unwrap();
+        drop(operating);
+        assert!(tmp_dir.join("download").exists());
+        assert!(!tmp_dir.join(".lock").exists());
+
+        let mut operating = Operating::create_in_tmp_dir(tmp_dir.clone(), false)
+            .ok()
+            .unwrap();
+        assert!(!tmp_dir.join("download").exists());
+        operating.keep_partial = true;
+        operating.commit();
+        drop(operating);
+        assert!(!tmp_dir.exists());
+    }
+
+    #[test]
+    fn extract_compressed_tar_archives() {
+        let mut builder = tar::Builder::new(Vec::new());
+        let mut header = tar::Header::new_gnu();
+        header.set_size(5);
+        header.set_mode(0o644);
+        builder
+            .append_data(&mut header, "tool/file", &b"hello"[..])
+            .unwrap();
+        let tar = builder.into_inner().unwrap();
+
+        let mut bz2 = bzip2::write::BzEncoder::new(Vec::new(), bzip2::Compression::fast());
+        std::io::Write::write_all(&mut bz2, &tar).unwrap();
+        let archives = [
+            (ArchiveType::Tar, tar.clone()),
+            (ArchiveType::TarBz2, bz2.finish().unwrap()),
+            (ArchiveType::TarZst, zstd::encode_all(&tar[..], 0).unwrap()),
+        ];
+
+        let dir = tempfile::tempdir().unwrap();
+        let cancel = CancellationToken::new();
+        for (i, (archive_type, content)) in archives.into_iter().enumerate() {
+            let archive_path = dir.path().join(format!("archive{i}"));
+            let extracted_dir = dir.path().join(format!("extracted{i}"));
+            std::fs::write(&archive_path, content).unwrap();
+            extract_archive(archive_type, &archive_path, &extracted_dir, &cancel).unwrap();
+            assert_eq!(
+                std::fs::read(extracted_dir.join("tool").join("file")).unwrap(),
+                b"hello"
+            );
+        }
+    }
+
+    /// A 7z archive of files with the paths and the contents of `entries`.
+    fn seven_zip_of(entries: &[(&str, &str)]) -> Vec<u8> {
+        let mut writer = sevenz_rust::SevenZWriter::new(std::io::Cursor::new(Vec::new())).unwrap();
+        for &(path, content) in entries {
+            let mut entry = sevenz_rust::SevenZArchiveEntry::new();
+            entry.name = path.to_owned();
+            writer
+                .push_archive_entry(entry, Some(content.as_bytes()))
+                .unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    #[test]
+    fn extract_7z_archives() {
+        let dir = extract_to(
+            ArchiveType::SevenZ,
+            seven_zip_of(&[("tool/bin/file", "hello"), ("tool/README", "readme")]),
+        );
+        let tool = dir.path().join("extracted").join("tool");
+        assert_eq!(
+            std::fs::read(tool.join("bin").join("file")).unwrap(),
+            b"hello"
+        );
+        assert_eq!(std::fs::read(tool.join("README")).unwrap(), b"readme");
+    }
+
+    #[test]
+    fn extract_7z_refuses_entries_outside_the_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let cancel = CancellationToken::new();
+        let outside = dir.path().join("outside");
+        for (i, name) in ["tool/../../outside", &*outside.to_string_lossy()]
+            .into_iter()
+            .enumerate()
+        {
+            let archive_path = dir.path().join(format!("archive{i}"));
+            let extracted_dir = dir.path().join(format!("extracted{i}"));
+            std::fs::write(&archive_path, seven_zip_of(&[(name, "evil")])).unwrap();
+            let error =
+                extract_archive(ArchiveType::SevenZ, &archive_path, &extracted_dir, &cancel)
+                    .unwrap_err();
+            assert!(
+                format!("{error:#}").contains("is outside the archive"),
+                "{error:#}"
+            );
+            assert!(!outside.exists());
+        }
+    }
+
+    /// A tar of `entries`, each a path with its mode and the content of a file, or the target
+    /// of a symbolic link, or `None` for a directory.
+    fn tar_of(entries: &[(&str, u32, Option<&str>, bool)]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for &(path, mode, content, is_link) in entries {
+            let mut header = tar::Header::new_gnu();
+            header.set_mode(mode);
+            match (content, is_link) {
+                (Some(target), true) => {
+                    header.set_entry_type(tar::EntryType::Symlink);
+                    header.set_size(0);
+                    builder.append_link(&mut header, path, target).unwrap();
+                }
+                (Some(content), false) => {
+                    header.set_size(content.len() as u64);
+                    builder
+                        .append_data(&mut header, path, content.as_bytes())
+                        .unwrap();
+                }
+                (None, _) => {
+                    header.set_entry_type(tar::EntryType::Directory);
+                    header.set_size(0);
+                    builder.append_data(&mut header, path, &[][..]).unwrap();
+                }
+            }
+        }
+        builder.into_inner().unwrap()
+    }
+
+    fn extract_to(archive_type: ArchiveType, content: Vec<u8>) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let archive_path = dir.path().join("archive");
+        std::fs::write(&archive_path, content).unwrap();
+        let extracted_dir = dir.path().join("extracted");
+        extract_archive(
+            archive_type,
+            &archive_path,
+            &extracted_dir,
+            &CancellationToken::new(),
+        )
+        .unwrap();
+        dir
+    }
+
+    #[test]
+    fn extract_archives_with_long_paths() {
+        let deep = format!("tool/{}/file", vec!["a".repeat(50); 6].join("/"));
+        assert!(deep.len() > 260);
+
+        let tar = tar_of(&[(&deep, 0o644, Some("deep"), false)]);
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        zip.start_file(&*deep, zip::write::SimpleFileOptions::default())
+            .unwrap();
+        std::io::Write::write_all(&mut zip, b"deep").unwrap();
+        let zip = zip.finish().unwrap().into_inner();
+
+        for (archive_type, content) in [(ArchiveType::Tar, tar), (ArchiveType::Zip, zip)] {
+            let dir = extract_to(archive_type, content);
+            let path = long_path(&dir.path().join("extracted").join(&deep)).unwrap();
+            assert_eq!(std::fs::read(path).unwrap(), b"deep");
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn extract_tar_keeps_links_and_sane_modes() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tar = tar_of(&[
+            ("tool/bin", 0o555, None, false),
+            ("tool/bin/java", 0o777, Some("../lib/java"), true),
+            ("tool/lib/java", 0o755, Some("java"), false),
+            ("tool/lib/setuid", 0o4755, Some("setuid"), false),
+            ("tool/lib/readonly", 0o444, Some("readonly"), false),
+        ]);
+        let dir = extract_to(ArchiveType::Tar, tar);
+        let tool = dir.path().join("extracted").join("tool");
+        let mode = |path: &str| {
+            std::fs::metadata(tool.join(path))
+                .unwrap()
+                .permissions()
+                .mode()
+                & 0o7777
+        };
+
+        assert_eq!(
+            std::fs::read_link(tool.join("bin/java")).unwrap(),
+            Path::new("../lib/java")
+        );
+        assert_eq!(std::fs::read(tool.join("bin/java")).unwrap(), b"java");
+        assert_eq!(mode("bin"), 0o755);
+        assert_eq!(mode("lib/java"), 0o755);
+        assert_eq!(mode("lib/setuid"), 0o755);
+        assert_eq!(mode("lib/readonly"), 0o644);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn extract_zip_keeps_links_and_sane_modes() {
+        use std::os::unix::fs::PermissionsExt;
+        use zip::write::SimpleFileOptions;
+
+        let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+        let options = SimpleFileOptions::default();
+        zip.add_directory("tool/bin", options.unix_permissions(0o555))
+            .unwrap();
+        zip.start_file("tool/bin/java", options.unix_permissions(0o755))
+            .unwrap();
+        std::io::Write::write_all(&mut zip, b"java").unwrap();
+        zip.add_symlink("tool/java", "bin/java", options).unwrap();
+        let zip = zip.finish().unwrap().into_inner();
+
+        let dir = extract_to(ArchiveType::Zip, zip);
+        let tool = dir.path().join("extracted").join("tool");
+        let mode = |path: &str| {
+            std::fs::metadata(tool.join(path))
+                .unwrap()
+                .permissions()
+                .mode()
+                & 0o7777
+        };
+        assert_eq!(
+            std::fs::read_link(tool.join("java")).unwrap(),
+            Path::new("bin/java")
+        );
+        assert_eq!(mode("bin"), 0o755);
+        assert_eq!(mode("bin/java"), 0o755);
+    }
+
+    #[test]
+    fn extract_zip_never_writes_through_links() {
+        use zip::write::SimpleFileOptions;
+
+        let dir = tempfile::tempdir().unwrap();
+        let outside = dir.path().join("outside");
+        std::fs::create_dir(&outside).unwrap();
+        let zip_of = |entries: &[(&str, &str, bool)]| {
+            let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+            let options = SimpleFileOptions::default();
+            for &(name, content, is_link) in entries {
+                if is_link {
+                    zip.add_symlink(name, content, options).unwrap();
+                } else {
+                    zip.start_file(name, options).unwrap();
+                    std::io::Write::write_all(&mut zip, content.as_bytes()).unwrap();
+                }
+            }
+            zip.finish().unwrap().into_inner()
+        };
+        let extract = |name: &str, content: Vec<u8>| {
+            let archive_path = dir.path().join(format!("{name}.zip"));
+            std::fs::write(&archive_path, content).unwrap();
+            extract_archive(
+                ArchiveType::Zip,
+                &archive_path,
+                &dir.path().join(name),
+                &CancellationToken::new(),
+            )
+        };
+
+        let absolute = outside.to_str().unwrap();
+        for (name, target) in [("absolute", absolute), ("relative", "../../outside")] {
+            let zip = zip_of(&[
+                ("tool/evil", target, true),
+                ("tool/evil/authorized_keys", "key", false),
+            ]);
+            extract(name, zip).unwrap();
+            let extracted = dir.path().join(name).join("tool/evil");
+            assert!(!extracted.is_symlink());
+            assert_eq!(
+                std::fs::read(extracted.join("authorized_keys")).unwrap(),
+                b"key"
+            );
+        }
+
+        // Even a link inside the archive is not written through.
+        let zip = zip_of(&[
+            ("tool/lib/file", "lib", false),
+            ("tool/link", "lib", true),
+            ("tool/link/file", "evil", false),
+        ]);
+        let result = extract("inside", zip);
+        #[cfg(unix)]
+        assert!(result.is_err());
+        // Elsewhere the link may be a copy, which is safe to write into.
+        #[cfg(not(unix))]
+        assert!(result.is_ok() || !dir.path().join("inside/tool/link").is_symlink());
+        assert_eq!(
+            std::fs::read(dir.path().join("inside/tool/lib/file")).unwrap(),
+            b"lib"
+        );
+        assert_eq!(std::fs::read_dir(&outside).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn links_that_cannot_be_created_are_copied() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("tool/lib/conf")).unwrap();
+        std::fs::write(dir.path().join("tool/lib/java"), b"java").unwrap();
+        std::fs::write(dir.path().join("tool/lib/conf/file"), b"conf").unwrap();
+        std::fs::create_dir_all(dir.path().join("tool/bin")).unwrap();
+
+        copy_link_targets(
+            dir.path(),
+            vec![
+                ("tool/bin/java".into(), "../lib/java".into()),
+                ("tool/conf".into(), "lib/conf".into()),
+                ("tool/outside".into(), "../../etc/passwd".into()),
+            ],
+            &CancellationToken::new(),
+        )
+        .unwrap();
+        assert_eq!(
+            std::fs::read(dir.path().join("tool/bin/java")).unwrap(),
+            b"java"
+        );
+        assert_eq!(
+            std::fs::read(dir.path().join("tool/conf/file")).unwrap(),
+            b"conf"
+        );
+        assert!(!dir.path().join("tool/outside").exists());
+    }
+}
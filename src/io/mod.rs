@@ -1,34 +1,234 @@
+use std::time::{Duration, Instant};
 use std::{fs::File, io::Write, path::PathBuf};
 
 use async_trait::async_trait;
+use sha1::Digest;
 use smol_str::SmolStr;
 
-use crate::HttpClient;
+use crate::download_cache::DownloadCache;
+use crate::{CancellationToken, FileHash, HttpClient};
 
 pub mod blocking;
 
-#[derive(Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ArchiveType {
     Zip,
+    Tar,
     TarGz,
     TarXz,
+    TarBz2,
+    TarZst,
+    SevenZ,
+    /// A bare executable rather than an archive. It is never detected, tools that publish
+    /// one declare it, and the installer places it under the name the tool gives.
+    SingleBinary,
 }
 
 impl ArchiveType {
-    pub(crate) fn from_path(path: &[u8]) -> anyhow::Result<ArchiveType> {
-        if path.ends_with(b".zip") {
-            Ok(ArchiveType::Zip)
-        } else if path.ends_with(b".tar.gz") || path.ends_with(b".tgz") {
-            Ok(ArchiveType::TarGz)
-        } else if path.ends_with(b".tar.xz") {
-            Ok(ArchiveType::TarXz)
+    pub(crate) fn from_file_name(name: &[u8]) -> Option<ArchiveType> {
+        if name.ends_with(b".zip") {
+            Some(ArchiveType::Zip)
+        } else if name.ends_with(b".tar.gz") || name.ends_with(b".tgz") {
+            Some(ArchiveType::TarGz)
+        } else if name.ends_with(b".tar.xz") || name.ends_with(b".txz") {
+            Some(ArchiveType::TarXz)
+        } else if name.ends_with(b".tar.bz2") || name.ends_with(b".tbz2") {
+            Some(ArchiveType::TarBz2)
+        } else if name.ends_with(b".tar.zst") || name.ends_with(b".tzst") {
+            Some(ArchiveType::TarZst)
+        } else if name.ends_with(b".tar") {
+            Some(ArchiveType::Tar)
+        } else if name.ends_with(b".7z") {
+            Some(ArchiveType::SevenZ)
         } else {
-            Err(anyhow::anyhow!(
-                "unknown archive type from {}",
-                String::from_utf8_lossy(path)
-            ))
+            None
         }
     }
+
+    /// Whether the archive can be unpacked as it is read, without seeking.
+    fn is_streamable(self) -> bool {
+        !matches!(
+            self,
+            ArchiveType::Zip | ArchiveType::SevenZ | ArchiveType::SingleBinary
+        )
+    }
+
+    /// Detect from the extension, falling back to the magic bytes of the file.
+    pub(crate) fn from_path(path: &std::path::Path) -> anyhow::Result<ArchiveType> {
+        match Self::from_file_name(path.as_os_str().as_encoded_bytes()) {
+            Some(archive_type) => Ok(archive_type),
+            None => blocking::detect_archive_type(path),
+        }
+    }
+
+    fn from_url(url: &reqwest::Url) -> Option<ArchiveType> {
+        Self::from_file_name(url.path().as_bytes())
+    }
+
+    fn from_content_disposition(value: &str) -> Option<ArchiveType> {
+        value.split(';').find_map(|param| {
+            let (key, value) = param.split_once('=')?;
+            let value = match key.trim() {
+                "filename" => value.trim().trim_matches('"'),
+                // RFC 5987 form, for example `UTF-8''node.tar.xz`.
+                "filename*" => value.trim().rsplit('\'').next()?,
+                _ => return None,
+            };
+            Self::from_file_name(value.as_bytes())
+        })
+    }
+
+    fn from_content_type(value: &str) -> Option<ArchiveType> {
+        let essence = value.split(';').next()?.trim();
+        match essence {
+            "application/zip" | "application/x-zip-compressed" => Some(ArchiveType::Zip),
+            "application/gzip" | "application/x-gzip" | "application/x-compressed-tar" => {
+                Some(ArchiveType::TarGz)
+            }
+            "application/x-xz" | "application/x-xz-compressed-tar" => Some(ArchiveType::TarXz),
+            "application/x-bzip2" | "application/x-bzip2-compressed-tar" => {
+                Some(ArchiveType::TarBz2)
+            }
+            "application/zstd" | "application/x-zstd-compressed-tar" => Some(ArchiveType::TarZst),
+            "application/x-tar" => Some(ArchiveType::Tar),
+            "application/x-7z-compressed" => Some(ArchiveType::SevenZ),
+            _ => None,
+        }
+    }
+
+    /// Detect from the magic bytes at the start of the file, which need at least 262 bytes
+    /// to recognize a plain tar.
+    pub(crate) fn from_magic(header: &[u8]) -> Option<ArchiveType> {
+        if header.starts_with(b"PK\x03\x04") || header.starts_with(b"PK\x05\x06") {
+            Some(ArchiveType::Zip)
+        } else if header.starts_with(&[0x1f, 0x8b]) {
+            Some(ArchiveType::TarGz)
+        } else if header.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(ArchiveType::TarXz)
+        } else if header.starts_with(b"BZh") {
+            Some(ArchiveType::TarBz2)
+        } else if header.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(ArchiveType::TarZst)
+        } else if header.starts_with(&[b'7', b'z', 0xbc, 0xaf, 0x27, 0x1c]) {
+            Some(ArchiveType::SevenZ)
+        } else if header.get(257..262) == Some(b"ustar") {
+            Some(ArchiveType::Tar)
+        } else {
+            None
+        }
+    }
+
+    /// Detect from the requested URL, the URL after redirects and the response headers.
+    /// `None` if the downloaded content has to be inspected.
+    fn from_response(url: &str, response: &reqwest::Response) -> Option<ArchiveType> {
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+        };
+        reqwest::Url::parse(url)
+            .ok()
+            .and_then(|url| Self::from_url(&url))
+            .or_else(|| Self::from_url(response.url()))
+            .or_else(|| {
+                header(reqwest::header::CONTENT_DISPOSITION)
+                    .and_then(Self::from_content_disposition)
+            })
+            .or_else(|| header(reqwest::header::CONTENT_TYPE).and_then(Self::from_content_type))
+    }
+}
+
+/// What to do with an item of a streamed JSON array.
+pub enum JsonItemAction<U> {
+    Skip,
+    Keep(U),
+    /// Keep the item and stop reading, the rest of the response is not downloaded.
+    KeepAndStop(U),
+}
+
+/// Parse a response whose body is a JSON array while it is being downloaded, without
+/// holding the whole body or every deserialized item in memory.
+pub async fn stream_json_array<T, U, F>(
+    mut response: reqwest::Response,
+    filter_map: F,
+) -> anyhow::Result<Vec<U>>
+where
+    T: serde::de::DeserializeOwned,
+    U: Send + 'static,
+    F: FnMut(T) -> JsonItemAction<U> + Send + 'static,
+{
+    let (sender, receiver) = tokio::sync::mpsc::channel(16);
+    let parse = crate::spawn_blocking(move || {
+        blocking::parse_json_array(blocking::ChannelReader::new(receiver), filter_map)
+    });
+    let feed = async move {
+        while let Some(chunk) = response.chunk().await? {
+            if sender.send(chunk).await.is_err() {
+                // The parser stopped early.
+                break;
+            }
+        }
+        anyhow::Ok(())
+    };
+
+    let (feed, parse) = tokio::join!(feed, parse);
+    // A broken download also makes the parser fail, report the cause instead.
+    feed?;
+    parse
+}
+
+/// Downloads `url` to `dest` without extracting it and verifies it against `expected`,
+/// returning its digests. The SHA-256 digest is computed as well if `expected` is empty.
+pub(crate) async fn download_verified(
+    client: &HttpClient,
+    url: &str,
+    expected: &FileHash,
+    dest: &std::path::Path,
+    cancel: &CancellationToken,
+) -> crate::Result<FileHash> {
+    save_verified(client, client.get(url), url, expected, dest, cancel).await
+}
+
+/// Like [`download_verified`] for a request already built for `url`.
+async fn save_verified(
+    client: &HttpClient,
+    request: crate::HttpRequest<'_>,
+    url: &str,
+    expected: &FileHash,
+    dest: &std::path::Path,
+    cancel: &CancellationToken,
+) -> crate::Result<FileHash> {
+    let mut response = request.send().await?;
+    if !response.status().is_success() {
+        return Err(crate::AvmError::HttpStatus {
+            url: url.to_owned(),
+            status: response.status(),
+            body: response.text().await?,
+        });
+    }
+    let hasher = if expected.is_empty() {
+        FileHasher::new(&FileHash::from_sha256(SmolStr::default()))
+    } else {
+        FileHasher::new(expected)
+    };
+    let mut writer = ChunkWriter::spawn(
+        ChunkTarget::file(File::create(dest)?, client.download_buffer_size()),
+        hasher,
+    );
+    loop {
+        let chunk = tokio::select! {
+            chunk = response.chunk() => chunk?,
+            _ = cancel.cancelled() => return Err(crate::AvmError::Cancelled),
+        };
+        let Some(chunk) = chunk else {
+            break;
+        };
+        writer.write(chunk).await?;
+    }
+    let digest = writer.finish().await?;
+    verify_digest(expected, &digest)?;
+    Ok(digest)
 }
 
 pub enum VerifyMethod {
@@ -36,36 +236,358 @@ pub enum VerifyMethod {
     Sha1(SmolStr),
 }
 
+/// Hashes data as it arrives, with the algorithms present in an expected hash.
+pub(crate) struct FileHasher {
+    sha1: Option<sha1::Sha1>,
+    sha256: Option<sha2::Sha256>,
+    sha512: Option<sha2::Sha512>,
+}
+
+impl FileHasher {
+    pub(crate) fn new(expected: &FileHash) -> Self {
+        Self {
+            sha1: expected.sha1.as_ref().map(|_| sha1::Sha1::new()),
+            sha256: expected.sha256.as_ref().map(|_| sha2::Sha256::new()),
+            sha512: expected.sha512.as_ref().map(|_| sha2::Sha512::new()),
+        }
+    }
+
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        if let Some(sha1) = &mut self.sha1 {
+            sha1.update(data);
+        }
+        if let Some(sha256) = &mut self.sha256 {
+            sha256.update(data);
+        }
+        if let Some(sha512) = &mut self.sha512 {
+            sha512.update(data);
+        }
+    }
+
+    /// The lowercase hex digests.
+    pub(crate) fn finalize(self) -> FileHash {
+        FileHash {
+            sha1: self.sha1.map(|h| hex::encode(h.finalize()).into()),
+            sha256: self.sha256.map(|h| hex::encode(h.finalize()).into()),
+            sha512: self.sha512.map(|h| hex::encode(h.finalize()).into()),
+        }
+    }
+}
+
+/// Compares the digests computed by [`FileHasher`] with the expected hash.
+pub(crate) fn verify_digest(expected: &FileHash, actual: &FileHash) -> crate::Result<()> {
+    let pairs = [
+        ("Sha1", &expected.sha1, &actual.sha1),
+        ("Sha256", &expected.sha256, &actual.sha256),
+        ("Sha512", &expected.sha512, &actual.sha512),
+    ];
+    for (algorithm, expected, actual) in pairs {
+        let Some(expected) = expected else {
+            continue;
+        };
+        let expected = expected.to_ascii_lowercase();
+        let actual = actual.clone().unwrap_or_default();
+        if expected != actual {
+            return Err(crate::AvmError::HashMismatch {
+                algorithm,
+                expected: expected.into(),
+                actual,
+            });
+        }
+    }
+    log::debug!("Hash verification passed");
+    Ok(())
+}
+
 pub struct ArchiveExtractInfo {
+    /// Not written if the archive was extracted while downloading.
     pub archive_path: PathBuf,
     pub archive_type: ArchiveType,
     pub extracted_dir: PathBuf,
+    /// The digests of the downloaded archive, for the algorithms of the expected hash.
+    pub digest: FileHash,
+    /// Cancels the download and the blocking work of extraction and the callbacks.
+    pub cancel: CancellationToken,
+    /// `None` if the archive was not downloaded, such as one from the download cache.
+    pub source: Option<DownloadSource>,
+}
+
+/// Where a downloaded archive came from, to explain a hash mismatch.
+pub struct DownloadSource {
+    /// The URL before the mirrors are applied.
+    pub url: SmolStr,
+    /// The URL requested instead, if a mirror applies.
+    pub mirrored_url: Option<SmolStr>,
+    pub downloaded_size: u64,
+    /// The size announced by the server.
+    pub content_length: Option<u64>,
+    client: HttpClient,
+    expected_hash: FileHash,
+}
+
+impl DownloadSource {
+    /// Turns a hash mismatch into [`crate::AvmError::DownloadHashMismatch`], other errors are
+    /// returned as is. If the archive was downloaded through a mirror, the direct URL is
+    /// downloaded to `dir` to find out whether the mirror is to blame.
+    async fn explain(
+        &self,
+        err: anyhow::Error,
+        dir: &std::path::Path,
+        cancel: &CancellationToken,
+    ) -> anyhow::Error {
+        let Some(crate::AvmError::HashMismatch {
+            algorithm,
+            expected,
+            actual,
+        }) = err.downcast_ref::<crate::AvmError>()
+        else {
+            return err;
+        };
+        let blamed_mirror = match &self.mirrored_url {
+            Some(_) => self.blame_mirror(dir, cancel).await,
+            None => None,
+        };
+        crate::AvmError::DownloadHashMismatch(Box::new(crate::error::DownloadMismatch {
+            url: self.url.clone(),
+            mirrored_url: self.mirrored_url.clone(),
+            algorithm,
+            expected: expected.clone(),
+            actual: actual.clone(),
+            downloaded_size: self.downloaded_size,
+            content_length: self.content_length,
+            blamed_mirror,
+        }))
+        .into()
+    }
+
+    /// The `from` of the mirror entry applied to the URL, if the direct URL serves the
+    /// expected file.
+    async fn blame_mirror(
+        &self,
+        dir: &std::path::Path,
+        cancel: &CancellationToken,
+    ) -> Option<SmolStr> {
+        log::info!(
+            "Downloading {} without the mirror to check the mirrored archive",
+            self.url
+        );
+        let request = self.client.get_direct(&self.url);
+        let dest = dir.join("direct");
+        match save_verified(
+            &self.client,
+            request,
+            &self.url,
+            &self.expected_hash,
+            &dest,
+            cancel,
+        )
+        .await
+        {
+            Ok(_) => self.client.mirror_from(&self.url),
+            Err(err) => {
+                log::debug!("The direct download doesn't verify either: {err:#}");
+                None
+            }
+        }
+    }
 }
 
 #[async_trait]
 pub trait DownloadExtractCallback {
+    /// The type of the download if it is known in advance, otherwise it is detected.
+    fn archive_type(&self) -> Option<ArchiveType> {
+        None
+    }
     async fn on_downloaded(&mut self, info: &ArchiveExtractInfo) -> anyhow::Result<()>;
     async fn on_extracted(&mut self, info: &ArchiveExtractInfo) -> anyhow::Result<()>;
 }
 
+/// How many downloaded chunks can wait for [`ChunkWriter`], so that a slow disk only stalls
+/// the download once it falls this far behind.
+const CHUNK_CHANNEL_CAPACITY: usize = 64;
+
+/// Hashes the downloaded chunks and writes them to their [`ChunkTarget`] on a blocking task,
+/// fed through a bounded channel, so that neither holds up the async runtime.
+struct ChunkWriter {
+    sender: tokio::sync::mpsc::Sender<bytes::Bytes>,
+    task: tokio::task::JoinHandle<anyhow::Result<FileHash>>,
+}
+
+enum ChunkTarget {
+    File(std::io::BufWriter<File>),
+    /// Passed on to a [`StreamExtractor`].
+    Extractor(tokio::sync::mpsc::Sender<bytes::Bytes>),
+}
+
+impl ChunkTarget {
+    fn file(file: File, buffer_size: usize) -> Self {
+        ChunkTarget::File(std::io::BufWriter::with_capacity(buffer_size, file))
+    }
+}
+
+impl ChunkWriter {
+    fn spawn(mut target: ChunkTarget, mut hasher: FileHasher) -> Self {
+        let (sender, mut receiver) =
+            tokio::sync::mpsc::channel::<bytes::Bytes>(CHUNK_CHANNEL_CAPACITY);
+        let task = tokio::task::spawn_blocking(move || {
+            while let Some(chunk) = receiver.blocking_recv() {
+                hasher.update(&chunk);
+                match &mut target {
+                    ChunkTarget::File(file) => file.write_all(&chunk)?,
+                    ChunkTarget::Extractor(sender) => {
+                        if sender.blocking_send(chunk).is_err() {
+                            anyhow::bail!("Extraction stopped before the download completed");
+                        }
+                    }
+                }
+            }
+            if let ChunkTarget::File(file) = target {
+                file.into_inner().map_err(|err| err.into_error())?;
+            }
+            Ok(hasher.finalize())
+        });
+        Self { sender, task }
+    }
+
+    async fn write(&mut self, chunk: bytes::Bytes) -> anyhow::Result<()> {
+        if self.sender.send(chunk).await.is_err() {
+            return Err(self.stopped_error().await);
+        }
+        Ok(())
+    }
+
+    /// Why the writer stopped before the end of the download.
+    async fn stopped_error(&mut self) -> anyhow::Error {
+        match (&mut self.task).await {
+            Ok(Err(err)) => err,
+            _ => anyhow::anyhow!("Writing the download stopped before it completed"),
+        }
+    }
+
+    /// Ends the stream and waits for the remaining chunks to be written, returning the
+    /// digests of the download.
+    async fn finish(self) -> anyhow::Result<FileHash> {
+        drop(self.sender);
+        match self.task.await {
+            Ok(result) => result,
+            Err(_) => Err(anyhow::anyhow!("Failed to join spawned IO task")),
+        }
+    }
+}
+
+/// Extracts the download by a blocking task as it arrives, without saving the archive. The
+/// chunks are sent by the [`ChunkWriter`] of the download.
+struct StreamExtractor {
+    archive_type: ArchiveType,
+    task: tokio::task::JoinHandle<anyhow::Result<()>>,
+}
+
+impl StreamExtractor {
+    /// Returns the extractor with the target to write the chunks to.
+    fn spawn(
+        archive_type: ArchiveType,
+        url: &str,
+        extracted_dir: PathBuf,
+        cancel: CancellationToken,
+    ) -> (Self, ChunkTarget) {
+        let (sender, receiver) = tokio::sync::mpsc::channel(16);
+        let url = url.to_owned();
+        let task = tokio::task::spawn_blocking(move || {
+            blocking::extract_archive_stream(
+                archive_type,
+                blocking::ChannelReader::new(receiver),
+                &url,
+                &extracted_dir,
+                &cancel,
+            )
+        });
+        (Self { archive_type, task }, ChunkTarget::Extractor(sender))
+    }
+
+    /// Why the extraction stopped before the end of the stream.
+    async fn stopped_error(&mut self) -> anyhow::Error {
+        match (&mut self.task).await {
+            Ok(Err(err)) => err,
+            _ => anyhow::anyhow!("Extraction stopped before the download completed"),
+        }
+    }
+
+    /// Waits for the extraction to complete, once the stream is ended by finishing the
+    /// [`ChunkWriter`].
+    async fn finish(self) -> anyhow::Result<()> {
+        match self.task.await {
+            Ok(result) => result,
+            Err(_) => Err(anyhow::anyhow!("Failed to join spawned IO task")),
+        }
+    }
+}
+
+/// Slows a download down to a rate by pausing whenever it gets ahead.
+struct Throttle {
+    bytes_per_second: u64,
+    started: Instant,
+}
+
+impl Throttle {
+    /// How long to pause after `downloaded` bytes to get back to the rate.
+    fn delay(&self, downloaded: u64, elapsed: Duration) -> Option<Duration> {
+        let due = Duration::from_secs_f64(downloaded as f64 / self.bytes_per_second as f64);
+        due.checked_sub(elapsed).filter(|delay| !delay.is_zero())
+    }
+}
+
 struct DownloadingState {
     response: reqwest::Response,
-    archive_file: File,
+    writer: ChunkWriter,
+    /// `None` if the archive is saved to be extracted once downloaded.
+    extractor: Option<StreamExtractor>,
+    throttle: Option<Throttle>,
     total_size: Option<u64>,
     downloaded_size: u64,
+    archive_path: PathBuf,
+    /// `None` if it is detected from the content once downloaded.
+    archive_type: Option<ArchiveType>,
+    extracted_dir: PathBuf,
+    /// Where to keep the archive once it matches the expected SHA-256 digest.
+    cache: Option<(DownloadCache, SmolStr)>,
+    cancel: CancellationToken,
+    url: SmolStr,
+    client: HttpClient,
+    expected_hash: FileHash,
+}
+
+impl DownloadingState {
+    fn source(&self) -> DownloadSource {
+        DownloadSource {
+            url: self.url.clone(),
+            mirrored_url: self.client.mirrored_url(&self.url).map(SmolStr::from),
+            downloaded_size: self.downloaded_size,
+            content_length: self.total_size,
+            client: self.client.clone(),
+            expected_hash: self.expected_hash.clone(),
+        }
+    }
 }
 
 enum DownloadExtractStateInner {
     Downloading(
         blocking::Operating,
-        ArchiveExtractInfo,
         DownloadingState,
         Box<dyn DownloadExtractCallback + Send>,
     ),
+    /// Checking the hash of the download. The `bool` tells whether the archive was already
+    /// extracted while downloading.
+    Verifying(
+        blocking::Operating,
+        ArchiveExtractInfo,
+        Box<dyn DownloadExtractCallback + Send>,
+        bool,
+    ),
     Extracting(
         blocking::Operating,
         ArchiveExtractInfo,
         Box<dyn DownloadExtractCallback + Send>,
+        bool,
     ),
     Stopped,
 }
@@ -76,48 +598,176 @@ impl DownloadExtractState {
         client: &HttpClient,
         url: &str,
         mut operating: blocking::Operating,
+        expected_hash: &FileHash,
+        stream_extract: bool,
         custom_action: Box<dyn DownloadExtractCallback + Send>,
-    ) -> anyhow::Result<Self> {
+        cancel: CancellationToken,
+    ) -> crate::Result<Self> {
+        let archive_path = operating.tmp_dir_path.join("download");
+        let extracted_dir = operating.tmp_dir_path.join("extracted");
+        if let Some(cache) = client.download_cache() {
+            let (cache, expected_hash, dest, fetch_cancel) = (
+                cache.clone(),
+                expected_hash.clone(),
+                archive_path.clone(),
+                cancel.clone(),
+            );
+            let cached = crate::spawn_blocking(move || {
+                cache.fetch_blocking(&expected_hash, &dest, &fetch_cancel)
+            })
+            .await;
+            match cached {
+                Ok(Some(digest)) => {
+                    log::info!("Using the cached archive of {url}");
+                    operating.drop_should_not_block = true;
+                    let archive_type = reqwest::Url::parse(url)
+                        .ok()
+                        .and_then(|url| ArchiveType::from_url(&url));
+                    return Self::downloaded(
+                        operating,
+                        archive_type,
+                        digest,
+                        custom_action,
+                        cancel,
+                    )
+                    .await;
+                }
+                Ok(None) => {}
+                Err(_) if cancel.is_cancelled() => return Err(crate::AvmError::Cancelled),
+                Err(err) => log::warn!("Failed to read the download cache: {err:#}"),
+            }
+        }
+
         let response = client.get(url).send().await?;
         if !response.status().is_success() {
-            anyhow::bail!(
-                "Failed to download '{}': {}\n{}",
-                url,
-                response.status(),
-                response.text().await?
-            );
+            return Err(crate::AvmError::HttpStatus {
+                url: url.to_owned(),
+                status: response.status(),
+                body: response.text().await?,
+            });
         }
 
-        let archive_type = ArchiveType::from_path(url.as_bytes())?;
+        let archive_type = custom_action
+            .archive_type()
+            .or_else(|| ArchiveType::from_response(url, &response));
         operating.drop_should_not_block = true;
-        let archive_path = operating.tmp_dir_path.join("download");
-        let extracted_dir = operating.tmp_dir_path.join("extracted");
-        let archive_file = File::create(&archive_path)?;
+        let (extractor, target) = match archive_type {
+            Some(archive_type) if stream_extract && archive_type.is_streamable() => {
+                let (extractor, target) = StreamExtractor::spawn(
+                    archive_type,
+                    url,
+                    extracted_dir.clone(),
+                    cancel.clone(),
+                );
+                (Some(extractor), target)
+            }
+            _ => {
+                if stream_extract {
+                    log::info!("The archive can't be extracted while downloading, saving it first");
+                }
+                (
+                    None,
+                    ChunkTarget::file(File::create(&archive_path)?, client.download_buffer_size()),
+                )
+            }
+        };
 
         let total_size = response.content_length();
         Ok(DownloadExtractState(
             DownloadExtractStateInner::Downloading(
                 operating,
-                ArchiveExtractInfo {
-                    archive_path,
-                    archive_type,
-                    extracted_dir,
-                },
                 DownloadingState {
                     response,
-                    archive_file,
+                    writer: ChunkWriter::spawn(target, FileHasher::new(expected_hash)),
+                    extractor,
+                    throttle: client.max_download_rate().map(|rate| Throttle {
+                        bytes_per_second: rate.bytes_per_second(),
+                        started: Instant::now(),
+                    }),
                     total_size,
                     downloaded_size: 0,
+                    archive_path,
+                    archive_type,
+                    extracted_dir,
+                    cache: client
+                        .download_cache()
+                        .cloned()
+                        .zip(expected_hash.sha256.clone()),
+                    cancel,
+                    url: url.into(),
+                    client: client.clone(),
+                    expected_hash: expected_hash.clone(),
                 },
                 custom_action,
             ),
         ))
     }
 
+    /// Like [`DownloadExtractState::start`] for an archive that is already in the temporary
+    /// directory of `operating`, as `download`. `file_name` is the name it had, telling its
+    /// type.
+    pub(crate) async fn from_archive(
+        mut operating: blocking::Operating,
+        file_name: &str,
+        expected_hash: &FileHash,
+        custom_action: Box<dyn DownloadExtractCallback + Send>,
+        cancel: CancellationToken,
+    ) -> crate::Result<Self> {
+        operating.drop_should_not_block = true;
+        let archive_path = operating.tmp_dir_path.join("download");
+        let (expected_hash, hash_cancel) = (expected_hash.clone(), cancel.clone());
+        let digest = crate::spawn_blocking(move || {
+            blocking::hash_file(&expected_hash, &archive_path, &hash_cancel)
+        })
+        .await?;
+        Self::downloaded(
+            operating,
+            ArchiveType::from_file_name(file_name.as_bytes()),
+            digest,
+            custom_action,
+            cancel,
+        )
+        .await
+    }
+
+    /// The state once the archive is saved as `download` in the temporary directory of
+    /// `operating`. Its type is detected from the content if neither the callback nor
+    /// `archive_type` tell it.
+    async fn downloaded(
+        operating: blocking::Operating,
+        archive_type: Option<ArchiveType>,
+        digest: FileHash,
+        custom_action: Box<dyn DownloadExtractCallback + Send>,
+        cancel: CancellationToken,
+    ) -> crate::Result<Self> {
+        let archive_path = operating.tmp_dir_path.join("download");
+        let extracted_dir = operating.tmp_dir_path.join("extracted");
+        let archive_type = match custom_action.archive_type().or(archive_type) {
+            Some(archive_type) => archive_type,
+            None => {
+                let archive_path = archive_path.clone();
+                crate::spawn_blocking(move || blocking::detect_archive_type(&archive_path)).await?
+            }
+        };
+        let archive_extract_info = ArchiveExtractInfo {
+            archive_path,
+            archive_type,
+            extracted_dir,
+            digest,
+            cancel,
+            source: None,
+        };
+        Ok(DownloadExtractState(DownloadExtractStateInner::Verifying(
+            operating,
+            archive_extract_info,
+            custom_action,
+            false,
+        )))
+    }
+
     pub fn status(&self) -> crate::Status {
         match &self.0 {
             DownloadExtractStateInner::Downloading(
-                _,
                 _,
                 DownloadingState {
                     total_size,
@@ -129,7 +779,11 @@ impl DownloadExtractState {
                 name: "Downloading".into(),
                 progress_ratio: total_size.map(|total| (*downloaded_size, total)),
             },
-            DownloadExtractStateInner::Extracting(_, _, _) => crate::Status::InProgress {
+            DownloadExtractStateInner::Verifying(..) => crate::Status::InProgress {
+                name: "Verifying".into(),
+                progress_ratio: None,
+            },
+            DownloadExtractStateInner::Extracting(..) => crate::Status::InProgress {
                 name: "Extracting".into(),
                 progress_ratio: None,
             },
@@ -142,66 +796,190 @@ impl DownloadExtractState {
         abandoned_operating: &mut Option<blocking::Operating>,
     ) -> anyhow::Result<Self> {
         match self.0 {
-            DownloadExtractStateInner::Downloading(
+            DownloadExtractStateInner::Downloading(operating, mut downloading, custom_action) => {
+                *abandoned_operating = Some(operating);
+                let chunk = tokio::select! {
+                    chunk = downloading.response.chunk() => chunk?,
+                    _ = downloading.cancel.cancelled() => {
+                        return Err(crate::AvmError::Cancelled.into())
+                    }
+                };
+                if let Some(chunk) = chunk {
+                    downloading.downloaded_size += chunk.len() as u64;
+                    if let Err(err) = downloading.writer.write(chunk).await {
+                        // A stopped extraction stops the writer, report its cause instead.
+                        return Err(match &mut downloading.extractor {
+                            Some(extractor) => extractor.stopped_error().await,
+                            None => err,
+                        });
+                    }
+                    if let Some(delay) = downloading.throttle.as_ref().and_then(|throttle| {
+                        throttle.delay(downloading.downloaded_size, throttle.started.elapsed())
+                    }) {
+                        tokio::select! {
+                            _ = tokio::time::sleep(delay) => {}
+                            _ = downloading.cancel.cancelled() => {
+                                return Err(crate::AvmError::Cancelled.into())
+                            }
+                        }
+                    }
+                    return Ok(DownloadExtractState(
+                        DownloadExtractStateInner::Downloading(
+                            abandoned_operating.take().unwrap(),
+                            downloading,
+                            custom_action,
+                        ),
+                    ));
+                }
+                let source = Some(downloading.source());
+                let DownloadingState {
+                    writer,
+                    extractor,
+                    archive_path,
+                    archive_type,
+                    extracted_dir,
+                    cache,
+                    cancel,
+                    ..
+                } = downloading;
+                let digest = writer.finish().await;
+                if let Some(mut extractor) = extractor {
+                    let digest = match digest {
+                        Ok(digest) => digest,
+                        Err(_) => return Err(extractor.stopped_error().await),
+                    };
+                    let archive_type = extractor.archive_type;
+                    extractor.finish().await?;
+                    let archive_extract_info = ArchiveExtractInfo {
+                        archive_path,
+                        archive_type,
+                        extracted_dir,
+                        digest,
+                        cancel,
+                        source,
+                    };
+                    // The extracted files are only used once the hash is verified.
+                    return Ok(DownloadExtractState(DownloadExtractStateInner::Verifying(
+                        abandoned_operating.take().unwrap(),
+                        archive_extract_info,
+                        custom_action,
+                        true,
+                    )));
+                }
+                let digest = digest?;
+                if let Some((cache, expected_sha256)) = cache {
+                    if digest
+                        .sha256
+                        .as_deref()
+                        .is_some_and(|sha256| sha256.eq_ignore_ascii_case(&expected_sha256))
+                    {
+                        let archive_path = archive_path.clone();
+                        let stored = crate::spawn_blocking(move || {
+                            cache.store_blocking(&expected_sha256, &archive_path)
+                        })
+                        .await;
+                        if let Err(err) = stored {
+                            log::warn!("Failed to add the archive to the download cache: {err:#}");
+                        }
+                    }
+                }
+                let (archive_path, archive_type) = match archive_type {
+                    Some(archive_type) => (archive_path, archive_type),
+                    None => {
+                        crate::spawn_blocking(move || {
+                            let archive_type = blocking::detect_archive_type(&archive_path)?;
+                            Ok((archive_path, archive_type))
+                        })
+                        .await?
+                    }
+                };
+                let archive_extract_info = ArchiveExtractInfo {
+                    archive_path,
+                    archive_type,
+                    extracted_dir,
+                    digest,
+                    cancel,
+                    source,
+                };
+                Ok(DownloadExtractState(DownloadExtractStateInner::Verifying(
+                    abandoned_operating.take().unwrap(),
+                    archive_extract_info,
+                    custom_action,
+                    false,
+                )))
+            }
+            DownloadExtractStateInner::Verifying(
                 operating,
                 archive_extract_info,
-                DownloadingState {
-                    mut response,
-                    mut archive_file,
-                    downloaded_size,
-                    total_size,
-                },
                 mut custom_action,
+                extracted,
             ) => {
+                let tmp_dir = operating.tmp_dir_path.clone();
                 *abandoned_operating = Some(operating);
-                Ok(DownloadExtractState(
-                    if let Some(chunk) = response.chunk().await? {
-                        archive_file.write_all(&chunk)?;
-                        DownloadExtractStateInner::Downloading(
-                            abandoned_operating.take().unwrap(),
-                            archive_extract_info,
-                            DownloadingState {
-                                response,
-                                archive_file,
-                                downloaded_size: downloaded_size + chunk.len() as u64,
-                                total_size,
-                            },
-                            custom_action,
-                        )
-                    } else {
-                        custom_action.on_downloaded(&archive_extract_info).await?;
-                        DownloadExtractStateInner::Extracting(
-                            abandoned_operating.take().unwrap(),
-                            archive_extract_info,
-                            custom_action,
-                        )
-                    },
-                ))
+                if let Err(err) = custom_action.on_downloaded(&archive_extract_info).await {
+                    return Err(match &archive_extract_info.source {
+                        Some(source) => {
+                            source
+                                .explain(err, &tmp_dir, &archive_extract_info.cancel)
+                                .await
+                        }
+                        None => err,
+                    });
+                }
+                Ok(DownloadExtractState(DownloadExtractStateInner::Extracting(
+                    abandoned_operating.take().unwrap(),
+                    archive_extract_info,
+                    custom_action,
+                    extracted,
+                )))
             }
             DownloadExtractStateInner::Extracting(
                 operating,
                 mut archive_extract_info,
                 mut custom_action,
+                extracted,
             ) => {
                 *abandoned_operating = Some(operating);
-                archive_extract_info = crate::spawn_blocking(move || {
-                    blocking::extract_archive(
-                        archive_extract_info.archive_type,
-                        &archive_extract_info.archive_path,
-                        &archive_extract_info.extracted_dir,
-                    )?;
-                    Ok(archive_extract_info)
-                })
-                .await?;
+                if !extracted {
+                    archive_extract_info = crate::spawn_blocking(move || {
+                        blocking::extract_archive(
+                            archive_extract_info.archive_type,
+                            &archive_extract_info.archive_path,
+                            &archive_extract_info.extracted_dir,
+                            &archive_extract_info.cancel,
+                        )?;
+                        Ok(archive_extract_info)
+                    })
+                    .await?;
+                }
                 custom_action.on_extracted(&archive_extract_info).await?;
-                abandoned_operating.as_mut().unwrap().drop_should_not_block = false;
+                let operating = abandoned_operating.as_mut().unwrap();
+                operating.commit();
+                operating.drop_should_not_block = false;
                 Ok(DownloadExtractState(DownloadExtractStateInner::Stopped))
             }
             DownloadExtractStateInner::Stopped => Err(anyhow::anyhow!("Already stopped")),
         }
     }
 
-    pub async fn advance(self) -> anyhow::Result<Self> {
+    /// Drives the state to the end, passing the status to `on_progress` before each step and
+    /// once stopped. An error returned by `on_progress` aborts the operation.
+    pub async fn run(
+        mut self,
+        mut on_progress: impl FnMut(crate::Status) -> anyhow::Result<()>,
+    ) -> crate::Result<()> {
+        loop {
+            let status = self.status();
+            let stopped = matches!(status, crate::Status::Stopped);
+            on_progress(status)?;
+            if stopped {
+                return Ok(());
+            }
+            self = self.advance().await?;
+        }
+    }
+
+    pub async fn advance(self) -> crate::Result<Self> {
         let mut abandoned_operating: Option<blocking::Operating> = None;
         let result = self.do_advance(&mut abandoned_operating).await;
         if let Some(mut abandoned_operating) = abandoned_operating {
@@ -213,6 +991,112 @@ impl DownloadExtractState {
             .await?;
         }
 
-        result
+        Ok(result?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archive_type_ignores_url_query() {
+        let url = reqwest::Url::parse("https://example.com/go1.24.linux-amd64.tar.gz?token=a.zip")
+            .unwrap();
+        assert_eq!(ArchiveType::from_url(&url), Some(ArchiveType::TarGz));
+        let url = reqwest::Url::parse("https://example.com/download?id=1").unwrap();
+        assert_eq!(ArchiveType::from_url(&url), None);
+    }
+
+    #[test]
+    fn archive_type_from_headers() {
+        assert_eq!(
+            ArchiveType::from_content_disposition("attachment; filename=\"jdk.zip\""),
+            Some(ArchiveType::Zip)
+        );
+        assert_eq!(
+            ArchiveType::from_content_disposition("attachment; filename*=UTF-8''node-v22.tar.xz"),
+            Some(ArchiveType::TarXz)
+        );
+        assert_eq!(
+            ArchiveType::from_content_type("application/x-7z-compressed"),
+            Some(ArchiveType::SevenZ)
+        );
+        assert_eq!(
+            ArchiveType::from_content_type("application/octet-stream"),
+            None
+        );
+    }
+
+    #[test]
+    fn archive_type_from_magic() {
+        assert_eq!(
+            ArchiveType::from_magic(b"PK\x03\x04rest"),
+            Some(ArchiveType::Zip)
+        );
+        assert_eq!(
+            ArchiveType::from_magic(&[0x28, 0xb5, 0x2f, 0xfd, 0]),
+            Some(ArchiveType::TarZst)
+        );
+        let mut tar = vec![0_u8; 512];
+        tar[257..262].copy_from_slice(b"ustar");
+        assert_eq!(ArchiveType::from_magic(&tar), Some(ArchiveType::Tar));
+        assert_eq!(ArchiveType::from_magic(b"plain text"), None);
+    }
+
+    #[test]
+    fn file_hasher_digests_chunks_incrementally() {
+        let expected = FileHash::from_sha256(
+            "BA7816BF8F01CFEA414140DE5DAE2223B00361A396177A9CB410FF61F20015AD".into(),
+        );
+        let mut hasher = FileHasher::new(&expected);
+        hasher.update(b"a");
+        hasher.update(b"bc");
+        let digest = hasher.finalize();
+        assert!(digest.sha1.is_none() && digest.sha512.is_none());
+        verify_digest(&expected, &digest).unwrap();
+
+        let mut hasher = FileHasher::new(&expected);
+        hasher.update(b"abd");
+        assert!(matches!(
+            verify_digest(&expected, &hasher.finalize()),
+            Err(crate::AvmError::HashMismatch {
+                algorithm: "Sha256",
+                ..
+            })
+        ));
+    }
+
+    #[tokio::test]
+    async fn chunk_writer_hashes_and_writes_through_buffer() {
+        let expected = FileHash::from_sha256(
+            "BA7816BF8F01CFEA414140DE5DAE2223B00361A396177A9CB410FF61F20015AD".into(),
+        );
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("archive");
+        let mut writer = ChunkWriter::spawn(
+            ChunkTarget::file(File::create(&path).unwrap(), 2),
+            FileHasher::new(&expected),
+        );
+        for chunk in ["a", "bc"] {
+            writer.write(bytes::Bytes::from(chunk)).await.unwrap();
+        }
+        let digest = writer.finish().await.unwrap();
+        verify_digest(&expected, &digest).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"abc");
+    }
+
+    #[test]
+    fn throttle_pauses_only_when_ahead() {
+        let throttle = Throttle {
+            bytes_per_second: 1000,
+            started: Instant::now(),
+        };
+        assert_eq!(
+            throttle.delay(500, Duration::from_millis(100)),
+            Some(Duration::from_millis(400))
+        );
+        assert_eq!(throttle.delay(500, Duration::from_millis(500)), None);
+        assert_eq!(throttle.delay(500, Duration::from_secs(1)), None);
     }
 }
@@ -0,0 +1,295 @@
+//! A journal of the operations changing tag directories, so that the ones interrupted by a
+//! crash or a power loss are rolled back or finished by the next avm process instead of
+//! leaving a tag half replaced or half removed.
+//!
+//! Each operation in progress has a file in the journal directory, written before it starts
+//! and removed once it is done or undone. A file left by a process that died is handled by
+//! [`recover`] once the lock of its tool directory can be taken.
+
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::io::blocking;
+
+static JOURNAL_DIR: OnceLock<PathBuf> = OnceLock::new();
+static NEXT_ENTRY: AtomicU64 = AtomicU64::new(0);
+
+/// Keeps the journal in `dir`, usually `journal` under the state directory. Operations are not
+/// journaled until it is set.
+pub fn set_dir(dir: PathBuf) {
+    let _ = JOURNAL_DIR.set(dir);
+}
+
+/// An operation changing a tag directory.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "kebab-case")]
+pub enum Operation {
+    /// Moving `source_dir` to `tag_dir`, with the tag it replaces moved to `backup_dir`, both
+    /// under the temporary directory of the install.
+    Install {
+        tag_dir: PathBuf,
+        source_dir: PathBuf,
+        backup_dir: PathBuf,
+        tmp_dir: PathBuf,
+    },
+    /// Moving `tag_dir` to `trash_dir`, then removing it.
+    Remove {
+        tag_dir: PathBuf,
+        trash_dir: PathBuf,
+    },
+}
+
+impl Operation {
+    /// The tag directory the operation changes.
+    pub fn tag_dir(&self) -> &Path {
+        match self {
+            Operation::Install { tag_dir, .. } | Operation::Remove { tag_dir, .. } => tag_dir,
+        }
+    }
+}
+
+/// The journal file of an operation in progress, removed on drop.
+#[must_use = "the operation is only journaled while the entry is alive"]
+pub struct Entry {
+    path: Option<PathBuf>,
+}
+
+impl Drop for Entry {
+    fn drop(&mut self) {
+        if let Some(path) = &self.path {
+            if let Err(err) = std::fs::remove_file(path) {
+                log::error!("Failed to remove '{}': {}", path.display(), err);
+            }
+        }
+    }
+}
+
+/// Records an operation before starting it. The caller must hold the lock of the tool
+/// directory until the entry is dropped.
+pub fn begin(operation: &Operation) -> anyhow::Result<Entry> {
+    let Some(dir) = JOURNAL_DIR.get() else {
+        return Ok(Entry { path: None });
+    };
+    begin_in(dir, operation)
+}
+
+fn begin_in(dir: &Path, operation: &Operation) -> anyhow::Result<Entry> {
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create '{}'", dir.display()))?;
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |now| now.as_nanos());
+    let name = format!(
+        "{}-{nanos}-{}.json",
+        std::process::id(),
+        NEXT_ENTRY.fetch_add(1, Ordering::Relaxed)
+    );
+    let path = dir.join(name);
+    let mut file = std::fs::File::create(&path)
+        .with_context(|| format!("Failed to write '{}'", path.display()))?;
+    std::io::Write::write_all(&mut file, &serde_json::to_vec_pretty(operation)?)?;
+    file.sync_all()?;
+    blocking::sync_dir(dir)?;
+    Ok(Entry { path: Some(path) })
+}
+
+/// Rolls back the interrupted installs and finishes the interrupted removals in the journal.
+/// Operations on tool directories locked by running processes are left alone. Returns the
+/// number of operations handled.
+pub async fn recover() -> crate::Result<usize> {
+    let Some(dir) = JOURNAL_DIR.get() else {
+        return Ok(0);
+    };
+    let dir = dir.clone();
+    Ok(crate::spawn_blocking(move || recover_in(&dir)).await?)
+}
+
+/// The operations left in the journal, either by running processes or because they could not
+/// be recovered. Unreadable entries are skipped.
+pub async fn pending() -> crate::Result<Vec<Operation>> {
+    let Some(dir) = JOURNAL_DIR.get() else {
+        return Ok(Vec::new());
+    };
+    let dir = dir.clone();
+    Ok(crate::spawn_blocking(move || pending_in(&dir)).await?)
+}
+
+fn pending_in(dir: &Path) -> anyhow::Result<Vec<Operation>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => {
+            return Err(
+                anyhow::Error::from(err).context(format!("Failed to read '{}'", dir.display()))
+            )
+        }
+    };
+    let mut operations = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        if let Ok(operation) = std::fs::read(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|content| Ok(serde_json::from_slice::<Operation>(&content)?))
+        {
+            operations.push(operation);
+        }
+    }
+    Ok(operations)
+}
+
+fn recover_in(dir: &Path) -> anyhow::Result<usize> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(err) => {
+            return Err(
+                anyhow::Error::from(err).context(format!("Failed to read '{}'", dir.display()))
+            )
+        }
+    };
+    let mut recovered = 0;
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        let operation = match std::fs::read(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|content| Ok(serde_json::from_slice::<Operation>(&content)?))
+        {
+            Ok(operation) => operation,
+            // Not written completely before the process died, so nothing was changed yet.
+            Err(err) => {
+                log::warn!(
+                    "Removing unreadable journal entry '{}': {err:#}",
+                    path.display()
+                );
+                std::fs::remove_file(&path)?;
+                continue;
+            }
+        };
+        let tool_dir = operation.tag_dir().parent().unwrap_or(Path::new("."));
+        let Some(_lock) = blocking::ToolDirLock::try_acquire(tool_dir)? else {
+            continue;
+        };
+        // Handled by another process before the lock was taken.
+        if !path.exists() {
+            continue;
+        }
+        recover_operation(&operation)
+            .with_context(|| format!("Failed to recover the operation in '{}'", path.display()))?;
+        std::fs::remove_file(&path)?;
+        recovered += 1;
+    }
+    Ok(recovered)
+}
+
+fn recover_operation(operation: &Operation) -> anyhow::Result<()> {
+    match operation {
+        Operation::Install {
+            tag_dir,
+            source_dir,
+            backup_dir,
+            tmp_dir,
+        } => {
+            let moved_in = tag_dir.symlink_metadata().is_ok() && !source_dir.exists();
+            if moved_in {
+                // The post-install setup may not have finished.
+                std::fs::remove_dir_all(tag_dir)?;
+            }
+            if backup_dir.symlink_metadata().is_ok() && tag_dir.symlink_metadata().is_err() {
+                std::fs::rename(backup_dir, tag_dir)?;
+                log::warn!(
+                    "Restored '{}' replaced by an interrupted install",
+                    tag_dir.display()
+                );
+            } else if moved_in {
+                log::warn!("Removed '{}' of an interrupted install", tag_dir.display());
+            }
+            match std::fs::remove_dir_all(tmp_dir) {
+                Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+                _ => Ok(()),
+            }
+        }
+        Operation::Remove { tag_dir, trash_dir } => {
+            if trash_dir.symlink_metadata().is_ok() {
+                std::fs::remove_dir_all(trash_dir)?;
+                log::warn!(
+                    "Finished removing '{}' after an interruption",
+                    tag_dir.display()
+                );
+            }
+            Ok(())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rolls_back_an_interrupted_install() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal_dir = dir.path().join("journal");
+        let tag_dir = dir.path().join("node").join("22");
+        let tmp_dir = dir.path().join("node").join(".tmp.22");
+        let backup_dir = tmp_dir.join("previous");
+        std::fs::create_dir_all(&tag_dir).unwrap();
+        std::fs::write(tag_dir.join("new"), "").unwrap();
+        std::fs::create_dir_all(&backup_dir).unwrap();
+        std::fs::write(backup_dir.join("old"), "").unwrap();
+        std::fs::write(tmp_dir.join(".lock"), "").unwrap();
+
+        // The process dies after moving the new tag in.
+        std::mem::forget(
+            begin_in(
+                &journal_dir,
+                &Operation::Install {
+                    tag_dir: tag_dir.clone(),
+                    source_dir: tmp_dir.join("extracted"),
+                    backup_dir,
+                    tmp_dir: tmp_dir.clone(),
+                },
+            )
+            .unwrap(),
+        );
+        assert_eq!(recover_in(&journal_dir).unwrap(), 1);
+        assert!(tag_dir.join("old").exists());
+        assert!(!tag_dir.join("new").exists());
+        assert!(!tmp_dir.exists());
+        assert_eq!(std::fs::read_dir(&journal_dir).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn finishes_an_interrupted_removal() {
+        let dir = tempfile::tempdir().unwrap();
+        let journal_dir = dir.path().join("journal");
+        let tag_dir = dir.path().join("node").join("22");
+        let trash_dir = dir.path().join("node").join(".tmp.22.removing");
+        std::fs::create_dir_all(trash_dir.join("bin")).unwrap();
+
+        let entry = begin_in(
+            &journal_dir,
+            &Operation::Remove {
+                tag_dir,
+                trash_dir: trash_dir.clone(),
+            },
+        )
+        .unwrap();
+        std::mem::forget(entry);
+        assert_eq!(pending_in(&journal_dir).unwrap().len(), 1);
+        assert_eq!(recover_in(&journal_dir).unwrap(), 1);
+        assert!(pending_in(&journal_dir).unwrap().is_empty());
+        assert!(!trash_dir.exists());
+        assert_eq!(recover_in(&journal_dir).unwrap(), 0);
+    }
+}
@@ -1,27 +1,120 @@
+use anyhow::Context as _;
 use rustc_hash::FxHashMap;
 use serde::{Deserialize, Serialize};
 use smol_str::SmolStr;
-use std::fmt;
 use std::future::Future;
-use std::ops::Deref;
+use std::path::PathBuf;
 use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, LazyLock};
 use std::task::{Context, Poll};
-use std::{path::PathBuf, sync::atomic::AtomicBool};
+use std::time::{SystemTime, UNIX_EPOCH};
 
+pub mod avm;
+pub mod bundle;
+pub mod download_cache;
+pub mod error;
+pub mod fetch;
 pub mod io;
+pub mod journal;
+pub mod pinned_hashes;
 pub mod platform;
+pub mod project;
+pub mod rustup;
+pub mod signature;
+pub mod tag;
 pub mod tool;
+pub mod usage;
+
+pub use error::{AvmError, Result};
+pub use tag::{Tag, TagIsNotValid, TagStr};
 
 #[derive(Debug, Deserialize)]
 pub struct UrlMirrorEntry {
+    /// URL prefix to replace, or a regular expression if `regex` is set.
     from: String,
+    /// Replacement of `from`. With `regex`, `$1`/`${name}` refer to the capture groups.
     to: String,
+    #[serde(default)]
+    regex: bool,
+    /// Only applies to requests made for these tools. Applies to every tool if absent.
+    tools: Option<Vec<SmolStr>>,
+    /// Skips TLS certificate verification for requests rewritten by this mirror.
+    #[serde(default)]
+    danger_accept_invalid_certs: bool,
+    /// Also rewrites redirect targets matching `from`, so that a redirect to a CDN still goes
+    /// through the mirror.
+    #[serde(default)]
+    follow_redirects: bool,
 }
 #[derive(Debug, Default, Deserialize)]
 pub struct UrlMirror {
     mirrors: Vec<UrlMirrorEntry>,
 }
 
+impl UrlMirror {
+    /// Tool names the mirror entries are scoped to.
+    pub fn scoped_tools(&self) -> impl Iterator<Item = &str> {
+        self.mirrors
+            .iter()
+            .flat_map(|entry| entry.tools.iter().flatten())
+            .map(SmolStr::as_str)
+    }
+}
+
+enum MirrorPattern {
+    Prefix,
+    Regex(regex::Regex),
+}
+
+struct MirrorRule {
+    entry: UrlMirrorEntry,
+    pattern: MirrorPattern,
+}
+
+impl MirrorRule {
+    fn new(entry: UrlMirrorEntry) -> anyhow::Result<Self> {
+        let pattern = if entry.regex {
+            MirrorPattern::Regex(
+                regex::Regex::new(&entry.from)
+                    .with_context(|| format!("Invalid mirror pattern '{}'", entry.from))?,
+            )
+        } else {
+            MirrorPattern::Prefix
+        };
+        Ok(Self { entry, pattern })
+    }
+
+    fn applies_to(&self, tool: Option<&str>) -> bool {
+        match (&self.entry.tools, tool) {
+            (None, _) => true,
+            (Some(tools), Some(tool)) => tools.iter().any(|t| t == tool),
+            (Some(_), None) => false,
+        }
+    }
+
+    fn rewrite(&self, url: &str) -> Option<String> {
+        match &self.pattern {
+            MirrorPattern::Prefix => {
+                let rest = url.strip_prefix(&self.entry.from)?;
+                let mut result = String::new();
+                result.push_str(self.entry.to.as_str());
+                result.push_str(rest);
+                Some(result)
+            }
+            MirrorPattern::Regex(regex) => {
+                let captures = regex.captures(url)?;
+                let matched = captures.get(0)?;
+                let mut result = String::new();
+                result.push_str(&url[..matched.start()]);
+                captures.expand(&self.entry.to, &mut result);
+                result.push_str(&url[matched.end()..]);
+                Some(result)
+            }
+        }
+    }
+}
+
 #[derive(Debug, Default, Deserialize)]
 pub struct DefaultPlatform {
     pub global: Option<String>,
@@ -29,137 +122,700 @@ pub struct DefaultPlatform {
     pub tools: FxHashMap<String, String>,
 }
 
+/// Defaults of the install options of a tool, used by the commands selecting a release
+/// when the options are not given on the command line.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct ToolDefaults {
+    /// Takes precedence over the `default-platform` of the tool.
+    pub platform: Option<SmolStr>,
+    pub flavor: Option<SmolStr>,
+    #[serde(default)]
+    pub lts_only: bool,
+    #[serde(default, alias = "allow_prerelease")]
+    pub include_prerelease: bool,
+    /// Shell commands run in order once a tag of the tool is installed.
+    #[serde(default)]
+    pub post_install: Vec<String>,
+    /// Go only: gives each tag its own `GOPATH` and `GOBIN`, see
+    /// [`tool::general_tool::go::Tool::with_isolated_gopath`].
+    #[serde(default)]
+    pub isolated_gopath: bool,
+}
+
+/// The `security` table of the config.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct SecurityConfig {
+    /// Cross-check the vendor checksums with the [`pinned_hashes`] on install.
+    #[serde(default)]
+    pub pin_hashes: bool,
+    /// Where the pinned hashes are updated from, instead of
+    /// [`pinned_hashes::DEFAULT_PINNED_HASHES_URL`].
+    pub pin_hashes_url: Option<SmolStr>,
+    /// Armored public keys that sign the database at `pin_hashes_url`.
+    pub pin_hashes_keys: Option<PathBuf>,
+    /// The fingerprints of the keys in `pin_hashes_keys` that may sign the database.
+    #[serde(default)]
+    pub pin_hashes_fingerprints: Vec<SmolStr>,
+}
+
+/// Proxy settings applied to every request made by [`HttpClient`].
+///
+/// When any proxy is configured here, the `HTTP_PROXY`/`HTTPS_PROXY`/`ALL_PROXY`/`NO_PROXY`
+/// environment variables are ignored. Otherwise they are picked up as usual.
+#[derive(Debug, Default, Deserialize)]
+pub struct ProxyConfig {
+    pub http: Option<String>,
+    pub https: Option<String>,
+    pub all: Option<String>,
+    #[serde(default)]
+    pub no_proxy: Vec<String>,
+}
+
+impl ProxyConfig {
+    fn is_empty(&self) -> bool {
+        self.http.is_none() && self.https.is_none() && self.all.is_none()
+    }
+}
+
+/// TLS settings applied to every request made by [`HttpClient`].
+#[derive(Debug, Default, Deserialize)]
+pub struct TlsConfig {
+    /// PEM file with extra root certificates, trusted in addition to the system ones.
+    pub ca_file: Option<PathBuf>,
+}
+
+/// Credentials [`HttpClient`] sends to one host, an `[[auth]]` entry of the config. The
+/// secrets are read from environment variables when a request is sent, so that the config
+/// can be shared. Either `token_env` or `username` is set.
+#[derive(Debug, Clone, Deserialize)]
+pub struct HostAuth {
+    /// Host name, like `mirror.example.com`, or `host:port` to only match that port.
+    pub host: String,
+    /// Environment variable with a token sent as `Authorization: Bearer <token>`.
+    pub token_env: Option<String>,
+    /// User name for HTTP basic authentication.
+    pub username: Option<String>,
+    /// Environment variable with the password for HTTP basic authentication.
+    pub password_env: Option<String>,
+    /// Also send the credentials over plain `http://`, such as to a mirror on the local
+    /// network. By default they are only sent over `https://`.
+    #[serde(default)]
+    pub allow_http: bool,
+}
+
+enum AuthScheme {
+    Bearer {
+        token_env: String,
+    },
+    Basic {
+        username: String,
+        password_env: Option<String>,
+    },
+}
+
+struct AuthRule {
+    host: String,
+    port: Option<u16>,
+    allow_http: bool,
+    scheme: AuthScheme,
+}
+
+impl AuthRule {
+    fn new(auth: HostAuth) -> anyhow::Result<Self> {
+        let scheme = match (auth.token_env, auth.username) {
+            (Some(token_env), None) if auth.password_env.is_none() => {
+                AuthScheme::Bearer { token_env }
+            }
+            (None, Some(username)) => AuthScheme::Basic {
+                username,
+                password_env: auth.password_env,
+            },
+            _ => anyhow::bail!(
+                "The credentials of host \"{}\" need either `token_env`, or `username` with an optional `password_env`",
+                auth.host
+            ),
+        };
+        let (host, port) = match auth.host.rsplit_once(':') {
+            Some((host, port)) => (
+                host,
+                Some(port.parse().with_context(|| {
+                    format!("Invalid port in the credentials of host \"{}\"", auth.host)
+                })?),
+            ),
+            None => (auth.host.as_str(), None),
+        };
+        Ok(Self {
+            host: host.to_ascii_lowercase(),
+            port,
+            allow_http: auth.allow_http,
+            scheme,
+        })
+    }
+
+    fn matches(&self, url: &reqwest::Url) -> bool {
+        (url.scheme() == "https" || self.allow_http && url.scheme() == "http")
+            && url
+                .host_str()
+                .is_some_and(|host| host.eq_ignore_ascii_case(&self.host))
+            && self
+                .port
+                .is_none_or(|port| url.port_or_known_default() == Some(port))
+    }
+
+    fn header(&self) -> anyhow::Result<reqwest::header::HeaderValue> {
+        let var = |name: &str| {
+            std::env::var(name).map_err(|_| {
+                anyhow::anyhow!(
+                    "The environment variable `{name}` with the credentials of host \"{}\" is not set",
+                    self.host
+                )
+            })
+        };
+        let value = match &self.scheme {
+            AuthScheme::Bearer { token_env } => format!("Bearer {}", var(token_env)?),
+            AuthScheme::Basic {
+                username,
+                password_env,
+            } => {
+                use base64::Engine as _;
+
+                let password = password_env.as_deref().map(var).transpose()?;
+                let credentials = format!("{username}:{}", password.unwrap_or_default());
+                format!(
+                    "Basic {}",
+                    base64::engine::general_purpose::STANDARD.encode(credentials)
+                )
+            }
+        };
+        let mut value = reqwest::header::HeaderValue::from_str(&value)
+            .with_context(|| format!("Invalid credentials for host \"{}\"", self.host))?;
+        value.set_sensitive(true);
+        Ok(value)
+    }
+}
+
+/// A transfer rate in bytes per second, written like `5MB/s`. `KB`/`MB`/`GB` are powers of
+/// 1000, `K`/`M`/`G` and `KiB`/`MiB`/`GiB` powers of 1024. The `/s` suffix is optional.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct ByteRate(u64);
+
+impl ByteRate {
+    pub fn bytes_per_second(self) -> u64 {
+        self.0
+    }
+}
+
+impl std::str::FromStr for ByteRate {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        let rate = s.trim();
+        let rate = rate.strip_suffix("/s").unwrap_or(rate);
+        let bytes = parse_bytes(rate, s, "rate", "5MB/s")?;
+        if bytes == 0 {
+            anyhow::bail!("Rate \"{s}\" must be positive");
+        }
+        Ok(ByteRate(bytes))
+    }
+}
+
+/// Parses a number of bytes with an optional unit, like `1.5 MiB`. The errors name the
+/// `original` text as a `what`, with an `example` of a valid one.
+fn parse_bytes(bytes: &str, original: &str, what: &str, example: &str) -> anyhow::Result<u64> {
+    let s = original;
+    let unit_start = bytes
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(bytes.len());
+    let (number, unit) = bytes.split_at(unit_start);
+    let multiplier: u64 = match unit.trim() {
+        "" | "B" => 1,
+        "KB" => 1000,
+        "MB" => 1000 * 1000,
+        "GB" => 1000 * 1000 * 1000,
+        "K" | "KiB" => 1 << 10,
+        "M" | "MiB" => 1 << 20,
+        "G" | "GiB" => 1 << 30,
+        unit => anyhow::bail!("Unknown unit \"{unit}\" in {what} \"{s}\""),
+    };
+    let number: f64 = number
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid {what} \"{s}\", expected a value like {example}"))?;
+    Ok((number * multiplier as f64) as u64)
+}
+
+/// A size in bytes, written like `2GiB` with the units of [`ByteRate`], or as a number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "ByteSizeRepr")]
+pub struct ByteSize(u64);
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ByteSizeRepr {
+    Bytes(u64),
+    Text(String),
+}
+
+impl ByteSize {
+    pub fn bytes(self) -> u64 {
+        self.0
+    }
+}
+
+impl std::str::FromStr for ByteSize {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        parse_bytes(s.trim(), s, "size", "2GiB").map(ByteSize)
+    }
+}
+
+impl TryFrom<ByteSizeRepr> for ByteSize {
+    type Error = anyhow::Error;
+
+    fn try_from(value: ByteSizeRepr) -> anyhow::Result<Self> {
+        match value {
+            ByteSizeRepr::Bytes(bytes) => Ok(ByteSize(bytes)),
+            ByteSizeRepr::Text(text) => text.parse(),
+        }
+    }
+}
+
+impl TryFrom<String> for ByteRate {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> anyhow::Result<Self> {
+        value.parse()
+    }
+}
+
 #[derive(Debug, Default, Deserialize)]
 pub struct Config {
     #[serde(flatten)]
     pub mirrors: Option<UrlMirror>,
     pub data_path: Option<PathBuf>,
+    /// Where downloads that can be fetched again are kept.
+    pub cache_path: Option<PathBuf>,
+    /// Where records of past operations are kept.
+    pub state_path: Option<PathBuf>,
     #[serde(rename = "default-platform")]
     pub default_platform: Option<DefaultPlatform>,
+    /// Install option defaults by tool name.
+    pub tools: Option<FxHashMap<String, ToolDefaults>>,
+    pub proxy: Option<ProxyConfig>,
+    pub tls: Option<TlsConfig>,
+    /// Downloads are slowed down to this rate if set.
+    pub max_download_rate: Option<ByteRate>,
+    /// The size the download cache is trimmed to, `0` turns it off. 2GiB by default.
+    pub max_download_cache_size: Option<ByteSize>,
+    /// The buffer archives are written to disk through while downloading, 1MiB by default.
+    pub download_buffer_size: Option<ByteSize>,
+    /// Token for the GitHub API. The `GITHUB_TOKEN` environment variable takes precedence.
+    pub github_token: Option<SmolStr>,
+    pub security: Option<SecurityConfig>,
+    /// Credentials by host, for mirrors that require them.
+    pub auth: Option<Vec<HostAuth>>,
+}
+
+impl Config {
+    /// The top-level keys.
+    pub const KEYS: &'static [&'static str] = &[
+        "mirrors",
+        "data_path",
+        "cache_path",
+        "state_path",
+        "default-platform",
+        "tools",
+        "proxy",
+        "tls",
+        "max_download_rate",
+        "max_download_cache_size",
+        "download_buffer_size",
+        "github_token",
+        "security",
+        "auth",
+    ];
 }
 
+/// Runs `f` on the blocking thread pool. Nothing is started once the process is cancelled;
+/// `f` itself should poll a [`CancellationToken`] if it may run for long.
 pub async fn spawn_blocking<T: Send + 'static>(
     f: impl FnOnce() -> anyhow::Result<T> + Send + 'static,
 ) -> anyhow::Result<T> {
+    check_cancelled(cancellation_token())?;
     match tokio::task::spawn_blocking(f).await {
         Ok(r) => r,
         Err(_) => Err(anyhow::anyhow!("Failed to join spawned IO task")),
     }
 }
 
+const MAX_REDIRECTS: usize = 10;
+
+#[derive(Clone)]
 pub struct HttpClient {
-    mirror: UrlMirror,
+    mirrors: Arc<[MirrorRule]>,
+    /// The tool requests are made for, selects the mirrors scoped to it.
+    tool: Option<SmolStr>,
     client_inner: reqwest::Client,
+    /// Client without certificate verification, only built when a mirror asks for it.
+    insecure_client_inner: Option<reqwest::Client>,
+    max_download_rate: Option<ByteRate>,
+    download_buffer_size: usize,
+    github_token: Option<SmolStr>,
+    download_cache: Option<download_cache::DownloadCache>,
+    auth: Arc<[AuthRule]>,
+    /// Send the requests for URLs of other schemes than HTTP, by scheme.
+    fetchers: Arc<FxHashMap<SmolStr, Arc<dyn fetch::Fetcher>>>,
 }
 
 impl HttpClient {
-    pub fn new(mirror: UrlMirror) -> HttpClient {
+    pub const DEFAULT_DOWNLOAD_BUFFER_SIZE: usize = 1 << 20;
+
+    pub fn new(
+        mirror: UrlMirror,
+        proxy: ProxyConfig,
+        tls: TlsConfig,
+    ) -> anyhow::Result<HttpClient> {
+        let mut proxies = Vec::new();
+        if !proxy.is_empty() {
+            let no_proxy = reqwest::NoProxy::from_string(&proxy.no_proxy.join(","));
+            if let Some(url) = &proxy.http {
+                proxies.push(with_no_proxy(reqwest::Proxy::http(url), url, &no_proxy)?);
+            }
+            if let Some(url) = &proxy.https {
+                proxies.push(with_no_proxy(reqwest::Proxy::https(url), url, &no_proxy)?);
+            }
+            if let Some(url) = &proxy.all {
+                proxies.push(with_no_proxy(reqwest::Proxy::all(url), url, &no_proxy)?);
+            }
+        }
+
+        let certs = match &tls.ca_file {
+            Some(path) => {
+                let pem = std::fs::read(path)
+                    .with_context(|| format!("Failed to read CA file '{}'", path.display()))?;
+                let certs = reqwest::Certificate::from_pem_bundle(&pem)
+                    .with_context(|| format!("Failed to parse CA file '{}'", path.display()))?;
+                log::debug!(
+                    "Loaded {} extra root certificates from {}",
+                    certs.len(),
+                    path.display()
+                );
+                certs
+            }
+            None => Vec::new(),
+        };
+
+        let build = |accept_invalid_certs: bool| -> anyhow::Result<reqwest::Client> {
+            // Some APIs, such as GitHub's, reject requests without a user agent.
+            let mut builder = reqwest::Client::builder()
+                .user_agent(concat!("avm/", env!("CARGO_PKG_VERSION")))
+                .tls_certs_merge(certs.iter().cloned())
+                .tls_danger_accept_invalid_certs(accept_invalid_certs)
+                // Redirects are followed by `HttpRequest::send`, which applies the mirrors
+                // and the credentials of each host to the redirect targets.
+                .redirect(reqwest::redirect::Policy::none());
+            for proxy in &proxies {
+                builder = builder.proxy(proxy.clone());
+            }
+            Ok(builder.build()?)
+        };
+
+        let insecure_client_inner = if mirror
+            .mirrors
+            .iter()
+            .any(|entry| entry.danger_accept_invalid_certs)
+        {
+            Some(build(true)?)
+        } else {
+            None
+        };
+
+        Ok(HttpClient {
+            mirrors: mirror
+                .mirrors
+                .into_iter()
+                .map(MirrorRule::new)
+                .collect::<anyhow::Result<_>>()?,
+            tool: None,
+            client_inner: build(false)?,
+            insecure_client_inner,
+            max_download_rate: None,
+            download_buffer_size: Self::DEFAULT_DOWNLOAD_BUFFER_SIZE,
+            github_token: None,
+            download_cache: None,
+            auth: Arc::new([]),
+            fetchers: Arc::new(FxHashMap::from_iter([
+                (
+                    SmolStr::new_static("s3"),
+                    Arc::new(fetch::S3Fetcher::default()) as Arc<dyn fetch::Fetcher>,
+                ),
+                (
+                    SmolStr::new_static("gs"),
+                    Arc::new(fetch::GcsFetcher::default()),
+                ),
+            ])),
+        })
+    }
+
+    /// A client whose archive downloads are limited to `rate`, or unlimited if `None`.
+    pub fn with_max_download_rate(self, rate: Option<ByteRate>) -> HttpClient {
         HttpClient {
-            mirror,
-            client_inner: reqwest::Client::new(),
+            max_download_rate: rate,
+            ..self
         }
     }
 
-    pub fn get(&self, url: &str) -> reqwest::RequestBuilder {
-        for entry in &self.mirror.mirrors {
-            if let Some(rest) = url.strip_prefix(&entry.from) {
-                let mut result = String::new();
-                result.push_str(entry.to.as_str());
-                result.push_str(rest);
-                log::debug!("Applied mirror {} => {}", url, result);
-                return self.client_inner.get(result);
-            }
+    pub fn max_download_rate(&self) -> Option<ByteRate> {
+        self.max_download_rate
+    }
+
+    /// A client that writes downloaded archives to disk through a buffer of `size` bytes. A
+    /// larger buffer means fewer, larger writes, which helps on network file systems.
+    pub fn with_download_buffer_size(self, size: usize) -> HttpClient {
+        HttpClient {
+            download_buffer_size: size,
+            ..self
         }
+    }
 
-        self.client_inner.get(url)
+    pub fn download_buffer_size(&self) -> usize {
+        self.download_buffer_size
     }
-}
 
-pub enum Status {
-    InProgress {
-        name: SmolStr,
-        progress_ratio: Option<(u64, u64)>,
-    },
-    Stopped,
-}
+    /// A client that authenticates GitHub API requests with `token`, raising the rate limit of
+    /// tools that list their releases there.
+    pub fn with_github_token(self, token: Option<SmolStr>) -> HttpClient {
+        HttpClient {
+            github_token: token,
+            ..self
+        }
+    }
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
-pub struct Tag(SmolStr);
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
-pub struct TagStr<'a>(&'a str);
+    pub(crate) fn github_token(&self) -> Option<&str> {
+        self.github_token.as_deref()
+    }
 
-impl<'a> Deref for TagStr<'a> {
-    type Target = str;
-    fn deref(&self) -> &Self::Target {
-        self.0
+    /// A client that keeps the verified archives it downloads in `cache`, and reuses them
+    /// instead of downloading the same archive again.
+    pub fn with_download_cache(self, cache: Option<download_cache::DownloadCache>) -> HttpClient {
+        HttpClient {
+            download_cache: cache,
+            ..self
+        }
     }
-}
 
-impl Deref for Tag {
-    type Target = SmolStr;
+    pub fn download_cache(&self) -> Option<&download_cache::DownloadCache> {
+        self.download_cache.as_ref()
+    }
 
-    fn deref(&self) -> &Self::Target {
-        &self.0
+    /// A client that sends the credentials of `auth` to their hosts. They are only sent to the
+    /// host of each request, over HTTPS unless the entry allows HTTP. When a request redirects
+    /// to another host, its credentials are dropped and those of the new host are sent.
+    pub fn with_auth(self, auth: Vec<HostAuth>) -> anyhow::Result<HttpClient> {
+        Ok(HttpClient {
+            auth: auth
+                .into_iter()
+                .map(AuthRule::new)
+                .collect::<anyhow::Result<_>>()?,
+            ..self
+        })
     }
-}
 
-impl Tag {
-    pub fn as_tag_str(&self) -> TagStr<'_> {
-        TagStr(self.0.as_str())
+    /// A client that sends the requests for URLs with `scheme` through `fetcher`, instead of
+    /// the built-in one for `s3` and `gs` or of failing for an unsupported scheme.
+    pub fn with_fetcher(self, scheme: &str, fetcher: Arc<dyn fetch::Fetcher>) -> HttpClient {
+        let mut fetchers = (*self.fetchers).clone();
+        fetchers.insert(scheme.into(), fetcher);
+        HttpClient {
+            fetchers: Arc::new(fetchers),
+            ..self
+        }
+    }
+
+    fn fetcher(&self, url: &reqwest::Url) -> Option<&dyn fetch::Fetcher> {
+        self.fetchers.get(url.scheme()).map(|fetcher| &**fetcher)
+    }
+
+    /// Adds the configured credentials of the host of `request`, unless it is authenticated
+    /// already, such as GitHub API requests with the token.
+    fn authenticate(&self, request: &mut reqwest::Request) -> anyhow::Result<()> {
+        if request
+            .headers()
+            .contains_key(reqwest::header::AUTHORIZATION)
+        {
+            return Ok(());
+        }
+        if let Some(rule) = self.auth.iter().find(|rule| rule.matches(request.url())) {
+            log::debug!("Sending the credentials of host \"{}\"", rule.host);
+            request
+                .headers_mut()
+                .insert(reqwest::header::AUTHORIZATION, rule.header()?);
+        }
+        Ok(())
     }
-}
 
-pub struct TagIsNotValid(char);
+    /// A client sharing the connections of this one, that also applies the mirrors scoped to `tool`.
+    pub fn for_tool(&self, tool: &str) -> HttpClient {
+        HttpClient {
+            tool: Some(tool.into()),
+            ..self.clone()
+        }
+    }
 
-impl fmt::Display for TagIsNotValid {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "tag contains invalid character: {:?}", self.0)
+    pub fn get(&self, url: &str) -> HttpRequest<'_> {
+        let (client, url) = self.route(url, false);
+        HttpRequest {
+            client: self,
+            inner: client.get(url),
+            mirror_redirects: true,
+        }
     }
-}
 
-impl fmt::Debug for TagIsNotValid {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        fmt::Display::fmt(self, f)
+    /// Sends the request to `url` as is, bypassing the configured mirrors.
+    pub fn get_direct(&self, url: &str) -> HttpRequest<'_> {
+        HttpRequest {
+            client: self,
+            inner: self.client_inner.get(url),
+            mirror_redirects: false,
+        }
     }
-}
 
-impl std::error::Error for TagIsNotValid {}
+    /// Returns the URL that [`HttpClient::get`] would request instead of `url`, if a mirror applies.
+    pub fn mirrored_url(&self, url: &str) -> Option<String> {
+        self.apply_mirror(url, false).map(|(_, result)| result)
+    }
 
-impl<'a> TryFrom<&'a str> for TagStr<'a> {
-    type Error = TagIsNotValid;
+    /// The `from` of the mirror entry that [`HttpClient::get`] would apply to `url`.
+    pub(crate) fn mirror_from(&self, url: &str) -> Option<SmolStr> {
+        self.apply_mirror(url, false)
+            .map(|(entry, _)| SmolStr::from(&entry.from))
+    }
 
-    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
-        if let Some(c) = value.chars().find(|&c| {
-            c == '/'
-                || c == '\\'
-                || c == '\0'
-                || c.is_control()
-                || c == '<'
-                || c == '>'
-                || c == ':'
-                || c == '"'
-                || c == '|'
-                || c == '?'
-                || c == '*'
-        }) {
-            return Err(TagIsNotValid(c));
+    /// Picks the client and the URL to request for `url`. For a redirect target, only the
+    /// mirrors with `follow_redirects` apply.
+    fn route(&self, url: &str, redirect: bool) -> (&reqwest::Client, String) {
+        match self.apply_mirror(url, redirect) {
+            Some((entry, result)) => {
+                log::debug!("Applied mirror {} => {}", url, result);
+                let client = match &self.insecure_client_inner {
+                    Some(insecure) if entry.danger_accept_invalid_certs => {
+                        log::warn!("TLS certificate verification is disabled for {}", result);
+                        insecure
+                    }
+                    _ => &self.client_inner,
+                };
+                (client, result)
+            }
+            None => (&self.client_inner, url.to_owned()),
         }
+    }
 
-        Ok(TagStr(value))
+    fn apply_mirror(&self, url: &str, redirect: bool) -> Option<(&UrlMirrorEntry, String)> {
+        self.mirrors
+            .iter()
+            .filter(|rule| !redirect || rule.entry.follow_redirects)
+            .filter(|rule| rule.applies_to(self.tool.as_deref()))
+            .find_map(|rule| Some((&rule.entry, rule.rewrite(url)?)))
     }
 }
 
-impl TryFrom<SmolStr> for Tag {
-    type Error = TagIsNotValid;
+/// A GET request made through [`HttpClient`].
+pub struct HttpRequest<'a> {
+    client: &'a HttpClient,
+    inner: reqwest::RequestBuilder,
+    mirror_redirects: bool,
+}
+
+impl HttpRequest<'_> {
+    pub fn header(
+        mut self,
+        key: reqwest::header::HeaderName,
+        value: reqwest::header::HeaderValue,
+    ) -> Self {
+        self.inner = self.inner.header(key, value);
+        self
+    }
 
-    fn try_from(value: SmolStr) -> Result<Self, Self::Error> {
-        match TagStr::try_from(value.as_str()) {
-            Ok(_) => Ok(Tag(value)),
-            Err(e) => Err(e),
+    pub fn timeout(mut self, timeout: std::time::Duration) -> Self {
+        self.inner = self.inner.timeout(timeout);
+        self
+    }
+
+    pub async fn send(self) -> anyhow::Result<reqwest::Response> {
+        let client = self.client;
+        let (mut http, request) = self.inner.build_split();
+        let mut request = request?;
+        if let Some(fetcher) = client.fetcher(request.url()) {
+            return fetcher.send(&http, request).await;
         }
+        client.authenticate(&mut request)?;
+
+        for _ in 0..MAX_REDIRECTS {
+            let request_url = request.url().clone();
+            // GET requests have no body, so they can always be cloned.
+            let next_request = request.try_clone();
+            let response = http.execute(request).await?;
+            let location = response
+                .headers()
+                .get(reqwest::header::LOCATION)
+                .and_then(|location| location.to_str().ok());
+            let (Some(location), Some(mut next_request)) = (
+                location.filter(|_| response.status().is_redirection()),
+                next_request,
+            ) else {
+                return Ok(response);
+            };
+
+            let target = request_url.join(location)?;
+            let (next_http, next_url) = if self.mirror_redirects {
+                client.route(target.as_str(), true)
+            } else {
+                (&client.client_inner, target.to_string())
+            };
+            log::debug!("Redirected {} => {}", request_url, next_url);
+            *next_request.url_mut() = reqwest::Url::parse(&next_url)?;
+            if !same_origin(&request_url, next_request.url()) {
+                next_request
+                    .headers_mut()
+                    .remove(reqwest::header::AUTHORIZATION);
+            }
+            if let Some(fetcher) = client.fetcher(next_request.url()) {
+                return fetcher.send(next_http, next_request).await;
+            }
+            client.authenticate(&mut next_request)?;
+            http = next_http.clone();
+            request = next_request;
+        }
+        anyhow::bail!("Too many redirects")
     }
 }
 
+/// Whether credentials for `a` may be sent to `b`, the same rule as reqwest's redirects.
+fn same_origin(a: &reqwest::Url, b: &reqwest::Url) -> bool {
+    a.scheme() == b.scheme()
+        && a.host_str() == b.host_str()
+        && a.port_or_known_default() == b.port_or_known_default()
+}
+
+fn with_no_proxy(
+    proxy: reqwest::Result<reqwest::Proxy>,
+    url: &str,
+    no_proxy: &Option<reqwest::NoProxy>,
+) -> anyhow::Result<reqwest::Proxy> {
+    let proxy = proxy.map_err(|e| anyhow::anyhow!("Invalid proxy URL '{url}': {e}"))?;
+    log::debug!("Using proxy {}", url);
+    Ok(proxy.no_proxy(no_proxy.clone()))
+}
+
+pub enum Status {
+    InProgress {
+        name: SmolStr,
+        progress_ratio: Option<(u64, u64)>,
+    },
+    Stopped,
+}
+
 #[derive(Clone, Default, Deserialize, Serialize)]
 pub struct FileHash {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -170,23 +826,115 @@ pub struct FileHash {
     sha512: Option<SmolStr>,
 }
 
-static CANCELLED: AtomicBool = AtomicBool::new(false);
+impl FileHash {
+    pub fn from_sha256(sha256: SmolStr) -> Self {
+        Self {
+            sha256: Some(sha256),
+            ..Self::default()
+        }
+    }
+
+    /// The hashes by algorithm name.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &SmolStr)> {
+        [
+            ("sha1", &self.sha1),
+            ("sha256", &self.sha256),
+            ("sha512", &self.sha512),
+        ]
+        .into_iter()
+        .filter_map(|(name, hash)| Some((name, hash.as_ref()?)))
+    }
+
+    /// `true` if there is no hash to verify against.
+    pub fn is_empty(&self) -> bool {
+        self.sha1.is_none() && self.sha256.is_none() && self.sha512.is_none()
+    }
+}
+
+pub use tokio_util::sync::CancellationToken;
+use tokio_util::sync::WaitForCancellationFutureOwned;
+
+static CANCELLATION: LazyLock<CancellationToken> = LazyLock::new(CancellationToken::new);
+
+/// The process-wide token, cancelled by [`set_cancelled`] (on Ctrl-C for the CLI).
+/// Formats `time` as RFC 3339 in UTC with milliseconds, like `2024-05-01T12:00:00.000Z`.
+pub fn format_utc(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (days, secs_of_day) = (secs / 86_400, secs % 86_400);
+    // Civil date from days since the epoch, from Howard Hinnant's `civil_from_days`.
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}.{:03}Z",
+        secs_of_day / 3_600,
+        secs_of_day / 60 % 60,
+        secs_of_day % 60,
+        since_epoch.subsec_millis()
+    )
+}
+
+pub fn cancellation_token() -> &'static CancellationToken {
+    &CANCELLATION
+}
+
+static WAIT_FOR_LOCKS: AtomicBool = AtomicBool::new(true);
+
+/// Whether to wait for another avm process changing the same tool directory, or fail with
+/// [`AvmError::ToolBusy`]. Waits by default.
+pub fn set_wait_for_locks(wait: bool) {
+    WAIT_FOR_LOCKS.store(wait, Ordering::Relaxed);
+}
+
+pub fn wait_for_locks() -> bool {
+    WAIT_FOR_LOCKS.load(Ordering::Relaxed)
+}
 
 pub fn set_cancelled() {
-    CANCELLED.store(true, std::sync::atomic::Ordering::Relaxed);
+    CANCELLATION.cancel();
 }
 
 pub fn is_cancelled() -> bool {
-    CANCELLED.load(std::sync::atomic::Ordering::Relaxed)
+    CANCELLATION.is_cancelled()
 }
 
-pub struct CancellableFuture<Fut> {
-    inner: Fut,
+/// Fails once `cancel` is cancelled. Blocking loops call this regularly to stop promptly.
+pub fn check_cancelled(cancel: &CancellationToken) -> anyhow::Result<()> {
+    if cancel.is_cancelled() {
+        return Err(AvmError::Cancelled.into());
+    }
+    Ok(())
+}
+
+pin_project_lite::pin_project! {
+    /// Resolves to `None` as soon as the token is cancelled. The inner future, including any
+    /// in-flight request, is dropped together with this future.
+    pub struct CancellableFuture<Fut> {
+        #[pin]
+        inner: Fut,
+        #[pin]
+        cancelled: WaitForCancellationFutureOwned,
+    }
 }
 
 impl<Fut> CancellableFuture<Fut> {
+    /// Cancelled together with the process-wide [`cancellation_token`].
     pub fn new(inner: Fut) -> Self {
-        CancellableFuture { inner }
+        Self::with_token(inner, cancellation_token().clone())
+    }
+
+    pub fn with_token(inner: Fut, cancel: CancellationToken) -> Self {
+        CancellableFuture {
+            inner,
+            cancelled: cancel.cancelled_owned(),
+        }
     }
 }
 
@@ -197,16 +945,136 @@ where
     type Output = Option<Fut::Output>;
 
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
-        if is_cancelled() {
-            Poll::Ready(None)
-        } else {
-            // SAFETY: CancellableFuture does not move inner after being pinned, and this
-            // projection only creates a pinned mutable reference to that field.
-            let inner = unsafe { self.map_unchecked_mut(|s| &mut s.inner) };
-            match inner.poll(cx) {
-                Poll::Ready(output) => Poll::Ready(Some(output)),
-                Poll::Pending => Poll::Pending,
-            }
+        let this = self.project();
+        // Polling the cancellation first also registers the waker for it.
+        if this.cancelled.poll(cx).is_ready() {
+            return Poll::Ready(None);
         }
+        this.inner.poll(cx).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancellable_future_wakes_up_on_cancel() {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        let cancel = CancellationToken::new();
+        let output = runtime.block_on(async {
+            let canceller = cancel.clone();
+            tokio::spawn(async move { canceller.cancel() });
+            CancellableFuture::with_token(std::future::pending::<()>(), cancel.clone()).await
+        });
+        assert!(output.is_none());
+
+        let output = runtime.block_on(CancellableFuture::with_token(
+            async { 42 },
+            CancellationToken::new(),
+        ));
+        assert_eq!(output, Some(42));
+    }
+
+    #[test]
+    fn byte_rate_parses_units() {
+        let rate = |s: &str| s.parse::<ByteRate>().map(ByteRate::bytes_per_second);
+        assert_eq!(rate("5MB/s").unwrap(), 5_000_000);
+        assert_eq!(rate("1.5 MiB/s").unwrap(), 3 << 19);
+        assert_eq!(rate("512K").unwrap(), 512 * 1024);
+        assert_eq!(rate("100").unwrap(), 100);
+        assert!(rate("5 parsecs").is_err());
+        assert!(rate("0MB/s").is_err());
+        assert!(rate("fast").is_err());
+    }
+
+    #[test]
+    fn byte_size_parses_units() {
+        let size = |s: &str| s.parse::<ByteSize>().map(ByteSize::bytes);
+        assert_eq!(size("2GiB").unwrap(), 2 << 30);
+        assert_eq!(size("500 MB").unwrap(), 500_000_000);
+        assert_eq!(size("0").unwrap(), 0);
+        assert!(size("2 GiB/s").is_err());
+        assert!(size("big").is_err());
+        let config: Config = toml::from_str("max_download_cache_size = 0").unwrap();
+        assert_eq!(config.max_download_cache_size, Some(ByteSize(0)));
+    }
+
+    #[test]
+    fn auth_matches_host_and_port() {
+        let rule = |host: &str| {
+            AuthRule::new(HostAuth {
+                host: host.to_owned(),
+                token_env: Some("TOKEN".to_owned()),
+                username: None,
+                password_env: None,
+                allow_http: false,
+            })
+        };
+        let url = |url: &str| reqwest::Url::parse(url).unwrap();
+        let any_port = rule("Mirror.example.com").unwrap();
+        assert!(any_port.matches(&url("https://mirror.example.com/go/")));
+        assert!(any_port.matches(&url("https://mirror.example.com:8443/go/")));
+        assert!(!any_port.matches(&url("http://mirror.example.com:8080/go/")));
+        assert!(!any_port.matches(&url("https://cdn.example.com/go/")));
+        let with_port = rule("mirror.example.com:443").unwrap();
+        assert!(with_port.matches(&url("https://mirror.example.com/go/")));
+        assert!(!with_port.matches(&url("http://mirror.example.com/go/")));
+        assert!(rule("mirror.example.com:https").is_err());
+        let http = AuthRule::new(HostAuth {
+            host: "mirror.example.com".to_owned(),
+            token_env: Some("TOKEN".to_owned()),
+            username: None,
+            password_env: None,
+            allow_http: true,
+        })
+        .unwrap();
+        assert!(http.matches(&url("http://mirror.example.com:8080/go/")));
+        assert!(!http.matches(&url("ftp://mirror.example.com/go/")));
+
+        let both = AuthRule::new(HostAuth {
+            host: "mirror.example.com".to_owned(),
+            token_env: Some("TOKEN".to_owned()),
+            username: Some("ci".to_owned()),
+            password_env: None,
+            allow_http: false,
+        });
+        assert!(both.is_err());
+    }
+
+    #[test]
+    fn mirror_regex_and_tool_scope() {
+        let mirror: UrlMirror = toml::from_str(
+            r#"
+            [[mirrors]]
+            from = '^https://github\.com/([^/]+)/([^/]+)/releases/download/'
+            to = "https://ghproxy.example.com/$1/$2/"
+            regex = true
+
+            [[mirrors]]
+            from = "https://nodejs.org/dist/"
+            to = "https://npmmirror.example.com/node/"
+            tools = ["node"]
+            "#,
+        )
+        .unwrap();
+        let client = HttpClient::new(mirror, Default::default(), Default::default()).unwrap();
+
+        assert_eq!(
+            client
+                .mirrored_url("https://github.com/owner/repo/releases/download/v1/a.zip")
+                .as_deref(),
+            Some("https://ghproxy.example.com/owner/repo/v1/a.zip")
+        );
+        let node_url = "https://nodejs.org/dist/index.json";
+        assert_eq!(client.mirrored_url(node_url), None);
+        assert_eq!(client.for_tool("go").mirrored_url(node_url), None);
+        assert_eq!(
+            client.for_tool("node").mirrored_url(node_url).as_deref(),
+            Some("https://npmmirror.example.com/node/index.json")
+        );
     }
 }
@@ -0,0 +1,330 @@
+//! Known-good hashes of tool archives, to detect tampered vendor checksums and corrupted
+//! mirrors. The database is embedded in avm and can be updated with a newer one downloaded
+//! into the data directory, whose entries take precedence. Downloaded databases must have a
+//! detached signature by a pinned key, so that a mirror can't replace the pins that are meant
+//! to catch it.
+
+use std::path::Path;
+
+use anyhow::Context;
+use serde::Deserialize;
+use smol_str::SmolStr;
+
+use crate::signature::DetachedSignature;
+use crate::{AvmError, FileHash, HttpClient};
+
+/// The downloaded database under the data directory.
+pub const PINNED_HASHES_FILE: &str = "pinned-hashes.toml";
+
+/// Where `avm update-pinned-hashes` downloads the database from by default.
+pub const DEFAULT_PINNED_HASHES_URL: &str =
+    "https://raw.githubusercontent.com/Jason5Lee/avm/main/src/pinned_hashes.toml";
+
+/// The armored public keys that sign the database at [`DEFAULT_PINNED_HASHES_URL`] with their
+/// fingerprints, `None` until the avm repository publishes a signed database. Other keys are
+/// pinned with `security.pin_hashes_keys`.
+const DEFAULT_SIGNING_KEYS: Option<(&str, &[&str])> = None;
+
+const EMBEDDED: &str = include_str!("pinned_hashes.toml");
+
+/// The keys a downloaded database must be signed by.
+#[derive(Debug, Clone)]
+pub struct SigningKeys {
+    /// Armored public keys.
+    pub keys: Vec<u8>,
+    /// The fingerprints of the keys in `keys` that may sign, in uppercase hex without spaces.
+    pub fingerprints: Vec<SmolStr>,
+}
+
+impl SigningKeys {
+    /// The keys of the database at [`DEFAULT_PINNED_HASHES_URL`], if it is signed yet.
+    pub fn default_database() -> Option<Self> {
+        DEFAULT_SIGNING_KEYS.map(|(keys, fingerprints)| Self {
+            keys: keys.as_bytes().to_vec(),
+            fingerprints: fingerprints
+                .iter()
+                .copied()
+                .map(SmolStr::new_static)
+                .collect(),
+        })
+    }
+}
+
+#[derive(Default, Deserialize)]
+pub struct PinnedHashes {
+    #[serde(default)]
+    hashes: Vec<PinnedHash>,
+}
+
+#[derive(Deserialize)]
+struct PinnedHash {
+    tool: SmolStr,
+    version: SmolStr,
+    /// Any platform if `None`.
+    #[serde(default)]
+    platform: Option<SmolStr>,
+    /// Any flavor if `None`.
+    #[serde(default)]
+    flavor: Option<SmolStr>,
+    #[serde(flatten)]
+    hash: FileHash,
+}
+
+impl PinnedHashes {
+    pub fn embedded() -> Self {
+        Self::parse(EMBEDDED).expect("The embedded pinned hashes are invalid")
+    }
+
+    pub fn parse(content: &str) -> crate::Result<Self> {
+        let pinned: Self = toml::from_str(content).map_err(anyhow::Error::from)?;
+        if let Some(entry) = pinned.hashes.iter().find(|entry| entry.hash.is_empty()) {
+            return Err(anyhow::anyhow!(
+                "The pinned entry of {} {} has no hash",
+                entry.tool,
+                entry.version
+            )
+            .into());
+        }
+        Ok(pinned)
+    }
+
+    /// The embedded database, after the entries downloaded into `data_dir` if any.
+    pub async fn load(data_dir: &Path) -> crate::Result<Self> {
+        let path = data_dir.join(PINNED_HASHES_FILE);
+        let mut pinned =
+            crate::spawn_blocking(move || match std::fs::read_to_string(&path) {
+                Ok(content) => Ok(Self::parse(&content)
+                    .with_context(|| format!("Invalid '{}'", path.display()))?),
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+                Err(err) => Err(anyhow::Error::from(err).context(path.display().to_string())),
+            })
+            .await?;
+        pinned.hashes.extend(Self::embedded().hashes);
+        Ok(pinned)
+    }
+
+    /// Downloads the database from `url` into `data_dir`, replacing the previous download, once
+    /// its signature at `<url>.sig` is verified with `keys`. Both are requested without the
+    /// mirrors. Returns the number of entries.
+    pub async fn update(
+        client: &HttpClient,
+        url: &str,
+        keys: &SigningKeys,
+        data_dir: &Path,
+    ) -> crate::Result<usize> {
+        let fetch = |url: String| async move {
+            let bytes = client
+                .get_direct(&url)
+                .send()
+                .await?
+                .error_for_status()?
+                .bytes()
+                .await?;
+            anyhow::Ok(bytes.to_vec())
+        };
+        let content = fetch(url.to_owned()).await?;
+        let signature_url = format!("{url}.sig");
+        let signature = DetachedSignature {
+            signature: fetch(signature_url.clone()).await?,
+            url: signature_url.into(),
+            keys: keys.keys.clone(),
+            fingerprints: keys.fingerprints.clone(),
+        };
+        let (url, data_dir) = (url.to_owned(), data_dir.to_path_buf());
+        Ok(crate::spawn_blocking(move || {
+            save_verified_blocking(&content, &signature, &data_dir)
+                .with_context(|| format!("Invalid database at {url}"))
+        })
+        .await?)
+    }
+
+    fn find(
+        &self,
+        tool_name: &str,
+        version: &str,
+        platform: Option<&str>,
+        flavor: Option<&str>,
+    ) -> Option<&FileHash> {
+        self.hashes
+            .iter()
+            .find(|entry| {
+                entry.tool == tool_name
+                    && entry.version == version
+                    && entry
+                        .platform
+                        .as_ref()
+                        .is_none_or(|p| Some(p.as_str()) == platform)
+                    && entry
+                        .flavor
+                        .as_ref()
+                        .is_none_or(|f| Some(f.as_str()) == flavor)
+            })
+            .map(|entry| &entry.hash)
+    }
+
+    /// Cross-checks the vendor hash of an archive with the pinned one. Returns the hash to
+    /// verify the download against, the vendor hash with the pinned algorithms it lacks.
+    pub fn check(
+        &self,
+        tool_name: &str,
+        version: &str,
+        platform: Option<&str>,
+        flavor: Option<&str>,
+        vendor: &FileHash,
+    ) -> crate::Result<FileHash> {
+        let Some(pinned) = self.find(tool_name, version, platform, flavor) else {
+            log::debug!("No pinned hash of {tool_name} {version}");
+            return Ok(vendor.clone());
+        };
+        let mut checked = vendor.clone();
+        let pairs = [
+            ("Sha1", &mut checked.sha1, &pinned.sha1),
+            ("Sha256", &mut checked.sha256, &pinned.sha256),
+            ("Sha512", &mut checked.sha512, &pinned.sha512),
+        ];
+        for (algorithm, vendor, pinned) in pairs {
+            let Some(pinned) = pinned else {
+                continue;
+            };
+            match vendor {
+                Some(vendor) if !vendor.eq_ignore_ascii_case(pinned) => {
+                    return Err(AvmError::PinnedHashMismatch {
+                        tool: tool_name.into(),
+                        version: version.into(),
+                        algorithm,
+                        pinned: pinned.to_ascii_lowercase().into(),
+                        vendor: vendor.to_ascii_lowercase().into(),
+                    });
+                }
+                Some(_) => {}
+                None => *vendor = Some(pinned.clone()),
+            }
+        }
+        log::debug!("The vendor hash of {tool_name} {version} matches the pinned one");
+        Ok(checked)
+    }
+}
+
+/// Saves the database `content` into `data_dir` if it parses and `signature` verifies it,
+/// returning the number of entries.
+fn save_verified_blocking(
+    content: &[u8],
+    signature: &DetachedSignature,
+    data_dir: &Path,
+) -> anyhow::Result<usize> {
+    let count = PinnedHashes::parse(std::str::from_utf8(content)?)?
+        .hashes
+        .len();
+    std::fs::create_dir_all(data_dir)?;
+    let path = data_dir.join(PINNED_HASHES_FILE);
+    let partial = data_dir.join(format!("{PINNED_HASHES_FILE}.partial"));
+    let work_dir = data_dir.join(format!("{PINNED_HASHES_FILE}.verify"));
+    std::fs::write(&partial, content)?;
+    let verified = signature.verify_blocking(&partial, &work_dir);
+    std::fs::remove_dir_all(&work_dir).ok();
+    if let Err(err) = verified {
+        std::fs::remove_file(&partial).ok();
+        return Err(err);
+    }
+    std::fs::rename(&partial, &path)?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PINNED: &str = r#"
+[[hashes]]
+tool = "node"
+version = "22.11.0"
+platform = "x64-linux"
+sha256 = "AABB"
+
+[[hashes]]
+tool = "liberica"
+version = "21.0.5"
+sha512 = "ccdd"
+"#;
+
+    #[test]
+    fn embedded_hashes_parse() {
+        PinnedHashes::embedded();
+    }
+
+    #[test]
+    fn cross_checks_vendor_hashes() {
+        let pinned = PinnedHashes::parse(PINNED).unwrap();
+        let vendor = FileHash::from_sha256("aabb".into());
+
+        let checked = pinned
+            .check("node", "22.11.0", Some("x64-linux"), None, &vendor)
+            .unwrap();
+        assert_eq!(checked.sha256.as_deref(), Some("aabb"));
+        let result = pinned.check(
+            "node",
+            "22.11.0",
+            Some("x64-linux"),
+            None,
+            &FileHash::from_sha256("eeff".into()),
+        );
+        assert!(matches!(result, Err(AvmError::PinnedHashMismatch { .. })));
+
+        // Entries of other platforms don't apply.
+        let checked = pinned
+            .check("node", "22.11.0", Some("arm64-mac"), None, &vendor)
+            .unwrap();
+        assert_eq!(checked.sha512, None);
+
+        // The pinned algorithms missing from the vendor hash are added.
+        let checked = pinned
+            .check(
+                "liberica",
+                "21.0.5",
+                Some("x64-linux"),
+                Some("jdk"),
+                &vendor,
+            )
+            .unwrap();
+        assert_eq!(checked.sha256.as_deref(), Some("aabb"));
+        assert_eq!(checked.sha512.as_deref(), Some("ccdd"));
+    }
+
+    #[test]
+    fn saves_databases_signed_by_a_pinned_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let signed = dir.path().join("signed.toml");
+        std::fs::write(&signed, PINNED).unwrap();
+        let Some((signature, keys, fingerprint)) =
+            crate::signature::sign_with_new_key(dir.path(), &signed)
+        else {
+            eprintln!("Skipped, `gpg` is not installed");
+            return;
+        };
+        let signature = DetachedSignature {
+            url: "https://example.com/pinned-hashes.toml.sig".into(),
+            signature,
+            keys,
+            fingerprints: vec![fingerprint.into()],
+        };
+        let data_dir = dir.path().join("data");
+
+        assert_eq!(
+            save_verified_blocking(PINNED.as_bytes(), &signature, &data_dir).unwrap(),
+            2
+        );
+        // A database the key didn't sign, like one replaced by a mirror, is not saved.
+        let tampered = PINNED.replace("AABB", "EEFF");
+        assert!(save_verified_blocking(tampered.as_bytes(), &signature, &data_dir).is_err());
+        assert_eq!(
+            std::fs::read_to_string(data_dir.join(PINNED_HASHES_FILE)).unwrap(),
+            PINNED
+        );
+        assert_eq!(std::fs::read_dir(&data_dir).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn rejects_entries_without_hash() {
+        assert!(PinnedHashes::parse("[[hashes]]\ntool = \"go\"\nversion = \"1.23.0\"\n").is_err());
+    }
+}
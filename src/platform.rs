@@ -50,11 +50,8 @@ pub fn current_os() -> Option<&'static str> {
     #[cfg(all(target_os = "windows", not(target_env = "gnu")))]
     return Some(os::WIN);
 
-    #[cfg(all(target_os = "linux", target_env = "musl"))]
-    return Some(os::LINUX_MUSL);
-
-    #[cfg(all(target_os = "linux", not(target_env = "musl")))]
-    return Some(os::LINUX);
+    #[cfg(target_os = "linux")]
+    return Some(if is_musl() { os::LINUX_MUSL } else { os::LINUX });
 
     #[cfg(target_os = "macos")]
     return Some(os::MAC);
@@ -83,6 +80,96 @@ pub fn current_os() -> Option<&'static str> {
     None
 }
 
+/// Whether the C library of the running Linux system is musl, as on Alpine, regardless of
+/// the libc avm itself is built for. The system has musl if it has the musl dynamic loader,
+/// `/lib/ld-musl-<arch>.so.1`, but not the glibc one, which some glibc distributions ship
+/// alongside the musl one.
+#[cfg(target_os = "linux")]
+pub fn is_musl() -> bool {
+    static IS_MUSL: std::sync::OnceLock<bool> = std::sync::OnceLock::new();
+    *IS_MUSL.get_or_init(|| {
+        let has_loader = |dirs: &[&str], prefix: &str| {
+            dirs.iter().any(|dir| {
+                std::fs::read_dir(dir).is_ok_and(|entries| {
+                    entries.flatten().any(|entry| {
+                        entry
+                            .file_name()
+                            .to_str()
+                            .is_some_and(|name| name.starts_with(prefix))
+                    })
+                })
+            })
+        };
+        if has_loader(&["/lib"], "ld-musl-") {
+            !has_loader(&["/lib", "/lib64"], "ld-linux")
+        } else {
+            // Not a dynamically linked system, such as a scratch container.
+            cfg!(target_env = "musl") && !has_loader(&["/lib", "/lib64"], "ld-linux")
+        }
+    })
+}
+
+/// OS fallbacks for tools whose builds for the first OS also run on the second one, such as
+/// statically linked binaries that only have glibc builds but run on musl.
+pub const MUSL_TO_GLIBC: (&str, &str) = (os::LINUX_MUSL, os::LINUX);
+
+/// Picks the default platform of a tool among `all_platforms`: the configured one if the tool
+/// supports it, otherwise the current platform, otherwise the current CPU on the first OS
+/// `os_fallbacks` maps the current OS to that the tool supports.
+pub fn resolve_default_platform(
+    all_platforms: &[SmolStr],
+    config_default_platform: Option<SmolStr>,
+    os_fallbacks: &[(&str, &str)],
+) -> Option<SmolStr> {
+    config_default_platform
+        .and_then(|p| all_platforms.iter().find(|&k| p == *k).cloned())
+        .or_else(|| {
+            let cpu = current_cpu()?;
+            let os = current_os()?;
+            pick_platform(all_platforms, cpu, os, os_fallbacks)
+        })
+}
+
+fn pick_platform(
+    all_platforms: &[SmolStr],
+    cpu: &str,
+    os: &str,
+    os_fallbacks: &[(&str, &str)],
+) -> Option<SmolStr> {
+    std::iter::once(os)
+        .chain(
+            os_fallbacks
+                .iter()
+                .filter(|(from, _)| *from == os)
+                .map(|(_, to)| *to),
+        )
+        .find_map(|os| {
+            let p = create_platform_string(cpu, os);
+            all_platforms.iter().find(|&k| p == *k).cloned()
+        })
+}
+
+/// The platform of the running system, `<cpu>-<os>`.
+pub fn current_platform() -> Option<SmolStr> {
+    Some(create_platform_string(current_cpu()?, current_os()?))
+}
+
+/// Platforms the system can run the builds of another platform for, under emulation:
+/// x64 builds on Apple Silicon with Rosetta 2 and on Windows on ARM.
+const EMULATED_PLATFORMS: [(&str, &str, &str); 2] = [
+    (cpu::ARM64, os::MAC, cpu::X64),
+    (cpu::ARM64, os::WIN, cpu::X64),
+];
+
+/// The platform whose builds run under emulation on `platform`, tried when a tool has no
+/// build for `platform`.
+pub fn emulated_platform(platform: &str) -> Option<SmolStr> {
+    EMULATED_PLATFORMS
+        .iter()
+        .find(|(cpu, os, _)| create_platform_string(cpu, os) == platform)
+        .map(|(_, os, emulated_cpu)| create_platform_string(emulated_cpu, os))
+}
+
 #[allow(unreachable_code)]
 pub fn current_cpu() -> Option<&'static str> {
     #[cfg(target_arch = "x86")]
@@ -138,3 +225,38 @@ pub fn current_cpu() -> Option<&'static str> {
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_fallback_os_without_native_build() {
+        let platforms = ["x64-linux", "arm64-linux", "x64-linux_musl"].map(SmolStr::new);
+        let fallbacks = [MUSL_TO_GLIBC];
+        assert_eq!(
+            pick_platform(&platforms, cpu::X64, os::LINUX_MUSL, &fallbacks).as_deref(),
+            Some("x64-linux_musl")
+        );
+        assert_eq!(
+            pick_platform(&platforms, cpu::ARM64, os::LINUX_MUSL, &fallbacks).as_deref(),
+            Some("arm64-linux")
+        );
+        assert_eq!(
+            pick_platform(&platforms, cpu::ARM64, os::LINUX_MUSL, &[]),
+            None
+        );
+        assert_eq!(
+            pick_platform(&platforms, cpu::ARM64, os::MAC, &fallbacks),
+            None
+        );
+    }
+
+    #[test]
+    fn maps_platforms_to_emulated_ones() {
+        assert_eq!(emulated_platform("arm64-mac").as_deref(), Some("x64-mac"));
+        assert_eq!(emulated_platform("arm64-win").as_deref(), Some("x64-win"));
+        assert_eq!(emulated_platform("arm64-linux"), None);
+        assert_eq!(emulated_platform("x64-mac"), None);
+    }
+}
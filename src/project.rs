@@ -0,0 +1,95 @@
+//! The versions a project pins in a `.tool-versions` file, in the format of asdf: a line per
+//! tool with its name and version, such as `node 22.13.1`. The version may be a tag or a
+//! version spec, see [`crate::tool::VersionSpec`].
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use smol_str::SmolStr;
+
+pub const TOOL_VERSIONS_FILE: &str = ".tool-versions";
+
+/// A version pinned for a tool by a `.tool-versions` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PinnedVersion {
+    pub version: SmolStr,
+    /// The file pinning it.
+    pub file: PathBuf,
+}
+
+/// Finds the version of `tool_name` in the nearest `.tool-versions` in `dir` or its
+/// ancestors. Files that don't list the tool are skipped.
+pub fn find_pinned_version(dir: &Path, tool_name: &str) -> crate::Result<Option<PinnedVersion>> {
+    for dir in dir.ancestors() {
+        let file = dir.join(TOOL_VERSIONS_FILE);
+        let content = match std::fs::read_to_string(&file) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => {
+                return Err(anyhow::Error::from(err)
+                    .context(format!("Failed to read '{}'", file.display()))
+                    .into())
+            }
+        };
+        if let Some(version) = parse_version(&content, tool_name)
+            .with_context(|| format!("Invalid '{}'", file.display()))?
+        {
+            return Ok(Some(PinnedVersion { version, file }));
+        }
+    }
+    Ok(None)
+}
+
+/// The version of `tool_name` in the content of a `.tool-versions` file. Only the first
+/// version of a line is used, asdf falls back to the others if it is not installed.
+fn parse_version(content: &str, tool_name: &str) -> anyhow::Result<Option<SmolStr>> {
+    for line in content.lines() {
+        let line = line.split_once('#').map_or(line, |(line, _)| line);
+        let mut fields = line.split_whitespace();
+        if fields.next() != Some(tool_name) {
+            continue;
+        }
+        return match fields.next() {
+            Some(version) => Ok(Some(version.into())),
+            None => Err(anyhow::anyhow!("No version for \"{tool_name}\"")),
+        };
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_tool_versions() {
+        let content =
+            "# Pinned for CI\nnodejs 20.0.0\nnode 22.13.1 21.0.0 # newest first\ngo  1.22\n";
+        assert_eq!(
+            parse_version(content, "node").unwrap().as_deref(),
+            Some("22.13.1")
+        );
+        assert_eq!(
+            parse_version(content, "go").unwrap().as_deref(),
+            Some("1.22")
+        );
+        assert_eq!(parse_version(content, "ruby").unwrap(), None);
+        assert!(parse_version("node\n", "node").is_err());
+    }
+
+    #[test]
+    fn nearest_file_listing_the_tool_wins() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir.path().join("app").join("src");
+        std::fs::create_dir_all(&nested).unwrap();
+        std::fs::write(dir.path().join(TOOL_VERSIONS_FILE), "node 20\ngo 1.22\n").unwrap();
+        std::fs::write(dir.path().join("app").join(TOOL_VERSIONS_FILE), "node 22\n").unwrap();
+
+        let node = find_pinned_version(&nested, "node").unwrap().unwrap();
+        assert_eq!(node.version, "22");
+        assert_eq!(node.file, dir.path().join("app").join(TOOL_VERSIONS_FILE));
+        let go = find_pinned_version(&nested, "go").unwrap().unwrap();
+        assert_eq!(go.version, "1.22");
+        assert_eq!(find_pinned_version(&nested, "ruby").unwrap(), None);
+    }
+}
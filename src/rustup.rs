@@ -0,0 +1,255 @@
+//! A rustup installation managed by avm, kept under the data directory apart from one the
+//! user may have installed. Its toolchains are managed by running it.
+
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use smol_str::SmolStr;
+
+use crate::platform::{cpu, create_platform_string, os};
+use crate::HttpClient;
+
+/// The directory under the data directory holding `RUSTUP_HOME` and `CARGO_HOME`.
+pub const RUST_DIR: &str = "rust";
+
+const DIST_URL: &str = "https://static.rust-lang.org/rustup/dist/";
+
+/// Target triples of the `rustup-init` builds by platform.
+const HOST_TRIPLES: [(&str, &str, &str); 10] = [
+    (cpu::X64, os::LINUX, "x86_64-unknown-linux-gnu"),
+    (cpu::ARM64, os::LINUX, "aarch64-unknown-linux-gnu"),
+    (cpu::X64, os::LINUX_MUSL, "x86_64-unknown-linux-musl"),
+    (cpu::ARM64, os::LINUX_MUSL, "aarch64-unknown-linux-musl"),
+    (cpu::X64, os::MAC, "x86_64-apple-darwin"),
+    (cpu::ARM64, os::MAC, "aarch64-apple-darwin"),
+    (cpu::X64, os::WIN, "x86_64-pc-windows-msvc"),
+    (cpu::ARM64, os::WIN, "aarch64-pc-windows-msvc"),
+    (cpu::X86, os::WIN, "i686-pc-windows-msvc"),
+    (cpu::X64, os::FREEBSD, "x86_64-unknown-freebsd"),
+];
+
+pub struct Rustup {
+    dir: PathBuf,
+    rustup_home: PathBuf,
+    cargo_home: PathBuf,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Toolchain {
+    pub name: SmolStr,
+    pub is_default: bool,
+}
+
+impl Rustup {
+    pub fn new(data_dir: &Path) -> Self {
+        let dir = data_dir.join(RUST_DIR);
+        Self {
+            rustup_home: dir.join("rustup"),
+            cargo_home: dir.join("cargo"),
+            dir,
+        }
+    }
+
+    /// Where `rustup`, `cargo` and the other proxies of the toolchains are installed.
+    pub fn bin_dir(&self) -> PathBuf {
+        self.cargo_home.join("bin")
+    }
+
+    pub fn rustup_path(&self) -> PathBuf {
+        self.bin_dir()
+            .join(format!("rustup{}", std::env::consts::EXE_SUFFIX))
+    }
+
+    pub async fn is_installed(&self) -> crate::Result<bool> {
+        let rustup_path = self.rustup_path();
+        Ok(crate::spawn_blocking(move || Ok(rustup_path.exists())).await?)
+    }
+
+    /// Downloads `rustup-init` for the current platform and runs it without a toolchain,
+    /// leaving the shell profiles untouched.
+    pub async fn install(&self, client: &HttpClient) -> crate::Result<()> {
+        let platform = crate::platform::current_platform().unwrap_or_default();
+        let triple =
+            host_triple(&platform).ok_or_else(|| crate::AvmError::UnsupportedPlatform {
+                platform: platform.clone(),
+                supported: HOST_TRIPLES
+                    .iter()
+                    .map(|(cpu, os, _)| create_platform_string(cpu, os))
+                    .collect(),
+            })?;
+        let file_name = format!("rustup-init{}", std::env::consts::EXE_SUFFIX);
+        let url = format!("{DIST_URL}{triple}/{file_name}");
+        let sha256 = client
+            .get(&format!("{url}.sha256"))
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        let expected = crate::FileHash::from_sha256(
+            sha256
+                .split_whitespace()
+                .next()
+                .ok_or_else(|| anyhow::anyhow!("Empty checksum file for {url}"))?
+                .into(),
+        );
+        log::info!("Downloading {url}");
+        let content = client
+            .get(&url)
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?;
+        let mut hasher = crate::io::FileHasher::new(&expected);
+        hasher.update(&content);
+        crate::io::verify_digest(&expected, &hasher.finalize())?;
+
+        let init_path = self.dir.join(file_name);
+        let mut command = self.command_of(&init_path);
+        command.args(["-y", "--no-modify-path", "--default-toolchain", "none"]);
+        let dir = self.dir.clone();
+        crate::spawn_blocking(move || {
+            std::fs::create_dir_all(&dir)?;
+            std::fs::write(&init_path, &content)?;
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+
+                std::fs::set_permissions(&init_path, std::fs::Permissions::from_mode(0o755))?;
+            }
+            let status = command.status();
+            std::fs::remove_file(&init_path).ok();
+            let status = status?;
+            if !status.success() {
+                anyhow::bail!("rustup-init failed with {status}");
+            }
+            Ok(())
+        })
+        .await?;
+        Ok(())
+    }
+
+    /// A `rustup` command working on this installation.
+    pub fn command(&self) -> Command {
+        self.command_of(&self.rustup_path())
+    }
+
+    fn command_of(&self, program: &Path) -> Command {
+        let mut command = Command::new(program);
+        command
+            .env("RUSTUP_HOME", &self.rustup_home)
+            .env("CARGO_HOME", &self.cargo_home);
+        command
+    }
+
+    /// Runs `rustup` with `args`, failing if it exits unsuccessfully.
+    pub async fn run(
+        &self,
+        args: impl IntoIterator<Item = impl AsRef<OsStr>>,
+    ) -> crate::Result<()> {
+        let mut command = self.command();
+        command.args(args);
+        crate::spawn_blocking(move || {
+            let status = command.status()?;
+            if !status.success() {
+                anyhow::bail!("rustup failed with {status}");
+            }
+            Ok(())
+        })
+        .await?;
+        Ok(())
+    }
+
+    pub async fn toolchains(&self) -> crate::Result<Vec<Toolchain>> {
+        let mut command = self.command();
+        command.args(["toolchain", "list"]);
+        let output = crate::spawn_blocking(move || {
+            let output = command.output()?;
+            if !output.status.success() {
+                anyhow::bail!(
+                    "rustup failed with {}\n{}",
+                    output.status,
+                    String::from_utf8_lossy(&output.stderr)
+                );
+            }
+            Ok(output.stdout)
+        })
+        .await?;
+        Ok(parse_toolchain_list(&String::from_utf8_lossy(&output)))
+    }
+}
+
+fn host_triple(platform: &str) -> Option<&'static str> {
+    HOST_TRIPLES
+        .iter()
+        .find(|(cpu, os, _)| create_platform_string(cpu, os) == platform)
+        .map(|(_, _, triple)| *triple)
+}
+
+/// Parses the output of `rustup toolchain list`, lines like
+/// `stable-x86_64-unknown-linux-gnu (active, default)`.
+fn parse_toolchain_list(output: &str) -> Vec<Toolchain> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && *line != "no installed toolchains")
+        .map(|line| {
+            let (name, notes) = line.split_once(' ').unwrap_or((line, ""));
+            let is_default = notes
+                .trim()
+                .trim_start_matches('(')
+                .trim_end_matches(')')
+                .split(',')
+                .any(|note| note.trim() == "default");
+            Toolchain {
+                name: name.into(),
+                is_default,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_toolchain_list() {
+        let output = "stable-x86_64-unknown-linux-gnu (active, default)\nnightly-2024-11-01-x86_64-unknown-linux-gnu\n1.82.0-x86_64-unknown-linux-gnu (override)\n";
+        assert_eq!(
+            parse_toolchain_list(output),
+            [
+                Toolchain {
+                    name: "stable-x86_64-unknown-linux-gnu".into(),
+                    is_default: true,
+                },
+                Toolchain {
+                    name: "nightly-2024-11-01-x86_64-unknown-linux-gnu".into(),
+                    is_default: false,
+                },
+                Toolchain {
+                    name: "1.82.0-x86_64-unknown-linux-gnu".into(),
+                    is_default: false,
+                },
+            ]
+        );
+        assert_eq!(parse_toolchain_list("no installed toolchains\n"), []);
+        // Older rustup versions only mark the default.
+        assert_eq!(
+            parse_toolchain_list("stable-aarch64-apple-darwin (default)\n"),
+            [Toolchain {
+                name: "stable-aarch64-apple-darwin".into(),
+                is_default: true,
+            }]
+        );
+    }
+
+    #[test]
+    fn maps_platforms_to_host_triples() {
+        assert_eq!(host_triple("x64-linux"), Some("x86_64-unknown-linux-gnu"));
+        assert_eq!(host_triple("arm64-mac"), Some("aarch64-apple-darwin"));
+        assert_eq!(host_triple("riscv64-linux"), None);
+    }
+}
@@ -0,0 +1,231 @@
+//! Detached OpenPGP signatures of release archives, for tools whose vendor signs the archives
+//! instead of publishing their checksums. They are checked with `gpg` in a keyring of its own,
+//! so the keys of the user are neither used nor changed. The keys are downloaded along with the
+//! signature, so only signatures by the key fingerprints pinned in avm are accepted.
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use anyhow::Context;
+use smol_str::SmolStr;
+
+/// A detached signature of an archive, with the public keys it must be made by.
+#[derive(Debug, Clone)]
+pub struct DetachedSignature {
+    /// The URL of the signature, to report failures.
+    pub url: SmolStr,
+    pub signature: Vec<u8>,
+    /// Armored public keys.
+    pub keys: Vec<u8>,
+    /// The fingerprints of the primary keys, or of the signing subkeys, that may make the
+    /// signature, in uppercase hex without spaces.
+    pub fingerprints: Vec<SmolStr>,
+}
+
+impl DetachedSignature {
+    /// Verifies `archive` with the signature, using `work_dir` for the keyring. Fails with
+    /// [`crate::AvmError::SignatureMismatch`] if the signature is not a valid one of a key
+    /// with one of the pinned fingerprints.
+    pub(crate) fn verify_blocking(&self, archive: &Path, work_dir: &Path) -> anyhow::Result<()> {
+        let home = work_dir.join("gnupg");
+        std::fs::create_dir_all(&home)
+            .with_context(|| format!("Failed to create '{}'", home.display()))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&home, std::fs::Permissions::from_mode(0o700))?;
+        }
+        let keys_path = work_dir.join("keys.asc");
+        let signature_path = work_dir.join("archive.sig");
+        std::fs::write(&keys_path, &self.keys)?;
+        std::fs::write(&signature_path, &self.signature)?;
+
+        let gpg = |args: &[&std::ffi::OsStr]| {
+            Command::new("gpg")
+                .arg("--homedir")
+                .arg(&home)
+                .args(["--batch", "--no-tty", "--quiet"])
+                .args(args)
+                .stdin(Stdio::null())
+                .output()
+                .map_err(|err| {
+                    anyhow::Error::from(err).context(format!(
+                        "Verifying '{}' requires `gpg`, install GnuPG",
+                        self.url
+                    ))
+                })
+        };
+        let import = gpg(&["--import".as_ref(), keys_path.as_os_str()])?;
+        if !import.status.success() {
+            anyhow::bail!(
+                "Failed to import the keys to verify '{}': {}",
+                self.url,
+                String::from_utf8_lossy(&import.stderr).trim()
+            );
+        }
+        let verify = gpg(&[
+            "--status-fd".as_ref(),
+            "1".as_ref(),
+            "--verify".as_ref(),
+            signature_path.as_os_str(),
+            archive.as_os_str(),
+        ])?;
+        if !verify.status.success() {
+            return Err(crate::AvmError::SignatureMismatch {
+                url: self.url.clone(),
+                reason: String::from_utf8_lossy(&verify.stderr).trim().to_owned(),
+            }
+            .into());
+        }
+        let signers = valid_signers(&String::from_utf8_lossy(&verify.stdout));
+        if !signers
+            .iter()
+            .any(|fingerprint| self.fingerprints.iter().any(|pinned| pinned == fingerprint))
+        {
+            return Err(crate::AvmError::SignatureMismatch {
+                url: self.url.clone(),
+                reason: format!(
+                    "the signature is by {}, which is not a pinned key",
+                    if signers.is_empty() {
+                        "no valid key".to_owned()
+                    } else {
+                        signers.join(", ")
+                    }
+                ),
+            }
+            .into());
+        }
+        log::debug!("Signature verification passed");
+        Ok(())
+    }
+}
+
+/// The fingerprints of the signing keys and of their primary keys in the `VALIDSIG` lines of
+/// the status output of `gpg --verify`.
+fn valid_signers(status: &str) -> Vec<String> {
+    status
+        .lines()
+        .filter_map(|line| line.strip_prefix("[GNUPG:] VALIDSIG "))
+        .flat_map(|args| {
+            let args = args.split_whitespace().collect::<Vec<_>>();
+            // The fingerprint of the signing key comes first and that of the primary key last.
+            [args.first().copied(), args.get(9).copied()]
+        })
+        .flatten()
+        .map(str::to_ascii_uppercase)
+        .collect()
+}
+
+/// Signs `file` with a key generated under `dir`, returning the detached signature, the
+/// armored public key and its fingerprint, or `None` if `gpg` is not installed.
+#[cfg(test)]
+pub(crate) fn sign_with_new_key(dir: &Path, file: &Path) -> Option<(Vec<u8>, Vec<u8>, String)> {
+    Command::new("gpg").arg("--version").output().ok()?;
+    let home = dir.join("signer");
+    std::fs::create_dir_all(&home).unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&home, std::fs::Permissions::from_mode(0o700)).unwrap();
+    }
+    let gpg = |args: &[&std::ffi::OsStr]| {
+        let output = Command::new("gpg")
+            .arg("--homedir")
+            .arg(&home)
+            .args([
+                "--batch",
+                "--no-tty",
+                "--quiet",
+                "--pinentry-mode",
+                "loopback",
+            ])
+            .args(["--passphrase", ""])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{output:?}");
+        output.stdout
+    };
+    gpg(&[
+        "--quick-generate-key".as_ref(),
+        "release@example.com".as_ref(),
+        "ed25519".as_ref(),
+        "sign".as_ref(),
+        "never".as_ref(),
+    ]);
+    let signature = gpg(&[
+        "--detach-sign".as_ref(),
+        "--output".as_ref(),
+        "-".as_ref(),
+        file.as_os_str(),
+    ]);
+    let keys = gpg(&["--armor".as_ref(), "--export".as_ref()]);
+    let listed = gpg(&["--with-colons".as_ref(), "--fingerprint".as_ref()]);
+    let fingerprint = String::from_utf8(listed)
+        .unwrap()
+        .lines()
+        .find_map(|line| Some(line.strip_prefix("fpr:")?.trim_matches(':').to_owned()))
+        .unwrap();
+    Some((signature, keys, fingerprint))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_the_fingerprints_of_valid_signatures() {
+        let status = "[GNUPG:] NEWSIG\n\
+            [GNUPG:] GOODSIG EF80A866B47A981F Swift Automatic Signing Key #4\n\
+            [GNUPG:] VALIDSIG 0123456789abcdef0123456789abcdef01234567 2024-06-10 1718000000 \
+            0 4 0 1 10 00 52BB7E3DE28A71BE22EC05FFEF80A866B47A981F\n";
+        assert_eq!(
+            valid_signers(status),
+            [
+                "0123456789ABCDEF0123456789ABCDEF01234567",
+                "52BB7E3DE28A71BE22EC05FFEF80A866B47A981F"
+            ]
+        );
+        assert!(valid_signers("[GNUPG:] BADSIG EF80A866B47A981F\n").is_empty());
+    }
+
+    #[test]
+    fn verifies_archives_with_the_signature() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("archive.tar.gz");
+        std::fs::write(&archive, "release").unwrap();
+        let Some((signature, keys, fingerprint)) = sign_with_new_key(dir.path(), &archive) else {
+            eprintln!("Skipped, `gpg` is not installed");
+            return;
+        };
+
+        let mut signature = DetachedSignature {
+            url: "https://example.com/archive.tar.gz.sig".into(),
+            signature,
+            keys,
+            fingerprints: vec!["A62AE125BBBFBB96A6E042EC925CC1CCED3D1561".into()],
+        };
+        // A valid signature by a key that is not pinned, like one from a tampered keyring.
+        let err = signature
+            .verify_blocking(&archive, &dir.path().join("work-unpinned"))
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<crate::AvmError>(),
+            Some(crate::AvmError::SignatureMismatch { .. })
+        ));
+
+        signature.fingerprints = vec![fingerprint.into()];
+        signature
+            .verify_blocking(&archive, &dir.path().join("work"))
+            .unwrap();
+
+        std::fs::write(&archive, "tampered").unwrap();
+        let err = signature
+            .verify_blocking(&archive, &dir.path().join("work-tampered"))
+            .unwrap_err();
+        assert!(matches!(
+            err.downcast_ref::<crate::AvmError>(),
+            Some(crate::AvmError::SignatureMismatch { .. })
+        ));
+    }
+}
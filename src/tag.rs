@@ -0,0 +1,195 @@
+//! Validation of tag names. A tag is the name of a directory under the tool directory, so
+//! it must be a single path component that is valid on every supported OS. Tags are
+//! NFC-normalized so that the same name typed on different systems refers to the same tag.
+
+use std::fmt;
+use std::ops::Deref;
+
+use icu_normalizer::ComposingNormalizerBorrowed;
+use serde::{Deserialize, Serialize};
+use smol_str::SmolStr;
+
+use crate::io::blocking::TMP_PREFIX;
+use crate::AvmError;
+
+/// The longest tag in bytes, leaving room for the temporary directory prefix and the
+/// path of the tool directory within the file name limits.
+pub const MAX_TAG_LEN: usize = 128;
+
+/// Device names that can't be used as file names on Windows, with or without an extension.
+const WINDOWS_RESERVED_NAMES: [&str; 22] = [
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Tag(SmolStr);
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct TagStr<'a>(&'a str);
+
+impl<'a> Deref for TagStr<'a> {
+    type Target = str;
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
+
+impl Deref for Tag {
+    type Target = SmolStr;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Tag {
+    pub fn as_tag_str(&self) -> TagStr<'_> {
+        TagStr(self.0.as_str())
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, thiserror::Error)]
+pub enum TagIsNotValid {
+    #[error("tag is empty")]
+    Empty,
+    #[error("tag contains invalid character: {0:?}")]
+    Char(char),
+    #[error("tag starts with `.`, which is reserved for the files of avm")]
+    LeadingDot,
+    #[error("tag ends with {0:?}, which is dropped on Windows")]
+    Trailing(char),
+    #[error("tag is the reserved Windows device name {0:?}")]
+    ReservedName(SmolStr),
+    #[error("tag is longer than {MAX_TAG_LEN} bytes")]
+    TooLong,
+}
+
+impl fmt::Debug for TagIsNotValid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
+
+/// Checks that `value` may be used as a tag. It is not normalized, see [`normalize`].
+impl<'a> TryFrom<&'a str> for TagStr<'a> {
+    type Error = TagIsNotValid;
+
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        if value.is_empty() {
+            return Err(TagIsNotValid::Empty);
+        }
+        if let Some(c) = value.chars().find(|&c| {
+            c == '/'
+                || c == '\\'
+                || c == '\0'
+                || c.is_control()
+                || c == '<'
+                || c == '>'
+                || c == ':'
+                || c == '"'
+                || c == '|'
+                || c == '?'
+                || c == '*'
+        }) {
+            return Err(TagIsNotValid::Char(c));
+        }
+        // Also rejects `.` and `..`.
+        if value.starts_with('.') {
+            return Err(TagIsNotValid::LeadingDot);
+        }
+        if let Some(c) = value.chars().last().filter(|&c| c == '.' || c == ' ') {
+            return Err(TagIsNotValid::Trailing(c));
+        }
+        let stem = value.split('.').next().unwrap_or(value).trim_end();
+        if WINDOWS_RESERVED_NAMES
+            .iter()
+            .any(|name| name.eq_ignore_ascii_case(stem))
+        {
+            return Err(TagIsNotValid::ReservedName(stem.into()));
+        }
+        if value.len() > MAX_TAG_LEN {
+            return Err(TagIsNotValid::TooLong);
+        }
+
+        Ok(TagStr(value))
+    }
+}
+
+/// NFC-normalizes and checks `value`.
+impl TryFrom<SmolStr> for Tag {
+    type Error = TagIsNotValid;
+
+    fn try_from(value: SmolStr) -> Result<Self, Self::Error> {
+        let value = match ComposingNormalizerBorrowed::new_nfc().normalize(&value) {
+            std::borrow::Cow::Borrowed(_) => value,
+            std::borrow::Cow::Owned(normalized) => normalized.into(),
+        };
+        TagStr::try_from(value.as_str())?;
+        Ok(Tag(value))
+    }
+}
+
+/// Returns the NFC-normalized `tag`, failing if it is not a valid tag. Applied to the tags
+/// given to the operations on the tool directory.
+pub fn normalize(tag: &str) -> crate::Result<SmolStr> {
+    if tag.starts_with(TMP_PREFIX) {
+        return Err(AvmError::TagReserved { tag: tag.into() });
+    }
+    match Tag::try_from(SmolStr::new(tag)) {
+        Ok(tag) => Ok(tag.0),
+        Err(reason) => Err(AvmError::TagInvalid {
+            tag: tag.into(),
+            reason,
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_unsafe_tags() {
+        for tag in [
+            "",
+            ".",
+            "..",
+            "../x",
+            "a/b",
+            "a\\b",
+            "a:b",
+            ".aliases.json",
+            "x.",
+            "x ",
+            "CON",
+            "nul",
+            "Com1.tar",
+            "lpt9 .x",
+        ] {
+            assert!(TagStr::try_from(tag).is_err(), "{tag:?} is accepted");
+        }
+        assert!(TagStr::try_from("a".repeat(MAX_TAG_LEN + 1).as_str()).is_err());
+        assert!(matches!(
+            normalize(".tmp.1.0"),
+            Err(AvmError::TagReserved { .. })
+        ));
+
+        for tag in [
+            "1.2.3",
+            "default",
+            "v20.1.0-lts",
+            "CONSOLE",
+            "nul1",
+            "COM10",
+        ] {
+            assert!(TagStr::try_from(tag).is_ok(), "{tag:?} is rejected");
+        }
+    }
+
+    #[test]
+    fn normalizes_to_nfc() {
+        // `e` followed by a combining acute accent.
+        assert_eq!(normalize("caf\u{65}\u{301}").unwrap(), "caf\u{e9}");
+        assert_eq!(normalize("caf\u{e9}").unwrap(), "caf\u{e9}");
+    }
+}
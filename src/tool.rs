@@ -1,5 +1,11 @@
+pub mod end_of_life;
 pub mod general_tool;
-use std::{ffi::OsString, future::Future, path::PathBuf};
+pub(crate) mod github_releases;
+use std::{
+    ffi::OsString,
+    future::Future,
+    path::{Path, PathBuf},
+};
 
 use serde::{Deserialize, Serialize};
 use smol_str::{SmolStr, SmolStrBuilder};
@@ -25,7 +31,7 @@ fn is_false(value: &bool) -> bool {
     !*value
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct VersionPrefix {
     pub major: u32,
     pub minor: Option<u32>,
@@ -79,23 +85,269 @@ impl VersionPrefix {
         }
         true
     }
+
+    /// The first version of the series.
+    fn lowest(&self) -> (u32, u32, u32) {
+        (self.major, self.minor.unwrap_or(0), self.patch.unwrap_or(0))
+    }
+
+    /// The first version after the series.
+    fn next(&self) -> (u32, u32, u32) {
+        match (self.minor, self.patch) {
+            (Some(minor), Some(patch)) => (self.major, minor, patch + 1),
+            (Some(minor), None) => (self.major, minor + 1, 0),
+            _ => (self.major + 1, 0, 0),
+        }
+    }
+}
+
+/// A version range, comparators like `>=1.21 <1.23` that must all hold. Missing parts of a
+/// comparator version cover the whole series, so `<=1.22` allows `1.22.5` and `>1` starts at
+/// `2.0.0`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct VersionRange {
+    comparators: Vec<(Comparison, VersionPrefix)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Comparison {
+    Eq,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl VersionRange {
+    /// Parses comparators separated by spaces or `,`. A bare version is matched as a prefix.
+    pub fn parse(raw: &str) -> anyhow::Result<Self> {
+        let mut comparators = Vec::new();
+        let mut tokens = raw
+            .split(|c: char| c.is_whitespace() || c == ',')
+            .filter(|token| !token.is_empty());
+        while let Some(token) = tokens.next() {
+            let (comparison, version) = [
+                (">=", Comparison::Ge),
+                ("<=", Comparison::Le),
+                (">", Comparison::Gt),
+                ("<", Comparison::Lt),
+                ("=", Comparison::Eq),
+            ]
+            .into_iter()
+            .find_map(|(op, comparison)| token.strip_prefix(op).map(|rest| (comparison, rest)))
+            .unwrap_or((Comparison::Eq, token));
+            // Allows a space between the operator and the version, like `>= 1.21`.
+            let version = match version {
+                "" => tokens
+                    .next()
+                    .ok_or_else(|| anyhow::anyhow!("Missing version after `{token}` in '{raw}'"))?,
+                version => version,
+            };
+            let version = VersionPrefix::parse(strip_wildcard(version).unwrap_or(version))
+                .map_err(|err| err.context(format!("Invalid version range '{raw}'")))?;
+            comparators.push((comparison, version));
+        }
+        if comparators.is_empty() {
+            anyhow::bail!("Version range is empty");
+        }
+        Ok(Self { comparators })
+    }
+
+    pub fn matches(&self, major: u32, minor: u32, patch: u32) -> bool {
+        let version = (major, minor, patch);
+        self.comparators
+            .iter()
+            .all(|(comparison, prefix)| match comparison {
+                Comparison::Eq => prefix.matches(major, minor, patch),
+                Comparison::Ge => version >= prefix.lowest(),
+                Comparison::Lt => version < prefix.lowest(),
+                Comparison::Gt => version >= prefix.next(),
+                Comparison::Le => version < prefix.next(),
+            })
+    }
+}
+
+/// `18` from `18.x` or `18.*`.
+fn strip_wildcard(raw: &str) -> Option<&str> {
+    raw.strip_suffix(".x")
+        .or_else(|| raw.strip_suffix(".X"))
+        .or_else(|| raw.strip_suffix(".*"))
+}
+
+/// The versions asked for by one string, parsed once for every tool.
+#[derive(Debug, Clone, PartialEq)]
+pub enum VersionSpec {
+    /// The latest release, `latest`, `*` or `x`.
+    Latest,
+    /// The latest LTS release, `lts`.
+    Lts,
+    /// A major, minor or patch series, like `18`, `18.x` or `1.21.3`.
+    Prefix(VersionPrefix),
+    /// A range like `>=1.21 <1.23`.
+    Range(VersionRange),
+    /// Any other string is a version as the tool spells it, like `21.0.5+11` or `1.22rc1`.
+    Exact(SmolStr),
+}
+
+impl VersionSpec {
+    pub fn parse(raw: &str) -> anyhow::Result<Self> {
+        let raw = raw.trim();
+        if raw.is_empty() {
+            anyhow::bail!("Version spec is empty");
+        }
+        if ["latest", "*", "x"]
+            .iter()
+            .any(|s| raw.eq_ignore_ascii_case(s))
+        {
+            return Ok(Self::Latest);
+        }
+        if raw.eq_ignore_ascii_case("lts") {
+            return Ok(Self::Lts);
+        }
+        if raw.starts_with(['<', '>', '=']) || raw.contains(|c: char| c.is_whitespace() || c == ',')
+        {
+            return VersionRange::parse(raw).map(Self::Range);
+        }
+        match strip_wildcard(raw) {
+            Some(prefix) => VersionPrefix::parse(prefix)
+                .map(Self::Prefix)
+                .map_err(|err| err.context(format!("Invalid version spec '{raw}'"))),
+            None => Ok(VersionPrefix::parse(raw)
+                .map(Self::Prefix)
+                .unwrap_or_else(|_| Self::Exact(raw.into()))),
+        }
+    }
+
+    pub fn into_filter(self, include_prerelease: bool) -> VersionFilter {
+        let mut filter = VersionFilter {
+            include_prerelease,
+            ..Default::default()
+        };
+        match self {
+            Self::Latest => {}
+            Self::Lts => filter.lts_only = true,
+            Self::Prefix(prefix) => filter.version_prefix = Some(prefix),
+            Self::Range(range) => filter.version_range = Some(range),
+            Self::Exact(version) => filter.exact_version = Some(version),
+        }
+        filter
+    }
 }
 
 /// Version filter for selecting version.
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct VersionFilter {
     pub lts_only: bool,
-    pub allow_prerelease: bool,
+    /// Whether prereleases such as betas and release candidates match. Without it, a
+    /// prerelease only matches its exact version.
+    pub include_prerelease: bool,
     pub version_prefix: Option<VersionPrefix>,
+    pub version_range: Option<VersionRange>,
     pub exact_version: Option<SmolStr>,
 }
 
+impl VersionFilter {
+    /// Whether prereleases may match, because they are included or a version is asked for.
+    pub fn includes_prerelease(&self) -> bool {
+        self.include_prerelease || self.exact_version.is_some()
+    }
+
+    /// Whether a version numbered `major.minor.patch` is within the prefix and the range.
+    pub fn matches_numbers(&self, major: u32, minor: u32, patch: u32) -> bool {
+        self.version_prefix
+            .is_none_or(|p| p.matches(major, minor, patch))
+            && self
+                .version_range
+                .as_ref()
+                .is_none_or(|r| r.matches(major, minor, patch))
+    }
+}
+
 pub struct ToolDownInfo {
     pub version: Version,
     pub url: SmolStr,
     pub hash: crate::FileHash,
 }
 
+/// An archive installed into the tag along with the release, such as a plugin a flavor adds.
+#[derive(Clone)]
+pub struct CompanionArchive {
+    pub url: SmolStr,
+    pub hash: crate::FileHash,
+    /// The executable at the root of the archive, without the `.exe` suffix on Windows. It is
+    /// placed in `bin` of the tag.
+    pub exe: SmolStr,
+}
+
+/// An option of the commands that only some tools support, listed by
+/// [`GeneralTool::options`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolOption {
+    /// Selecting builds with JavaFX, in the flavor.
+    Fx,
+    /// Selecting builds in another archive type, in the flavor.
+    PackageType,
+    /// Selecting the builds of a release channel, in the flavor.
+    Channel,
+    /// Replacing the bundled npm with another version once installed.
+    Npm,
+    /// Running `corepack enable` once installed.
+    Corepack,
+}
+
+impl ToolOption {
+    /// The command-line flag of the option.
+    pub fn flag(self) -> &'static str {
+        match self {
+            ToolOption::Fx => "--fx",
+            ToolOption::PackageType => "--package-type",
+            ToolOption::Channel => "--channel",
+            ToolOption::Npm => "--npm",
+            ToolOption::Corepack => "--with-corepack",
+        }
+    }
+}
+
+/// The values of the [`ToolOption`]s that select the flavor, see
+/// [`GeneralTool::flavor_with_options`].
+#[derive(Debug, Clone, Default)]
+pub struct FlavorOptions {
+    /// For [`ToolOption::Fx`].
+    pub fx: bool,
+    /// For [`ToolOption::PackageType`].
+    pub package_type: Option<SmolStr>,
+    /// For [`ToolOption::Channel`].
+    pub channel: Option<SmolStr>,
+}
+
+/// The values of the [`ToolOption`]s that set up a newly installed tag.
+#[derive(Debug, Clone, Default)]
+pub struct TagOptions {
+    /// The npm version to install, for [`ToolOption::Npm`].
+    pub npm: Option<SmolStr>,
+    /// Whether to enable corepack, for [`ToolOption::Corepack`].
+    pub corepack: bool,
+}
+
+/// What [`GeneralTool::set_up_tag`] is given about a newly installed tag.
+pub struct NewTag<'a> {
+    pub tools_base: &'a Path,
+    pub tag: &'a str,
+    pub tag_dir: &'a Path,
+    pub flavor: Option<&'a str>,
+    pub options: &'a TagOptions,
+}
+
+/// A binary patch from the archive of one release to the archive of another, made with
+/// `zstd --patch-from`.
+#[derive(Clone)]
+pub struct ArchiveDelta {
+    pub url: SmolStr,
+    /// The hash of the patch itself, empty if unknown. The restored archive is checked against
+    /// the hash of the release either way.
+    pub hash: crate::FileHash,
+}
+
 #[derive(Serialize)]
 pub struct DownInfo {
     pub tag: SmolStr,
@@ -139,18 +391,42 @@ pub trait GeneralTool: Send + Sync {
     fn describe_flavor(&self, _flavor: &str) -> &'static str {
         "Tool-specific build flavor."
     }
+    /// The flavor listed in `ToolInfo` that `flavor` selects builds of, for tools whose
+    /// flavors take options.
+    fn base_flavor<'a>(&self, flavor: &'a str) -> &'a str {
+        flavor
+    }
+    /// Whether the upstream publishes builds for the platform/flavor combination.
+    /// Both values are already known to be listed in `ToolInfo`.
+    fn supports_combination(&self, _platform: &str, _flavor: &str) -> bool {
+        true
+    }
+    /// Upstream metadata URLs the tool queries, used by `avm ping` to check reachability.
+    fn endpoints(&self) -> Vec<SmolStr>;
+    /// The page of the upstream release notes of `version`, for `avm changelog`.
+    fn release_notes_url(&self, _version: &str) -> Option<SmolStr> {
+        None
+    }
+    /// Whether the release cycle of `version` is still supported, `None` for tools without
+    /// published support windows.
+    fn support_status(
+        &self,
+        _version: &str,
+    ) -> impl Future<Output = crate::Result<Option<end_of_life::SupportStatus>>> + Send {
+        async { Ok(None) }
+    }
     fn fetch_versions(
         &self,
         platform: Option<SmolStr>,
         flavor: Option<SmolStr>,
         version_filter: VersionFilter,
-    ) -> impl Future<Output = anyhow::Result<Vec<Version>>> + Send;
+    ) -> impl Future<Output = crate::Result<Vec<Version>>> + Send;
     fn get_down_info(
         &self,
         platform: Option<SmolStr>,
         flavor: Option<SmolStr>,
         version_filter: VersionFilter,
-    ) -> impl Future<Output = anyhow::Result<ToolDownInfo>> + Send;
+    ) -> impl Future<Output = crate::Result<ToolDownInfo>> + Send;
     fn find_best_matching_local_tag<'a, I>(
         &self,
         tags_and_versions: I,
@@ -158,20 +434,174 @@ pub trait GeneralTool: Send + Sync {
     ) -> Option<SmolStr>
     where
         I: Iterator<Item = (&'a str, &'a Version)>;
-    fn entry_path(&self, tag_dir: PathBuf) -> anyhow::Result<PathBuf>;
-    fn run(
+    /// The detached signature the archive at `url` must match besides its hash, for tools whose
+    /// vendor signs the archives instead of publishing their checksums.
+    fn archive_signature(
+        &self,
+        _url: &str,
+    ) -> impl Future<Output = crate::Result<Option<crate::signature::DetachedSignature>>> + Send
+    {
+        async { Ok(None) }
+    }
+    /// The fingerprints of the keys that may make [`GeneralTool::archive_signature`], which
+    /// installs from a bundle check its stored signature against. Empty if the archives are not
+    /// signed.
+    fn signing_keys(&self) -> &'static [&'static str] {
+        &[]
+    }
+    /// Archives installed into the tag with the release of `platform` and `flavor`. Bundles
+    /// hold them along with the release.
+    fn companion_archives(
+        &self,
+        _platform: Option<SmolStr>,
+        _flavor: Option<SmolStr>,
+    ) -> impl Future<Output = crate::Result<Vec<CompanionArchive>>> + Send {
+        async { Ok(Vec::new()) }
+    }
+    /// The patch from the archive at `from_url`, of an installed tag, to the archive at
+    /// `to_url`. Installs download it instead of the whole archive when the archive at
+    /// `from_url` is in the download cache, and fall back to the whole archive if the patch is
+    /// missing or fails to apply. `None` if the vendor publishes no patches.
+    fn archive_delta(
+        &self,
+        _from_url: &str,
+        _to_url: &str,
+    ) -> impl Future<Output = crate::Result<Option<ArchiveDelta>>> + Send {
+        async { Ok(None) }
+    }
+    /// Whether the archives are large enough to extract while downloading even without
+    /// `--stream`, sparing the disk a copy of the whole archive.
+    fn prefers_stream_extract(&self) -> bool {
+        false
+    }
+    fn entry_path(&self, tag_dir: PathBuf) -> crate::Result<PathBuf>;
+    /// Executables the tool ships, by name. The first one is the entry.
+    fn exe_paths(&self, tag_dir: PathBuf) -> crate::Result<Vec<(SmolStr, PathBuf)>> {
+        let entry_path = self.entry_path(tag_dir)?;
+        let name = entry_path
+            .file_stem()
+            .map(|stem| SmolStr::new(stem.to_string_lossy()))
+            .unwrap_or_default();
+        Ok(vec![(name, entry_path)])
+    }
+    /// Environment variables for processes started by `run`, such as `JAVA_HOME`.
+    fn env_vars(&self, _tag_dir: &Path) -> Vec<(OsString, OsString)> {
+        Vec::new()
+    }
+    /// A step run on a blocking thread once the tag directory is in place, for archives that
+    /// must be told where they are installed. The install is rolled back if it fails.
+    fn post_install(&self) -> Option<fn(&Path) -> crate::Result<()>> {
+        None
+    }
+    /// The options only some tools support that this one does.
+    fn options(&self) -> &'static [ToolOption] {
+        &[]
+    }
+    /// The flavor selecting the builds of `flavor` with the `options`, for tools whose
+    /// [`GeneralTool::options`] select the flavor. Only called if one of them is given.
+    fn flavor_with_options(
+        &self,
+        flavor: Option<&str>,
+        _options: &FlavorOptions,
+    ) -> crate::Result<Option<SmolStr>> {
+        Ok(flavor.map(SmolStr::from))
+    }
+    /// Whether all the builds of `flavor` are prereleases, such as those of a release channel,
+    /// so that they match without `--pre`.
+    fn is_prerelease_flavor(&self, _flavor: &str) -> bool {
+        false
+    }
+    /// Sets up a tag once it is installed, however it was, for example with the options of
+    /// [`GeneralTool::options`]. Unlike [`GeneralTool::post_install`], the tag stays installed
+    /// if it fails.
+    fn set_up_tag(&self, _tag: &NewTag<'_>) -> impl Future<Output = crate::Result<()>> + Send {
+        async { Ok(()) }
+    }
+    /// The executable name, without the `.exe` suffix on Windows, of a tool whose download is
+    /// the bare executable rather than an archive. It is installed as `bin/<name>`.
+    fn single_binary(&self) -> Option<&'static str> {
+        None
+    }
+    /// The command `run` starts for an executable of the tool. It inherits stdin, stdout and
+    /// stderr.
+    fn command(
         &self,
         entry_path: PathBuf,
         args: Vec<OsString>,
-    ) -> impl Future<Output = anyhow::Result<()>> + Send {
-        async move {
-            crate::spawn_blocking(move || {
-                let mut command = std::process::Command::new(entry_path);
-                command.args(args);
-                command.spawn()?.wait()?;
-                Ok(())
-            })
-            .await
-        }
+        env_vars: Vec<(OsString, OsString)>,
+    ) -> crate::Result<std::process::Command> {
+        let mut command = std::process::Command::new(entry_path);
+        command.args(args).envs(env_vars);
+        Ok(command)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(raw: &str) -> VersionRange {
+        VersionRange::parse(raw).unwrap()
+    }
+
+    #[test]
+    fn parses_version_specs() {
+        assert_eq!(VersionSpec::parse("latest").unwrap(), VersionSpec::Latest);
+        assert_eq!(VersionSpec::parse("*").unwrap(), VersionSpec::Latest);
+        assert_eq!(VersionSpec::parse("LTS").unwrap(), VersionSpec::Lts);
+        let major = VersionSpec::Prefix(VersionPrefix::parse("18").unwrap());
+        assert_eq!(VersionSpec::parse("18").unwrap(), major);
+        assert_eq!(VersionSpec::parse("18.x").unwrap(), major);
+        assert_eq!(
+            VersionSpec::parse("1.21.3").unwrap(),
+            VersionSpec::Prefix(VersionPrefix::parse("1.21.3").unwrap())
+        );
+        assert_eq!(
+            VersionSpec::parse(">=1.21 <1.23").unwrap(),
+            VersionSpec::Range(range(">=1.21, <1.23"))
+        );
+        assert_eq!(
+            VersionSpec::parse("21.0.5+11").unwrap(),
+            VersionSpec::Exact("21.0.5+11".into())
+        );
+        assert_eq!(
+            VersionSpec::parse("1.22rc1").unwrap(),
+            VersionSpec::Exact("1.22rc1".into())
+        );
+        assert!(VersionSpec::parse("").is_err());
+        assert!(VersionSpec::parse("a.x").is_err());
+        assert!(VersionSpec::parse(">=").is_err());
+        assert!(VersionSpec::parse(">=1.21 <abc").is_err());
+    }
+
+    #[test]
+    fn matches_version_ranges() {
+        let between = range(">=1.21 <1.23");
+        assert!(!between.matches(1, 20, 14));
+        assert!(between.matches(1, 21, 0));
+        assert!(between.matches(1, 22, 9));
+        assert!(!between.matches(1, 23, 0));
+
+        // A partial version covers its whole series.
+        assert!(range("<=1.22").matches(1, 22, 5));
+        assert!(!range("<=1.22").matches(1, 23, 0));
+        assert!(!range(">1").matches(1, 99, 0));
+        assert!(range(">1").matches(2, 0, 0));
+        assert!(!range(">1.21.3").matches(1, 21, 3));
+        assert!(range(">1.21.3").matches(1, 21, 4));
+        assert!(range("= 18").matches(18, 3, 0));
+        assert!(range("1.21.x").matches(1, 21, 7));
+        assert!(!range("1.21.x").matches(1, 22, 0));
+    }
+
+    #[test]
+    fn filters_by_prefix_and_range() {
+        let filter = VersionSpec::parse(">=20 <23").unwrap().into_filter(false);
+        assert!(!filter.lts_only);
+        assert!(filter.matches_numbers(22, 1, 0));
+        assert!(!filter.matches_numbers(23, 0, 0));
+        let filter = VersionSpec::parse("lts").unwrap().into_filter(false);
+        assert!(filter.lts_only);
+        assert!(filter.matches_numbers(23, 0, 0));
     }
 }
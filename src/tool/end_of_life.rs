@@ -0,0 +1,147 @@
+//! Support windows of release cycles from [endoflife.date](https://endoflife.date), for the
+//! tools it covers.
+
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use serde::Deserialize;
+use smol_str::SmolStr;
+
+use crate::HttpClient;
+
+const API_URL: &str = "https://endoflife.date/api/";
+
+/// Whether the release cycle of a version is still supported.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SupportStatus {
+    /// The release cycle of the version, like `22` for Node.js or `1.22` for Go.
+    pub cycle: SmolStr,
+    /// The day its support ends or ended, like `2027-04-30`. Some cycles are marked as past
+    /// their end of life without a date.
+    pub eol_date: Option<SmolStr>,
+    pub is_eol: bool,
+}
+
+/// The release cycles of a product, fetched once.
+pub(crate) struct EndOfLife {
+    client: Arc<HttpClient>,
+    product: &'static str,
+    cycles: tokio::sync::OnceCell<Vec<CycleDto>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CycleDto {
+    cycle: SmolStr,
+    #[serde(default)]
+    eol: EolDto,
+}
+
+/// `false` while the end of life is not planned, `true` for a past one without a date.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum EolDto {
+    Flag(bool),
+    Date(SmolStr),
+}
+
+impl Default for EolDto {
+    fn default() -> Self {
+        EolDto::Flag(false)
+    }
+}
+
+impl EndOfLife {
+    /// `product` is the name of the product on endoflife.date, such as `nodejs`.
+    pub fn new(client: Arc<HttpClient>, product: &'static str) -> Self {
+        Self {
+            client,
+            product,
+            cycles: tokio::sync::OnceCell::new(),
+        }
+    }
+
+    pub fn url(&self) -> SmolStr {
+        smol_str::format_smolstr!("{API_URL}{}.json", self.product)
+    }
+
+    /// The status of the cycle of `version`, `None` if endoflife.date has no such cycle.
+    pub async fn support_status(&self, version: &str) -> crate::Result<Option<SupportStatus>> {
+        let cycles = self
+            .cycles
+            .get_or_try_init(|| async {
+                self.client
+                    .get(&self.url())
+                    .send()
+                    .await?
+                    .error_for_status()?
+                    .json::<Vec<CycleDto>>()
+                    .await
+                    .map_err(anyhow::Error::from)
+            })
+            .await?;
+        let today = crate::format_utc(SystemTime::now());
+        Ok(support_status(cycles, version, &today[..10]))
+    }
+}
+
+/// The status of the longest cycle that `version` is in, on `today` like `2025-01-31`.
+fn support_status(cycles: &[CycleDto], version: &str, today: &str) -> Option<SupportStatus> {
+    let cycle = cycles
+        .iter()
+        .filter(|cycle| {
+            version
+                .strip_prefix(cycle.cycle.as_str())
+                .is_some_and(|rest| !rest.starts_with(|c: char| c.is_ascii_digit()))
+        })
+        .max_by_key(|cycle| cycle.cycle.len())?;
+    let (eol_date, is_eol) = match &cycle.eol {
+        EolDto::Flag(is_eol) => (None, *is_eol),
+        // The dates are ISO 8601, which compare like strings.
+        EolDto::Date(date) => (Some(date.clone()), date.as_str() <= today),
+    };
+    Some(SupportStatus {
+        cycle: cycle.cycle.clone(),
+        eol_date,
+        is_eol,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_cycle_of_a_version() {
+        let cycles: Vec<CycleDto> = serde_json::from_str(
+            r#"[
+                {"cycle": "1.22", "eol": false, "latest": "1.22.3"},
+                {"cycle": "1.2", "eol": true},
+                {"cycle": "22", "eol": "2027-04-30", "lts": "2024-10-29"},
+                {"cycle": "20", "eol": "2026-04-30"}
+            ]"#,
+        )
+        .unwrap();
+        let status = |version| support_status(&cycles, version, "2026-10-16");
+        assert_eq!(
+            status("1.22.3"),
+            Some(SupportStatus {
+                cycle: "1.22".into(),
+                eol_date: None,
+                is_eol: false,
+            })
+        );
+        assert_eq!(status("1.22rc1").unwrap().cycle, "1.22");
+        assert!(status("1.2.2").unwrap().is_eol);
+        assert_eq!(
+            status("20.18.1"),
+            Some(SupportStatus {
+                cycle: "20".into(),
+                eol_date: Some("2026-04-30".into()),
+                is_eol: true,
+            })
+        );
+        assert!(!status("22.13.1").unwrap().is_eol);
+        assert_eq!(status("21.0.5+11"), None);
+        assert_eq!(status("2.0.0"), None);
+    }
+}
@@ -1,107 +1,383 @@
+pub mod cmake;
 pub mod dotnet;
+pub mod elixir;
+pub mod erlang;
+pub mod flutter;
 pub mod go;
+pub mod graalvm;
+pub mod gradle;
+pub mod julia;
+pub mod kotlin;
 pub mod liberica;
+pub mod llvm;
+pub mod manifest;
+pub mod maven;
+pub mod ninja;
 pub mod node;
+pub mod opentofu;
+pub mod php;
+pub mod plugin;
 pub mod pnpm;
+pub mod protoc;
+pub mod ruby;
+pub mod swift;
+pub mod temurin;
+pub mod terraform;
 
-use crate::io::{
-    blocking, ArchiveExtractInfo, ArchiveType, DownloadExtractCallback, DownloadExtractState,
-};
-use crate::tool::{GeneralTool, ToolInfo, Version, VersionFilter};
-use crate::{HttpClient, Tag};
+use crate::bundle::{BundledCompanion, BundledRelease, BundledSignature};
+use crate::io::blocking::{self, TMP_PREFIX};
+use crate::io::{ArchiveExtractInfo, ArchiveType, DownloadExtractCallback, DownloadExtractState};
+use crate::journal;
+use crate::pinned_hashes::PinnedHashes;
+use crate::signature::DetachedSignature;
+use crate::tool::{CompanionArchive, GeneralTool, ToolInfo, Version, VersionFilter, VersionPrefix};
+use crate::{AvmError, CancellationToken, HttpClient, Tag};
+use anyhow::Context;
 use async_trait::async_trait;
 use rustc_hash::FxHashSet;
+use serde::{Deserialize, Serialize};
 use smol_str::SmolStr;
 use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-const TMP_PREFIX: &str = ".tmp.";
 const DEFAULT_TAG: &str = "default";
 const VERSION_INFO_FILE: &str = ".avm.version-info.toml";
+/// Suffix of the temporary directory a tag is moved to while it is removed.
+const REMOVING_SUFFIX: &str = ".removing";
+const PING_TIMEOUT: Duration = Duration::from_secs(10);
 
 pub fn default_tag() -> Tag {
     Tag::try_from(SmolStr::new(DEFAULT_TAG)).expect("Default tag is invalid") // DEFAULT_TAG is a constant that should be defined as a valid tag.
 }
 
+/// What a tag was installed from, kept in the tag directory.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct InstallInfo {
+    pub version: SmolStr,
+    #[serde(rename = "lts", default, skip_serializing_if = "std::ops::Not::not")]
+    pub is_lts: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub platform: Option<SmolStr>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flavor: Option<SmolStr>,
+    /// `None` for tags installed from local archives.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<SmolStr>,
+    #[serde(default, skip_serializing_if = "crate::FileHash::is_empty")]
+    pub hash: crate::FileHash,
+    /// Seconds since the Unix epoch, `None` for tags installed by older versions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub installed_at: Option<u64>,
+}
+
+impl InstallInfo {
+    fn new(version: Version) -> Self {
+        Self {
+            version: version.version,
+            is_lts: version.is_lts,
+            platform: None,
+            flavor: None,
+            url: None,
+            hash: crate::FileHash::default(),
+            installed_at: None,
+        }
+    }
+
+    pub fn version(&self) -> Version {
+        Version {
+            version: self.version.clone(),
+            is_lts: self.is_lts,
+        }
+    }
+
+    /// Time since the tag was installed, `None` if unknown.
+    pub fn age(&self) -> Option<Duration> {
+        let installed_at = UNIX_EPOCH + Duration::from_secs(self.installed_at?);
+        SystemTime::now().duration_since(installed_at).ok()
+    }
+}
+
 struct InstallCustomAction {
-    hash: crate::FileHash,
-    version: Version,
+    info: InstallInfo,
     tool_dir: PathBuf,
     target_tag: SmolStr,
     target_dir: PathBuf,
+    /// Where an existing tag is moved while it is being replaced, inside the temporary directory.
+    backup_dir: PathBuf,
     default: bool,
+    post_install: Option<fn(&Path) -> crate::Result<()>>,
+    /// The executable name if the download is a bare executable.
+    single_binary: Option<&'static str>,
+    /// Checked once the archive matches its hash.
+    signature: Option<DetachedSignature>,
+    /// Downloaded once the archive is extracted.
+    companions: Option<Companions>,
+}
+
+/// The companion archives of an install, with where to get them and the cancellation.
+struct Companions {
+    archives: Vec<CompanionArchive>,
+    source: CompanionSource,
+    cancel: CancellationToken,
 }
 
-async fn create_operating(tmp_dir: PathBuf, tag: String) -> anyhow::Result<blocking::Operating> {
-    crate::spawn_blocking(
-        move || match blocking::Operating::create_in_tmp_dir(tmp_dir.clone()) {
+enum CompanionSource {
+    /// Downloaded with the client.
+    Download(HttpClient),
+    /// Copied from the bundle at the path, from the files of the archives by their index.
+    Bundle(PathBuf, Vec<String>),
+}
+
+async fn create_operating(
+    tmp_dir: PathBuf,
+    tag: String,
+    break_stale_lock: bool,
+) -> anyhow::Result<blocking::Operating> {
+    crate::spawn_blocking(move || {
+        match blocking::Operating::create_in_tmp_dir(tmp_dir.clone(), break_stale_lock) {
             Ok(operating) => Ok(operating),
-            Err(blocking::CreateOperatingError::AlreadyOperating) => {
-                anyhow::bail!("\"{}\" is being operated", tag)
+            Err(blocking::CreateOperatingError::AlreadyOperating) => Err(AvmError::TagBusy {
+                tag: tag.into(),
+                tmp_dir,
             }
+            .into()),
+            Err(blocking::CreateOperatingError::ToolDirBusy) => Err(AvmError::ToolBusy {
+                tool_dir: tmp_dir.parent().unwrap_or(&tmp_dir).to_path_buf(),
+            }
+            .into()),
             Err(blocking::CreateOperatingError::Io(err)) => {
                 Err(anyhow::Error::from(err).context(format!(
                     "Failed to create operation lock under temporary directory '{}'",
                     tmp_dir.display()
                 )))
             }
-        },
-    )
+        }
+    })
     .await
 }
 
+/// Takes the lock of the tool directory for an operation without a temporary directory.
+fn lock_tool_dir(tool_dir: &Path) -> anyhow::Result<blocking::ToolDirLock> {
+    blocking::ToolDirLock::acquire(tool_dir)
+        .with_context(|| format!("Failed to lock '{}'", tool_dir.display()))?
+        .ok_or_else(|| {
+            AvmError::ToolBusy {
+                tool_dir: tool_dir.to_path_buf(),
+            }
+            .into()
+        })
+}
+
+/// Takes the operation lock of the tag to install and checks that it may be installed. The
+/// tag must have been normalized with [`crate::tag::normalize`]. With `force`, an existing tag
+/// that is not an alias is replaced like with `update`, so it is kept if the install fails,
+/// and a stale operation lock is removed.
+/// Returns the lock, the tag directory and where an existing tag is moved while replacing it.
+async fn prepare_install(
+    tool_dir: &Path,
+    tag: &str,
+    update: bool,
+    force: bool,
+    keep_partial: bool,
+) -> crate::Result<(blocking::Operating, PathBuf, PathBuf)> {
+    log::debug!("Tool dir: {}", tool_dir.display());
+    let tag_dir = tool_dir.join(tag);
+    log::debug!("Tag dir: {}", tag_dir.display());
+    let tmp_dir = tool_dir.join(format!("{}{}", TMP_PREFIX, tag));
+    log::debug!("Tmp dir: {}", tmp_dir.display());
+    let backup_dir = tmp_dir.join("previous");
+    let mut operating = create_operating(tmp_dir, tag.to_owned(), force).await?;
+    operating.keep_partial = keep_partial;
+
+    let tag_dir = if update && !force {
+        tag_dir
+    } else {
+        let owned_tool_dir = tool_dir.to_path_buf();
+        let owned_tag = SmolStr::from(tag);
+        let (tag_dir, exists) = crate::spawn_blocking(move || {
+            let exists = tag_dir.exists();
+            if !exists || !force {
+                return Ok((tag_dir, exists));
+            }
+            if blocking::AliasStore::load(&owned_tool_dir)?
+                .aliases
+                .contains_key(&owned_tag)
+            {
+                anyhow::bail!(
+                    "Tag \"{owned_tag}\" is an alias, delete it with `avm alias delete` instead"
+                );
+            }
+            log::warn!("Replacing the existing tag \"{owned_tag}\"");
+            Ok((tag_dir, false))
+        })
+        .await?;
+
+        if exists {
+            return Err(AvmError::TagExists { tag: tag.into() });
+        }
+
+        tag_dir
+    };
+    Ok((operating, tag_dir, backup_dir))
+}
+
+/// Extracts a downloaded companion archive next to it and moves its executable to `bin` of
+/// `dir`.
+fn place_companion(
+    companion: &CompanionArchive,
+    download: &Path,
+    dir: &Path,
+    cancel: &CancellationToken,
+) -> anyhow::Result<()> {
+    let archive_type = ArchiveType::from_file_name(companion.url.as_bytes())
+        .map_or_else(|| blocking::detect_archive_type(download), Ok)?;
+    let extracted_dir = download.with_extension("extracted");
+    blocking::extract_archive(archive_type, download, &extracted_dir, cancel)?;
+    let file_name = format!("{}{}", companion.exe, std::env::consts::EXE_SUFFIX);
+    let exe = extracted_dir.join(&file_name);
+    if !exe.is_file() {
+        anyhow::bail!("'{}' has no `{file_name}`", companion.url);
+    }
+    blocking::place_single_binary(&exe, dir, &file_name)
+}
+
+/// Removes a tag directory, moving it out of the tags first so that an interrupted removal
+/// doesn't leave a partial tag behind. The tool directory must be locked.
+fn remove_tag_dir(tool_dir: &Path, tag: &str) -> std::io::Result<()> {
+    let tag_dir = tool_dir.join(tag);
+    let trash_dir = tool_dir.join(format!("{TMP_PREFIX}{tag}{REMOVING_SUFFIX}"));
+    if trash_dir.symlink_metadata().is_ok() {
+        std::fs::remove_dir_all(&trash_dir)?;
+    }
+    let _journal = journal::begin(&journal::Operation::Remove {
+        tag_dir: tag_dir.clone(),
+        trash_dir: trash_dir.clone(),
+    })
+    .map_err(std::io::Error::other)?;
+    std::fs::rename(&tag_dir, &trash_dir)?;
+    std::fs::remove_dir_all(&trash_dir)
+}
+
 #[async_trait]
 impl DownloadExtractCallback for InstallCustomAction {
+    fn archive_type(&self) -> Option<ArchiveType> {
+        self.single_binary.map(|_| ArchiveType::SingleBinary)
+    }
+
     async fn on_downloaded(&mut self, info: &ArchiveExtractInfo) -> anyhow::Result<()> {
-        crate::spawn_blocking({
-            let hash = self.hash.clone();
+        crate::io::verify_digest(&self.info.hash, &info.digest)?;
+        if let Some(signature) = self.signature.clone() {
             let archive_path = info.archive_path.clone();
-            move || blocking::verify_hash(&hash, &archive_path)
-        })
-        .await?;
+            crate::spawn_blocking(move || {
+                let work_dir = archive_path.with_file_name("signature");
+                signature.verify_blocking(&archive_path, &work_dir)
+            })
+            .await?;
+        }
         Ok(())
     }
 
     async fn on_extracted(&mut self, info: &ArchiveExtractInfo) -> anyhow::Result<()> {
+        let mut companion_downloads = Vec::new();
+        if let Some(companions) = &self.companions {
+            for (i, companion) in companions.archives.iter().enumerate() {
+                let download = info.archive_path.with_file_name(format!("companion-{i}"));
+                match &companions.source {
+                    CompanionSource::Download(client) => {
+                        log::info!("Downloading {}", companion.url);
+                        crate::io::download_verified(
+                            client,
+                            &companion.url,
+                            &companion.hash,
+                            &download,
+                            &companions.cancel,
+                        )
+                        .await?;
+                    }
+                    CompanionSource::Bundle(bundle, files) => {
+                        let (bundle, file, hash, download, cancel) = (
+                            bundle.clone(),
+                            files[i].clone(),
+                            companion.hash.clone(),
+                            download.clone(),
+                            companions.cancel.clone(),
+                        );
+                        crate::spawn_blocking(move || {
+                            crate::bundle::extract_archive_blocking(&bundle, &file, &download)?;
+                            blocking::verify_hash(&hash, &download, &cancel)
+                        })
+                        .await?;
+                    }
+                }
+                companion_downloads.push((companion.clone(), download));
+            }
+        }
+        let cancel = self
+            .companions
+            .as_ref()
+            .map_or_else(CancellationToken::new, |companions| {
+                companions.cancel.clone()
+            });
         let extracted_dir = info.extracted_dir.clone();
+        let archive_path = info.archive_path.clone();
+        let single_binary = self.single_binary;
         let target_dir = self.target_dir.clone();
-        let version = self.version.clone();
-        let target_dir = crate::spawn_blocking(move || {
-            let entries = std::fs::read_dir(&extracted_dir)?
-                .take(2)
-                .collect::<Result<Vec<_>, _>>()?;
-
-            let move_source = if entries.len() == 1 {
-                let entry = &entries[0];
-                let path = entry.path();
-                if path.is_dir() {
-                    path
+        let backup_dir = self.backup_dir.clone();
+        let info = self.info.clone();
+        let post_install = self.post_install;
+        crate::spawn_blocking(move || {
+            let move_source = if let Some(name) = single_binary {
+                let file_name = format!("{name}{}", std::env::consts::EXE_SUFFIX);
+                blocking::place_single_binary(&archive_path, &extracted_dir, &file_name)?;
+                extracted_dir
+            } else {
+                let entries = std::fs::read_dir(&extracted_dir)?
+                    .take(2)
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                if entries.len() == 1 {
+                    let entry = &entries[0];
+                    let path = entry.path();
+                    if path.is_dir() {
+                        path
+                    } else {
+                        extracted_dir
+                    }
                 } else {
                     extracted_dir
                 }
-            } else {
-                extracted_dir
             };
 
-            if target_dir.exists() {
-                std::fs::remove_dir_all(&target_dir)?;
-            }
-            if let Some(parent) = target_dir.parent() {
-                std::fs::create_dir_all(parent)?;
+            for (companion, download) in &companion_downloads {
+                place_companion(companion, download, &move_source, &cancel)?;
             }
 
-            std::fs::rename(move_source, &target_dir)?;
-            write_version_info_file(&target_dir, &version)?;
-            Ok(target_dir)
+            // Complete the directory before it becomes visible under the tag.
+            write_install_info(&move_source, info)?;
+            let _journal = journal::begin(&journal::Operation::Install {
+                tag_dir: target_dir.clone(),
+                source_dir: move_source.clone(),
+                backup_dir: backup_dir.clone(),
+                tmp_dir: backup_dir.parent().unwrap_or(&backup_dir).to_path_buf(),
+            })?;
+            blocking::install_dir(&move_source, &target_dir, &backup_dir)?;
+            if let Some(post_install) = post_install {
+                if let Err(err) = post_install(&target_dir) {
+                    if let Err(revert_err) =
+                        blocking::uninstall_dir(&move_source, &target_dir, &backup_dir)
+                    {
+                        log::error!("Failed to roll back the install: {revert_err:#}");
+                    }
+                    return Err(anyhow::Error::from(err)
+                        .context(format!("Failed to set up '{}'", target_dir.display())));
+                }
+            }
+            Ok(())
         })
         .await?;
 
         if self.default {
-            let default_path = self.tool_dir.join(DEFAULT_TAG);
-            let target_tag = self.target_tag.clone();
-            crate::spawn_blocking(move || {
-                blocking::set_alias_tag(&target_tag, &target_dir, DEFAULT_TAG, &default_path)
-            })
-            .await?;
+            set_alias(&self.tool_dir, self.target_tag.clone(), DEFAULT_TAG.into()).await?;
         }
 
         Ok(())
@@ -117,70 +393,554 @@ pub struct InstallArgs<'a, T: GeneralTool> {
     pub flavor: Option<SmolStr>,
     pub install_version: VersionFilter,
     pub update: bool,
+    /// Replace an existing tag that is not an alias, even without `update`, and remove a stale
+    /// operation lock first.
+    pub force: bool,
     pub default: bool,
+    /// Keep the temporary directory of a failed install for debugging.
+    pub keep_partial: bool,
+    /// Extract tar archives while downloading instead of saving them first.
+    pub stream_extract: bool,
+    /// If the tool has no build of the version for the platform, or for the current platform
+    /// if none is given, install the build of the platform it emulates, such as `x64-mac` on
+    /// Apple Silicon. Meant for platforms that were not chosen by the user.
+    pub platform_fallback: bool,
+    /// Cross-check the vendor hash of the archive with these if set.
+    pub pinned_hashes: Option<&'a PinnedHashes>,
+    pub cancel: &'a CancellationToken,
+}
+
+/// What [`InstallArgs::install`] would download and where it would install it.
+pub struct InstallPlan {
+    pub tag: SmolStr,
+    pub version: Version,
+    pub platform: Option<SmolStr>,
+    pub flavor: Option<SmolStr>,
+    /// The URL of the archive, once the mirrors are applied.
+    pub url: SmolStr,
+    pub hash: crate::FileHash,
+    pub tag_dir: PathBuf,
+    /// Whether the tag is installed already, which fails the install unless it updates.
+    pub exists: bool,
 }
 
 impl<T: GeneralTool> InstallArgs<'_, T> {
-    pub async fn install(self) -> anyhow::Result<(SmolStr, SmolStr, DownloadExtractState)> {
-        let down_info = self
+    pub async fn install(self) -> crate::Result<(SmolStr, SmolStr, DownloadExtractState)> {
+        let (down_info, platform) = self.resolve().await?;
+        log::info!(
+            "Selected {} {}{}",
+            self.tool_name,
+            down_info.version,
+            if down_info.is_lts { " (LTS)" } else { "" }
+        );
+        let signature = self.tool.archive_signature(&down_info.url).await?;
+        let companions = self
             .tool
-            .get_down_info(
-                self.platform.clone(),
-                self.flavor.clone(),
-                self.install_version,
+            .companion_archives(platform.clone(), self.flavor.clone())
+            .await?;
+        let stream_extract = self.stream_extract || self.tool.prefers_stream_extract();
+        let stream_extract = if signature.is_some() && stream_extract {
+            log::info!(
+                "The archive is signed, saving it to verify the signature before extracting it"
+            );
+            false
+        } else {
+            stream_extract
+        };
+        let tool_dir = self.tools_base.join(self.tool_name);
+        let (operating, tag_dir, backup_dir) = prepare_install(
+            &tool_dir,
+            &down_info.tag,
+            self.update,
+            self.force,
+            self.keep_partial,
+        )
+        .await?;
+
+        let client = self.client.for_tool(self.tool_name);
+        let patched = self
+            .patch_archive(&client, &down_info, &platform, &operating)
+            .await;
+        let custom_action = Box::new(InstallCustomAction {
+            info: InstallInfo {
+                platform,
+                flavor: self.flavor,
+                url: Some(down_info.url.clone()),
+                hash: down_info.hash.clone(),
+                ..InstallInfo::new(Version {
+                    version: down_info.version.clone(),
+                    is_lts: down_info.is_lts,
+                })
+            },
+            tool_dir,
+            target_tag: down_info.tag.clone(),
+            target_dir: tag_dir,
+            backup_dir,
+            default: self.default,
+            post_install: self.tool.post_install(),
+            single_binary: self.tool.single_binary(),
+            signature,
+            companions: (!companions.is_empty()).then(|| Companions {
+                archives: companions,
+                source: CompanionSource::Download(client.clone()),
+                cancel: self.cancel.clone(),
+            }),
+        });
+        let state = if patched {
+            let file_name = down_info.url.rsplit('/').next().unwrap_or_default();
+            DownloadExtractState::from_archive(
+                operating,
+                file_name,
+                &down_info.hash,
+                custom_action,
+                self.cancel.clone(),
+            )
+            .await?
+        } else {
+            DownloadExtractState::start(
+                &client,
+                &down_info.url,
+                operating,
+                &down_info.hash,
+                stream_extract,
+                custom_action,
+                self.cancel.clone(),
+            )
+            .await?
+        };
+
+        Ok((down_info.tag, down_info.url, state))
+    }
+
+    /// Restores the archive of `down_info` as `download` in the temporary directory of
+    /// `operating` from a patch the tool publishes against the cached archive of an installed
+    /// tag of the same platform and flavor. The tag being replaced is preferred, then the most
+    /// recently installed. `false` if there is no such patch or it fails, so that the whole
+    /// archive is downloaded instead.
+    async fn patch_archive(
+        &self,
+        client: &HttpClient,
+        down_info: &super::DownInfo,
+        platform: &Option<SmolStr>,
+        operating: &blocking::Operating,
+    ) -> bool {
+        let Some(cache) = client.download_cache() else {
+            return false;
+        };
+        // Without a SHA-256 digest, the restored archive could be neither verified nor cached.
+        if down_info.hash.sha256.is_none() {
+            return false;
+        }
+        let mut bases = match installed_tags(self.tool_name, self.tool, self.tools_base).await {
+            Ok(installed) => installed,
+            Err(err) => {
+                log::debug!("Failed to list the installed tags to patch: {err:#}");
+                return false;
+            }
+        };
+        bases.retain(|installed| {
+            installed.info.platform == *platform
+                && installed.info.flavor == self.flavor
+                && installed.info.hash.sha256.is_some()
+                && installed
+                    .info
+                    .url
+                    .as_ref()
+                    .is_some_and(|url| *url != down_info.url)
+        });
+        bases.sort_by_key(|installed| {
+            (
+                installed.tag != down_info.tag,
+                std::cmp::Reverse(installed.info.installed_at),
             )
+        });
+        for base in bases {
+            let from_url = base.info.url.as_deref().unwrap_or_default();
+            let delta = match self.tool.archive_delta(from_url, &down_info.url).await {
+                Ok(Some(delta)) => delta,
+                Ok(None) => continue,
+                Err(err) => {
+                    log::warn!("Failed to look up a patch from {from_url}: {err:#}");
+                    return false;
+                }
+            };
+            log::info!(
+                "Downloading the patch {} from tag \"{}\"",
+                delta.url,
+                base.tag
+            );
+            let patch_path = operating.tmp_dir_path.join("delta");
+            let archive_path = operating.tmp_dir_path.join("download");
+            let result = async {
+                crate::io::download_verified(
+                    client,
+                    &delta.url,
+                    &delta.hash,
+                    &patch_path,
+                    self.cancel,
+                )
+                .await?;
+                let (cache, base_hash, expected, cancel) = (
+                    cache.clone(),
+                    base.info.hash.clone(),
+                    down_info.hash.clone(),
+                    self.cancel.clone(),
+                );
+                let (patch_path, archive_path) = (patch_path.clone(), archive_path.clone());
+                Ok::<_, AvmError>(
+                    crate::spawn_blocking(move || {
+                        let patched = cache.patch_blocking(
+                            &base_hash,
+                            &patch_path,
+                            &expected,
+                            &archive_path,
+                            &cancel,
+                        );
+                        std::fs::remove_file(&patch_path)?;
+                        patched
+                    })
+                    .await?,
+                )
+            }
+            .await;
+            match result {
+                Ok(Some(_)) => return true,
+                // Evicted since the tags were listed.
+                Ok(None) => continue,
+                Err(err) => {
+                    log::warn!(
+                        "Failed to apply the patch {}, downloading the whole archive: {err:#}",
+                        delta.url
+                    );
+                    let _ = crate::spawn_blocking(move || Ok(std::fs::remove_file(&archive_path)?))
+                        .await;
+                    return false;
+                }
+            }
+        }
+        false
+    }
+
+    /// Resolves the release like [`InstallArgs::install`] without downloading it or taking
+    /// any lock.
+    pub async fn plan(&self) -> crate::Result<InstallPlan> {
+        let (down_info, platform) = self.resolve().await?;
+        let tag_dir = self.tools_base.join(self.tool_name).join(&*down_info.tag);
+        let (tag_dir, exists) = crate::spawn_blocking(move || {
+            let exists = tag_dir.exists();
+            Ok((tag_dir, exists))
+        })
+        .await?;
+        let url = self
+            .client
+            .for_tool(self.tool_name)
+            .mirrored_url(&down_info.url)
+            .map_or(down_info.url, SmolStr::from);
+        Ok(InstallPlan {
+            tag: down_info.tag,
+            version: Version {
+                version: down_info.version,
+                is_lts: down_info.is_lts,
+            },
+            platform,
+            flavor: self.flavor.clone(),
+            url,
+            hash: down_info.hash,
+            tag_dir,
+            exists,
+        })
+    }
+
+    /// Resolves the release like [`InstallArgs::install`] to be written to a bundle with
+    /// [`crate::bundle::write`].
+    pub async fn bundle_release(&self) -> crate::Result<BundledRelease> {
+        let (down_info, platform) = self.resolve().await?;
+        let signature = self.tool.archive_signature(&down_info.url).await?;
+        let companions = self
+            .tool
+            .companion_archives(platform.clone(), self.flavor.clone())
             .await?;
-        let down_info = super::DownInfo::from_tool_down_info(
+        Ok(BundledRelease {
+            tag: down_info.tag,
+            version: down_info.version,
+            is_lts: down_info.is_lts,
+            platform,
+            flavor: self.flavor.clone(),
+            url: down_info.url,
+            hash: down_info.hash,
+            file: String::new(),
+            signature: signature.as_ref().map(BundledSignature::new),
+            companions: companions.into_iter().map(BundledCompanion::from).collect(),
+        })
+    }
+
+    /// The release to install with its normalized tag, and the platform of its build.
+    async fn resolve(&self) -> crate::Result<(super::DownInfo, Option<SmolStr>)> {
+        let mut platform = self.platform.clone();
+        let down_info = match self.down_info(platform.clone()).await {
+            Err(err) if self.platform_fallback && is_missing_build(&err) => {
+                let Some((native, emulated)) = platform
+                    .clone()
+                    .or_else(crate::platform::current_platform)
+                    .and_then(|native| {
+                        Some((native.clone(), crate::platform::emulated_platform(&native)?))
+                    })
+                else {
+                    return Err(err);
+                };
+                let Ok(down_info) = self.down_info(Some(emulated.clone())).await else {
+                    return Err(err);
+                };
+                log::warn!(
+                    "No {native} build found, installing the {emulated} build, which runs under emulation"
+                );
+                platform = Some(emulated);
+                down_info
+            }
+            result => result?,
+        };
+        let mut down_info = super::DownInfo::from_tool_down_info(
             down_info,
-            self.platform.as_deref(),
+            platform.as_deref(),
             self.flavor.as_deref(),
         );
-        if down_info.tag.starts_with(TMP_PREFIX) {
-            anyhow::bail!("Tag \"{}\" is reserved for temporary use", down_info.tag);
+        if let Some(pinned_hashes) = self.pinned_hashes {
+            down_info.hash = pinned_hashes.check(
+                self.tool_name,
+                &down_info.version,
+                platform.as_deref(),
+                self.flavor.as_deref(),
+                &down_info.hash,
+            )?;
         }
-        let tool_dir = self.tools_base.join(self.tool_name);
-        log::debug!("Tool dir: {}", tool_dir.display());
-        let tag_dir = tool_dir.join(&down_info.tag);
-        log::debug!("Tag dir: {}", tag_dir.display());
-        let tmp_dir = tool_dir.join(format!("{}{}", TMP_PREFIX, down_info.tag));
-        log::debug!("Tmp dir: {}", tmp_dir.display());
-        let operating = create_operating(tmp_dir, down_info.tag.to_string()).await?;
-
-        let tag_dir = if self.update {
-            tag_dir
-        } else {
-            let (tag_dir, exists) = crate::spawn_blocking(move || {
-                let exists = tag_dir.exists();
-                Ok((tag_dir, exists))
-            })
-            .await?;
+        down_info.tag = crate::tag::normalize(&down_info.tag)?;
+        Ok((down_info, platform))
+    }
 
-            if exists {
-                anyhow::bail!("\"{}\" already exists", down_info.tag);
-            }
+    async fn down_info(&self, platform: Option<SmolStr>) -> crate::Result<super::ToolDownInfo> {
+        validate_platform_flavor(self.tool, platform.as_deref(), self.flavor.as_deref())?;
+        self.tool
+            .get_down_info(platform, self.flavor.clone(), self.install_version.clone())
+            .await
+    }
+}
 
-            tag_dir
-        };
+/// Whether `err` tells that the tool has no build for the platform.
+fn is_missing_build(err: &AvmError) -> bool {
+    matches!(
+        err,
+        AvmError::VersionNotFound
+            | AvmError::PlatformRequired
+            | AvmError::UnsupportedPlatform { .. }
+            | AvmError::UnsupportedCombination { .. }
+    )
+}
+
+/// Installs an archive from a URL as a tag, without looking up the tool's releases.
+pub struct UrlInstaller<'a> {
+    pub tool_name: &'a str,
+    pub client: &'a HttpClient,
+    pub tools_base: &'a Path,
+    pub url: &'a str,
+    pub target_tag: &'a str,
+    pub version: Version,
+    /// The archive is not verified if empty.
+    pub hash: crate::FileHash,
+    pub update: bool,
+    pub default: bool,
+    /// Keep the temporary directory of a failed install for debugging.
+    pub keep_partial: bool,
+    /// Extract tar archives while downloading instead of saving them first.
+    pub stream_extract: bool,
+    pub cancel: &'a CancellationToken,
+}
+
+impl UrlInstaller<'_> {
+    pub async fn install(self) -> crate::Result<DownloadExtractState> {
+        let target_tag = crate::tag::normalize(self.target_tag)?;
+        let tool_dir = self.tools_base.join(self.tool_name);
+        let (operating, tag_dir, backup_dir) = prepare_install(
+            &tool_dir,
+            &target_tag,
+            self.update,
+            false,
+            self.keep_partial,
+        )
+        .await?;
+        if self.hash.is_empty() {
+            log::warn!("No hash given, the archive won't be verified");
+        }
 
+        let client = self.client.for_tool(self.tool_name);
         let state = DownloadExtractState::start(
-            self.client,
-            &down_info.url,
+            &client,
+            self.url,
             operating,
+            &self.hash,
+            self.stream_extract,
             Box::new(InstallCustomAction {
-                hash: down_info.hash,
-                version: Version {
-                    version: down_info.version.clone(),
-                    is_lts: down_info.is_lts,
+                info: InstallInfo {
+                    url: Some(self.url.into()),
+                    hash: self.hash.clone(),
+                    ..InstallInfo::new(self.version)
                 },
                 tool_dir,
-                target_tag: down_info.tag.clone(),
+                target_tag,
                 target_dir: tag_dir,
+                backup_dir,
                 default: self.default,
+                post_install: None,
+                single_binary: None,
+                signature: None,
+                companions: None,
             }),
+            self.cancel.clone(),
         )
         .await?;
+        Ok(state)
+    }
+}
 
-        Ok((down_info.tag, down_info.url, state))
+/// Installs a release from a bundle written by [`crate::bundle::write`], without network
+/// access.
+pub struct BundleInstaller<'a, T: GeneralTool> {
+    pub tool_name: &'a str,
+    pub tool: &'a T,
+    pub tools_base: &'a Path,
+    pub bundle: &'a Path,
+    /// The platform of the release to install, the current one if `None`.
+    pub platform: Option<SmolStr>,
+    pub flavor: Option<SmolStr>,
+    pub update: bool,
+    pub default: bool,
+    /// Keep the temporary directory of a failed install for debugging.
+    pub keep_partial: bool,
+    pub cancel: &'a CancellationToken,
+}
+
+impl<T: GeneralTool> BundleInstaller<'_, T> {
+    /// Returns the tag and the URL the release was bundled from with the state of the
+    /// install.
+    pub async fn install(self) -> crate::Result<(SmolStr, SmolStr, DownloadExtractState)> {
+        let manifest = crate::bundle::read_manifest(self.bundle).await?;
+        if manifest.tool != self.tool_name {
+            return Err(anyhow::anyhow!(
+                "The bundle '{}' is of {}, not {}",
+                self.bundle.display(),
+                manifest.tool,
+                self.tool_name
+            )
+            .into());
+        }
+        let platform = self
+            .platform
+            .clone()
+            .or_else(crate::platform::current_platform);
+        let Some(release) = manifest.releases.iter().find(|release| {
+            (platform.is_none() || release.platform.is_none() || release.platform == platform)
+                && (self.flavor.is_none() || release.flavor == self.flavor)
+        }) else {
+            let available = manifest
+                .releases
+                .iter()
+                .map(|release| match &release.platform {
+                    Some(platform) => format!("{} ({platform})", release.tag),
+                    None => release.tag.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            return Err(anyhow::anyhow!(
+                "No release in the bundle '{}' matches the platform {}, it has: {available}",
+                self.bundle.display(),
+                platform.as_deref().unwrap_or("of this machine"),
+            )
+            .into());
+        };
+        let mut release = release.clone();
+        release.tag = crate::tag::normalize(&release.tag)?;
+        let signing_keys = self.tool.signing_keys();
+        let signature = match &release.signature {
+            _ if signing_keys.is_empty() => None,
+            Some(signature) => Some(signature.to_detached(signing_keys)?),
+            None => {
+                return Err(anyhow::anyhow!(
+                    "The bundle '{}' has no signature of \"{}\", which {} requires",
+                    self.bundle.display(),
+                    release.tag,
+                    self.tool_name
+                )
+                .into())
+            }
+        };
+        let companions = (!release.companions.is_empty()).then(|| Companions {
+            archives: release
+                .companions
+                .iter()
+                .map(BundledCompanion::to_archive)
+                .collect(),
+            source: CompanionSource::Bundle(
+                self.bundle.to_path_buf(),
+                release
+                    .companions
+                    .iter()
+                    .map(|companion| companion.file.clone())
+                    .collect(),
+            ),
+            cancel: self.cancel.clone(),
+        });
+
+        let tool_dir = self.tools_base.join(self.tool_name);
+        let (operating, tag_dir, backup_dir) = prepare_install(
+            &tool_dir,
+            &release.tag,
+            self.update,
+            false,
+            self.keep_partial,
+        )
+        .await?;
+        let (bundle, file, archive_path) = (
+            self.bundle.to_path_buf(),
+            release.file.clone(),
+            operating.tmp_dir_path.join("download"),
+        );
+        crate::spawn_blocking(move || {
+            crate::bundle::extract_archive_blocking(&bundle, &file, &archive_path)
+        })
+        .await?;
+
+        let file_name = Path::new(&release.file)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let state = DownloadExtractState::from_archive(
+            operating,
+            &file_name,
+            &release.hash,
+            Box::new(InstallCustomAction {
+                info: InstallInfo {
+                    platform: release.platform,
+                    flavor: release.flavor,
+                    url: Some(release.url.clone()),
+                    hash: release.hash.clone(),
+                    ..InstallInfo::new(Version {
+                        version: release.version,
+                        is_lts: release.is_lts,
+                    })
+                },
+                tool_dir,
+                target_tag: release.tag.clone(),
+                target_dir: tag_dir,
+                backup_dir,
+                default: self.default,
+                post_install: self.tool.post_install(),
+                single_binary: self.tool.single_binary(),
+                signature,
+                companions,
+            }),
+            self.cancel.clone(),
+        )
+        .await?;
+        Ok((release.tag, release.url, state))
     }
 }
 
@@ -189,14 +949,22 @@ pub struct LocalInstaller<'a> {
     pub tools_base: &'a Path,
     pub archive: PathBuf,
     pub target_tag: &'a str,
-    pub version: Version,
+    /// Required unless the archive was written by [`export_tag`], whose recorded version
+    /// it overrides.
+    pub version: Option<Version>,
     pub hash: Option<&'a str>,
     pub update: bool,
+    /// Replace an existing tag that is not an alias, even without `update`, and remove a stale
+    /// operation lock first.
+    pub force: bool,
     pub default: bool,
+    /// Keep the temporary directory of a failed install for debugging.
+    pub keep_partial: bool,
+    pub cancel: &'a CancellationToken,
 }
 
 impl LocalInstaller<'_> {
-    pub async fn install(self) -> anyhow::Result<()> {
+    pub async fn install(self) -> crate::Result<()> {
         let Self {
             tool_name,
             tools_base,
@@ -205,42 +973,27 @@ impl LocalInstaller<'_> {
             version,
             hash,
             update,
+            force,
             default,
+            keep_partial,
+            cancel,
         } = self;
 
-        if target_tag.starts_with(TMP_PREFIX) {
-            anyhow::bail!("Tag '{}' is reserved for temporary use", target_tag);
-        }
+        let target_tag = crate::tag::normalize(target_tag)?;
         let tool_dir = tools_base.join(tool_name);
-        log::debug!("Tool dir: {}", tool_dir.display());
-        let tag_dir = tool_dir.join(target_tag);
-        log::debug!("Tag dir: {}", tag_dir.display());
-        let tmp_dir = tool_dir.join(format!("{}{}", TMP_PREFIX, target_tag));
-        log::debug!("Tmp dir: {}", tmp_dir.display());
-        let operating = create_operating(tmp_dir, target_tag.to_owned()).await?;
-
-        let tag_dir = if update {
-            tag_dir
-        } else {
-            let (tag_dir, exists) = crate::spawn_blocking(move || {
-                let exists = tag_dir.exists();
-                Ok((tag_dir, exists))
-            })
-            .await?;
-
-            if exists {
-                anyhow::bail!("\"{}\" already exists", target_tag);
-            }
-
-            tag_dir
-        };
+        let (operating, tag_dir, backup_dir) =
+            prepare_install(&tool_dir, &target_tag, update, force, keep_partial).await?;
 
-        let archive_type = ArchiveType::from_path(archive.as_os_str().as_encoded_bytes())?;
-        let hash = hash.map(toml::from_str::<crate::FileHash>);
-        let tag_dir = crate::spawn_blocking(move || {
+        let hash = hash
+            .map(toml::from_str::<crate::FileHash>)
+            .transpose()
+            .map_err(anyhow::Error::from)?;
+        let cancel = cancel.clone();
+        crate::spawn_blocking(move || {
             let mut operating = operating;
-            if let Some(hash) = hash {
-                blocking::verify_hash(&hash?, &archive)?;
+            let archive_type = ArchiveType::from_path(&archive)?;
+            if let Some(hash) = &hash {
+                blocking::verify_hash(hash, &archive, &cancel)?;
             }
 
             log::info!("Extracting ...");
@@ -248,22 +1001,48 @@ impl LocalInstaller<'_> {
             let extracted_dir = operating.tmp_dir_path.join("extracted");
             std::fs::remove_dir_all(&extracted_dir).ok();
             std::fs::create_dir_all(&extracted_dir)?;
-            blocking::extract_archive(archive_type, &archive, &extracted_dir)?;
-            std::fs::remove_dir_all(&tag_dir).ok();
-            std::fs::rename(&extracted_dir, &tag_dir)?;
-            write_version_info_file(&tag_dir, &version)?;
+            blocking::extract_archive(archive_type, &archive, &extracted_dir, &cancel)?;
+            // Archives written by `export_tag` carry what the tag was installed from.
+            let exported = match std::fs::read_to_string(extracted_dir.join(VERSION_INFO_FILE)) {
+                Ok(content) => {
+                    Some(toml::from_str::<InstallInfo>(&content).with_context(|| {
+                        format!("Invalid {VERSION_INFO_FILE} in '{}'", archive.display())
+                    })?)
+                }
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+                Err(err) => return Err(err.into()),
+            };
+            let info = match (exported, version) {
+                (Some(exported), Some(version)) => InstallInfo {
+                    version: version.version,
+                    is_lts: version.is_lts,
+                    ..exported
+                },
+                (Some(exported), None) => exported,
+                (None, Some(version)) => InstallInfo {
+                    hash: hash.unwrap_or_default(),
+                    ..InstallInfo::new(version)
+                },
+                (None, None) => anyhow::bail!(
+                    "`--version` is required for archives not written by `avm export-tag`"
+                ),
+            };
+            write_install_info(&extracted_dir, info)?;
+            let _journal = journal::begin(&journal::Operation::Install {
+                tag_dir: tag_dir.clone(),
+                source_dir: extracted_dir.clone(),
+                backup_dir: backup_dir.clone(),
+                tmp_dir: operating.tmp_dir_path.clone(),
+            })?;
+            blocking::install_dir(&extracted_dir, &tag_dir, &backup_dir)?;
+            operating.commit();
             operating.drop_should_not_block = false;
-            Ok(tag_dir)
+            Ok(())
         })
         .await?;
 
         if default {
-            let default_path = tool_dir.join(DEFAULT_TAG);
-            let target_tag = target_tag.to_owned();
-            crate::spawn_blocking(move || {
-                blocking::set_alias_tag(&target_tag, &tag_dir, DEFAULT_TAG, &default_path)
-            })
-            .await?;
+            set_alias(&tool_dir, target_tag, DEFAULT_TAG.into()).await?;
         }
 
         Ok(())
@@ -275,7 +1054,8 @@ pub async fn get_downinfo(
     platform: Option<SmolStr>,
     flavor: Option<SmolStr>,
     version_filter: VersionFilter,
-) -> anyhow::Result<super::DownInfo> {
+) -> crate::Result<super::DownInfo> {
+    validate_platform_flavor(tool, platform.as_deref(), flavor.as_deref())?;
     let down_info = tool
         .get_down_info(platform.clone(), flavor.clone(), version_filter)
         .await?;
@@ -289,57 +1069,174 @@ pub async fn get_vers(
     platform: Option<SmolStr>,
     flavor: Option<SmolStr>,
     version_filter: VersionFilter,
-) -> anyhow::Result<Vec<super::Version>> {
+) -> crate::Result<Vec<super::Version>> {
+    validate_platform_flavor(tool, platform.as_deref(), flavor.as_deref())?;
     tool.fetch_versions(platform, flavor, version_filter).await
 }
 
+/// Check the platform and flavor against the tool metadata and the combinations the tool
+/// publishes builds for, so that the user gets the valid values instead of an empty lookup.
+pub fn validate_platform_flavor(
+    tool: &impl GeneralTool,
+    platform: Option<&str>,
+    flavor: Option<&str>,
+) -> crate::Result<()> {
+    let info = tool.info();
+    if let (Some(platform), Some(all_platforms)) = (platform, &info.all_platforms) {
+        if !all_platforms.iter().any(|p| p == platform) {
+            return Err(AvmError::UnsupportedPlatform {
+                platform: platform.into(),
+                supported: all_platforms.clone(),
+            });
+        }
+    }
+    if let (Some(flavor), Some(all_flavors)) = (flavor, &info.all_flavors) {
+        if !all_flavors.iter().any(|f| f == tool.base_flavor(flavor)) {
+            return Err(AvmError::UnsupportedFlavor {
+                flavor: flavor.into(),
+                supported: all_flavors.clone(),
+            });
+        }
+    }
+    if let (Some(platform), Some(flavor)) = (platform, flavor) {
+        if !tool.supports_combination(platform, flavor) {
+            return Err(AvmError::UnsupportedCombination {
+                platform: platform.into(),
+                flavor: flavor.into(),
+                matrix: support_matrix(tool).unwrap_or_default(),
+            });
+        }
+    }
+    Ok(())
+}
+
+/// The flavors available on each platform, or `None` if the tool doesn't have both
+/// distinct platforms and flavors.
+pub fn support_matrix(tool: &impl GeneralTool) -> Option<Vec<(SmolStr, Vec<SmolStr>)>> {
+    let info = tool.info();
+    let (all_platforms, all_flavors) = (info.all_platforms.as_ref()?, info.all_flavors.as_ref()?);
+    Some(
+        all_platforms
+            .iter()
+            .map(|platform| {
+                let flavors = all_flavors
+                    .iter()
+                    .filter(|flavor| tool.supports_combination(platform, flavor))
+                    .cloned()
+                    .collect();
+                (platform.clone(), flavors)
+            })
+            .collect(),
+    )
+}
+
+pub struct EndpointCheck {
+    pub url: SmolStr,
+    /// `true` if the request went to the upstream URL even though a mirror applies to it.
+    pub direct: bool,
+    /// The status code and the time until the response headers arrived.
+    pub result: Result<(reqwest::StatusCode, Duration), SmolStr>,
+}
+
+impl EndpointCheck {
+    pub fn is_ok(&self) -> bool {
+        matches!(&self.result, Ok((status, _)) if status.is_success())
+    }
+}
+
+/// Check the reachability and latency of the tool's upstream endpoints. When a mirror applies
+/// to an endpoint, both the mirror and the upstream URL are checked.
+pub async fn ping(tool: &impl GeneralTool, client: &HttpClient) -> Vec<EndpointCheck> {
+    let mut checks = Vec::new();
+    for endpoint in tool.endpoints() {
+        let mirrored_url = client.mirrored_url(&endpoint);
+        let request = client.get(&endpoint);
+        checks.push(EndpointCheck {
+            url: mirrored_url.map_or_else(|| endpoint.clone(), SmolStr::from),
+            direct: false,
+            result: time_request(request).await,
+        });
+        if checks.last().is_some_and(|c| c.url != endpoint) {
+            checks.push(EndpointCheck {
+                url: endpoint.clone(),
+                direct: true,
+                result: time_request(client.get_direct(&endpoint)).await,
+            });
+        }
+    }
+    checks
+}
+
+async fn time_request(
+    request: crate::HttpRequest<'_>,
+) -> Result<(reqwest::StatusCode, Duration), SmolStr> {
+    let start = Instant::now();
+    // Only the response headers are awaited, the body is dropped unread.
+    match request.timeout(PING_TIMEOUT).send().await {
+        Ok(response) => Ok((response.status(), start.elapsed())),
+        Err(err) => Err(smol_str::format_smolstr!("{:#}", err)),
+    }
+}
+
 pub async fn remove_tag(
     tool_name: &str,
     tools_base: &Path,
     tags_to_remove: Vec<SmolStr>,
     allow_dangling: bool,
-) -> anyhow::Result<()> {
+) -> crate::Result<()> {
     let tool_dir = tools_base.join(tool_name);
+    let tags_to_remove = tags_to_remove
+        .iter()
+        .map(|tag| crate::tag::normalize(tag))
+        .collect::<crate::Result<Vec<_>>>()?;
     let tags_set = tags_to_remove.iter().cloned().collect::<FxHashSet<_>>();
 
     crate::spawn_blocking(move || {
-        if !allow_dangling {
-            // Check if the tag is an alias target
-            for (tag, alias_tag) in blocking::list_tags(&tool_dir, TMP_PREFIX)? {
-                if let Some(alias_tag) = alias_tag {
-                    if !tags_set.contains(&tag) && tags_set.contains(&alias_tag) {
-                        anyhow::bail!(
-                            "Tag \"{}\" is an alias target of \"{}\", remove the alias first",
-                            alias_tag,
-                            tag
-                        );
+        let _lock = lock_tool_dir(&tool_dir)?;
+        blocking::AliasStore::update(&tool_dir, |store| {
+            if !allow_dangling {
+                // Check if the tag is an alias target
+                for (tag, alias_tag) in blocking::list_tags(&tool_dir, TMP_PREFIX)? {
+                    if let Some(alias_tag) = alias_tag {
+                        if !tags_set.contains(&tag) && tags_set.contains(&alias_tag) {
+                            return Err(AvmError::AliasTarget {
+                                tag: alias_tag,
+                                alias: tag,
+                            }
+                            .into());
+                        }
                     }
                 }
             }
-        }
 
-        for tag in tags_to_remove {
-            let tag_dir = tool_dir.join(&*tag);
-            // Attempt to remove the directory
-            std::fs::remove_dir_all(&tag_dir).map_err(|err| {
-                if err.kind() == std::io::ErrorKind::NotFound {
-                    anyhow::anyhow!("Tag \"{}\" not found", tag)
-                } else {
-                    anyhow::Error::from(err).context(format!("Failed to remove tag \"{}\"", tag))
+            for tag in tags_to_remove {
+                let is_alias = store.aliases.remove(&tag).is_some();
+                match remove_tag_dir(&tool_dir, &tag) {
+                    Ok(()) => {}
+                    // The alias may have lost its link, removing the record is enough.
+                    Err(err) if err.kind() == std::io::ErrorKind::NotFound && is_alias => {}
+                    Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                        return Err(AvmError::TagNotFound { tag: tag.clone() }.into());
+                    }
+                    Err(err) => {
+                        return Err(anyhow::Error::from(err)
+                            .context(format!("Failed to remove tag \"{}\"", tag)));
+                    }
                 }
-            })?;
-        }
-        Ok(())
+            }
+            Ok(())
+        })
     })
-    .await
+    .await?;
+    Ok(())
 }
 
 pub async fn list_tags(
     tool_name: &str,
     tools_base: &Path,
-) -> anyhow::Result<Vec<(SmolStr, Option<SmolStr>)>> {
+) -> crate::Result<Vec<(SmolStr, Option<SmolStr>)>> {
     let tool_dir = tools_base.join(tool_name);
-    crate::spawn_blocking(move || Ok(blocking::list_tags(&tool_dir, TMP_PREFIX)?)).await
+    Ok(crate::spawn_blocking(move || blocking::list_tags(&tool_dir, TMP_PREFIX)).await?)
 }
 
 pub async fn create_alias_tag(
@@ -347,64 +1244,393 @@ pub async fn create_alias_tag(
     tools_base: &Path,
     src_tag: SmolStr,
     alias_tag: SmolStr,
-) -> anyhow::Result<()> {
+) -> crate::Result<()> {
     let tool_dir = tools_base.join(tool_name);
+    let src_tag = crate::tag::normalize(&src_tag)?;
+    let alias_tag = crate::tag::normalize(&alias_tag)?;
+    if set_alias(&tool_dir, src_tag, alias_tag).await? == blocking::AliasKind::Copy {
+        log::warn!("The alias is a copy and won't follow updates of its target, run `avm repair-alias` to refresh it");
+    }
+    Ok(())
+}
+
+/// Points the alias tag to the source tag while holding the alias tag's operation lock.
+async fn set_alias(
+    tool_dir: &Path,
+    src_tag: SmolStr,
+    alias_tag: SmolStr,
+) -> anyhow::Result<blocking::AliasKind> {
     let tmp_dir = tool_dir.join(format!("{}{}", TMP_PREFIX, alias_tag));
-    let operating = create_operating(tmp_dir, alias_tag.to_string()).await?;
+    let operating = create_operating(tmp_dir, alias_tag.to_string(), false).await?;
     let src_path = tool_dir.join(&src_tag);
     let alias_path = tool_dir.join(&alias_tag);
     log::debug!("Alias src path: {}", src_path.display());
     log::debug!("Alias path: {}", alias_path.display());
 
+    let tool_dir = tool_dir.to_path_buf();
     crate::spawn_blocking(move || {
-        let _operating = operating;
-        blocking::set_alias_tag(&src_tag, &src_path, &alias_tag, &alias_path)
+        let staging_dir = operating.tmp_dir_path.join("alias");
+        blocking::AliasStore::update(&tool_dir, |store| {
+            let kind = blocking::set_alias_tag(
+                &src_tag,
+                &src_path,
+                &alias_tag,
+                &alias_path,
+                &staging_dir,
+            )?;
+            store.aliases.insert(alias_tag, src_tag);
+            Ok(kind)
+        })
     })
     .await
 }
 
+/// What [`repair_aliases`] did to an alias tag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AliasRepair {
+    /// The link already pointed to the target.
+    Unchanged,
+    /// The link was recreated to point to the target.
+    Relinked,
+    /// The alias was refreshed as a copy of the target.
+    Copied,
+    /// The target tag doesn't exist, the alias was left as is.
+    Dangling,
+}
+
+pub struct RepairedAlias {
+    pub alias: SmolStr,
+    pub target: SmolStr,
+    pub repair: AliasRepair,
+}
+
+/// Re-points alias tags to their targets in the current tool directory, for example after
+/// the data directory was moved. Copied aliases are refreshed, or replaced by links if
+/// linking works now. Repairs every alias if `tags` is empty.
+pub async fn repair_aliases(
+    tool_name: &str,
+    tools_base: &Path,
+    tags: Vec<SmolStr>,
+) -> crate::Result<Vec<RepairedAlias>> {
+    let tool_dir = tools_base.join(tool_name);
+    let aliases = {
+        let tool_dir = tool_dir.clone();
+        crate::spawn_blocking(move || blocking::list_tags(&tool_dir, TMP_PREFIX)).await?
+    };
+
+    for tag in &tags {
+        match aliases.iter().find(|(alias, _)| alias == tag) {
+            Some((_, Some(_))) => {}
+            Some((_, None)) => {
+                return Err(anyhow::anyhow!("Tag \"{tag}\" is not an alias").into());
+            }
+            None => return Err(AvmError::TagNotFound { tag: tag.clone() }),
+        }
+    }
+
+    let mut repaired = Vec::new();
+    for (alias, target) in aliases {
+        let Some(target) = target else {
+            continue;
+        };
+        if !tags.is_empty() && !tags.contains(&alias) {
+            continue;
+        }
+        let target_path = tool_dir.join(&target);
+        let alias_path = tool_dir.join(&alias);
+        let (target_exists, linked) = crate::spawn_blocking(move || {
+            let linked = match blocking::get_link_target(&alias_path) {
+                blocking::GetLinkResult::Link(link_target) => Some(link_target == target_path),
+                _ => None,
+            };
+            Ok((target_path.exists(), linked))
+        })
+        .await?;
+
+        let repair = if !target_exists {
+            AliasRepair::Dangling
+        } else if linked == Some(true) {
+            AliasRepair::Unchanged
+        } else {
+            match set_alias(&tool_dir, target.clone(), alias.clone()).await? {
+                blocking::AliasKind::Link => AliasRepair::Relinked,
+                blocking::AliasKind::Copy => AliasRepair::Copied,
+            }
+        };
+        repaired.push(RepairedAlias {
+            alias,
+            target,
+            repair,
+        });
+    }
+    repaired.sort_by(|a, b| a.alias.cmp(&b.alias));
+    Ok(repaired)
+}
+
+/// An alias tag with its target, see [`list_aliases`].
+pub struct AliasEntry {
+    pub alias: SmolStr,
+    pub target: SmolStr,
+    /// `false` if the alias is dangling.
+    pub target_exists: bool,
+}
+
+/// Lists the alias tags of a tool with their targets, sorted by alias.
+pub async fn list_aliases(tool_name: &str, tools_base: &Path) -> crate::Result<Vec<AliasEntry>> {
+    let tool_dir = tools_base.join(tool_name);
+    Ok(crate::spawn_blocking(move || {
+        Ok(blocking::list_tags(&tool_dir, TMP_PREFIX)?
+            .into_iter()
+            .filter_map(|(alias, target)| {
+                let target = target?;
+                Some(AliasEntry {
+                    target_exists: tool_dir.join(&*target).exists(),
+                    alias,
+                    target,
+                })
+            })
+            .collect())
+    })
+    .await?)
+}
+
+/// Looks up the target of an alias tag, failing if the tag is missing or not an alias.
+async fn alias_target(tool_name: &str, tools_base: &Path, alias: &str) -> crate::Result<SmolStr> {
+    match list_tags(tool_name, tools_base)
+        .await?
+        .into_iter()
+        .find(|(tag, _)| tag == alias)
+    {
+        Some((_, Some(target))) => Ok(target),
+        Some((_, None)) => Err(anyhow::anyhow!("Tag \"{alias}\" is not an alias").into()),
+        None => Err(AvmError::TagNotFound { tag: alias.into() }),
+    }
+}
+
+/// Deletes alias tags, keeping their targets. Fails without deleting anything if one of the
+/// tags is not an alias.
+pub async fn delete_aliases(
+    tool_name: &str,
+    tools_base: &Path,
+    aliases: Vec<SmolStr>,
+) -> crate::Result<()> {
+    let aliases = aliases
+        .iter()
+        .map(|alias| crate::tag::normalize(alias))
+        .collect::<crate::Result<Vec<_>>>()?;
+    for alias in &aliases {
+        alias_target(tool_name, tools_base, alias).await?;
+    }
+    remove_tag(tool_name, tools_base, aliases, false).await
+}
+
+/// Points an existing alias tag to another tag. Returns the previous target.
+pub async fn move_alias(
+    tool_name: &str,
+    tools_base: &Path,
+    alias: SmolStr,
+    new_target: SmolStr,
+) -> crate::Result<SmolStr> {
+    let tool_dir = tools_base.join(tool_name);
+    let alias = crate::tag::normalize(&alias)?;
+    let new_target = crate::tag::normalize(&new_target)?;
+    if new_target == alias {
+        return Err(anyhow::anyhow!("Alias \"{alias}\" can't point to itself").into());
+    }
+    let old_target = alias_target(tool_name, tools_base, &alias).await?;
+    if set_alias(&tool_dir, new_target, alias).await? == blocking::AliasKind::Copy {
+        log::warn!("The alias is a copy and won't follow updates of its target, run `avm repair-alias` to refresh it");
+    }
+    Ok(old_target)
+}
+
 pub async fn copy_tag(
     tool_name: &str,
     tools_base: &Path,
     src_tag: SmolStr,
     dest_tag: SmolStr,
-) -> anyhow::Result<()> {
+) -> crate::Result<()> {
+    copy_tag_from(tool_name, tools_base, tools_base, src_tag, dest_tag).await
+}
+
+/// Copies a tag from the tools of another data directory, such as a mounted backup, with its
+/// install info. An alias is copied as a regular tag with the content of its target.
+pub async fn copy_tag_from(
+    tool_name: &str,
+    src_tools_base: &Path,
+    tools_base: &Path,
+    src_tag: SmolStr,
+    dest_tag: SmolStr,
+) -> crate::Result<()> {
+    let src_tool_dir = src_tools_base.join(tool_name);
     let tool_dir = tools_base.join(tool_name);
+    let src_tag = crate::tag::normalize(&src_tag)?;
+    let dest_tag = crate::tag::normalize(&dest_tag)?;
     if dest_tag == DEFAULT_TAG {
-        anyhow::bail!("\"{DEFAULT_TAG}\" tag is only allowed as an alias tag");
+        return Err(
+            anyhow::anyhow!("\"{DEFAULT_TAG}\" tag is only allowed as an alias tag").into(),
+        );
     }
 
-    let src_path = tool_dir.join(&*src_tag);
     let dest_path = tool_dir.join(&*dest_tag);
     let tmp_dir = tool_dir.join(format!("{}{}", TMP_PREFIX, dest_tag));
-    let operating = create_operating(tmp_dir, dest_tag.to_string()).await?;
-    log::debug!("Copy src path: {}", src_path.display());
+    let operating = create_operating(tmp_dir, dest_tag.to_string(), false).await?;
     log::debug!("Copy dest path: {}", dest_path.display());
 
+    let cancel = crate::cancellation_token();
     crate::spawn_blocking(move || {
         let operating = operating;
+        // The link of an alias may point to where the source data directory was mounted
+        // before, its target is taken from the store instead.
+        let src_path = match blocking::AliasStore::load(&src_tool_dir)?
+            .aliases
+            .get(&src_tag)
+        {
+            Some(target) => src_tool_dir.join(&**target),
+            None => src_tool_dir.join(&*src_tag),
+        };
+        log::debug!("Copy src path: {}", src_path.display());
         if !src_path.exists() {
-            anyhow::bail!("Src tag \"{}\" not found", src_tag);
+            return Err(AvmError::TagNotFound { tag: src_tag }.into());
         }
         if dest_path.exists() {
-            anyhow::bail!("Dest tag \"{}\" already exists", dest_tag);
+            return Err(AvmError::TagExists { tag: dest_tag }.into());
         }
 
-        let tmp_copy_root = operating.tmp_dir_path.join("copy");
-        std::fs::remove_dir_all(&tmp_copy_root).ok();
-        std::fs::create_dir_all(&tmp_copy_root)?;
+        let tmp_copy = operating.tmp_dir_path.join("copy");
+        std::fs::remove_dir_all(&tmp_copy).ok();
+        blocking::copy_tree(&src_path, &tmp_copy, cancel)?;
+        // A copied alias becomes a regular tag.
+        match std::fs::remove_file(tmp_copy.join(blocking::COPY_ALIAS_MARKER)) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => return Err(err.into()),
+            _ => {}
+        }
+        std::fs::rename(tmp_copy, &dest_path)?;
+        Ok(())
+    })
+    .await?;
+    Ok(())
+}
+
+/// Replaces the identical files of the installed tags of a tool by hard links, see
+/// [`blocking::dedup_files`]. Aliases are skipped, they already share the files of their
+/// targets.
+pub async fn dedup_tags(
+    tool_name: &str,
+    tools_base: &Path,
+    dry_run: bool,
+) -> crate::Result<blocking::DedupStats> {
+    let tool_dir = tools_base.join(tool_name);
+    let cancel = crate::cancellation_token();
+    Ok(crate::spawn_blocking(move || {
+        let _lock = if dry_run || !tool_dir.exists() {
+            None
+        } else {
+            Some(lock_tool_dir(&tool_dir)?)
+        };
+        let dirs = blocking::list_tags(&tool_dir, TMP_PREFIX)?
+            .into_iter()
+            .filter(|(_, target)| target.is_none())
+            .map(|(tag, _)| tool_dir.join(&*tag))
+            .filter(|path| path.is_dir())
+            .collect::<Vec<_>>();
+        blocking::dedup_files(&dirs, dry_run, cancel)
+    })
+    .await?)
+}
+
+/// Packs a tag, along with what it was installed from, into a `.tar.zst` archive. The
+/// archive is installed with [`LocalInstaller`], for example on a machine without network
+/// access. An alias is exported as a copy of its target.
+pub async fn export_tag(
+    tool_name: &str,
+    tools_base: &Path,
+    tag: SmolStr,
+    output: PathBuf,
+    cancel: &CancellationToken,
+) -> crate::Result<()> {
+    if ArchiveType::from_file_name(output.as_os_str().as_encoded_bytes())
+        != Some(ArchiveType::TarZst)
+    {
+        return Err(anyhow::anyhow!(
+            "The exported archive must be a `.tar.zst` file, got '{}'",
+            output.display()
+        )
+        .into());
+    }
+    let tag_dir = get_tag_path(tool_name, tools_base, &tag)?;
+    let cancel = cancel.clone();
+    crate::spawn_blocking(move || blocking::pack_tar_zst(&tag_dir, &output, &cancel)).await?;
+    Ok(())
+}
 
-        let copy_options = fs_extra::dir::CopyOptions::new();
-        fs_extra::dir::copy(&src_path, &tmp_copy_root, &copy_options)?;
-        let copied_dir = tmp_copy_root.join(
-            src_path
-                .file_name()
-                .ok_or_else(|| anyhow::anyhow!("Invalid source tag path"))?,
+/// Renames a tag and re-points the aliases targeting it. Returns the re-pointed aliases.
+pub async fn rename_tag(
+    tool_name: &str,
+    tools_base: &Path,
+    tag: SmolStr,
+    new_tag: SmolStr,
+) -> crate::Result<Vec<SmolStr>> {
+    let tool_dir = tools_base.join(tool_name);
+    let tag = crate::tag::normalize(&tag)?;
+    let new_tag = crate::tag::normalize(&new_tag)?;
+    if new_tag == DEFAULT_TAG {
+        return Err(
+            anyhow::anyhow!("\"{DEFAULT_TAG}\" tag is only allowed as an alias tag").into(),
         );
-        std::fs::rename(copied_dir, &dest_path)?;
-        Ok(())
+    }
+
+    let tmp_dir = tool_dir.join(format!("{}{}", TMP_PREFIX, new_tag));
+    let operating = create_operating(tmp_dir, new_tag.to_string(), false).await?;
+    let retargeted = crate::spawn_blocking(move || {
+        blocking::AliasStore::update(&tool_dir, |store| {
+            if store.aliases.contains_key(&tag) {
+                anyhow::bail!("Tag \"{tag}\" is an alias, create the new alias with `avm alias` instead");
+            }
+            let path = tool_dir.join(&*tag);
+            let new_path = tool_dir.join(&*new_tag);
+            if !path.exists() {
+                return Err(AvmError::TagNotFound { tag }.into());
+            }
+            if new_path.exists() || store.aliases.contains_key(&new_tag) {
+                return Err(AvmError::TagExists { tag: new_tag }.into());
+            }
+            std::fs::rename(&path, &new_path).map_err(|err| {
+                anyhow::Error::from(err)
+                    .context(format!("Failed to rename \"{tag}\" to \"{new_tag}\""))
+            })?;
+
+            let staging_dir = operating.tmp_dir_path.join("alias");
+            let mut retargeted = Vec::new();
+            for (alias, target) in store.aliases.iter_mut() {
+                if *target != tag {
+                    continue;
+                }
+                // The store is the source of truth, a link that can't be updated now is
+                // left for `avm repair-alias`.
+                match blocking::set_alias_tag(
+                    &new_tag,
+                    &new_path,
+                    alias,
+                    &tool_dir.join(&**alias),
+                    &staging_dir,
+                ) {
+                    Ok(blocking::AliasKind::Link) => {}
+                    Ok(blocking::AliasKind::Copy) => {
+                        log::warn!("Alias \"{alias}\" was recreated as a copy")
+                    }
+                    Err(err) => log::warn!(
+                        "Failed to re-point alias \"{alias}\" ({err:#}), run `avm repair-alias` to fix it"
+                    ),
+                }
+                *target = new_tag.clone();
+                retargeted.push(alias.clone());
+            }
+            Ok(retargeted)
+        })
     })
-    .await
+    .await?;
+    Ok(retargeted)
 }
 
 pub async fn find_matching_local_tag(
@@ -414,47 +1640,20 @@ pub async fn find_matching_local_tag(
     platform: Option<SmolStr>,
     flavor: Option<SmolStr>,
     version_filter: VersionFilter,
-) -> anyhow::Result<Option<SmolStr>> {
+) -> crate::Result<Option<SmolStr>> {
     let tool_dir = tools_base.join(tool_name);
     let info = tool.info();
     let tag_prefixes = build_tag_prefixes(info, platform.as_deref(), flavor.as_deref());
-    let local_tags_and_versions =
-        crate::spawn_blocking(move || -> anyhow::Result<Vec<(SmolStr, Version)>> {
-            let tags = blocking::list_tags(&tool_dir, TMP_PREFIX)?;
-            let mut local_tags_and_versions = Vec::new();
-            for (tag, _) in tags {
-                let tag_path = tool_dir.join(&*tag);
-                let version_info_path = tag_path.join(VERSION_INFO_FILE);
-                let version_info_raw = match std::fs::read_to_string(&version_info_path) {
-                    Ok(value) => value,
-                    Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
-                    Err(err) => {
-                        log::warn!(
-                            "Failed to read version info for tag '{}': {}",
-                            tag,
-                            anyhow::Error::from(err)
-                                .context(version_info_path.display().to_string())
-                        );
-                        continue;
-                    }
-                };
-                let version = match toml::from_str::<Version>(&version_info_raw) {
-                    Ok(version) => version,
-                    Err(err) => {
-                        log::warn!(
-                            "Failed to parse version info for tag '{}': {}",
-                            tag,
-                            anyhow::Error::from(err)
-                                .context(version_info_path.display().to_string())
-                        );
-                        continue;
-                    }
-                };
-                local_tags_and_versions.push((tag, version));
-            }
-            Ok(local_tags_and_versions)
-        })
-        .await?;
+    let local_tags_and_versions = crate::spawn_blocking(move || {
+        let tags = blocking::list_tags(&tool_dir, TMP_PREFIX)?;
+        Ok(
+            read_install_infos(&tool_dir, tags.into_iter().map(|(tag, _)| tag))
+                .into_iter()
+                .map(|(tag, info)| (tag, info.version()))
+                .collect::<Vec<_>>(),
+        )
+    })
+    .await?;
     let tags_and_versions = local_tags_and_versions
         .iter()
         .filter(|(tag, _)| parse_tag_version_start(tag, &tag_prefixes).is_some())
@@ -463,9 +1662,276 @@ pub async fn find_matching_local_tag(
     Ok(tool.find_best_matching_local_tag(tags_and_versions, &version_filter))
 }
 
+/// A tag with what is known about it, for listing every tag of a tool.
+pub struct TagDetails {
+    pub tag: SmolStr,
+    /// The target if the tag is an alias.
+    pub alias_of: Option<SmolStr>,
+    /// `None` for aliases and tags not installed by avm.
+    pub info: Option<InstallInfo>,
+    /// The disk usage in bytes, `None` for aliases.
+    pub size: Option<u64>,
+}
+
+/// Lists the tags of a tool with their install info and disk usage. Measuring walks every
+/// installed tag, so this is slower than [`list_tags`].
+pub async fn list_tag_details(
+    tool_name: &str,
+    tools_base: &Path,
+) -> crate::Result<Vec<TagDetails>> {
+    let tool_dir = tools_base.join(tool_name);
+    Ok(crate::spawn_blocking(move || {
+        let tags = blocking::list_tags(&tool_dir, TMP_PREFIX)?;
+        let mut infos = read_install_infos(
+            &tool_dir,
+            tags.iter()
+                .filter(|(_, target)| target.is_none())
+                .map(|(tag, _)| tag.clone()),
+        );
+        Ok(tags
+            .into_iter()
+            .map(|(tag, alias_of)| {
+                let (info, size) = if alias_of.is_some() {
+                    (None, None)
+                } else {
+                    let info = infos
+                        .iter()
+                        .position(|(info_tag, _)| *info_tag == tag)
+                        .map(|i| infos.swap_remove(i).1);
+                    let path = tool_dir.join(&*tag);
+                    let size = blocking::dir_size(&path).unwrap_or_else(|err| {
+                        log::warn!("Failed to measure {}: {}", path.display(), err);
+                        0
+                    });
+                    (info, Some(size))
+                };
+                TagDetails {
+                    tag,
+                    alias_of,
+                    info,
+                    size,
+                }
+            })
+            .collect())
+    })
+    .await?)
+}
+
+/// An installed tag with the release it was installed from.
+pub struct InstalledTag {
+    pub tag: SmolStr,
+    /// For tags installed by older versions without the platform and flavor recorded, they
+    /// are taken from the tag name if it is named after them.
+    pub info: InstallInfo,
+}
+
+/// Lists the installed tags that are not aliases, skipping those without install info.
+pub async fn installed_tags(
+    tool_name: &str,
+    tool: &impl GeneralTool,
+    tools_base: &Path,
+) -> crate::Result<Vec<InstalledTag>> {
+    let tool_dir = tools_base.join(tool_name);
+    let tags_and_infos = crate::spawn_blocking(move || {
+        let tags = blocking::list_tags(&tool_dir, TMP_PREFIX)?;
+        Ok(read_install_infos(
+            &tool_dir,
+            tags.into_iter()
+                .filter(|(_, target)| target.is_none())
+                .map(|(tag, _)| tag),
+        ))
+    })
+    .await?;
+
+    let tag_prefixes = build_tag_prefixes(tool.info(), None, None);
+    Ok(tags_and_infos
+        .into_iter()
+        .map(|(tag, mut info)| {
+            if info.platform.is_none() && info.flavor.is_none() {
+                let prefix = tag_prefixes.iter().find(|prefix| {
+                    tag.strip_prefix(prefix.value.as_str()) == Some(info.version.as_str())
+                });
+                info.platform = prefix.and_then(|prefix| prefix.platform.clone());
+                info.flavor = prefix.and_then(|prefix| prefix.flavor.clone());
+            }
+            InstalledTag { tag, info }
+        })
+        .collect())
+}
+
+/// Finds the newest release for the platform and flavor of an installed tag, within the same
+/// major version unless `any_major`, and only LTS releases if the tag is LTS. `None` if the
+/// installed version is the newest or is not listed upstream, so it can't be compared.
+pub async fn find_upgrade(
+    tool: &impl GeneralTool,
+    installed: &InstalledTag,
+    any_major: bool,
+) -> crate::Result<Option<Version>> {
+    let info = &installed.info;
+    let major = info
+        .version
+        .split(|c: char| !c.is_ascii_digit())
+        .next()
+        .and_then(|major| major.parse().ok());
+    let version_filter = VersionFilter {
+        lts_only: info.is_lts,
+        version_prefix: major.filter(|_| !any_major).map(|major| VersionPrefix {
+            major,
+            minor: None,
+            patch: None,
+        }),
+        ..Default::default()
+    };
+    let versions = tool
+        .fetch_versions(info.platform.clone(), info.flavor.clone(), version_filter)
+        .await?;
+    let Some(current) = versions.iter().position(|v| v.version == info.version) else {
+        return Ok(None);
+    };
+    Ok(versions.into_iter().skip(current + 1).last())
+}
+
+/// Selects the installed tags to prune.
+pub struct PruneFilter {
+    pub platform: Option<SmolStr>,
+    pub flavor: Option<SmolStr>,
+    pub version_filter: VersionFilter,
+    /// The number of newest matching tags to keep.
+    pub keep_latest: usize,
+}
+
+/// Finds the installed tags matching the filter, newest first, except the newest
+/// `keep_latest` of them and the alias targets.
+pub async fn prune_candidates(
+    tool_name: &str,
+    tool: &impl GeneralTool,
+    tools_base: &Path,
+    filter: &PruneFilter,
+) -> crate::Result<Vec<InstalledTag>> {
+    let alias_targets = list_tags(tool_name, tools_base)
+        .await?
+        .into_iter()
+        .filter_map(|(_, target)| target)
+        .collect::<FxHashSet<_>>();
+    let mut remaining = installed_tags(tool_name, tool, tools_base).await?;
+    remaining.retain(|installed| {
+        filter
+            .platform
+            .as_ref()
+            .is_none_or(|platform| installed.info.platform.as_ref() == Some(platform))
+            && filter
+                .flavor
+                .as_ref()
+                .is_none_or(|flavor| installed.info.flavor.as_ref() == Some(flavor))
+    });
+
+    // Let the tool rank the tags, as it does when resolving a version to a local tag.
+    let mut matched = Vec::new();
+    loop {
+        let versions = remaining
+            .iter()
+            .map(|installed| (installed.tag.clone(), installed.info.version()))
+            .collect::<Vec<_>>();
+        let Some(newest) = tool.find_best_matching_local_tag(
+            versions.iter().map(|(tag, version)| (&**tag, version)),
+            &filter.version_filter,
+        ) else {
+            break;
+        };
+        let Some(index) = remaining
+            .iter()
+            .position(|installed| installed.tag == newest)
+        else {
+            break;
+        };
+        matched.push(remaining.swap_remove(index));
+    }
+
+    Ok(matched
+        .into_iter()
+        .skip(filter.keep_latest)
+        .filter(|installed| !alias_targets.contains(&installed.tag))
+        .collect())
+}
+
+/// Points the aliases of a tag to another tag. Returns the re-pointed aliases.
+pub async fn retarget_aliases(
+    tool_name: &str,
+    tools_base: &Path,
+    from_tag: &str,
+    to_tag: SmolStr,
+) -> crate::Result<Vec<SmolStr>> {
+    let tool_dir = tools_base.join(tool_name);
+    let mut retargeted = Vec::new();
+    for (alias, target) in list_tags(tool_name, tools_base).await? {
+        if target.as_deref() != Some(from_tag) {
+            continue;
+        }
+        set_alias(&tool_dir, to_tag.clone(), alias.clone()).await?;
+        retargeted.push(alias);
+    }
+    Ok(retargeted)
+}
+
+/// Reads the install info of a tag, `None` if it has none.
+pub async fn install_info(
+    tool_name: &str,
+    tools_base: &Path,
+    tag: SmolStr,
+) -> crate::Result<Option<InstallInfo>> {
+    let tool_dir = tools_base.join(tool_name);
+    if !tool_dir.join(&*tag).exists() {
+        return Err(AvmError::TagNotFound { tag });
+    }
+    Ok(crate::spawn_blocking(move || {
+        Ok(read_install_infos(&tool_dir, std::iter::once(tag))
+            .pop()
+            .map(|(_, info)| info))
+    })
+    .await?)
+}
+
+/// Reads the install info of the tags, skipping the tags where it is missing or invalid.
+fn read_install_infos(
+    tool_dir: &Path,
+    tags: impl Iterator<Item = SmolStr>,
+) -> Vec<(SmolStr, InstallInfo)> {
+    let mut tags_and_infos = Vec::new();
+    for tag in tags {
+        let version_info_path = tool_dir.join(&*tag).join(VERSION_INFO_FILE);
+        let version_info_raw = match std::fs::read_to_string(&version_info_path) {
+            Ok(value) => value,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(err) => {
+                log::warn!(
+                    "Failed to read version info for tag '{}': {}",
+                    tag,
+                    anyhow::Error::from(err).context(version_info_path.display().to_string())
+                );
+                continue;
+            }
+        };
+        let info = match toml::from_str::<InstallInfo>(&version_info_raw) {
+            Ok(info) => info,
+            Err(err) => {
+                log::warn!(
+                    "Failed to parse version info for tag '{}': {}",
+                    tag,
+                    anyhow::Error::from(err).context(version_info_path.display().to_string())
+                );
+                continue;
+            }
+        };
+        tags_and_infos.push((tag, info));
+    }
+    tags_and_infos
+}
+
 #[derive(Clone)]
 struct TagPrefix {
     value: SmolStr,
+    platform: Option<SmolStr>,
+    flavor: Option<SmolStr>,
 }
 
 impl TagPrefix {
@@ -513,6 +1979,8 @@ fn build_tag_prefixes(
             }
             tag_prefixes.push(TagPrefix {
                 value: SmolStr::from(prefix),
+                platform: platform.clone(),
+                flavor: flavor.clone(),
             });
         }
     }
@@ -535,17 +2003,22 @@ fn parse_tag_version_start(tag: &str, tag_prefixes: &[TagPrefix]) -> Option<usiz
     None
 }
 
-fn write_version_info_file(tag_dir: &Path, version: &Version) -> anyhow::Result<()> {
+/// Writes the install info, stamped with the current time.
+fn write_install_info(tag_dir: &Path, mut info: InstallInfo) -> anyhow::Result<()> {
+    info.installed_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|since_epoch| since_epoch.as_secs());
     let version_info_path = tag_dir.join(VERSION_INFO_FILE);
-    let content = toml::to_string(version)?;
+    let content = toml::to_string(&info)?;
     std::fs::write(version_info_path, content)?;
     Ok(())
 }
 
-pub fn get_tag_path(tool_name: &str, tools_base: &Path, tag: &str) -> anyhow::Result<PathBuf> {
+pub fn get_tag_path(tool_name: &str, tools_base: &Path, tag: &str) -> crate::Result<PathBuf> {
     let tag_path = tools_base.join(tool_name).join(tag);
     if !tag_path.exists() {
-        anyhow::bail!("Tag \"{}\" not found", tag);
+        return Err(AvmError::TagNotFound { tag: tag.into() });
     }
     Ok(tag_path)
 }
@@ -555,16 +2028,82 @@ pub fn get_entry_path<T: GeneralTool + ?Sized>(
     tool: &T,
     tools_base: &Path,
     tag: &str,
-) -> anyhow::Result<PathBuf> {
+) -> crate::Result<PathBuf> {
     let tag_dir = get_tag_path(tool_name, tools_base, tag)?;
     tool.entry_path(tag_dir)
 }
 
+/// Executables in `dir` by name, with the `.exe` suffix on Windows.
+pub fn exes_in(dir: &Path, names: &[&str]) -> Vec<(SmolStr, PathBuf)> {
+    let suffix = if cfg!(windows) { ".exe" } else { "" };
+    names
+        .iter()
+        .map(|&name| (name.into(), dir.join(format!("{name}{suffix}"))))
+        .collect()
+}
+
+/// Resolves an executable of the tool by name, see [`GeneralTool::exe_paths`].
+pub fn get_exe_path<T: GeneralTool + ?Sized>(
+    tool_name: &str,
+    tool: &T,
+    tools_base: &Path,
+    tag: &str,
+    exe: &str,
+) -> crate::Result<PathBuf> {
+    let tag_dir = get_tag_path(tool_name, tools_base, tag)?;
+    let exe_paths = tool.exe_paths(tag_dir)?;
+    let available = exe_paths.iter().map(|(name, _)| name.clone()).collect();
+    exe_paths
+        .into_iter()
+        .find(|(name, _)| name == exe)
+        .map(|(_, path)| path)
+        .ok_or_else(|| AvmError::UnknownExecutable {
+            name: exe.into(),
+            available,
+        })
+}
+
+pub struct CleanOptions {
+    /// Only report what would be removed.
+    pub dry_run: bool,
+    /// Only remove temporary directories that have not been modified for this long.
+    pub older_than: Option<Duration>,
+}
+
+/// A temporary directory left by an install or alias operation.
+pub struct TmpDirEntry {
+    pub tag: SmolStr,
+    pub path: PathBuf,
+    /// Time since the directory was last modified, `None` if unknown.
+    pub age: Option<Duration>,
+    pub size: u64,
+    /// Old enough to be removed according to [`CleanOptions::older_than`].
+    pub expired: bool,
+    pub removed: bool,
+}
+
+#[derive(Default)]
+pub struct CleanReport {
+    pub tmp_dirs: Vec<TmpDirEntry>,
+    pub dangling_aliases: Vec<SmolStr>,
+}
+
 /// Clean up the temporary directories and dangling alias tags
-pub async fn clean(tool_name: &str, tools_base: &Path) -> anyhow::Result<()> {
+pub async fn clean(
+    tool_name: &str,
+    tools_base: &Path,
+    options: CleanOptions,
+) -> crate::Result<CleanReport> {
     let tool_dir = tools_base.join(tool_name);
 
-    crate::spawn_blocking(move || {
+    let report = crate::spawn_blocking(move || {
+        let mut report = CleanReport::default();
+        // Keeps the temporary directories of running operations.
+        let _lock = if options.dry_run || !tool_dir.exists() {
+            None
+        } else {
+            Some(lock_tool_dir(&tool_dir)?)
+        };
         let entries = match std::fs::read_dir(&tool_dir) {
             Ok(entries) => entries,
             Err(err) => {
@@ -574,7 +2113,7 @@ pub async fn clean(tool_name: &str, tools_base: &Path) -> anyhow::Result<()> {
                         "Tool directory {} not found, nothing to clean.",
                         tool_dir.display()
                     );
-                    return Ok(());
+                    return Ok(report);
                 }
                 return Err(anyhow::Error::from(err).context(format!(
                     "Failed to read tool directory: {}",
@@ -603,33 +2142,83 @@ pub async fn clean(tool_name: &str, tools_base: &Path) -> anyhow::Result<()> {
             let file_name_str = file_name.to_string_lossy();
 
             // Clean temporary directories
-            if file_name_str.starts_with(TMP_PREFIX) {
-                log::debug!("Removing temporary directory: {}", entry_path.display());
-                if let Err(err) = std::fs::remove_dir_all(&entry_path) {
-                    log::warn!(
-                        "Failed to remove temporary directory {}: {}",
-                        entry_path.display(),
-                        err
-                    );
+            if let Some(tag) = file_name_str.strip_prefix(TMP_PREFIX) {
+                let age = entry
+                    .metadata()
+                    .and_then(|metadata| metadata.modified())
+                    .ok()
+                    .and_then(|modified| modified.elapsed().ok());
+                let size = blocking::dir_size(&entry_path).unwrap_or_else(|err| {
+                    log::warn!("Failed to measure {}: {}", entry_path.display(), err);
+                    0
+                });
+                let expired = match (options.older_than, age) {
+                    (None, _) => true,
+                    (Some(older_than), Some(age)) => age >= older_than,
+                    (Some(_), None) => false,
+                };
+
+                let mut removed = false;
+                if expired && !options.dry_run {
+                    log::debug!("Removing temporary directory: {}", entry_path.display());
+                    match std::fs::remove_dir_all(&entry_path) {
+                        Ok(()) => removed = true,
+                        Err(err) => log::warn!(
+                            "Failed to remove temporary directory {}: {}",
+                            entry_path.display(),
+                            err
+                        ),
+                    }
                 }
+                report.tmp_dirs.push(TmpDirEntry {
+                    tag: tag.into(),
+                    path: entry_path,
+                    age,
+                    size,
+                    expired,
+                    removed,
+                });
                 continue; // Move to the next entry
             }
 
             // Check for dangling aliases (symlinks)
             match std::fs::symlink_metadata(&entry_path) {
                 Ok(metadata) => {
-                    if metadata.file_type().is_symlink() {
+                    if metadata.is_dir() {
+                        // A copied alias is dangling once its target is gone.
+                        let dangling = blocking::copy_alias_target(&entry_path)
+                            .is_some_and(|target| !tool_dir.join(&*target).exists());
+                        if dangling {
+                            if !options.dry_run {
+                                log::debug!("Removing dangling alias '{}'", entry_path.display());
+                                if let Err(err) = std::fs::remove_dir_all(&entry_path) {
+                                    log::warn!(
+                                        "Failed to remove dangling alias {}: {}",
+                                        entry_path.display(),
+                                        err
+                                    );
+                                    continue;
+                                }
+                            }
+                            report.dangling_aliases.push(file_name_str.into());
+                        }
+                    } else if metadata.file_type().is_symlink() {
                         // Check if the target exists. We use metadata() which follows the link.
                         // If it fails (e.g., NotFound), the link is dangling.
                         if std::fs::metadata(&entry_path).is_err() {
+                            if options.dry_run {
+                                report.dangling_aliases.push(file_name_str.into());
+                                continue;
+                            }
                             log::debug!("Removing dangling alias '{}'", entry_path.display());
                             // Use remove_file to remove dangling symlinks
-                            if let Err(err) = blocking::remove_link(&entry_path) {
-                                log::warn!(
+                            match blocking::remove_link(&entry_path) {
+                                Ok(()) => report.dangling_aliases.push(file_name_str.into()),
+                                Err(err) => log::warn!(
                                     "Failed to remove dangling alias {}: {}",
                                     entry_path.display(),
                                     err
-                                );
+                                ),
                             }
                         }
                     }
@@ -646,8 +2235,33 @@ pub async fn clean(tool_name: &str, tools_base: &Path) -> anyhow::Result<()> {
                 }
             }
         }
+
+        // Recorded aliases whose target is gone, including those without a link left.
+        let mut prune_store = |store: &mut blocking::AliasStore| {
+            let dangling: Vec<_> = store
+                .aliases
+                .iter()
+                .filter(|(_, target)| !tool_dir.join(&***target).exists())
+                .map(|(alias, _)| alias.clone())
+                .collect();
+            for alias in dangling {
+                store.aliases.remove(&alias);
+                if !report.dangling_aliases.contains(&alias) {
+                    report.dangling_aliases.push(alias);
+                }
+            }
+        };
+        if options.dry_run {
+            prune_store(&mut blocking::AliasStore::load(&tool_dir)?);
+        } else {
+            blocking::AliasStore::update(&tool_dir, |store| {
+                prune_store(store);
+                Ok(())
+            })?;
+        }
         log::debug!("Finished cleaning up {}", tool_dir.display());
-        Ok(())
+        Ok(report)
     })
-    .await
+    .await?;
+    Ok(report)
 }
@@ -0,0 +1,270 @@
+use serde::Deserialize;
+use smol_str::SmolStr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use super::manifest::{best_local_tag, sorted_versions, ReleaseVersion};
+use super::terraform::{find_sha256, ignore_lts_only};
+use crate::platform::{cpu, create_platform_string, os, resolve_default_platform};
+use crate::tool::github_releases::{self, AssetMatcher};
+use crate::tool::{ToolDownInfo, ToolInfo, Version, VersionFilter};
+use crate::HttpClient;
+
+pub struct Tool {
+    client: Arc<HttpClient>,
+    info: ToolInfo,
+    assets: AssetMatcher,
+}
+
+/// The releases are listed by their `cmake-<version>-files-v1.json` assets, the index of the
+/// files of a release that is also published on cmake.org.
+const REPO: &str = "Kitware/CMake";
+const FILES_URL: &str = "https://cmake.org/files/";
+
+/// Platforms by the `os` and `architecture` of the files in the index. The macOS archive is a
+/// universal one.
+const PLATFORMS: [(&str, &str, &str, &str); 7] = [
+    (cpu::X64, os::LINUX, "linux", "x86_64"),
+    (cpu::ARM64, os::LINUX, "linux", "aarch64"),
+    (cpu::X64, os::MAC, "macos", "x86_64"),
+    (cpu::ARM64, os::MAC, "macos", "arm64"),
+    (cpu::X64, os::WIN, "windows", "x86_64"),
+    (cpu::ARM64, os::WIN, "windows", "arm64"),
+    (cpu::X86, os::WIN, "windows", "i386"),
+];
+
+const EXES: [&str; 3] = ["cmake", "ctest", "cpack"];
+
+impl crate::tool::GeneralTool for Tool {
+    fn info(&self) -> &ToolInfo {
+        &self.info
+    }
+
+    fn endpoints(&self) -> Vec<SmolStr> {
+        vec![github_releases::releases_url(REPO), FILES_URL.into()]
+    }
+
+    async fn fetch_versions(
+        &self,
+        _platform: Option<SmolStr>,
+        _flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<Vec<Version>> {
+        let version_filter = ignore_lts_only(version_filter, "cmake");
+        Ok(self.fetch_release_versions(&version_filter).await?)
+    }
+
+    async fn get_down_info(
+        &self,
+        platform: Option<SmolStr>,
+        _flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<ToolDownInfo> {
+        let platform = platform.ok_or(crate::AvmError::PlatformRequired)?;
+        let (dto_os, dto_arch) = dto_os_arch(&platform)?;
+        let version_filter = ignore_lts_only(version_filter, "cmake");
+        let versions = self.fetch_release_versions(&version_filter).await?;
+        let version = versions.last().ok_or(crate::AvmError::VersionNotFound)?;
+
+        let release_dir = release_dir_url(&version.version);
+        let index: FilesDto = self
+            .client
+            .get(&format!(
+                "{release_dir}cmake-{}-files-v1.json",
+                version.version
+            ))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        let file = index
+            .archive(dto_os, dto_arch)
+            .ok_or(crate::AvmError::VersionNotFound)?;
+        let hash_file = index
+            .hash_files
+            .iter()
+            .find(|hash_file| hash_file.algorithm.iter().any(|a| a == "SHA-256"))
+            .ok_or_else(|| anyhow::anyhow!("CMake {} has no SHA-256 file", version.version))?;
+        let sums = self
+            .client
+            .get(&format!("{release_dir}{}", hash_file.name))
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        let sha256 = find_sha256(&sums, &file.name)
+            .ok_or_else(|| anyhow::anyhow!("'{}' is not in '{}'", file.name, hash_file.name))?;
+        Ok(ToolDownInfo {
+            version: version.clone(),
+            url: smol_str::format_smolstr!("{release_dir}{}", file.name),
+            hash: crate::FileHash {
+                sha256: Some(sha256),
+                ..Default::default()
+            },
+        })
+    }
+
+    fn find_best_matching_local_tag<'a, I>(
+        &self,
+        tags_and_versions: I,
+        version_filter: &VersionFilter,
+    ) -> Option<SmolStr>
+    where
+        I: Iterator<Item = (&'a str, &'a Version)>,
+    {
+        let version_filter = ignore_lts_only(version_filter.clone(), "cmake");
+        best_local_tag(tags_and_versions, &version_filter)
+    }
+
+    fn entry_path(&self, tag_dir: PathBuf) -> crate::Result<PathBuf> {
+        Ok(self.exe_paths(tag_dir)?.swap_remove(0).1)
+    }
+
+    fn exe_paths(&self, tag_dir: PathBuf) -> crate::Result<Vec<(SmolStr, PathBuf)>> {
+        // The macOS archive holds an application bundle.
+        let app_bin_dir = tag_dir.join("CMake.app").join("Contents").join("bin");
+        let bin_dir = if app_bin_dir.is_dir() {
+            app_bin_dir
+        } else {
+            tag_dir.join("bin")
+        };
+        Ok(super::exes_in(&bin_dir, &EXES))
+    }
+}
+
+impl Tool {
+    pub fn new(client: Arc<HttpClient>, config_default_platform: Option<SmolStr>) -> Self {
+        let all_platforms = PLATFORMS
+            .iter()
+            .map(|(cpu, os, _, _)| create_platform_string(cpu, os))
+            .collect::<Vec<_>>();
+        let default_platform =
+            resolve_default_platform(&all_platforms, config_default_platform, &[]);
+        Tool {
+            client,
+            info: ToolInfo {
+                about: "CMake build system generator, with ctest and cpack".into(),
+                after_long_help: None,
+                all_platforms: Some(all_platforms),
+                default_platform,
+                all_flavors: None,
+                default_flavor: None,
+            },
+            assets: AssetMatcher::new([(None, r"cmake-(?<version>.+)-files-v1\.json")]),
+        }
+    }
+
+    async fn fetch_release_versions(
+        &self,
+        version_filter: &VersionFilter,
+    ) -> anyhow::Result<Vec<Version>> {
+        let releases = github_releases::fetch_releases(&self.client, REPO).await?;
+        Ok(sorted_versions(
+            self.assets
+                .matching(None, &releases)?
+                .iter()
+                .filter_map(|asset| {
+                    let version = ReleaseVersion::parse(asset.version().into(), false, None)?;
+                    version.matches(version_filter).then_some(version)
+                }),
+        ))
+    }
+}
+
+/// The directory of the files of a release, `v<major>.<minor>/`.
+fn release_dir_url(version: &str) -> String {
+    let series = version.splitn(3, '.').take(2).collect::<Vec<_>>().join(".");
+    format!("{FILES_URL}v{series}/")
+}
+
+fn dto_os_arch(platform: &str) -> anyhow::Result<(&'static str, &'static str)> {
+    PLATFORMS
+        .iter()
+        .find(|(cpu, os, _, _)| create_platform_string(cpu, os) == platform)
+        .map(|(_, _, dto_os, dto_arch)| (*dto_os, *dto_arch))
+        .ok_or_else(|| anyhow::anyhow!("Unsupported platform: {platform}"))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FilesDto {
+    files: Vec<FileDto>,
+    hash_files: Vec<HashFileDto>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileDto {
+    /// Names of the OS, such as `["linux", "Linux"]`, or of an old version of it, such as
+    /// `["macos10.10"]`.
+    os: Vec<SmolStr>,
+    #[serde(default)]
+    architecture: Vec<SmolStr>,
+    /// `archive`, `installer` or `volume`.
+    class: SmolStr,
+    name: SmolStr,
+}
+
+#[derive(Debug, Deserialize)]
+struct HashFileDto {
+    algorithm: Vec<SmolStr>,
+    name: SmolStr,
+}
+
+impl FilesDto {
+    fn archive(&self, dto_os: &str, dto_arch: &str) -> Option<&FileDto> {
+        self.files.iter().find(|file| {
+            file.class == "archive"
+                && file.os.iter().any(|os| os == dto_os)
+                && file.architecture.iter().any(|arch| arch == dto_arch)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_archives_from_the_files_index() {
+        let index: FilesDto = serde_json::from_str(
+            r#"{
+                "version": {"major": 3, "minor": 30, "patch": 2, "string": "3.30.2"},
+                "files": [
+                    {"os": ["source"], "class": "archive", "name": "cmake-3.30.2.tar.gz"},
+                    {"os": ["linux", "Linux"], "architecture": ["x86_64"], "class": "installer", "name": "cmake-3.30.2-linux-x86_64.sh"},
+                    {"os": ["linux", "Linux"], "architecture": ["x86_64"], "class": "archive", "name": "cmake-3.30.2-linux-x86_64.tar.gz"},
+                    {"os": ["macos10.10"], "architecture": ["arm64", "x86_64"], "class": "archive", "name": "cmake-3.30.2-macos10.10-universal.tar.gz"},
+                    {"os": ["macos", "macOS"], "architecture": ["arm64", "x86_64"], "class": "archive", "name": "cmake-3.30.2-macos-universal.tar.gz"},
+                    {"os": ["windows", "Windows"], "architecture": ["arm64"], "class": "archive", "name": "cmake-3.30.2-windows-arm64.zip"}
+                ],
+                "hashFiles": [{"algorithm": ["SHA-256"], "name": "cmake-3.30.2-SHA-256.txt"}]
+            }"#,
+        )
+        .unwrap();
+        let name = |platform| {
+            let (dto_os, dto_arch) = dto_os_arch(platform).unwrap();
+            index
+                .archive(dto_os, dto_arch)
+                .map(|file| file.name.clone())
+        };
+        assert_eq!(
+            name("x64-linux").as_deref(),
+            Some("cmake-3.30.2-linux-x86_64.tar.gz")
+        );
+        assert_eq!(
+            name("arm64-mac").as_deref(),
+            Some("cmake-3.30.2-macos-universal.tar.gz")
+        );
+        assert_eq!(
+            name("arm64-win").as_deref(),
+            Some("cmake-3.30.2-windows-arm64.zip")
+        );
+        assert_eq!(name("arm64-linux"), None);
+        assert_eq!(
+            release_dir_url("3.31.0-rc1"),
+            "https://cmake.org/files/v3.31/"
+        );
+    }
+}
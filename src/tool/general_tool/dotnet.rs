@@ -2,12 +2,14 @@ use rustc_hash::FxHashSet;
 use serde::Deserialize;
 use smol_str::SmolStr;
 use std::cmp::Ordering;
-use std::path::PathBuf;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use crate::HttpClient;
 use crate::{
     platform::{cpu, create_platform_string, current_cpu, current_os, os},
+    tool::end_of_life::{EndOfLife, SupportStatus},
     tool::{ToolDownInfo, ToolInfo, Version, VersionFilter},
 };
 
@@ -24,6 +26,7 @@ pub struct Tool {
     client: Arc<HttpClient>,
     info: ToolInfo,
     corresponding_rids: Vec<&'static str>,
+    end_of_life: EndOfLife,
 }
 
 impl crate::tool::GeneralTool for Tool {
@@ -31,6 +34,14 @@ impl crate::tool::GeneralTool for Tool {
         &self.info
     }
 
+    fn endpoints(&self) -> Vec<SmolStr> {
+        vec![RELEASES_INDEX_URL.into(), self.end_of_life.url()]
+    }
+
+    async fn support_status(&self, version: &str) -> crate::Result<Option<SupportStatus>> {
+        self.end_of_life.support_status(version).await
+    }
+
     fn describe_flavor(&self, flavor: &str) -> &'static str {
         match flavor {
             "sdk" => "The full .NET SDK including the CLI, compiler, and runtime.",
@@ -45,13 +56,17 @@ impl crate::tool::GeneralTool for Tool {
         }
     }
 
+    fn supports_combination(&self, platform: &str, flavor: &str) -> bool {
+        flavor != "windowsdesktop_runtime" || platform.ends_with(&format!("-{}", os::WIN))
+    }
+
     async fn fetch_versions(
         &self,
         platform: Option<SmolStr>,
         flavor: Option<SmolStr>,
         version_filter: VersionFilter,
-    ) -> anyhow::Result<Vec<Version>> {
-        let platform = platform.ok_or_else(|| anyhow::anyhow!("Platform is required"))?;
+    ) -> crate::Result<Vec<Version>> {
+        let platform = platform.ok_or(crate::AvmError::PlatformRequired)?;
         let rid = self.get_rid(&platform)?;
         let flavor = Flavor::parse(flavor.as_deref())?;
 
@@ -79,8 +94,8 @@ impl crate::tool::GeneralTool for Tool {
         platform: Option<SmolStr>,
         flavor: Option<SmolStr>,
         version_filter: VersionFilter,
-    ) -> anyhow::Result<ToolDownInfo> {
-        let platform = platform.ok_or_else(|| anyhow::anyhow!("Platform is required"))?;
+    ) -> crate::Result<ToolDownInfo> {
+        let platform = platform.ok_or(crate::AvmError::PlatformRequired)?;
         let rid = self.get_rid(&platform)?;
         let flavor = Flavor::parse(flavor.as_deref())?;
 
@@ -100,7 +115,7 @@ impl crate::tool::GeneralTool for Tool {
                     ..Default::default()
                 },
             }),
-            None => Err(anyhow::anyhow!("No download URL found.")),
+            None => Err(crate::AvmError::VersionNotFound),
         }
     }
 
@@ -129,7 +144,7 @@ impl crate::tool::GeneralTool for Tool {
             .map(|(_, tag)| tag)
     }
 
-    fn entry_path(&self, tag_dir: PathBuf) -> anyhow::Result<PathBuf> {
+    fn entry_path(&self, tag_dir: PathBuf) -> crate::Result<PathBuf> {
         let mut path = tag_dir;
         #[cfg(windows)]
         path.push("dotnet.exe");
@@ -137,6 +152,10 @@ impl crate::tool::GeneralTool for Tool {
         path.push("dotnet");
         Ok(path)
     }
+
+    fn env_vars(&self, tag_dir: &Path) -> Vec<(OsString, OsString)> {
+        vec![("DOTNET_ROOT".into(), tag_dir.into())]
+    }
 }
 
 impl Tool {
@@ -153,6 +172,7 @@ impl Tool {
                 })
             });
 
+        let end_of_life = EndOfLife::new(client.clone(), "dotnet");
         Self {
             client,
             info: ToolInfo {
@@ -171,6 +191,7 @@ The selected flavor controls which artifact family is queried from the official
                 default_flavor: Some("sdk".into()),
             },
             corresponding_rids,
+            end_of_life,
         }
     }
 
@@ -298,7 +319,7 @@ The selected flavor controls which artifact family is queried from the official
             });
         }
 
-        channels.sort_by(|a, b| b.channel_version.cmp(&a.channel_version));
+        channels.sort_by_key(|c| std::cmp::Reverse(c.channel_version));
         Ok(channels)
     }
 
@@ -463,9 +484,7 @@ fn channel_matches_filter(channel: &ReleaseChannelDto, version_filter: &VersionF
         return false;
     }
 
-    if !version_filter.allow_prerelease
-        && channel.support_phase.as_deref() == Some("preview")
-        && version_filter.exact_version.is_none()
+    if !version_filter.includes_prerelease() && channel.support_phase.as_deref() == Some("preview")
     {
         return false;
     }
@@ -628,13 +647,10 @@ fn matches_version_filter(
     if version_filter.lts_only && !is_lts {
         return false;
     }
-    if !version_filter.allow_prerelease && version.is_prerelease() {
+    if !version_filter.includes_prerelease() && version.is_prerelease() {
         return false;
     }
-    if version_filter
-        .version_prefix
-        .is_some_and(|prefix| !prefix.matches(version.major, version.minor, version.patch))
-    {
+    if !version_filter.matches_numbers(version.major, version.minor, version.patch) {
         return false;
     }
     if version_filter
@@ -766,9 +782,8 @@ mod tests {
         let stable = parse_dotnet_version("10.0.100").unwrap();
         let filter = VersionFilter {
             lts_only: true,
-            allow_prerelease: true,
-            version_prefix: None,
-            exact_version: None,
+            include_prerelease: true,
+            ..Default::default()
         };
 
         assert!(!matches_version_filter(
@@ -797,7 +812,7 @@ mod tests {
             },
         ];
 
-        channels.sort_by(|a, b| b.channel_version.cmp(&a.channel_version));
+        channels.sort_by_key(|c| std::cmp::Reverse(c.channel_version));
 
         assert_eq!(channels[0].channel_version, (10, 0));
         assert_eq!(channels[1].channel_version, (9, 0));
@@ -806,12 +821,7 @@ mod tests {
 
     #[test]
     fn select_latest_release_asset_returns_highest_match_in_channel() {
-        let filter = VersionFilter {
-            lts_only: false,
-            allow_prerelease: false,
-            version_prefix: None,
-            exact_version: None,
-        };
+        let filter = VersionFilter::default();
         let channel_release = ChannelReleaseDto {
             release_type: "sts".into(),
             releases: vec![
@@ -0,0 +1,231 @@
+use smol_str::SmolStr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use super::erlang::{parse_builds, HexBuild, OtpVersion, HEX_BUILDS_URL};
+use super::manifest::{best_local_tag, sorted_versions, ReleaseVersion};
+use crate::tool::{NewTag, ToolDownInfo, ToolInfo, Version, VersionFilter};
+use crate::HttpClient;
+
+pub struct Tool {
+    client: Arc<HttpClient>,
+    info: ToolInfo,
+}
+
+/// Each flavor is built for an Erlang/OTP major version.
+const FLAVORS: [&str; 5] = ["otp-24", "otp-25", "otp-26", "otp-27", "otp-28"];
+const DEFAULT_FLAVOR: &str = "otp-27";
+/// The tool that installs Erlang/OTP.
+const ERLANG: &str = "erlang";
+
+impl crate::tool::GeneralTool for Tool {
+    fn info(&self) -> &ToolInfo {
+        &self.info
+    }
+
+    fn describe_flavor(&self, flavor: &str) -> &'static str {
+        match flavor {
+            "otp-24" => "Compiled for Erlang/OTP 24.",
+            "otp-25" => "Compiled for Erlang/OTP 25.",
+            "otp-26" => "Compiled for Erlang/OTP 26.",
+            "otp-27" => "Compiled for Erlang/OTP 27.",
+            "otp-28" => "Compiled for Erlang/OTP 28.",
+            _ => "Tool-specific build flavor.",
+        }
+    }
+
+    fn endpoints(&self) -> Vec<SmolStr> {
+        vec![smol_str::format_smolstr!(
+            "{HEX_BUILDS_URL}elixir/builds.txt"
+        )]
+    }
+
+    async fn fetch_versions(
+        &self,
+        _platform: Option<SmolStr>,
+        flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<Vec<Version>> {
+        let version_filter = ignore_lts_only(version_filter);
+        let builds = self.fetch_builds(flavor.as_deref()).await?;
+        Ok(sorted_versions(matching_releases(&builds, &version_filter)))
+    }
+
+    async fn get_down_info(
+        &self,
+        _platform: Option<SmolStr>,
+        flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<ToolDownInfo> {
+        let version_filter = ignore_lts_only(version_filter);
+        let builds = self.fetch_builds(flavor.as_deref()).await?;
+        let versions = sorted_versions(matching_releases(&builds, &version_filter));
+        let version = versions.last().ok_or(crate::AvmError::VersionNotFound)?;
+        let build = builds
+            .iter()
+            .find(|build| build.version == version.version)
+            .ok_or(crate::AvmError::VersionNotFound)?;
+        Ok(ToolDownInfo {
+            version: version.clone(),
+            url: smol_str::format_smolstr!("{HEX_BUILDS_URL}elixir/{}.zip", build.name),
+            hash: crate::FileHash {
+                sha256: build.sha256.clone(),
+                ..Default::default()
+            },
+        })
+    }
+
+    fn find_best_matching_local_tag<'a, I>(
+        &self,
+        tags_and_versions: I,
+        version_filter: &VersionFilter,
+    ) -> Option<SmolStr>
+    where
+        I: Iterator<Item = (&'a str, &'a Version)>,
+    {
+        best_local_tag(tags_and_versions, &ignore_lts_only(version_filter.clone()))
+    }
+
+    fn entry_path(&self, tag_dir: PathBuf) -> crate::Result<PathBuf> {
+        Ok(script_paths(tag_dir).swap_remove(0).1)
+    }
+
+    fn exe_paths(&self, tag_dir: PathBuf) -> crate::Result<Vec<(SmolStr, PathBuf)>> {
+        Ok(script_paths(tag_dir))
+    }
+
+    /// Warns when no installed Erlang/OTP has the major version the flavor is built for.
+    async fn set_up_tag(&self, tag: &NewTag<'_>) -> crate::Result<()> {
+        let Some((flavor, otp_major)) = tag
+            .flavor
+            .and_then(|flavor| Some((flavor, otp_major(flavor)?)))
+        else {
+            return Ok(());
+        };
+        let mut has_otp = false;
+        for (erlang_tag, target) in super::list_tags(ERLANG, tag.tools_base).await? {
+            if target.is_some() {
+                continue;
+            }
+            let info = super::install_info(ERLANG, tag.tools_base, erlang_tag).await?;
+            if info.is_some_and(|info| {
+                OtpVersion::parse(&info.version).is_some_and(|version| version.major() == otp_major)
+            }) {
+                has_otp = true;
+                break;
+            }
+        }
+        if !has_otp {
+            log::warn!(
+                "Elixir flavor `{flavor}` needs Erlang/OTP {otp_major}, which is not installed. Install it with `avm install {ERLANG} -x {otp_major}`"
+            );
+        }
+        Ok(())
+    }
+}
+
+impl Tool {
+    pub fn new(client: Arc<HttpClient>) -> Self {
+        Tool {
+            client,
+            info: ToolInfo {
+                about: "Elixir programming language".into(),
+                after_long_help: Some(
+                    r#"Elixir runs on Erlang/OTP, which is installed separately with the `erlang` tool.
+The flavor selects the Erlang/OTP major version the build is compiled for, and should match the installed one."#
+                        .into(),
+                ),
+                all_platforms: None,
+                default_platform: None,
+                all_flavors: Some(FLAVORS.iter().map(SmolStr::new).collect()),
+                default_flavor: Some(DEFAULT_FLAVOR.into()),
+            },
+        }
+    }
+
+    async fn fetch_builds(&self, flavor: Option<&str>) -> anyhow::Result<Vec<HexBuild>> {
+        let suffix = format!("-{}", flavor.unwrap_or(DEFAULT_FLAVOR));
+        let content = self
+            .client
+            .get(&format!("{HEX_BUILDS_URL}elixir/builds.txt"))
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        Ok(parse_builds(&content, |name| {
+            release_version(name, &suffix)
+        }))
+    }
+}
+
+/// The Erlang/OTP major version an Elixir flavor is compiled for.
+pub fn otp_major(flavor: &str) -> Option<u32> {
+    flavor.strip_prefix("otp-")?.parse().ok()
+}
+
+/// The version of a release build named like `v1.17.0-rc.1-otp-27`. Builds of branches,
+/// named like `main-otp-27` or `v1.17-otp-27`, are not releases.
+fn release_version(name: &str, flavor_suffix: &str) -> Option<SmolStr> {
+    let version = name.strip_prefix('v')?.strip_suffix(flavor_suffix)?;
+    let core = version.split('-').next()?;
+    let is_release =
+        core.split('.').count() == 3 && core.split('.').all(|n| n.parse::<u32>().is_ok());
+    is_release.then(|| version.into())
+}
+
+fn matching_releases<'a>(
+    builds: &'a [HexBuild],
+    version_filter: &'a VersionFilter,
+) -> impl Iterator<Item = ReleaseVersion> + 'a {
+    builds.iter().filter_map(move |build| {
+        let version = ReleaseVersion::parse(build.version.clone(), false, None)?;
+        version.matches(version_filter).then_some(version)
+    })
+}
+
+fn script_paths(tag_dir: PathBuf) -> Vec<(SmolStr, PathBuf)> {
+    let bin_dir = tag_dir.join("bin");
+    let suffix = if cfg!(windows) { ".bat" } else { "" };
+    ["elixir", "elixirc", "iex", "mix"]
+        .into_iter()
+        .map(|name| (name.into(), bin_dir.join(format!("{name}{suffix}"))))
+        .collect()
+}
+
+fn ignore_lts_only(mut version_filter: VersionFilter) -> VersionFilter {
+    if version_filter.lts_only {
+        log::warn!(
+            "`--lts-only` is ignored for `elixir` because this tool does not define LTS releases."
+        );
+        version_filter.lts_only = false;
+    }
+    version_filter
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_release_versions() {
+        assert_eq!(
+            release_version("v1.17.2-otp-27", "-otp-27").as_deref(),
+            Some("1.17.2")
+        );
+        assert_eq!(
+            release_version("v1.18.0-rc.0-otp-27", "-otp-27").as_deref(),
+            Some("1.18.0-rc.0")
+        );
+        assert_eq!(release_version("v1.17.2-otp-26", "-otp-27"), None);
+        assert_eq!(release_version("v1.17-otp-27", "-otp-27"), None);
+        assert_eq!(release_version("main-otp-27", "-otp-27"), None);
+    }
+
+    #[test]
+    fn flavors_name_otp_majors() {
+        assert!(FLAVORS.iter().all(|flavor| otp_major(flavor).is_some()));
+        assert_eq!(otp_major("otp-26"), Some(26));
+        assert_eq!(otp_major("26"), None);
+    }
+}
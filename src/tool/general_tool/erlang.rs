@@ -0,0 +1,390 @@
+use smol_str::SmolStr;
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use super::manifest::resolve_default_platform;
+use crate::platform::{cpu, create_platform_string, os};
+use crate::tool::{ToolDownInfo, ToolInfo, Version, VersionFilter};
+use crate::HttpClient;
+
+pub struct Tool {
+    client: Arc<HttpClient>,
+    info: ToolInfo,
+}
+
+/// Precompiled builds published by Hex, the package manager of the Erlang ecosystem.
+pub(super) const HEX_BUILDS_URL: &str = "https://builds.hex.pm/builds/";
+
+/// The directory holding the builds of each platform, under `otp/`.
+const PLATFORM_DIRS: [(&str, &str, &str); 2] = [
+    (cpu::X64, os::LINUX, "ubuntu-22.04"),
+    (cpu::ARM64, os::LINUX, "arm64/ubuntu-22.04"),
+];
+
+impl crate::tool::GeneralTool for Tool {
+    fn info(&self) -> &ToolInfo {
+        &self.info
+    }
+
+    fn endpoints(&self) -> Vec<SmolStr> {
+        PLATFORM_DIRS
+            .iter()
+            .map(|(_, _, dir)| smol_str::format_smolstr!("{HEX_BUILDS_URL}otp/{dir}/builds.txt"))
+            .collect()
+    }
+
+    async fn fetch_versions(
+        &self,
+        platform: Option<SmolStr>,
+        _flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<Vec<Version>> {
+        let platform = platform.ok_or(crate::AvmError::PlatformRequired)?;
+        let version_filter = ignore_lts_only(version_filter);
+        let builds = self.fetch_builds(&platform).await?;
+        let mut releases = matching_releases(&builds, &version_filter);
+        releases.sort_by(|a, b| a.0.cmp(&b.0));
+        Ok(releases
+            .into_iter()
+            .map(|(_, build)| Version {
+                version: build.version.clone(),
+                is_lts: false,
+            })
+            .collect())
+    }
+
+    async fn get_down_info(
+        &self,
+        platform: Option<SmolStr>,
+        _flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<ToolDownInfo> {
+        let platform = platform.ok_or(crate::AvmError::PlatformRequired)?;
+        let version_filter = ignore_lts_only(version_filter);
+        let builds = self.fetch_builds(&platform).await?;
+        let (_, build) = matching_releases(&builds, &version_filter)
+            .into_iter()
+            .max_by(|a, b| a.0.cmp(&b.0))
+            .ok_or(crate::AvmError::VersionNotFound)?;
+        Ok(ToolDownInfo {
+            version: Version {
+                version: build.version.clone(),
+                is_lts: false,
+            },
+            url: smol_str::format_smolstr!(
+                "{HEX_BUILDS_URL}otp/{}/{}.tar.gz",
+                platform_dir(&platform)?,
+                build.name
+            ),
+            hash: crate::FileHash {
+                sha256: build.sha256.clone(),
+                ..Default::default()
+            },
+        })
+    }
+
+    fn find_best_matching_local_tag<'a, I>(
+        &self,
+        tags_and_versions: I,
+        version_filter: &VersionFilter,
+    ) -> Option<SmolStr>
+    where
+        I: Iterator<Item = (&'a str, &'a Version)>,
+    {
+        let version_filter = ignore_lts_only(version_filter.clone());
+        tags_and_versions
+            .filter_map(|(tag, version_info)| {
+                let version = OtpVersion::parse(&version_info.version)?;
+                version
+                    .matches(&version_info.version, &version_filter)
+                    .then(|| (version, SmolStr::from(tag)))
+            })
+            .max_by(|a, b| a.0.cmp(&b.0))
+            .map(|(_, tag)| tag)
+    }
+
+    fn entry_path(&self, tag_dir: PathBuf) -> crate::Result<PathBuf> {
+        Ok(tag_dir.join("bin").join("erl"))
+    }
+
+    fn exe_paths(&self, tag_dir: PathBuf) -> crate::Result<Vec<(SmolStr, PathBuf)>> {
+        Ok(super::exes_in(
+            &tag_dir.join("bin"),
+            &["erl", "erlc", "escript"],
+        ))
+    }
+
+    fn post_install(&self) -> Option<fn(&Path) -> crate::Result<()>> {
+        Some(run_install_script)
+    }
+}
+
+impl Tool {
+    pub fn new(client: Arc<HttpClient>, config_default_platform: Option<SmolStr>) -> Self {
+        let all_platforms: Vec<SmolStr> = PLATFORM_DIRS
+            .iter()
+            .map(|(cpu, os, _)| create_platform_string(cpu, os))
+            .collect();
+        let default_platform = resolve_default_platform(&all_platforms, config_default_platform);
+
+        Tool {
+            client,
+            info: ToolInfo {
+                about: "Erlang/OTP runtime and compiler".into(),
+                after_long_help: Some(
+                    "Installs the precompiled builds published on builds.hex.pm, which are only available for Linux."
+                        .into(),
+                ),
+                all_platforms: Some(all_platforms),
+                default_platform,
+                all_flavors: None,
+                default_flavor: None,
+            },
+        }
+    }
+
+    async fn fetch_builds(&self, platform: &str) -> anyhow::Result<Vec<HexBuild>> {
+        let url = format!("{HEX_BUILDS_URL}otp/{}/builds.txt", platform_dir(platform)?);
+        let content = self
+            .client
+            .get(&url)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        Ok(parse_builds(&content, |name| {
+            name.strip_prefix("OTP-").map(SmolStr::new)
+        }))
+    }
+}
+
+fn platform_dir(platform: &str) -> anyhow::Result<&'static str> {
+    PLATFORM_DIRS
+        .iter()
+        .find(|(cpu, os, _)| create_platform_string(cpu, os) == platform)
+        .map(|(_, _, dir)| *dir)
+        .ok_or_else(|| anyhow::anyhow!("Unsupported Erlang/OTP platform: {platform}"))
+}
+
+fn matching_releases<'a>(
+    builds: &'a [HexBuild],
+    version_filter: &VersionFilter,
+) -> Vec<(OtpVersion, &'a HexBuild)> {
+    builds
+        .iter()
+        .filter_map(|build| {
+            let version = OtpVersion::parse(&build.version)?;
+            version
+                .matches(&build.version, version_filter)
+                .then_some((version, build))
+        })
+        .collect()
+}
+
+/// Runs the `Install` script shipped with the release, which writes the location of the
+/// tag directory into the `erl` start scripts.
+fn run_install_script(tag_dir: &Path) -> crate::Result<()> {
+    let tag_dir = std::path::absolute(tag_dir)?;
+    let output = std::process::Command::new(tag_dir.join("Install"))
+        .arg("-minimal")
+        .arg(&tag_dir)
+        .current_dir(&tag_dir)
+        .output()?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "The Install script failed with {}:\n{}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr)
+        )
+        .into());
+    }
+    Ok(())
+}
+
+fn ignore_lts_only(mut version_filter: VersionFilter) -> VersionFilter {
+    if version_filter.lts_only {
+        log::warn!(
+            "`--lts-only` is ignored for `erlang` because this tool does not define LTS releases."
+        );
+        version_filter.lts_only = false;
+    }
+    version_filter
+}
+
+/// A build listed in a builds.hex.pm `builds.txt`.
+#[derive(Debug, PartialEq)]
+pub(super) struct HexBuild {
+    /// The git ref the build was made from, which also names its archive.
+    pub(super) name: SmolStr,
+    /// The release version, derived from the name.
+    pub(super) version: SmolStr,
+    pub(super) sha256: Option<SmolStr>,
+}
+
+/// Parses the lines of a `builds.txt`, `<ref> <commit> <build time> [<sha256>]`. Builds that
+/// `version_of` returns `None` for, such as branch builds, are skipped.
+pub(super) fn parse_builds(
+    content: &str,
+    version_of: impl Fn(&str) -> Option<SmolStr>,
+) -> Vec<HexBuild> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let name = fields.next()?;
+            let version = version_of(name)?;
+            let sha256 = fields.nth(2).map(SmolStr::new);
+            Some(HexBuild {
+                name: name.into(),
+                version,
+                sha256,
+            })
+        })
+        .collect()
+}
+
+/// An Erlang/OTP version such as `26.2.5.3` or `27.0-rc1`. Unlike semver, releases may
+/// have a fourth number for patches of maintenance releases.
+#[derive(Debug, Clone)]
+pub struct OtpVersion {
+    numbers: Vec<u32>,
+    release_candidate: Option<u32>,
+}
+
+impl OtpVersion {
+    pub fn parse(raw: &str) -> Option<Self> {
+        let (numbers, release_candidate) = match raw.split_once("-rc") {
+            Some((numbers, rc)) => (numbers, Some(rc.parse().ok()?)),
+            None => (raw, None),
+        };
+        let numbers = numbers
+            .split('.')
+            .map(|n| n.parse().ok())
+            .collect::<Option<Vec<u32>>>()?;
+        Some(Self {
+            numbers,
+            release_candidate,
+        })
+    }
+
+    pub fn major(&self) -> u32 {
+        self.numbers[0]
+    }
+
+    fn number(&self, index: usize) -> u32 {
+        self.numbers.get(index).copied().unwrap_or(0)
+    }
+
+    fn matches(&self, raw: &str, version_filter: &VersionFilter) -> bool {
+        if !version_filter.includes_prerelease() && self.release_candidate.is_some() {
+            return false;
+        }
+        if !version_filter.matches_numbers(self.major(), self.number(1), self.number(2)) {
+            return false;
+        }
+        if version_filter
+            .exact_version
+            .as_ref()
+            .is_some_and(|exact| exact != raw)
+        {
+            return false;
+        }
+        true
+    }
+}
+
+impl PartialEq for OtpVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other).is_eq()
+    }
+}
+
+impl Eq for OtpVersion {}
+
+impl PartialOrd for OtpVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OtpVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let len = self.numbers.len().max(other.numbers.len());
+        (0..len)
+            .map(|i| self.number(i).cmp(&other.number(i)))
+            .find(|ordering| ordering.is_ne())
+            .unwrap_or(Ordering::Equal)
+            .then_with(|| match (self.release_candidate, other.release_candidate) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(a), Some(b)) => a.cmp(&b),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(prefix: Option<&str>, include_prerelease: bool) -> VersionFilter {
+        VersionFilter {
+            include_prerelease,
+            version_prefix: prefix.map(|p| crate::tool::VersionPrefix::parse(p).unwrap()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parses_builds_txt() {
+        let content = "\
+OTP-26.2.5.3 1d1fb5ba0e2f6d67e7a2e8c4b0bb2a8f7a3f4f7e 2024-09-03T10:20:30Z 0123abcd
+maint-26 8e2c2f7d3f4b5a6c7d8e9f0a1b2c3d4e5f6a7b8c 2024-09-04T10:20:30Z 4567ef01
+OTP-20.0 5a6b7c8d 2017-06-21T10:20:30Z
+";
+        let builds = parse_builds(content, |name| name.strip_prefix("OTP-").map(SmolStr::new));
+        assert_eq!(
+            builds,
+            [
+                HexBuild {
+                    name: "OTP-26.2.5.3".into(),
+                    version: "26.2.5.3".into(),
+                    sha256: Some("0123abcd".into()),
+                },
+                HexBuild {
+                    name: "OTP-20.0".into(),
+                    version: "20.0".into(),
+                    sha256: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn orders_versions() {
+        let parse = |raw| OtpVersion::parse(raw).unwrap();
+        assert!(parse("26.2.5.3") > parse("26.2.5"));
+        assert!(parse("26.2.5") > parse("26.2"));
+        assert_eq!(parse("26.2"), parse("26.2.0"));
+        assert!(parse("27.0") > parse("27.0-rc3"));
+        assert!(parse("27.0-rc3") > parse("27.0-rc1"));
+        assert!(parse("27.0-rc1") > parse("26.2.5.3"));
+        assert_eq!(parse("27.0-rc2").major(), 27);
+        assert!(OtpVersion::parse("maint").is_none());
+        assert!(OtpVersion::parse("27.0-rcx").is_none());
+    }
+
+    #[test]
+    fn filters_versions() {
+        let version = OtpVersion::parse("26.2.5.3").unwrap();
+        assert!(version.matches("26.2.5.3", &filter(None, false)));
+        assert!(version.matches("26.2.5.3", &filter(Some("26.2"), false)));
+        assert!(!version.matches("26.2.5.3", &filter(Some("26.1"), false)));
+
+        let rc = OtpVersion::parse("27.0-rc1").unwrap();
+        assert!(!rc.matches("27.0-rc1", &filter(Some("27"), false)));
+        assert!(rc.matches("27.0-rc1", &filter(Some("27"), true)));
+    }
+}
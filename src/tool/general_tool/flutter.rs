@@ -0,0 +1,267 @@
+use serde::Deserialize;
+use smol_str::SmolStr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use super::manifest::{best_local_tag, sorted_versions, ReleaseVersion};
+use super::terraform::ignore_lts_only;
+use crate::platform::{cpu, create_platform_string, os, resolve_default_platform};
+use crate::tool::{ToolDownInfo, ToolInfo, Version, VersionFilter};
+use crate::HttpClient;
+
+pub struct Tool {
+    client: Arc<HttpClient>,
+    info: ToolInfo,
+}
+
+/// Each OS has its own releases file, `releases_<os>.json`.
+const RELEASES_URL: &str = "https://storage.googleapis.com/flutter_infra_release/releases/";
+
+/// Platforms by the OS of the releases file and the `dart_sdk_arch` of the release.
+const PLATFORMS: [(&str, &str, &str, &str); 5] = [
+    (cpu::X64, os::LINUX, "linux", "x64"),
+    (cpu::ARM64, os::LINUX, "linux", "arm64"),
+    (cpu::X64, os::MAC, "macos", "x64"),
+    (cpu::ARM64, os::MAC, "macos", "arm64"),
+    (cpu::X64, os::WIN, "windows", "x64"),
+];
+
+/// The release channels, which are the flavors.
+const FLAVORS: [&str; 3] = ["stable", "beta", "dev"];
+const DEFAULT_FLAVOR: &str = "stable";
+
+impl crate::tool::GeneralTool for Tool {
+    fn info(&self) -> &ToolInfo {
+        &self.info
+    }
+
+    fn describe_flavor(&self, flavor: &str) -> &'static str {
+        match flavor {
+            "stable" => "Releases of the stable channel.",
+            "beta" => "Previews of the next stable release, published monthly.",
+            "dev" => "Releases of the retired dev channel, kept for old projects.",
+            _ => "Tool-specific build flavor.",
+        }
+    }
+
+    fn endpoints(&self) -> Vec<SmolStr> {
+        vec![smol_str::format_smolstr!(
+            "{RELEASES_URL}releases_linux.json"
+        )]
+    }
+
+    async fn fetch_versions(
+        &self,
+        platform: Option<SmolStr>,
+        flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<Vec<Version>> {
+        let platform = platform.ok_or(crate::AvmError::PlatformRequired)?;
+        let version_filter = ignore_lts_only(version_filter, "flutter");
+        let (dto_os, arch) = dto_os_arch(&platform)?;
+        let releases = self.fetch_releases(dto_os).await?;
+        Ok(sorted_versions(
+            releases
+                .matching(arch, channel(flavor.as_deref()), &version_filter)
+                .map(|(version, _)| version),
+        ))
+    }
+
+    async fn get_down_info(
+        &self,
+        platform: Option<SmolStr>,
+        flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<ToolDownInfo> {
+        let platform = platform.ok_or(crate::AvmError::PlatformRequired)?;
+        let version_filter = ignore_lts_only(version_filter, "flutter");
+        let (dto_os, arch) = dto_os_arch(&platform)?;
+        let releases = self.fetch_releases(dto_os).await?;
+        let channel = channel(flavor.as_deref());
+        let versions = sorted_versions(
+            releases
+                .matching(arch, channel, &version_filter)
+                .map(|(version, _)| version),
+        );
+        let version = versions.last().ok_or(crate::AvmError::VersionNotFound)?;
+        // Versions are listed without the `v` some old releases have.
+        let (_, release) = releases
+            .matching(arch, channel, &version_filter)
+            .find(|(_, release)| release.version.trim_start_matches('v') == version.version)
+            .ok_or(crate::AvmError::VersionNotFound)?;
+        Ok(ToolDownInfo {
+            version: version.clone(),
+            url: smol_str::format_smolstr!(
+                "{}/{}",
+                releases.base_url.trim_end_matches('/'),
+                release.archive
+            ),
+            hash: crate::FileHash {
+                sha256: Some(release.sha256.clone()),
+                ..Default::default()
+            },
+        })
+    }
+
+    fn find_best_matching_local_tag<'a, I>(
+        &self,
+        tags_and_versions: I,
+        version_filter: &VersionFilter,
+    ) -> Option<SmolStr>
+    where
+        I: Iterator<Item = (&'a str, &'a Version)>,
+    {
+        let version_filter = ignore_lts_only(version_filter.clone(), "flutter");
+        best_local_tag(tags_and_versions, &version_filter)
+    }
+
+    fn entry_path(&self, tag_dir: PathBuf) -> crate::Result<PathBuf> {
+        Ok(script_paths(tag_dir).swap_remove(0).1)
+    }
+
+    fn exe_paths(&self, tag_dir: PathBuf) -> crate::Result<Vec<(SmolStr, PathBuf)>> {
+        Ok(script_paths(tag_dir))
+    }
+}
+
+impl Tool {
+    pub fn new(client: Arc<HttpClient>, config_default_platform: Option<SmolStr>) -> Self {
+        let all_platforms = PLATFORMS
+            .iter()
+            .map(|(cpu, os, _, _)| create_platform_string(cpu, os))
+            .collect::<Vec<_>>();
+        let default_platform =
+            resolve_default_platform(&all_platforms, config_default_platform, &[]);
+        Tool {
+            client,
+            info: ToolInfo {
+                about: "Flutter SDK, with the Dart SDK it is built with".into(),
+                after_long_help: Some(
+                    r#"The flavor selects the release channel. The SDK ships both `flutter` and `dart`, run them with `avm run flutter --exe dart`.
+Flutter downloads its platform tools into the tag directory on first use."#
+                        .into(),
+                ),
+                all_platforms: Some(all_platforms),
+                default_platform,
+                all_flavors: Some(FLAVORS.iter().map(SmolStr::new).collect()),
+                default_flavor: Some(DEFAULT_FLAVOR.into()),
+            },
+        }
+    }
+
+    async fn fetch_releases(&self, dto_os: &str) -> anyhow::Result<ReleasesDto> {
+        Ok(self
+            .client
+            .get(&format!("{RELEASES_URL}releases_{dto_os}.json"))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ReleasesDto {
+    base_url: SmolStr,
+    releases: Vec<ReleaseDto>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReleaseDto {
+    channel: SmolStr,
+    version: SmolStr,
+    /// Absent in the releases before Apple Silicon builds, which are all x64.
+    dart_sdk_arch: Option<SmolStr>,
+    /// The path of the archive under `base_url`.
+    archive: SmolStr,
+    sha256: SmolStr,
+}
+
+impl ReleasesDto {
+    /// The releases of the channel built for `arch` that match the filter. Every release of the
+    /// selected channel counts as a release, the channel tells how stable it is.
+    fn matching<'a>(
+        &'a self,
+        arch: &'a str,
+        channel: &'a str,
+        version_filter: &'a VersionFilter,
+    ) -> impl Iterator<Item = (ReleaseVersion, &'a ReleaseDto)> + 'a {
+        self.releases.iter().filter_map(move |release| {
+            if release.channel != channel
+                || release.dart_sdk_arch.as_deref().unwrap_or("x64") != arch
+            {
+                return None;
+            }
+            let version = ReleaseVersion::parse(release.version.clone(), false, Some(false))?;
+            version
+                .matches(version_filter)
+                .then_some((version, release))
+        })
+    }
+}
+
+fn channel(flavor: Option<&str>) -> &str {
+    flavor.unwrap_or(DEFAULT_FLAVOR)
+}
+
+/// The OS of the releases file and the architecture of the platform.
+fn dto_os_arch(platform: &str) -> anyhow::Result<(&'static str, &'static str)> {
+    PLATFORMS
+        .iter()
+        .find(|(cpu, os, _, _)| create_platform_string(cpu, os) == platform)
+        .map(|(_, _, dto_os, dto_arch)| (*dto_os, *dto_arch))
+        .ok_or_else(|| anyhow::anyhow!("Unsupported platform: {platform}"))
+}
+
+fn script_paths(tag_dir: PathBuf) -> Vec<(SmolStr, PathBuf)> {
+    let bin_dir = tag_dir.join("bin");
+    let suffix = if cfg!(windows) { ".bat" } else { "" };
+    ["flutter", "dart"]
+        .into_iter()
+        .map(|name| (name.into(), bin_dir.join(format!("{name}{suffix}"))))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selects_releases_of_the_channel_and_arch() {
+        let releases: ReleasesDto = serde_json::from_str(
+            r#"{
+                "base_url": "https://storage.googleapis.com/flutter_infra_release/releases",
+                "releases": [
+                    {"channel": "beta", "version": "3.27.0-0.1.pre", "dart_sdk_arch": "arm64", "archive": "beta/macos/flutter_macos_arm64_3.27.0-0.1.pre-beta.zip", "sha256": "b1"},
+                    {"channel": "stable", "version": "3.24.3", "dart_sdk_arch": "arm64", "archive": "stable/macos/flutter_macos_arm64_3.24.3-stable.zip", "sha256": "s2"},
+                    {"channel": "stable", "version": "3.24.3", "dart_sdk_arch": "x64", "archive": "stable/macos/flutter_macos_3.24.3-stable.zip", "sha256": "s1"},
+                    {"channel": "stable", "version": "v1.12.13+hotfix.9", "archive": "stable/macos/flutter_macos_v1.12.13+hotfix.9-stable.zip", "sha256": "s0"}
+                ]
+            }"#,
+        )
+        .unwrap();
+        let filter = VersionFilter::default();
+        let versions = |arch, channel| {
+            releases
+                .matching(arch, channel, &filter)
+                .map(|(version, release)| (Version::from(version).version, release.sha256.clone()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            versions("x64", "stable"),
+            [
+                ("3.24.3".into(), "s1".into()),
+                ("1.12.13+hotfix.9".into(), "s0".into())
+            ]
+        );
+        assert_eq!(
+            versions("arm64", "stable"),
+            [("3.24.3".into(), "s2".into())]
+        );
+        assert_eq!(
+            versions("arm64", "beta"),
+            [("3.27.0-0.1.pre".into(), "b1".into())]
+        );
+    }
+}
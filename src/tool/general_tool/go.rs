@@ -1,12 +1,15 @@
 use rustc_hash::FxHashSet;
 use serde::Deserialize;
 use smol_str::SmolStr;
-use std::path::PathBuf;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use crate::HttpClient;
 use crate::{
-    platform::{cpu, create_platform_string, current_cpu, current_os, os},
+    io::{stream_json_array, JsonItemAction},
+    platform::{cpu, create_platform_string, os, resolve_default_platform, MUSL_TO_GLIBC},
+    tool::end_of_life::{EndOfLife, SupportStatus},
     tool::{ToolDownInfo, ToolInfo, Version, VersionFilter},
 };
 
@@ -14,44 +17,63 @@ pub struct Tool {
     client: Arc<HttpClient>,
     info: ToolInfo,
     corresponding_dto_cpu_os: Vec<(&'static str, &'static str)>,
+    /// Where each tag gets its own `GOPATH`, if isolated.
+    gopath_base: Option<PathBuf>,
+    end_of_life: EndOfLife,
 }
 
 const BASE_URL: &str = "https://golang.org/dl/";
+/// The directory under the data directory holding the isolated `GOPATH` of each tag.
+pub const GOPATH_DIR: &str = "gopath";
 
 impl crate::tool::GeneralTool for Tool {
     fn info(&self) -> &ToolInfo {
         &self.info
     }
 
+    fn endpoints(&self) -> Vec<SmolStr> {
+        vec![
+            smol_str::format_smolstr!("{BASE_URL}?mode=json"),
+            self.end_of_life.url(),
+        ]
+    }
+
+    async fn support_status(&self, version: &str) -> crate::Result<Option<SupportStatus>> {
+        self.end_of_life.support_status(version).await
+    }
+
+    fn release_notes_url(&self, version: &str) -> Option<SmolStr> {
+        Some(release_notes_url(version))
+    }
+
     async fn fetch_versions(
         &self,
         platform: Option<SmolStr>,
         _flavor: Option<SmolStr>,
         version_filter: VersionFilter,
-    ) -> anyhow::Result<Vec<Version>> {
-        let platform = platform.ok_or_else(|| anyhow::anyhow!("Platform is required"))?;
+    ) -> crate::Result<Vec<Version>> {
+        let platform = platform.ok_or(crate::AvmError::PlatformRequired)?;
         let (cpu, os) = self.get_dto_cpu_os(&platform)?;
         let version_filter = ignore_lts_only(version_filter);
         let version_filter = GoVersionFilter::try_from(&version_filter)?;
 
         let mut releases = self
-            .fetch_go_releases(&self.client)
-            .await?
-            .into_iter()
-            .filter_map(|r| {
+            .fetch_go_releases(&self.client, true, move |r| {
                 if !r.files.iter().any(|f| f.matches(cpu, os)) {
-                    return None;
+                    return JsonItemAction::Skip;
                 }
-                let (raw_version, version) = parse_go_version(&r.version)
+                let Ok((raw_version, version)) = parse_go_version(&r.version)
                     .map_err(|e| log::error!("Failed to parse Go version: {}", e))
-                    .ok()?;
+                else {
+                    return JsonItemAction::Skip;
+                };
                 if !version_filter.matches(raw_version, &version) {
-                    None
+                    JsonItemAction::Skip
                 } else {
-                    Some((version, SmolStr::from(raw_version)))
+                    version_filter.found((version, SmolStr::from(raw_version)))
                 }
             })
-            .collect::<Vec<_>>();
+            .await?;
         releases.sort_by(|a, b| a.0.cmp(&b.0));
         let mut versions = Vec::new();
         let mut version_set = FxHashSet::default();
@@ -73,29 +95,29 @@ impl crate::tool::GeneralTool for Tool {
         platform: Option<SmolStr>,
         _flavor: Option<SmolStr>,
         version_filter: VersionFilter,
-    ) -> anyhow::Result<ToolDownInfo> {
-        let platform = platform.ok_or_else(|| anyhow::anyhow!("Platform is required"))?;
+    ) -> crate::Result<ToolDownInfo> {
+        let platform = platform.ok_or(crate::AvmError::PlatformRequired)?;
         let (cpu, os) = self.get_dto_cpu_os(&platform)?;
 
         let version_filter = ignore_lts_only(version_filter);
         let version_filter = GoVersionFilter::try_from(&version_filter)?;
 
-        let release = self
-            .fetch_go_releases(&self.client)
-            .await?
-            .into_iter()
-            .filter_map(|r| {
-                let item = r.files.into_iter().find(|f| f.matches(cpu, os))?;
-                let (raw_version, version) = parse_go_version(&r.version)
-                    .map_err(|e| log::error!("Failed to parse Go version: {}", e))
-                    .ok()?;
-                if !version_filter.matches(raw_version, &version) {
-                    None
-                } else {
-                    Some((version, SmolStr::from(raw_version), item))
-                }
-            })
-            .max_by(|a, b| a.0.cmp(&b.0));
+        let mut releases = Vec::new();
+        if version_filter.supported_releases_suffice() {
+            releases = self
+                .fetch_go_releases(
+                    &self.client,
+                    false,
+                    select_download(cpu, os, version_filter.clone()),
+                )
+                .await?;
+        }
+        if releases.is_empty() {
+            releases = self
+                .fetch_go_releases(&self.client, true, select_download(cpu, os, version_filter))
+                .await?;
+        }
+        let release = releases.into_iter().max_by(|a, b| a.0.cmp(&b.0));
         if let Some((_, raw_version, item)) = release {
             Ok(ToolDownInfo {
                 version: Version {
@@ -109,7 +131,7 @@ impl crate::tool::GeneralTool for Tool {
                 },
             })
         } else {
-            Err(anyhow::anyhow!("No download URL found."))
+            Err(crate::AvmError::VersionNotFound)
         }
     }
 
@@ -136,7 +158,7 @@ impl crate::tool::GeneralTool for Tool {
             .map(|(_, tag)| tag)
     }
 
-    fn entry_path(&self, tag_dir: PathBuf) -> anyhow::Result<PathBuf> {
+    fn entry_path(&self, tag_dir: PathBuf) -> crate::Result<PathBuf> {
         let mut p = tag_dir;
         p.push("bin");
         #[cfg(windows)]
@@ -145,6 +167,24 @@ impl crate::tool::GeneralTool for Tool {
         p.push("go");
         Ok(p)
     }
+
+    fn exe_paths(&self, tag_dir: PathBuf) -> crate::Result<Vec<(SmolStr, PathBuf)>> {
+        Ok(super::exes_in(&tag_dir.join("bin"), &["go", "gofmt"]))
+    }
+
+    fn env_vars(&self, tag_dir: &Path) -> Vec<(OsString, OsString)> {
+        let mut vars = vec![("GOROOT".into(), tag_dir.into())];
+        if let Some(gopath_base) = &self.gopath_base {
+            // An alias shares the `GOPATH` of its target.
+            let tag_dir = std::fs::canonicalize(tag_dir).unwrap_or_else(|_| tag_dir.to_path_buf());
+            if let Some(tag) = tag_dir.file_name() {
+                let gopath = gopath_base.join(tag);
+                vars.push(("GOBIN".into(), gopath.join("bin").into()));
+                vars.push(("GOPATH".into(), gopath.into()));
+            }
+        }
+        vars
+    }
 }
 
 impl Tool {
@@ -152,16 +192,11 @@ impl Tool {
         let (all_platforms, corresponding_dto_cpu_os) =
             Self::get_platforms_and_corresponding_dto_cpu_os();
 
-        let default_platform = config_default_platform
-            .and_then(|p| all_platforms.iter().find(|&k| p == *k).cloned())
-            .or_else(|| {
-                current_cpu().and_then(|cpu| {
-                    let os = current_os()?;
-                    let p = create_platform_string(cpu, os);
-                    all_platforms.iter().find(|&k| p == *k).cloned()
-                })
-            });
+        // Go builds are statically linked, the Linux ones also run on musl.
+        let default_platform =
+            resolve_default_platform(&all_platforms, config_default_platform, &[MUSL_TO_GLIBC]);
 
+        let end_of_life = EndOfLife::new(client.clone(), "go");
         Tool {
             client,
             info: ToolInfo {
@@ -173,6 +208,17 @@ impl Tool {
                 default_flavor: None,
             },
             corresponding_dto_cpu_os,
+            gopath_base: None,
+            end_of_life,
+        }
+    }
+
+    /// Gives each tag its own `GOPATH` under [`GOPATH_DIR`] in `data_dir`, so that Go versions
+    /// don't share their module cache and installed commands.
+    pub fn with_isolated_gopath(self, data_dir: &Path) -> Self {
+        Self {
+            gopath_base: Some(data_dir.join(GOPATH_DIR)),
+            ..self
         }
     }
 
@@ -274,19 +320,45 @@ impl Tool {
             .ok_or_else(|| anyhow::anyhow!("Missing Go platform mapping for: {platform}"))
     }
 
-    async fn fetch_go_releases(&self, client: &HttpClient) -> reqwest::Result<Vec<ReleaseDto>> {
+    /// Without `include_all`, the server only lists the latest release of each supported
+    /// minor version, which is much smaller than the full listing.
+    async fn fetch_go_releases<U: Send + 'static>(
+        &self,
+        client: &HttpClient,
+        include_all: bool,
+        filter_map: impl FnMut(ReleaseDto) -> JsonItemAction<U> + Send + 'static,
+    ) -> anyhow::Result<Vec<U>> {
         let mut url = reqwest::Url::parse(BASE_URL).expect("BASE_URL should be a valid URL"); // BASE_URL is a constant that should be defined as a valid Url.
-        url.query_pairs_mut()
-            .append_pair("mode", "json")
-            .append_pair("include", "all");
-
-        client
-            .get(url.as_str())
-            .send()
-            .await?
-            .error_for_status()?
-            .json()
-            .await
+        url.query_pairs_mut().append_pair("mode", "json");
+        if include_all {
+            url.query_pairs_mut().append_pair("include", "all");
+        }
+
+        let response = client.get(url.as_str()).send().await?.error_for_status()?;
+        stream_json_array(response, filter_map).await
+    }
+}
+
+fn select_download(
+    cpu: &'static str,
+    os: &'static str,
+    version_filter: GoVersionFilter,
+) -> impl FnMut(ReleaseDto) -> JsonItemAction<(GoVersion, SmolStr, ReleaseFileDto)> + Send + 'static
+{
+    move |r| {
+        let Some(item) = r.files.into_iter().find(|f| f.matches(cpu, os)) else {
+            return JsonItemAction::Skip;
+        };
+        let Ok((raw_version, version)) = parse_go_version(&r.version)
+            .map_err(|e| log::error!("Failed to parse Go version: {}", e))
+        else {
+            return JsonItemAction::Skip;
+        };
+        if !version_filter.matches(raw_version, &version) {
+            JsonItemAction::Skip
+        } else {
+            version_filter.found((version, SmolStr::from(raw_version), item))
+        }
     }
 }
 
@@ -332,25 +404,34 @@ pub struct GoVersion {
     pre_release: PreRelease,
 }
 
+#[derive(Clone)]
 struct GoVersionFilter {
-    allow_prerelease: bool,
+    include_prerelease: bool,
     version_prefix: Option<crate::tool::VersionPrefix>,
+    version_range: Option<crate::tool::VersionRange>,
     exact_version: Option<SmolStr>,
 }
 
 impl GoVersionFilter {
     fn matches(&self, raw_version: &str, version: &GoVersion) -> bool {
         let Self {
-            allow_prerelease,
+            include_prerelease,
             version_prefix,
+            version_range,
             exact_version,
         } = self;
-        if !*allow_prerelease && version.pre_release != PreRelease::None {
+        if !*include_prerelease && version.pre_release != PreRelease::None {
             return false;
         }
         if version_prefix.is_some_and(|p| !p.matches(version.major, version.minor, version.patch)) {
             return false;
         }
+        if version_range
+            .as_ref()
+            .is_some_and(|r| !r.matches(version.major, version.minor, version.patch))
+        {
+            return false;
+        }
         if let Some(exact_version) = exact_version {
             if exact_version != raw_version {
                 return false;
@@ -358,6 +439,22 @@ impl GoVersionFilter {
         }
         true
     }
+
+    /// Whether a match among the latest supported releases is also the best match in the full
+    /// listing. Those are the newest stable patches of their minor versions, so this holds as
+    /// long as prereleases, which the short listing omits, are not allowed.
+    fn supported_releases_suffice(&self) -> bool {
+        !self.include_prerelease
+    }
+
+    /// Keeps a matching release, stopping the listing parsing if no other release can match.
+    fn found<U>(&self, item: U) -> JsonItemAction<U> {
+        if self.exact_version.is_some() {
+            JsonItemAction::KeepAndStop(item)
+        } else {
+            JsonItemAction::Keep(item)
+        }
+    }
 }
 
 impl TryFrom<&VersionFilter> for GoVersionFilter {
@@ -365,13 +462,34 @@ impl TryFrom<&VersionFilter> for GoVersionFilter {
 
     fn try_from(value: &VersionFilter) -> Result<Self, Self::Error> {
         Ok(Self {
-            allow_prerelease: value.allow_prerelease,
+            include_prerelease: value.includes_prerelease(),
             version_prefix: value.version_prefix,
+            version_range: value.version_range.clone(),
             exact_version: value.exact_version.clone(),
         })
     }
 }
 
+/// The release notes of the series, like `go.dev/doc/go1.22`, for its first release and its
+/// prereleases, and the entry in the release history for the minor revisions.
+fn release_notes_url(version: &str) -> SmolStr {
+    let series = version
+        .find(|c: char| c.is_ascii_alphabetic())
+        .map_or(version, |index| &version[..index]);
+    let mut parts = series.splitn(3, '.');
+    let major_minor = [parts.next(), parts.next()]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join(".");
+    match parts.next() {
+        Some(patch) if patch != "0" && series == version => {
+            smol_str::format_smolstr!("https://go.dev/doc/devel/release#go{version}")
+        }
+        _ => smol_str::format_smolstr!("https://go.dev/doc/go{major_minor}"),
+    }
+}
+
 fn ignore_lts_only(mut version_filter: VersionFilter) -> VersionFilter {
     if version_filter.lts_only {
         log::warn!(
@@ -827,13 +945,58 @@ mod tests {
     fn version_filter_ignores_lts_only() {
         let filter = GoVersionFilter::try_from(&VersionFilter {
             lts_only: true,
-            allow_prerelease: false,
-            version_prefix: None,
-            exact_version: None,
+            ..Default::default()
         })
         .unwrap();
         let (_, version) = parse_go_version("go1.24.1").unwrap();
 
         assert!(filter.matches("1.24.1", &version));
     }
+
+    #[test]
+    fn release_notes_of_series_and_revisions() {
+        assert_eq!(release_notes_url("1.22.0"), "https://go.dev/doc/go1.22");
+        assert_eq!(release_notes_url("1.20"), "https://go.dev/doc/go1.20");
+        assert_eq!(release_notes_url("1.23rc2"), "https://go.dev/doc/go1.23");
+        assert_eq!(
+            release_notes_url("1.22.3"),
+            "https://go.dev/doc/devel/release#go1.22.3"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn isolated_gopath_follows_aliases() {
+        use crate::tool::GeneralTool;
+
+        let dir = tempfile::tempdir().unwrap();
+        let go_dir = dir.path().join("tools").join("go");
+        std::fs::create_dir_all(go_dir.join("x64-linux_1.23.4")).unwrap();
+        std::os::unix::fs::symlink(go_dir.join("x64-linux_1.23.4"), go_dir.join("default"))
+            .unwrap();
+        let client = Arc::new(
+            HttpClient::new(Default::default(), Default::default(), Default::default()).unwrap(),
+        );
+
+        let env_vars = |tool: &Tool| {
+            tool.env_vars(&go_dir.join("default"))
+                .into_iter()
+                .map(|(name, value)| (name.into_string().unwrap(), PathBuf::from(value)))
+                .collect::<Vec<_>>()
+        };
+        let tool = Tool::new(client, None);
+        assert_eq!(
+            env_vars(&tool),
+            [("GOROOT".to_owned(), go_dir.join("default"))]
+        );
+        let tool = tool.with_isolated_gopath(dir.path());
+        let gopath = dir.path().join(GOPATH_DIR).join("x64-linux_1.23.4");
+        assert_eq!(
+            env_vars(&tool)[1..],
+            [
+                ("GOBIN".to_owned(), gopath.join("bin")),
+                ("GOPATH".to_owned(), gopath),
+            ]
+        );
+    }
 }
@@ -0,0 +1,294 @@
+//! GraalVM Community Edition builds for JDK 17 and later, published as GitHub release assets.
+
+use rustc_hash::FxHashMap;
+use smol_str::SmolStr;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use super::liberica::JDK_EXES;
+use super::manifest::{best_local_tag, resolve_default_platform, sorted_versions, ReleaseVersion};
+use super::temurin::java_home;
+use crate::platform::{cpu, create_platform_string, os};
+use crate::tool::github_releases::{self, AssetMatch, AssetMatcher, ReleaseDto};
+use crate::tool::{ToolDownInfo, ToolInfo, Version, VersionFilter};
+use crate::HttpClient;
+
+pub struct Tool {
+    client: Arc<HttpClient>,
+    info: ToolInfo,
+    assets: AssetMatcher,
+}
+
+/// Each release, tagged like `jdk-21.0.2`, has `graalvm-community-jdk-<version>_<os>-<arch>_bin`
+/// archives and their `.sha256` files.
+const REPO: &str = "graalvm/graalvm-ce-builds";
+
+const PLATFORMS: [(&str, &str, &str); 5] = [
+    (cpu::X64, os::LINUX, "linux-x64_bin.tar.gz"),
+    (cpu::ARM64, os::LINUX, "linux-aarch64_bin.tar.gz"),
+    (cpu::X64, os::MAC, "macos-x64_bin.tar.gz"),
+    (cpu::ARM64, os::MAC, "macos-aarch64_bin.tar.gz"),
+    (cpu::X64, os::WIN, "windows-x64_bin.zip"),
+];
+
+impl crate::tool::GeneralTool for Tool {
+    fn info(&self) -> &ToolInfo {
+        &self.info
+    }
+
+    fn endpoints(&self) -> Vec<SmolStr> {
+        vec![github_releases::releases_url(REPO)]
+    }
+
+    async fn fetch_versions(
+        &self,
+        platform: Option<SmolStr>,
+        _flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<Vec<Version>> {
+        let platform = platform.ok_or(crate::AvmError::PlatformRequired)?;
+        let builds = self.fetch_builds(&platform).await?;
+        Ok(sorted_versions(builds.into_iter().filter_map(|build| {
+            build.release_version(&version_filter)
+        })))
+    }
+
+    async fn get_down_info(
+        &self,
+        platform: Option<SmolStr>,
+        _flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<ToolDownInfo> {
+        let platform = platform.ok_or(crate::AvmError::PlatformRequired)?;
+        let builds = self.fetch_builds(&platform).await?;
+        let versions = sorted_versions(
+            builds
+                .iter()
+                .filter_map(|build| build.release_version(&version_filter)),
+        );
+        let version = versions.last().ok_or(crate::AvmError::VersionNotFound)?;
+        let build = builds
+            .into_iter()
+            .find(|build| build.version == version.version)
+            .ok_or(crate::AvmError::VersionNotFound)?;
+        let sha256 = match (build.sha256, build.sha256_url) {
+            (Some(sha256), _) => Some(sha256),
+            (None, Some(sha256_url)) => Some(self.fetch_sha256(&sha256_url).await?),
+            (None, None) => None,
+        };
+        Ok(ToolDownInfo {
+            version: version.clone(),
+            url: build.url,
+            hash: crate::FileHash {
+                sha256,
+                ..Default::default()
+            },
+        })
+    }
+
+    fn find_best_matching_local_tag<'a, I>(
+        &self,
+        tags_and_versions: I,
+        version_filter: &VersionFilter,
+    ) -> Option<SmolStr>
+    where
+        I: Iterator<Item = (&'a str, &'a Version)>,
+    {
+        best_local_tag(tags_and_versions, version_filter)
+    }
+
+    fn entry_path(&self, tag_dir: PathBuf) -> crate::Result<PathBuf> {
+        Ok(super::exes_in(&java_home(tag_dir).join("bin"), &["java"])
+            .swap_remove(0)
+            .1)
+    }
+
+    fn exe_paths(&self, tag_dir: PathBuf) -> crate::Result<Vec<(SmolStr, PathBuf)>> {
+        let bin_dir = java_home(tag_dir).join("bin");
+        let mut exes = super::exes_in(&bin_dir, JDK_EXES);
+        if let Some(native_image) = native_image_path(&bin_dir) {
+            exes.push(("native-image".into(), native_image));
+        }
+        Ok(exes)
+    }
+
+    fn env_vars(&self, tag_dir: &Path) -> Vec<(OsString, OsString)> {
+        let java_home = java_home(tag_dir.to_path_buf());
+        vec![
+            ("JAVA_HOME".into(), java_home.clone().into()),
+            ("GRAALVM_HOME".into(), java_home.into()),
+        ]
+    }
+
+    fn post_install(&self) -> Option<fn(&Path) -> crate::Result<()>> {
+        Some(|tag_dir| {
+            if native_image_path(&java_home(tag_dir.to_path_buf()).join("bin")).is_none() {
+                log::warn!(
+                    "This GraalVM build does not include `native-image`, it is only available as `java` and the JDK tools."
+                );
+            }
+            Ok(())
+        })
+    }
+}
+
+impl Tool {
+    pub fn new(client: Arc<HttpClient>, config_default_platform: Option<SmolStr>) -> Self {
+        let all_platforms: Vec<SmolStr> = PLATFORMS
+            .iter()
+            .map(|(cpu, os, _)| create_platform_string(cpu, os))
+            .collect();
+        let default_platform = resolve_default_platform(&all_platforms, config_default_platform);
+
+        Tool {
+            client,
+            info: ToolInfo {
+                about: "GraalVM Community Edition JDK with native-image".into(),
+                after_long_help: Some(
+                    r#"Versions are the JDK versions of the builds, select a JDK major with `--verpfx 21`. JDK 17, 21 and 25 are LTS.
+The builds include `native-image`, run with `avm run graalvm --exe native-image`. Older GraalVM 22.x releases, which installed it separately, are not listed."#
+                        .into(),
+                ),
+                all_platforms: Some(all_platforms),
+                default_platform,
+                all_flavors: None,
+                default_flavor: None,
+            },
+            assets: AssetMatcher::new(PLATFORMS.iter().map(|(cpu, os, suffix)| {
+                (
+                    Some(create_platform_string(cpu, os)),
+                    format!(
+                        r"graalvm-community-jdk-(?<version>\d+(?:\.\d+)*)_{}",
+                        regex::escape(suffix)
+                    ),
+                )
+            })),
+        }
+    }
+
+    async fn fetch_builds(&self, platform: &str) -> anyhow::Result<Vec<Build>> {
+        let releases = github_releases::fetch_releases(&self.client, REPO).await?;
+        builds(&self.assets, platform, &releases)
+    }
+
+    /// The digest in a `.sha256` file, for assets uploaded before GitHub computed them.
+    async fn fetch_sha256(&self, url: &str) -> anyhow::Result<SmolStr> {
+        let content = self
+            .client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        content
+            .split_whitespace()
+            .next()
+            .map(SmolStr::new)
+            .ok_or_else(|| anyhow::anyhow!("Empty checksum file {url}"))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Build {
+    version: SmolStr,
+    url: SmolStr,
+    sha256: Option<SmolStr>,
+    sha256_url: Option<SmolStr>,
+}
+
+impl Build {
+    fn from_match(build: &AssetMatch<'_>, checksums: &FxHashMap<&str, &SmolStr>) -> Self {
+        let checksum_name = format!("{}.sha256", build.asset.name);
+        Self {
+            version: build.version().into(),
+            url: build.asset.browser_download_url.clone(),
+            sha256: build.asset.sha256(),
+            sha256_url: checksums
+                .get(checksum_name.as_str())
+                .map(|&url| url.clone()),
+        }
+    }
+
+    fn release_version(&self, version_filter: &VersionFilter) -> Option<ReleaseVersion> {
+        let major = self.version.split('.').next()?.parse::<u32>().ok()?;
+        let version = ReleaseVersion::parse(self.version.clone(), is_lts(major), None)?;
+        version.matches(version_filter).then_some(version)
+    }
+}
+
+fn builds(
+    assets: &AssetMatcher,
+    platform: &str,
+    releases: &[ReleaseDto],
+) -> anyhow::Result<Vec<Build>> {
+    let checksums = releases
+        .iter()
+        .flat_map(|release| &release.assets)
+        .filter(|asset| asset.name.ends_with(".sha256"))
+        .map(|asset| (asset.name.as_str(), &asset.browser_download_url))
+        .collect::<FxHashMap<_, _>>();
+    Ok(assets
+        .matching(Some(platform), releases)?
+        .iter()
+        .map(|build| Build::from_match(build, &checksums))
+        .collect())
+}
+
+/// JDK 17 and every fourth release after it are LTS.
+fn is_lts(major: u32) -> bool {
+    major >= 17 && (major - 17).is_multiple_of(4)
+}
+
+/// `native-image` is a launcher script on Windows.
+fn native_image_path(bin_dir: &Path) -> Option<PathBuf> {
+    let suffix = if cfg!(windows) { ".cmd" } else { "" };
+    let path = bin_dir.join(format!("native-image{suffix}"));
+    path.exists().then_some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picks_platform_builds_with_checksums() {
+        let releases: Vec<ReleaseDto> = serde_json::from_str(
+            r#"[{"assets": [
+                {"name": "graalvm-community-jdk-21.0.2_linux-x64_bin.tar.gz", "browser_download_url": "https://example.com/linux-x64.tar.gz"},
+                {"name": "graalvm-community-jdk-21.0.2_linux-x64_bin.tar.gz.sha256", "browser_download_url": "https://example.com/linux-x64.tar.gz.sha256"},
+                {"name": "graalvm-community-jdk-21.0.2_windows-x64_bin.zip", "browser_download_url": "https://example.com/windows-x64.zip", "digest": "sha256:ab"}
+            ]}, {"assets": [
+                {"name": "graalvm-ce-java17-linux-amd64-22.3.3.tar.gz", "browser_download_url": "https://example.com/22.3.3.tar.gz"}
+            ]}]"#,
+        )
+        .unwrap();
+        let tool = Tool::new(
+            Arc::new(
+                HttpClient::new(Default::default(), Default::default(), Default::default())
+                    .unwrap(),
+            ),
+            None,
+        );
+        assert_eq!(
+            builds(&tool.assets, "x64-linux", &releases).unwrap(),
+            [Build {
+                version: "21.0.2".into(),
+                url: "https://example.com/linux-x64.tar.gz".into(),
+                sha256: None,
+                sha256_url: Some("https://example.com/linux-x64.tar.gz.sha256".into()),
+            }]
+        );
+        assert_eq!(
+            builds(&tool.assets, "x64-win", &releases).unwrap(),
+            [Build {
+                version: "21.0.2".into(),
+                url: "https://example.com/windows-x64.zip".into(),
+                sha256: Some("ab".into()),
+                sha256_url: None,
+            }]
+        );
+        assert!(is_lts(21) && is_lts(25) && !is_lts(23) && !is_lts(11));
+    }
+}
@@ -0,0 +1,217 @@
+use serde::Deserialize;
+use smol_str::SmolStr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use super::manifest::{best_local_tag, sorted_versions, ReleaseVersion};
+use crate::tool::{ToolDownInfo, ToolInfo, Version, VersionFilter};
+use crate::HttpClient;
+
+pub struct Tool {
+    client: Arc<HttpClient>,
+    info: ToolInfo,
+}
+
+const VERSIONS_URL: &str = "https://services.gradle.org/versions/all";
+const DISTRIBUTIONS_URL: &str = "https://services.gradle.org/distributions/";
+const FLAVORS: [&str; 2] = ["bin", "all"];
+
+impl crate::tool::GeneralTool for Tool {
+    fn info(&self) -> &ToolInfo {
+        &self.info
+    }
+
+    fn describe_flavor(&self, flavor: &str) -> &'static str {
+        match flavor {
+            "bin" => "Binaries only.",
+            "all" => {
+                "Binaries with sources and documentation, for IDE assistance in build scripts."
+            }
+            _ => "Tool-specific build flavor.",
+        }
+    }
+
+    fn endpoints(&self) -> Vec<SmolStr> {
+        vec![VERSIONS_URL.into()]
+    }
+
+    async fn fetch_versions(
+        &self,
+        _platform: Option<SmolStr>,
+        _flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<Vec<Version>> {
+        let version_filter = ignore_lts_only(version_filter);
+        let releases = self.fetch_releases().await?;
+        Ok(sorted_versions(releases.into_iter().filter_map(
+            |release| release.release_version(&version_filter),
+        )))
+    }
+
+    async fn get_down_info(
+        &self,
+        _platform: Option<SmolStr>,
+        flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<ToolDownInfo> {
+        let version_filter = ignore_lts_only(version_filter);
+        let releases = self.fetch_releases().await?;
+        let versions = sorted_versions(
+            releases
+                .into_iter()
+                .filter_map(|release| release.release_version(&version_filter)),
+        );
+        let version = versions.last().ok_or(crate::AvmError::VersionNotFound)?;
+
+        let file_name = format!(
+            "gradle-{}-{}.zip",
+            version.version,
+            flavor.as_deref().unwrap_or(FLAVORS[0])
+        );
+        let url = smol_str::format_smolstr!("{DISTRIBUTIONS_URL}{file_name}");
+        let sha256 = self
+            .client
+            .get(&format!("{url}.sha256"))
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        Ok(ToolDownInfo {
+            version: version.clone(),
+            url,
+            hash: crate::FileHash {
+                sha256: Some(sha256.trim().into()),
+                ..Default::default()
+            },
+        })
+    }
+
+    fn find_best_matching_local_tag<'a, I>(
+        &self,
+        tags_and_versions: I,
+        version_filter: &VersionFilter,
+    ) -> Option<SmolStr>
+    where
+        I: Iterator<Item = (&'a str, &'a Version)>,
+    {
+        best_local_tag(tags_and_versions, &ignore_lts_only(version_filter.clone()))
+    }
+
+    fn entry_path(&self, tag_dir: PathBuf) -> crate::Result<PathBuf> {
+        let name = if cfg!(windows) {
+            "gradle.bat"
+        } else {
+            "gradle"
+        };
+        Ok(tag_dir.join("bin").join(name))
+    }
+}
+
+impl Tool {
+    pub fn new(client: Arc<HttpClient>) -> Self {
+        Tool {
+            client,
+            info: ToolInfo {
+                about: "Gradle build tool".into(),
+                after_long_help: Some(
+                    "Gradle runs on the JVM and needs a JDK, such as one installed with the `liberica` tool."
+                        .into(),
+                ),
+                all_platforms: None,
+                default_platform: None,
+                all_flavors: Some(FLAVORS.iter().map(SmolStr::new).collect()),
+                default_flavor: Some(FLAVORS[0].into()),
+            },
+        }
+    }
+
+    async fn fetch_releases(&self) -> anyhow::Result<Vec<ReleaseDto>> {
+        let releases: Vec<ReleaseDto> = self
+            .client
+            .get(VERSIONS_URL)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(releases)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ReleaseDto {
+    version: SmolStr,
+    #[serde(default)]
+    snapshot: bool,
+    #[serde(default)]
+    nightly: bool,
+    #[serde(default)]
+    release_nightly: bool,
+    #[serde(default)]
+    broken: bool,
+    /// The release a release candidate is for, empty for other releases.
+    #[serde(default)]
+    rc_for: SmolStr,
+    /// The release a milestone is for, empty for other releases.
+    #[serde(default)]
+    milestone_for: SmolStr,
+}
+
+impl ReleaseDto {
+    /// `None` for builds that are not published releases and for broken releases.
+    fn release_version(&self, version_filter: &VersionFilter) -> Option<ReleaseVersion> {
+        if self.snapshot || self.nightly || self.release_nightly || self.broken {
+            return None;
+        }
+        let prerelease = !self.rc_for.is_empty() || !self.milestone_for.is_empty();
+        let version = ReleaseVersion::parse(self.version.clone(), false, Some(prerelease))?;
+        version.matches(version_filter).then_some(version)
+    }
+}
+
+fn ignore_lts_only(mut version_filter: VersionFilter) -> VersionFilter {
+    if version_filter.lts_only {
+        log::warn!(
+            "`--lts-only` is ignored for `gradle` because this tool does not define LTS releases."
+        );
+        version_filter.lts_only = false;
+    }
+    version_filter
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skips_unpublished_and_broken_builds() {
+        let releases: Vec<ReleaseDto> = serde_json::from_str(
+            r#"[
+                {"version": "8.12-20241105002153+0000", "nightly": true},
+                {"version": "8.11-rc-2", "rcFor": "8.11"},
+                {"version": "8.10.2"},
+                {"version": "8.10.1", "broken": true},
+                {"version": "7.0-milestone-3", "milestoneFor": "7.0"}
+            ]"#,
+        )
+        .unwrap();
+        let filter = |include_prerelease| VersionFilter {
+            include_prerelease,
+            ..Default::default()
+        };
+        let versions = |include_prerelease| {
+            sorted_versions(
+                releases
+                    .iter()
+                    .filter_map(|release| release.release_version(&filter(include_prerelease))),
+            )
+            .into_iter()
+            .map(|version| version.version)
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(versions(false), ["8.10.2"]);
+        assert_eq!(versions(true), ["7.0-milestone-3", "8.10.2", "8.11-rc-2"]);
+    }
+}
@@ -0,0 +1,271 @@
+use serde::Deserialize;
+use smol_str::SmolStr;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use super::manifest::{best_local_tag, sorted_versions, ReleaseVersion};
+use crate::platform::{cpu, create_platform_string, os, resolve_default_platform};
+use crate::tool::{ToolDownInfo, ToolInfo, Version, VersionFilter};
+use crate::HttpClient;
+
+pub struct Tool {
+    client: Arc<HttpClient>,
+    info: ToolInfo,
+}
+
+const VERSIONS_URL: &str = "https://julialang-s3.julialang.org/bin/versions.json";
+
+/// Platforms by the `os` and `arch` of the files in `versions.json`, and whether the build is
+/// the musl one, which is told by the triplet only.
+const PLATFORMS: [(&str, &str, &str, &str, bool); 11] = [
+    (cpu::X64, os::LINUX, "linux", "x86_64", false),
+    (cpu::X86, os::LINUX, "linux", "i686", false),
+    (cpu::ARM64, os::LINUX, "linux", "aarch64", false),
+    (cpu::ARMV7L, os::LINUX, "linux", "armv7l", false),
+    (cpu::PPC64LE, os::LINUX, "linux", "powerpc64le", false),
+    (cpu::X64, os::LINUX_MUSL, "linux", "x86_64", true),
+    (cpu::X64, os::MAC, "mac", "x86_64", false),
+    (cpu::ARM64, os::MAC, "mac", "aarch64", false),
+    (cpu::X64, os::WIN, "winnt", "x86_64", false),
+    (cpu::X86, os::WIN, "winnt", "i686", false),
+    (cpu::X64, os::FREEBSD, "freebsd", "x86_64", false),
+];
+
+/// The minor series that are or were the long-term support release. `versions.json` doesn't
+/// tell them, they are announced on julialang.org/downloads.
+const LTS_SERIES: [(u32, u32); 3] = [(1, 0), (1, 6), (1, 10)];
+
+impl crate::tool::GeneralTool for Tool {
+    fn info(&self) -> &ToolInfo {
+        &self.info
+    }
+
+    fn endpoints(&self) -> Vec<SmolStr> {
+        vec![VERSIONS_URL.into()]
+    }
+
+    async fn fetch_versions(
+        &self,
+        platform: Option<SmolStr>,
+        _flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<Vec<Version>> {
+        let platform = platform.ok_or(crate::AvmError::PlatformRequired)?;
+        let target = Target::new(&platform)?;
+        let versions = self.fetch_versions_dto().await?;
+        Ok(sorted_versions(
+            matching_files(&versions, &target, &version_filter).map(|(version, _)| version),
+        ))
+    }
+
+    async fn get_down_info(
+        &self,
+        platform: Option<SmolStr>,
+        _flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<ToolDownInfo> {
+        let platform = platform.ok_or(crate::AvmError::PlatformRequired)?;
+        let target = Target::new(&platform)?;
+        let versions = self.fetch_versions_dto().await?;
+        let sorted = sorted_versions(
+            matching_files(&versions, &target, &version_filter).map(|(version, _)| version),
+        );
+        let version = sorted.last().ok_or(crate::AvmError::VersionNotFound)?;
+        // The keys of `versions.json` are the versions as listed, without a `v`.
+        let file = versions
+            .get(&version.version)
+            .and_then(|release| release.files.iter().find(|file| target.matches(file)))
+            .ok_or(crate::AvmError::VersionNotFound)?;
+        Ok(ToolDownInfo {
+            version: version.clone(),
+            url: file.url.clone(),
+            hash: crate::FileHash {
+                sha256: Some(file.sha256.clone()),
+                ..Default::default()
+            },
+        })
+    }
+
+    fn find_best_matching_local_tag<'a, I>(
+        &self,
+        tags_and_versions: I,
+        version_filter: &VersionFilter,
+    ) -> Option<SmolStr>
+    where
+        I: Iterator<Item = (&'a str, &'a Version)>,
+    {
+        best_local_tag(tags_and_versions, version_filter)
+    }
+
+    fn entry_path(&self, tag_dir: PathBuf) -> crate::Result<PathBuf> {
+        Ok(self.exe_paths(tag_dir)?.swap_remove(0).1)
+    }
+
+    fn exe_paths(&self, tag_dir: PathBuf) -> crate::Result<Vec<(SmolStr, PathBuf)>> {
+        Ok(super::exes_in(&tag_dir.join("bin"), &["julia"]))
+    }
+}
+
+impl Tool {
+    pub fn new(client: Arc<HttpClient>, config_default_platform: Option<SmolStr>) -> Self {
+        let all_platforms = PLATFORMS
+            .iter()
+            .map(|(cpu, os, _, _, _)| create_platform_string(cpu, os))
+            .collect::<Vec<_>>();
+        let default_platform =
+            resolve_default_platform(&all_platforms, config_default_platform, &[]);
+        Tool {
+            client,
+            info: ToolInfo {
+                about: "Julia programming language".into(),
+                after_long_help: Some(
+                    "`--lts` selects the releases of the long-term support series, currently 1.10."
+                        .into(),
+                ),
+                all_platforms: Some(all_platforms),
+                default_platform,
+                all_flavors: None,
+                default_flavor: None,
+            },
+        }
+    }
+
+    async fn fetch_versions_dto(&self) -> anyhow::Result<HashMap<SmolStr, VersionDto>> {
+        Ok(self
+            .client
+            .get(VERSIONS_URL)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct VersionDto {
+    stable: bool,
+    files: Vec<FileDto>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FileDto {
+    triplet: SmolStr,
+    /// `archive` or `installer`.
+    kind: SmolStr,
+    arch: SmolStr,
+    os: SmolStr,
+    /// Such as `tar.gz`, `zip` or `dmg`.
+    extension: SmolStr,
+    url: SmolStr,
+    sha256: SmolStr,
+}
+
+/// The files in `versions.json` of a platform.
+struct Target {
+    os: &'static str,
+    arch: &'static str,
+    musl: bool,
+}
+
+impl Target {
+    fn new(platform: &str) -> anyhow::Result<Self> {
+        PLATFORMS
+            .iter()
+            .find(|(cpu, os, _, _, _)| create_platform_string(cpu, os) == platform)
+            .map(|&(_, _, os, arch, musl)| Target { os, arch, musl })
+            .ok_or_else(|| anyhow::anyhow!("Unsupported platform: {platform}"))
+    }
+
+    fn matches(&self, file: &FileDto) -> bool {
+        file.kind == "archive"
+            && matches!(file.extension.as_str(), "tar.gz" | "zip")
+            && file.os == self.os
+            && file.arch == self.arch
+            && file.triplet.ends_with("-musl") == self.musl
+    }
+}
+
+fn is_lts(version: &str) -> bool {
+    let mut parts = version.split(['.', '-']).map(str::parse::<u32>);
+    match (parts.next(), parts.next()) {
+        (Some(Ok(major)), Some(Ok(minor))) => LTS_SERIES.contains(&(major, minor)),
+        _ => false,
+    }
+}
+
+/// The archives of the platform in the releases that match the filter. Only the stable
+/// releases of an LTS series count as LTS ones.
+fn matching_files<'a>(
+    versions: &'a HashMap<SmolStr, VersionDto>,
+    target: &'a Target,
+    version_filter: &'a VersionFilter,
+) -> impl Iterator<Item = (ReleaseVersion, &'a FileDto)> + 'a {
+    versions.iter().filter_map(move |(raw_version, release)| {
+        let file = release.files.iter().find(|file| target.matches(file))?;
+        let version = ReleaseVersion::parse(
+            raw_version.clone(),
+            release.stable && is_lts(raw_version),
+            Some(!release.stable),
+        )?;
+        version.matches(version_filter).then_some((version, file))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(triplet: &str, os: &str, arch: &str, extension: &str) -> String {
+        format!(
+            r#"{{"triplet": "{triplet}", "kind": "archive", "os": "{os}", "arch": "{arch}", "extension": "{extension}", "url": "https://example.com/{triplet}.{extension}", "sha256": "{triplet}"}}"#
+        )
+    }
+
+    #[test]
+    fn selects_archives_of_the_platform() {
+        let releases = [
+            file("x86_64-linux-gnu", "linux", "x86_64", "tar.gz"),
+            file("x86_64-linux-musl", "linux", "x86_64", "tar.gz"),
+            file("x86_64-apple-darwin14", "mac", "x86_64", "dmg"),
+            file("x86_64-unknown-freebsd11.1", "freebsd", "x86_64", "tar.gz"),
+        ]
+        .join(",");
+        let versions: HashMap<SmolStr, VersionDto> = serde_json::from_str(&format!(
+            r#"{{
+                "1.11.0-rc1": {{"stable": false, "files": [{releases}]}},
+                "1.10.4": {{"stable": true, "files": [{releases}]}},
+                "1.9.4": {{"stable": true, "files": [{releases}]}}
+            }}"#
+        ))
+        .unwrap();
+        let filter = |lts_only, include_prerelease| VersionFilter {
+            lts_only,
+            include_prerelease,
+            ..Default::default()
+        };
+        let versions = |platform: &str, filter: VersionFilter| {
+            let target = Target::new(platform).unwrap();
+            let mut found = matching_files(&versions, &target, &filter)
+                .map(|(version, file)| (Version::from(version).version, file.sha256.clone()))
+                .collect::<Vec<_>>();
+            found.sort();
+            found
+        };
+
+        assert_eq!(
+            versions("x64-linux_musl", filter(false, false)),
+            [
+                ("1.10.4".into(), "x86_64-linux-musl".into()),
+                ("1.9.4".into(), "x86_64-linux-musl".into())
+            ]
+        );
+        assert_eq!(
+            versions("x64-freebsd", filter(true, false)),
+            [("1.10.4".into(), "x86_64-unknown-freebsd11.1".into())]
+        );
+        assert_eq!(versions("x64-linux", filter(false, true)).len(), 3);
+        assert!(versions("x64-mac", filter(false, false)).is_empty());
+    }
+}
@@ -0,0 +1,194 @@
+use smol_str::SmolStr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use super::manifest::{best_local_tag, sorted_versions, ReleaseVersion};
+use crate::tool::github_releases::{self, AssetMatch, AssetMatcher};
+use crate::tool::{ToolDownInfo, ToolInfo, Version, VersionFilter};
+use crate::HttpClient;
+
+pub struct Tool {
+    client: Arc<HttpClient>,
+    info: ToolInfo,
+    assets: AssetMatcher,
+}
+
+/// Each release has a `kotlin-compiler-<version>.zip` asset.
+const REPO: &str = "JetBrains/kotlin";
+
+impl crate::tool::GeneralTool for Tool {
+    fn info(&self) -> &ToolInfo {
+        &self.info
+    }
+
+    fn endpoints(&self) -> Vec<SmolStr> {
+        vec![github_releases::releases_url(REPO)]
+    }
+
+    async fn fetch_versions(
+        &self,
+        _platform: Option<SmolStr>,
+        _flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<Vec<Version>> {
+        let version_filter = ignore_lts_only(version_filter);
+        let compilers = self.fetch_compilers().await?;
+        Ok(sorted_versions(compilers.into_iter().filter_map(
+            |compiler| compiler.release_version(&version_filter),
+        )))
+    }
+
+    async fn get_down_info(
+        &self,
+        _platform: Option<SmolStr>,
+        _flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<ToolDownInfo> {
+        let version_filter = ignore_lts_only(version_filter);
+        let compilers = self.fetch_compilers().await?;
+        let versions = sorted_versions(
+            compilers
+                .iter()
+                .filter_map(|compiler| compiler.release_version(&version_filter)),
+        );
+        let version = versions.last().ok_or(crate::AvmError::VersionNotFound)?;
+        let compiler = compilers
+            .into_iter()
+            .find(|compiler| compiler.version == version.version)
+            .ok_or(crate::AvmError::VersionNotFound)?;
+        Ok(ToolDownInfo {
+            version: version.clone(),
+            url: compiler.url,
+            hash: crate::FileHash {
+                sha256: compiler.sha256,
+                ..Default::default()
+            },
+        })
+    }
+
+    fn find_best_matching_local_tag<'a, I>(
+        &self,
+        tags_and_versions: I,
+        version_filter: &VersionFilter,
+    ) -> Option<SmolStr>
+    where
+        I: Iterator<Item = (&'a str, &'a Version)>,
+    {
+        best_local_tag(tags_and_versions, &ignore_lts_only(version_filter.clone()))
+    }
+
+    fn entry_path(&self, tag_dir: PathBuf) -> crate::Result<PathBuf> {
+        Ok(script_paths(tag_dir).swap_remove(0).1)
+    }
+
+    fn exe_paths(&self, tag_dir: PathBuf) -> crate::Result<Vec<(SmolStr, PathBuf)>> {
+        Ok(script_paths(tag_dir))
+    }
+}
+
+impl Tool {
+    pub fn new(client: Arc<HttpClient>) -> Self {
+        Tool {
+            client,
+            info: ToolInfo {
+                about: "Kotlin command-line compiler".into(),
+                after_long_help: Some(
+                    "The compiler runs on the JVM and needs a JDK, such as one installed with the `liberica` tool."
+                        .into(),
+                ),
+                all_platforms: None,
+                default_platform: None,
+                all_flavors: None,
+                default_flavor: None,
+            },
+            assets: AssetMatcher::new([(None, r"kotlin-compiler-(?<version>.+)\.zip")]),
+        }
+    }
+
+    async fn fetch_compilers(&self) -> anyhow::Result<Vec<Compiler>> {
+        let releases = github_releases::fetch_releases(&self.client, REPO).await?;
+        Ok(self
+            .assets
+            .matching(None, &releases)?
+            .iter()
+            .map(Compiler::from_match)
+            .collect())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Compiler {
+    version: SmolStr,
+    url: SmolStr,
+    sha256: Option<SmolStr>,
+}
+
+impl Compiler {
+    fn from_match(compiler: &AssetMatch<'_>) -> Self {
+        Self {
+            version: compiler.version().into(),
+            url: compiler.asset.browser_download_url.clone(),
+            sha256: compiler.asset.sha256(),
+        }
+    }
+
+    fn release_version(&self, version_filter: &VersionFilter) -> Option<ReleaseVersion> {
+        let version = ReleaseVersion::parse(self.version.clone(), false, None)?;
+        version.matches(version_filter).then_some(version)
+    }
+}
+
+fn script_paths(tag_dir: PathBuf) -> Vec<(SmolStr, PathBuf)> {
+    let bin_dir = tag_dir.join("bin");
+    let suffix = if cfg!(windows) { ".bat" } else { "" };
+    ["kotlinc", "kotlin"]
+        .into_iter()
+        .map(|name| (name.into(), bin_dir.join(format!("{name}{suffix}"))))
+        .collect()
+}
+
+fn ignore_lts_only(mut version_filter: VersionFilter) -> VersionFilter {
+    if version_filter.lts_only {
+        log::warn!(
+            "`--lts-only` is ignored for `kotlin` because this tool does not define LTS releases."
+        );
+        version_filter.lts_only = false;
+    }
+    version_filter
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tool::github_releases::ReleaseDto;
+
+    #[test]
+    fn picks_compiler_archives() {
+        let releases: Vec<ReleaseDto> = serde_json::from_str(
+            r#"[{"assets": [
+                {"name": "kotlin-compiler-2.1.0-RC2.zip", "browser_download_url": "https://example.com/kotlin-compiler-2.1.0-RC2.zip", "digest": "sha256:cd"},
+                {"name": "kotlin-compiler-2.1.0.zip.sha256", "browser_download_url": "https://example.com/kotlin-compiler-2.1.0.zip.sha256"},
+                {"name": "kotlin-native-prebuilt-linux-x86_64-2.1.0.tar.gz", "browser_download_url": "https://example.com/kotlin-native-prebuilt-linux-x86_64-2.1.0.tar.gz"}
+            ]}]"#,
+        )
+        .unwrap();
+        let tool = Tool::new(Arc::new(
+            HttpClient::new(Default::default(), Default::default(), Default::default()).unwrap(),
+        ));
+        let compilers = tool
+            .assets
+            .matching(None, &releases)
+            .unwrap()
+            .iter()
+            .map(Compiler::from_match)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            compilers,
+            [Compiler {
+                version: "2.1.0-RC2".into(),
+                url: "https://example.com/kotlin-compiler-2.1.0-RC2.zip".into(),
+                sha256: Some("cd".into()),
+            }]
+        );
+    }
+}
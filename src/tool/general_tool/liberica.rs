@@ -2,13 +2,14 @@ use anyhow::Context;
 use rustc_hash::FxHashSet;
 use serde::Deserialize;
 use smol_str::SmolStr;
-use std::path::PathBuf;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use crate::HttpClient;
 use crate::{
     platform::{cpu, create_platform_string, current_cpu, current_os, os},
-    tool::{ToolDownInfo, ToolInfo, Version, VersionFilter},
+    tool::{FlavorOptions, ToolDownInfo, ToolInfo, ToolOption, Version, VersionFilter},
 };
 
 pub struct Tool {
@@ -27,6 +28,12 @@ const FLAVOR: &[&str] = &[
     "nik_standard",
     "nik_full",
 ];
+/// The executables of a JDK, JRE builds only ship some of them.
+pub(super) const JDK_EXES: &[&str] = &[
+    "java", "javac", "jar", "jshell", "javadoc", "jlink", "jpackage", "keytool",
+];
+/// The archive package types, selected with a `+<type>` flavor option.
+const PACKAGE_TYPES: &[&str] = &["tar.gz", "zip"];
 const BASE_URL: &str = "https://api.bell-sw.com/v1/";
 
 struct FetchReleaseArgs<'a> {
@@ -43,6 +50,20 @@ impl crate::tool::GeneralTool for Tool {
         &self.info
     }
 
+    fn endpoints(&self) -> Vec<SmolStr> {
+        vec![
+            smol_str::format_smolstr!("{BASE_URL}liberica/releases"),
+            smol_str::format_smolstr!("{BASE_URL}nik/releases"),
+        ]
+    }
+
+    /// The GitHub release, whose tags are the versions like `21.0.5+11`.
+    fn release_notes_url(&self, version: &str) -> Option<SmolStr> {
+        Some(smol_str::format_smolstr!(
+            "https://github.com/bell-sw/Liberica/releases/tag/{version}"
+        ))
+    }
+
     fn describe_flavor(&self, flavor: &str) -> &'static str {
         match flavor {
             "jdk" => "Standard Liberica JDK.",
@@ -57,13 +78,31 @@ impl crate::tool::GeneralTool for Tool {
         }
     }
 
+    fn base_flavor<'a>(&self, flavor: &'a str) -> &'a str {
+        flavor.split('+').next().unwrap_or(flavor)
+    }
+
+    fn supports_combination(&self, platform: &str, flavor: &str) -> bool {
+        if !flavor.starts_with("nik") {
+            return true;
+        }
+        // Native Image Kit is only published for the mainstream 64-bit platforms.
+        let Some((cpu, os)) = platform.split_once('-') else {
+            return false;
+        };
+        matches!(
+            (cpu, os),
+            (cpu::X64 | cpu::ARM64, os::LINUX | os::LINUX_MUSL | os::MAC) | (cpu::X64, os::WIN)
+        )
+    }
+
     async fn fetch_versions(
         &self,
         platform: Option<SmolStr>,
         flavor: Option<SmolStr>,
         version_filter: VersionFilter,
-    ) -> anyhow::Result<Vec<Version>> {
-        let platform = platform.ok_or_else(|| anyhow::anyhow!("Platform is required"))?;
+    ) -> crate::Result<Vec<Version>> {
+        let platform = platform.ok_or(crate::AvmError::PlatformRequired)?;
         let (cpu, os, bitness) = self.get_dto_os_arch_bitness(&platform)?;
         let flavor = Flavor::parse(flavor.as_deref())?;
 
@@ -82,7 +121,7 @@ impl crate::tool::GeneralTool for Tool {
             self.fetch_liberica_releases(args).await?
         };
 
-        releases.sort_by(|a, b| a.version.cmp(&b.version));
+        releases.sort_by_key(|r| r.version);
         let mut versions = Vec::new();
         let mut version_set = FxHashSet::default();
         for release in releases {
@@ -103,8 +142,8 @@ impl crate::tool::GeneralTool for Tool {
         platform: Option<SmolStr>,
         flavor: Option<SmolStr>,
         version_filter: VersionFilter,
-    ) -> anyhow::Result<ToolDownInfo> {
-        let platform = platform.ok_or_else(|| anyhow::anyhow!("Platform is required"))?;
+    ) -> crate::Result<ToolDownInfo> {
+        let platform = platform.ok_or(crate::AvmError::PlatformRequired)?;
         let (cpu, os, bitness) = self.get_dto_os_arch_bitness(&platform)?;
         let flavor = Flavor::parse(flavor.as_deref())?;
 
@@ -124,7 +163,7 @@ impl crate::tool::GeneralTool for Tool {
         };
 
         // Ensure the latest version is first
-        releases.sort_by(|a, b| b.version.cmp(&a.version));
+        releases.sort_by_key(|r| std::cmp::Reverse(r.version));
         if let Some(release) = releases.into_iter().next() {
             Ok(ToolDownInfo {
                 version: Version {
@@ -138,7 +177,7 @@ impl crate::tool::GeneralTool for Tool {
                 },
             })
         } else {
-            Err(anyhow::anyhow!("No download URL found."))
+            Err(crate::AvmError::VersionNotFound)
         }
     }
 
@@ -150,25 +189,22 @@ impl crate::tool::GeneralTool for Tool {
     where
         I: Iterator<Item = (&'a str, &'a Version)>,
     {
-        tags_and_versions
-            .filter_map(|(tag, version_info)| {
-                let raw_version = &*version_info.version;
-                let version = JdkVersion::parse(raw_version);
-                if !match_liberica_version_filter(
-                    raw_version,
-                    version,
-                    version_info.is_lts,
-                    version_filter,
-                ) {
-                    return None;
-                }
-                Some((version, SmolStr::from(tag)))
-            })
-            .max_by(|a, b| a.0.cmp(&b.0))
-            .map(|(_, tag)| tag)
+        best_local_jdk_tag(tags_and_versions, version_filter)
     }
 
-    fn entry_path(&self, tag_dir: PathBuf) -> anyhow::Result<PathBuf> {
+    fn options(&self) -> &'static [ToolOption] {
+        &[ToolOption::Fx, ToolOption::PackageType]
+    }
+
+    fn flavor_with_options(
+        &self,
+        flavor: Option<&str>,
+        options: &FlavorOptions,
+    ) -> crate::Result<Option<SmolStr>> {
+        flavor_with_options(flavor, options.fx, options.package_type.as_deref()).map(Some)
+    }
+
+    fn entry_path(&self, tag_dir: PathBuf) -> crate::Result<PathBuf> {
         let mut p = tag_dir;
         p.push("bin");
         #[cfg(windows)]
@@ -177,6 +213,14 @@ impl crate::tool::GeneralTool for Tool {
         p.push("java");
         Ok(p)
     }
+
+    fn exe_paths(&self, tag_dir: PathBuf) -> crate::Result<Vec<(SmolStr, PathBuf)>> {
+        Ok(super::exes_in(&tag_dir.join("bin"), JDK_EXES))
+    }
+
+    fn env_vars(&self, tag_dir: &Path) -> Vec<(OsString, OsString)> {
+        vec![("JAVA_HOME".into(), tag_dir.into())]
+    }
 }
 
 impl Tool {
@@ -217,7 +261,13 @@ These distributions are tailored for running, compiling, and debugging Java appl
 These distributions are designed for building native executables from Java bytecode for improved performance and startup time:
 - **`nik_core` (Core version):** A minimal distribution with Liberica VM and native image (based on GraalVM), suitable for Java development.
 - **`nik_standard` (Standard version):** Adds support for plugins to enable the use of non-Java programming languages.
-- **`nik_full` (Full version):** A comprehensive build that includes LibericaFX for GUI-based applications."#.into()),
+- **`nik_full` (Full version):** A comprehensive build that includes LibericaFX for GUI-based applications.
+
+#### **Flavor Options**
+
+Options are added to a flavor with `+`, and are part of the tag name:
+- **`fx`:** A build with LibericaFX, like `jdk+fx`. Also set by `--fx`. Not available for NIK.
+- **`tar.gz` or `zip`:** The package type of the archive, like `jdk+zip`. Also set by `--package-type`."#.into()),
                 all_platforms: Some(all_platforms),
                 default_platform,
                 all_flavors: Some(all_flavors),
@@ -290,7 +340,7 @@ These distributions are designed for building native executables from Java bytec
             args.cpu,
             args.os,
             args.bitness,
-            &args.flavor.bundle_type,
+            args.flavor,
         )?;
 
         if let Some(version_prefix) = args.version_filter.version_prefix {
@@ -321,7 +371,7 @@ These distributions are designed for building native executables from Java bytec
             .into_iter()
             .map(ReleaseItem::from)
             .filter(|release| {
-                match_liberica_version_filter(
+                match_jdk_version_filter(
                     &release.version_raw,
                     release.version,
                     release.lts,
@@ -340,7 +390,7 @@ These distributions are designed for building native executables from Java bytec
             args.cpu,
             args.os,
             args.bitness,
-            &args.flavor.bundle_type,
+            args.flavor,
         )?;
 
         let release_type = if args.version_filter.lts_only {
@@ -373,12 +423,7 @@ These distributions are designed for building native executables from Java bytec
         Ok(releases
             .into_iter()
             .filter(|r| {
-                match_liberica_version_filter(
-                    &r.version_raw,
-                    r.version,
-                    r.lts,
-                    &args.version_filter,
-                )
+                match_jdk_version_filter(&r.version_raw, r.version, r.lts, &args.version_filter)
             })
             .collect())
     }
@@ -389,7 +434,7 @@ These distributions are designed for building native executables from Java bytec
         arch: &str,
         os: &str,
         bitness: u32,
-        bundle_type: &str,
+        flavor: &Flavor,
     ) -> anyhow::Result<reqwest::Url> {
         let mut url = reqwest::Url::parse(&base_url)
             .map_err(|err| anyhow::anyhow!("Invalid Liberica API base URL '{base_url}': {err}"))?;
@@ -398,20 +443,47 @@ These distributions are designed for building native executables from Java bytec
             .append_pair("os", os)
             .append_pair("installation-type", "archive")
             .append_pair("bitness", &bitness.to_string())
-            .append_pair("bundle-type", bundle_type);
+            .append_pair("bundle-type", &flavor.bundle_type);
+        if flavor.fx {
+            url.query_pairs_mut().append_pair("fx", "true");
+        }
+        if let Some(package_type) = &flavor.package_type {
+            url.query_pairs_mut()
+                .append_pair("package-type", package_type);
+        }
         Ok(url)
     }
 }
 
+/// A flavor with its options, like `jdk+fx+zip`.
 #[derive(Debug)]
 struct Flavor {
     is_nik: bool,
     bundle_type: SmolStr,
+    /// Builds with LibericaFX.
+    fx: bool,
+    package_type: Option<SmolStr>,
 }
 
 impl Flavor {
     fn parse(s: Option<&str>) -> anyhow::Result<Flavor> {
         let s = s.unwrap_or("jdk");
+        let mut options = s.split('+');
+        let s = options.next().unwrap_or(s);
+        let mut fx = false;
+        let mut package_type = None;
+        for option in options {
+            match option {
+                "fx" if !fx => fx = true,
+                _ if package_type.is_none() && PACKAGE_TYPES.contains(&option) => {
+                    package_type = Some(SmolStr::new(option))
+                }
+                _ => anyhow::bail!(
+                    "Invalid option `{option}` of Liberica flavor `{s}`, the options are `fx` and one of {}",
+                    PACKAGE_TYPES.join(", ")
+                ),
+            }
+        }
         let is_nik = s.starts_with("nik");
         let bundle_type = SmolStr::new(s.strip_prefix("nik_").unwrap_or(s));
 
@@ -424,13 +496,37 @@ impl Flavor {
             anyhow::bail!("Invalid jdk/jre flavor: {}", s);
         }
 
+        if is_nik && fx {
+            anyhow::bail!("NIK flavors don't take the `fx` option, `nik_full` includes LibericaFX");
+        }
+
         Ok(Flavor {
             is_nik,
             bundle_type,
+            fx,
+            package_type,
         })
     }
 }
 
+/// The flavor selecting the builds of `flavor`, `jdk` by default, with LibericaFX if `fx`
+/// and in the archive `package_type`, like `jdk+fx+zip`.
+fn flavor_with_options(
+    flavor: Option<&str>,
+    fx: bool,
+    package_type: Option<&str>,
+) -> crate::Result<SmolStr> {
+    let mut flavor = SmolStr::new(flavor.unwrap_or("jdk"));
+    if fx && !flavor.split('+').any(|option| option == "fx") {
+        flavor = smol_str::format_smolstr!("{flavor}+fx");
+    }
+    if let Some(package_type) = package_type {
+        flavor = smol_str::format_smolstr!("{flavor}+{package_type}");
+    }
+    Flavor::parse(Some(&flavor))?;
+    Ok(flavor)
+}
+
 #[derive(Debug)]
 struct ReleaseItem {
     download_url: String,
@@ -471,7 +567,26 @@ impl TryFrom<NikReleaseItemDto> for ReleaseItem {
     }
 }
 
-fn match_liberica_version_filter(
+/// The installed tag with the newest JDK version matching `version_filter`.
+pub(super) fn best_local_jdk_tag<'a>(
+    tags_and_versions: impl Iterator<Item = (&'a str, &'a Version)>,
+    version_filter: &VersionFilter,
+) -> Option<SmolStr> {
+    tags_and_versions
+        .filter_map(|(tag, version_info)| {
+            let raw_version = &*version_info.version;
+            let version = JdkVersion::parse(raw_version);
+            if !match_jdk_version_filter(raw_version, version, version_info.is_lts, version_filter)
+            {
+                return None;
+            }
+            Some((version, SmolStr::from(tag)))
+        })
+        .max_by(|a, b| a.0.cmp(&b.0))
+        .map(|(_, tag)| tag)
+}
+
+pub(super) fn match_jdk_version_filter(
     raw_version: &str,
     version: JdkVersion,
     is_lts: bool,
@@ -480,10 +595,7 @@ fn match_liberica_version_filter(
     if version_filter.lts_only && !is_lts {
         return false;
     }
-    if version_filter
-        .version_prefix
-        .is_some_and(|p| !p.matches(version.major, version.minor, version.security))
-    {
+    if !version_filter.matches_numbers(version.major, version.minor, version.security) {
         return false;
     }
     if version_filter
@@ -585,7 +697,56 @@ impl JdkVersion {
 
 #[cfg(test)]
 mod tests {
-    use super::JdkVersion;
+    use super::{flavor_with_options, Flavor, JdkVersion};
+    use crate::tool::GeneralTool;
+
+    #[test]
+    fn flavor_options_map_to_query_parameters() {
+        let tool = super::Tool::new(
+            std::sync::Arc::new(
+                crate::HttpClient::new(Default::default(), Default::default(), Default::default())
+                    .unwrap(),
+            ),
+            None,
+        );
+        let flavor = flavor_with_options(Some("jre"), true, Some("zip")).unwrap();
+        assert_eq!(flavor, "jre+fx+zip");
+        assert_eq!(tool.base_flavor(&flavor), "jre");
+        let url = tool
+            .build_url(
+                "https://api.bell-sw.com/v1/liberica/releases".into(),
+                "x86",
+                "macos",
+                64,
+                &Flavor::parse(Some(&flavor)).unwrap(),
+            )
+            .unwrap();
+        assert_eq!(
+            url.query(),
+            Some("arch=x86&os=macos&installation-type=archive&bitness=64&bundle-type=jre&fx=true&package-type=zip")
+        );
+
+        assert_eq!(flavor_with_options(None, true, None).unwrap(), "jdk+fx");
+        assert!(flavor_with_options(Some("nik_core"), true, None).is_err());
+        assert!(flavor_with_options(None, false, Some("msi")).is_err());
+        assert!(Flavor::parse(Some("jdk+fx+fx")).is_err());
+    }
+
+    #[test]
+    fn nik_flavors_are_limited_to_supported_platforms() {
+        let tool = super::Tool::new(
+            std::sync::Arc::new(
+                crate::HttpClient::new(Default::default(), Default::default(), Default::default())
+                    .unwrap(),
+            ),
+            None,
+        );
+
+        assert!(tool.supports_combination("sparc64-solaris", "jdk"));
+        assert!(!tool.supports_combination("sparc64-solaris", "nik_full"));
+        assert!(tool.supports_combination("arm64-mac", "nik_core"));
+        assert!(!tool.supports_combination("arm64-win", "nik_standard"));
+    }
 
     #[test]
     #[rustfmt::skip]
@@ -0,0 +1,243 @@
+use smol_str::SmolStr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use super::manifest::{best_local_tag, sorted_versions, ReleaseVersion};
+use super::terraform::ignore_lts_only;
+use crate::platform::{cpu, create_platform_string, os, resolve_default_platform};
+use crate::tool::github_releases::{self, AssetMatch, AssetMatcher};
+use crate::tool::{ToolDownInfo, ToolInfo, Version, VersionFilter};
+use crate::HttpClient;
+
+pub struct Tool {
+    client: Arc<HttpClient>,
+    info: ToolInfo,
+    assets: AssetMatcher,
+}
+
+/// Each release has `clang+llvm-<version>-<triple>.tar.xz` assets, renamed to
+/// `LLVM-<version>-<os>-<arch>.tar.xz` since LLVM 19 except on Windows.
+const REPO: &str = "llvm/llvm-project";
+
+/// Platforms by the triples of the older asset names and the `<os>-<arch>` of the newer ones.
+/// The Linux triples of some releases end with the Ubuntu release they were built on, and the
+/// macOS ones with the oldest macOS release they support.
+const PLATFORMS: [(&str, &str, &str, Option<&str>); 7] = [
+    (
+        cpu::X64,
+        os::LINUX,
+        r"x86_64-linux-gnu(?:-ubuntu-[\d.]+)?",
+        Some("Linux-X64"),
+    ),
+    (
+        cpu::ARM64,
+        os::LINUX,
+        r"aarch64-linux-gnu",
+        Some("Linux-ARM64"),
+    ),
+    (cpu::ARMV7L, os::LINUX, r"armv7a-linux-gnueabihf", None),
+    (
+        cpu::X64,
+        os::MAC,
+        r"x86_64-apple-(?:darwin|macos)[\d.]*",
+        Some("macOS-X64"),
+    ),
+    (
+        cpu::ARM64,
+        os::MAC,
+        r"arm64-apple-(?:darwin|macos)[\d.]*",
+        Some("macOS-ARM64"),
+    ),
+    (cpu::X64, os::WIN, r"x86_64-pc-windows-msvc", None),
+    (cpu::ARM64, os::WIN, r"aarch64-pc-windows-msvc", None),
+];
+
+/// Release candidates are versioned like `19.1.0-rc3`.
+const VERSION_PATTERN: &str = r"\d+\.\d+\.\d+(?:-rc\d+)?";
+
+const EXES: [&str; 3] = ["clang", "clang++", "lld"];
+
+impl crate::tool::GeneralTool for Tool {
+    fn info(&self) -> &ToolInfo {
+        &self.info
+    }
+
+    fn endpoints(&self) -> Vec<SmolStr> {
+        vec![github_releases::releases_url(REPO)]
+    }
+
+    async fn fetch_versions(
+        &self,
+        platform: Option<SmolStr>,
+        _flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<Vec<Version>> {
+        let platform = platform.ok_or(crate::AvmError::PlatformRequired)?;
+        let version_filter = ignore_lts_only(version_filter, "llvm");
+        let releases = github_releases::fetch_releases(&self.client, REPO).await?;
+        Ok(sorted_versions(
+            self.assets
+                .matching(Some(&platform), &releases)?
+                .iter()
+                .filter_map(|asset| release_version(asset, &version_filter)),
+        ))
+    }
+
+    async fn get_down_info(
+        &self,
+        platform: Option<SmolStr>,
+        _flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<ToolDownInfo> {
+        let platform = platform.ok_or(crate::AvmError::PlatformRequired)?;
+        let version_filter = ignore_lts_only(version_filter, "llvm");
+        let releases = github_releases::fetch_releases(&self.client, REPO).await?;
+        let assets = self.assets.matching(Some(&platform), &releases)?;
+        let versions = sorted_versions(
+            assets
+                .iter()
+                .filter_map(|asset| release_version(asset, &version_filter)),
+        );
+        let version = versions.last().ok_or(crate::AvmError::VersionNotFound)?;
+        let asset = assets
+            .iter()
+            .find(|asset| asset.version() == version.version)
+            .ok_or(crate::AvmError::VersionNotFound)?;
+        Ok(ToolDownInfo {
+            version: version.clone(),
+            url: asset.asset.browser_download_url.clone(),
+            hash: crate::FileHash {
+                sha256: asset.asset.sha256(),
+                ..Default::default()
+            },
+        })
+    }
+
+    fn find_best_matching_local_tag<'a, I>(
+        &self,
+        tags_and_versions: I,
+        version_filter: &VersionFilter,
+    ) -> Option<SmolStr>
+    where
+        I: Iterator<Item = (&'a str, &'a Version)>,
+    {
+        let version_filter = ignore_lts_only(version_filter.clone(), "llvm");
+        best_local_tag(tags_and_versions, &version_filter)
+    }
+
+    /// The archives are gigabytes once extracted.
+    fn prefers_stream_extract(&self) -> bool {
+        true
+    }
+
+    fn entry_path(&self, tag_dir: PathBuf) -> crate::Result<PathBuf> {
+        Ok(self.exe_paths(tag_dir)?.swap_remove(0).1)
+    }
+
+    fn exe_paths(&self, tag_dir: PathBuf) -> crate::Result<Vec<(SmolStr, PathBuf)>> {
+        Ok(super::exes_in(&tag_dir.join("bin"), &EXES))
+    }
+}
+
+impl Tool {
+    pub fn new(client: Arc<HttpClient>, config_default_platform: Option<SmolStr>) -> Self {
+        let all_platforms = PLATFORMS
+            .iter()
+            .map(|(cpu, os, _, _)| create_platform_string(cpu, os))
+            .collect::<Vec<_>>();
+        let default_platform =
+            resolve_default_platform(&all_platforms, config_default_platform, &[]);
+        Tool {
+            client,
+            info: ToolInfo {
+                about: "LLVM toolchain with Clang and LLD".into(),
+                after_long_help: Some(
+                    "The archives are extracted while downloading, as with `--stream`.".into(),
+                ),
+                all_platforms: Some(all_platforms),
+                default_platform,
+                all_flavors: None,
+                default_flavor: None,
+            },
+            assets: AssetMatcher::new(PLATFORMS.iter().map(|(cpu, os, triple, os_arch)| {
+                let suffix = match os_arch {
+                    Some(os_arch) => format!("(?:{triple}|{os_arch})"),
+                    None => triple.to_string(),
+                };
+                (
+                    Some(create_platform_string(cpu, os)),
+                    format!(
+                        r"(?:clang\+llvm|LLVM)-(?<version>{VERSION_PATTERN})-{suffix}\.tar\.xz"
+                    ),
+                )
+            })),
+        }
+    }
+}
+
+fn release_version(
+    asset: &AssetMatch<'_>,
+    version_filter: &VersionFilter,
+) -> Option<ReleaseVersion> {
+    let version = ReleaseVersion::parse(asset.version().into(), false, None)?;
+    version.matches(version_filter).then_some(version)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tool::github_releases::ReleaseDto;
+
+    #[test]
+    fn maps_platforms_to_asset_triples() {
+        let releases: Vec<ReleaseDto> = serde_json::from_str(
+            r#"[{"assets": [
+                {"name": "LLVM-19.1.0-Linux-X64.tar.xz", "browser_download_url": "https://example.com/a", "digest": "sha256:aa"},
+                {"name": "LLVM-19.1.0-Linux-X64.tar.xz.sig", "browser_download_url": "https://example.com/b"},
+                {"name": "LLVM-19.1.0-macOS-ARM64.tar.xz", "browser_download_url": "https://example.com/c"},
+                {"name": "clang+llvm-19.1.0-x86_64-pc-windows-msvc.tar.xz", "browser_download_url": "https://example.com/d"},
+                {"name": "LLVM-19.1.0-win64.exe", "browser_download_url": "https://example.com/e"}
+            ]}, {"assets": [
+                {"name": "clang+llvm-19.1.0-rc1-x86_64-linux-gnu-ubuntu-22.04.tar.xz", "browser_download_url": "https://example.com/f"}
+            ]}, {"assets": [
+                {"name": "clang+llvm-17.0.6-x86_64-linux-gnu-ubuntu-22.04.tar.xz", "browser_download_url": "https://example.com/g"},
+                {"name": "clang+llvm-17.0.6-arm64-apple-darwin22.0.tar.xz", "browser_download_url": "https://example.com/h"},
+                {"name": "clang+llvm-17.0.6-aarch64-linux-gnu.tar.xz", "browser_download_url": "https://example.com/i"},
+                {"name": "clang+llvm-17.0.6-armv7a-linux-gnueabihf.tar.gz", "browser_download_url": "https://example.com/j"}
+            ]}]"#,
+        )
+        .unwrap();
+        let tool = Tool::new(
+            Arc::new(
+                HttpClient::new(Default::default(), Default::default(), Default::default())
+                    .unwrap(),
+            ),
+            None,
+        );
+        let filter = |include_prerelease| VersionFilter {
+            include_prerelease,
+            ..Default::default()
+        };
+        let versions = |platform, filter: VersionFilter| {
+            sorted_versions(
+                tool.assets
+                    .matching(Some(platform), &releases)
+                    .unwrap()
+                    .iter()
+                    .filter_map(|asset| release_version(asset, &filter)),
+            )
+            .into_iter()
+            .map(|version| version.version)
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(versions("x64-linux", filter(false)), ["17.0.6", "19.1.0"]);
+        assert_eq!(
+            versions("x64-linux", filter(true)),
+            ["17.0.6", "19.1.0-rc1", "19.1.0"]
+        );
+        assert_eq!(versions("arm64-linux", filter(false)), ["17.0.6"]);
+        assert_eq!(versions("arm64-mac", filter(false)), ["17.0.6", "19.1.0"]);
+        assert_eq!(versions("x64-win", filter(false)), ["19.1.0"]);
+        assert!(versions("armv7l-linux", filter(false)).is_empty());
+    }
+}
@@ -0,0 +1,646 @@
+//! Tools declared by a TOML manifest instead of code.
+//!
+//! A manifest points at a JSON releases endpoint and describes where the version, LTS flag,
+//! download URL and hashes live in each release, so simple upstreams can be added without
+//! writing a `GeneralTool` implementation.
+
+use anyhow::Context as _;
+use rustc_hash::FxHashSet;
+use serde::Deserialize;
+use serde_json::Value;
+use smol_str::SmolStr;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::tool::{ArchiveDelta, ToolDownInfo, ToolInfo, Version, VersionFilter};
+use crate::HttpClient;
+
+/// A declarative tool description, read from `<name>.toml`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    pub about: SmolStr,
+    /// URL of the JSON document listing the releases.
+    pub releases_url: SmolStr,
+    /// Path to the releases array inside the document. The document itself if omitted.
+    #[serde(default)]
+    pub releases_path: Option<SmolStr>,
+    pub fields: FieldPaths,
+    /// Download URL built from `{version}`, `{raw_version}` and `{platform}`.
+    /// Used when `fields.url` is not set.
+    #[serde(default)]
+    pub url_template: Option<SmolStr>,
+    /// Entry path relative to the tag directory. `{exe}` expands to `.exe` on Windows.
+    pub entry_path: SmolStr,
+    /// URL of the zstd patch from one archive to another, built from `{from}` and `{to}`, the
+    /// file names of both archives.
+    #[serde(default)]
+    pub delta_url_template: Option<SmolStr>,
+    /// avm platform to the platform string used by the upstream.
+    /// The tool has no distinct platforms if empty.
+    #[serde(default)]
+    pub platforms: BTreeMap<SmolStr, SmolStr>,
+}
+
+/// Paths of the fields inside a release, such as `files.{platform}.url`.
+///
+/// Segments are separated by `.`, a numeric segment indexes an array and `{platform}`
+/// expands to the upstream platform string.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FieldPaths {
+    pub version: SmolStr,
+    #[serde(default)]
+    pub lts: Option<SmolStr>,
+    /// Prerelease flag. If omitted, versions containing `-` are prereleases.
+    #[serde(default)]
+    pub prerelease: Option<SmolStr>,
+    /// Download URL. Releases without it are not available for the platform.
+    #[serde(default)]
+    pub url: Option<SmolStr>,
+    #[serde(default)]
+    pub sha256: Option<SmolStr>,
+    #[serde(default)]
+    pub sha512: Option<SmolStr>,
+}
+
+impl Manifest {
+    pub fn parse(content: &str) -> anyhow::Result<Self> {
+        let manifest: Manifest = toml::from_str(content)?;
+        if manifest.fields.url.is_none() && manifest.url_template.is_none() {
+            anyhow::bail!("Either `fields.url` or `url_template` is required");
+        }
+        Ok(manifest)
+    }
+
+    /// The URL of the patch from the archive at `from_url` to the one at `to_url`.
+    fn delta_url(&self, from_url: &str, to_url: &str) -> Option<SmolStr> {
+        let file_name = |url: &str| url.rsplit('/').next().unwrap_or_default().to_owned();
+        let template = self.delta_url_template.as_ref()?;
+        Some(
+            template
+                .replace("{from}", &file_name(from_url))
+                .replace("{to}", &file_name(to_url))
+                .into(),
+        )
+    }
+}
+
+/// Reads every `*.toml` manifest in `dir`, keyed by file stem. A missing directory has none.
+pub fn load_dir(dir: &Path) -> crate::Result<Vec<(SmolStr, Manifest)>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut manifests = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_none_or(|ext| ext != "toml") {
+            continue;
+        }
+        let name = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .filter(|s| is_valid_name(s))
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Invalid tool manifest name '{}', expected lowercase letters, digits and '-'",
+                    path.display()
+                )
+            })?;
+        let manifest = std::fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|content| Manifest::parse(&content))
+            .with_context(|| format!("Failed to load tool manifest '{}'", path.display()))?;
+        manifests.push((SmolStr::new(name), manifest));
+    }
+    manifests.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(manifests)
+}
+
+pub(super) fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('-')
+        && name
+            .bytes()
+            .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+}
+
+pub struct Tool {
+    client: Arc<HttpClient>,
+    manifest: Manifest,
+    info: ToolInfo,
+}
+
+impl crate::tool::GeneralTool for Tool {
+    fn info(&self) -> &ToolInfo {
+        &self.info
+    }
+
+    fn endpoints(&self) -> Vec<SmolStr> {
+        vec![self.manifest.releases_url.clone()]
+    }
+
+    async fn fetch_versions(
+        &self,
+        platform: Option<SmolStr>,
+        _flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<Vec<Version>> {
+        let mut releases = self.fetch_releases(platform.as_deref()).await?;
+        releases.retain(|r| r.version.matches(&version_filter));
+        Ok(sorted_versions(releases.into_iter().map(|r| r.version)))
+    }
+
+    async fn get_down_info(
+        &self,
+        platform: Option<SmolStr>,
+        _flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<ToolDownInfo> {
+        let release = self
+            .fetch_releases(platform.as_deref())
+            .await?
+            .into_iter()
+            .filter(|r| r.version.matches(&version_filter))
+            .max_by(|a, b| a.version.parsed.cmp(&b.version.parsed))
+            .ok_or(crate::AvmError::VersionNotFound)?;
+
+        if release.sha256.is_none() && release.sha512.is_none() {
+            log::warn!("No hash found");
+        }
+        Ok(ToolDownInfo {
+            version: release.version.into(),
+            url: release.url,
+            hash: crate::FileHash {
+                sha256: release.sha256,
+                sha512: release.sha512,
+                ..Default::default()
+            },
+        })
+    }
+
+    fn find_best_matching_local_tag<'a, I>(
+        &self,
+        tags_and_versions: I,
+        version_filter: &VersionFilter,
+    ) -> Option<SmolStr>
+    where
+        I: Iterator<Item = (&'a str, &'a Version)>,
+    {
+        best_local_tag(tags_and_versions, version_filter)
+    }
+
+    async fn archive_delta(
+        &self,
+        from_url: &str,
+        to_url: &str,
+    ) -> crate::Result<Option<ArchiveDelta>> {
+        Ok(self
+            .manifest
+            .delta_url(from_url, to_url)
+            .map(|url| ArchiveDelta {
+                url,
+                // The restored archive is checked against the hash of the release instead.
+                hash: crate::FileHash::default(),
+            }))
+    }
+
+    fn entry_path(&self, tag_dir: PathBuf) -> crate::Result<PathBuf> {
+        let exe = if cfg!(windows) { ".exe" } else { "" };
+        Ok(tag_dir.join(self.manifest.entry_path.replace("{exe}", exe)))
+    }
+}
+
+impl Tool {
+    pub fn new(
+        client: Arc<HttpClient>,
+        manifest: Manifest,
+        config_default_platform: Option<SmolStr>,
+    ) -> Self {
+        let all_platforms = (!manifest.platforms.is_empty())
+            .then(|| manifest.platforms.keys().cloned().collect::<Vec<_>>());
+        let default_platform = all_platforms.as_deref().and_then(|all_platforms| {
+            resolve_default_platform(all_platforms, config_default_platform)
+        });
+
+        Tool {
+            client,
+            info: ToolInfo {
+                about: manifest.about.clone(),
+                after_long_help: None,
+                all_platforms,
+                default_platform,
+                all_flavors: None,
+                default_flavor: None,
+            },
+            manifest,
+        }
+    }
+
+    async fn fetch_releases(&self, platform: Option<&str>) -> anyhow::Result<Vec<Release>> {
+        let upstream_platform = match platform {
+            Some(platform) if !self.manifest.platforms.is_empty() => Some(
+                self.manifest
+                    .platforms
+                    .get(platform)
+                    .ok_or_else(|| anyhow::anyhow!("Unsupported platform: {platform}"))?
+                    .as_str(),
+            ),
+            None if !self.manifest.platforms.is_empty() => {
+                return Err(crate::AvmError::PlatformRequired.into())
+            }
+            _ => None,
+        };
+
+        let document = self
+            .client
+            .get(&self.manifest.releases_url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<Value>()
+            .await?;
+        parse_releases(&self.manifest, &document, upstream_platform)
+    }
+}
+
+/// Picks the configured platform if supported, falling back to the current one.
+pub(super) fn resolve_default_platform(
+    all_platforms: &[SmolStr],
+    config_default_platform: Option<SmolStr>,
+) -> Option<SmolStr> {
+    crate::platform::resolve_default_platform(all_platforms, config_default_platform, &[])
+}
+
+/// A release as read from the releases document.
+#[derive(Debug)]
+struct Release {
+    version: ReleaseVersion,
+    url: SmolStr,
+    sha256: Option<SmolStr>,
+    sha512: Option<SmolStr>,
+}
+
+/// A published version with the flags `VersionFilter` selects on.
+#[derive(Debug)]
+pub(super) struct ReleaseVersion {
+    raw_version: SmolStr,
+    version: SmolStr,
+    parsed: LooseVersion,
+    lts: bool,
+    prerelease: bool,
+}
+
+impl ReleaseVersion {
+    /// Parses a published version. Without an explicit flag, versions containing `-`
+    /// are prereleases. Returns `None` for versions that are not recognized.
+    pub(super) fn parse(raw_version: SmolStr, lts: bool, prerelease: Option<bool>) -> Option<Self> {
+        let Some((version, parsed)) = parse_loose_version(&raw_version) else {
+            log::debug!("Skipping unrecognized version '{raw_version}'");
+            return None;
+        };
+        Some(Self {
+            version: SmolStr::new(version),
+            prerelease: prerelease.unwrap_or(!parsed.is_release),
+            parsed,
+            lts,
+            raw_version,
+        })
+    }
+
+    pub(super) fn matches(&self, version_filter: &VersionFilter) -> bool {
+        if version_filter.lts_only && !self.lts {
+            return false;
+        }
+        if !version_filter.includes_prerelease() && self.prerelease {
+            return false;
+        }
+        if !version_filter.matches_numbers(self.parsed.major, self.parsed.minor, self.parsed.patch)
+        {
+            return false;
+        }
+        if version_filter
+            .exact_version
+            .as_ref()
+            .is_some_and(|exact| *exact != self.version && *exact != self.raw_version)
+        {
+            return false;
+        }
+        true
+    }
+}
+
+impl From<ReleaseVersion> for Version {
+    fn from(value: ReleaseVersion) -> Self {
+        Version {
+            version: value.version,
+            is_lts: value.lts,
+        }
+    }
+}
+
+/// Sorts the versions from oldest to newest, dropping duplicates.
+pub(super) fn sorted_versions(versions: impl Iterator<Item = ReleaseVersion>) -> Vec<Version> {
+    let mut versions: Vec<_> = versions.collect();
+    versions.sort_by(|a, b| a.parsed.cmp(&b.parsed));
+    let mut version_set = FxHashSet::default();
+    versions
+        .into_iter()
+        .filter(|v| version_set.insert(v.version.clone()))
+        .map(Version::from)
+        .collect()
+}
+
+/// Finds the newest installed tag matching the filter, for tools using `ReleaseVersion`.
+pub(super) fn best_local_tag<'a>(
+    tags_and_versions: impl Iterator<Item = (&'a str, &'a Version)>,
+    version_filter: &VersionFilter,
+) -> Option<SmolStr> {
+    tags_and_versions
+        .filter_map(|(tag, version_info)| {
+            let version =
+                ReleaseVersion::parse(version_info.version.clone(), version_info.is_lts, None)?;
+            version
+                .matches(version_filter)
+                .then(|| (version.parsed, SmolStr::from(tag)))
+        })
+        .max_by(|a, b| a.0.cmp(&b.0))
+        .map(|(_, tag)| tag)
+}
+
+/// A version read leniently: a leading `v`, and one to three numeric parts, followed by
+/// an optional `-prerelease` or `+build` suffix.
+#[derive(PartialOrd, Ord, Debug, PartialEq, Eq, Clone)]
+struct LooseVersion {
+    major: u32,
+    minor: u32,
+    patch: u32,
+    // Orders a prerelease before the release of the same numbers.
+    is_release: bool,
+}
+
+fn parse_loose_version(s: &str) -> Option<(&str, LooseVersion)> {
+    let version = s.strip_prefix('v').unwrap_or(s);
+    let core_end = version.find(['-', '+']).unwrap_or(version.len());
+    let mut parts = version[..core_end].split('.');
+    let mut next = || parts.next().map(str::parse::<u32>).transpose().ok();
+    let major = next()??;
+    let minor = next()?.unwrap_or(0);
+    let patch = next()?.unwrap_or(0);
+    if next()?.is_some() {
+        return None;
+    }
+    Some((
+        version,
+        LooseVersion {
+            major,
+            minor,
+            patch,
+            is_release: !version[core_end..].starts_with('-'),
+        },
+    ))
+}
+
+/// Looks up a dot-separated path. A leading `$` or `$.` is accepted as the root.
+fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    let path = path.strip_prefix('$').unwrap_or(path);
+    let path = path.strip_prefix('.').unwrap_or(path);
+    if path.is_empty() {
+        return Some(value);
+    }
+    path.split('.')
+        .try_fold(value, |value, segment| match value {
+            Value::Array(items) => items.get(segment.parse::<usize>().ok()?),
+            Value::Object(map) => map.get(segment),
+            _ => None,
+        })
+}
+
+fn lookup_string(value: &Value, path: &str, platform: Option<&str>) -> Option<SmolStr> {
+    let path = match platform {
+        Some(platform) => path.replace("{platform}", platform),
+        None => path.to_owned(),
+    };
+    match lookup(value, &path)? {
+        Value::String(s) if !s.is_empty() => Some(SmolStr::new(s)),
+        Value::Number(n) => Some(smol_str::format_smolstr!("{n}")),
+        _ => None,
+    }
+}
+
+/// Whether the value is a truthy flag, where a non-empty string such as an LTS codename counts.
+fn lookup_flag(value: &Value, path: &str) -> bool {
+    match lookup(value, path) {
+        Some(Value::Bool(b)) => *b,
+        Some(Value::String(s)) => !s.is_empty(),
+        _ => false,
+    }
+}
+
+fn parse_releases(
+    manifest: &Manifest,
+    document: &Value,
+    platform: Option<&str>,
+) -> anyhow::Result<Vec<Release>> {
+    let path = manifest.releases_path.as_deref().unwrap_or("");
+    let items = lookup(document, path)
+        .and_then(Value::as_array)
+        .ok_or_else(|| anyhow::anyhow!("No releases array at '{path}'"))?;
+
+    let fields = &manifest.fields;
+    Ok(items
+        .iter()
+        .filter_map(|item| {
+            let version = ReleaseVersion::parse(
+                lookup_string(item, &fields.version, platform)?,
+                fields.lts.as_ref().is_some_and(|p| lookup_flag(item, p)),
+                fields.prerelease.as_ref().map(|p| lookup_flag(item, p)),
+            )?;
+            let url = match (&fields.url, &manifest.url_template) {
+                (Some(url), _) => lookup_string(item, url, platform)?,
+                (None, Some(template)) => SmolStr::new(
+                    template
+                        .replace("{version}", &version.version)
+                        .replace("{raw_version}", &version.raw_version)
+                        .replace("{platform}", platform.unwrap_or_default()),
+                ),
+                (None, None) => return None,
+            };
+            Some(Release {
+                sha256: fields
+                    .sha256
+                    .as_ref()
+                    .and_then(|p| lookup_string(item, p, platform)),
+                sha512: fields
+                    .sha512
+                    .as_ref()
+                    .and_then(|p| lookup_string(item, p, platform)),
+                version,
+                url,
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tool::VersionPrefix;
+
+    const MANIFEST: &str = r#"
+about = "Demo tool"
+releases_url = "https://example.com/releases.json"
+releases_path = "data.releases"
+entry_path = "bin/demo{exe}"
+
+[fields]
+version = "tag"
+lts = "lts"
+url = "assets.{platform}.url"
+sha256 = "assets.{platform}.sha256"
+
+[platforms]
+x64-linux = "linux-amd64"
+arm64-mac = "darwin-arm64"
+"#;
+
+    fn document() -> Value {
+        serde_json::json!({
+            "data": { "releases": [
+                { "tag": "v2.0.0-rc.1", "lts": false, "assets": {
+                    "linux-amd64": { "url": "https://example.com/2.0.0-rc.1.tar.gz" } } },
+                { "tag": "v1.10.0", "lts": "Maple", "assets": {
+                    "linux-amd64": { "url": "https://example.com/1.10.0.tar.gz", "sha256": "ab" } } },
+                { "tag": "v1.9.2", "lts": false, "assets": {
+                    "darwin-arm64": { "url": "https://example.com/1.9.2.tar.gz" } } },
+                { "tag": "nightly", "assets": {} }
+            ] }
+        })
+    }
+
+    fn filter(
+        version_prefix: Option<&str>,
+        lts_only: bool,
+        include_prerelease: bool,
+    ) -> VersionFilter {
+        VersionFilter {
+            lts_only,
+            include_prerelease,
+            version_prefix: version_prefix.map(|p| VersionPrefix::parse(p).unwrap()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_lookup() {
+        let value = serde_json::json!({ "a": [{ "b": "x" }, { "b": 3 }] });
+        assert_eq!(lookup(&value, "a.0.b"), Some(&Value::from("x")));
+        assert_eq!(lookup(&value, "$.a.1.b"), Some(&Value::from(3)));
+        assert_eq!(lookup(&value, "$"), Some(&value));
+        assert_eq!(lookup(&value, "a.b"), None);
+        assert_eq!(lookup(&value, "a.2.b"), None);
+    }
+
+    #[test]
+    fn test_parse_loose_version() {
+        let (version, parsed) = parse_loose_version("v1.2").unwrap();
+        assert_eq!(version, "1.2");
+        assert_eq!((parsed.major, parsed.minor, parsed.patch), (1, 2, 0));
+        assert!(
+            parse_loose_version("1.2.3-rc.1").unwrap().1 < parse_loose_version("1.2.3").unwrap().1
+        );
+        assert!(parse_loose_version("1.2.3+build.5").unwrap().1.is_release);
+        assert!(parse_loose_version("nightly").is_none());
+        assert!(parse_loose_version("1.2.3.4").is_none());
+    }
+
+    #[test]
+    fn test_parse_releases() {
+        let manifest = Manifest::parse(MANIFEST).unwrap();
+        let releases = parse_releases(&manifest, &document(), Some("linux-amd64")).unwrap();
+        let versions: Vec<_> = releases
+            .iter()
+            .map(|r| r.version.version.as_str())
+            .collect();
+        assert_eq!(versions, ["2.0.0-rc.1", "1.10.0"]);
+        assert!(releases[0].version.prerelease);
+        assert!(releases[1].version.lts);
+        assert_eq!(releases[1].url, "https://example.com/1.10.0.tar.gz");
+        assert_eq!(releases[1].sha256.as_deref(), Some("ab"));
+
+        assert!(!releases[0].version.matches(&filter(None, false, false)));
+        assert!(releases[0].version.matches(&filter(Some("2"), false, true)));
+        assert!(!releases[1]
+            .version
+            .matches(&filter(Some("1.9"), false, false)));
+        assert!(releases[1]
+            .version
+            .matches(&filter(Some("1.10"), true, false)));
+    }
+
+    #[test]
+    fn test_url_template() {
+        let manifest = Manifest::parse(
+            r#"
+about = "Demo tool"
+releases_url = "https://example.com/releases.json"
+url_template = "https://example.com/{raw_version}/demo-{version}-{platform}.zip"
+entry_path = "demo"
+
+[fields]
+version = "name"
+"#,
+        )
+        .unwrap();
+        let document = serde_json::json!([{ "name": "v3.1" }]);
+        let releases = parse_releases(&manifest, &document, Some("linux")).unwrap();
+        assert_eq!(
+            releases[0].url,
+            "https://example.com/v3.1/demo-3.1-linux.zip"
+        );
+    }
+
+    #[test]
+    fn test_delta_url_template() {
+        let manifest = Manifest::parse(
+            r#"
+about = "Demo tool"
+releases_url = "https://example.com/releases.json"
+url_template = "https://example.com/{raw_version}/demo-{version}.zip"
+delta_url_template = "https://example.com/deltas/{from}..{to}.zst"
+entry_path = "demo"
+
+[fields]
+version = "name"
+"#,
+        )
+        .unwrap();
+        assert_eq!(
+            manifest
+                .delta_url(
+                    "https://example.com/v3.0/demo-3.0.zip",
+                    "https://example.com/v3.1/demo-3.1.zip"
+                )
+                .unwrap(),
+            "https://example.com/deltas/demo-3.0.zip..demo-3.1.zip.zst"
+        );
+        let manifest = Manifest {
+            delta_url_template: None,
+            ..manifest
+        };
+        assert!(manifest.delta_url("a", "b").is_none());
+    }
+
+    #[test]
+    fn test_manifest_requires_url() {
+        let err = Manifest::parse(
+            "about = \"x\"\nreleases_url = \"https://example.com\"\nentry_path = \"x\"\n[fields]\nversion = \"v\"\n",
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("url_template"));
+    }
+}
@@ -0,0 +1,170 @@
+use smol_str::SmolStr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use super::manifest::{best_local_tag, sorted_versions, ReleaseVersion};
+use crate::tool::{ToolDownInfo, ToolInfo, Version, VersionFilter};
+use crate::HttpClient;
+
+pub struct Tool {
+    client: Arc<HttpClient>,
+    info: ToolInfo,
+}
+
+/// The Apache Maven distributions, as published to Maven Central.
+const BASE_URL: &str = "https://repo.maven.apache.org/maven2/org/apache/maven/apache-maven/";
+
+impl crate::tool::GeneralTool for Tool {
+    fn info(&self) -> &ToolInfo {
+        &self.info
+    }
+
+    fn endpoints(&self) -> Vec<SmolStr> {
+        vec![smol_str::format_smolstr!("{BASE_URL}maven-metadata.xml")]
+    }
+
+    async fn fetch_versions(
+        &self,
+        _platform: Option<SmolStr>,
+        _flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<Vec<Version>> {
+        let version_filter = ignore_lts_only(version_filter);
+        let versions = self.fetch_matching_versions(&version_filter).await?;
+        Ok(versions)
+    }
+
+    async fn get_down_info(
+        &self,
+        _platform: Option<SmolStr>,
+        _flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<ToolDownInfo> {
+        let version_filter = ignore_lts_only(version_filter);
+        let versions = self.fetch_matching_versions(&version_filter).await?;
+        let version = versions.last().ok_or(crate::AvmError::VersionNotFound)?;
+
+        let url =
+            smol_str::format_smolstr!("{BASE_URL}{0}/apache-maven-{0}-bin.zip", version.version);
+        let sha512 = self
+            .client
+            .get(&format!("{url}.sha512"))
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        // Some checksum files are followed by the file name, like the output of `sha512sum`.
+        let sha512 = sha512
+            .split_whitespace()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("Empty checksum file for Maven {}", version.version))?;
+        Ok(ToolDownInfo {
+            version: version.clone(),
+            url,
+            hash: crate::FileHash {
+                sha512: Some(sha512.into()),
+                ..Default::default()
+            },
+        })
+    }
+
+    fn find_best_matching_local_tag<'a, I>(
+        &self,
+        tags_and_versions: I,
+        version_filter: &VersionFilter,
+    ) -> Option<SmolStr>
+    where
+        I: Iterator<Item = (&'a str, &'a Version)>,
+    {
+        best_local_tag(tags_and_versions, &ignore_lts_only(version_filter.clone()))
+    }
+
+    fn entry_path(&self, tag_dir: PathBuf) -> crate::Result<PathBuf> {
+        let name = if cfg!(windows) { "mvn.cmd" } else { "mvn" };
+        Ok(tag_dir.join("bin").join(name))
+    }
+}
+
+impl Tool {
+    pub fn new(client: Arc<HttpClient>) -> Self {
+        Tool {
+            client,
+            info: ToolInfo {
+                about: "Apache Maven build tool".into(),
+                after_long_help: Some(
+                    "Maven runs on the JVM and needs a JDK, such as one installed with the `liberica` tool."
+                        .into(),
+                ),
+                all_platforms: None,
+                default_platform: None,
+                all_flavors: None,
+                default_flavor: None,
+            },
+        }
+    }
+
+    async fn fetch_matching_versions(
+        &self,
+        version_filter: &VersionFilter,
+    ) -> anyhow::Result<Vec<Version>> {
+        let metadata = self
+            .client
+            .get(&format!("{BASE_URL}maven-metadata.xml"))
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        Ok(sorted_versions(
+            parse_metadata_versions(&metadata).filter_map(|raw| {
+                let version = ReleaseVersion::parse(raw.into(), false, None)?;
+                version.matches(version_filter).then_some(version)
+            }),
+        ))
+    }
+}
+
+/// The `<version>` elements of a `maven-metadata.xml`.
+fn parse_metadata_versions(metadata: &str) -> impl Iterator<Item = &str> {
+    metadata.split("<version>").skip(1).filter_map(|rest| {
+        let (version, _) = rest.split_once("</version>")?;
+        Some(version.trim())
+    })
+}
+
+fn ignore_lts_only(mut version_filter: VersionFilter) -> VersionFilter {
+    if version_filter.lts_only {
+        log::warn!(
+            "`--lts-only` is ignored for `maven` because this tool does not define LTS releases."
+        );
+        version_filter.lts_only = false;
+    }
+    version_filter
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_metadata_versions() {
+        let metadata = r#"<?xml version="1.0" encoding="UTF-8"?>
+<metadata>
+  <groupId>org.apache.maven</groupId>
+  <artifactId>apache-maven</artifactId>
+  <versioning>
+    <latest>4.0.0-rc-2</latest>
+    <release>4.0.0-rc-2</release>
+    <versions>
+      <version>3.9.9</version>
+      <version>4.0.0-rc-2</version>
+    </versions>
+  </versioning>
+</metadata>"#;
+        assert_eq!(
+            parse_metadata_versions(metadata).collect::<Vec<_>>(),
+            ["3.9.9", "4.0.0-rc-2"]
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use smol_str::SmolStr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use super::manifest::{best_local_tag, sorted_versions, ReleaseVersion};
+use super::terraform::ignore_lts_only;
+use crate::platform::{cpu, create_platform_string, os, resolve_default_platform};
+use crate::tool::github_releases::{self, AssetDto, ReleaseDto};
+use crate::tool::{ToolDownInfo, ToolInfo, Version, VersionFilter};
+use crate::HttpClient;
+
+pub struct Tool {
+    client: Arc<HttpClient>,
+    info: ToolInfo,
+}
+
+/// Each release has a zip of the single `ninja` binary per platform, named without the version.
+const REPO: &str = "ninja-build/ninja";
+
+/// Platforms by the asset name. The macOS binary is a universal one.
+const PLATFORMS: [(&str, &str, &str); 6] = [
+    (cpu::X64, os::LINUX, "ninja-linux.zip"),
+    (cpu::ARM64, os::LINUX, "ninja-linux-aarch64.zip"),
+    (cpu::X64, os::MAC, "ninja-mac.zip"),
+    (cpu::ARM64, os::MAC, "ninja-mac.zip"),
+    (cpu::X64, os::WIN, "ninja-win.zip"),
+    (cpu::ARM64, os::WIN, "ninja-winarm64.zip"),
+];
+
+impl crate::tool::GeneralTool for Tool {
+    fn info(&self) -> &ToolInfo {
+        &self.info
+    }
+
+    fn endpoints(&self) -> Vec<SmolStr> {
+        vec![github_releases::releases_url(REPO)]
+    }
+
+    async fn fetch_versions(
+        &self,
+        platform: Option<SmolStr>,
+        _flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<Vec<Version>> {
+        let platform = platform.ok_or(crate::AvmError::PlatformRequired)?;
+        let asset_name = asset_name(&platform)?;
+        let version_filter = ignore_lts_only(version_filter, "ninja");
+        let releases = github_releases::fetch_releases(&self.client, REPO).await?;
+        Ok(sorted_versions(
+            matching_assets(&releases, asset_name, &version_filter).map(|(version, _)| version),
+        ))
+    }
+
+    async fn get_down_info(
+        &self,
+        platform: Option<SmolStr>,
+        _flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<ToolDownInfo> {
+        let platform = platform.ok_or(crate::AvmError::PlatformRequired)?;
+        let asset_name = asset_name(&platform)?;
+        let version_filter = ignore_lts_only(version_filter, "ninja");
+        let releases = github_releases::fetch_releases(&self.client, REPO).await?;
+        let versions = sorted_versions(
+            matching_assets(&releases, asset_name, &version_filter).map(|(version, _)| version),
+        );
+        let version = versions.last().ok_or(crate::AvmError::VersionNotFound)?;
+        let asset = releases
+            .iter()
+            .find(|release| release.tag_name.trim_start_matches('v') == version.version)
+            .and_then(|release| release.assets.iter().find(|a| a.name == asset_name))
+            .ok_or(crate::AvmError::VersionNotFound)?;
+        Ok(ToolDownInfo {
+            version: version.clone(),
+            url: asset.browser_download_url.clone(),
+            hash: crate::FileHash {
+                sha256: asset.sha256(),
+                ..Default::default()
+            },
+        })
+    }
+
+    fn find_best_matching_local_tag<'a, I>(
+        &self,
+        tags_and_versions: I,
+        version_filter: &VersionFilter,
+    ) -> Option<SmolStr>
+    where
+        I: Iterator<Item = (&'a str, &'a Version)>,
+    {
+        let version_filter = ignore_lts_only(version_filter.clone(), "ninja");
+        best_local_tag(tags_and_versions, &version_filter)
+    }
+
+    fn entry_path(&self, tag_dir: PathBuf) -> crate::Result<PathBuf> {
+        Ok(super::exes_in(&tag_dir, &["ninja"]).swap_remove(0).1)
+    }
+}
+
+impl Tool {
+    pub fn new(client: Arc<HttpClient>, config_default_platform: Option<SmolStr>) -> Self {
+        let all_platforms = PLATFORMS
+            .iter()
+            .map(|(cpu, os, _)| create_platform_string(cpu, os))
+            .collect::<Vec<_>>();
+        let default_platform =
+            resolve_default_platform(&all_platforms, config_default_platform, &[]);
+        Tool {
+            client,
+            info: ToolInfo {
+                about: "Ninja, a small build system focused on speed".into(),
+                after_long_help: None,
+                all_platforms: Some(all_platforms),
+                default_platform,
+                all_flavors: None,
+                default_flavor: None,
+            },
+        }
+    }
+}
+
+fn asset_name(platform: &str) -> anyhow::Result<&'static str> {
+    PLATFORMS
+        .iter()
+        .find(|(cpu, os, _)| create_platform_string(cpu, os) == platform)
+        .map(|(_, _, name)| *name)
+        .ok_or_else(|| anyhow::anyhow!("Unsupported platform: {platform}"))
+}
+
+/// The assets named `asset_name` of the releases that match the filter, versioned by the tag.
+fn matching_assets<'a>(
+    releases: &'a [ReleaseDto],
+    asset_name: &'a str,
+    version_filter: &'a VersionFilter,
+) -> impl Iterator<Item = (ReleaseVersion, &'a AssetDto)> + 'a {
+    releases.iter().filter_map(move |release| {
+        let asset = release.assets.iter().find(|a| a.name == asset_name)?;
+        let version = ReleaseVersion::parse(release.tag_name.clone(), false, None)?;
+        version.matches(version_filter).then_some((version, asset))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn versions_assets_by_the_release_tag() {
+        let releases: Vec<ReleaseDto> = serde_json::from_str(
+            r#"[{"tag_name": "v1.12.1", "assets": [
+                {"name": "ninja-linux.zip", "browser_download_url": "https://example.com/v1.12.1/ninja-linux.zip", "digest": "sha256:aa"},
+                {"name": "ninja-mac.zip", "browser_download_url": "https://example.com/v1.12.1/ninja-mac.zip"}
+            ]}, {"tag_name": "v1.10.2", "assets": [
+                {"name": "ninja-linux.zip", "browser_download_url": "https://example.com/v1.10.2/ninja-linux.zip"}
+            ]}]"#,
+        )
+        .unwrap();
+        let filter = VersionFilter::default();
+        let versions = |platform| {
+            matching_assets(&releases, asset_name(platform).unwrap(), &filter)
+                .map(|(version, asset)| (Version::from(version).version, asset.sha256()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            versions("x64-linux"),
+            [
+                ("1.12.1".into(), Some("aa".into())),
+                ("1.10.2".into(), None)
+            ]
+        );
+        assert_eq!(versions("arm64-mac"), [("1.12.1".into(), None)]);
+        assert!(versions("arm64-win").is_empty());
+    }
+}
@@ -1,66 +1,82 @@
 use rustc_hash::FxHashSet;
 use serde::Deserialize;
 use smol_str::SmolStr;
-use std::path::PathBuf;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
 use crate::HttpClient;
 use crate::{
+    io::{stream_json_array, JsonItemAction},
     platform::{cpu, create_platform_string, current_cpu, current_os, os},
-    tool::{ToolDownInfo, ToolInfo, Version, VersionFilter},
+    tool::end_of_life::{EndOfLife, SupportStatus},
+    tool::{FlavorOptions, NewTag, ToolDownInfo, ToolInfo, ToolOption, Version, VersionFilter},
 };
 
 pub struct Tool {
     client: Arc<HttpClient>,
     info: ToolInfo,
     corresponding_file_dto_and_archive_suffix: Vec<(&'static str, &'static str)>,
+    end_of_life: EndOfLife,
 }
 
 const BASE_URL: &str = "https://nodejs.org/dist/";
+/// The channels besides the releases, whose builds are the flavors of the same name.
+const CHANNELS: [(&str, &str); 2] = [
+    ("rc", "https://nodejs.org/download/rc/"),
+    ("nightly", "https://nodejs.org/download/nightly/"),
+];
 
 impl crate::tool::GeneralTool for Tool {
     fn info(&self) -> &ToolInfo {
         &self.info
     }
 
+    fn endpoints(&self) -> Vec<SmolStr> {
+        vec![
+            smol_str::format_smolstr!("{BASE_URL}index.json"),
+            self.end_of_life.url(),
+        ]
+    }
+
+    async fn support_status(&self, version: &str) -> crate::Result<Option<SupportStatus>> {
+        self.end_of_life.support_status(version).await
+    }
+
+    /// The entry of the changelog of the major version, which has an anchor per release.
+    fn release_notes_url(&self, version: &str) -> Option<SmolStr> {
+        let major = version.split('.').next()?;
+        Some(smol_str::format_smolstr!(
+            "https://github.com/nodejs/node/blob/main/doc/changelogs/CHANGELOG_V{major}.md#{version}"
+        ))
+    }
+
+    fn describe_flavor(&self, flavor: &str) -> &'static str {
+        match flavor {
+            "rc" => "Release candidates of the next major version.",
+            "nightly" => "Nightly builds of the main branch.",
+            _ => "Tool-specific build flavor.",
+        }
+    }
+
     async fn fetch_versions(
         &self,
         platform: Option<SmolStr>,
-        _flavor: Option<SmolStr>,
+        flavor: Option<SmolStr>,
         version_filter: VersionFilter,
-    ) -> anyhow::Result<Vec<Version>> {
-        let platform = platform.ok_or_else(|| anyhow::anyhow!("Platform is required"))?;
+    ) -> crate::Result<Vec<Version>> {
+        let platform = platform.ok_or(crate::AvmError::PlatformRequired)?;
         let (file_dto, _) = self.get_file_dto_and_archive_suffix(&platform)?;
-        let version_filter = NodeVersionFilter::try_from(&version_filter)?;
-
-        let mut releases = self
-            .fetch_node_releases(&self.client)
-            .await?
-            .into_iter()
-            .filter_map(|r| {
-                let (version_raw, version) = parse_node_version(&r.version)
-                    .map_err(|e| log::error!("Failed to parse Node version: {}", e))
-                    .ok()?;
-                let lts = r.lts.is();
-
-                if !version_filter.verify(version_raw, &version, lts) {
-                    return None;
-                }
-                if !r.files.iter().any(|f| f == file_dto) {
-                    return None;
-                }
-                Some((version, SmolStr::from(version_raw), lts))
-            })
-            .collect::<Vec<_>>();
-        releases.sort_by(|a, b| a.0.cmp(&b.0));
+        let builds = self
+            .matching_builds(file_dto, flavor.as_deref(), &version_filter)
+            .await?;
         let mut versions = Vec::new();
         let mut version_set = FxHashSet::default();
-        for release in releases {
-            let version_raw = release.1;
-            if version_set.insert(version_raw.clone()) {
+        for build in builds {
+            if version_set.insert(build.raw_version.clone()) {
                 versions.push(Version {
-                    version: version_raw,
-                    is_lts: release.2,
+                    version: build.raw_version,
+                    is_lts: build.is_lts,
                 });
             }
         }
@@ -71,35 +87,24 @@ impl crate::tool::GeneralTool for Tool {
     async fn get_down_info(
         &self,
         platform: Option<SmolStr>,
-        _flavor: Option<SmolStr>,
+        flavor: Option<SmolStr>,
         version: VersionFilter,
-    ) -> anyhow::Result<ToolDownInfo> {
-        let platform = platform.ok_or_else(|| anyhow::anyhow!("Platform is required"))?;
+    ) -> crate::Result<ToolDownInfo> {
+        let platform = platform.ok_or(crate::AvmError::PlatformRequired)?;
         let (file_dto, archive_suffix) = self.get_file_dto_and_archive_suffix(&platform)?;
-        let version_filter = NodeVersionFilter::try_from(&version)?;
-
-        let release = self
-            .fetch_node_releases(&self.client)
+        let build = self
+            .matching_builds(file_dto, flavor.as_deref(), &version)
             .await?
-            .into_iter()
-            .filter_map(|r| {
-                let (version_raw, version) = parse_node_version(&r.version)
-                    .map_err(|e| log::error!("Failed to parse Node version: {}", e))
-                    .ok()?;
-
-                if !version_filter.verify(version_raw, &version, r.lts.is()) {
-                    return None;
-                }
-                if !r.files.iter().any(|f| f == file_dto) {
-                    return None;
-                }
-                Some((version, SmolStr::from(version_raw), r.lts.is()))
-            })
-            .max_by(|a, b| a.0.cmp(&b.0));
-        match release {
-            Some((_, version_raw, is_lts)) => {
+            .pop();
+        match build {
+            Some(NodeBuild {
+                raw_version: version_raw,
+                is_lts,
+                base_url,
+                ..
+            }) => {
                 // Read the shasum file non-streamingly because it's not large.
-                let url_dir = format!("{}/v{}", BASE_URL, version_raw);
+                let url_dir = format!("{}v{}", base_url, version_raw);
                 let sha256_content = self
                     .client
                     .get(&format!("{}/SHASUMS256.txt", url_dir))
@@ -138,7 +143,7 @@ impl crate::tool::GeneralTool for Tool {
                     },
                 })
             }
-            None => Err(anyhow::anyhow!("No download URL found.")),
+            None => Err(crate::AvmError::VersionNotFound),
         }
     }
 
@@ -161,13 +166,19 @@ impl crate::tool::GeneralTool for Tool {
                 if !version_filter.verify(raw_version, &version, version_info.is_lts) {
                     return None;
                 }
-                Some((version, SmolStr::from(tag)))
+                // Without a flavor, the tags of every channel are candidates if prereleases are
+                // included, and releases win.
+                let order = build_order(raw_version, &version);
+                Some((
+                    (prerelease(raw_version).is_none(), order),
+                    SmolStr::from(tag),
+                ))
             })
             .max_by(|a, b| a.0.cmp(&b.0))
             .map(|(_, tag)| tag)
     }
 
-    fn entry_path(&self, tag_dir: PathBuf) -> anyhow::Result<PathBuf> {
+    fn entry_path(&self, tag_dir: PathBuf) -> crate::Result<PathBuf> {
         let mut p = tag_dir;
         #[cfg(windows)]
         p.push("node.exe");
@@ -178,6 +189,82 @@ impl crate::tool::GeneralTool for Tool {
         }
         Ok(p)
     }
+
+    fn exe_paths(&self, tag_dir: PathBuf) -> crate::Result<Vec<(SmolStr, PathBuf)>> {
+        let node = self.entry_path(tag_dir.clone())?;
+        #[cfg(windows)]
+        let (npm, npx) = (tag_dir.join("npm.cmd"), tag_dir.join("npx.cmd"));
+        #[cfg(not(windows))]
+        let (npm, npx) = (
+            tag_dir.join("bin").join("npm"),
+            tag_dir.join("bin").join("npx"),
+        );
+        let mut exes = vec![
+            ("node".into(), node),
+            ("npm".into(), npm),
+            ("npx".into(), npx),
+        ];
+        // Bundled up to Node 24, and the shims of `corepack enable`.
+        let bin_dir = bin_dir(&tag_dir);
+        exes.extend(COREPACK_SCRIPTS.iter().filter_map(|&name| {
+            let path = script_path(&bin_dir, name);
+            path.exists().then(|| (name.into(), path))
+        }));
+        Ok(exes)
+    }
+
+    fn options(&self) -> &'static [ToolOption] {
+        &[ToolOption::Channel, ToolOption::Npm, ToolOption::Corepack]
+    }
+
+    fn flavor_with_options(
+        &self,
+        flavor: Option<&str>,
+        options: &FlavorOptions,
+    ) -> crate::Result<Option<SmolStr>> {
+        match &options.channel {
+            Some(channel) => channel_flavor(channel),
+            None => Ok(flavor.map(SmolStr::from)),
+        }
+    }
+
+    /// The builds of the rc and nightly channels are all prereleases.
+    fn is_prerelease_flavor(&self, _flavor: &str) -> bool {
+        true
+    }
+
+    async fn set_up_tag(&self, tag: &NewTag<'_>) -> crate::Result<()> {
+        if let Some(npm) = &tag.options.npm {
+            log::info!("Installing npm {npm} into \"{}\"", tag.tag);
+            install_npm(tag.tag_dir.to_path_buf(), npm).await?;
+        }
+        if tag.options.corepack {
+            log::info!("Enabling corepack in \"{}\"", tag.tag);
+            enable_corepack(tag.tag_dir.to_path_buf()).await?;
+        }
+        Ok(())
+    }
+
+    fn command(
+        &self,
+        entry_path: PathBuf,
+        args: Vec<OsString>,
+        env_vars: Vec<(OsString, OsString)>,
+    ) -> crate::Result<std::process::Command> {
+        // `npm` and `npx` are scripts that find `node` through `PATH`.
+        let bin_dir = entry_path.parent().map(PathBuf::from);
+        let mut command = std::process::Command::new(&entry_path);
+        command.args(args).envs(env_vars);
+        if let Some(bin_dir) = bin_dir {
+            let paths = std::env::var_os("PATH").unwrap_or_default();
+            let paths = std::iter::once(bin_dir).chain(std::env::split_paths(&paths));
+            command.env(
+                "PATH",
+                std::env::join_paths(paths).map_err(anyhow::Error::from)?,
+            );
+        }
+        Ok(command)
+    }
 }
 
 impl Tool {
@@ -195,17 +282,22 @@ impl Tool {
                 })
             });
 
+        let end_of_life = EndOfLife::new(client.clone(), "nodejs");
         Tool {
             client,
             info: ToolInfo {
                 about: "Node.js JavaScript runtime".into(),
-                after_long_help: None,
+                after_long_help: Some(
+                    "Releases have no flavor. The `rc` and `nightly` flavors are the builds of the release candidate and nightly channels, also selected with `--channel`."
+                        .into(),
+                ),
                 all_platforms: Some(all_platforms),
                 default_platform,
-                all_flavors: None,
+                all_flavors: Some(CHANNELS.iter().map(|(channel, _)| SmolStr::new(channel)).collect()),
                 default_flavor: None,
             },
             corresponding_file_dto_and_archive_suffix,
+            end_of_life,
         }
     }
 
@@ -267,14 +359,66 @@ impl Tool {
             .ok_or_else(|| anyhow::anyhow!("Missing Node platform mapping for: {platform}"))
     }
 
-    async fn fetch_node_releases(&self, client: &HttpClient) -> reqwest::Result<Vec<ReleaseDto>> {
-        client
-            .get(&format!("{BASE_URL}index.json"))
+    /// The builds for `file_dto` matching the filter, oldest first. Every build of the rc and
+    /// nightly channels is a prerelease, and the release candidates are searched along with
+    /// the releases if prereleases are included.
+    async fn matching_builds(
+        &self,
+        file_dto: &'static str,
+        flavor: Option<&str>,
+        version_filter: &VersionFilter,
+    ) -> crate::Result<Vec<NodeBuild>> {
+        let mut version_filter = NodeVersionFilter::try_from(version_filter)?;
+        version_filter.include_prerelease |= flavor.is_some();
+        let mut base_urls = vec![base_url(flavor)?];
+        if flavor.is_none() && version_filter.searches_release_candidates() {
+            base_urls.push(base_url(Some("rc"))?);
+        }
+
+        let mut builds = Vec::new();
+        for base_url in base_urls {
+            let version_filter = version_filter.clone();
+            let channel_builds = self
+                .fetch_node_releases(&self.client, base_url, move |r| {
+                    let Ok((version_raw, version)) = parse_node_version(&r.version)
+                        .map_err(|e| log::error!("Failed to parse Node version: {}", e))
+                    else {
+                        return JsonItemAction::Skip;
+                    };
+                    let is_lts = r.lts.is();
+
+                    if !version_filter.verify(version_raw, &version, is_lts) {
+                        return JsonItemAction::Skip;
+                    }
+                    if !r.files.iter().any(|f| f == file_dto) {
+                        return JsonItemAction::Skip;
+                    }
+                    version_filter.found(NodeBuild {
+                        version,
+                        raw_version: SmolStr::from(version_raw),
+                        is_lts,
+                        base_url,
+                    })
+                })
+                .await?;
+            builds.extend(channel_builds);
+        }
+        builds.sort_by_cached_key(|build| build_order(&build.raw_version, &build.version));
+        Ok(builds)
+    }
+
+    async fn fetch_node_releases<U: Send + 'static>(
+        &self,
+        client: &HttpClient,
+        base_url: &str,
+        filter_map: impl FnMut(ReleaseDto) -> JsonItemAction<U> + Send + 'static,
+    ) -> anyhow::Result<Vec<U>> {
+        let response = client
+            .get(&format!("{base_url}index.json"))
             .send()
             .await?
-            .error_for_status()?
-            .json()
-            .await
+            .error_for_status()?;
+        stream_json_array(response, filter_map).await
     }
 }
 
@@ -310,9 +454,21 @@ pub struct NodeVersion {
     patch: u32,
 }
 
+/// A build listed in the index of a channel.
+struct NodeBuild {
+    version: NodeVersion,
+    raw_version: SmolStr,
+    is_lts: bool,
+    /// The directory of the channel.
+    base_url: &'static str,
+}
+
+#[derive(Clone)]
 struct NodeVersionFilter {
     lts_only: bool,
+    include_prerelease: bool,
     version_prefix: Option<crate::tool::VersionPrefix>,
+    version_range: Option<crate::tool::VersionRange>,
     exact_version: Option<SmolStr>,
 }
 
@@ -321,12 +477,22 @@ impl NodeVersionFilter {
         if self.lts_only && !is_lts {
             return false;
         }
+        if !self.include_prerelease && prerelease(raw_version).is_some() {
+            return false;
+        }
         if self
             .version_prefix
             .is_some_and(|p| !p.matches(version.major, version.minor, version.patch))
         {
             return false;
         }
+        if self
+            .version_range
+            .as_ref()
+            .is_some_and(|r| !r.matches(version.major, version.minor, version.patch))
+        {
+            return false;
+        }
         if self
             .exact_version
             .as_ref()
@@ -336,6 +502,24 @@ impl NodeVersionFilter {
         }
         true
     }
+
+    /// Whether the release candidates may match besides the releases, which are in another
+    /// index.
+    fn searches_release_candidates(&self) -> bool {
+        match &self.exact_version {
+            Some(exact_version) => prerelease(exact_version).is_some(),
+            None => self.include_prerelease,
+        }
+    }
+
+    /// Keeps a matching release, stopping the index parsing if no other release can match.
+    fn found<U>(&self, item: U) -> JsonItemAction<U> {
+        if self.exact_version.is_some() {
+            JsonItemAction::KeepAndStop(item)
+        } else {
+            JsonItemAction::Keep(item)
+        }
+    }
 }
 
 impl TryFrom<&VersionFilter> for NodeVersionFilter {
@@ -344,12 +528,129 @@ impl TryFrom<&VersionFilter> for NodeVersionFilter {
     fn try_from(value: &VersionFilter) -> Result<Self, Self::Error> {
         Ok(Self {
             lts_only: value.lts_only,
+            include_prerelease: value.includes_prerelease(),
             version_prefix: value.version_prefix,
+            version_range: value.version_range.clone(),
             exact_version: value.exact_version.clone(),
         })
     }
 }
+/// Runs `corepack enable` in the Node installed in `tag_dir`, adding the `yarn` and `pnpm`
+/// shims next to `node`.
+async fn enable_corepack(tag_dir: PathBuf) -> crate::Result<()> {
+    let bin_dir = bin_dir(&tag_dir);
+    let install_dir = OsString::from(&bin_dir);
+    run_script(
+        bin_dir,
+        "corepack",
+        vec!["enable".into(), "--install-directory".into(), install_dir],
+    )
+    .await
+}
+
+/// Replaces the npm bundled with the Node installed in `tag_dir` by `version`. The global
+/// prefix of npm is the tag directory, so npm replaces itself.
+async fn install_npm(tag_dir: PathBuf, version: &str) -> crate::Result<()> {
+    run_script(
+        bin_dir(&tag_dir),
+        "npm",
+        vec![
+            "install".into(),
+            "--global".into(),
+            format!("npm@{version}").into(),
+        ],
+    )
+    .await
+}
+
+/// Runs a script of `bin_dir`, which finds `node` through `PATH`.
+async fn run_script(
+    bin_dir: PathBuf,
+    name: &'static str,
+    args: Vec<OsString>,
+) -> crate::Result<()> {
+    crate::spawn_blocking(move || {
+        let script = script_path(&bin_dir, name);
+        if !script.exists() {
+            anyhow::bail!("`{name}` is not bundled with this Node version");
+        }
+        let paths = std::env::var_os("PATH").unwrap_or_default();
+        let paths = std::iter::once(bin_dir).chain(std::env::split_paths(&paths));
+        let status = std::process::Command::new(&script)
+            .args(args)
+            .env("PATH", std::env::join_paths(paths)?)
+            .status()?;
+        if !status.success() {
+            anyhow::bail!("`{name}` failed with {status}");
+        }
+        Ok(())
+    })
+    .await?;
+    Ok(())
+}
+
+/// `corepack` and the shims `corepack enable` adds.
+const COREPACK_SCRIPTS: &[&str] = &["corepack", "yarn", "yarnpkg", "pnpm", "pnpx"];
+
+/// Where `node` and the scripts are, the tag directory itself on Windows.
+fn bin_dir(tag_dir: &Path) -> PathBuf {
+    if cfg!(windows) {
+        tag_dir.to_path_buf()
+    } else {
+        tag_dir.join("bin")
+    }
+}
+
+/// Scripts are run through `.cmd` wrappers on Windows.
+fn script_path(bin_dir: &Path, name: &str) -> PathBuf {
+    let suffix = if cfg!(windows) { ".cmd" } else { "" };
+    bin_dir.join(format!("{name}{suffix}"))
+}
+
+/// The flavor of a release channel, `None` for `release`.
+fn channel_flavor(channel: &str) -> crate::Result<Option<SmolStr>> {
+    if channel == "release" {
+        return Ok(None);
+    }
+    match CHANNELS.iter().find(|(name, _)| *name == channel) {
+        Some((name, _)) => Ok(Some(SmolStr::new(name))),
+        None => Err(anyhow::anyhow!(
+            "Unknown Node channel `{channel}`, the channels are: release, rc, nightly"
+        )
+        .into()),
+    }
+}
+
+/// The directory of the index and the builds of the channel of a flavor.
+fn base_url(flavor: Option<&str>) -> anyhow::Result<&'static str> {
+    let Some(flavor) = flavor else {
+        return Ok(BASE_URL);
+    };
+    CHANNELS
+        .iter()
+        .find(|(channel, _)| *channel == flavor)
+        .map(|(_, url)| *url)
+        .ok_or_else(|| anyhow::anyhow!("Unsupported Node flavor: {flavor}"))
+}
+
+/// The suffix of an rc or nightly build, such as `rc.1` or `nightly20250101<commit>`.
+fn prerelease(raw_version: &str) -> Option<&str> {
+    raw_version.split_once('-').map(|(_, suffix)| suffix)
+}
+
+/// Orders builds by version, then the rc and nightly builds of a version, by their suffix,
+/// before its release.
+fn build_order(raw_version: &str, version: &NodeVersion) -> (NodeVersion, bool, SmolStr) {
+    let prerelease = prerelease(raw_version);
+    (
+        version.clone(),
+        prerelease.is_none(),
+        prerelease.map(SmolStr::new).unwrap_or_default(),
+    )
+}
+
 /// Parses a Node.js version string and returns the trimmed version string and parsed NodeVersion.
+/// The suffix of an rc or nightly build is kept in the trimmed version only.
 pub fn parse_node_version(s: &str) -> anyhow::Result<(&str, NodeVersion)> {
     // Remove 'v' prefix if present
     let raw_version = s.strip_prefix('v').unwrap_or(s);
@@ -358,7 +659,10 @@ pub fn parse_node_version(s: &str) -> anyhow::Result<(&str, NodeVersion)> {
     }
 
     // Split into major.minor.patch
-    let parts: Vec<&str> = raw_version.split('.').collect();
+    let numbers = raw_version
+        .split_once('-')
+        .map_or(raw_version, |(numbers, _)| numbers);
+    let parts: Vec<&str> = numbers.split('.').collect();
     if parts.len() != 3 {
         return Err(anyhow::anyhow!("Invalid version format: {}", s));
     }
@@ -1180,4 +1484,32 @@ mod tests {
         assert_eq!(parse_node_version("v0.1.15").unwrap(), ("0.1.15", NodeVersion { major: 0, minor: 1, patch: 15 }));
         assert_eq!(parse_node_version("v0.1.14").unwrap(), ("0.1.14", NodeVersion { major: 0, minor: 1, patch: 14 }));
     }
+
+    #[test]
+    fn orders_channel_builds_before_release() {
+        let order = |raw: &str| {
+            let (raw, version) = parse_node_version(raw).unwrap();
+            build_order(raw, &version)
+        };
+        assert_eq!(
+            parse_node_version("v24.0.0-nightly20250101a1b2c3").unwrap(),
+            (
+                "24.0.0-nightly20250101a1b2c3",
+                NodeVersion {
+                    major: 24,
+                    minor: 0,
+                    patch: 0
+                }
+            )
+        );
+        assert!(order("v23.0.0-rc.1") < order("v23.0.0-rc.2"));
+        assert!(order("v23.0.0-rc.2") < order("v23.0.0"));
+        assert!(order("v22.11.0") < order("v23.0.0-rc.1"));
+        assert_eq!(channel_flavor("release").unwrap(), None);
+        assert_eq!(
+            channel_flavor("nightly").unwrap().as_deref(),
+            Some("nightly")
+        );
+        assert!(channel_flavor("beta").is_err());
+    }
 }
@@ -0,0 +1,189 @@
+use serde::Deserialize;
+use smol_str::SmolStr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use super::manifest::{best_local_tag, sorted_versions, ReleaseVersion};
+use super::terraform::{all_platforms, dto_os_arch, find_sha256, ignore_lts_only};
+use crate::platform::{resolve_default_platform, MUSL_TO_GLIBC};
+use crate::tool::{ToolDownInfo, ToolInfo, Version, VersionFilter};
+use crate::HttpClient;
+
+pub struct Tool {
+    client: Arc<HttpClient>,
+    info: ToolInfo,
+}
+
+/// Every release with the names of its files.
+const API_URL: &str = "https://get.opentofu.org/tofu/api.json";
+const DOWNLOAD_URL: &str = "https://github.com/opentofu/opentofu/releases/download/";
+
+impl crate::tool::GeneralTool for Tool {
+    fn info(&self) -> &ToolInfo {
+        &self.info
+    }
+
+    fn endpoints(&self) -> Vec<SmolStr> {
+        vec![API_URL.into()]
+    }
+
+    async fn fetch_versions(
+        &self,
+        platform: Option<SmolStr>,
+        _flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<Vec<Version>> {
+        let platform = platform.ok_or(crate::AvmError::PlatformRequired)?;
+        let version_filter = ignore_lts_only(version_filter, "opentofu");
+        let api = self.fetch_api().await?;
+        Ok(api.matching_versions(&platform, &version_filter)?)
+    }
+
+    async fn get_down_info(
+        &self,
+        platform: Option<SmolStr>,
+        _flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<ToolDownInfo> {
+        let platform = platform.ok_or(crate::AvmError::PlatformRequired)?;
+        let version_filter = ignore_lts_only(version_filter, "opentofu");
+        let api = self.fetch_api().await?;
+        let versions = api.matching_versions(&platform, &version_filter)?;
+        let version = versions.last().ok_or(crate::AvmError::VersionNotFound)?;
+
+        let release_url = format!("{DOWNLOAD_URL}v{}/", version.version);
+        let file_name = archive_name(&version.version, &platform)?;
+        let sums_name = format!("tofu_{}_SHA256SUMS", version.version);
+        let sums = self
+            .client
+            .get(&format!("{release_url}{sums_name}"))
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        let sha256 = find_sha256(&sums, &file_name)
+            .ok_or_else(|| anyhow::anyhow!("No checksum for {file_name} in {sums_name}"))?;
+        Ok(ToolDownInfo {
+            version: version.clone(),
+            url: smol_str::format_smolstr!("{release_url}{file_name}"),
+            hash: crate::FileHash {
+                sha256: Some(sha256),
+                ..Default::default()
+            },
+        })
+    }
+
+    fn find_best_matching_local_tag<'a, I>(
+        &self,
+        tags_and_versions: I,
+        version_filter: &VersionFilter,
+    ) -> Option<SmolStr>
+    where
+        I: Iterator<Item = (&'a str, &'a Version)>,
+    {
+        let version_filter = ignore_lts_only(version_filter.clone(), "opentofu");
+        best_local_tag(tags_and_versions, &version_filter)
+    }
+
+    fn entry_path(&self, tag_dir: PathBuf) -> crate::Result<PathBuf> {
+        Ok(super::exes_in(&tag_dir, &["tofu"]).swap_remove(0).1)
+    }
+}
+
+impl Tool {
+    pub fn new(client: Arc<HttpClient>, config_default_platform: Option<SmolStr>) -> Self {
+        let all_platforms = all_platforms();
+        // OpenTofu is a statically linked Go binary, the Linux builds also run on musl.
+        let default_platform =
+            resolve_default_platform(&all_platforms, config_default_platform, &[MUSL_TO_GLIBC]);
+        Tool {
+            client,
+            info: ToolInfo {
+                about: "OpenTofu infrastructure as code tool, an open-source Terraform fork".into(),
+                after_long_help: None,
+                all_platforms: Some(all_platforms),
+                default_platform,
+                all_flavors: None,
+                default_flavor: None,
+            },
+        }
+    }
+
+    async fn fetch_api(&self) -> anyhow::Result<ApiDto> {
+        Ok(self
+            .client
+            .get(API_URL)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+}
+
+fn archive_name(version: &str, platform: &str) -> anyhow::Result<String> {
+    let (os, arch) = dto_os_arch(platform)?;
+    Ok(format!("tofu_{version}_{os}_{arch}.zip"))
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiDto {
+    versions: Vec<ReleaseDto>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReleaseDto {
+    id: SmolStr,
+    files: Vec<SmolStr>,
+}
+
+impl ApiDto {
+    fn matching_versions(
+        &self,
+        platform: &str,
+        version_filter: &VersionFilter,
+    ) -> anyhow::Result<Vec<Version>> {
+        let mut versions = Vec::new();
+        for release in &self.versions {
+            let file_name = archive_name(&release.id, platform)?;
+            if !release.files.iter().any(|file| *file == file_name) {
+                continue;
+            }
+            if let Some(version) = ReleaseVersion::parse(release.id.clone(), false, None) {
+                if version.matches(version_filter) {
+                    versions.push(version);
+                }
+            }
+        }
+        Ok(sorted_versions(versions.into_iter()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lists_releases_with_platform_archives() {
+        let api: ApiDto = serde_json::from_str(
+            r#"{"versions": [
+                {"id": "1.9.0-beta1", "files": ["tofu_1.9.0-beta1_linux_amd64.zip"]},
+                {"id": "1.8.5", "files": ["tofu_1.8.5_linux_amd64.zip", "tofu_1.8.5_linux_amd64.tar.gz", "tofu_1.8.5_SHA256SUMS"]},
+                {"id": "1.8.4", "files": ["tofu_1.8.4_linux_amd64.tar.gz"]}
+            ]}"#,
+        )
+        .unwrap();
+        let filter = VersionFilter {
+            include_prerelease: true,
+            ..Default::default()
+        };
+        let versions = api
+            .matching_versions("x64-linux", &filter)
+            .unwrap()
+            .into_iter()
+            .map(|version| version.version)
+            .collect::<Vec<_>>();
+        assert_eq!(versions, ["1.8.5", "1.9.0-beta1"]);
+    }
+}
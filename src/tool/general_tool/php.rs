@@ -0,0 +1,272 @@
+use serde::Deserialize;
+use smol_str::SmolStr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use super::manifest::{best_local_tag, sorted_versions, ReleaseVersion};
+use super::terraform::ignore_lts_only;
+use crate::platform::{cpu, create_platform_string, os, resolve_default_platform, MUSL_TO_GLIBC};
+use crate::tool::end_of_life::{EndOfLife, SupportStatus};
+use crate::tool::{ToolDownInfo, ToolInfo, Version, VersionFilter};
+use crate::HttpClient;
+
+pub struct Tool {
+    client: Arc<HttpClient>,
+    info: ToolInfo,
+    end_of_life: EndOfLife,
+}
+
+/// The static builds of static-php-cli with the common extensions.
+const BASE_URL: &str = "https://dl.static-php.dev/static-php-cli/common/";
+
+/// Platforms by the suffix of the file names, `php-<version>-<flavor>-<suffix>`.
+const PLATFORMS: [(&str, &str, &str); 5] = [
+    (cpu::X64, os::LINUX, "linux-x86_64.tar.gz"),
+    (cpu::ARM64, os::LINUX, "linux-aarch64.tar.gz"),
+    (cpu::X64, os::MAC, "macos-x86_64.tar.gz"),
+    (cpu::ARM64, os::MAC, "macos-aarch64.tar.gz"),
+    (cpu::X64, os::WIN, "win.zip"),
+];
+
+/// The SAPIs the builds are made for, which are the flavors.
+const FLAVORS: [&str; 2] = ["cli", "fpm"];
+const DEFAULT_FLAVOR: &str = "cli";
+
+impl crate::tool::GeneralTool for Tool {
+    fn info(&self) -> &ToolInfo {
+        &self.info
+    }
+
+    fn describe_flavor(&self, flavor: &str) -> &'static str {
+        match flavor {
+            "cli" => "The `php` command-line interpreter.",
+            "fpm" => {
+                "The `php-fpm` FastCGI process manager, for web servers. Not built for Windows."
+            }
+            _ => "Tool-specific build flavor.",
+        }
+    }
+
+    fn endpoints(&self) -> Vec<SmolStr> {
+        vec![
+            smol_str::format_smolstr!("{BASE_URL}?format=json"),
+            self.end_of_life.url(),
+        ]
+    }
+
+    async fn support_status(&self, version: &str) -> crate::Result<Option<SupportStatus>> {
+        self.end_of_life.support_status(version).await
+    }
+
+    async fn fetch_versions(
+        &self,
+        platform: Option<SmolStr>,
+        flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<Vec<Version>> {
+        let platform = platform.ok_or(crate::AvmError::PlatformRequired)?;
+        let version_filter = ignore_lts_only(version_filter, "php");
+        let suffix = file_suffix(&platform)?;
+        let files = self.fetch_files().await?;
+        Ok(sorted_versions(
+            matching_files(&files, sapi(flavor.as_deref()), suffix, &version_filter)
+                .map(|(version, _)| version),
+        ))
+    }
+
+    async fn get_down_info(
+        &self,
+        platform: Option<SmolStr>,
+        flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<ToolDownInfo> {
+        let platform = platform.ok_or(crate::AvmError::PlatformRequired)?;
+        let version_filter = ignore_lts_only(version_filter, "php");
+        let suffix = file_suffix(&platform)?;
+        let flavor = sapi(flavor.as_deref());
+        let files = self.fetch_files().await?;
+        let versions = sorted_versions(
+            matching_files(&files, flavor, suffix, &version_filter).map(|(version, _)| version),
+        );
+        let version = versions.last().ok_or(crate::AvmError::VersionNotFound)?;
+        // static-php-cli publishes no checksums.
+        Ok(ToolDownInfo {
+            version: version.clone(),
+            url: smol_str::format_smolstr!(
+                "{BASE_URL}{}",
+                file_name(&version.version, flavor, suffix)
+            ),
+            hash: crate::FileHash::default(),
+        })
+    }
+
+    fn find_best_matching_local_tag<'a, I>(
+        &self,
+        tags_and_versions: I,
+        version_filter: &VersionFilter,
+    ) -> Option<SmolStr>
+    where
+        I: Iterator<Item = (&'a str, &'a Version)>,
+    {
+        let version_filter = ignore_lts_only(version_filter.clone(), "php");
+        best_local_tag(tags_and_versions, &version_filter)
+    }
+
+    /// `php` of the `cli` flavor, otherwise `php-fpm` of the `fpm` one.
+    fn entry_path(&self, tag_dir: PathBuf) -> crate::Result<PathBuf> {
+        let exes = self.exe_paths(tag_dir)?;
+        let found = exes
+            .iter()
+            .find(|(_, path)| path.exists())
+            .unwrap_or(&exes[0]);
+        Ok(found.1.clone())
+    }
+
+    fn exe_paths(&self, tag_dir: PathBuf) -> crate::Result<Vec<(SmolStr, PathBuf)>> {
+        Ok(super::exes_in(&tag_dir, &["php", "php-fpm"]))
+    }
+}
+
+impl Tool {
+    pub fn new(client: Arc<HttpClient>, config_default_platform: Option<SmolStr>) -> Self {
+        let all_platforms = PLATFORMS
+            .iter()
+            .map(|(cpu, os, _)| create_platform_string(cpu, os))
+            .collect::<Vec<_>>();
+        // The Linux builds are statically linked, they also run on musl.
+        let default_platform =
+            resolve_default_platform(&all_platforms, config_default_platform, &[MUSL_TO_GLIBC]);
+        let end_of_life = EndOfLife::new(client.clone(), "php");
+        Tool {
+            client,
+            info: ToolInfo {
+                about: "PHP, the static builds of static-php-cli".into(),
+                after_long_help: Some(
+                    r#"The flavor selects the SAPI, `cli` for `php` or `fpm` for `php-fpm`. The builds include the common extensions and need no system libraries.
+static-php-cli publishes no checksums, so the archives are not verified."#
+                        .into(),
+                ),
+                all_platforms: Some(all_platforms),
+                default_platform,
+                all_flavors: Some(FLAVORS.iter().map(SmolStr::new).collect()),
+                default_flavor: Some(DEFAULT_FLAVOR.into()),
+            },
+            end_of_life,
+        }
+    }
+
+    async fn fetch_files(&self) -> anyhow::Result<Vec<FileDto>> {
+        Ok(self
+            .client
+            .get(&format!("{BASE_URL}?format=json"))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+}
+
+/// An entry of the directory listing.
+#[derive(Debug, Deserialize)]
+struct FileDto {
+    /// Such as `php-8.3.7-cli-linux-x86_64.tar.gz`.
+    name: SmolStr,
+    #[serde(default)]
+    is_dir: bool,
+}
+
+fn sapi(flavor: Option<&str>) -> &str {
+    flavor.unwrap_or(DEFAULT_FLAVOR)
+}
+
+fn file_suffix(platform: &str) -> anyhow::Result<&'static str> {
+    PLATFORMS
+        .iter()
+        .find(|(cpu, os, _)| create_platform_string(cpu, os) == platform)
+        .map(|(_, _, suffix)| *suffix)
+        .ok_or_else(|| anyhow::anyhow!("Unsupported platform: {platform}"))
+}
+
+fn file_name(version: &str, flavor: &str, suffix: &str) -> String {
+    format!("php-{version}-{flavor}-{suffix}")
+}
+
+/// The version of a file name of the flavor and platform, only the `major.minor.patch` releases
+/// that static-php-cli builds.
+fn parse_file_name<'a>(name: &'a str, flavor: &str, suffix: &str) -> Option<&'a str> {
+    let version = name
+        .strip_prefix("php-")?
+        .strip_suffix(suffix)?
+        .strip_suffix('-')?
+        .strip_suffix(flavor)?
+        .strip_suffix('-')?;
+    let mut parts = version.split('.');
+    let is_release = parts.clone().count() == 3 && parts.all(|p| p.parse::<u32>().is_ok());
+    is_release.then_some(version)
+}
+
+fn matching_files<'a>(
+    files: &'a [FileDto],
+    flavor: &'a str,
+    suffix: &'a str,
+    version_filter: &'a VersionFilter,
+) -> impl Iterator<Item = (ReleaseVersion, &'a FileDto)> + 'a {
+    files.iter().filter_map(move |file| {
+        if file.is_dir {
+            return None;
+        }
+        let version = parse_file_name(&file.name, flavor, suffix)?;
+        let version = ReleaseVersion::parse(version.into(), false, Some(false))?;
+        version.matches(version_filter).then_some((version, file))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tool::VersionPrefix;
+
+    #[test]
+    fn selects_builds_of_the_flavor_and_major() {
+        let files: Vec<FileDto> = serde_json::from_str(
+            r#"[
+                {"name": "bulk", "is_dir": true},
+                {"name": "php-8.3.7-cli-linux-x86_64.tar.gz", "is_dir": false},
+                {"name": "php-8.3.7-fpm-linux-x86_64.tar.gz", "is_dir": false},
+                {"name": "php-8.3.7-micro-linux-x86_64.tar.gz", "is_dir": false},
+                {"name": "php-8.2.19-cli-linux-x86_64.tar.gz", "is_dir": false},
+                {"name": "php-8.3.7-cli-linux-aarch64.tar.gz", "is_dir": false},
+                {"name": "php-8.3-cli-linux-x86_64.tar.gz", "is_dir": false},
+                {"name": "php-7.4.33-cli-win.zip", "is_dir": false}
+            ]"#,
+        )
+        .unwrap();
+        let filter = |prefix: Option<&str>| VersionFilter {
+            version_prefix: prefix.map(|p| VersionPrefix::parse(p).unwrap()),
+            ..Default::default()
+        };
+        let versions = |flavor, platform, filter: VersionFilter| {
+            let suffix = file_suffix(platform).unwrap();
+            sorted_versions(matching_files(&files, flavor, suffix, &filter).map(|(v, _)| v))
+                .into_iter()
+                .map(|version| version.version)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            versions("cli", "x64-linux", filter(None)),
+            ["8.2.19", "8.3.7"]
+        );
+        assert_eq!(versions("fpm", "x64-linux", filter(None)), ["8.3.7"]);
+        assert_eq!(
+            versions("cli", "x64-linux", filter(Some("8.2"))),
+            ["8.2.19"]
+        );
+        assert!(versions("cli", "x64-win", filter(Some("8"))).is_empty());
+        assert_eq!(versions("cli", "x64-win", filter(Some("7"))), ["7.4.33"]);
+        assert_eq!(
+            file_name("8.3.7", "fpm", file_suffix("arm64-mac").unwrap()),
+            "php-8.3.7-fpm-macos-aarch64.tar.gz"
+        );
+    }
+}
@@ -0,0 +1,328 @@
+//! Tools provided by external executables, in the spirit of asdf plugins.
+//!
+//! A plugin is a directory holding a `list-versions` and a `download-info` executable.
+//! `list-versions` prints a JSON array of versions, `download-info <version>` prints a JSON
+//! object with the download URL and hashes. The selected platform, if any, is passed in the
+//! `AVM_PLATFORM` environment variable.
+
+use anyhow::Context as _;
+use serde::Deserialize;
+use smol_str::SmolStr;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use super::manifest::{
+    best_local_tag, is_valid_name, resolve_default_platform, sorted_versions, ReleaseVersion,
+};
+use crate::tool::{ToolDownInfo, ToolInfo, Version, VersionFilter};
+
+const LIST_VERSIONS: &str = "list-versions";
+const DOWNLOAD_INFO: &str = "download-info";
+const SPEC_FILE: &str = "plugin.toml";
+
+/// Optional `plugin.toml` in the plugin directory.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct PluginSpec {
+    #[serde(default)]
+    about: Option<SmolStr>,
+    /// Entry path relative to the tag directory. `{exe}` expands to `.exe` on Windows.
+    /// Default: `bin/<name>{exe}`.
+    #[serde(default)]
+    entry_path: Option<SmolStr>,
+    /// Platforms the plugin can install. The tool has no distinct platforms if empty.
+    #[serde(default)]
+    platforms: Vec<SmolStr>,
+}
+
+/// A plugin found by [`discover`].
+pub struct Plugin {
+    dir: PathBuf,
+    spec: PluginSpec,
+}
+
+/// Finds the plugins in `dir`, one per subdirectory, keyed by directory name.
+/// A missing directory has none. A subdirectory whose name is not a valid tool name is
+/// skipped with a warning.
+pub fn discover(dir: &Path) -> crate::Result<Vec<(SmolStr, Plugin)>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut plugins = Vec::new();
+    for entry in entries {
+        let dir = entry?.path();
+        if !dir.is_dir() {
+            continue;
+        }
+        let Some(name) = dir
+            .file_name()
+            .and_then(|s| s.to_str())
+            .filter(|s| is_valid_name(s))
+        else {
+            log::warn!(
+                "Skipping plugin '{}', its name should only have lowercase letters, digits and '-'",
+                dir.display()
+            );
+            continue;
+        };
+        let plugin = Plugin::load(dir.clone())
+            .with_context(|| format!("Failed to load plugin '{}'", dir.display()))?;
+        plugins.push((SmolStr::new(name), plugin));
+    }
+    plugins.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(plugins)
+}
+
+impl Plugin {
+    fn load(dir: PathBuf) -> anyhow::Result<Self> {
+        for script in [LIST_VERSIONS, DOWNLOAD_INFO] {
+            find_script(&dir, script)?;
+        }
+        let spec = match std::fs::read_to_string(dir.join(SPEC_FILE)) {
+            Ok(content) => toml::from_str(&content)
+                .with_context(|| format!("Failed to parse '{SPEC_FILE}'"))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => PluginSpec::default(),
+            Err(e) => return Err(e.into()),
+        };
+        Ok(Self { dir, spec })
+    }
+}
+
+fn find_script(dir: &Path, name: &str) -> anyhow::Result<PathBuf> {
+    let extensions: &[&str] = if cfg!(windows) {
+        &["exe", "cmd", "bat"]
+    } else {
+        &[""]
+    };
+    extensions
+        .iter()
+        .map(|ext| dir.join(name).with_extension(ext))
+        .find(|path| path.is_file())
+        .ok_or_else(|| anyhow::anyhow!("Missing `{name}` executable"))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum VersionDto {
+    Plain(SmolStr),
+    Detailed {
+        version: SmolStr,
+        #[serde(default)]
+        lts: bool,
+        #[serde(default)]
+        prerelease: Option<bool>,
+    },
+}
+
+#[derive(Deserialize)]
+struct DownloadInfoDto {
+    url: SmolStr,
+    #[serde(flatten)]
+    hash: crate::FileHash,
+}
+
+pub struct Tool {
+    name: SmolStr,
+    plugin: Plugin,
+    info: ToolInfo,
+}
+
+impl crate::tool::GeneralTool for Tool {
+    fn info(&self) -> &ToolInfo {
+        &self.info
+    }
+
+    fn endpoints(&self) -> Vec<SmolStr> {
+        Vec::new()
+    }
+
+    async fn fetch_versions(
+        &self,
+        platform: Option<SmolStr>,
+        _flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<Vec<Version>> {
+        let versions = self.list_versions(platform).await?;
+        Ok(sorted_versions(
+            versions.into_iter().filter(|v| v.matches(&version_filter)),
+        ))
+    }
+
+    async fn get_down_info(
+        &self,
+        platform: Option<SmolStr>,
+        _flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<ToolDownInfo> {
+        let version = self
+            .fetch_versions(platform.clone(), None, version_filter)
+            .await?
+            .pop()
+            .ok_or(crate::AvmError::VersionNotFound)?;
+
+        let output = self
+            .invoke(DOWNLOAD_INFO, Some(version.version.clone()), platform)
+            .await?;
+        let info: DownloadInfoDto = serde_json::from_slice(&output)
+            .with_context(|| format!("Invalid JSON output of `{DOWNLOAD_INFO}`"))?;
+        Ok(ToolDownInfo {
+            version,
+            url: info.url,
+            hash: info.hash,
+        })
+    }
+
+    fn find_best_matching_local_tag<'a, I>(
+        &self,
+        tags_and_versions: I,
+        version_filter: &VersionFilter,
+    ) -> Option<SmolStr>
+    where
+        I: Iterator<Item = (&'a str, &'a Version)>,
+    {
+        best_local_tag(tags_and_versions, version_filter)
+    }
+
+    fn entry_path(&self, tag_dir: PathBuf) -> crate::Result<PathBuf> {
+        let exe = if cfg!(windows) { ".exe" } else { "" };
+        let entry_path = match &self.plugin.spec.entry_path {
+            Some(entry_path) => entry_path.replace("{exe}", exe),
+            None => format!("bin/{}{exe}", self.name),
+        };
+        Ok(tag_dir.join(entry_path))
+    }
+}
+
+impl Tool {
+    pub fn new(name: SmolStr, plugin: Plugin, config_default_platform: Option<SmolStr>) -> Self {
+        let all_platforms =
+            (!plugin.spec.platforms.is_empty()).then(|| plugin.spec.platforms.clone());
+        let default_platform = all_platforms.as_deref().and_then(|all_platforms| {
+            resolve_default_platform(all_platforms, config_default_platform)
+        });
+        let about = plugin
+            .spec
+            .about
+            .clone()
+            .unwrap_or_else(|| smol_str::format_smolstr!("Plugin at {}", plugin.dir.display()));
+
+        Tool {
+            name,
+            info: ToolInfo {
+                about,
+                after_long_help: None,
+                all_platforms,
+                default_platform,
+                all_flavors: None,
+                default_flavor: None,
+            },
+            plugin,
+        }
+    }
+
+    async fn list_versions(
+        &self,
+        platform: Option<SmolStr>,
+    ) -> anyhow::Result<Vec<ReleaseVersion>> {
+        let output = self.invoke(LIST_VERSIONS, None, platform).await?;
+        let versions: Vec<VersionDto> = serde_json::from_slice(&output)
+            .with_context(|| format!("Invalid JSON output of `{LIST_VERSIONS}`"))?;
+        Ok(versions
+            .into_iter()
+            .filter_map(|v| match v {
+                VersionDto::Plain(version) => ReleaseVersion::parse(version, false, None),
+                VersionDto::Detailed {
+                    version,
+                    lts,
+                    prerelease,
+                } => ReleaseVersion::parse(version, lts, prerelease),
+            })
+            .collect())
+    }
+
+    /// Runs a plugin executable and returns its stdout. Its stderr is passed through.
+    async fn invoke(
+        &self,
+        script: &'static str,
+        arg: Option<SmolStr>,
+        platform: Option<SmolStr>,
+    ) -> anyhow::Result<Vec<u8>> {
+        let path = find_script(&self.plugin.dir, script)?;
+        let dir = self.plugin.dir.clone();
+        crate::spawn_blocking(move || {
+            let mut command = Command::new(&path);
+            command
+                .args(arg.as_deref())
+                .current_dir(&dir)
+                .env("AVM_PLUGIN_DIR", &dir)
+                .stdin(Stdio::null())
+                .stderr(Stdio::inherit());
+            match &platform {
+                Some(platform) => command.env("AVM_PLATFORM", platform.as_str()),
+                None => command.env_remove("AVM_PLATFORM"),
+            };
+            let output = command
+                .output()
+                .with_context(|| format!("Failed to run '{}'", path.display()))?;
+            if !output.status.success() {
+                anyhow::bail!("'{}' failed with {}", path.display(), output.status);
+            }
+            Ok(output.stdout)
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_version_dto() {
+        let versions: Vec<VersionDto> = serde_json::from_str(
+            r#"["1.0.0", {"version": "2.0.0", "lts": true}, {"version": "3.0.0", "prerelease": true}]"#,
+        )
+        .unwrap();
+        assert!(matches!(&versions[0], VersionDto::Plain(v) if v == "1.0.0"));
+        assert!(matches!(
+            &versions[1],
+            VersionDto::Detailed { lts: true, .. }
+        ));
+        assert!(matches!(
+            &versions[2],
+            VersionDto::Detailed {
+                prerelease: Some(true),
+                ..
+            }
+        ));
+
+        let info: DownloadInfoDto =
+            serde_json::from_str(r#"{"url": "https://example.com/a.zip", "sha256": "ab"}"#)
+                .unwrap();
+        assert_eq!(info.url, "https://example.com/a.zip");
+        assert_eq!(info.hash.sha256.as_deref(), Some("ab"));
+    }
+
+    #[test]
+    fn test_discover_skips_invalid_names() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["fixture", "Not_Valid"] {
+            let plugin = dir.path().join(name);
+            std::fs::create_dir(&plugin).unwrap();
+            for script in [LIST_VERSIONS, DOWNLOAD_INFO] {
+                let script = plugin.join(script);
+                std::fs::write(&script, "").unwrap();
+                if cfg!(windows) {
+                    std::fs::rename(&script, script.with_extension("cmd")).unwrap();
+                }
+            }
+        }
+
+        let plugins = discover(dir.path()).unwrap();
+        let names: Vec<_> = plugins.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["fixture"]);
+    }
+}
@@ -22,12 +22,16 @@ impl crate::tool::GeneralTool for Tool {
         &self.info
     }
 
+    fn endpoints(&self) -> Vec<SmolStr> {
+        vec![REGISTRY_URL.into()]
+    }
+
     async fn fetch_versions(
         &self,
         _platform: Option<SmolStr>,
         _flavor: Option<SmolStr>,
         version_filter: VersionFilter,
-    ) -> anyhow::Result<Vec<Version>> {
+    ) -> crate::Result<Vec<Version>> {
         let version_filter = ignore_lts_only(version_filter);
         let version_filter = PnpmVersionFilter::try_from(&version_filter)?;
 
@@ -63,7 +67,7 @@ impl crate::tool::GeneralTool for Tool {
         _platform: Option<SmolStr>,
         _flavor: Option<SmolStr>,
         version_filter: VersionFilter,
-    ) -> anyhow::Result<ToolDownInfo> {
+    ) -> crate::Result<ToolDownInfo> {
         let version_filter = ignore_lts_only(version_filter);
         let version_filter = PnpmVersionFilter::try_from(&version_filter)?;
 
@@ -94,7 +98,7 @@ impl crate::tool::GeneralTool for Tool {
                     ..Default::default()
                 },
             }),
-            None => Err(anyhow::anyhow!("No download URL found.")),
+            None => Err(crate::AvmError::VersionNotFound),
         }
     }
 
@@ -121,23 +125,32 @@ impl crate::tool::GeneralTool for Tool {
             .map(|(_, tag)| tag)
     }
 
-    fn entry_path(&self, tag_dir: PathBuf) -> anyhow::Result<PathBuf> {
+    fn entry_path(&self, tag_dir: PathBuf) -> crate::Result<PathBuf> {
         let mut p = tag_dir;
         p.push("bin");
         p.push("pnpm.cjs");
         Ok(p)
     }
 
+    fn exe_paths(&self, tag_dir: PathBuf) -> crate::Result<Vec<(SmolStr, PathBuf)>> {
+        let bin_dir = tag_dir.join("bin");
+        Ok(vec![
+            ("pnpm".into(), bin_dir.join("pnpm.cjs")),
+            ("pnpx".into(), bin_dir.join("pnpx.cjs")),
+        ])
+    }
+
     #[cfg(windows)]
-    async fn run(&self, entry_path: PathBuf, args: Vec<OsString>) -> anyhow::Result<()> {
-        crate::spawn_blocking(move || {
-            let mut command = std::process::Command::new("node.exe");
-            command.arg(entry_path);
-            command.args(args);
-            command.spawn()?.wait()?;
-            Ok(())
-        })
-        .await
+    fn command(
+        &self,
+        entry_path: PathBuf,
+        args: Vec<OsString>,
+        env_vars: Vec<(OsString, OsString)>,
+    ) -> crate::Result<std::process::Command> {
+        let mut command = std::process::Command::new("node.exe");
+        command.arg(entry_path);
+        command.args(args).envs(env_vars);
+        Ok(command)
     }
 }
 
@@ -159,7 +172,10 @@ impl Tool {
     async fn fetch_registry(&self, client: &HttpClient) -> anyhow::Result<RegistryDto> {
         client
             .get(REGISTRY_URL)
-            .header("Accept", "application/vnd.npm.install-v1+json")
+            .header(
+                reqwest::header::ACCEPT,
+                reqwest::header::HeaderValue::from_static("application/vnd.npm.install-v1+json"),
+            )
             .send()
             .await?
             .error_for_status()?
@@ -237,14 +253,15 @@ impl Ord for PnpmVersion {
 }
 
 struct PnpmVersionFilter {
-    allow_prerelease: bool,
+    include_prerelease: bool,
     version_prefix: Option<crate::tool::VersionPrefix>,
+    version_range: Option<crate::tool::VersionRange>,
     exact_version: Option<SmolStr>,
 }
 
 impl PnpmVersionFilter {
     fn matches(&self, raw_version: &str, version: &PnpmVersion) -> bool {
-        if !self.allow_prerelease && version.pre != PreRelease::None {
+        if !self.include_prerelease && version.pre != PreRelease::None {
             return false;
         }
         if self
@@ -253,6 +270,13 @@ impl PnpmVersionFilter {
         {
             return false;
         }
+        if self
+            .version_range
+            .as_ref()
+            .is_some_and(|r| !r.matches(version.major, version.minor, version.patch))
+        {
+            return false;
+        }
         if self
             .exact_version
             .as_ref()
@@ -269,8 +293,9 @@ impl TryFrom<&VersionFilter> for PnpmVersionFilter {
 
     fn try_from(value: &VersionFilter) -> Result<Self, Self::Error> {
         Ok(Self {
-            allow_prerelease: value.allow_prerelease,
+            include_prerelease: value.includes_prerelease(),
             version_prefix: value.version_prefix,
+            version_range: value.version_range.clone(),
             exact_version: value.exact_version.clone(),
         })
     }
@@ -404,9 +429,7 @@ mod tests {
     fn version_filter_ignores_lts_only() {
         let filter = PnpmVersionFilter::try_from(&VersionFilter {
             lts_only: true,
-            allow_prerelease: false,
-            version_prefix: None,
-            exact_version: None,
+            ..Default::default()
         })
         .unwrap();
         let version = parse_pnpm_version("9.9.0").unwrap();
@@ -0,0 +1,332 @@
+use smol_str::SmolStr;
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use super::manifest::{best_local_tag, sorted_versions, ReleaseVersion};
+use super::terraform::ignore_lts_only;
+use crate::platform::{cpu, create_platform_string, os, resolve_default_platform};
+use crate::tool::github_releases::{self, AssetMatch, AssetMatcher, ReleaseDto};
+use crate::tool::{CompanionArchive, ToolDownInfo, ToolInfo, Version, VersionFilter};
+use crate::HttpClient;
+
+pub struct Tool {
+    client: Arc<HttpClient>,
+    info: ToolInfo,
+    assets: AssetMatcher,
+}
+
+/// Each release has a `protoc-<version>-<platform>.zip` asset.
+const REPO: &str = "protocolbuffers/protobuf";
+
+/// Platforms by the suffix of the protoc assets and the `<os>.<arch>` of the Go plugin assets,
+/// which are built for fewer platforms.
+const PLATFORMS: [(&str, &str, &str, Option<&str>); 9] = [
+    (cpu::X64, os::LINUX, "linux-x86_64", Some("linux.amd64")),
+    (cpu::X86, os::LINUX, "linux-x86_32", Some("linux.386")),
+    (cpu::ARM64, os::LINUX, "linux-aarch_64", Some("linux.arm64")),
+    (cpu::PPC64LE, os::LINUX, "linux-ppcle_64", None),
+    (cpu::S390X, os::LINUX, "linux-s390_64", None),
+    (cpu::X64, os::MAC, "osx-x86_64", Some("darwin.amd64")),
+    (cpu::ARM64, os::MAC, "osx-aarch_64", Some("darwin.arm64")),
+    (cpu::X64, os::WIN, "win64", Some("windows.amd64")),
+    (cpu::X86, os::WIN, "win32", Some("windows.386")),
+];
+
+/// The flavors, `protoc` alone or with the code generator plugins of a language.
+const FLAVORS: [&str; 2] = ["protoc", "go"];
+const DEFAULT_FLAVOR: &str = "protoc";
+
+/// The plugins of the `go` flavor by the repository releasing them. Their assets are named
+/// `<plugin>.v<version>.<os>.<arch>.tar.gz`, or `.zip` on Windows.
+const GO_PLUGINS: [(&str, &str); 2] = [
+    ("protoc-gen-go", "protocolbuffers/protobuf-go"),
+    ("protoc-gen-go-grpc", "grpc/grpc-go"),
+];
+
+impl crate::tool::GeneralTool for Tool {
+    fn info(&self) -> &ToolInfo {
+        &self.info
+    }
+
+    fn describe_flavor(&self, flavor: &str) -> &'static str {
+        match flavor {
+            "protoc" => "The protoc compiler alone.",
+            "go" => "With the latest protoc-gen-go and protoc-gen-go-grpc plugins.",
+            _ => "Tool-specific build flavor.",
+        }
+    }
+
+    fn supports_combination(&self, platform: &str, flavor: &str) -> bool {
+        flavor != "go" || go_platform(platform).is_some()
+    }
+
+    fn endpoints(&self) -> Vec<SmolStr> {
+        vec![github_releases::releases_url(REPO)]
+    }
+
+    async fn fetch_versions(
+        &self,
+        platform: Option<SmolStr>,
+        _flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<Vec<Version>> {
+        let platform = platform.ok_or(crate::AvmError::PlatformRequired)?;
+        let version_filter = ignore_lts_only(version_filter, "protoc");
+        let releases = github_releases::fetch_releases(&self.client, REPO).await?;
+        Ok(sorted_versions(
+            self.assets
+                .matching(Some(&platform), &releases)?
+                .iter()
+                .filter_map(|asset| release_version(asset, &version_filter)),
+        ))
+    }
+
+    async fn get_down_info(
+        &self,
+        platform: Option<SmolStr>,
+        _flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<ToolDownInfo> {
+        let platform = platform.ok_or(crate::AvmError::PlatformRequired)?;
+        let version_filter = ignore_lts_only(version_filter, "protoc");
+        let releases = github_releases::fetch_releases(&self.client, REPO).await?;
+        let assets = self.assets.matching(Some(&platform), &releases)?;
+        let versions = sorted_versions(
+            assets
+                .iter()
+                .filter_map(|asset| release_version(asset, &version_filter)),
+        );
+        let version = versions.last().ok_or(crate::AvmError::VersionNotFound)?;
+        let asset = assets
+            .iter()
+            .find(|asset| asset.version() == version.version)
+            .ok_or(crate::AvmError::VersionNotFound)?;
+        Ok(ToolDownInfo {
+            version: version.clone(),
+            url: asset.asset.browser_download_url.clone(),
+            hash: crate::FileHash {
+                sha256: asset.asset.sha256(),
+                ..Default::default()
+            },
+        })
+    }
+
+    async fn companion_archives(
+        &self,
+        platform: Option<SmolStr>,
+        flavor: Option<SmolStr>,
+    ) -> crate::Result<Vec<CompanionArchive>> {
+        if flavor.as_deref().unwrap_or(DEFAULT_FLAVOR) != "go" {
+            return Ok(Vec::new());
+        }
+        let platform = platform.ok_or(crate::AvmError::PlatformRequired)?;
+        let go_platform = go_platform(&platform)
+            .ok_or_else(|| anyhow::anyhow!("The Go plugins are not built for {platform}"))?;
+        let mut companions = Vec::with_capacity(GO_PLUGINS.len());
+        for (plugin, repo) in GO_PLUGINS {
+            let releases = github_releases::fetch_releases(&self.client, repo).await?;
+            let (version, companion) = latest_plugin(plugin, go_platform, &releases)?
+                .ok_or_else(|| anyhow::anyhow!("No release of {plugin} for {platform} found"))?;
+            log::info!("Installing {plugin} {version}");
+            companions.push(companion);
+        }
+        Ok(companions)
+    }
+
+    fn find_best_matching_local_tag<'a, I>(
+        &self,
+        tags_and_versions: I,
+        version_filter: &VersionFilter,
+    ) -> Option<SmolStr>
+    where
+        I: Iterator<Item = (&'a str, &'a Version)>,
+    {
+        let version_filter = ignore_lts_only(version_filter.clone(), "protoc");
+        best_local_tag(tags_and_versions, &version_filter)
+    }
+
+    fn entry_path(&self, tag_dir: PathBuf) -> crate::Result<PathBuf> {
+        Ok(self.exe_paths(tag_dir)?.swap_remove(0).1)
+    }
+
+    /// `protoc`, then the plugins installed with it.
+    fn exe_paths(&self, tag_dir: PathBuf) -> crate::Result<Vec<(SmolStr, PathBuf)>> {
+        let mut names = vec!["protoc"];
+        names.extend(GO_PLUGINS.iter().map(|(plugin, _)| *plugin));
+        let mut exes = super::exes_in(&tag_dir.join("bin"), &names);
+        let plugins = exes.split_off(1);
+        exes.extend(plugins.into_iter().filter(|(_, path)| path.exists()));
+        Ok(exes)
+    }
+
+    fn command(
+        &self,
+        entry_path: PathBuf,
+        args: Vec<OsString>,
+        env_vars: Vec<(OsString, OsString)>,
+    ) -> crate::Result<std::process::Command> {
+        // protoc finds the `protoc-gen-<name>` plugins through `PATH`.
+        let bin_dir = entry_path.parent().map(PathBuf::from);
+        let mut command = std::process::Command::new(&entry_path);
+        command.args(args).envs(env_vars);
+        if let Some(bin_dir) = bin_dir {
+            let paths = std::env::var_os("PATH").unwrap_or_default();
+            let paths = std::iter::once(bin_dir).chain(std::env::split_paths(&paths));
+            command.env(
+                "PATH",
+                std::env::join_paths(paths).map_err(anyhow::Error::from)?,
+            );
+        }
+        Ok(command)
+    }
+}
+
+impl Tool {
+    pub fn new(client: Arc<HttpClient>, config_default_platform: Option<SmolStr>) -> Self {
+        let all_platforms = PLATFORMS
+            .iter()
+            .map(|(cpu, os, _, _)| create_platform_string(cpu, os))
+            .collect::<Vec<_>>();
+        let default_platform =
+            resolve_default_platform(&all_platforms, config_default_platform, &[]);
+        Tool {
+            client,
+            info: ToolInfo {
+                about: "Protocol Buffers compiler, optionally with code generator plugins".into(),
+                after_long_help: Some(
+                    r#"The `go` flavor also installs the latest protoc-gen-go and protoc-gen-go-grpc next to `protoc`, which finds them when started by `avm run protoc`.
+The well-known `.proto` files are in `include` of the tag."#
+                        .into(),
+                ),
+                all_platforms: Some(all_platforms),
+                default_platform,
+                all_flavors: Some(FLAVORS.iter().map(SmolStr::new).collect()),
+                default_flavor: Some(DEFAULT_FLAVOR.into()),
+            },
+            assets: AssetMatcher::new(PLATFORMS.iter().map(|(cpu, os, suffix, _)| {
+                (
+                    Some(create_platform_string(cpu, os)),
+                    format!(r"protoc-(?<version>.+)-{}\.zip", regex::escape(suffix)),
+                )
+            })),
+        }
+    }
+}
+
+fn go_platform(platform: &str) -> Option<&'static str> {
+    PLATFORMS
+        .iter()
+        .find(|(cpu, os, _, _)| create_platform_string(cpu, os) == platform)
+        .and_then(|(_, _, _, go_platform)| *go_platform)
+}
+
+fn release_version(
+    asset: &AssetMatch<'_>,
+    version_filter: &VersionFilter,
+) -> Option<ReleaseVersion> {
+    let version = ReleaseVersion::parse(asset.version().into(), false, None)?;
+    version.matches(version_filter).then_some(version)
+}
+
+/// The newest stable release of a Go plugin for `go_platform`, with its version.
+fn latest_plugin(
+    plugin: &'static str,
+    go_platform: &str,
+    releases: &[ReleaseDto],
+) -> anyhow::Result<Option<(SmolStr, CompanionArchive)>> {
+    let matcher = AssetMatcher::new([(
+        None,
+        format!(
+            r"{}\.v(?<version>[^.]+\.[^.]+\.[^.]+)\.{}\.(?:tar\.gz|zip)",
+            regex::escape(plugin),
+            regex::escape(go_platform)
+        ),
+    )]);
+    let assets = matcher.matching(None, releases)?;
+    let stable = VersionFilter::default();
+    let versions = sorted_versions(
+        assets
+            .iter()
+            .filter_map(|asset| release_version(asset, &stable)),
+    );
+    let Some(version) = versions.last() else {
+        return Ok(None);
+    };
+    Ok(assets
+        .iter()
+        .find(|asset| asset.version() == version.version)
+        .map(|asset| {
+            (
+                version.version.clone(),
+                CompanionArchive {
+                    url: asset.asset.browser_download_url.clone(),
+                    hash: crate::FileHash {
+                        sha256: asset.asset.sha256(),
+                        ..Default::default()
+                    },
+                    exe: SmolStr::new_static(plugin),
+                },
+            )
+        }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tool::GeneralTool;
+
+    #[test]
+    fn picks_protoc_and_plugin_assets() {
+        let releases: Vec<ReleaseDto> = serde_json::from_str(
+            r#"[{"assets": [
+                {"name": "protoc-29.0-rc-2-linux-x86_64.zip", "browser_download_url": "https://example.com/protoc-29.0-rc-2-linux-x86_64.zip"},
+                {"name": "protoc-28.2-linux-x86_64.zip", "browser_download_url": "https://example.com/protoc-28.2-linux-x86_64.zip", "digest": "sha256:aa"},
+                {"name": "protoc-28.2-linux-aarch_64.zip", "browser_download_url": "https://example.com/protoc-28.2-linux-aarch_64.zip"},
+                {"name": "protobuf-28.2.tar.gz", "browser_download_url": "https://example.com/protobuf-28.2.tar.gz"}
+            ]}, {"assets": [
+                {"name": "protoc-gen-go-grpc.v1.5.1.linux.amd64.tar.gz", "browser_download_url": "https://example.com/grpc-1.5.1", "digest": "sha256:bb"},
+                {"name": "protoc-gen-go-grpc.v1.4.0.linux.amd64.tar.gz", "browser_download_url": "https://example.com/grpc-1.4.0"},
+                {"name": "protoc-gen-go-grpc.v1.5.1.windows.amd64.zip", "browser_download_url": "https://example.com/grpc-1.5.1.zip"}
+            ]}]"#,
+        )
+        .unwrap();
+        let tool = Tool::new(
+            Arc::new(
+                HttpClient::new(Default::default(), Default::default(), Default::default())
+                    .unwrap(),
+            ),
+            None,
+        );
+        let filter = |include_prerelease| VersionFilter {
+            include_prerelease,
+            ..Default::default()
+        };
+        let versions = |platform, filter: VersionFilter| {
+            sorted_versions(
+                tool.assets
+                    .matching(Some(platform), &releases)
+                    .unwrap()
+                    .iter()
+                    .filter_map(|asset| release_version(asset, &filter)),
+            )
+            .into_iter()
+            .map(|version| version.version)
+            .collect::<Vec<_>>()
+        };
+        assert_eq!(versions("x64-linux", filter(false)), ["28.2"]);
+        assert_eq!(versions("x64-linux", filter(true)), ["28.2", "29.0-rc-2"]);
+        assert_eq!(versions("arm64-linux", filter(false)), ["28.2"]);
+
+        let (version, plugin) = latest_plugin("protoc-gen-go-grpc", "linux.amd64", &releases)
+            .unwrap()
+            .unwrap();
+        assert_eq!(version, "1.5.1");
+        assert_eq!(plugin.url, "https://example.com/grpc-1.5.1");
+        assert_eq!(plugin.hash.sha256.as_deref(), Some("bb"));
+        assert!(latest_plugin("protoc-gen-go", "linux.amd64", &releases)
+            .unwrap()
+            .is_none());
+        assert!(!tool.supports_combination("s390x-linux", "go"));
+    }
+}
@@ -0,0 +1,414 @@
+use smol_str::SmolStr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use super::manifest::resolve_default_platform;
+use crate::platform::{cpu, create_platform_string, os};
+use crate::tool::github_releases::{self, AssetMatch, AssetMatcher};
+use crate::tool::{ToolDownInfo, ToolInfo, Version, VersionFilter};
+use crate::HttpClient;
+
+pub struct Tool {
+    client: Arc<HttpClient>,
+    info: ToolInfo,
+    assets: AssetMatcher,
+}
+
+/// The release of ruby/ruby-builder holding the builds of every version, as used by `setup-ruby`.
+const RUBY_BUILDER_REPO: &str = "ruby/ruby-builder";
+const RUBY_BUILDER_TAG: &str = "toolcache";
+/// One release per Ruby version and package revision.
+const RUBY_INSTALLER_REPO: &str = "oneclick/rubyinstaller2";
+
+#[derive(Clone, Copy)]
+enum Source {
+    /// `ruby-<version>-<os>.tar.gz` assets of ruby/ruby-builder.
+    RubyBuilder(&'static str),
+    /// `rubyinstaller-<version>-<revision>-<arch>.7z` assets of RubyInstaller for Windows.
+    RubyInstaller(&'static str),
+}
+
+impl Source {
+    fn asset_pattern(self) -> String {
+        match self {
+            Source::RubyBuilder(os) => {
+                format!(r"ruby-(?<version>.+)-{}\.tar\.gz", regex::escape(os))
+            }
+            Source::RubyInstaller(arch) => format!(
+                r"rubyinstaller-(?<version>.+)-(?<revision>\d+)-{}\.7z",
+                regex::escape(arch)
+            ),
+        }
+    }
+}
+
+const PLATFORMS: [(&str, &str, Source); 6] = [
+    (cpu::X64, os::LINUX, Source::RubyBuilder("ubuntu-22.04")),
+    (
+        cpu::ARM64,
+        os::LINUX,
+        Source::RubyBuilder("ubuntu-22.04-arm64"),
+    ),
+    (cpu::X64, os::MAC, Source::RubyBuilder("macos-13")),
+    (cpu::ARM64, os::MAC, Source::RubyBuilder("macos-14-arm64")),
+    (cpu::X64, os::WIN, Source::RubyInstaller("x64")),
+    (cpu::X86, os::WIN, Source::RubyInstaller("x86")),
+];
+
+impl crate::tool::GeneralTool for Tool {
+    fn info(&self) -> &ToolInfo {
+        &self.info
+    }
+
+    fn endpoints(&self) -> Vec<SmolStr> {
+        vec![
+            github_releases::release_by_tag_url(RUBY_BUILDER_REPO, RUBY_BUILDER_TAG),
+            github_releases::releases_url(RUBY_INSTALLER_REPO),
+        ]
+    }
+
+    async fn fetch_versions(
+        &self,
+        platform: Option<SmolStr>,
+        _flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<Vec<Version>> {
+        let platform = platform.ok_or(crate::AvmError::PlatformRequired)?;
+        let version_filter = ignore_lts_only(version_filter);
+        let mut artifacts = self.fetch_artifacts(&platform).await?;
+        artifacts.retain(|artifact| artifact.matches(&version_filter));
+        artifacts.sort_by(|a, b| a.parsed.cmp(&b.parsed));
+        artifacts.dedup_by(|a, b| a.version == b.version);
+        Ok(artifacts
+            .into_iter()
+            .map(|artifact| Version {
+                version: artifact.version,
+                is_lts: false,
+            })
+            .collect())
+    }
+
+    async fn get_down_info(
+        &self,
+        platform: Option<SmolStr>,
+        _flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<ToolDownInfo> {
+        let platform = platform.ok_or(crate::AvmError::PlatformRequired)?;
+        let version_filter = ignore_lts_only(version_filter);
+        let best = self
+            .fetch_artifacts(&platform)
+            .await?
+            .into_iter()
+            .filter(|artifact| artifact.matches(&version_filter))
+            .max_by(|a, b| (&a.parsed, a.revision).cmp(&(&b.parsed, b.revision)))
+            .ok_or(crate::AvmError::VersionNotFound)?;
+        Ok(ToolDownInfo {
+            version: Version {
+                version: best.version,
+                is_lts: false,
+            },
+            url: best.url,
+            hash: crate::FileHash {
+                sha256: best.sha256,
+                ..Default::default()
+            },
+        })
+    }
+
+    fn find_best_matching_local_tag<'a, I>(
+        &self,
+        tags_and_versions: I,
+        version_filter: &VersionFilter,
+    ) -> Option<SmolStr>
+    where
+        I: Iterator<Item = (&'a str, &'a Version)>,
+    {
+        let version_filter = ignore_lts_only(version_filter.clone());
+        tags_and_versions
+            .filter_map(|(tag, version_info)| {
+                let version = RubyVersion::parse(&version_info.version)?;
+                version
+                    .matches(&version_info.version, &version_filter)
+                    .then(|| (version, SmolStr::from(tag)))
+            })
+            .max_by(|a, b| a.0.cmp(&b.0))
+            .map(|(_, tag)| tag)
+    }
+
+    fn entry_path(&self, tag_dir: PathBuf) -> crate::Result<PathBuf> {
+        Ok(super::exes_in(&tag_dir.join("bin"), &["ruby"])
+            .swap_remove(0)
+            .1)
+    }
+
+    fn exe_paths(&self, tag_dir: PathBuf) -> crate::Result<Vec<(SmolStr, PathBuf)>> {
+        let bin_dir = tag_dir.join("bin");
+        let mut exes = super::exes_in(&bin_dir, &["ruby"]);
+        // Except for `ruby`, the executables are scripts, run through `.cmd` wrappers on Windows.
+        let suffix = if cfg!(windows) { ".cmd" } else { "" };
+        exes.extend(
+            ["gem", "irb", "bundle", "rake"]
+                .into_iter()
+                .map(|name| (name.into(), bin_dir.join(format!("{name}{suffix}")))),
+        );
+        Ok(exes)
+    }
+}
+
+impl Tool {
+    pub fn new(client: Arc<HttpClient>, config_default_platform: Option<SmolStr>) -> Self {
+        let all_platforms: Vec<SmolStr> = PLATFORMS
+            .iter()
+            .map(|(cpu, os, _)| create_platform_string(cpu, os))
+            .collect();
+        let default_platform = resolve_default_platform(&all_platforms, config_default_platform);
+
+        Tool {
+            client,
+            info: ToolInfo {
+                about: "Ruby programming language".into(),
+                after_long_help: Some(
+                    r#"Linux and macOS builds are the ones `setup-ruby` uses on GitHub Actions, from ruby/ruby-builder.
+Windows builds are from RubyInstaller."#
+                        .into(),
+                ),
+                all_platforms: Some(all_platforms),
+                default_platform,
+                all_flavors: None,
+                default_flavor: None,
+            },
+            assets: AssetMatcher::new(PLATFORMS.iter().map(|(cpu, os, source)| {
+                (
+                    Some(create_platform_string(cpu, os)),
+                    source.asset_pattern(),
+                )
+            })),
+        }
+    }
+
+    async fn fetch_artifacts(&self, platform: &str) -> anyhow::Result<Vec<Artifact>> {
+        let source = PLATFORMS
+            .iter()
+            .find(|(cpu, os, _)| create_platform_string(cpu, os) == platform)
+            .map(|(_, _, source)| *source)
+            .ok_or_else(|| anyhow::anyhow!("Unsupported Ruby platform: {platform}"))?;
+        let releases = match source {
+            Source::RubyBuilder(_) => vec![
+                github_releases::fetch_release_by_tag(
+                    &self.client,
+                    RUBY_BUILDER_REPO,
+                    RUBY_BUILDER_TAG,
+                )
+                .await?,
+            ],
+            Source::RubyInstaller(_) => {
+                github_releases::fetch_releases(&self.client, RUBY_INSTALLER_REPO).await?
+            }
+        };
+        Ok(self
+            .assets
+            .matching(Some(platform), &releases)?
+            .iter()
+            .filter_map(Artifact::from_match)
+            .collect())
+    }
+}
+
+#[derive(Debug)]
+struct Artifact {
+    version: SmolStr,
+    parsed: RubyVersion,
+    /// The RubyInstaller package revision, a version may be packaged more than once.
+    revision: u32,
+    url: SmolStr,
+    sha256: Option<SmolStr>,
+}
+
+impl Artifact {
+    /// `None` for assets whose version is not a Ruby release, such as `head`.
+    fn from_match(artifact: &AssetMatch<'_>) -> Option<Self> {
+        let version = artifact.version();
+        let revision = match artifact.captures.name("revision") {
+            Some(revision) => revision.as_str().parse().ok()?,
+            None => 0,
+        };
+        Some(Self {
+            parsed: RubyVersion::parse(version)?,
+            version: version.into(),
+            revision,
+            url: artifact.asset.browser_download_url.clone(),
+            sha256: artifact.asset.sha256(),
+        })
+    }
+
+    fn matches(&self, version_filter: &VersionFilter) -> bool {
+        self.parsed.matches(&self.version, version_filter)
+    }
+}
+
+fn ignore_lts_only(mut version_filter: VersionFilter) -> VersionFilter {
+    if version_filter.lts_only {
+        log::warn!(
+            "`--lts-only` is ignored for `ruby` because this tool does not define LTS releases."
+        );
+        version_filter.lts_only = false;
+    }
+    version_filter
+}
+
+/// A Ruby version such as `3.3.6`, `3.4.0-preview2`, `3.4.0-rc1` or, for old releases,
+/// `1.9.3-p551` with a patch level.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
+pub struct RubyVersion {
+    major: u32,
+    minor: u32,
+    patch: u32,
+    stage: Stage,
+}
+
+/// Previews come before release candidates, which come before the release.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+enum Stage {
+    Preview(u32),
+    ReleaseCandidate(u32),
+    Release { patch_level: u32 },
+}
+
+impl RubyVersion {
+    pub fn parse(raw: &str) -> Option<Self> {
+        let (numbers, stage) = match raw.split_once('-') {
+            Some((numbers, stage)) => (numbers, Some(stage)),
+            None => (raw, None),
+        };
+        let mut numbers = numbers.split('.').map(str::parse::<u32>);
+        let (major, minor, patch) = (
+            numbers.next()?.ok()?,
+            numbers.next()?.ok()?,
+            numbers.next()?.ok()?,
+        );
+        if numbers.next().is_some() {
+            return None;
+        }
+        let stage = match stage {
+            None => Stage::Release { patch_level: 0 },
+            Some(stage) => {
+                if let Some(n) = stage.strip_prefix("preview") {
+                    Stage::Preview(n.parse().ok()?)
+                } else if let Some(n) = stage.strip_prefix("rc") {
+                    Stage::ReleaseCandidate(n.parse().ok()?)
+                } else {
+                    Stage::Release {
+                        patch_level: stage.strip_prefix('p')?.parse().ok()?,
+                    }
+                }
+            }
+        };
+        Some(Self {
+            major,
+            minor,
+            patch,
+            stage,
+        })
+    }
+
+    pub fn is_prerelease(&self) -> bool {
+        !matches!(self.stage, Stage::Release { .. })
+    }
+
+    fn matches(&self, raw: &str, version_filter: &VersionFilter) -> bool {
+        if !version_filter.includes_prerelease() && self.is_prerelease() {
+            return false;
+        }
+        if !version_filter.matches_numbers(self.major, self.minor, self.patch) {
+            return false;
+        }
+        if version_filter
+            .exact_version
+            .as_ref()
+            .is_some_and(|exact| exact != raw)
+        {
+            return false;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tool::github_releases::{AssetDto, ReleaseDto};
+
+    /// The artifacts among assets named `names`, all with the digest `sha256:ab`.
+    fn artifacts(platform: &str, names: &[&str]) -> Vec<Artifact> {
+        let release = ReleaseDto {
+            tag_name: Default::default(),
+            assets: names
+                .iter()
+                .map(|name| AssetDto {
+                    name: (*name).into(),
+                    browser_download_url: format!("https://example.com/{name}").into(),
+                    digest: Some("sha256:ab".into()),
+                })
+                .collect(),
+        };
+        let tool = Tool::new(
+            Arc::new(
+                HttpClient::new(Default::default(), Default::default(), Default::default())
+                    .unwrap(),
+            ),
+            None,
+        );
+        tool.assets
+            .matching(Some(platform), &[release])
+            .unwrap()
+            .iter()
+            .filter_map(Artifact::from_match)
+            .collect()
+    }
+
+    #[test]
+    fn orders_versions() {
+        let parse = |raw| RubyVersion::parse(raw).unwrap();
+        assert!(parse("3.4.0-preview1") < parse("3.4.0-preview2"));
+        assert!(parse("3.4.0-preview2") < parse("3.4.0-rc1"));
+        assert!(parse("3.4.0-rc1") < parse("3.4.0"));
+        assert!(parse("3.3.10") > parse("3.3.9"));
+        assert!(parse("1.9.3-p551") > parse("1.9.3-p392"));
+        assert!(parse("3.4.0-rc1").is_prerelease());
+        assert!(!parse("1.9.3-p551").is_prerelease());
+        assert!(RubyVersion::parse("head").is_none());
+        assert!(RubyVersion::parse("3.4").is_none());
+        assert!(RubyVersion::parse("3.4.0-dev").is_none());
+    }
+
+    #[test]
+    fn matches_platform_assets() {
+        let linux = artifacts(
+            "x64-linux",
+            &[
+                "ruby-3.3.6-ubuntu-22.04.tar.gz",
+                "ruby-3.3.6-ubuntu-22.04-arm64.tar.gz",
+                "jruby-9.4.9.0-ubuntu-22.04.tar.gz",
+                "ruby-head-ubuntu-22.04.tar.gz",
+            ],
+        );
+        assert_eq!(linux.len(), 1);
+        assert_eq!(linux[0].version, "3.3.6");
+        assert_eq!(linux[0].sha256.as_deref(), Some("ab"));
+
+        let windows = artifacts(
+            "x64-win",
+            &[
+                "rubyinstaller-3.4.0-preview1-2-x64.7z",
+                "rubyinstaller-devkit-3.3.6-1-x64.exe",
+                "rubyinstaller-3.3.6-1-x86.7z",
+            ],
+        );
+        assert_eq!(windows.len(), 1);
+        assert_eq!(
+            (windows[0].version.as_str(), windows[0].revision),
+            ("3.4.0-preview1", 2)
+        );
+    }
+}
@@ -0,0 +1,329 @@
+use serde::Deserialize;
+use smol_str::SmolStr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use super::manifest::{best_local_tag, sorted_versions, ReleaseVersion};
+use super::terraform::ignore_lts_only;
+use crate::platform::{cpu, create_platform_string, os, resolve_default_platform};
+use crate::signature::DetachedSignature;
+use crate::tool::{ToolDownInfo, ToolInfo, Version, VersionFilter};
+use crate::HttpClient;
+
+pub struct Tool {
+    client: Arc<HttpClient>,
+    info: ToolInfo,
+}
+
+const RELEASES_URL: &str = "https://www.swift.org/api/v1/install/releases.json";
+const DOWNLOAD_URL: &str = "https://download.swift.org/";
+/// The keys the toolchains are signed with.
+const KEYS_URL: &str = "https://www.swift.org/keys/all-keys.asc";
+/// The keys of [`KEYS_URL`] that sign the toolchains avm installs: the Swift 5.x Release
+/// Signing Key and the Swift Automatic Signing Key #4 of Swift 6. The keyring is downloaded
+/// like the archives, so its other keys are not trusted.
+const SIGNING_KEYS: &[&str] = &[
+    "A62AE125BBBFBB96A6E042EC925CC1CCED3D1561",
+    "52BB7E3DE28A71BE22EC05FFEF80A866B47A981F",
+];
+
+/// Platforms by the architecture in the releases and the suffix of the archive names.
+const PLATFORMS: [(&str, &str, &str); 2] = [
+    (cpu::X64, "x86_64", ""),
+    (cpu::ARM64, "aarch64", "-aarch64"),
+];
+
+/// The Linux distributions the toolchains are built for, which are the flavors, by the name of
+/// the platform in the releases, the download directory and the name in the archive names.
+const FLAVORS: [(&str, &str, &str, &str); 4] = [
+    ("ubuntu24.04", "Ubuntu 24.04", "ubuntu2404", "ubuntu24.04"),
+    ("ubuntu22.04", "Ubuntu 22.04", "ubuntu2204", "ubuntu22.04"),
+    ("ubuntu20.04", "Ubuntu 20.04", "ubuntu2004", "ubuntu20.04"),
+    (
+        "amazonlinux2",
+        "Amazon Linux 2",
+        "amazonlinux2",
+        "amazonlinux2",
+    ),
+];
+const DEFAULT_FLAVOR: &str = "ubuntu22.04";
+
+impl crate::tool::GeneralTool for Tool {
+    fn info(&self) -> &ToolInfo {
+        &self.info
+    }
+
+    fn describe_flavor(&self, flavor: &str) -> &'static str {
+        match flavor {
+            "ubuntu24.04" => "Built for Ubuntu 24.04.",
+            "ubuntu22.04" => "Built for Ubuntu 22.04.",
+            "ubuntu20.04" => "Built for Ubuntu 20.04.",
+            "amazonlinux2" => "Built for Amazon Linux 2.",
+            _ => "Tool-specific build flavor.",
+        }
+    }
+
+    fn endpoints(&self) -> Vec<SmolStr> {
+        vec![RELEASES_URL.into()]
+    }
+
+    async fn fetch_versions(
+        &self,
+        platform: Option<SmolStr>,
+        flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<Vec<Version>> {
+        let platform = platform.ok_or(crate::AvmError::PlatformRequired)?;
+        let version_filter = ignore_lts_only(version_filter, "swift");
+        let build = Build::new(&platform, flavor.as_deref())?;
+        let releases = self.fetch_releases().await?;
+        Ok(sorted_versions(
+            matching_releases(&releases, &build, &version_filter).map(|(version, _)| version),
+        ))
+    }
+
+    async fn get_down_info(
+        &self,
+        platform: Option<SmolStr>,
+        flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<ToolDownInfo> {
+        let platform = platform.ok_or(crate::AvmError::PlatformRequired)?;
+        let version_filter = ignore_lts_only(version_filter, "swift");
+        let build = Build::new(&platform, flavor.as_deref())?;
+        let releases = self.fetch_releases().await?;
+        let versions = sorted_versions(
+            matching_releases(&releases, &build, &version_filter).map(|(version, _)| version),
+        );
+        let version = versions.last().ok_or(crate::AvmError::VersionNotFound)?;
+        let (_, release) = matching_releases(&releases, &build, &version_filter)
+            .find(|(_, release)| release.name == version.version)
+            .ok_or(crate::AvmError::VersionNotFound)?;
+        // Only the signatures are published, the archive is checked with the signature.
+        Ok(ToolDownInfo {
+            version: version.clone(),
+            url: build.url(&release.tag),
+            hash: crate::FileHash::default(),
+        })
+    }
+
+    fn signing_keys(&self) -> &'static [&'static str] {
+        SIGNING_KEYS
+    }
+
+    async fn archive_signature(&self, url: &str) -> crate::Result<Option<DetachedSignature>> {
+        let signature_url = format!("{url}.sig");
+        let signature = self.fetch_bytes(&signature_url).await?;
+        let keys = self.fetch_bytes(KEYS_URL).await?;
+        Ok(Some(DetachedSignature {
+            url: signature_url.into(),
+            signature,
+            keys,
+            fingerprints: self
+                .signing_keys()
+                .iter()
+                .copied()
+                .map(SmolStr::new_static)
+                .collect(),
+        }))
+    }
+
+    fn find_best_matching_local_tag<'a, I>(
+        &self,
+        tags_and_versions: I,
+        version_filter: &VersionFilter,
+    ) -> Option<SmolStr>
+    where
+        I: Iterator<Item = (&'a str, &'a Version)>,
+    {
+        let version_filter = ignore_lts_only(version_filter.clone(), "swift");
+        best_local_tag(tags_and_versions, &version_filter)
+    }
+
+    fn entry_path(&self, tag_dir: PathBuf) -> crate::Result<PathBuf> {
+        Ok(self.exe_paths(tag_dir)?.swap_remove(0).1)
+    }
+
+    fn exe_paths(&self, tag_dir: PathBuf) -> crate::Result<Vec<(SmolStr, PathBuf)>> {
+        Ok(super::exes_in(
+            &tag_dir.join("usr").join("bin"),
+            &["swift", "swiftc", "sourcekit-lsp"],
+        ))
+    }
+}
+
+impl Tool {
+    pub fn new(client: Arc<HttpClient>, config_default_platform: Option<SmolStr>) -> Self {
+        let all_platforms = PLATFORMS
+            .iter()
+            .map(|(cpu, _, _)| create_platform_string(cpu, os::LINUX))
+            .collect::<Vec<_>>();
+        let default_platform =
+            resolve_default_platform(&all_platforms, config_default_platform, &[]);
+        Tool {
+            client,
+            info: ToolInfo {
+                about: "Swift toolchains for Linux".into(),
+                after_long_help: Some(
+                    r#"The flavor selects the Linux distribution the toolchain is built for, and should match the system.
+swift.org publishes signatures instead of checksums, which are verified with `gpg`."#
+                        .into(),
+                ),
+                all_platforms: Some(all_platforms),
+                default_platform,
+                all_flavors: Some(
+                    FLAVORS
+                        .iter()
+                        .map(|(flavor, _, _, _)| SmolStr::new(flavor))
+                        .collect(),
+                ),
+                default_flavor: Some(DEFAULT_FLAVOR.into()),
+            },
+        }
+    }
+
+    async fn fetch_releases(&self) -> anyhow::Result<Vec<ReleaseDto>> {
+        Ok(self
+            .client
+            .get(RELEASES_URL)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    async fn fetch_bytes(&self, url: &str) -> anyhow::Result<Vec<u8>> {
+        Ok(self
+            .client
+            .get(url)
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?
+            .to_vec())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ReleaseDto {
+    /// The version, such as `5.10.1`.
+    name: SmolStr,
+    /// Such as `swift-5.10.1-RELEASE`.
+    tag: SmolStr,
+    platforms: Vec<PlatformDto>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PlatformDto {
+    name: SmolStr,
+    #[serde(default)]
+    archs: Vec<SmolStr>,
+}
+
+/// The build of a platform and flavor.
+struct Build {
+    platform_name: &'static str,
+    dir: &'static str,
+    file_platform: &'static str,
+    arch: &'static str,
+    arch_suffix: &'static str,
+}
+
+impl Build {
+    fn new(platform: &str, flavor: Option<&str>) -> anyhow::Result<Self> {
+        let (_, arch, arch_suffix) = PLATFORMS
+            .iter()
+            .find(|(cpu, _, _)| create_platform_string(cpu, os::LINUX) == platform)
+            .ok_or_else(|| anyhow::anyhow!("Unsupported platform: {platform}"))?;
+        let flavor = flavor.unwrap_or(DEFAULT_FLAVOR);
+        let (_, platform_name, dir, file_platform) = FLAVORS
+            .iter()
+            .find(|(name, _, _, _)| *name == flavor)
+            .ok_or_else(|| anyhow::anyhow!("Unsupported flavor: {flavor}"))?;
+        Ok(Build {
+            platform_name,
+            dir,
+            file_platform,
+            arch,
+            arch_suffix,
+        })
+    }
+
+    fn url(&self, tag: &str) -> SmolStr {
+        let Build {
+            dir,
+            file_platform,
+            arch_suffix,
+            ..
+        } = self;
+        smol_str::format_smolstr!(
+            "{DOWNLOAD_URL}{}/{dir}{arch_suffix}/{tag}/{tag}-{file_platform}{arch_suffix}.tar.gz",
+            tag.to_ascii_lowercase()
+        )
+    }
+
+    fn is_published(&self, release: &ReleaseDto) -> bool {
+        release.platforms.iter().any(|platform| {
+            platform.name == self.platform_name && platform.archs.iter().any(|a| a == self.arch)
+        })
+    }
+}
+
+fn matching_releases<'a>(
+    releases: &'a [ReleaseDto],
+    build: &'a Build,
+    version_filter: &'a VersionFilter,
+) -> impl Iterator<Item = (ReleaseVersion, &'a ReleaseDto)> + 'a {
+    releases.iter().filter_map(move |release| {
+        if !build.is_published(release) {
+            return None;
+        }
+        let version = ReleaseVersion::parse(release.name.clone(), false, None)?;
+        version
+            .matches(version_filter)
+            .then_some((version, release))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selects_the_builds_of_the_distribution() {
+        let releases: Vec<ReleaseDto> = serde_json::from_str(
+            r#"[
+                {"name": "5.9", "tag": "swift-5.9-RELEASE", "platforms": [
+                    {"name": "Ubuntu 20.04", "platform": "Linux", "archs": ["x86_64", "aarch64"]},
+                    {"name": "Ubuntu 22.04", "platform": "Linux", "archs": ["x86_64"]}
+                ]},
+                {"name": "5.10.1", "tag": "swift-5.10.1-RELEASE", "platforms": [
+                    {"name": "Ubuntu 22.04", "platform": "Linux", "archs": ["x86_64", "aarch64"]},
+                    {"name": "Windows 10", "platform": "Windows", "archs": ["x86_64"]}
+                ]}
+            ]"#,
+        )
+        .unwrap();
+        let filter = VersionFilter::default();
+        let versions = |platform, flavor| {
+            let build = Build::new(platform, Some(flavor)).unwrap();
+            sorted_versions(matching_releases(&releases, &build, &filter).map(|(v, _)| v))
+                .into_iter()
+                .map(|version| version.version)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(versions("x64-linux", "ubuntu22.04"), ["5.9", "5.10.1"]);
+        assert_eq!(versions("arm64-linux", "ubuntu22.04"), ["5.10.1"]);
+        assert_eq!(versions("arm64-linux", "ubuntu20.04"), ["5.9"]);
+        assert!(versions("x64-linux", "amazonlinux2").is_empty());
+
+        assert_eq!(
+            Build::new("arm64-linux", Some("ubuntu22.04"))
+                .unwrap()
+                .url("swift-5.10.1-RELEASE"),
+            "https://download.swift.org/swift-5.10.1-release/ubuntu2204-aarch64/swift-5.10.1-RELEASE/swift-5.10.1-RELEASE-ubuntu22.04-aarch64.tar.gz"
+        );
+    }
+}
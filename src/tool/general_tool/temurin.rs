@@ -0,0 +1,405 @@
+//! Eclipse Temurin JDK/JRE builds, listed by the Adoptium API.
+
+use rustc_hash::FxHashSet;
+use serde::Deserialize;
+use smol_str::SmolStr;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use super::liberica::{best_local_jdk_tag, match_jdk_version_filter, JdkVersion, JDK_EXES};
+use crate::platform::{cpu, create_platform_string, os, resolve_default_platform};
+use crate::tool::end_of_life::{EndOfLife, SupportStatus};
+use crate::tool::{ToolDownInfo, ToolInfo, Version, VersionFilter};
+use crate::HttpClient;
+
+pub struct Tool {
+    client: Arc<HttpClient>,
+    info: ToolInfo,
+    end_of_life: EndOfLife,
+}
+
+const API_URL: &str = "https://api.adoptium.net/v3/";
+/// The image types, only HotSpot builds are published.
+const FLAVORS: &[&str] = &["jdk", "jre"];
+/// The largest page the API returns.
+const PAGE_SIZE: usize = 50;
+
+/// avm platforms to the `os` and `architecture` of the API.
+const PLATFORMS: [(&str, &str, &str, &str); 15] = [
+    (cpu::X64, os::LINUX, "linux", "x64"),
+    (cpu::ARM64, os::LINUX, "linux", "aarch64"),
+    (cpu::ARM32, os::LINUX, "linux", "arm"),
+    (cpu::PPC64LE, os::LINUX, "linux", "ppc64le"),
+    (cpu::S390X, os::LINUX, "linux", "s390x"),
+    (cpu::RISCV64, os::LINUX, "linux", "riscv64"),
+    (cpu::X64, os::LINUX_MUSL, "alpine-linux", "x64"),
+    (cpu::ARM64, os::LINUX_MUSL, "alpine-linux", "aarch64"),
+    (cpu::X64, os::MAC, "mac", "x64"),
+    (cpu::ARM64, os::MAC, "mac", "aarch64"),
+    (cpu::X64, os::WIN, "windows", "x64"),
+    (cpu::X86, os::WIN, "windows", "x86"),
+    (cpu::ARM64, os::WIN, "windows", "aarch64"),
+    (cpu::PPC64, os::AIX, "aix", "ppc64"),
+    (cpu::SPARC64, os::SOLARIS, "solaris", "sparcv9"),
+];
+
+impl crate::tool::GeneralTool for Tool {
+    fn info(&self) -> &ToolInfo {
+        &self.info
+    }
+
+    fn endpoints(&self) -> Vec<SmolStr> {
+        vec![
+            smol_str::format_smolstr!("{API_URL}info/available_releases"),
+            self.end_of_life.url(),
+        ]
+    }
+
+    async fn support_status(&self, version: &str) -> crate::Result<Option<SupportStatus>> {
+        self.end_of_life.support_status(version).await
+    }
+
+    fn describe_flavor(&self, flavor: &str) -> &'static str {
+        match flavor {
+            "jdk" => "Java Development Kit, to build and run Java applications.",
+            "jre" => "Java Runtime Environment, to run Java applications.",
+            _ => "Tool-specific build flavor.",
+        }
+    }
+
+    async fn fetch_versions(
+        &self,
+        platform: Option<SmolStr>,
+        flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<Vec<Version>> {
+        let query = self.query(platform, flavor)?;
+        let available = self.available_releases().await?;
+
+        let mut versions = Vec::new();
+        for page in 0.. {
+            let mut url = self.url("info/release_versions", &query)?;
+            url.query_pairs_mut().append_pair("page", &page.to_string());
+            if let Some(prefix) = version_filter.version_prefix {
+                url.query_pairs_mut().append_pair(
+                    "version",
+                    &format!("[{},{})", prefix.major, prefix.major + 1),
+                );
+            }
+            let Some(page) = self.get_page::<ReleaseVersionsDto>(url).await? else {
+                break;
+            };
+            let len = page.versions.len();
+            versions.extend(page.versions);
+            if len < PAGE_SIZE {
+                break;
+            }
+        }
+
+        let mut versions = versions
+            .iter()
+            .map(|version| available.version(version))
+            .filter(|version| {
+                let raw = &*version.version;
+                match_jdk_version_filter(
+                    raw,
+                    JdkVersion::parse(raw),
+                    version.is_lts,
+                    &version_filter,
+                )
+            })
+            .collect::<Vec<_>>();
+        versions.sort_by_key(|version| JdkVersion::parse(&version.version));
+        let mut version_set = FxHashSet::default();
+        versions.retain(|version| version_set.insert(version.version.clone()));
+        Ok(versions)
+    }
+
+    async fn get_down_info(
+        &self,
+        platform: Option<SmolStr>,
+        flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<ToolDownInfo> {
+        let query = self.query(platform, flavor)?;
+        let available = self.available_releases().await?;
+        let features = if let Some(exact) = &version_filter.exact_version {
+            vec![JdkVersion::parse(exact).major]
+        } else if let Some(prefix) = version_filter.version_prefix {
+            vec![prefix.major]
+        } else if version_filter.lts_only {
+            available.available_lts_releases.clone()
+        } else {
+            available.available_releases.clone()
+        };
+
+        // The newest feature release may not be built for the platform yet, or any more.
+        for feature in features.into_iter().rev() {
+            for page in 0.. {
+                let mut url = self.url(&format!("assets/feature_releases/{feature}/ga"), &query)?;
+                url.query_pairs_mut().append_pair("page", &page.to_string());
+                let Some(releases) = self.get_page::<Vec<ReleaseDto>>(url).await? else {
+                    break;
+                };
+                for release in &releases {
+                    let version = available.version(&release.version_data);
+                    let raw = &*version.version;
+                    if !match_jdk_version_filter(
+                        raw,
+                        JdkVersion::parse(raw),
+                        version.is_lts,
+                        &version_filter,
+                    ) {
+                        continue;
+                    }
+                    let Some(binary) = release.binaries.first() else {
+                        continue;
+                    };
+                    return Ok(ToolDownInfo {
+                        version,
+                        url: binary.package.link.clone(),
+                        hash: crate::FileHash::from_sha256(binary.package.checksum.clone()),
+                    });
+                }
+                if releases.len() < PAGE_SIZE {
+                    break;
+                }
+            }
+        }
+        Err(crate::AvmError::VersionNotFound)
+    }
+
+    fn find_best_matching_local_tag<'a, I>(
+        &self,
+        tags_and_versions: I,
+        version_filter: &VersionFilter,
+    ) -> Option<SmolStr>
+    where
+        I: Iterator<Item = (&'a str, &'a Version)>,
+    {
+        best_local_jdk_tag(tags_and_versions, version_filter)
+    }
+
+    fn entry_path(&self, tag_dir: PathBuf) -> crate::Result<PathBuf> {
+        let mut p = java_home(tag_dir);
+        p.push("bin");
+        p.push(format!("java{}", std::env::consts::EXE_SUFFIX));
+        Ok(p)
+    }
+
+    fn exe_paths(&self, tag_dir: PathBuf) -> crate::Result<Vec<(SmolStr, PathBuf)>> {
+        Ok(super::exes_in(&java_home(tag_dir).join("bin"), JDK_EXES))
+    }
+
+    fn env_vars(&self, tag_dir: &Path) -> Vec<(OsString, OsString)> {
+        vec![("JAVA_HOME".into(), java_home(tag_dir.to_path_buf()).into())]
+    }
+}
+
+impl Tool {
+    pub fn new(client: Arc<HttpClient>, config_default_platform: Option<SmolStr>) -> Self {
+        let all_platforms = PLATFORMS
+            .iter()
+            .map(|(cpu, os, _, _)| create_platform_string(cpu, os))
+            .collect::<Vec<_>>();
+        let default_platform =
+            resolve_default_platform(&all_platforms, config_default_platform, &[]);
+        let end_of_life = EndOfLife::new(client.clone(), "eclipse-temurin");
+        Tool {
+            client,
+            info: ToolInfo {
+                about: "Eclipse Temurin Java JDK/JRE".into(),
+                after_long_help: Some(
+                    "Flavors are the `jdk` and `jre` images of the HotSpot builds. Only general availability releases are listed, the archives are verified with the SHA-256 published by Adoptium."
+                        .into(),
+                ),
+                all_platforms: Some(all_platforms),
+                default_platform,
+                all_flavors: Some(FLAVORS.iter().map(SmolStr::new).collect()),
+                default_flavor: Some("jdk".into()),
+            },
+            end_of_life,
+        }
+    }
+
+    /// The query parameters selecting the builds for the platform and flavor.
+    fn query(
+        &self,
+        platform: Option<SmolStr>,
+        flavor: Option<SmolStr>,
+    ) -> crate::Result<Vec<(&'static str, SmolStr)>> {
+        let platform = platform.ok_or(crate::AvmError::PlatformRequired)?;
+        let (api_os, api_arch) = PLATFORMS
+            .iter()
+            .find(|(cpu, os, _, _)| create_platform_string(cpu, os) == platform)
+            .map(|(_, _, api_os, api_arch)| (*api_os, *api_arch))
+            .ok_or_else(|| anyhow::anyhow!("Unsupported Temurin platform: {platform}"))?;
+        let image_type = flavor.unwrap_or_else(|| "jdk".into());
+        Ok(vec![
+            ("architecture", api_arch.into()),
+            ("os", api_os.into()),
+            ("image_type", image_type),
+            ("jvm_impl", "hotspot".into()),
+            ("heap_size", "normal".into()),
+            ("vendor", "eclipse".into()),
+            ("project", "jdk".into()),
+            ("release_type", "ga".into()),
+            ("sort_order", "DESC".into()),
+            ("page_size", smol_str::format_smolstr!("{PAGE_SIZE}")),
+        ])
+    }
+
+    fn url(&self, path: &str, query: &[(&str, SmolStr)]) -> anyhow::Result<reqwest::Url> {
+        let mut url = reqwest::Url::parse(&format!("{API_URL}{path}"))
+            .map_err(|err| anyhow::anyhow!("Invalid Adoptium API URL '{path}': {err}"))?;
+        url.query_pairs_mut()
+            .extend_pairs(query.iter().map(|(key, value)| (key, value.as_str())));
+        Ok(url)
+    }
+
+    async fn available_releases(&self) -> anyhow::Result<AvailableReleasesDto> {
+        Ok(self
+            .client
+            .get(&format!("{API_URL}info/available_releases"))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    /// A page of results, `None` past the last one, for which the API answers 404.
+    async fn get_page<T: serde::de::DeserializeOwned>(
+        &self,
+        url: reqwest::Url,
+    ) -> anyhow::Result<Option<T>> {
+        let response = self.client.get(url.as_str()).send().await?;
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        Ok(Some(response.error_for_status()?.json().await?))
+    }
+}
+
+/// The Java home in a tag directory, under `Contents/Home` for the macOS bundles.
+pub(super) fn java_home(tag_dir: PathBuf) -> PathBuf {
+    let bundle_home = tag_dir.join("Contents").join("Home");
+    if bundle_home.is_dir() {
+        bundle_home
+    } else {
+        tag_dir
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AvailableReleasesDto {
+    available_releases: Vec<u32>,
+    available_lts_releases: Vec<u32>,
+}
+
+impl AvailableReleasesDto {
+    /// The version in the format of [`JdkVersion`], like `21.0.5+11` or `8u432+6`.
+    fn version(&self, data: &VersionDataDto) -> Version {
+        let mut version = if data.major == 8 {
+            format!("8u{}", data.security)
+        } else if data.minor == 0 && data.security == 0 && data.patch.unwrap_or(0) == 0 {
+            data.major.to_string()
+        } else {
+            format!("{}.{}.{}", data.major, data.minor, data.security)
+        };
+        if let Some(patch) = data.patch.filter(|&patch| patch > 0 && data.major != 8) {
+            version.push_str(&format!(".{patch}"));
+        }
+        version.push_str(&format!("+{}", data.build));
+        Version {
+            version: version.into(),
+            is_lts: self.available_lts_releases.contains(&data.major),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ReleaseVersionsDto {
+    versions: Vec<VersionDataDto>,
+}
+
+#[derive(Debug, Deserialize)]
+struct VersionDataDto {
+    major: u32,
+    minor: u32,
+    security: u32,
+    #[serde(default)]
+    patch: Option<u32>,
+    build: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReleaseDto {
+    binaries: Vec<BinaryDto>,
+    version_data: VersionDataDto,
+}
+
+#[derive(Debug, Deserialize)]
+struct BinaryDto {
+    package: PackageDto,
+}
+
+#[derive(Debug, Deserialize)]
+struct PackageDto {
+    link: SmolStr,
+    checksum: SmolStr,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn formats_versions_like_jdk_versions() {
+        let available: AvailableReleasesDto = serde_json::from_str(
+            r#"{"available_releases": [8, 11, 17, 21, 22], "available_lts_releases": [8, 11, 17, 21], "most_recent_feature_release": 22}"#,
+        )
+        .unwrap();
+        let release: ReleaseDto = serde_json::from_str(
+            r#"{
+                "binaries": [{"architecture": "x64", "image_type": "jdk", "package": {"checksum": "abc", "link": "https://example.com/OpenJDK21U-jdk_x64_linux_hotspot_21.0.5_11.tar.gz", "name": "OpenJDK21U-jdk_x64_linux_hotspot_21.0.5_11.tar.gz"}}],
+                "release_name": "jdk-21.0.5+11",
+                "version_data": {"build": 11, "major": 21, "minor": 0, "security": 5, "openjdk_version": "21.0.5+11-LTS", "semver": "21.0.5+11.0.LTS"}
+            }"#,
+        )
+        .unwrap();
+        let version = available.version(&release.version_data);
+        assert_eq!(version.version, "21.0.5+11");
+        assert!(version.is_lts);
+        assert_eq!(release.binaries[0].package.checksum, "abc");
+
+        let version = |json: &str| {
+            let data: VersionDataDto = serde_json::from_str(json).unwrap();
+            let version = available.version(&data);
+            (version.version, version.is_lts)
+        };
+        assert_eq!(
+            version(r#"{"major": 8, "minor": 0, "security": 432, "build": 6}"#),
+            ("8u432+6".into(), true)
+        );
+        assert_eq!(
+            version(r#"{"major": 22, "minor": 0, "security": 0, "build": 36}"#),
+            ("22+36".into(), false)
+        );
+        assert_eq!(
+            version(r#"{"major": 17, "minor": 0, "security": 8, "patch": 1, "build": 1}"#),
+            ("17.0.8.1+1".into(), true)
+        );
+        assert_eq!(
+            JdkVersion::parse("17.0.8.1+1"),
+            JdkVersion {
+                major: 17,
+                minor: 0,
+                security: 8,
+                patch: 1,
+                build: 1
+            }
+        );
+    }
+}
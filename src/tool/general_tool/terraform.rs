@@ -0,0 +1,275 @@
+use rustc_hash::FxHashMap;
+use serde::Deserialize;
+use smol_str::SmolStr;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use super::manifest::{best_local_tag, sorted_versions, ReleaseVersion};
+use crate::platform::{cpu, create_platform_string, os, resolve_default_platform, MUSL_TO_GLIBC};
+use crate::tool::{ToolDownInfo, ToolInfo, Version, VersionFilter};
+use crate::HttpClient;
+
+pub struct Tool {
+    client: Arc<HttpClient>,
+    info: ToolInfo,
+}
+
+const BASE_URL: &str = "https://releases.hashicorp.com/terraform/";
+
+/// Platforms by the OS and architecture names in HashiCorp-style release file names,
+/// `<name>_<version>_<os>_<arch>.zip`, which OpenTofu shares.
+const PLATFORMS: [(&str, &str, &str, &str); 9] = [
+    (cpu::X64, os::LINUX, "linux", "amd64"),
+    (cpu::ARM64, os::LINUX, "linux", "arm64"),
+    (cpu::X86, os::LINUX, "linux", "386"),
+    (cpu::ARM32, os::LINUX, "linux", "arm"),
+    (cpu::X64, os::MAC, "darwin", "amd64"),
+    (cpu::ARM64, os::MAC, "darwin", "arm64"),
+    (cpu::X64, os::WIN, "windows", "amd64"),
+    (cpu::X86, os::WIN, "windows", "386"),
+    (cpu::X64, os::FREEBSD, "freebsd", "amd64"),
+];
+
+impl crate::tool::GeneralTool for Tool {
+    fn info(&self) -> &ToolInfo {
+        &self.info
+    }
+
+    fn endpoints(&self) -> Vec<SmolStr> {
+        vec![smol_str::format_smolstr!("{BASE_URL}index.json")]
+    }
+
+    async fn fetch_versions(
+        &self,
+        platform: Option<SmolStr>,
+        _flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<Vec<Version>> {
+        let platform = platform.ok_or(crate::AvmError::PlatformRequired)?;
+        let version_filter = ignore_lts_only(version_filter, "terraform");
+        let index = self.fetch_index().await?;
+        Ok(index.matching_versions(&platform, &version_filter)?)
+    }
+
+    async fn get_down_info(
+        &self,
+        platform: Option<SmolStr>,
+        _flavor: Option<SmolStr>,
+        version_filter: VersionFilter,
+    ) -> crate::Result<ToolDownInfo> {
+        let platform = platform.ok_or(crate::AvmError::PlatformRequired)?;
+        let version_filter = ignore_lts_only(version_filter, "terraform");
+        let index = self.fetch_index().await?;
+        let versions = index.matching_versions(&platform, &version_filter)?;
+        let version = versions.last().ok_or(crate::AvmError::VersionNotFound)?;
+        let release = &index.versions[&version.version];
+        let build = release
+            .build(&platform)?
+            .ok_or(crate::AvmError::VersionNotFound)?;
+
+        let sums = self
+            .client
+            .get(&format!(
+                "{BASE_URL}{}/{}",
+                release.version, release.shasums
+            ))
+            .send()
+            .await?
+            .error_for_status()?
+            .text()
+            .await?;
+        let sha256 = find_sha256(&sums, &build.filename).ok_or_else(|| {
+            anyhow::anyhow!("No checksum for {} in {}", build.filename, release.shasums)
+        })?;
+        Ok(ToolDownInfo {
+            version: version.clone(),
+            url: build.url.clone(),
+            hash: crate::FileHash {
+                sha256: Some(sha256),
+                ..Default::default()
+            },
+        })
+    }
+
+    fn find_best_matching_local_tag<'a, I>(
+        &self,
+        tags_and_versions: I,
+        version_filter: &VersionFilter,
+    ) -> Option<SmolStr>
+    where
+        I: Iterator<Item = (&'a str, &'a Version)>,
+    {
+        let version_filter = ignore_lts_only(version_filter.clone(), "terraform");
+        best_local_tag(tags_and_versions, &version_filter)
+    }
+
+    fn entry_path(&self, tag_dir: PathBuf) -> crate::Result<PathBuf> {
+        Ok(super::exes_in(&tag_dir, &["terraform"]).swap_remove(0).1)
+    }
+}
+
+impl Tool {
+    pub fn new(client: Arc<HttpClient>, config_default_platform: Option<SmolStr>) -> Self {
+        let all_platforms = all_platforms();
+        // Terraform is a statically linked Go binary, the Linux builds also run on musl.
+        let default_platform =
+            resolve_default_platform(&all_platforms, config_default_platform, &[MUSL_TO_GLIBC]);
+        Tool {
+            client,
+            info: ToolInfo {
+                about: "HashiCorp Terraform infrastructure as code tool".into(),
+                after_long_help: None,
+                all_platforms: Some(all_platforms),
+                default_platform,
+                all_flavors: None,
+                default_flavor: None,
+            },
+        }
+    }
+
+    async fn fetch_index(&self) -> anyhow::Result<IndexDto> {
+        Ok(self
+            .client
+            .get(&format!("{BASE_URL}index.json"))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct IndexDto {
+    versions: FxHashMap<SmolStr, ReleaseDto>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReleaseDto {
+    version: SmolStr,
+    /// The file name of the checksums of the builds.
+    shasums: SmolStr,
+    builds: Vec<BuildDto>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BuildDto {
+    os: SmolStr,
+    arch: SmolStr,
+    filename: SmolStr,
+    url: SmolStr,
+}
+
+impl IndexDto {
+    fn matching_versions(
+        &self,
+        platform: &str,
+        version_filter: &VersionFilter,
+    ) -> anyhow::Result<Vec<Version>> {
+        let mut versions = Vec::new();
+        for release in self.versions.values() {
+            // Versions with build metadata, such as `+ent`, are other editions.
+            if release.version.contains('+') || release.build(platform)?.is_none() {
+                continue;
+            }
+            if let Some(version) = ReleaseVersion::parse(release.version.clone(), false, None) {
+                if version.matches(version_filter) {
+                    versions.push(version);
+                }
+            }
+        }
+        Ok(sorted_versions(versions.into_iter()))
+    }
+}
+
+impl ReleaseDto {
+    fn build(&self, platform: &str) -> anyhow::Result<Option<&BuildDto>> {
+        let (os, arch) = dto_os_arch(platform)?;
+        Ok(self
+            .builds
+            .iter()
+            .find(|build| build.os == os && build.arch == arch))
+    }
+}
+
+pub(super) fn all_platforms() -> Vec<SmolStr> {
+    PLATFORMS
+        .iter()
+        .map(|(cpu, os, _, _)| create_platform_string(cpu, os))
+        .collect()
+}
+
+/// The OS and architecture names of the platform in release file names.
+pub(super) fn dto_os_arch(platform: &str) -> anyhow::Result<(&'static str, &'static str)> {
+    PLATFORMS
+        .iter()
+        .find(|(cpu, os, _, _)| create_platform_string(cpu, os) == platform)
+        .map(|(_, _, dto_os, dto_arch)| (*dto_os, *dto_arch))
+        .ok_or_else(|| anyhow::anyhow!("Unsupported platform: {platform}"))
+}
+
+/// Finds the checksum of `file_name` in a `SHA256SUMS` file, lines of `<sha256>  <file name>`.
+pub(super) fn find_sha256(sums: &str, file_name: &str) -> Option<SmolStr> {
+    sums.lines().find_map(|line| {
+        let mut fields = line.split_whitespace();
+        let sha256 = fields.next()?;
+        (fields.next()? == file_name).then(|| sha256.into())
+    })
+}
+
+pub(super) fn ignore_lts_only(mut version_filter: VersionFilter, tool: &str) -> VersionFilter {
+    if version_filter.lts_only {
+        log::warn!(
+            "`--lts-only` is ignored for `{tool}` because this tool does not define LTS releases."
+        );
+        version_filter.lts_only = false;
+    }
+    version_filter
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selects_platform_builds_of_releases() {
+        let index: IndexDto = serde_json::from_str(
+            r#"{"name": "terraform", "versions": {
+                "1.9.8": {"version": "1.9.8", "shasums": "terraform_1.9.8_SHA256SUMS", "builds": [
+                    {"os": "linux", "arch": "amd64", "filename": "terraform_1.9.8_linux_amd64.zip", "url": "https://example.com/terraform_1.9.8_linux_amd64.zip"},
+                    {"os": "darwin", "arch": "arm64", "filename": "terraform_1.9.8_darwin_arm64.zip", "url": "https://example.com/terraform_1.9.8_darwin_arm64.zip"}
+                ]},
+                "1.10.0-rc1": {"version": "1.10.0-rc1", "shasums": "terraform_1.10.0-rc1_SHA256SUMS", "builds": [
+                    {"os": "linux", "arch": "amd64", "filename": "terraform_1.10.0-rc1_linux_amd64.zip", "url": "https://example.com/terraform_1.10.0-rc1_linux_amd64.zip"}
+                ]},
+                "1.8.0": {"version": "1.8.0", "shasums": "terraform_1.8.0_SHA256SUMS", "builds": [
+                    {"os": "darwin", "arch": "arm64", "filename": "terraform_1.8.0_darwin_arm64.zip", "url": "https://example.com/terraform_1.8.0_darwin_arm64.zip"}
+                ]}
+            }}"#,
+        )
+        .unwrap();
+        let filter = VersionFilter::default();
+        let versions = |platform| {
+            index
+                .matching_versions(platform, &filter)
+                .unwrap()
+                .into_iter()
+                .map(|version| version.version)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(versions("x64-linux"), ["1.9.8"]);
+        assert_eq!(versions("arm64-mac"), ["1.8.0", "1.9.8"]);
+        assert!(index.matching_versions("riscv64-linux", &filter).is_err());
+    }
+
+    #[test]
+    fn finds_checksums() {
+        let sums =
+            "ab12  terraform_1.9.8_darwin_arm64.zip\ncd34  terraform_1.9.8_linux_amd64.zip\n";
+        assert_eq!(
+            find_sha256(sums, "terraform_1.9.8_linux_amd64.zip").as_deref(),
+            Some("cd34")
+        );
+        assert_eq!(find_sha256(sums, "terraform_1.9.8_linux_arm64.zip"), None);
+    }
+}
@@ -0,0 +1,311 @@
+//! Releases of GitHub repositories, for the tools that publish their builds as release assets.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use reqwest::header::{HeaderMap, HeaderValue};
+use serde::Deserialize;
+use smol_str::SmolStr;
+
+use crate::HttpClient;
+
+const API_URL: &str = "https://api.github.com/";
+/// Older releases are not listed, bounding the requests spent on a listing.
+const MAX_PAGES: usize = 10;
+/// Attempts of a request that hits the rate limit.
+const MAX_ATTEMPTS: usize = 3;
+/// The longest wait for the rate limit to reset before giving up.
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct ReleaseDto {
+    /// Such as `v1.12.1`, for the tools whose asset names have no version.
+    #[serde(default)]
+    pub tag_name: SmolStr,
+    pub assets: Vec<AssetDto>,
+}
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct AssetDto {
+    pub name: SmolStr,
+    pub browser_download_url: SmolStr,
+    /// Like `sha256:<hex>`, missing for assets uploaded before GitHub computed digests.
+    #[serde(default)]
+    pub digest: Option<SmolStr>,
+}
+
+impl AssetDto {
+    pub fn sha256(&self) -> Option<SmolStr> {
+        self.digest
+            .as_deref()
+            .and_then(|digest| digest.strip_prefix("sha256:"))
+            .map(SmolStr::new)
+    }
+}
+
+/// The first page of the releases of `repo`, such as `JetBrains/kotlin`, newest first.
+pub(crate) fn releases_url(repo: &str) -> SmolStr {
+    smol_str::format_smolstr!("{API_URL}repos/{repo}/releases?per_page=100")
+}
+
+pub(crate) fn release_by_tag_url(repo: &str, tag: &str) -> SmolStr {
+    smol_str::format_smolstr!("{API_URL}repos/{repo}/releases/tags/{tag}")
+}
+
+/// The releases of `repo`, following the pages up to [`MAX_PAGES`].
+pub(crate) async fn fetch_releases(
+    client: &HttpClient,
+    repo: &str,
+) -> anyhow::Result<Vec<ReleaseDto>> {
+    let mut releases = Vec::new();
+    let mut url = Some(releases_url(repo).to_string());
+    for _ in 0..MAX_PAGES {
+        let Some(page_url) = url.take() else {
+            break;
+        };
+        let response = get(client, &page_url).await?;
+        url = next_page_url(response.headers());
+        releases.extend(response.json::<Vec<ReleaseDto>>().await?);
+    }
+    if url.is_some() {
+        log::debug!("Only the first {MAX_PAGES} pages of the releases of {repo} are listed");
+    }
+    Ok(releases)
+}
+
+pub(crate) async fn fetch_release_by_tag(
+    client: &HttpClient,
+    repo: &str,
+    tag: &str,
+) -> anyhow::Result<ReleaseDto> {
+    Ok(get(client, &release_by_tag_url(repo, tag))
+        .await?
+        .json()
+        .await?)
+}
+
+/// Sends an API request, authenticated with the configured token, waiting for the rate limit
+/// to reset when it is hit shortly before it does.
+async fn get(client: &HttpClient, url: &str) -> anyhow::Result<reqwest::Response> {
+    // The token is only for GitHub, not for a mirror standing in for it.
+    let token = client
+        .github_token()
+        .filter(|_| url.starts_with(API_URL) && client.mirrored_url(url).is_none());
+    let mut attempt = 1;
+    loop {
+        let mut request = client.get(url).header(
+            reqwest::header::ACCEPT,
+            HeaderValue::from_static("application/vnd.github+json"),
+        );
+        if let Some(token) = token {
+            request = request.header(
+                reqwest::header::AUTHORIZATION,
+                HeaderValue::from_str(&format!("Bearer {token}"))?,
+            );
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        if status != reqwest::StatusCode::FORBIDDEN
+            && status != reqwest::StatusCode::TOO_MANY_REQUESTS
+        {
+            return Ok(response.error_for_status()?);
+        }
+        let Some(wait) = rate_limit_wait(response.headers(), SystemTime::now()) else {
+            return Ok(response.error_for_status()?);
+        };
+        if wait > MAX_BACKOFF || attempt == MAX_ATTEMPTS {
+            let hint = if token.is_some() {
+                ""
+            } else {
+                ", set `GITHUB_TOKEN` or `github_token` in the config to raise the limit"
+            };
+            anyhow::bail!(
+                "GitHub API rate limit exceeded, it resets in {}s{hint}",
+                wait.as_secs()
+            );
+        }
+        log::warn!(
+            "GitHub API rate limit exceeded, retrying in {}s",
+            wait.as_secs()
+        );
+        tokio::time::sleep(wait).await;
+        attempt += 1;
+    }
+}
+
+/// How long to wait before retrying a rejected request, `None` if it was not rate limited.
+fn rate_limit_wait(headers: &HeaderMap, now: SystemTime) -> Option<Duration> {
+    let header = |name: &str| headers.get(name)?.to_str().ok()?.trim().parse::<u64>().ok();
+    // Secondary rate limits tell how long to wait.
+    if let Some(seconds) = header("retry-after") {
+        return Some(Duration::from_secs(seconds));
+    }
+    if header("x-ratelimit-remaining")? != 0 {
+        return None;
+    }
+    let reset = UNIX_EPOCH + Duration::from_secs(header("x-ratelimit-reset")?);
+    Some(reset.duration_since(now).unwrap_or_default())
+}
+
+/// The `rel="next"` target of a `Link` header.
+fn next_page_url(headers: &HeaderMap) -> Option<String> {
+    let link = headers.get(reqwest::header::LINK)?.to_str().ok()?;
+    link.split(',').find_map(|link| {
+        let (target, params) = link.split_once(';')?;
+        params
+            .split(';')
+            .any(|param| param.trim() == r#"rel="next""#)
+            .then(|| {
+                target
+                    .trim()
+                    .trim_start_matches('<')
+                    .trim_end_matches('>')
+                    .to_owned()
+            })
+    })
+}
+
+/// Picks the assets of a platform by a regular expression matched against the whole asset
+/// name, whose `version` group captures the version. Tools without platforms have a single
+/// pattern for `None`.
+pub(crate) struct AssetMatcher {
+    patterns: Vec<(Option<SmolStr>, regex::Regex)>,
+}
+
+pub(crate) struct AssetMatch<'a> {
+    pub asset: &'a AssetDto,
+    pub captures: regex::Captures<'a>,
+}
+
+impl AssetMatch<'_> {
+    pub fn version(&self) -> &str {
+        &self.captures["version"]
+    }
+}
+
+impl AssetMatcher {
+    /// Panics on an invalid pattern, the patterns are part of the tools.
+    pub fn new<P: AsRef<str>>(patterns: impl IntoIterator<Item = (Option<SmolStr>, P)>) -> Self {
+        Self {
+            patterns: patterns
+                .into_iter()
+                .map(|(platform, pattern)| {
+                    let pattern = pattern.as_ref();
+                    let regex = regex::Regex::new(&format!("^(?:{pattern})$"))
+                        .unwrap_or_else(|err| panic!("Invalid asset pattern {pattern:?}: {err}"));
+                    assert!(
+                        regex.capture_names().any(|name| name == Some("version")),
+                        "Asset pattern {pattern:?} has no `version` group"
+                    );
+                    (platform, regex)
+                })
+                .collect(),
+        }
+    }
+
+    /// The assets of the releases matching the pattern of `platform`.
+    pub fn matching<'a>(
+        &self,
+        platform: Option<&str>,
+        releases: &'a [ReleaseDto],
+    ) -> anyhow::Result<Vec<AssetMatch<'a>>> {
+        let (_, regex) = self
+            .patterns
+            .iter()
+            .find(|(pattern_platform, _)| pattern_platform.as_deref() == platform)
+            .ok_or_else(|| {
+                anyhow::anyhow!("Unsupported platform: {}", platform.unwrap_or_default())
+            })?;
+        Ok(releases
+            .iter()
+            .flat_map(|release| &release.assets)
+            .filter_map(|asset| {
+                Some(AssetMatch {
+                    captures: regex.captures(&asset.name)?,
+                    asset,
+                })
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        pairs
+            .iter()
+            .map(|(name, value)| {
+                (
+                    reqwest::header::HeaderName::from_static(name),
+                    HeaderValue::from_str(value).unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn follows_next_page_links() {
+        let link = r#"<https://api.github.com/repositories/1/releases?per_page=100&page=3>; rel="next", <https://api.github.com/repositories/1/releases?per_page=100&page=5>; rel="last""#;
+        assert_eq!(
+            next_page_url(&headers(&[("link", link)])).as_deref(),
+            Some("https://api.github.com/repositories/1/releases?per_page=100&page=3")
+        );
+        let last_page =
+            r#"<https://api.github.com/repositories/1/releases?per_page=100&page=1>; rel="first""#;
+        assert_eq!(next_page_url(&headers(&[("link", last_page)])), None);
+        assert_eq!(next_page_url(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn waits_for_rate_limit_reset() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_000);
+        let exhausted = headers(&[
+            ("x-ratelimit-remaining", "0"),
+            ("x-ratelimit-reset", "1030"),
+        ]);
+        assert_eq!(
+            rate_limit_wait(&exhausted, now),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            rate_limit_wait(&headers(&[("retry-after", "5")]), now),
+            Some(Duration::from_secs(5))
+        );
+        // Forbidden for another reason.
+        let remaining = headers(&[
+            ("x-ratelimit-remaining", "12"),
+            ("x-ratelimit-reset", "1030"),
+        ]);
+        assert_eq!(rate_limit_wait(&remaining, now), None);
+        assert_eq!(rate_limit_wait(&HeaderMap::new(), now), None);
+    }
+
+    #[test]
+    fn matches_assets_of_platform() {
+        let releases: Vec<ReleaseDto> = serde_json::from_str(
+            r#"[{"assets": [
+                {"name": "tool-2.1.0-linux-x64.tar.gz", "browser_download_url": "https://example.com/a", "digest": "sha256:ab"},
+                {"name": "tool-2.1.0-linux-x64.tar.gz.sha256", "browser_download_url": "https://example.com/b"},
+                {"name": "tool-2.1.0-macos-arm64.tar.gz", "browser_download_url": "https://example.com/c"}
+            ]}]"#,
+        )
+        .unwrap();
+        let matcher = AssetMatcher::new([
+            (
+                Some("x64-linux".into()),
+                r"tool-(?<version>.+)-linux-x64\.tar\.gz",
+            ),
+            (
+                Some("arm64-mac".into()),
+                r"tool-(?<version>.+)-macos-arm64\.tar\.gz",
+            ),
+        ]);
+        let matches = matcher.matching(Some("x64-linux"), &releases).unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].version(), "2.1.0");
+        assert_eq!(matches[0].asset.sha256().as_deref(), Some("ab"));
+        assert!(matcher.matching(Some("x64-win"), &releases).is_err());
+        assert!(matcher.matching(None, &releases).is_err());
+    }
+}
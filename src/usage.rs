@@ -0,0 +1,153 @@
+//! When the installed tags were last run, kept in the state directory for `avm prune
+//! --unused-for` to find the tags nobody runs anymore. It is only local bookkeeping, nothing is
+//! reported anywhere.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use smol_str::SmolStr;
+
+use crate::io::blocking::AliasStore;
+use crate::tool::general_tool::InstalledTag;
+
+pub const USAGE_FILE: &str = "usage.json";
+const USAGE_LOCK_FILE: &str = "usage.lock";
+
+/// The last time each tag was run, by tool.
+#[derive(Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UsageStats {
+    /// Tool name to tag to seconds since the Unix epoch.
+    #[serde(default)]
+    pub last_used: BTreeMap<SmolStr, BTreeMap<SmolStr, u64>>,
+}
+
+impl UsageStats {
+    /// Reads the statistics in `state_dir`, empty if nothing was run yet.
+    pub async fn load(state_dir: &Path) -> crate::Result<Self> {
+        let state_dir = state_dir.to_path_buf();
+        Ok(crate::spawn_blocking(move || Self::load_blocking(&state_dir)).await?)
+    }
+
+    fn load_blocking(state_dir: &Path) -> anyhow::Result<Self> {
+        let path = state_dir.join(USAGE_FILE);
+        match std::fs::read(&path) {
+            Ok(content) => serde_json::from_slice(&content)
+                .with_context(|| format!("Failed to parse '{}'", path.display())),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => {
+                Err(anyhow::Error::from(err)
+                    .context(format!("Failed to read '{}'", path.display())))
+            }
+        }
+    }
+
+    /// The last time a tag was run, `None` if it was not run since the statistics were kept.
+    pub fn last_used(&self, tool_name: &str, tag: &str) -> Option<SystemTime> {
+        let secs = *self.last_used.get(tool_name)?.get(tag)?;
+        Some(UNIX_EPOCH + Duration::from_secs(secs))
+    }
+
+    /// Whether an installed tag was neither run nor installed within `period`. Tags installed by
+    /// older versions and never run since count as used, as their age is unknown.
+    pub fn is_unused_for(
+        &self,
+        tool_name: &str,
+        installed: &InstalledTag,
+        period: Duration,
+    ) -> bool {
+        let installed_at = installed
+            .info
+            .installed_at
+            .map(|secs| UNIX_EPOCH + Duration::from_secs(secs));
+        let Some(last_active) = self
+            .last_used(tool_name, &installed.tag)
+            .into_iter()
+            .chain(installed_at)
+            .max()
+        else {
+            return false;
+        };
+        SystemTime::now()
+            .duration_since(last_active)
+            .is_ok_and(|elapsed| elapsed >= period)
+    }
+}
+
+/// Records that a tag was run now. For an alias tag, its target is recorded.
+pub async fn record_use(
+    state_dir: &Path,
+    tools_base: &Path,
+    tool_name: &str,
+    tag: &str,
+) -> crate::Result<()> {
+    let state_dir = state_dir.to_path_buf();
+    let tool_dir = tools_base.join(tool_name);
+    let tool_name = SmolStr::from(tool_name);
+    let tag = SmolStr::from(tag);
+    Ok(crate::spawn_blocking(move || {
+        let tag = AliasStore::load(&tool_dir)?
+            .aliases
+            .remove(&tag)
+            .unwrap_or(tag);
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |now| now.as_secs());
+
+        std::fs::create_dir_all(&state_dir)?;
+        let lock_path = state_dir.join(USAGE_LOCK_FILE);
+        let lock = std::fs::File::create(&lock_path)
+            .with_context(|| format!("Failed to open '{}'", lock_path.display()))?;
+        lock.lock()
+            .with_context(|| format!("Failed to lock '{}'", lock_path.display()))?;
+
+        let mut stats = UsageStats::load_blocking(&state_dir)?;
+        stats
+            .last_used
+            .entry(tool_name)
+            .or_default()
+            .insert(tag, now);
+        let path = state_dir.join(USAGE_FILE);
+        let tmp_path = state_dir.join(format!("{USAGE_FILE}.tmp"));
+        std::fs::write(&tmp_path, serde_json::to_vec_pretty(&stats)?)?;
+        std::fs::rename(&tmp_path, &path)
+            .with_context(|| format!("Failed to write '{}'", path.display()))?;
+        Ok(())
+    })
+    .await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn records_the_target_of_aliases() {
+        let dir = tempfile::tempdir().unwrap();
+        let state_dir = dir.path().join("state");
+        let tools_base = dir.path().join("tools");
+        let tool_dir = tools_base.join("node");
+        std::fs::create_dir_all(tool_dir.join("x64-linux_22.0.0")).unwrap();
+        AliasStore::update(&tool_dir, |store| {
+            store
+                .aliases
+                .insert("default".into(), "x64-linux_22.0.0".into());
+            Ok(())
+        })
+        .unwrap();
+
+        assert_eq!(
+            UsageStats::load(&state_dir).await.unwrap(),
+            UsageStats::default()
+        );
+        record_use(&state_dir, &tools_base, "node", "default")
+            .await
+            .unwrap();
+        let stats = UsageStats::load(&state_dir).await.unwrap();
+        assert!(stats.last_used("node", "x64-linux_22.0.0").is_some());
+        assert!(stats.last_used("node", "default").is_none());
+        assert!(stats.last_used("go", "x64-linux_22.0.0").is_none());
+    }
+}
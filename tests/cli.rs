@@ -0,0 +1,3658 @@
+//! End-to-end tests running the `avm` binary against a fixture server.
+
+mod fixture;
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use assert_cmd::Command;
+use fixture::FixtureServer;
+use predicates::prelude::*;
+
+const NODE_SCRIPT: &[u8] = b"#!/bin/sh\necho \"fixture node $@\"\n";
+// Like the real npm, finds `node` through `PATH`.
+const NPM_SCRIPT: &[u8] = b"#!/bin/sh\nexec node npm-cli.js \"$@\"\n";
+
+struct Env {
+    server: FixtureServer,
+    _dir: tempfile::TempDir,
+    config_path: PathBuf,
+    data_path: PathBuf,
+}
+
+impl Env {
+    fn new() -> Self {
+        let mut files = HashMap::new();
+        files.insert(
+            "index.json".to_owned(),
+            br#"[
+                {"version": "v29.3.0", "lts": false, "files": ["linux-x64", "win-x64-zip"]},
+                {"version": "v28.1.0", "lts": "Fixture", "files": ["linux-x64"]},
+                {"version": "v27.0.0", "lts": false, "files": ["win-x64-zip"]}
+            ]"#
+            .to_vec(),
+        );
+        for version in ["29.3.0", "28.1.0"] {
+            let name = format!("node-v{version}-linux-x64.tar.xz");
+            let archive = fixture::tar_xz(
+                &format!("node-v{version}-linux-x64"),
+                &[
+                    ("bin/node", NODE_SCRIPT, 0o755),
+                    ("bin/npm", NPM_SCRIPT, 0o755),
+                ],
+            );
+            files.insert(
+                format!("v{version}/SHASUMS256.txt"),
+                format!("{}  {name}\n", fixture::sha256_hex(&archive)).into_bytes(),
+            );
+            files.insert(format!("v{version}/{name}"), archive);
+        }
+        let server = FixtureServer::start(files);
+
+        let dir = tempfile::tempdir().unwrap();
+        let data_path = dir.path().join("data");
+        let config_path = dir.path().join("config.toml");
+        std::fs::write(
+            &config_path,
+            format!(
+                "data_path = {:?}\n\n[[mirrors]]\nfrom = \"https://nodejs.org/dist/\"\nto = {:?}\n",
+                data_path,
+                server.base_url()
+            ),
+        )
+        .unwrap();
+
+        Self {
+            server,
+            _dir: dir,
+            config_path,
+            data_path,
+        }
+    }
+
+    fn avm(&self) -> Command {
+        let mut cmd = Command::cargo_bin("avm").unwrap();
+        cmd.env("CONFIG_PATH", &self.config_path)
+            .env("RUST_BACKTRACE", "0");
+        cmd
+    }
+
+    fn node_dir(&self) -> PathBuf {
+        self.data_path.join("tools").join("node")
+    }
+
+    fn append_config(&self, content: &str) {
+        let mut config = std::fs::read_to_string(&self.config_path).unwrap();
+        config.push_str(content);
+        std::fs::write(&self.config_path, config).unwrap();
+    }
+}
+
+fn assert_exists(path: &Path) {
+    assert!(path.exists(), "'{}' should exist", path.display());
+}
+
+#[test]
+fn config_path_uses_env_var() {
+    let env = Env::new();
+    env.avm()
+        .arg("config-path")
+        .assert()
+        .success()
+        .stdout(format!("{}\n", env.config_path.display()));
+}
+
+#[test]
+fn config_and_data_dir_flags_override() {
+    let env = Env::new();
+    let other_config = env.config_path.with_file_name("other.toml");
+    std::fs::copy(&env.config_path, &other_config).unwrap();
+    env.avm()
+        .arg("config-path")
+        .arg(format!("--config={}", other_config.display()))
+        .assert()
+        .success()
+        .stdout(format!("{}\n", other_config.display()));
+
+    let other_data = env.config_path.with_file_name("other-data");
+    env.avm()
+        .args([
+            "install",
+            "node",
+            "-p",
+            "x64-linux",
+            "-x",
+            "28",
+            "--data-dir",
+        ])
+        .arg(&other_data)
+        .assert()
+        .success();
+    assert_exists(
+        &other_data
+            .join("tools")
+            .join("node")
+            .join("x64-linux_28.1.0"),
+    );
+    assert!(!env.node_dir().exists());
+    env.avm()
+        .args(["list", "node", "--data-dir"])
+        .arg(&other_data)
+        .assert()
+        .success()
+        .stdout("x64-linux_28.1.0\n");
+}
+
+#[test]
+fn config_set_get_and_edit() {
+    let env = Env::new();
+    let config = std::fs::read_to_string(&env.config_path).unwrap();
+    std::fs::write(&env.config_path, format!("# Kept as is.\n{config}")).unwrap();
+    env.append_config("\n[tools.node]\n# LTS only.\nlts_only = false\n\n[tls]\n");
+
+    env.avm()
+        .args(["config", "set", "max_download_rate", "5MB/s"])
+        .assert()
+        .success();
+    env.avm()
+        .args(["config", "set", "tools.node.lts_only", "true"])
+        .assert()
+        .success();
+    env.avm()
+        .args(["config", "set", "tools.go.platform", "x64-linux"])
+        .assert()
+        .success();
+    let config = std::fs::read_to_string(&env.config_path).unwrap();
+    assert!(config.starts_with("# Kept as is.\ndata_path = "));
+    assert!(config.contains("max_download_rate = \"5MB/s\"\n\n[[mirrors]]"));
+    // A new table goes next to the other tables of its parent.
+    assert!(config.contains(
+        "[tools.node]\n# LTS only.\nlts_only = true\n\n[tools.go]\nplatform = \"x64-linux\"\n\n[tls]\n"
+    ));
+
+    env.avm()
+        .args(["config", "get", "tools.node.lts_only"])
+        .assert()
+        .success()
+        .stdout("true\n");
+    env.avm()
+        .args(["config", "get", "max_download_rate"])
+        .assert()
+        .success()
+        .stdout("5MB/s\n");
+    env.avm()
+        .args(["config", "get", "github_token"])
+        .assert()
+        .failure();
+    env.avm()
+        .args(["config", "set", "max_download_rate", "fast"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown unit \"fast\""));
+    env.avm()
+        .args(["config", "set", "mirror.github", "https://example.com"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown config key `mirror`"));
+    assert_eq!(std::fs::read_to_string(&env.config_path).unwrap(), config);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let editor = env.config_path.with_file_name("editor.sh");
+        std::fs::write(
+            &editor,
+            "#!/bin/sh\necho 'github_token = \"from-editor\"' >> \"$1\"\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&editor, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let other_config = env.config_path.with_file_name("other.toml");
+        env.avm()
+            .args(["config", "edit", "--config"])
+            .arg(&other_config)
+            .env_remove("VISUAL")
+            .env("EDITOR", &editor)
+            .assert()
+            .success();
+        assert_eq!(
+            std::fs::read_to_string(&other_config).unwrap(),
+            "github_token = \"from-editor\"\n"
+        );
+    }
+}
+
+#[test]
+fn get_vers_goes_through_mirror() {
+    let env = Env::new();
+    env.avm()
+        .args(["get-vers", "node", "-p", "x64-linux"])
+        .assert()
+        .success()
+        .stdout("28.1.0 [LTS]\n29.3.0\n");
+    assert_eq!(env.server.requests(), vec!["index.json"]);
+
+    env.avm()
+        .args(["get-vers", "node", "-p", "x64-linux", "--lts-only"])
+        .assert()
+        .success()
+        .stdout("28.1.0 [LTS]\n");
+}
+
+#[test]
+fn get_vers_accepts_version_specs() {
+    let env = Env::new();
+    for (spec, stdout) in [
+        ("28.x", "28.1.0 [LTS]\n"),
+        ("lts", "28.1.0 [LTS]\n"),
+        (">=28.5 <30", "29.3.0\n"),
+        ("<=28", "28.1.0 [LTS]\n"),
+        ("latest", "28.1.0 [LTS]\n29.3.0\n"),
+    ] {
+        env.avm()
+            .args(["get-vers", "node", "-p", "x64-linux", "--spec", spec])
+            .assert()
+            .success()
+            .stdout(stdout);
+    }
+    env.avm()
+        .args(["get-vers", "node", "--spec", ">=28 <"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn changelog_prints_release_notes_url() {
+    let env = Env::new();
+    env.avm()
+        .args(["changelog", "node", "-p", "x64-linux"])
+        .assert()
+        .success()
+        .stdout(
+            "https://github.com/nodejs/node/blob/main/doc/changelogs/CHANGELOG_V29.md#29.3.0\n",
+        );
+    env.avm()
+        .args(["changelog", "node", "28", "-p", "x64-linux"])
+        .assert()
+        .success()
+        .stdout(
+            "https://github.com/nodejs/node/blob/main/doc/changelogs/CHANGELOG_V28.md#28.1.0\n",
+        );
+    env.avm()
+        .args(["changelog", "node", "30", "-p", "x64-linux"])
+        .assert()
+        .failure();
+    env.avm()
+        .args(["changelog", "node", "28", "-s", "29"])
+        .assert()
+        .failure();
+}
+
+#[cfg(unix)]
+#[test]
+fn flags_versions_past_end_of_life() {
+    let env = Env::new();
+    let server = FixtureServer::start(HashMap::from([(
+        "nodejs.json".to_owned(),
+        br#"[
+            {"cycle": "29", "eol": "2099-06-01", "lts": false},
+            {"cycle": "28", "eol": "2020-04-30", "lts": "2019-10-21"}
+        ]"#
+        .to_vec(),
+    )]));
+    env.append_config(&format!(
+        "\n[[mirrors]]\nfrom = \"https://endoflife.date/api/\"\nto = {:?}\n",
+        server.base_url()
+    ));
+
+    env.avm().arg("doctor").assert().success();
+    assert!(server.requests().is_empty());
+
+    env.avm()
+        .args(["get-vers", "node", "-p", "x64-linux", "--eol"])
+        .assert()
+        .success()
+        .stdout("28.1.0 [LTS] [EOL]\n29.3.0\n");
+    env.avm()
+        .args(["get-vers", "node", "-p", "x64-linux", "--eol", "--table"])
+        .assert()
+        .success()
+        .stdout("VERSION  LTS  EOL\n28.1.0   yes  yes\n29.3.0   -    -\n");
+
+    env.avm()
+        .args([
+            "install",
+            "node",
+            "-p",
+            "x64-linux",
+            "-x",
+            "28",
+            "--default",
+        ])
+        .assert()
+        .success();
+    env.avm()
+        .args(["install", "node", "-p", "x64-linux", "-x", "29"])
+        .assert()
+        .success();
+    env.avm()
+        .args(["list", "node", "--eol"])
+        .assert()
+        .success()
+        .stdout("default -> x64-linux_28.1.0\nx64-linux_28.1.0 [EOL]\nx64-linux_29.3.0\n");
+    env.avm()
+        .arg("doctor")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(
+            "The default node tag, 28.1.0, is in the 28 release cycle, which is past its end of life since 2020-04-30",
+        ));
+}
+
+#[test]
+fn doctor_reports_unreachable_endpoints_and_dangling_aliases() {
+    let env = Env::new();
+    let server = FixtureServer::start(HashMap::new());
+    env.append_config(&format!(
+        "\n[[mirrors]]\nfrom = \"https://endoflife.date/api/\"\nto = {:?}\n",
+        server.base_url()
+    ));
+
+    env.avm()
+        .args(["install", "node", "-p", "x64-linux", "-v", "29.3.0"])
+        .assert()
+        .success();
+    env.avm()
+        .args(["alias", "node", "x64-linux_29.3.0", "current"])
+        .assert()
+        .success();
+    env.avm()
+        .args(["remove", "node", "x64-linux_29.3.0", "--allow-dangling"])
+        .assert()
+        .success();
+
+    env.avm()
+        .arg("doctor")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains(format!(
+            "The node endpoint {}nodejs.json responded with 404",
+            server.base_url()
+        )))
+        .stderr(predicate::str::contains("index.json").not())
+        .stderr(predicate::str::contains(
+            "The node alias `current` points to the removed tag `x64-linux_29.3.0`",
+        ));
+}
+
+#[test]
+fn install_latest_lts() {
+    let env = Env::new();
+    env.avm()
+        .args(["install", "node", "-p", "x64-linux", "--latest", "--lts"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("28.1.0 (LTS)"));
+    assert_exists(
+        &env.node_dir()
+            .join("x64-linux_28.1.0")
+            .join("bin")
+            .join("node"),
+    );
+    assert!(!env.node_dir().join("x64-linux_29.3.0").exists());
+
+    env.avm()
+        .args(["install", "node", "--latest", "-v", "28.1.0"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn get_vers_limits_sorts_and_tabulates() {
+    let env = Env::new();
+    let server = FixtureServer::start(HashMap::from([(
+        "index.json".to_owned(),
+        br#"[
+            {"version": "v29.3.0", "lts": false, "files": ["linux-x64"]},
+            {"version": "v29.2.1", "lts": false, "files": ["linux-x64"]},
+            {"version": "v28.1.0", "lts": "Fixture", "files": ["linux-x64"]},
+            {"version": "v28.0.0", "lts": false, "files": ["linux-x64"]},
+            {"version": "v9.11.2", "lts": "Carbon", "files": ["linux-x64"]}
+        ]"#
+        .to_vec(),
+    )]));
+    std::fs::write(
+        &env.config_path,
+        format!(
+            "data_path = {:?}\n\n[[mirrors]]\nfrom = \"https://nodejs.org/dist/\"\nto = {:?}\n",
+            env.data_path,
+            server.base_url()
+        ),
+    )
+    .unwrap();
+    let get_vers = |args: &[&str]| {
+        let mut cmd = env.avm();
+        cmd.args(["get-vers", "node", "-p", "x64-linux"]).args(args);
+        cmd
+    };
+
+    get_vers(&["--limit", "2"])
+        .assert()
+        .success()
+        .stdout("29.2.1\n29.3.0\n");
+    get_vers(&["--reverse", "--limit", "3"])
+        .assert()
+        .success()
+        .stdout("29.3.0\n29.2.1\n28.1.0 [LTS]\n");
+    get_vers(&["--majors-only"])
+        .assert()
+        .success()
+        .stdout("9.11.2 [LTS]\n28.1.0 [LTS]\n29.3.0\n");
+    get_vers(&["--majors-only", "--reverse", "--table"])
+        .assert()
+        .success()
+        .stdout("VERSION  LTS\n29.3.0   -\n28.1.0   yes\n9.11.2   yes\n");
+    get_vers(&["--limit", "0"]).assert().success().stdout("");
+}
+
+#[test]
+fn search_finds_tools_and_releases() {
+    let env = Env::new();
+    env.avm()
+        .args(["search", "BUILD TOOL"])
+        .assert()
+        .success()
+        .stdout("- gradle: Gradle build tool\n- maven: Apache Maven build tool\n");
+    assert!(env.server.requests().is_empty());
+
+    env.avm()
+        .args(["search", "javascript", "28"])
+        .assert()
+        .success()
+        .stdout("- node: Node.js JavaScript runtime (newest 28 release is 28.1.0 [LTS])\n");
+    env.avm()
+        .args(["search", "javascript", "30"])
+        .assert()
+        .success()
+        .stdout("- node: Node.js JavaScript runtime (no release matching 30)\n");
+
+    env.avm()
+        .args(["search", "cobol"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("No tool matches \"cobol\""));
+}
+
+#[test]
+fn tool_defaults_from_config() {
+    let env = Env::new();
+    env.append_config(
+        "\n[tools.node]\nplatform = \"x64-linux\"\nlts_only = true\n\n[tools.liberica]\nflavor = \"jdk_lite\"\n",
+    );
+
+    env.avm()
+        .args(["get-vers", "node"])
+        .assert()
+        .success()
+        .stdout("28.1.0 [LTS]\n");
+    // An exact version is not filtered out as a non-LTS release.
+    env.avm()
+        .args(["get-vers", "node", "-v", "29.3.0"])
+        .assert()
+        .success()
+        .stdout("29.3.0\n");
+    env.avm()
+        .args(["tool", "node"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Default platform: x64-linux\n"));
+    env.avm()
+        .args(["tool", "liberica"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Default flavor: jdk_lite\n"));
+
+    env.append_config("\n[tools.no-such-tool]\nlts_only = true\n");
+    env.avm()
+        .args(["get-vers", "node"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Unknown tool \"no-such-tool\" in `tools`",
+        ));
+}
+
+#[test]
+fn logs_as_json_to_file() {
+    let env = Env::new();
+    let log_file = env.data_path.join("logs").join("avm.log");
+    let output = env
+        .avm()
+        .args(["install", "node", "-p", "x64-linux", "-x", "28"])
+        .args(["--log-format", "json", "--log-file"])
+        .arg(&log_file)
+        .assert()
+        .success()
+        .get_output()
+        .stderr
+        .clone();
+    let records = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .collect::<Vec<_>>();
+    assert!(records.iter().any(|record| record["level"] == "INFO"
+        && record["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("Will download from ")));
+    assert!(records
+        .iter()
+        .all(|record| record["time"].as_str().unwrap().ends_with('Z')));
+    let logged = std::fs::read_to_string(&log_file).unwrap();
+    assert_eq!(logged.lines().count(), records.len());
+
+    // Text records in the file have their time.
+    env.avm()
+        .args([
+            "install",
+            "node",
+            "-p",
+            "x64-linux",
+            "-x",
+            "29",
+            "--log-file",
+        ])
+        .arg(&log_file)
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("INFO - Will download from "));
+    let logged = std::fs::read_to_string(&log_file).unwrap();
+    assert!(predicate::str::is_match(
+        r"\n\d{4}-\d\d-\d\dT\d\d:\d\d:\d\d\.\d{3}Z INFO - Will download from "
+    )
+    .unwrap()
+    .eval(&logged));
+
+    env.avm()
+        .args(["remove", "node", "x64-linux_29.3.0"])
+        .args(["--log-level", "warn,any_version_manager=error"])
+        .assert()
+        .success()
+        .stderr("");
+    env.avm()
+        .args(["list", "node", "--log-level", "loud"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid log level \"loud\""));
+}
+
+#[test]
+fn failures_have_distinct_exit_codes() {
+    let env = Env::new();
+    let install = ["install", "node", "-p", "x64-linux", "-x", "28"];
+    env.avm().args(install).assert().success();
+    env.avm().args(install).assert().code(4);
+    env.avm().args(["path", "node", "missing"]).assert().code(5);
+    env.avm()
+        .args(["get-vers", "node", "-p", "foo-bar"])
+        .assert()
+        .code(7);
+    env.avm()
+        .args(["install", "node", "-p", "x64-linux", "-x", "30"])
+        .assert()
+        .code(7);
+    env.avm()
+        .args(["install-url", "node", "http://127.0.0.1:1/node.tar.xz"])
+        .args(["--tag", "custom"])
+        .assert()
+        .code(3);
+    env.avm()
+        .args(["install-url", "node"])
+        .arg(format!(
+            "{}v28.1.0/node-v28.1.0-linux-x64.tar.xz",
+            env.server.base_url()
+        ))
+        .args(["--tag", "custom", "--sha256", &"0".repeat(64)])
+        .assert()
+        .code(6);
+
+    env.avm()
+        .args(["prune", "node", "-x", "28", "--non-interactive"])
+        .assert()
+        .code(8)
+        .stderr(predicate::str::contains("non-interactive mode"));
+    env.avm()
+        .args(["prune", "node", "-x", "28"])
+        .env("AVM_NONINTERACTIVE", "1")
+        .assert()
+        .code(8);
+    env.avm()
+        .args(["prune", "node", "-x", "28", "--yes", "--non-interactive"])
+        .assert()
+        .success();
+}
+
+/// Signs `content` with a key generated under `dir`, returning the detached signature, the
+/// path of the armored public key and its fingerprint, or `None` if `gpg` is not installed.
+fn sign_with_new_key(dir: &Path, content: &[u8]) -> Option<(Vec<u8>, PathBuf, String)> {
+    std::process::Command::new("gpg")
+        .arg("--version")
+        .output()
+        .ok()?;
+    let home = dir.join("signer");
+    std::fs::create_dir_all(&home).unwrap();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&home, std::fs::Permissions::from_mode(0o700)).unwrap();
+    }
+    let gpg = |args: &[&str]| {
+        let output = std::process::Command::new("gpg")
+            .arg("--homedir")
+            .arg(&home)
+            .args([
+                "--batch",
+                "--no-tty",
+                "--quiet",
+                "--pinentry-mode",
+                "loopback",
+            ])
+            .args(["--passphrase", ""])
+            .args(args)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{output:?}");
+        output.stdout
+    };
+    gpg(&[
+        "--quick-generate-key",
+        "pins@example.com",
+        "ed25519",
+        "sign",
+        "never",
+    ]);
+    let file = dir.join("signed");
+    std::fs::write(&file, content).unwrap();
+    let signature = gpg(&["--detach-sign", "--output", "-", file.to_str().unwrap()]);
+    let keys = dir.join("keys.asc");
+    std::fs::write(&keys, gpg(&["--armor", "--export"])).unwrap();
+    let fingerprint = String::from_utf8(gpg(&["--with-colons", "--fingerprint"]))
+        .unwrap()
+        .lines()
+        .find_map(|line| Some(line.strip_prefix("fpr:")?.trim_matches(':').to_owned()))
+        .unwrap();
+    Some((signature, keys, fingerprint))
+}
+
+#[test]
+fn pinned_hashes_are_cross_checked() {
+    let env = Env::new();
+    let pinned = |platform: &str| {
+        format!(
+            "[[hashes]]\ntool = \"node\"\nversion = \"28.1.0\"\nplatform = \"{platform}\"\nsha256 = \"{}\"\n",
+            "0".repeat(64)
+        )
+    };
+    let keys_dir = tempfile::tempdir().unwrap();
+    let Some((signature, keys, fingerprint)) =
+        sign_with_new_key(keys_dir.path(), pinned("arm64-mac").as_bytes())
+    else {
+        eprintln!("Skipped, `gpg` is not installed");
+        return;
+    };
+    let server = FixtureServer::start(HashMap::from([
+        (
+            "pinned-hashes.toml".to_owned(),
+            pinned("arm64-mac").into_bytes(),
+        ),
+        ("pinned-hashes.toml.sig".to_owned(), signature),
+        (
+            "unsigned.toml".to_owned(),
+            pinned("arm64-linux").into_bytes(),
+        ),
+    ]));
+    let security = |file: &str| {
+        format!(
+            "\n[security]\npin_hashes = true\npin_hashes_url = \"{}{file}\"\npin_hashes_keys = {keys:?}\npin_hashes_fingerprints = [\"{fingerprint}\"]\n",
+            server.base_url()
+        )
+    };
+    let config = std::fs::read_to_string(&env.config_path).unwrap();
+    std::fs::write(
+        &env.config_path,
+        format!("{config}{}", security("unsigned.toml")),
+    )
+    .unwrap();
+    std::fs::create_dir_all(&env.data_path).unwrap();
+    std::fs::write(
+        env.data_path.join("pinned-hashes.toml"),
+        pinned("x64-linux"),
+    )
+    .unwrap();
+
+    let install = ["install", "node", "-p", "x64-linux", "-x", "28"];
+    env.avm()
+        .args(install)
+        .assert()
+        .code(6)
+        .stderr(predicate::str::contains(
+            "The vendor Sha256 of node 28.1.0 is ",
+        ));
+
+    // A database without a signature by the pinned key is not saved.
+    env.avm()
+        .arg("update-pinned-hashes")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unsigned.toml.sig"));
+    env.avm().args(install).assert().code(6);
+
+    std::fs::write(
+        &env.config_path,
+        format!("{config}{}", security("pinned-hashes.toml")),
+    )
+    .unwrap();
+    env.avm()
+        .arg("update-pinned-hashes")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Downloaded 1 pinned hashes"));
+    env.avm().args(install).assert().success();
+}
+
+#[test]
+fn redirect_targets_go_through_mirror() {
+    let env = Env::new();
+    let mut redirects = HashMap::new();
+    redirects.insert(
+        "index.json".to_owned(),
+        "https://cdn.invalid/node/index.json".to_owned(),
+    );
+    let origin = FixtureServer::start_with_redirects(HashMap::new(), redirects);
+    let config = |follow_redirects: bool| {
+        format!(
+            "data_path = {:?}\n\n[[mirrors]]\nfrom = \"https://nodejs.org/dist/\"\nto = {:?}\n\n[[mirrors]]\nfrom = \"https://cdn.invalid/node/\"\nto = {:?}\nfollow_redirects = {follow_redirects}\n",
+            env.data_path,
+            origin.base_url(),
+            env.server.base_url(),
+        )
+    };
+
+    std::fs::write(&env.config_path, config(false)).unwrap();
+    env.avm().args(["get-vers", "node"]).assert().failure();
+
+    std::fs::write(&env.config_path, config(true)).unwrap();
+    env.avm()
+        .args(["get-vers", "node"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("29.3.0"));
+    assert!(env.server.requests().contains(&"index.json".to_owned()));
+}
+
+#[test]
+fn auth_is_sent_to_its_host_only() {
+    let env = Env::new();
+    let host = |server: &FixtureServer| {
+        server
+            .base_url()
+            .trim_start_matches("http://")
+            .trim_end_matches('/')
+            .to_owned()
+    };
+    // Redirected to the fixture server by reqwest, and through a mirror by avm with
+    // `follow_redirects`.
+    let redirects = HashMap::from([
+        (
+            "index.json".to_owned(),
+            format!("{}index.json", env.server.base_url()),
+        ),
+        (
+            "v28.1.0/SHASUMS256.txt".to_owned(),
+            "https://cdn.invalid/node/v28.1.0/SHASUMS256.txt".to_owned(),
+        ),
+    ]);
+    let origin = FixtureServer::start_with_redirects(HashMap::new(), redirects);
+    let config = |follow_redirects: bool| {
+        format!(
+            "data_path = {:?}\n\n[[mirrors]]\nfrom = \"https://nodejs.org/dist/\"\nto = {:?}\n\n[[mirrors]]\nfrom = \"https://cdn.invalid/node/\"\nto = {:?}\nfollow_redirects = {follow_redirects}\n\n[[auth]]\nhost = {:?}\ntoken_env = \"AVM_TEST_TOKEN\"\nallow_http = true\n",
+            env.data_path,
+            origin.base_url(),
+            env.server.base_url(),
+            host(&origin),
+        )
+    };
+    // The fixture servers are plain HTTP, where credentials are only sent when allowed.
+    std::fs::write(
+        &env.config_path,
+        config(false).replace("allow_http = true\n", ""),
+    )
+    .unwrap();
+    env.avm()
+        .args(["get-vers", "node"])
+        .env("AVM_TEST_TOKEN", "secret")
+        .assert()
+        .success();
+    assert_eq!(origin.authorizations(), vec![None]);
+
+    std::fs::write(&env.config_path, config(false)).unwrap();
+    env.avm()
+        .args(["get-vers", "node"])
+        .env("AVM_TEST_TOKEN", "secret")
+        .assert()
+        .success();
+    std::fs::write(&env.config_path, config(true)).unwrap();
+    env.avm()
+        .args(["get-downinfo", "node", "-p", "x64-linux", "-x", "28"])
+        .env("AVM_TEST_TOKEN", "secret")
+        .assert()
+        .success();
+    assert_eq!(origin.requests().len(), 4);
+    assert!(origin.authorizations()[1..]
+        .iter()
+        .all(|auth| auth.as_deref() == Some("Bearer secret")));
+    assert!(env.server.authorizations().iter().all(Option::is_none));
+
+    env.avm()
+        .args(["get-vers", "node"])
+        .env_remove("AVM_TEST_TOKEN")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("`AVM_TEST_TOKEN`"));
+
+    std::fs::write(
+        &env.config_path,
+        format!(
+            "data_path = {:?}\n\n[[mirrors]]\nfrom = \"https://nodejs.org/dist/\"\nto = {:?}\n\n[[auth]]\nhost = {:?}\nusername = \"ci\"\npassword_env = \"AVM_TEST_PASSWORD\"\nallow_http = true\n",
+            env.data_path,
+            env.server.base_url(),
+            host(&env.server),
+        ),
+    )
+    .unwrap();
+    env.avm()
+        .args(["get-vers", "node"])
+        .env("AVM_TEST_PASSWORD", "hunter2")
+        .assert()
+        .success();
+    // `ci:hunter2`
+    assert_eq!(
+        env.server.authorizations().last().unwrap().as_deref(),
+        Some("Basic Y2k6aHVudGVyMg==")
+    );
+
+    // After a redirect to another host, the credentials of that host are sent instead.
+    let sent = env.server.authorizations().len();
+    std::fs::write(
+        &env.config_path,
+        format!(
+            "{}\n[[auth]]\nhost = {:?}\nusername = \"ci\"\npassword_env = \"AVM_TEST_PASSWORD\"\nallow_http = true\n",
+            config(false),
+            host(&env.server),
+        ),
+    )
+    .unwrap();
+    env.avm()
+        .args(["get-vers", "node"])
+        .env("AVM_TEST_TOKEN", "secret")
+        .env("AVM_TEST_PASSWORD", "hunter2")
+        .assert()
+        .success();
+    assert_eq!(
+        origin.authorizations().last().unwrap().as_deref(),
+        Some("Bearer secret")
+    );
+    assert_eq!(
+        env.server.authorizations()[sent..],
+        [Some("Basic Y2k6aHVudGVyMg==".to_owned())]
+    );
+}
+
+#[test]
+fn get_downinfo_prints_toml() {
+    let env = Env::new();
+    env.avm()
+        .args(["get-downinfo", "node", "-p", "x64-linux", "-x", "28"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("tag = \"x64-linux_28.1.0\""))
+        .stdout(predicate::str::contains("lts = true"))
+        .stdout(predicate::str::contains("sha256 = "));
+}
+
+#[test]
+fn invalid_platform_fails() {
+    let env = Env::new();
+    env.avm()
+        .args(["install", "node", "-p", "foo-bar"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unsupported platform \"foo-bar\""));
+    env.avm()
+        .args(["install", "node", "--fx"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("only supported by liberica"));
+    env.avm()
+        .args(["get-vers", "liberica", "-f", "nik_core", "--fx"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("don't take the `fx` option"));
+    assert!(env.server.requests().is_empty());
+}
+
+#[test]
+fn install_list_path_remove() {
+    let env = Env::new();
+    env.avm()
+        .args(["install", "node", "-p", "x64-linux", "--default"])
+        .assert()
+        .success();
+    env.avm()
+        .args(["install", "node", "-p", "x64-linux", "-x", "28"])
+        .assert()
+        .success();
+    assert_exists(
+        &env.node_dir()
+            .join("x64-linux_29.3.0")
+            .join("bin")
+            .join("node"),
+    );
+
+    env.avm()
+        .args(["list", "node"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("default -> x64-linux_29.3.0\n"))
+        .stdout(predicate::str::contains("x64-linux_28.1.0\n"));
+
+    env.avm()
+        .args(["path", "node", "x64-linux_28.1.0"])
+        .assert()
+        .success()
+        .stdout(format!(
+            "{}\n",
+            env.node_dir().join("x64-linux_28.1.0").display()
+        ));
+
+    // The target of an alias can only be removed together with the alias.
+    env.avm()
+        .args(["remove", "node", "x64-linux_29.3.0"])
+        .assert()
+        .failure();
+    env.avm()
+        .args(["remove", "node", "default", "x64-linux_29.3.0"])
+        .assert()
+        .success();
+    env.avm()
+        .args(["list", "node"])
+        .assert()
+        .success()
+        .stdout("x64-linux_28.1.0\n");
+
+    env.avm()
+        .args(["remove", "node", "no-such-tag"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn install_dry_run_prints_plan_without_downloading() {
+    let env = Env::new();
+    env.avm()
+        .args([
+            "install",
+            "node",
+            "-p",
+            "x64-linux",
+            "-x",
+            "28",
+            "--dry-run",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Tag: x64-linux_28.1.0\n"))
+        .stdout(predicate::str::contains("Version: 28.1.0"))
+        .stdout(predicate::str::contains("Platform: x64-linux\n"))
+        .stdout(predicate::str::contains("URL: http://"))
+        .stdout(predicate::str::contains("Overwrites: no\n"));
+    // Resolution reads the index and the checksums, but the archive is not downloaded.
+    assert_eq!(
+        env.server.requests(),
+        vec!["index.json", "v28.1.0/SHASUMS256.txt"]
+    );
+    assert!(!env.node_dir().join("x64-linux_28.1.0").exists());
+
+    env.avm()
+        .args(["install", "node", "-p", "x64-linux", "-x", "28"])
+        .assert()
+        .success();
+    env.avm()
+        .args([
+            "install",
+            "node",
+            "-p",
+            "x64-linux",
+            "-x",
+            "28",
+            "--dry-run",
+            "-u",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Overwrites: yes\n"));
+}
+
+#[test]
+fn install_from_bundle_without_network() {
+    let env = Env::new();
+    let bundle = env.data_path.with_file_name("node.tar");
+    env.avm()
+        .args(["bundle", "node", "-x", "29", "-p", "x64-linux", "-o"])
+        .arg(&bundle)
+        .assert()
+        .success()
+        .stdout("bundled: x64-linux_29.3.0\n");
+    assert!(env
+        .server
+        .requests()
+        .contains(&"v29.3.0/node-v29.3.0-linux-x64.tar.xz".to_owned()));
+    assert!(!env.node_dir().join("x64-linux_29.3.0").exists());
+
+    let requests = env.server.requests().len();
+    env.avm()
+        .args(["install", "node", "-p", "x64-windows", "--from-bundle"])
+        .arg(&bundle)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("x64-linux_29.3.0 (x64-linux)"));
+    env.avm()
+        .args(["install", "node", "-p", "x64-linux", "--from-bundle"])
+        .arg(&bundle)
+        .assert()
+        .success();
+    assert_exists(
+        &env.node_dir()
+            .join("x64-linux_29.3.0")
+            .join("bin")
+            .join("node"),
+    );
+    assert_eq!(env.server.requests().len(), requests);
+    env.avm()
+        .args(["info", "node", "x64-linux_29.3.0"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("node-v29.3.0-linux-x64.tar.xz"));
+
+    // The tags of a bundle are checked like the ones given to the other installs.
+    let manifest = "tool = \"node\"\n\n[[release]]\ntag = \"../../escaped\"\nversion = \"29.3.0\"\nurl = \"https://nodejs.org/dist/v29.3.0/node-v29.3.0-linux-x64.tar.xz\"\nhash = {}\nfile = \"archives/node.tar.xz\"\n";
+    let mut crafted = tar::Builder::new(Vec::new());
+    let mut header = tar::Header::new_gnu();
+    header.set_size(manifest.len() as u64);
+    header.set_mode(0o644);
+    crafted
+        .append_data(&mut header, "avm-bundle.toml", manifest.as_bytes())
+        .unwrap();
+    let crafted_bundle = env.data_path.with_file_name("crafted.tar");
+    std::fs::write(&crafted_bundle, crafted.into_inner().unwrap()).unwrap();
+    env.avm()
+        .args(["install", "node", "-p", "x64-linux", "--from-bundle"])
+        .arg(&crafted_bundle)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid tag \"../../escaped\""));
+    assert!(!env.node_dir().join("../../escaped").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn install_runs_post_install_hooks() {
+    let env = Env::new();
+    env.append_config(
+        "\n[tools.node]\npost_install = [\n    \"node --version > \\\"$AVM_TAG_DIR/hook-out\\\"\",\n    \"echo \\\"$AVM_TOOL $AVM_TAG\\\" >> \\\"$AVM_TAG_DIR/hook-out\\\"\",\n    \"echo hook output\",\n]\n",
+    );
+
+    env.avm()
+        .args(["install", "node", "-p", "x64-linux", "-x", "28"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("hook output").not())
+        .stderr(predicate::str::contains(
+            "Running the post-install hook of \"x64-linux_28.1.0\"",
+        ))
+        .stderr(predicate::str::contains("hook output"));
+    // The hooks find the installed `node` first on `PATH`.
+    assert_eq!(
+        std::fs::read_to_string(env.node_dir().join("x64-linux_28.1.0").join("hook-out")).unwrap(),
+        "fixture node --version\nnode x64-linux_28.1.0\n"
+    );
+
+    // Not only `avm install` runs them.
+    env.avm()
+        .args(["install-url", "node"])
+        .arg(format!(
+            "{}v28.1.0/node-v28.1.0-linux-x64.tar.xz",
+            env.server.base_url()
+        ))
+        .args(["--tag", "custom"])
+        .assert()
+        .success();
+    assert_eq!(
+        std::fs::read_to_string(env.node_dir().join("custom").join("hook-out")).unwrap(),
+        "fixture node --version\nnode custom\n"
+    );
+
+    let config = std::fs::read_to_string(&env.config_path)
+        .unwrap()
+        .replace("post_install = [", "post_install = [\n    \"exit 3\",");
+    std::fs::write(&env.config_path, config).unwrap();
+    env.avm()
+        .args(["install", "node", "-p", "x64-linux", "-x", "29"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "\"x64-linux_29.3.0\" is installed, but a post-install hook failed",
+        ))
+        .stderr(predicate::str::contains("The hook `exit 3` failed"));
+    assert_exists(&env.node_dir().join("x64-linux_29.3.0"));
+    assert!(!env
+        .node_dir()
+        .join("x64-linux_29.3.0")
+        .join("hook-out")
+        .exists());
+}
+
+#[test]
+fn install_prints_json_progress_events() {
+    let env = Env::new();
+    let parse_events = |output: &[u8]| {
+        String::from_utf8_lossy(output)
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<_>>()
+    };
+    let output = env
+        .avm()
+        .args([
+            "install",
+            "node",
+            "-p",
+            "x64-linux",
+            "-x",
+            "28",
+            "--progress",
+            "json",
+        ])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let events = parse_events(&output);
+    let names = events
+        .iter()
+        .map(|event| event["event"].as_str().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(names.first(), Some(&"download-started"));
+    assert_eq!(
+        names[names.len() - 3..],
+        ["verifying", "extracting", "done"]
+    );
+    assert!(events
+        .iter()
+        .all(|event| event["tag"] == "x64-linux_28.1.0"));
+    let progress = events
+        .iter()
+        .rfind(|event| event["event"] == "progress")
+        .unwrap();
+    assert_eq!(progress["bytes"], progress["total"]);
+
+    let server = FixtureServer::start(HashMap::from([
+        (
+            "index.json".to_owned(),
+            br#"[{"version": "v29.3.0", "lts": false, "files": ["linux-x64"]}]"#.to_vec(),
+        ),
+        (
+            "v29.3.0/SHASUMS256.txt".to_owned(),
+            format!("{}  node-v29.3.0-linux-x64.tar.xz\n", "0".repeat(64)).into_bytes(),
+        ),
+        (
+            "v29.3.0/node-v29.3.0-linux-x64.tar.xz".to_owned(),
+            fixture::tar_xz(
+                "node-v29.3.0-linux-x64",
+                &[("bin/node", NODE_SCRIPT, 0o755)],
+            ),
+        ),
+    ]));
+    std::fs::write(
+        &env.config_path,
+        format!(
+            "data_path = {:?}\n\n[[mirrors]]\nfrom = \"https://nodejs.org/dist/\"\nto = {:?}\n",
+            env.data_path,
+            server.base_url()
+        ),
+    )
+    .unwrap();
+    let output = env
+        .avm()
+        .args(["install", "node", "-p", "x64-linux", "--progress", "json"])
+        .assert()
+        .failure()
+        .get_output()
+        .stdout
+        .clone();
+    let last = parse_events(&output).pop().unwrap();
+    assert_eq!(last["event"], "error");
+    assert!(last["message"]
+        .as_str()
+        .unwrap()
+        .contains("verification failed"));
+}
+
+#[cfg(unix)]
+#[test]
+fn serve_installs_resolves_and_removes() {
+    use std::io::{BufRead, Read, Write};
+
+    struct Server(std::process::Child);
+    impl Drop for Server {
+        fn drop(&mut self) {
+            let _ = self.0.kill();
+            let _ = self.0.wait();
+        }
+    }
+
+    let env = Env::new();
+    let mut server = Server(
+        std::process::Command::new(env!("CARGO_BIN_EXE_avm"))
+            .env("CONFIG_PATH", &env.config_path)
+            .args(["serve", "--listen", "127.0.0.1:0"])
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::null())
+            .spawn()
+            .unwrap(),
+    );
+    let (mut url, mut token) = (String::new(), String::new());
+    let mut stdout = std::io::BufReader::new(server.0.stdout.take().unwrap());
+    stdout.read_line(&mut url).unwrap();
+    stdout.read_line(&mut token).unwrap();
+    let address = url.trim().strip_prefix("http://").unwrap().to_owned();
+    let send = |head: &str| {
+        let mut stream = std::net::TcpStream::connect(&address).unwrap();
+        write!(stream, "{head}\r\n\r\n").unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        (head.lines().next().unwrap().to_owned(), body.to_owned())
+    };
+    let request = |method: &str, target: &str| {
+        send(&format!(
+            "{method} {target} HTTP/1.1\r\nHost: localhost\r\nAuthorization: Bearer {}",
+            token.trim()
+        ))
+    };
+
+    // Requests without the token, of web pages, or to another host, such as after DNS
+    // rebinding, are rejected.
+    let tags = "GET /tools/node/tags HTTP/1.1\r\nHost: localhost";
+    assert_eq!(send(tags).0, "HTTP/1.1 401 Unauthorized");
+    assert_eq!(
+        send(&format!("{tags}\r\nAuthorization: Bearer wrong")).0,
+        "HTTP/1.1 401 Unauthorized"
+    );
+    let authorized = format!("Authorization: Bearer {}", token.trim());
+    assert_eq!(
+        send(&format!(
+            "POST /tools/node/install?spec=28 HTTP/1.1\r\nHost: localhost\r\nOrigin: https://example.com\r\n{authorized}"
+        ))
+        .0,
+        "HTTP/1.1 403 Forbidden"
+    );
+    assert_eq!(
+        send(&format!(
+            "GET /tools/node/tags HTTP/1.1\r\nHost: rebound.example.com\r\n{authorized}"
+        ))
+        .0,
+        "HTTP/1.1 403 Forbidden"
+    );
+    assert_eq!(
+        send(&format!("{tags}\r\nX-Long: {}", "a".repeat(10_000))).0,
+        "HTTP/1.1 400 Bad Request"
+    );
+    assert!(!env.node_dir().exists());
+
+    let (status, body) = request("POST", "/tools/node/install?spec=28&platform=x64-linux");
+    assert_eq!(status, "HTTP/1.1 200 OK");
+    let events = body
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(events.first().unwrap()["event"], "download-started");
+    assert_eq!(events.last().unwrap()["event"], "done");
+    assert!(events
+        .iter()
+        .all(|event| event["tag"] == "x64-linux_28.1.0"));
+    assert_exists(
+        &env.node_dir()
+            .join("x64-linux_28.1.0")
+            .join("bin")
+            .join("node"),
+    );
+
+    let (status, body) = request("GET", "/tools/node/tags");
+    assert_eq!(status, "HTTP/1.1 200 OK");
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(&body).unwrap(),
+        serde_json::json!([{ "tag": "x64-linux_28.1.0", "alias_of": null }])
+    );
+
+    let (status, body) = request("GET", "/tools/node/resolve?spec=28.x&platform=x64-linux");
+    assert_eq!(status, "HTTP/1.1 200 OK");
+    let resolved = serde_json::from_str::<serde_json::Value>(&body).unwrap();
+    assert_eq!(resolved["tag"], "x64-linux_28.1.0");
+    assert_eq!(
+        resolved["path"],
+        env.node_dir()
+            .join("x64-linux_28.1.0")
+            .display()
+            .to_string()
+    );
+    let (status, _) = request("GET", "/tools/node/resolve?spec=29&platform=x64-linux");
+    assert_eq!(status, "HTTP/1.1 404 Not Found");
+    let (status, _) = request("GET", "/tools/no-such-tool/tags");
+    assert_eq!(status, "HTTP/1.1 404 Not Found");
+    let (status, _) = request("GET", "/tools/node/resolve?lts_only=maybe");
+    assert_eq!(status, "HTTP/1.1 400 Bad Request");
+
+    let (status, _) = request("DELETE", "/tools/node/tags/x64-linux_28.1.0");
+    assert_eq!(status, "HTTP/1.1 200 OK");
+    let (_, body) = request("GET", "/tools/node/tags");
+    assert_eq!(body, "[]");
+}
+
+#[test]
+fn list_verbose_and_info_show_install_info() {
+    let env = Env::new();
+    env.avm()
+        .args([
+            "install",
+            "node",
+            "-p",
+            "x64-linux",
+            "-x",
+            "28",
+            "--default",
+        ])
+        .assert()
+        .success();
+
+    env.avm()
+        .args(["list", "node", "--verbose"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "default -> x64-linux_28.1.0\nx64-linux_28.1.0 (28.1.0 LTS, x64-linux, installed ",
+        ));
+    env.avm()
+        .args(["info", "node"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            "Tag: default\nAlias of: x64-linux_28.1.0\n",
+        ))
+        .stdout(predicate::str::contains(
+            "Version: 28.1.0 (LTS)\nPlatform: x64-linux\n",
+        ))
+        .stdout(predicate::str::contains("URL: https://nodejs.org/dist/"))
+        .stdout(predicate::str::contains(
+            "node-v28.1.0-linux-x64.tar.xz\nsha256: ",
+        ))
+        .stdout(predicate::str::contains("Installed: "));
+    env.avm()
+        .args(["info", "node", "missing"])
+        .assert()
+        .failure();
+}
+
+#[cfg(unix)]
+#[test]
+fn node_channels_select_rc_builds() {
+    let env = Env::new();
+    let mut files = HashMap::from([(
+        "index.json".to_owned(),
+        br#"[
+            {"version": "v30.0.0-rc.2", "lts": false, "files": ["linux-x64"]},
+            {"version": "v30.0.0-rc.1", "lts": false, "files": ["linux-x64"]}
+        ]"#
+        .to_vec(),
+    )]);
+    let name = "node-v30.0.0-rc.2-linux-x64.tar.xz";
+    let archive = fixture::tar_xz(
+        "node-v30.0.0-rc.2-linux-x64",
+        &[("bin/node", NODE_SCRIPT, 0o755)],
+    );
+    files.insert(
+        "v30.0.0-rc.2/SHASUMS256.txt".to_owned(),
+        format!("{}  {name}\n", fixture::sha256_hex(&archive)).into_bytes(),
+    );
+    files.insert(format!("v30.0.0-rc.2/{name}"), archive);
+    let rc_server = FixtureServer::start(files);
+    env.append_config(&format!(
+        "\n[[mirrors]]\nfrom = \"https://nodejs.org/download/rc/\"\nto = {:?}\n",
+        rc_server.base_url()
+    ));
+
+    env.avm()
+        .args(["get-vers", "node", "-p", "x64-linux", "--channel", "rc"])
+        .assert()
+        .success()
+        .stdout("30.0.0-rc.1\n30.0.0-rc.2\n");
+    // The release candidates are listed with the releases only if prereleases are included.
+    env.avm()
+        .args(["get-vers", "node", "-p", "x64-linux"])
+        .assert()
+        .success()
+        .stdout("28.1.0 [LTS]\n29.3.0\n");
+    for flag in ["--include-prerelease", "--allow-prere"] {
+        env.avm()
+            .args(["get-vers", "node", "-p", "x64-linux", flag])
+            .assert()
+            .success()
+            .stdout("28.1.0 [LTS]\n29.3.0\n30.0.0-rc.1\n30.0.0-rc.2\n");
+    }
+    env.avm()
+        .args(["install", "node", "-p", "x64-linux", "--channel", "rc"])
+        .assert()
+        .success();
+    env.avm()
+        .args(["install", "node", "-p", "x64-linux", "--channel", "release"])
+        .assert()
+        .success();
+    env.avm()
+        .args(["info", "node", "x64-linux_rc_30.0.0-rc.2"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Flavor: rc\n"))
+        .stdout(predicate::str::contains(
+            "URL: https://nodejs.org/download/rc/",
+        ));
+    assert_exists(&env.node_dir().join("x64-linux_29.3.0"));
+
+    env.avm()
+        .args(["get-vers", "node", "--channel", "beta"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Unknown Node channel `beta`"));
+    env.avm()
+        .args(["get-vers", "go", "--channel", "rc"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("only supported by node"));
+}
+
+#[cfg(unix)]
+#[test]
+fn node_install_enables_corepack_and_pins_npm() {
+    let env = Env::new();
+    // Like the real scripts, record their arguments where they are installed.
+    let corepack = b"#!/bin/sh\necho \"$@\" > \"$(dirname \"$0\")/../corepack-args\"\ntouch \"$3/yarn\" \"$3/pnpm\"\n";
+    let npm = b"#!/bin/sh\necho \"$@\" > \"$(dirname \"$0\")/../npm-args\"\n";
+    let name = "node-v29.3.0-linux-x64.tar.xz";
+    let archive = fixture::tar_xz(
+        "node-v29.3.0-linux-x64",
+        &[
+            ("bin/node", NODE_SCRIPT, 0o755),
+            ("bin/npm", npm, 0o755),
+            ("bin/corepack", corepack, 0o755),
+        ],
+    );
+    let server = FixtureServer::start(HashMap::from([
+        (
+            "index.json".to_owned(),
+            br#"[{"version": "v29.3.0", "lts": false, "files": ["linux-x64"]}]"#.to_vec(),
+        ),
+        (
+            "v29.3.0/SHASUMS256.txt".to_owned(),
+            format!("{}  {name}\n", fixture::sha256_hex(&archive)).into_bytes(),
+        ),
+        (format!("v29.3.0/{name}"), archive),
+    ]));
+    std::fs::write(
+        &env.config_path,
+        format!(
+            "data_path = {:?}\n\n[[mirrors]]\nfrom = \"https://nodejs.org/dist/\"\nto = {:?}\n",
+            env.data_path,
+            server.base_url()
+        ),
+    )
+    .unwrap();
+
+    env.avm()
+        .args([
+            "install",
+            "node",
+            "-p",
+            "x64-linux",
+            "--with-corepack",
+            "--npm",
+            "11.1.0",
+        ])
+        .assert()
+        .success();
+    let tag_dir = env.node_dir().join("x64-linux_29.3.0");
+    assert_eq!(
+        std::fs::read_to_string(tag_dir.join("npm-args")).unwrap(),
+        "install --global npm@11.1.0\n"
+    );
+    assert_eq!(
+        std::fs::read_to_string(tag_dir.join("corepack-args")).unwrap(),
+        format!(
+            "enable --install-directory {}\n",
+            tag_dir.join("bin").display()
+        )
+    );
+    env.avm()
+        .args(["entry-path", "node", "x64-linux_29.3.0", "--exe", "yarn"])
+        .assert()
+        .success()
+        .stdout(format!("{}\n", tag_dir.join("bin").join("yarn").display()));
+
+    env.avm()
+        .args(["install", "go", "--with-corepack"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("only supported by node"));
+}
+
+#[cfg(unix)]
+#[test]
+fn list_all_shows_every_tool() {
+    let env = Env::new();
+    env.avm()
+        .args(["list", "--all"])
+        .assert()
+        .success()
+        .stdout("TOOL  TAG  VERSION  LTS  SIZE\nTotal: 0 B\n");
+    env.avm()
+        .args([
+            "install",
+            "node",
+            "-p",
+            "x64-linux",
+            "-x",
+            "28",
+            "--default",
+        ])
+        .assert()
+        .success();
+
+    env.avm()
+        .args(["list", "--all"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("TOOL  TAG"))
+        .stdout(
+            predicate::str::is_match(r"\nnode  default -> x64-linux_28\.1\.0  -  +-  +-\n")
+                .unwrap(),
+        )
+        .stdout(
+            predicate::str::is_match(r"\nnode  x64-linux_28\.1\.0 +28\.1\.0  +yes  +\d").unwrap(),
+        );
+    let output = env
+        .avm()
+        .args(["list", "--all", "--json"])
+        .assert()
+        .success()
+        .get_output()
+        .stdout
+        .clone();
+    let rows: serde_json::Value = serde_json::from_slice(&output).unwrap();
+    assert_eq!(rows.as_array().unwrap().len(), 2);
+    assert_eq!(rows[0]["tool"], "node");
+    assert_eq!(rows[0]["tag"], "default");
+    assert_eq!(rows[0]["alias_of"], "x64-linux_28.1.0");
+    assert!(rows[0]["size"].is_null());
+    assert_eq!(rows[0]["lts"], false);
+    assert_eq!(rows[1]["version"], "28.1.0");
+    assert_eq!(rows[1]["lts"], true);
+    assert!(rows[1]["size"].as_u64().unwrap() > 0);
+
+    env.avm().args(["list"]).assert().failure();
+    env.avm().args(["list", "node", "--all"]).assert().failure();
+}
+
+#[test]
+fn repair_alias_after_moving_data_dir() {
+    let env = Env::new();
+    env.avm()
+        .args(["install", "node", "-p", "x64-linux", "--default"])
+        .assert()
+        .success();
+
+    let moved = env.config_path.parent().unwrap().join("moved");
+    std::fs::rename(&env.data_path, &moved).unwrap();
+    let config = std::fs::read_to_string(&env.config_path)
+        .unwrap()
+        .replace(&format!("{:?}", env.data_path), &format!("{:?}", moved));
+    std::fs::write(&env.config_path, config).unwrap();
+    env.avm()
+        .args(["run", "node", "--", "-v"])
+        .assert()
+        .failure();
+
+    env.avm()
+        .args(["repair-alias", "node"])
+        .assert()
+        .success()
+        .stdout("relinked: default -> x64-linux_29.3.0\n");
+    env.avm()
+        .args(["repair-alias", "node", "default"])
+        .assert()
+        .success()
+        .stdout("unchanged: default -> x64-linux_29.3.0\n");
+    env.avm()
+        .args(["run", "node", "--", "-v"])
+        .assert()
+        .success()
+        .stdout("fixture node -v\n");
+    env.avm()
+        .args(["repair-alias", "node", "x64-linux_29.3.0"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is not an alias"));
+}
+
+#[test]
+fn migrate_data_moves_tools_and_updates_config() {
+    let env = Env::new();
+    env.avm()
+        .args(["install", "node", "-p", "x64-linux", "--default"])
+        .assert()
+        .success();
+
+    let moved = env.config_path.parent().unwrap().join("moved");
+    env.avm()
+        .args(["migrate-data"])
+        .arg(env.data_path.join("inside"))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is inside the data directory"));
+    env.avm()
+        .arg("migrate-data")
+        .arg(&moved)
+        .assert()
+        .success()
+        .stdout("relinked: node default -> x64-linux_29.3.0\n");
+    assert!(!env.data_path.exists());
+    env.avm()
+        .args(["config", "get", "data_path"])
+        .assert()
+        .success()
+        .stdout(format!("{}\n", moved.display()));
+    env.avm()
+        .args(["run", "node", "--", "-v"])
+        .assert()
+        .success()
+        .stdout("fixture node -v\n");
+}
+
+#[test]
+fn rename_retargets_aliases() {
+    let env = Env::new();
+    env.avm()
+        .args(["install", "node", "-p", "x64-linux", "--default"])
+        .assert()
+        .success();
+    env.avm()
+        .args(["alias", "node", "x64-linux_29.3.0", "lts"])
+        .assert()
+        .success();
+
+    env.avm()
+        .args(["rename", "node", "x64-linux_29.3.0", "29"])
+        .assert()
+        .success()
+        .stdout("default -> 29\nlts -> 29\n");
+    env.avm()
+        .args(["list", "node"])
+        .assert()
+        .success()
+        .stdout("29\ndefault -> 29\nlts -> 29\n");
+    env.avm()
+        .args(["run", "node", "--", "-v"])
+        .assert()
+        .success()
+        .stdout("fixture node -v\n");
+    env.avm()
+        .args(["rename", "node", "default", "current"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is an alias"));
+
+    // The store keeps an alias whose link was lost, so it can be repaired.
+    std::fs::remove_file(env.node_dir().join("lts")).unwrap();
+    env.avm()
+        .args(["repair-alias", "node", "lts"])
+        .assert()
+        .success()
+        .stdout("relinked: lts -> 29\n");
+}
+
+#[test]
+fn alias_list_move_and_delete() {
+    let env = Env::new();
+    for version in ["29.3.0", "28.1.0"] {
+        env.avm()
+            .args(["install", "node", "-p", "x64-linux", "-v", version])
+            .assert()
+            .success();
+    }
+    env.avm()
+        .args(["alias", "node", "x64-linux_29.3.0", "lts"])
+        .assert()
+        .success();
+    env.avm()
+        .args(["alias", "node", "x64-linux_29.3.0", "current"])
+        .assert()
+        .success();
+
+    env.avm()
+        .args(["alias", "move", "node", "lts", "x64-linux_28.1.0"])
+        .assert()
+        .success()
+        .stdout(
+            "lts: x64-linux_29.3.0 -> x64-linux_28.1.0
+",
+        );
+    env.avm()
+        .args(["run", "node", "-t", "lts", "--", "-v"])
+        .assert()
+        .success();
+    // Only existing aliases are moved or deleted.
+    env.avm()
+        .args(["alias", "move", "node", "stable", "x64-linux_28.1.0"])
+        .assert()
+        .failure();
+    env.avm()
+        .args(["alias", "delete", "node", "x64-linux_28.1.0"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("is not an alias"));
+
+    env.avm()
+        .args(["remove", "node", "x64-linux_29.3.0", "--allow-dangling"])
+        .assert()
+        .success();
+    env.avm()
+        .args(["alias", "list", "node"])
+        .assert()
+        .success()
+        .stdout(
+            "current -> x64-linux_29.3.0 (missing)
+lts -> x64-linux_28.1.0
+",
+        );
+    env.avm()
+        .args(["alias", "delete", "node", "current"])
+        .assert()
+        .success();
+    env.avm().args(["list", "node"]).assert().success().stdout(
+        "lts -> x64-linux_28.1.0
+x64-linux_28.1.0
+",
+    );
+}
+
+#[test]
+fn copy_from_another_data_dir_keeps_install_info() {
+    let backup = Env::new();
+    backup
+        .avm()
+        .args(["install", "node", "-p", "x64-linux", "--default"])
+        .assert()
+        .success();
+    let env = Env::new();
+
+    env.avm()
+        .args(["copy", "node", "x64-linux_29.3.0", "--from-data-dir"])
+        .arg(&backup.data_path)
+        .assert()
+        .success();
+    // An alias is copied as a regular tag.
+    env.avm()
+        .args(["copy", "node", "default", "--to-tag", "backup"])
+        .args(["--from-data-dir"])
+        .arg(&backup.data_path)
+        .assert()
+        .success();
+    env.avm()
+        .args(["list", "node"])
+        .assert()
+        .success()
+        .stdout("backup\nx64-linux_29.3.0\n");
+    env.avm()
+        .args(["info", "node", "backup"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Version: 29.3.0"));
+    env.avm()
+        .args(["copy", "node", "x64-linux_29.3.0"])
+        .assert()
+        .failure();
+}
+
+#[test]
+fn tags_are_validated_and_normalized() {
+    let env = Env::new();
+    env.avm()
+        .args(["install", "node", "-p", "x64-linux"])
+        .assert()
+        .success();
+    for tag in ["../escaped", "CON", ".aliases.json", "x."] {
+        env.avm()
+            .args(["alias", "node", "x64-linux_29.3.0", tag])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("Invalid tag"));
+    }
+    env.avm()
+        .args(["copy", "node", "x64-linux_29.3.0", ".tmp.copy"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("reserved"));
+    assert!(!env.data_path.join("tools").join("escaped").exists());
+
+    // Decomposed and composed forms name the same tag.
+    env.avm()
+        .args(["copy", "node", "x64-linux_29.3.0", "caf\u{65}\u{301}"])
+        .assert()
+        .success();
+    env.avm()
+        .args(["list", "node"])
+        .assert()
+        .success()
+        .stdout("caf\u{e9}\nx64-linux_29.3.0\n");
+    env.avm()
+        .args(["remove", "node", "caf\u{e9}"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn tool_dir_lock_waits_or_fails() {
+    let env = Env::new();
+    env.avm()
+        .args(["install", "node", "-p", "x64-linux"])
+        .assert()
+        .success();
+    // Stands for another avm process changing the tool directory.
+    let lock = std::fs::File::create(env.node_dir().join(".avm.lock")).unwrap();
+    lock.lock().unwrap();
+
+    env.avm()
+        .args(["--no-wait", "alias", "node", "x64-linux_29.3.0", "lts"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Another avm process is changing"));
+    env.avm()
+        .args(["remove", "node", "x64-linux_29.3.0", "--no-wait"])
+        .assert()
+        .failure();
+    // Reading doesn't take the lock.
+    env.avm()
+        .args(["list", "node"])
+        .assert()
+        .success()
+        .stdout("x64-linux_29.3.0\n");
+
+    let release = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(500));
+        drop(lock);
+    });
+    env.avm()
+        .args(["alias", "node", "x64-linux_29.3.0", "lts"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Waiting for another avm process"));
+    release.join().unwrap();
+    env.avm()
+        .args(["list", "node"])
+        .assert()
+        .success()
+        .stdout("lts -> x64-linux_29.3.0\nx64-linux_29.3.0\n");
+}
+
+#[test]
+fn hash_mismatch_blames_the_mirror() {
+    let env = Env::new();
+    let archive = fixture::tar_xz("custom", &[("bin/node", NODE_SCRIPT, 0o755)]);
+    let sha256 = fixture::sha256_hex(&archive);
+    let server = FixtureServer::start(HashMap::from([("custom.tar.xz".to_owned(), archive)]));
+    let mirror = FixtureServer::start(HashMap::from([(
+        "custom.tar.xz".to_owned(),
+        b"corrupt".to_vec(),
+    )]));
+    env.append_config(&format!(
+        "\n[[mirrors]]\nfrom = {:?}\nto = {:?}\n",
+        server.base_url(),
+        mirror.base_url()
+    ));
+
+    let url = format!("{}custom.tar.xz", server.base_url());
+    env.avm()
+        .args(["install-url", "node", &url, "--tag", "custom", "--retries", "1"])
+        .args(["--sha256", &sha256])
+        .assert()
+        .code(6)
+        .stderr(predicate::str::contains(format!(
+            "Sha256 verification failed for '{url}'\n  expected: {sha256}\n"
+        )))
+        .stderr(predicate::str::contains(format!(
+            "requested through a mirror as '{}custom.tar.xz'\n  downloaded 7 bytes, as announced by the server\n",
+            mirror.base_url()
+        )))
+        .stderr(predicate::str::contains(format!(
+            "the mirror of \"{}\" serves a different one",
+            server.base_url()
+        )))
+        .stderr(predicate::str::contains("Retrying the download (1/1)"));
+    assert_eq!(mirror.requests().len(), 2);
+    assert_eq!(server.requests().len(), 2);
+    assert!(!env.node_dir().join("custom").exists());
+}
+
+#[test]
+fn install_url_verifies_and_installs_archive() {
+    let env = Env::new();
+    let archive = fixture::tar_xz("custom", &[("bin/node", NODE_SCRIPT, 0o755)]);
+    let sha256 = fixture::sha256_hex(&archive);
+    let server = FixtureServer::start(HashMap::from([("custom.tar.xz".to_owned(), archive)]));
+    let url = format!("{}custom.tar.xz", server.base_url());
+
+    env.avm()
+        .args(["install-url", "node", &url, "--tag", "custom"])
+        .args(["--sha256", &"0".repeat(64)])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("verification failed"));
+    assert!(!env.node_dir().join("custom").exists());
+
+    env.avm()
+        .args(["install-url", "node", &url, "--tag", "custom", "--default"])
+        .args(["--sha256", &sha256])
+        .assert()
+        .success();
+    env.avm()
+        .args(["run", "node", "--", "-v"])
+        .assert()
+        .success()
+        .stdout("fixture node -v\n");
+    env.avm()
+        .args(["install-url", "node", &url, "--tag", "custom"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("already exists"));
+}
+
+#[test]
+fn object_store_urls_are_fetched_with_credentials() {
+    let env = Env::new();
+    let s3_archive = fixture::tar_xz("s3", &[("bin/node", NODE_SCRIPT, 0o755)]);
+    let gcs_archive = fixture::tar_xz("gcs", &[("bin/node", NODE_SCRIPT, 0o755)]);
+    let (s3_sha256, gcs_sha256) = (
+        fixture::sha256_hex(&s3_archive),
+        fixture::sha256_hex(&gcs_archive),
+    );
+    // Path-style S3 and the download endpoint of the GCS JSON API.
+    let server = FixtureServer::start(HashMap::from([
+        (
+            "tools/node/index.json".to_owned(),
+            br#"[{"version": "v29.3.0", "lts": false, "files": ["linux-x64"]}]"#.to_vec(),
+        ),
+        ("tools/node/custom.tar.xz".to_owned(), s3_archive),
+        (
+            "download/storage/v1/b/tools/o/node%2Fcustom.tar.xz".to_owned(),
+            gcs_archive,
+        ),
+    ]));
+    // Without the download cache, the archive is fetched again for every tag.
+    std::fs::write(
+        &env.config_path,
+        format!(
+            "data_path = {:?}\nmax_download_cache_size = 0\n",
+            env.data_path
+        ),
+    )
+    .unwrap();
+    let s3 = |cmd: &mut Command| {
+        cmd.env("AWS_ENDPOINT_URL", server.base_url())
+            .env("AWS_ACCESS_KEY_ID", "AKID")
+            .env("AWS_SECRET_ACCESS_KEY", "secret")
+            .env("AWS_REGION", "eu-west-1")
+            .env_remove("AWS_SESSION_TOKEN");
+    };
+
+    env.avm()
+        .args(["install-url", "node", "s3://tools/node/custom.tar.xz"])
+        .args(["--tag", "from-s3", "--sha256", &s3_sha256])
+        .env_remove("AWS_ACCESS_KEY_ID")
+        .env_remove("AWS_ENDPOINT_URL")
+        .env("AWS_EC2_METADATA_DISABLED", "true")
+        .env("AWS_ENDPOINT_URL_S3", server.base_url())
+        .env(
+            "AWS_SHARED_CREDENTIALS_FILE",
+            env.data_path.join("no-credentials"),
+        )
+        .assert()
+        .success();
+    assert_eq!(server.authorizations(), vec![None]);
+
+    let mut cmd = env.avm();
+    s3(&mut cmd);
+    cmd.args(["install-url", "node", "s3://tools/node/custom.tar.xz"])
+        .args(["--tag", "signed", "--sha256", &s3_sha256])
+        .assert()
+        .success();
+    let authorization = server.authorizations().pop().unwrap().unwrap();
+    assert!(
+        authorization.starts_with("AWS4-HMAC-SHA256 Credential=AKID/")
+            && authorization.contains("/eu-west-1/s3/aws4_request"),
+        "{authorization}"
+    );
+
+    env.avm()
+        .args(["install-url", "node", "gs://tools/node/custom.tar.xz"])
+        .args(["--tag", "from-gcs", "--sha256", &gcs_sha256])
+        .env("STORAGE_EMULATOR_HOST", server.base_url())
+        .env("GOOGLE_OAUTH_ACCESS_TOKEN", "ya29.token")
+        .assert()
+        .success();
+    assert_eq!(
+        server.authorizations().pop().unwrap().as_deref(),
+        Some("Bearer ya29.token")
+    );
+    for tag in ["from-s3", "signed", "from-gcs"] {
+        assert_exists(&env.node_dir().join(tag).join("bin").join("node"));
+    }
+
+    // Mirrors can rewrite to object store URLs as well.
+    std::fs::write(
+        &env.config_path,
+        format!(
+            "data_path = {:?}\n\n[[mirrors]]\nfrom = \"https://nodejs.org/dist/\"\nto = \"s3://tools/node/\"\n",
+            env.data_path
+        ),
+    )
+    .unwrap();
+    let mut cmd = env.avm();
+    s3(&mut cmd);
+    cmd.args(["get-vers", "node"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("29.3.0"));
+}
+
+#[test]
+fn s3_credentials_from_web_identity_containers_and_instances() {
+    let env = Env::new();
+    let archive = fixture::tar_xz("s3", &[("bin/node", NODE_SCRIPT, 0o755)]);
+    let sha256 = fixture::sha256_hex(&archive);
+    let credentials = |id: &str| {
+        format!(
+            r#"{{"AccessKeyId": "{id}", "SecretAccessKey": "secret", "Token": "token", "Expiration": "9999-01-01T00:00:00Z"}}"#
+        )
+        .into_bytes()
+    };
+    let server = FixtureServer::start(HashMap::from([
+        ("tools/node/custom.tar.xz".to_owned(), archive),
+        (
+            // STS, answering `AssumeRoleWithWebIdentity`.
+            String::new(),
+            b"<AssumeRoleWithWebIdentityResponse><AssumeRoleWithWebIdentityResult><Credentials>\
+              <AccessKeyId>ASIAWEB</AccessKeyId><SecretAccessKey>secret</SecretAccessKey>\
+              <SessionToken>token</SessionToken></Credentials></AssumeRoleWithWebIdentityResult>\
+              </AssumeRoleWithWebIdentityResponse>"
+                .to_vec(),
+        ),
+        ("container/credentials".to_owned(), credentials("ASIATASK")),
+        ("latest/api/token".to_owned(), b"imds-token".to_vec()),
+        (
+            "latest/meta-data/iam/security-credentials".to_owned(),
+            b"instance-role\n".to_vec(),
+        ),
+        (
+            "latest/meta-data/iam/security-credentials/instance-role".to_owned(),
+            credentials("ASIAINSTANCE"),
+        ),
+    ]));
+    // Without the download cache, the archive is fetched for every tag.
+    std::fs::write(
+        &env.config_path,
+        format!(
+            "data_path = {:?}\nmax_download_cache_size = 0\n",
+            env.data_path
+        ),
+    )
+    .unwrap();
+    let token_file = env.data_path.join("web-identity-token");
+    std::fs::create_dir_all(&env.data_path).unwrap();
+    std::fs::write(&token_file, "jwt\n").unwrap();
+
+    let install = |tag: &str, configure: &dyn Fn(&mut Command)| {
+        let mut cmd = env.avm();
+        for name in [
+            "AWS_ACCESS_KEY_ID",
+            "AWS_SECRET_ACCESS_KEY",
+            "AWS_SESSION_TOKEN",
+            "AWS_PROFILE",
+            "AWS_WEB_IDENTITY_TOKEN_FILE",
+            "AWS_ROLE_ARN",
+            "AWS_CONTAINER_CREDENTIALS_RELATIVE_URI",
+            "AWS_CONTAINER_CREDENTIALS_FULL_URI",
+            "AWS_CONTAINER_AUTHORIZATION_TOKEN",
+            "AWS_CONTAINER_AUTHORIZATION_TOKEN_FILE",
+            "AWS_EC2_METADATA_DISABLED",
+        ] {
+            cmd.env_remove(name);
+        }
+        cmd.env("AWS_ENDPOINT_URL_S3", server.base_url())
+            .env("AWS_EC2_METADATA_SERVICE_ENDPOINT", server.base_url())
+            .env("AWS_REGION", "eu-west-1")
+            .env(
+                "AWS_SHARED_CREDENTIALS_FILE",
+                env.data_path.join("no-credentials"),
+            )
+            .env("AWS_CONFIG_FILE", env.data_path.join("no-config"));
+        configure(&mut cmd);
+        cmd.args(["install-url", "node", "s3://tools/node/custom.tar.xz"])
+            .args(["--tag", tag, "--sha256", &sha256])
+            .assert()
+            .success();
+        server.authorizations()
+    };
+
+    let authorizations = install("web", &|cmd| {
+        cmd.env("AWS_WEB_IDENTITY_TOKEN_FILE", &token_file)
+            .env("AWS_ROLE_ARN", "arn:aws:iam::123456789012:role/ci")
+            .env("AWS_ENDPOINT_URL_STS", server.base_url());
+    });
+    assert!(authorizations
+        .last()
+        .unwrap()
+        .as_ref()
+        .unwrap()
+        .starts_with("AWS4-HMAC-SHA256 Credential=ASIAWEB/"));
+
+    let authorizations = install("task", &|cmd| {
+        cmd.env(
+            "AWS_CONTAINER_CREDENTIALS_FULL_URI",
+            format!("{}container/credentials", server.base_url()),
+        )
+        .env("AWS_CONTAINER_AUTHORIZATION_TOKEN", "task-token");
+    });
+    assert!(authorizations.contains(&Some("task-token".to_owned())));
+    assert!(authorizations
+        .last()
+        .unwrap()
+        .as_ref()
+        .unwrap()
+        .starts_with("AWS4-HMAC-SHA256 Credential=ASIATASK/"));
+
+    let authorizations = install("instance", &|_| {});
+    assert!(authorizations
+        .last()
+        .unwrap()
+        .as_ref()
+        .unwrap()
+        .starts_with("AWS4-HMAC-SHA256 Credential=ASIAINSTANCE/"));
+    assert!(server
+        .requests()
+        .contains(&"latest/meta-data/iam/security-credentials/instance-role".to_owned()));
+}
+
+#[test]
+fn install_stream_extracts_while_downloading() {
+    let env = Env::new();
+    let archive = fixture::tar_xz("custom", &[("bin/node", NODE_SCRIPT, 0o755)]);
+    let server = FixtureServer::start(HashMap::from([("custom.tar.xz".to_owned(), archive)]));
+    let url = format!("{}custom.tar.xz", server.base_url());
+
+    // The extracted files are discarded when the hash doesn't match.
+    env.avm()
+        .args(["install-url", "node", &url, "--tag", "custom", "--stream"])
+        .args(["--sha256", &"0".repeat(64)])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("verification failed"));
+    assert!(!env.node_dir().join("custom").exists());
+
+    env.avm()
+        .args(["install", "node", "-p", "x64-linux", "-x", "28", "--stream"])
+        .assert()
+        .success();
+    assert_exists(
+        &env.node_dir()
+            .join("x64-linux_28.1.0")
+            .join("bin")
+            .join("node"),
+    );
+    assert!(!env.node_dir().join(".tmp.x64-linux_28.1.0").exists());
+}
+
+#[test]
+fn limit_rate_slows_down_downloads() {
+    let env = Env::new();
+    // Incompressible, so that the archive stays about as large.
+    let mut state = 1_u32;
+    let payload = (0..8192)
+        .map(|_| {
+            state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            (state >> 24) as u8
+        })
+        .collect::<Vec<_>>();
+    let archive = fixture::tar_xz(
+        "custom",
+        &[
+            ("bin/node", NODE_SCRIPT, 0o755),
+            ("payload", &payload, 0o644),
+        ],
+    );
+    assert!(archive.len() > 8192);
+    let server = FixtureServer::start(HashMap::from([("custom.tar.xz".to_owned(), archive)]));
+    let url = format!("{}custom.tar.xz", server.base_url());
+
+    let started = std::time::Instant::now();
+    env.avm()
+        .args(["install-url", "node", &url, "--tag", "custom"])
+        .args(["--limit-rate", "16KB/s"])
+        .assert()
+        .success();
+    assert!(started.elapsed() >= std::time::Duration::from_millis(400));
+    assert_exists(&env.node_dir().join("custom").join("payload"));
+}
+
+#[test]
+fn export_then_import_on_another_data_dir() {
+    let env = Env::new();
+    env.avm()
+        .args(["install", "node", "-p", "x64-linux", "--default"])
+        .assert()
+        .success();
+    env.avm()
+        .args(["install", "node", "-p", "x64-linux", "-x", "28"])
+        .assert()
+        .success();
+    env.avm()
+        .args(["alias", "node", "x64-linux_28.1.0", "lts"])
+        .assert()
+        .success();
+    let output = env.avm().arg("export").output().unwrap();
+    assert!(output.status.success());
+    let exported = String::from_utf8(output.stdout).unwrap();
+    assert!(exported.contains("platform = \"x64-linux\""));
+
+    let other = Env::new();
+    let file = other.config_path.with_file_name("tools.toml");
+    std::fs::write(&file, exported).unwrap();
+    other
+        .avm()
+        .arg("import")
+        .arg(&file)
+        .assert()
+        .success()
+        .stdout(
+            "installed: node x64-linux_28.1.0\n\
+             installed: node x64-linux_29.3.0\n\
+             aliased: node default -> x64-linux_29.3.0\n\
+             aliased: node lts -> x64-linux_28.1.0\n",
+        );
+    other
+        .avm()
+        .args(["run", "node", "--tag", "lts", "--", "-v"])
+        .assert()
+        .success()
+        .stdout("fixture node -v\n");
+
+    // Importing again keeps what is there.
+    other
+        .avm()
+        .arg("import")
+        .arg(&file)
+        .assert()
+        .success()
+        .stdout("exists: node x64-linux_28.1.0\nexists: node x64-linux_29.3.0\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn export_tag_installs_with_install_local() {
+    let env = Env::new();
+    env.avm()
+        .args([
+            "install",
+            "node",
+            "-p",
+            "x64-linux",
+            "-x",
+            "28",
+            "--default",
+        ])
+        .assert()
+        .success();
+    let archive = env.config_path.with_file_name("node.tar.zst");
+    env.avm()
+        .args(["export-tag", "node", "default"])
+        .arg(archive.with_extension("zip"))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("must be a `.tar.zst` file"));
+    env.avm()
+        .args(["export-tag", "node", "default"])
+        .arg(&archive)
+        .assert()
+        .success();
+
+    let other = Env::new();
+    other
+        .avm()
+        .args(["install-local", "node"])
+        .arg(&archive)
+        .arg("copied")
+        .assert()
+        .success();
+    other
+        .avm()
+        .args(["info", "node", "copied"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Version: 28.1.0 (LTS)\nPlatform: x64-linux\n",
+        ))
+        .stdout(predicate::str::contains(
+            "node-v28.1.0-linux-x64.tar.xz\nsha256: ",
+        ));
+    other
+        .avm()
+        .args(["run", "node", "--tag", "copied", "--", "-v"])
+        .assert()
+        .success()
+        .stdout("fixture node -v\n");
+
+    // Other archives need the version.
+    let plain = other.config_path.with_file_name("node.tar.xz");
+    std::fs::write(
+        &plain,
+        fixture::tar_xz("node", &[("bin/node", NODE_SCRIPT, 0o755)]),
+    )
+    .unwrap();
+    other
+        .avm()
+        .args(["install-local", "node"])
+        .arg(&plain)
+        .arg("plain")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("`--version` is required"));
+}
+
+#[test]
+fn sync_installs_locked_artifacts() {
+    let env = Env::new();
+    env.avm()
+        .args(["install", "node", "-p", "x64-linux", "--default"])
+        .assert()
+        .success();
+    let output = env.avm().args(["export", "--lock"]).output().unwrap();
+    assert!(output.status.success());
+    let lock = String::from_utf8(output.stdout).unwrap();
+    assert!(lock.contains("node-v29.3.0-linux-x64.tar.xz\""), "{lock}");
+
+    let other = Env::new();
+    let lock_path = other.config_path.with_file_name("avm.lock");
+    let hash_start = lock.find("sha256 = \"").unwrap() + "sha256 = \"".len();
+    let tampered = format!(
+        "{}{}{}",
+        &lock[..hash_start],
+        "0".repeat(64),
+        &lock[hash_start + 64..]
+    );
+    std::fs::write(&lock_path, tampered).unwrap();
+    other
+        .avm()
+        .arg("sync")
+        .arg(&lock_path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("verification failed"));
+
+    std::fs::write(&lock_path, &lock).unwrap();
+    other
+        .avm()
+        .arg("sync")
+        .arg(&lock_path)
+        .assert()
+        .success()
+        .stdout("installed: node x64-linux_29.3.0\naliased: node default -> x64-linux_29.3.0\n");
+
+    // An existing tag must have the locked version.
+    std::fs::write(
+        &lock_path,
+        lock.replace("version = \"29.3.0\"", "version = \"29.3.1\""),
+    )
+    .unwrap();
+    other
+        .avm()
+        .arg("sync")
+        .arg(&lock_path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("but 29.3.1 is locked"));
+
+    // Lock files need URLs and hashes.
+    let exported = env.avm().arg("export").output().unwrap().stdout;
+    std::fs::write(&lock_path, exported).unwrap();
+    other
+        .avm()
+        .arg("sync")
+        .arg(&lock_path)
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("avm export --lock"));
+}
+
+#[test]
+fn download_cache_reuses_verified_archives() {
+    let env = Env::new();
+    let archive = "v28.1.0/node-v28.1.0-linux-x64.tar.xz";
+    let downloads = || {
+        env.server
+            .requests()
+            .iter()
+            .filter(|path| *path == archive)
+            .count()
+    };
+    let install = || {
+        env.avm()
+            .args(["install", "node", "-p", "x64-linux", "-x", "28"])
+            .assert()
+            .success()
+    };
+    let remove = || {
+        env.avm()
+            .args(["remove", "node", "x64-linux_28.1.0"])
+            .assert()
+            .success()
+    };
+    install();
+    assert_eq!(downloads(), 1);
+    remove();
+    install().stderr(predicate::str::contains("Using the cached archive"));
+    assert_eq!(downloads(), 1);
+    assert_exists(
+        &env.node_dir()
+            .join("x64-linux_28.1.0")
+            .join("bin")
+            .join("node"),
+    );
+
+    let cache_dir = env.data_path.join("cache").join("sha256");
+    env.avm()
+        .args(["clean", "--cache", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("would remove: "));
+    assert_exists(&cache_dir);
+    env.avm()
+        .args(["clean", "--cache"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("removed: "));
+    assert!(!cache_dir.exists());
+    remove();
+    install();
+    assert_eq!(downloads(), 2);
+
+    // A maximum size of 0 turns the cache off.
+    env.avm()
+        .args(["config", "set", "max_download_cache_size", "0"])
+        .assert()
+        .success();
+    env.avm().args(["clean", "--cache"]).assert().success();
+    remove();
+    install();
+    assert_eq!(downloads(), 3);
+    assert!(!cache_dir.exists());
+}
+
+#[test]
+fn clean_removes_leftover_tmp_dirs() {
+    let env = Env::new();
+    // Left by an interrupted install.
+    let tmp_dir = env.node_dir().join(".tmp.x64-linux_29.3.0");
+    std::fs::create_dir_all(&tmp_dir).unwrap();
+    std::fs::write(tmp_dir.join(".lock"), "").unwrap();
+    env.avm()
+        .args(["install", "node", "-p", "x64-linux"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("avm clean"));
+
+    env.avm()
+        .args(["clean", "node", "--dry-run", "--older-than", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("kept: "));
+    env.avm()
+        .args(["clean", "node", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("would remove: "));
+    assert_exists(&tmp_dir);
+
+    env.avm()
+        .args(["clean", "node"])
+        .assert()
+        .success()
+        .stdout(predicate::str::starts_with("removed: "));
+    assert!(!tmp_dir.exists());
+    env.avm()
+        .args(["install", "node", "-p", "x64-linux"])
+        .assert()
+        .success();
+}
+
+#[test]
+fn clean_deletes_or_repoints_dangling_aliases() {
+    let env = Env::new();
+    for version in ["29.3.0", "28.1.0"] {
+        env.avm()
+            .args(["install", "node", "-p", "x64-linux", "-v", version])
+            .assert()
+            .success();
+    }
+    for alias in ["current", "stable"] {
+        env.avm()
+            .args(["alias", "node", "x64-linux_29.3.0", alias])
+            .assert()
+            .success();
+    }
+    env.avm()
+        .args(["remove", "node", "x64-linux_29.3.0", "--allow-dangling"])
+        .assert()
+        .success();
+
+    env.avm()
+        .args(["clean", "node", "--dangling-aliases", "--dry-run"])
+        .assert()
+        .success()
+        .stdout(
+            "dangling alias: current -> x64-linux_29.3.0
+\
+             dangling alias: stable -> x64-linux_29.3.0
+",
+        );
+    // Not asked without a terminal.
+    env.avm()
+        .args(["clean", "node", "--dangling-aliases"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--yes"));
+
+    env.avm()
+        .args(["alias", "delete", "node", "stable"])
+        .assert()
+        .success();
+    env.avm()
+        .args([
+            "clean",
+            "node",
+            "--dangling-aliases",
+            "--retarget",
+            "x64-linux_28.1.0",
+        ])
+        .assert()
+        .success()
+        .stdout("re-pointed dangling alias: current -> x64-linux_28.1.0\n");
+    env.avm()
+        .args(["alias", "node", "x64-linux_28.1.0", "stable"])
+        .assert()
+        .success();
+    env.avm()
+        .args(["remove", "node", "x64-linux_28.1.0", "--allow-dangling"])
+        .assert()
+        .success();
+    env.avm()
+        .args(["clean", "node", "--dangling-aliases", "--yes"])
+        .assert()
+        .success()
+        .stdout("removed dangling alias: current\nremoved dangling alias: stable\n");
+    env.avm()
+        .args(["list", "node"])
+        .assert()
+        .success()
+        .stdout("");
+}
+
+#[test]
+fn manifest_tool_installs_from_releases_json() {
+    let env = Env::new();
+    let manifest_dir = env.config_path.parent().unwrap().join("tools");
+    std::fs::create_dir_all(&manifest_dir).unwrap();
+    std::fs::write(
+        manifest_dir.join("fixture-node.toml"),
+        format!(
+            r#"about = "Node.js from a manifest"
+releases_url = "{base}index.json"
+url_template = "{base}{{raw_version}}/node-{{raw_version}}-{{platform}}.tar.xz"
+entry_path = "bin/node{{exe}}"
+
+[fields]
+version = "version"
+lts = "lts"
+
+[platforms]
+x64-linux = "linux-x64"
+"#,
+            base = env.server.base_url()
+        ),
+    )
+    .unwrap();
+
+    env.avm()
+        .arg("tool")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "- fixture-node: Node.js from a manifest",
+        ));
+    env.avm()
+        .args(["get-vers", "fixture-node", "-p", "x64-linux", "--lts-only"])
+        .assert()
+        .success()
+        .stdout("28.1.0 [LTS]\n");
+    env.avm()
+        .args(["changelog", "fixture-node", "-p", "x64-linux"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("are not known to avm"));
+    env.avm()
+        .args(["install", "fixture-node", "-p", "x64-linux", "-x", "28"])
+        .assert()
+        .success();
+    assert_exists(
+        &env.data_path
+            .join("tools")
+            .join("fixture-node")
+            .join("x64-linux_28.1.0")
+            .join("bin")
+            .join("node"),
+    );
+
+    std::fs::write(manifest_dir.join("node.toml"), "").unwrap();
+    env.avm()
+        .args(["list", "fixture-node"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("node.toml"));
+}
+
+#[test]
+fn manifest_tool_installs_from_patches() {
+    let env = Env::new();
+    let archive = |version: &str| {
+        fixture::tar_gz(
+            &format!("tool-{version}"),
+            &[("bin/tool", format!("tool {version}").as_bytes(), 0o755)],
+        )
+    };
+    let (v1, v2) = (archive("1.0.0"), archive("2.0.0"));
+    let mut encoder = zstd::stream::write::Encoder::with_ref_prefix(Vec::new(), 3, &v1).unwrap();
+    std::io::Write::write_all(&mut encoder, &v2).unwrap();
+    let patch = encoder.finish().unwrap();
+    let server = FixtureServer::start(HashMap::from([
+        (
+            "releases.json".to_owned(),
+            serde_json::to_vec(&serde_json::json!([
+                { "version": "1.0.0", "sha256": fixture::sha256_hex(&v1) },
+                { "version": "2.0.0", "sha256": fixture::sha256_hex(&v2) },
+            ]))
+            .unwrap(),
+        ),
+        ("tool-1.0.0.tar.gz".to_owned(), v1),
+        // Only the patch of version 2 is served.
+        (
+            "deltas/tool-1.0.0.tar.gz..tool-2.0.0.tar.gz.zst".to_owned(),
+            patch,
+        ),
+    ]));
+    let manifest_dir = env.config_path.parent().unwrap().join("tools");
+    std::fs::create_dir_all(&manifest_dir).unwrap();
+    std::fs::write(
+        manifest_dir.join("patched.toml"),
+        format!(
+            r#"about = "A tool publishing patches"
+releases_url = "{base}releases.json"
+url_template = "{base}tool-{{raw_version}}.tar.gz"
+delta_url_template = "{base}deltas/{{from}}..{{to}}.zst"
+entry_path = "bin/tool"
+
+[fields]
+version = "version"
+sha256 = "sha256"
+"#,
+            base = server.base_url()
+        ),
+    )
+    .unwrap();
+
+    env.avm()
+        .args(["install", "patched", "-x", "1.0.0"])
+        .assert()
+        .success();
+    env.avm()
+        .args(["install", "patched", "-x", "2.0.0"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Downloading the patch"));
+    assert_eq!(
+        std::fs::read_to_string(
+            env.data_path
+                .join("tools")
+                .join("patched")
+                .join("2.0.0")
+                .join("bin")
+                .join("tool")
+        )
+        .unwrap(),
+        "tool 2.0.0"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn plugin_tool_installs_from_script_output() {
+    let env = Env::new();
+    write_fixture_plugin(
+        &env,
+        r#"echo '["27.0.0", {"version": "28.1.0", "lts": true}, "29.3.0"]'"#,
+    );
+
+    env.avm()
+        .args(["get-vers", "fixture-node", "-p", "x64-linux", "-x", "28"])
+        .assert()
+        .success()
+        .stdout("28.1.0 [LTS]\n");
+    env.avm()
+        .args(["run", "fixture-node", "-p", "x64-linux", "--", "-v"])
+        .assert()
+        .success()
+        .stdout("fixture node -v\n");
+    assert_exists(
+        &env.data_path
+            .join("tools")
+            .join("fixture-node")
+            .join("x64-linux_29.3.0"),
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn outdated_and_upgrade_follow_new_releases() {
+    let env = Env::new();
+    let plugin_dir = write_fixture_plugin(&env, r#"cat "$AVM_PLUGIN_DIR/versions.json""#);
+    std::fs::write(plugin_dir.join("versions.json"), r#"["28.1.0"]"#).unwrap();
+    env.avm()
+        .args(["install", "fixture-node", "-p", "x64-linux", "--default"])
+        .assert()
+        .success();
+    env.avm()
+        .args(["outdated", "fixture-node"])
+        .assert()
+        .success()
+        .stdout("");
+
+    std::fs::write(plugin_dir.join("versions.json"), r#"["28.1.0", "29.3.0"]"#).unwrap();
+    env.avm()
+        .args(["outdated", "fixture-node"])
+        .assert()
+        .success()
+        .stdout("");
+    env.avm()
+        .args(["outdated", "fixture-node", "--any-major"])
+        .assert()
+        .success()
+        .stdout("x64-linux_28.1.0: 28.1.0 -> 29.3.0\n");
+    env.avm()
+        .args([
+            "upgrade",
+            "fixture-node",
+            "--all",
+            "--any-major",
+            "--dry-run",
+        ])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Tag: x64-linux_29.3.0\n"))
+        .stdout(predicate::str::contains(
+            "would upgrade: x64-linux_28.1.0 -> x64-linux_29.3.0\n",
+        ));
+    assert!(!env
+        .data_path
+        .join("tools")
+        .join("fixture-node")
+        .join("x64-linux_29.3.0")
+        .exists());
+    env.avm()
+        .args([
+            "upgrade",
+            "fixture-node",
+            "--all",
+            "--any-major",
+            "--remove-old",
+        ])
+        .assert()
+        .success()
+        .stdout(
+            "upgraded: x64-linux_28.1.0 -> x64-linux_29.3.0\n\
+             aliased: default -> x64-linux_29.3.0\n\
+             removed: x64-linux_28.1.0\n",
+        );
+    env.avm()
+        .args(["list", "fixture-node"])
+        .assert()
+        .success()
+        .stdout("default -> x64-linux_29.3.0\nx64-linux_29.3.0\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn prune_removes_matching_tags_except_alias_targets() {
+    let env = Env::new();
+    write_fixture_plugin(&env, r#"echo '["28.1.0", "29.3.0"]'"#);
+    for version in ["28.1.0", "29.3.0"] {
+        env.avm()
+            .args(["install", "fixture-node", "-p", "x64-linux", "-v", version])
+            .assert()
+            .success();
+    }
+    env.avm().args(["prune", "fixture-node"]).assert().failure();
+    env.avm()
+        .args(["alias", "fixture-node", "x64-linux_29.3.0", "stable"])
+        .assert()
+        .success();
+
+    // Without a terminal to confirm on, nothing is removed.
+    env.avm()
+        .args(["prune", "fixture-node", "--major", "28"])
+        .assert()
+        .failure()
+        .stdout("");
+    env.avm()
+        .args(["prune", "fixture-node", "--all-but-latest", "1", "--yes"])
+        .assert()
+        .success()
+        .stdout("removed: x64-linux_28.1.0\n");
+    env.avm()
+        .args(["prune", "fixture-node", "-x", "29", "--yes"])
+        .assert()
+        .success()
+        .stdout("");
+    env.avm()
+        .args(["list", "fixture-node"])
+        .assert()
+        .success()
+        .stdout("stable -> x64-linux_29.3.0\nx64-linux_29.3.0\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn install_force_replaces_a_broken_tag() {
+    let env = Env::new();
+    let install = |force: bool| {
+        let mut cmd = env.avm();
+        cmd.args(["install", "node", "-p", "x64-linux", "-x", "28"]);
+        if force {
+            cmd.arg("--force");
+        }
+        cmd.assert()
+    };
+    install(false).success();
+    let tag_dir = env.node_dir().join("x64-linux_28.1.0");
+    // Left by an install that was killed.
+    std::fs::remove_file(tag_dir.join("bin").join("node")).unwrap();
+    let tmp_dir = env.node_dir().join(".tmp.x64-linux_28.1.0");
+    std::fs::create_dir_all(&tmp_dir).unwrap();
+    std::fs::write(tmp_dir.join(".lock"), "").unwrap();
+
+    install(false).failure();
+    install(true).success();
+    assert!(tag_dir.join("bin").join("node").exists());
+    assert!(!tmp_dir.exists());
+
+    // A forced reinstall that fails keeps the tag and its aliases.
+    env.avm()
+        .args(["alias", "node", "x64-linux_28.1.0", "lts"])
+        .assert()
+        .success();
+    // Lists the release without serving its archive.
+    let broken = FixtureServer::start(HashMap::from([
+        (
+            "index.json".to_owned(),
+            br#"[{"version": "v28.1.0", "lts": "Fixture", "files": ["linux-x64"]}]"#.to_vec(),
+        ),
+        (
+            "v28.1.0/SHASUMS256.txt".to_owned(),
+            format!("{}  node-v28.1.0-linux-x64.tar.xz\n", "0".repeat(64)).into_bytes(),
+        ),
+    ]));
+    let config = std::fs::read_to_string(&env.config_path)
+        .unwrap()
+        .replace(env.server.base_url(), broken.base_url());
+    std::fs::write(&env.config_path, config).unwrap();
+    install(true).failure();
+    assert!(tag_dir.join("bin").join("node").exists());
+    env.avm().args(["path", "node", "lts"]).assert().success();
+}
+
+#[cfg(unix)]
+#[test]
+fn prune_unused_for_keeps_recently_run_tags() {
+    let env = Env::new();
+    for prefix in ["28", "29"] {
+        env.avm()
+            .args(["install", "node", "-p", "x64-linux", "-x", prefix])
+            .assert()
+            .success();
+    }
+    // Both were installed long ago.
+    for tag in ["x64-linux_28.1.0", "x64-linux_29.3.0"] {
+        let info_path = env.node_dir().join(tag).join(".avm.version-info.toml");
+        let info = std::fs::read_to_string(&info_path).unwrap();
+        let info = info
+            .lines()
+            .map(|line| {
+                if line.starts_with("installed_at") {
+                    "installed_at = 1000000000"
+                } else {
+                    line
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        std::fs::write(&info_path, info).unwrap();
+    }
+    env.avm()
+        .args(["run", "node", "-t", "x64-linux_29.3.0", "--", "-v"])
+        .assert()
+        .success();
+    let usage = std::fs::read_to_string(env.data_path.join("state").join("usage.json")).unwrap();
+    assert!(usage.contains("x64-linux_29.3.0"));
+
+    env.avm()
+        .args(["prune", "node", "--unused-for", "90x"])
+        .assert()
+        .failure();
+    env.avm()
+        .args(["prune", "node", "--unused-for", "90d", "--yes"])
+        .assert()
+        .success()
+        .stdout("removed: x64-linux_28.1.0\n");
+    env.avm()
+        .args(["list", "node"])
+        .assert()
+        .success()
+        .stdout("x64-linux_29.3.0\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn elixir_install_checks_installed_erlang() {
+    let env = Env::new();
+    // Like the real script, writes the install location into the start script.
+    let install_script = b"#!/bin/sh\necho \"ROOTDIR=$2\" > bin/erl\n";
+    let otp = fixture::tar_gz(
+        "OTP-26.2.5",
+        &[("Install", install_script, 0o755), ("bin/erl", b"", 0o755)],
+    );
+    let elixir = fixture::zip(&[
+        ("VERSION", b"1.17.2", 0o644),
+        ("bin/elixir", b"#!/bin/sh\n", 0o755),
+    ]);
+    let server = FixtureServer::start(HashMap::from([
+        (
+            "otp/ubuntu-22.04/builds.txt".to_owned(),
+            format!(
+                "maint-26 0a1b 2024-05-02T00:00:00Z 00\nOTP-26.2.5 2c3d 2024-05-01T00:00:00Z {}\n",
+                fixture::sha256_hex(&otp)
+            )
+            .into_bytes(),
+        ),
+        ("otp/ubuntu-22.04/OTP-26.2.5.tar.gz".to_owned(), otp),
+        (
+            "elixir/builds.txt".to_owned(),
+            format!(
+                "v1.17.2-otp-26 4e5f 2024-07-06T00:00:00Z {}\nmain-otp-26 6a7b 2024-07-07T00:00:00Z 00\n",
+                fixture::sha256_hex(&elixir)
+            )
+            .into_bytes(),
+        ),
+        ("elixir/v1.17.2-otp-26.zip".to_owned(), elixir),
+    ]));
+    env.append_config(&format!(
+        "\n[[mirrors]]\nfrom = \"https://builds.hex.pm/builds/\"\nto = {:?}\n",
+        server.base_url()
+    ));
+    let tools_dir = env.data_path.join("tools");
+
+    env.avm()
+        .args(["install", "elixir", "-f", "otp-26"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("needs Erlang/OTP 26"));
+    assert_exists(
+        &tools_dir
+            .join("elixir")
+            .join("otp-26_1.17.2")
+            .join("bin")
+            .join("elixir"),
+    );
+
+    env.avm()
+        .args(["install", "erlang", "-p", "x64-linux"])
+        .assert()
+        .success();
+    let otp_dir = tools_dir.join("erlang").join("x64-linux_26.2.5");
+    assert_eq!(
+        std::fs::read_to_string(otp_dir.join("bin").join("erl")).unwrap(),
+        format!("ROOTDIR={}\n", otp_dir.display())
+    );
+
+    env.avm()
+        .args(["install", "elixir", "-f", "otp-26", "-u"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("needs Erlang/OTP").not());
+}
+
+#[test]
+fn temurin_installs_from_adoptium_api() {
+    let env = Env::new();
+    let jdk = fixture::tar_gz(
+        "jdk-21.0.5+11",
+        &[
+            ("bin/java", b"#!/bin/sh\n", 0o755),
+            ("bin/javac", b"#!/bin/sh\n", 0o755),
+        ],
+    );
+    let version_data = |security: u32, build: u32| {
+        format!(r#"{{"major": 21, "minor": 0, "security": {security}, "build": {build}}}"#)
+    };
+    let server = FixtureServer::start(HashMap::from([
+        (
+            "adoptium/info/available_releases".to_owned(),
+            br#"{"available_releases": [8, 11, 17, 21, 22], "available_lts_releases": [8, 11, 17, 21], "most_recent_feature_release": 22}"#.to_vec(),
+        ),
+        (
+            "adoptium/info/release_versions".to_owned(),
+            format!(
+                r#"{{"versions": [{}, {}]}}"#,
+                version_data(5, 11),
+                version_data(4, 7)
+            )
+            .into_bytes(),
+        ),
+        (
+            "adoptium/assets/feature_releases/21/ga".to_owned(),
+            format!(
+                r#"[{{"binaries": [{{"package": {{"checksum": "{}", "link": "https://github.com/adoptium/temurin21-binaries/releases/download/jdk-21.0.5/OpenJDK21U-jdk_x64_linux_hotspot_21.0.5_11.tar.gz"}}}}], "version_data": {}}}]"#,
+                fixture::sha256_hex(&jdk),
+                version_data(5, 11)
+            )
+            .into_bytes(),
+        ),
+        (
+            "github/jdk-21.0.5/OpenJDK21U-jdk_x64_linux_hotspot_21.0.5_11.tar.gz".to_owned(),
+            jdk,
+        ),
+    ]));
+    env.append_config(&format!(
+        "\n[[mirrors]]\nfrom = \"https://api.adoptium.net/v3/\"\nto = \"{0}adoptium/\"\n\n[[mirrors]]\nfrom = \"https://github.com/adoptium/temurin21-binaries/releases/download/\"\nto = \"{0}github/\"\n",
+        server.base_url()
+    ));
+
+    env.avm()
+        .args(["get-vers", "temurin", "-p", "x64-linux", "-x", "21"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("21.0.4+7").and(predicate::str::contains("21.0.5+11")));
+    env.avm()
+        .args(["install", "temurin", "-p", "x64-linux", "-x", "21"])
+        .assert()
+        .success();
+    let tag_dir = env
+        .data_path
+        .join("tools")
+        .join("temurin")
+        .join("x64-linux_jdk_21.0.5+11");
+    assert_exists(&tag_dir.join("bin").join("javac"));
+    env.avm()
+        .args(["entry-path", "temurin", "x64-linux_jdk_21.0.5+11"])
+        .assert()
+        .success()
+        .stdout(format!("{}\n", tag_dir.join("bin").join("java").display()));
+}
+
+#[test]
+fn graalvm_installs_from_github_releases() {
+    let env = Env::new();
+    let archive = fixture::tar_gz(
+        "graalvm-community-openjdk-21.0.2+13.1",
+        &[
+            ("bin/java", b"#!/bin/sh\n", 0o755),
+            ("bin/native-image", b"#!/bin/sh\n", 0o755),
+        ],
+    );
+    let name = "graalvm-community-jdk-21.0.2_linux-x64_bin.tar.gz";
+    let server = FixtureServer::start(HashMap::from([
+        (
+            "api/repos/graalvm/graalvm-ce-builds/releases".to_owned(),
+            format!(
+                r#"[{{"assets": [
+                    {{"name": "{name}", "browser_download_url": "https://github.com/graalvm/graalvm-ce-builds/releases/download/jdk-21.0.2/{name}"}},
+                    {{"name": "{name}.sha256", "browser_download_url": "https://github.com/graalvm/graalvm-ce-builds/releases/download/jdk-21.0.2/{name}.sha256"}}
+                ]}}]"#
+            )
+            .into_bytes(),
+        ),
+        (
+            format!("download/jdk-21.0.2/{name}.sha256"),
+            fixture::sha256_hex(&archive).into_bytes(),
+        ),
+        (format!("download/jdk-21.0.2/{name}"), archive),
+    ]));
+    env.append_config(&format!(
+        "\n[[mirrors]]\nfrom = \"https://api.github.com/\"\nto = \"{0}api/\"\n\n[[mirrors]]\nfrom = \"https://github.com/graalvm/graalvm-ce-builds/releases/download/\"\nto = \"{0}download/\"\n",
+        server.base_url()
+    ));
+
+    env.avm()
+        .args(["get-vers", "graalvm", "-p", "x64-linux", "-x", "21"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("21.0.2"));
+    env.avm()
+        .args(["install", "graalvm", "-p", "x64-linux", "-x", "21"])
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("native-image").not());
+    let tag_dir = env
+        .data_path
+        .join("tools")
+        .join("graalvm")
+        .join("x64-linux_21.0.2");
+    env.avm()
+        .args([
+            "entry-path",
+            "graalvm",
+            "x64-linux_21.0.2",
+            "--exe",
+            "native-image",
+        ])
+        .assert()
+        .success()
+        .stdout(format!(
+            "{}\n",
+            tag_dir.join("bin").join("native-image").display()
+        ));
+}
+
+#[test]
+fn protoc_go_flavor_installs_plugins() {
+    let env = Env::new();
+    let protoc = fixture::zip(&[
+        ("bin/protoc", b"#!/bin/sh\n", 0o755),
+        ("include/google/protobuf/empty.proto", b"", 0o644),
+    ]);
+    let plugin = |name: &str| fixture::tar_gz(".", &[(name, b"#!/bin/sh\n", 0o755)]);
+    let (go, grpc) = (plugin("protoc-gen-go"), plugin("protoc-gen-go-grpc"));
+    let release = |name: &str, tag: &str, repo: &str, digest: &str| {
+        format!(
+            r#"{{"name": "{name}", "browser_download_url": "https://github.com/{repo}/releases/download/{tag}/{name}", "digest": "sha256:{digest}"}}"#
+        )
+    };
+    let server = FixtureServer::start(HashMap::from([
+        (
+            "api/repos/protocolbuffers/protobuf/releases".to_owned(),
+            format!(
+                r#"[{{"assets": [{}]}}]"#,
+                release(
+                    "protoc-28.2-linux-x86_64.zip",
+                    "v28.2",
+                    "protocolbuffers/protobuf",
+                    &fixture::sha256_hex(&protoc)
+                )
+            )
+            .into_bytes(),
+        ),
+        (
+            "api/repos/protocolbuffers/protobuf-go/releases".to_owned(),
+            format!(
+                r#"[{{"assets": [{}]}}]"#,
+                release(
+                    "protoc-gen-go.v1.34.2.linux.amd64.tar.gz",
+                    "v1.34.2",
+                    "protocolbuffers/protobuf-go",
+                    &fixture::sha256_hex(&go)
+                )
+            )
+            .into_bytes(),
+        ),
+        (
+            "api/repos/grpc/grpc-go/releases".to_owned(),
+            format!(
+                r#"[{{"assets": []}}, {{"assets": [{}]}}]"#,
+                release(
+                    "protoc-gen-go-grpc.v1.5.1.linux.amd64.tar.gz",
+                    "cmd/protoc-gen-go-grpc/v1.5.1",
+                    "grpc/grpc-go",
+                    &fixture::sha256_hex(&grpc)
+                )
+            )
+            .into_bytes(),
+        ),
+        (
+            "download/protocolbuffers/protobuf/releases/download/v28.2/protoc-28.2-linux-x86_64.zip"
+                .to_owned(),
+            protoc,
+        ),
+        (
+            "download/protocolbuffers/protobuf-go/releases/download/v1.34.2/protoc-gen-go.v1.34.2.linux.amd64.tar.gz"
+                .to_owned(),
+            go,
+        ),
+        (
+            "download/grpc/grpc-go/releases/download/cmd/protoc-gen-go-grpc/v1.5.1/protoc-gen-go-grpc.v1.5.1.linux.amd64.tar.gz"
+                .to_owned(),
+            grpc,
+        ),
+    ]));
+    env.append_config(&format!(
+        "\n[[mirrors]]\nfrom = \"https://api.github.com/\"\nto = \"{0}api/\"\n\n[[mirrors]]\nfrom = \"https://github.com/\"\nto = \"{0}download/\"\n",
+        server.base_url()
+    ));
+
+    env.avm()
+        .args(["install", "protoc", "-p", "x64-linux", "-f", "go"])
+        .assert()
+        .success();
+    let tag_dir = env
+        .data_path
+        .join("tools")
+        .join("protoc")
+        .join("x64-linux_go_28.2");
+    assert_exists(&tag_dir.join("include").join("google"));
+    env.avm()
+        .args([
+            "entry-path",
+            "protoc",
+            "x64-linux_go_28.2",
+            "--exe",
+            "protoc-gen-go-grpc",
+        ])
+        .assert()
+        .success()
+        .stdout(format!(
+            "{}\n",
+            tag_dir.join("bin").join("protoc-gen-go-grpc").display()
+        ));
+    assert_exists(&tag_dir.join("bin").join("protoc-gen-go"));
+}
+
+#[cfg(all(target_os = "linux", target_arch = "x86_64", target_env = "gnu"))]
+#[test]
+fn rust_sets_up_managed_rustup() {
+    let env = Env::new();
+    // Like the real installer, puts `rustup` into `CARGO_HOME`. The fake `rustup` records
+    // its arguments and lists two toolchains.
+    let rustup_init = br#"#!/bin/sh
+mkdir -p "$CARGO_HOME/bin" "$RUSTUP_HOME"
+echo "$@" > "$RUSTUP_HOME/init-args"
+cat > "$CARGO_HOME/bin/rustup" <<'SCRIPT'
+#!/bin/sh
+echo "$@" >> "$RUSTUP_HOME/calls"
+if [ "$1" = toolchain ] && [ "$2" = list ]; then
+    echo "stable-x86_64-unknown-linux-gnu (active, default)"
+    echo "1.82.0-x86_64-unknown-linux-gnu"
+fi
+SCRIPT
+chmod +x "$CARGO_HOME/bin/rustup"
+"#;
+    let server = FixtureServer::start(HashMap::from([
+        (
+            "rustup/dist/x86_64-unknown-linux-gnu/rustup-init.sha256".to_owned(),
+            format!("{}  *rustup-init\n", fixture::sha256_hex(rustup_init)).into_bytes(),
+        ),
+        (
+            "rustup/dist/x86_64-unknown-linux-gnu/rustup-init".to_owned(),
+            rustup_init.to_vec(),
+        ),
+    ]));
+    env.append_config(&format!(
+        "\n[[mirrors]]\nfrom = \"https://static.rust-lang.org/\"\nto = {:?}\n",
+        server.base_url()
+    ));
+    let rust_dir = env.data_path.join("rust");
+
+    env.avm()
+        .args(["rust", "list"])
+        .assert()
+        .success()
+        .stdout("");
+    env.avm()
+        .args(["rust", "default", "stable"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("avm rust setup"));
+
+    env.avm()
+        .args(["rust", "install", "stable", "1.82.0", "--default"])
+        .assert()
+        .success();
+    assert_eq!(
+        std::fs::read_to_string(rust_dir.join("rustup").join("init-args")).unwrap(),
+        "-y --no-modify-path --default-toolchain none\n"
+    );
+    assert!(!rust_dir.join("rustup-init").exists());
+    assert_eq!(
+        std::fs::read_to_string(rust_dir.join("rustup").join("calls")).unwrap(),
+        "toolchain install stable 1.82.0\ndefault stable\n"
+    );
+
+    env.avm()
+        .args(["rust", "list"])
+        .assert()
+        .success()
+        .stdout(
+            "default -> stable-x86_64-unknown-linux-gnu\nstable-x86_64-unknown-linux-gnu\n1.82.0-x86_64-unknown-linux-gnu\n",
+        );
+    env.avm()
+        .args(["rust", "path"])
+        .assert()
+        .success()
+        .stdout(format!(
+            "{}\n",
+            rust_dir.join("cargo").join("bin").display()
+        ));
+}
+
+/// Writes a plugin named `fixture-node` that installs the fixture node archives.
+#[cfg(unix)]
+fn write_fixture_plugin(env: &Env, list_versions: &str) -> PathBuf {
+    use std::os::unix::fs::PermissionsExt;
+
+    let plugin_dir = env.data_path.join("plugins").join("fixture-node");
+    std::fs::create_dir_all(&plugin_dir).unwrap();
+    let scripts = [
+        ("list-versions", list_versions.to_owned()),
+        (
+            "download-info",
+            format!(
+                r#"echo "{{\"url\": \"{}v$1/node-v$1-linux-x64.tar.xz\"}}""#,
+                env.server.base_url()
+            ),
+        ),
+    ];
+    for (name, body) in scripts {
+        let path = plugin_dir.join(name);
+        std::fs::write(&path, format!("#!/bin/sh\n{body}\n")).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+    std::fs::write(
+        plugin_dir.join("plugin.toml"),
+        "about = \"Node.js from a plugin\"\nentry_path = \"bin/node\"\nplatforms = [\"x64-linux\"]\n",
+    )
+    .unwrap();
+    plugin_dir
+}
+
+#[cfg(unix)]
+#[test]
+fn run_installs_missing_version_and_passes_args() {
+    let env = Env::new();
+    env.avm()
+        .args([
+            "run",
+            "node",
+            "-p",
+            "x64-linux",
+            "-x",
+            "28",
+            "--",
+            "-e",
+            "1",
+        ])
+        .assert()
+        .success()
+        .stdout("fixture node -e 1\n");
+    assert_exists(&env.node_dir().join("x64-linux_28.1.0"));
+
+    env.avm()
+        .args(["entry-path", "node", "x64-linux_28.1.0"])
+        .assert()
+        .success()
+        .stdout(format!(
+            "{}\n",
+            env.node_dir()
+                .join("x64-linux_28.1.0")
+                .join("bin")
+                .join("node")
+                .display()
+        ));
+}
+
+#[cfg(unix)]
+#[test]
+fn run_passes_stdin_and_exit_code() {
+    use std::os::unix::fs::PermissionsExt;
+    let env = Env::new();
+    env.avm()
+        .args(["install", "node", "-p", "x64-linux", "--default"])
+        .assert()
+        .success();
+    let node = env
+        .node_dir()
+        .join("x64-linux_29.3.0")
+        .join("bin")
+        .join("node");
+    std::fs::write(&node, "#!/bin/sh\nread line\necho \"read $line\"\nexit 3\n").unwrap();
+    std::fs::set_permissions(&node, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+    env.avm()
+        .args(["run", "node"])
+        .write_stdin("input\n")
+        .assert()
+        .code(3)
+        .stdout("read input\n")
+        .stderr("");
+}
+
+#[cfg(unix)]
+#[test]
+fn shims_run_the_version_pinned_by_the_project() {
+    let env = Env::new();
+    env.avm()
+        .args(["install", "node", "-p", "x64-linux", "--default"])
+        .assert()
+        .success();
+    env.avm()
+        .args(["install", "node", "-p", "x64-linux", "-x", "28"])
+        .assert()
+        .success();
+    std::fs::write(
+        env.node_dir()
+            .join("x64-linux_28.1.0")
+            .join("bin")
+            .join("node"),
+        "#!/bin/sh\necho \"fixture node 28 $@\"\n",
+    )
+    .unwrap();
+    let project = env.data_path.parent().unwrap().join("project");
+    std::fs::create_dir_all(project.join("src")).unwrap();
+    std::fs::write(project.join(".tool-versions"), "node 28\n").unwrap();
+
+    let shims_dir = env.data_path.join("shims");
+    env.avm()
+        .args(["reshim", "node"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            shims_dir.join("npm").display().to_string(),
+        ));
+    let shim = |dir: &Path| {
+        let mut cmd = Command::new(shims_dir.join("node"));
+        cmd.env("CONFIG_PATH", &env.config_path)
+            .current_dir(dir)
+            .arg("-v");
+        cmd
+    };
+    shim(&project.join("src"))
+        .assert()
+        .success()
+        .stdout("fixture node 28 -v\n");
+    shim(env.data_path.parent().unwrap())
+        .assert()
+        .success()
+        .stdout("fixture node -v\n");
+
+    // The project may also pin a tag.
+    std::fs::write(project.join(".tool-versions"), "node x64-linux_29.3.0\n").unwrap();
+    shim(&project)
+        .assert()
+        .success()
+        .stdout("fixture node -v\n");
+
+    // The variable overrides the project.
+    shim(&project)
+        .env("AVM_NODE_VERSION", "28")
+        .assert()
+        .success()
+        .stdout("fixture node 28 -v\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn init_switches_to_the_pinned_tags_on_cd() {
+    let env = Env::new();
+    env.avm()
+        .args(["install", "node", "-p", "x64-linux", "-x", "28"])
+        .assert()
+        .success();
+    let project = env.data_path.parent().unwrap().join("project");
+    std::fs::create_dir_all(&project).unwrap();
+    std::fs::write(project.join(".tool-versions"), "node 28\n").unwrap();
+
+    let output = env
+        .avm()
+        .args(["init", "bash", "--auto-switch"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let snippet = String::from_utf8(output.stdout).unwrap();
+    assert!(snippet.contains(&env.data_path.join("shims").display().to_string()));
+    assert!(snippet.contains("complete -F _avm"));
+
+    let bin_dir = env.node_dir().join("x64-linux_28.1.0").join("bin");
+    let script = format!(
+        "{snippet}\ncd '{}'\n_avm_hook\necho \"$PATH\"\ncd ..\n_avm_hook\necho \"$PATH\"\n",
+        project.display()
+    );
+    let script_path = env.data_path.parent().unwrap().join("init.sh");
+    std::fs::write(&script_path, script).unwrap();
+    Command::new("bash")
+        .arg("--norc")
+        .arg(&script_path)
+        .env("CONFIG_PATH", &env.config_path)
+        .env("PATH", "/usr/bin:/bin")
+        .assert()
+        .success()
+        .stdout(format!(
+            "{}:{}:/usr/bin:/bin\n{}:/usr/bin:/bin\n",
+            bin_dir.display(),
+            env.data_path.join("shims").display(),
+            env.data_path.join("shims").display(),
+        ));
+}
+
+#[cfg(unix)]
+#[test]
+fn run_exe_selects_executable() {
+    let env = Env::new();
+    env.avm()
+        .args(["install", "node", "-p", "x64-linux", "--default"])
+        .assert()
+        .success();
+    env.avm()
+        .args(["run", "node", "--exe", "npm", "--", "install"])
+        .env("PATH", "/usr/bin:/bin")
+        .assert()
+        .success()
+        .stdout("fixture node npm-cli.js install\n");
+    env.avm()
+        .args(["run", "node", "--exe", "yarn"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "Available executables: node, npm, npx",
+        ));
+}
+
+#[test]
+fn install_shim_pack_writes_launcher_and_shims() {
+    let env = Env::new();
+    let dir = env.data_path.join("repo");
+    let hash = "ab".repeat(32);
+    env.avm()
+        .arg("install-shim-pack")
+        .arg(&dir)
+        .args(["--tool", "node=22", "--avm-version", "1.2.3"])
+        .arg(format!("--sha256=x64-linux={hash}"))
+        .assert()
+        .success();
+
+    let properties = std::fs::read_to_string(dir.join("avmw.properties")).unwrap();
+    assert!(properties.contains("version=1.2.3\n"));
+    assert!(properties.contains(&format!("sha256.x64-linux={hash}\n")));
+    for name in ["avmw", "avmw.cmd", "avmw.ps1", "node.cmd"] {
+        assert_exists(&dir.join(name));
+    }
+    assert!(std::fs::read_to_string(dir.join("node"))
+        .unwrap()
+        .contains("avmw\" run node -x 22 -- \"$@\""));
+    assert!(std::fs::read_to_string(dir.join("npm.cmd"))
+        .unwrap()
+        .contains("run node -x 22 --exe npm -- %*"));
+
+    // Existing files are only replaced with `--force`.
+    env.avm()
+        .arg("install-shim-pack")
+        .arg(&dir)
+        .assert()
+        .failure();
+    env.avm()
+        .arg("install-shim-pack")
+        .arg(&dir)
+        .arg("--force")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--no-verify"));
+    env.avm()
+        .arg("install-shim-pack")
+        .arg(&dir)
+        .args(["--force", "--no-verify"])
+        .assert()
+        .success();
+    let properties = std::fs::read_to_string(dir.join("avmw.properties")).unwrap();
+    assert!(properties.contains("verify=false\n"));
+    assert!(properties.contains("/avm-{target}.zip\n"));
+}
@@ -0,0 +1,167 @@
+//! A minimal HTTP server serving fixed responses, standing in for upstream tool endpoints.
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+
+use sha2::Digest;
+
+pub struct FixtureServer {
+    base_url: String,
+    requests: Arc<Mutex<Vec<String>>>,
+    authorizations: Arc<Mutex<Vec<Option<String>>>>,
+}
+
+impl FixtureServer {
+    pub fn start(files: HashMap<String, Vec<u8>>) -> Self {
+        Self::start_with_redirects(files, HashMap::new())
+    }
+
+    /// Like [`FixtureServer::start`], additionally answering the paths in `redirects` with a
+    /// `302` to the mapped location.
+    pub fn start_with_redirects(
+        files: HashMap<String, Vec<u8>>,
+        redirects: HashMap<String, String>,
+    ) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind fixture server");
+        let base_url = format!("http://{}/", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let authorizations = Arc::new(Mutex::new(Vec::new()));
+        let files = Arc::new(files);
+        let redirects = Arc::new(redirects);
+
+        let requests_clone = requests.clone();
+        let authorizations_clone = authorizations.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let files = files.clone();
+                let redirects = redirects.clone();
+                let requests = requests_clone.clone();
+                let authorizations = authorizations_clone.clone();
+                std::thread::spawn(move || {
+                    handle(stream, &files, &redirects, &requests, &authorizations)
+                });
+            }
+        });
+
+        Self {
+            base_url,
+            requests,
+            authorizations,
+        }
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// Paths requested so far, without the query string.
+    pub fn requests(&self) -> Vec<String> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// The `Authorization` headers of the requests so far, in the order of [`Self::requests`].
+    pub fn authorizations(&self) -> Vec<Option<String>> {
+        self.authorizations.lock().unwrap().clone()
+    }
+}
+
+fn handle(
+    mut stream: TcpStream,
+    files: &HashMap<String, Vec<u8>>,
+    redirects: &HashMap<String, String>,
+    requests: &Mutex<Vec<String>>,
+    authorizations: &Mutex<Vec<Option<String>>>,
+) {
+    let mut reader = BufReader::new(stream.try_clone().unwrap());
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+    let mut authorization = None;
+    loop {
+        let mut header = String::new();
+        match reader.read_line(&mut header) {
+            Ok(0) | Err(_) => break,
+            Ok(_) if header == "\r\n" => break,
+            Ok(_) => {}
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("authorization") {
+                authorization = Some(value.trim().to_owned());
+            }
+        }
+    }
+
+    let target = request_line.split_whitespace().nth(1).unwrap_or("/");
+    let path = target.split('?').next().unwrap_or_default();
+    // Collapse duplicated slashes like real file servers do.
+    let path = path
+        .split('/')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("/");
+    requests.lock().unwrap().push(path.clone());
+    authorizations.lock().unwrap().push(authorization);
+
+    if let Some(location) = redirects.get(&path) {
+        let _ = write!(
+            stream,
+            "HTTP/1.1 302 Found\r\nLocation: {location}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+        );
+        return;
+    }
+
+    let (status, body) = match files.get(&path) {
+        Some(body) => ("200 OK", body.as_slice()),
+        None => ("404 Not Found", &b"not found"[..]),
+    };
+    let _ = write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        body.len()
+    );
+    let _ = stream.write_all(body);
+}
+
+/// Builds a `.tar.xz` archive with a single top-level directory holding `files`.
+pub fn tar_xz(top_dir: &str, files: &[(&str, &[u8], u32)]) -> Vec<u8> {
+    let encoder = xz2::write::XzEncoder::new(Vec::new(), 6);
+    build_tar(encoder, top_dir, files).finish().unwrap()
+}
+
+/// Builds a `.tar.gz` archive with a single top-level directory holding `files`.
+pub fn tar_gz(top_dir: &str, files: &[(&str, &[u8], u32)]) -> Vec<u8> {
+    let encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    build_tar(encoder, top_dir, files).finish().unwrap()
+}
+
+fn build_tar<W: Write>(writer: W, top_dir: &str, files: &[(&str, &[u8], u32)]) -> W {
+    let mut builder = tar::Builder::new(writer);
+    for (path, content, mode) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(*mode);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, format!("{top_dir}/{path}"), *content)
+            .unwrap();
+    }
+    builder.into_inner().unwrap()
+}
+
+/// Builds a `.zip` archive holding `files` at its root.
+pub fn zip(files: &[(&str, &[u8], u32)]) -> Vec<u8> {
+    let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    for (path, content, mode) in files {
+        let options = zip::write::SimpleFileOptions::default().unix_permissions(*mode);
+        writer.start_file(*path, options).unwrap();
+        writer.write_all(content).unwrap();
+    }
+    writer.finish().unwrap().into_inner()
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(sha2::Sha256::digest(data))
+}
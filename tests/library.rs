@@ -0,0 +1,421 @@
+//! Tests of the library API against a fixture server.
+
+#[allow(dead_code)]
+mod fixture;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use any_version_manager::avm::{Avm, InstallRequest};
+use any_version_manager::download_cache::DownloadCache;
+use any_version_manager::tool::general_tool::node;
+use any_version_manager::tool::{
+    ArchiveDelta, GeneralTool, ToolDownInfo, ToolInfo, Version, VersionFilter,
+};
+use any_version_manager::{AvmError, FileHash, HttpClient, Status, UrlMirror};
+use fixture::FixtureServer;
+use smol_str::SmolStr;
+
+#[tokio::test(flavor = "multi_thread")]
+async fn avm_installs_with_progress_aliases_and_removes() {
+    let archive = fixture::tar_xz(
+        "node-v28.1.0-linux-x64",
+        &[("bin/node", b"#!/bin/sh\n", 0o755)],
+    );
+    let server = FixtureServer::start(HashMap::from([
+        (
+            "index.json".to_owned(),
+            br#"[{"version": "v28.1.0", "lts": false, "files": ["linux-x64"]}]"#.to_vec(),
+        ),
+        (
+            "v28.1.0/SHASUMS256.txt".to_owned(),
+            format!(
+                "{}  node-v28.1.0-linux-x64.tar.xz\n",
+                fixture::sha256_hex(&archive)
+            )
+            .into_bytes(),
+        ),
+        ("v28.1.0/node-v28.1.0-linux-x64.tar.xz".to_owned(), archive),
+    ]));
+    let mirror: UrlMirror = toml::from_str(&format!(
+        "[[mirrors]]\nfrom = \"https://nodejs.org/dist/\"\nto = {:?}\n",
+        server.base_url()
+    ))
+    .unwrap();
+    let client = HttpClient::new(mirror, Default::default(), Default::default()).unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let avm = Avm::new(client.clone(), dir.path());
+    let tool = node::Tool::new(Arc::new(client.for_tool("node")), None);
+
+    let handle = avm
+        .install(
+            "node",
+            &tool,
+            InstallRequest {
+                platform: Some("x64-linux".into()),
+                ..InstallRequest::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(handle.tag, "x64-linux_28.1.0");
+    let mut steps = Vec::new();
+    handle
+        .run(|status| {
+            let step = match status {
+                Status::InProgress { name, .. } => name,
+                Status::Stopped => "Stopped".into(),
+            };
+            if steps.last() != Some(&step) {
+                steps.push(step);
+            }
+            Ok(())
+        })
+        .await
+        .unwrap();
+    assert_eq!(steps, ["Downloading", "Verifying", "Extracting", "Stopped"]);
+    assert!(avm
+        .entry_path("node", &tool, "x64-linux_28.1.0")
+        .unwrap()
+        .exists());
+
+    avm.alias("node", "x64-linux_28.1.0".into(), "lts".into())
+        .await
+        .unwrap();
+    let installed = avm.installed_tags("node", &tool).await.unwrap();
+    assert_eq!(installed.len(), 1);
+    assert_eq!(installed[0].info.version, "28.1.0");
+    assert_eq!(
+        avm.list("node").await.unwrap(),
+        vec![
+            ("lts".into(), Some("x64-linux_28.1.0".into())),
+            ("x64-linux_28.1.0".into(), None),
+        ]
+    );
+
+    avm.remove("node", vec!["lts".into(), "x64-linux_28.1.0".into()], false)
+        .await
+        .unwrap();
+    assert!(avm.list("node").await.unwrap().is_empty());
+}
+
+/// A tool published as a bare executable, like `jq`, without builds for `missing_platforms`.
+struct SingleBinaryTool {
+    url: SmolStr,
+    info: ToolInfo,
+    missing_platforms: &'static [&'static str],
+}
+
+impl GeneralTool for SingleBinaryTool {
+    fn info(&self) -> &ToolInfo {
+        &self.info
+    }
+
+    fn endpoints(&self) -> Vec<SmolStr> {
+        vec![self.url.clone()]
+    }
+
+    async fn fetch_versions(
+        &self,
+        _platform: Option<SmolStr>,
+        _flavor: Option<SmolStr>,
+        _version_filter: VersionFilter,
+    ) -> any_version_manager::Result<Vec<Version>> {
+        Ok(vec![Version {
+            version: "1.7.1".into(),
+            is_lts: false,
+        }])
+    }
+
+    async fn get_down_info(
+        &self,
+        platform: Option<SmolStr>,
+        _flavor: Option<SmolStr>,
+        _version_filter: VersionFilter,
+    ) -> any_version_manager::Result<ToolDownInfo> {
+        if platform.is_some_and(|platform| self.missing_platforms.contains(&platform.as_str())) {
+            return Err(AvmError::VersionNotFound);
+        }
+        Ok(ToolDownInfo {
+            version: Version {
+                version: "1.7.1".into(),
+                is_lts: false,
+            },
+            url: self.url.clone(),
+            hash: FileHash::default(),
+        })
+    }
+
+    fn find_best_matching_local_tag<'a, I>(
+        &self,
+        mut tags_and_versions: I,
+        _version_filter: &VersionFilter,
+    ) -> Option<SmolStr>
+    where
+        I: Iterator<Item = (&'a str, &'a Version)>,
+    {
+        tags_and_versions.next().map(|(tag, _)| tag.into())
+    }
+
+    fn entry_path(&self, tag_dir: PathBuf) -> any_version_manager::Result<PathBuf> {
+        Ok(tag_dir
+            .join("bin")
+            .join(format!("jq{}", std::env::consts::EXE_SUFFIX)))
+    }
+
+    fn single_binary(&self) -> Option<&'static str> {
+        Some("jq")
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn avm_installs_single_binary_artifacts() {
+    let server = FixtureServer::start(HashMap::from([(
+        "jq-linux-amd64".to_owned(),
+        b"#!/bin/sh\necho jq\n".to_vec(),
+    )]));
+    let client =
+        HttpClient::new(Default::default(), Default::default(), Default::default()).unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let avm = Avm::new(client, dir.path());
+    let tool = SingleBinaryTool {
+        url: format!("{}jq-linux-amd64", server.base_url()).into(),
+        info: ToolInfo {
+            about: "jq".into(),
+            after_long_help: None,
+            all_platforms: None,
+            default_platform: None,
+            all_flavors: None,
+            default_flavor: None,
+        },
+        missing_platforms: &[],
+    };
+
+    let handle = avm
+        .install("jq", &tool, InstallRequest::default())
+        .await
+        .unwrap();
+    assert_eq!(handle.tag, "1.7.1");
+    handle.run(|_| Ok(())).await.unwrap();
+
+    let entry_path = avm.entry_path("jq", &tool, "1.7.1").unwrap();
+    assert_eq!(std::fs::read(&entry_path).unwrap(), b"#!/bin/sh\necho jq\n");
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        let mode = std::fs::metadata(&entry_path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o755);
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn avm_falls_back_to_emulated_platform() {
+    let server = FixtureServer::start(HashMap::from([(
+        "jq-macos-amd64".to_owned(),
+        b"#!/bin/sh\necho jq\n".to_vec(),
+    )]));
+    let client =
+        HttpClient::new(Default::default(), Default::default(), Default::default()).unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let avm = Avm::new(client, dir.path());
+    let tool = SingleBinaryTool {
+        url: format!("{}jq-macos-amd64", server.base_url()).into(),
+        info: ToolInfo {
+            about: "jq".into(),
+            after_long_help: None,
+            all_platforms: Some(vec!["arm64-mac".into(), "x64-mac".into()]),
+            default_platform: Some("arm64-mac".into()),
+            all_flavors: None,
+            default_flavor: None,
+        },
+        missing_platforms: &["arm64-mac"],
+    };
+
+    // Only the default platform falls back, not the one asked for.
+    let err = avm
+        .install(
+            "jq",
+            &tool,
+            InstallRequest {
+                platform: Some("arm64-mac".into()),
+                ..InstallRequest::default()
+            },
+        )
+        .await
+        .err()
+        .unwrap();
+    assert!(matches!(err, AvmError::VersionNotFound));
+
+    let handle = avm
+        .install("jq", &tool, InstallRequest::default())
+        .await
+        .unwrap();
+    assert_eq!(handle.tag, "x64-mac_1.7.1");
+    handle.run(|_| Ok(())).await.unwrap();
+    let installed = avm.installed_tags("jq", &tool).await.unwrap();
+    assert_eq!(installed[0].info.platform.as_deref(), Some("x64-mac"));
+}
+
+/// A single binary tool with one release per instance, publishing patches from `from_url`.
+struct PatchedTool {
+    version: &'static str,
+    url: SmolStr,
+    hash: FileHash,
+    delta: Option<(SmolStr, ArchiveDelta)>,
+    info: ToolInfo,
+}
+
+impl GeneralTool for PatchedTool {
+    fn info(&self) -> &ToolInfo {
+        &self.info
+    }
+
+    fn endpoints(&self) -> Vec<SmolStr> {
+        vec![self.url.clone()]
+    }
+
+    async fn fetch_versions(
+        &self,
+        _platform: Option<SmolStr>,
+        _flavor: Option<SmolStr>,
+        _version_filter: VersionFilter,
+    ) -> any_version_manager::Result<Vec<Version>> {
+        Ok(vec![Version {
+            version: self.version.into(),
+            is_lts: false,
+        }])
+    }
+
+    async fn get_down_info(
+        &self,
+        _platform: Option<SmolStr>,
+        _flavor: Option<SmolStr>,
+        _version_filter: VersionFilter,
+    ) -> any_version_manager::Result<ToolDownInfo> {
+        Ok(ToolDownInfo {
+            version: Version {
+                version: self.version.into(),
+                is_lts: false,
+            },
+            url: self.url.clone(),
+            hash: self.hash.clone(),
+        })
+    }
+
+    fn find_best_matching_local_tag<'a, I>(
+        &self,
+        mut tags_and_versions: I,
+        _version_filter: &VersionFilter,
+    ) -> Option<SmolStr>
+    where
+        I: Iterator<Item = (&'a str, &'a Version)>,
+    {
+        tags_and_versions.next().map(|(tag, _)| tag.into())
+    }
+
+    async fn archive_delta(
+        &self,
+        from_url: &str,
+        to_url: &str,
+    ) -> any_version_manager::Result<Option<ArchiveDelta>> {
+        Ok(self
+            .delta
+            .as_ref()
+            .filter(|(from, _)| from == from_url && to_url == self.url)
+            .map(|(_, delta)| delta.clone()))
+    }
+
+    fn entry_path(&self, tag_dir: PathBuf) -> any_version_manager::Result<PathBuf> {
+        Ok(tag_dir.join("bin").join("tool"))
+    }
+
+    fn single_binary(&self) -> Option<&'static str> {
+        Some("tool")
+    }
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn avm_upgrades_from_patches_of_cached_archives() {
+    let v1: Vec<u8> = (0..50_000_u32)
+        .flat_map(|i| (i % 251).to_le_bytes())
+        .collect();
+    let mut v2 = v1.clone();
+    v2[100..110].copy_from_slice(b"version 2!");
+    let mut v3 = v1.clone();
+    v3[100..110].copy_from_slice(b"version 3!");
+    let patch = |to: &[u8]| {
+        let mut encoder =
+            zstd::stream::write::Encoder::with_ref_prefix(Vec::new(), 3, &v1).unwrap();
+        std::io::Write::write_all(&mut encoder, to).unwrap();
+        encoder.finish().unwrap()
+    };
+    let (v1_to_v2, v1_to_v3) = (patch(&v2), patch(&v3));
+    let server = FixtureServer::start(HashMap::from([
+        ("tool-1".to_owned(), v1.clone()),
+        ("tool-1-to-2.zst".to_owned(), v1_to_v2.clone()),
+        ("tool-1-to-3.zst".to_owned(), v1_to_v3.clone()),
+        ("tool-3".to_owned(), v3.clone()),
+    ]));
+    let dir = tempfile::tempdir().unwrap();
+    let client = HttpClient::new(Default::default(), Default::default(), Default::default())
+        .unwrap()
+        .with_download_cache(Some(DownloadCache::new(
+            &dir.path().join("cache"),
+            DownloadCache::DEFAULT_MAX_SIZE,
+        )));
+    let avm = Avm::new(client, dir.path().join("data"));
+    let url = |name: &str| SmolStr::from(format!("{}{name}", server.base_url()));
+    let sha256 = |content: &[u8]| FileHash::from_sha256(fixture::sha256_hex(content).into());
+    let tool = |version, content: &[u8], delta: Option<(&str, &[u8])>| PatchedTool {
+        version,
+        url: url(&format!("tool-{version}")),
+        hash: sha256(content),
+        delta: delta.map(|(name, patch)| {
+            (
+                url("tool-1"),
+                ArchiveDelta {
+                    url: url(name),
+                    hash: sha256(patch),
+                },
+            )
+        }),
+        info: ToolInfo {
+            about: "tool".into(),
+            after_long_help: None,
+            all_platforms: None,
+            default_platform: None,
+            all_flavors: None,
+            default_flavor: None,
+        },
+    };
+    let install = |tool: PatchedTool| {
+        let avm = &avm;
+        async move {
+            let handle = avm
+                .install("tool", &tool, InstallRequest::default())
+                .await
+                .unwrap();
+            let tag = handle.tag.clone();
+            handle.run(|_| Ok(())).await.unwrap();
+            std::fs::read(avm.entry_path("tool", &tool, &tag).unwrap()).unwrap()
+        }
+    };
+
+    assert_eq!(install(tool("1", &v1, None)).await, v1);
+    // The whole archive of version 2 is not served.
+    assert_eq!(
+        install(tool("2", &v2, Some(("tool-1-to-2.zst", &v1_to_v2)))).await,
+        v2
+    );
+    // A patch restoring something else falls back to the whole archive.
+    assert_eq!(
+        install(tool("3", &v3, Some(("tool-1-to-2.zst", &v1_to_v2)))).await,
+        v3
+    );
+    assert_eq!(
+        server.requests(),
+        ["tool-1", "tool-1-to-2.zst", "tool-1-to-2.zst", "tool-3"]
+    );
+}